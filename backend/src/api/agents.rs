@@ -0,0 +1,259 @@
+use axum::{
+    extract::{DefaultBodyLimit, Path, State},
+    routing::{delete, get, post, put},
+    body::Body,
+    http::{header, HeaderMap},
+    Json, Router,
+};
+use futures::StreamExt;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path as StdPath, PathBuf};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+
+use crate::models::{Agent, AgentFailRequest, AgentPollRequest, AgentPollResponse, AgentProgressRequest, CreateAgentRequest, LogLevel};
+use crate::services::agents;
+use crate::state::AppState;
+use super::{ApiError, ApiResult, success_response};
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub(crate) struct AgentResponse {
+    #[serde(flatten)]
+    agent: Agent,
+    online: bool,
+    database_count: i64,
+}
+
+/// Management of agents by the central instance's users
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(list_agents).post(create_agent))
+        .route("/:id", delete(delete_agent))
+        .route("/:id/token", post(rotate_token))
+        .with_state(state)
+}
+
+/// Endpoints agents call with their bearer token
+pub fn protocol_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/poll", post(poll))
+        .route("/jobs/:id/progress", post(report_progress))
+        .route("/jobs/:id/fail", post(report_failure))
+        .route("/jobs/:id/archive", put(upload_archive).layer(DefaultBodyLimit::disable()))
+        .with_state(state)
+}
+
+async fn list_agents(
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let agents: Vec<Agent> = sqlx::query_as("SELECT * FROM agents ORDER BY name")
+        .fetch_all(&state.pool)
+        .await?;
+
+    let mut response = Vec::with_capacity(agents.len());
+    for agent in agents {
+        let (database_count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM database_configs WHERE agent_id = ?")
+            .bind(&agent.id)
+            .fetch_one(&state.pool)
+            .await?;
+        response.push(AgentResponse { online: agent.is_online(), agent, database_count });
+    }
+    Ok(success_response(response))
+}
+
+/// Register an agent; the token is only returned here and can't be read back later
+async fn create_agent(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreateAgentRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let name = req.name.trim().to_string();
+    if name.is_empty() {
+        return Err(ApiError::BadRequest("Agent name is required".to_string()));
+    }
+    let existing: Option<(String,)> = sqlx::query_as("SELECT id FROM agents WHERE name = ?")
+        .bind(&name)
+        .fetch_optional(&state.pool)
+        .await?;
+    if existing.is_some() {
+        return Err(ApiError::BadRequest("Agent with this name already exists".to_string()));
+    }
+
+    let token = agents::generate_token();
+    let agent = Agent::new(name, agents::hash_token(&token));
+    sqlx::query("INSERT INTO agents (id, name, token_hash, version, last_seen_at, created_at) VALUES (?, ?, ?, ?, ?, ?)")
+        .bind(&agent.id)
+        .bind(&agent.name)
+        .bind(&agent.token_hash)
+        .bind(&agent.version)
+        .bind(agent.last_seen_at)
+        .bind(agent.created_at)
+        .execute(&state.pool)
+        .await?;
+
+    let _ = state.logging_service.log_system(&format!("Agent '{}' created", agent.name), LogLevel::Info).await;
+    Ok(success_response(serde_json::json!({
+        "agent": agent,
+        "token": token
+    })))
+}
+
+async fn delete_agent(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let (assigned,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM database_configs WHERE agent_id = ?")
+        .bind(&id)
+        .fetch_one(&state.pool)
+        .await?;
+    if assigned > 0 {
+        return Err(ApiError::BadRequest(format!("Agent still runs {} database configuration(s), reassign them first", assigned)));
+    }
+
+    let result = sqlx::query("DELETE FROM agents WHERE id = ?")
+        .bind(&id)
+        .execute(&state.pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Agent not found".to_string()));
+    }
+
+    Ok(success_response(serde_json::json!({"message": "Agent deleted successfully"})))
+}
+
+/// Issue a new token, e.g. after the old one leaked or got lost; the previous token stops working
+async fn rotate_token(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let token = agents::generate_token();
+    let result = sqlx::query("UPDATE agents SET token_hash = ? WHERE id = ?")
+        .bind(agents::hash_token(&token))
+        .bind(&id)
+        .execute(&state.pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Agent not found".to_string()));
+    }
+
+    Ok(success_response(serde_json::json!({ "token": token })))
+}
+
+/// Agent calling with `Authorization: Bearer <token>`
+async fn authenticate(state: &AppState, headers: &HeaderMap) -> Result<Agent, ApiError> {
+    let token = headers.get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or_else(|| ApiError::Unauthorized("Missing agent token".to_string()))?;
+    agents::authenticate(&state.pool, token).await?
+        .ok_or_else(|| ApiError::Unauthorized("Invalid agent token".to_string()))
+}
+
+/// Heartbeat that also hands out the next job dispatched to the agent, if any
+async fn poll(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<AgentPollRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let agent = authenticate(&state, &headers).await?;
+    if req.version.is_some() && req.version != agent.version {
+        sqlx::query("UPDATE agents SET version = ? WHERE id = ?")
+            .bind(&req.version)
+            .bind(&agent.id)
+            .execute(&state.pool)
+            .await?;
+    }
+
+    let job = agents::claim_next_job(&state.pool, &agent).await?;
+    Ok(success_response(AgentPollResponse { job }))
+}
+
+async fn report_progress(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<AgentProgressRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let agent = authenticate(&state, &headers).await?;
+    let job = agents::find_running_job(&state.pool, &agent, &job_id).await?
+        .ok_or_else(|| ApiError::NotFound("No running job with this id for this agent".to_string()))?;
+
+    sqlx::query("UPDATE jobs SET progress = ? WHERE id = ?")
+        .bind(req.progress.clamp(0, 99))
+        .bind(&job.id)
+        .execute(&state.pool)
+        .await?;
+    Ok(success_response(serde_json::json!({ "job_id": job.id })))
+}
+
+async fn report_failure(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<AgentFailRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let agent = authenticate(&state, &headers).await?;
+    let job = agents::find_running_job(&state.pool, &agent, &job_id).await?
+        .ok_or_else(|| ApiError::NotFound("No running job with this id for this agent".to_string()))?;
+
+    agents::fail_job(&state.pool, &job, &req.error).await?;
+    Ok(success_response(serde_json::json!({ "job_id": job.id })))
+}
+
+/// Receive the finished archive of a job, check its hash and add it to the catalog
+async fn upload_archive(
+    State(state): State<Arc<AppState>>,
+    Path(job_id): Path<String>,
+    headers: HeaderMap,
+    body: Body,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let agent = authenticate(&state, &headers).await?;
+    let job = agents::find_running_job(&state.pool, &agent, &job_id).await?
+        .ok_or_else(|| ApiError::NotFound("No running job with this id for this agent".to_string()))?;
+
+    let file_name = headers.get("X-Rdumper-Filename")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| StdPath::new(v).file_name())
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| ApiError::BadRequest("Missing X-Rdumper-Filename header".to_string()))?;
+    let expected_hash = headers.get("X-Rdumper-Sha256")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_lowercase)
+        .ok_or_else(|| ApiError::BadRequest("Missing X-Rdumper-Sha256 header".to_string()))?;
+
+    let upload_dir = PathBuf::from(format!("{}/agent_{}", state.config.temp_dir, job.id));
+    let archive = upload_dir.join(&file_name);
+    let result = async {
+        let actual_hash = receive_file(body, &upload_dir, &archive).await
+            .map_err(|e| ApiError::InternalError(format!("Failed to receive archive: {}", e)))?;
+        if actual_hash != expected_hash {
+            return Err(ApiError::BadRequest(format!("Checksum mismatch: expected {}, got {}", expected_hash, actual_hash)));
+        }
+        agents::complete_with_archive(&state.pool, &state.backup_service, &state.config.temp_dir, &job, &archive).await
+            .map_err(|e| ApiError::BadRequest(format!("Failed to register archive: {}", e)))
+    }.await;
+    let _ = tokio::fs::remove_dir_all(&upload_dir).await;
+    let metadata = result?;
+
+    Ok(success_response(serde_json::json!({
+        "job_id": job.id,
+        "backup_id": metadata.id,
+        "file_path": metadata.file_path
+    })))
+}
+
+/// Write the request body to `path`, returning its SHA-256
+async fn receive_file(body: Body, dir: &StdPath, path: &StdPath) -> anyhow::Result<String> {
+    tokio::fs::create_dir_all(dir).await?;
+    let mut file = tokio::fs::File::create(path).await?;
+    let mut hasher = Sha256::new();
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        hasher.update(&chunk);
+        file.write_all(&chunk).await?;
+    }
+    file.sync_all().await?;
+    Ok(hex::encode(hasher.finalize()))
+}
@@ -0,0 +1,84 @@
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get},
+    Json, Router,
+};
+use std::sync::Arc;
+
+use crate::models::{ApiKey, CreateApiKeyRequest, LogLevel};
+use crate::services::api_keys;
+use crate::state::AppState;
+use super::{ApiError, ApiResult, success_response};
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(list_api_keys).post(create_api_key))
+        .route("/:id", delete(delete_api_key))
+        .with_state(state)
+}
+
+async fn list_api_keys(
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let keys: Vec<ApiKey> = sqlx::query_as("SELECT * FROM api_keys ORDER BY name")
+        .fetch_all(&state.pool)
+        .await?;
+    Ok(success_response(keys))
+}
+
+/// Create a key; it is only returned here and can't be read back later. The first key switches
+/// authentication on for the whole API.
+async fn create_api_key(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreateApiKeyRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let name = req.name.trim().to_string();
+    if name.is_empty() {
+        return Err(ApiError::BadRequest("API key name is required".to_string()));
+    }
+    let existing: Option<(String,)> = sqlx::query_as("SELECT id FROM api_keys WHERE name = ?")
+        .bind(&name)
+        .fetch_optional(&state.pool)
+        .await?;
+    if existing.is_some() {
+        return Err(ApiError::BadRequest("API key with this name already exists".to_string()));
+    }
+
+    let key = api_keys::generate_key();
+    let api_key = ApiKey::new(name, &key, api_keys::hash_key(&key));
+    sqlx::query("INSERT INTO api_keys (id, name, key_hash, prefix, last_used_at, created_at) VALUES (?, ?, ?, ?, ?, ?)")
+        .bind(&api_key.id)
+        .bind(&api_key.name)
+        .bind(&api_key.key_hash)
+        .bind(&api_key.prefix)
+        .bind(api_key.last_used_at)
+        .bind(api_key.created_at)
+        .execute(&state.pool)
+        .await?;
+
+    let _ = state.logging_service.log_system(&format!("API key '{}' created", api_key.name), LogLevel::Info).await;
+    Ok(success_response(serde_json::json!({
+        "api_key": api_key,
+        "key": key
+    })))
+}
+
+/// Revoke a key; deleting the last one opens the API again
+async fn delete_api_key(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let name: Option<String> = sqlx::query_scalar("SELECT name FROM api_keys WHERE id = ?")
+        .bind(&id)
+        .fetch_optional(&state.pool)
+        .await?;
+    let name = name.ok_or_else(|| ApiError::NotFound("API key not found".to_string()))?;
+
+    sqlx::query("DELETE FROM api_keys WHERE id = ?")
+        .bind(&id)
+        .execute(&state.pool)
+        .await?;
+
+    let _ = state.logging_service.log_system(&format!("API key '{}' deleted", name), LogLevel::Info).await;
+    Ok(success_response(serde_json::json!({"message": "API key deleted successfully"})))
+}
@@ -0,0 +1,234 @@
+use axum::{
+    body::Body,
+    body::HttpBody,
+    extract::{MatchedPath, Query, Request, State},
+    http::{header, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use chrono::Utc;
+use serde_json::Value;
+use sqlx::types::Json;
+use std::sync::Arc;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::models::{AuditEntry, AuditQuery};
+use crate::services::audit;
+use crate::state::AppState;
+use super::auth::Principal;
+use super::list_params::{self, Cursor};
+use super::{ApiResult, cursor_response, paginated_response};
+
+/// Largest request or response body kept in an entry; bigger ones, e.g. uploads, are recorded without it
+const MAX_RECORDED_BODY: usize = 64 * 1024;
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(list_audit_log))
+        .with_state(state)
+}
+
+async fn list_audit_log(
+    State(state): State<Arc<AppState>>,
+    Query(mut query): Query<AuditQuery>,
+) -> ApiResult<impl IntoResponse> {
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    if let Some(cursor) = Cursor::resolve(&state.pool, "audit_log", query.after_id.as_deref(), query.created_before).await? {
+        query.created_before = Some(cursor.created_at);
+        // One more entry than asked for tells whether another page follows
+        let entries = audit::list_after(&state.pool, &query, limit + 1).await?;
+        return Ok(cursor_response(entries, limit, |entry| list_params::next_cursor(&entry.id, entry.created_at)).into_response());
+    }
+
+    let (entries, total) = audit::list(&state.pool, &query, limit, (page - 1) * limit).await?;
+    Ok(paginated_response(entries, page, limit, total as u64).into_response())
+}
+
+/// Mutating calls worth an entry; logins, the agent protocol and upload chunks are left out
+fn is_audited(method: &Method, path: &str) -> bool {
+    matches!(*method, Method::POST | Method::PUT | Method::PATCH | Method::DELETE)
+        && path.starts_with("/api/")
+        && !path.starts_with("/api/auth/")
+        && !path.starts_with("/api/agent/")
+        && !(*method == Method::PATCH && path.starts_with("/api/backups/uploads/"))
+}
+
+/// What a call does to its entity, named after the route for anything but plain CRUD
+fn action(method: &Method, template: &str) -> String {
+    match *method {
+        Method::DELETE => "delete".to_string(),
+        Method::PUT | Method::PATCH => "update".to_string(),
+        _ => {
+            let segments: Vec<&str> = template.trim_start_matches("/api/").split('/').collect();
+            match segments.last() {
+                _ if segments.len() == 1 => "create".to_string(),
+                Some(last) if last.starts_with(':') => "update".to_string(),
+                Some(last) => last.to_string(),
+                None => "create".to_string(),
+            }
+        }
+    }
+}
+
+/// Values of a route template's `:param` segments in `path`, e.g. `[("id", "42")]` for
+/// `/api/jobs/:id` and `/api/jobs/42`
+fn path_params<'a>(template: &'a str, path: &str) -> Vec<(&'a str, String)> {
+    template.split('/')
+        .zip(path.split('/'))
+        .filter_map(|(part, segment)| part.strip_prefix(':').map(|name| (name, segment.to_string())))
+        .collect()
+}
+
+/// Current state of an entity; backups come from the catalog, everything else from its table
+async fn snapshot(state: &AppState, entity_type: &str, id: &str) -> Option<Value> {
+    let table = match entity_type {
+        "backups" => {
+            let backup = state.backup_service.find_backup(id).await.ok()??;
+            return serde_json::to_value(backup).ok().map(audit::redact);
+        }
+        "database-configs" => "database_configs",
+        "tasks" => "tasks",
+        "jobs" => "jobs",
+        "storage-destinations" => "storage_destinations",
+        "blackout-windows" => "blackout_windows",
+        "agents" => "agents",
+        "api-keys" => "api_keys",
+        "users" => "users",
+        "webhooks" => "webhooks",
+        _ => return None,
+    };
+    match audit::table_snapshot(&state.pool, table, id).await {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            warn!("Failed to read {} {} for the audit log: {}", entity_type, id, e);
+            None
+        }
+    }
+}
+
+/// The JSON body of a request, handing back an equivalent request to pass on
+async fn buffer_request(request: Request) -> (Request, Option<Value>) {
+    let is_json = request.headers().get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    let small = request.headers().get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok())
+        .is_some_and(|length| length <= MAX_RECORDED_BODY);
+    if !is_json || !small {
+        return (request, None);
+    }
+
+    let (parts, body) = request.into_parts();
+    match axum::body::to_bytes(body, MAX_RECORDED_BODY).await {
+        Ok(bytes) => {
+            let value = serde_json::from_slice(&bytes).ok().map(audit::redact);
+            (Request::from_parts(parts, Body::from(bytes)), value)
+        }
+        Err(e) => {
+            warn!("Failed to read request body for the audit log: {}", e);
+            (Request::from_parts(parts, Body::empty()), None)
+        }
+    }
+}
+
+/// The `data` of a JSON response, or the whole body of an error, handing back an equivalent response.
+///
+/// Responses that may be larger than what an entry keeps, e.g. streamed lists, pass through unread.
+async fn buffer_response(response: Response) -> (Response, Option<Value>) {
+    let is_json = response.headers().get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    let small = response.body().size_hint().upper()
+        .is_some_and(|length| length <= MAX_RECORDED_BODY as u64);
+    if !is_json || !small {
+        return (response, None);
+    }
+
+    let (parts, body) = response.into_parts();
+    match axum::body::to_bytes(body, MAX_RECORDED_BODY).await {
+        Ok(bytes) => {
+            let value = serde_json::from_slice::<Value>(&bytes).ok()
+                .map(|mut body| body.get_mut("data").map(Value::take).unwrap_or(body))
+                .map(audit::redact);
+            (Response::from_parts(parts, Body::from(bytes)), value)
+        }
+        Err(e) => {
+            warn!("Failed to read response body for the audit log: {}", e);
+            (Response::from_parts(parts, Body::empty()), None)
+        }
+    }
+}
+
+/// Record every mutating API call with its caller and the entity before and after it.
+///
+/// Runs inside the authentication layer, which attaches the [`Principal`].
+pub async fn record(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let route_path = super::unversioned_path(&path).into_owned();
+    if !is_audited(&method, &route_path) {
+        return next.run(request).await;
+    }
+    // The route the router matched; a request matching none is recorded under its own path
+    let template = match request.extensions().get::<MatchedPath>() {
+        Some(matched) => super::unversioned_path(matched.as_str().trim_end_matches('/')).into_owned(),
+        None => route_path.clone(),
+    };
+    let template = template.as_str();
+    let entity_type = template.trim_start_matches("/api/").split('/').next().unwrap_or_default();
+    let entity_id = path_params(template, &route_path).into_iter().next().map(|(_, value)| value);
+    let principal = request.extensions().get::<Principal>().cloned().unwrap_or(Principal::Anonymous);
+
+    let (request, body) = buffer_request(request).await;
+    let before = match &entity_id {
+        Some(id) => snapshot(&state, entity_type, id).await,
+        None => None,
+    };
+
+    let response = next.run(request).await;
+    let status = response.status();
+    let (response, result) = buffer_response(response).await;
+
+    let action = action(&method, template);
+    // A created entity is known by the id in the response
+    let entity_id = entity_id.or_else(|| match (action.as_str(), &result) {
+        ("create", Some(result)) if status.is_success() => result.get("id").and_then(Value::as_str).map(str::to_string),
+        _ => None,
+    });
+    let after = match &entity_id {
+        Some(id) if method != Method::DELETE => snapshot(&state, entity_type, id).await,
+        _ => None,
+    };
+
+    let entry = AuditEntry {
+        id: Uuid::new_v4().to_string(),
+        actor: principal.display_name(),
+        actor_kind: principal.kind().to_string(),
+        actor_id: principal.id(),
+        method: method.to_string(),
+        path,
+        action,
+        entity_type: entity_type.to_string(),
+        entity_id,
+        status_code: status.as_u16() as i64,
+        request: body.map(Json),
+        before: before.map(Json),
+        after: after.map(Json),
+        response: result.map(Json),
+        created_at: Utc::now(),
+    };
+    if let Err(e) = audit::record(&state.pool, &entry).await {
+        warn!("Failed to write audit log entry for {} {}: {}", entry.method, entry.path, e);
+    }
+
+    response
+}
@@ -0,0 +1,174 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request, State},
+    http::{header, request::Parts},
+    middleware::Next,
+    response::Response,
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::Utc;
+use serde::Serialize;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use crate::models::{LoginRequest, RefreshRequest, User};
+use crate::services::{api_keys, auth};
+use crate::services::auth::TokenKind;
+use crate::state::AppState;
+use super::{ApiError, ApiResult, success_response};
+use utoipa::ToSchema;
+
+/// Who made a request, attached to every request by [`authenticate`]
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Principal {
+    /// No credentials, only possible while the API is open or on public routes
+    Anonymous,
+    User { id: String, username: String },
+    ApiKey { id: String, name: String },
+}
+
+impl Principal {
+    /// Name recorded in logs and audit entries
+    pub fn display_name(&self) -> String {
+        match self {
+            Principal::Anonymous => "anonymous".to_string(),
+            Principal::User { username, .. } => username.clone(),
+            Principal::ApiKey { name, .. } => format!("api-key:{}", name),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Principal::Anonymous => "anonymous",
+            Principal::User { .. } => "user",
+            Principal::ApiKey { .. } => "api_key",
+        }
+    }
+
+    pub fn id(&self) -> Option<String> {
+        match self {
+            Principal::Anonymous => None,
+            Principal::User { id, .. } | Principal::ApiKey { id, .. } => Some(id.clone()),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Principal {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts.extensions.get::<Principal>().cloned().unwrap_or(Principal::Anonymous))
+    }
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/login", post(login))
+        .route("/refresh", post(refresh))
+        .route("/me", get(me))
+        .with_state(state)
+}
+
+/// Routes reachable without credentials: the health check, the API description, logging in and
+/// the agent protocol, which authenticates with agent tokens instead
+fn is_public(path: &str) -> bool {
+    matches!(path, "/api/health" | "/api/openapi.json" | "/api/docs" | "/api/auth/login" | "/api/auth/refresh")
+        || path.starts_with("/api/agent/")
+        || !path.starts_with("/api/")
+}
+
+/// Identify the caller by `Authorization: Bearer <token>`, a user's access token or an API key,
+/// and attach it as [`Principal`]. Once an API key or user exists, all but the public routes
+/// require valid credentials.
+///
+/// EventSource and download links can't set headers, so `?api_key=<token>` is accepted as well.
+pub async fn authenticate(
+    State(state): State<Arc<AppState>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let path = super::unversioned_path(request.uri().path()).into_owned();
+    let credential = request.headers().get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string)
+        .or_else(|| query_key(request.uri().query()));
+
+    let principal = match &credential {
+        Some(credential) if !path.starts_with("/api/agent/") => identify(&state, credential).await?,
+        _ => None,
+    };
+    if principal.is_none() && !is_public(&path) && auth::required(&state.pool).await? {
+        let message = if credential.is_some() { "Invalid credentials" } else { "Missing credentials" };
+        return Err(ApiError::Unauthorized(message.to_string()));
+    }
+
+    request.extensions_mut().insert(principal.unwrap_or(Principal::Anonymous));
+    Ok(next.run(request).await)
+}
+
+async fn identify(state: &AppState, credential: &str) -> Result<Option<Principal>, ApiError> {
+    // Access tokens are JWTs, API keys never contain dots
+    if credential.contains('.') {
+        return Ok(auth::authenticate(&state.pool, &state.config.jwt_secret, credential).await?
+            .map(|user| Principal::User { id: user.id, username: user.username }));
+    }
+    Ok(api_keys::authenticate(&state.pool, credential).await?
+        .map(|key| Principal::ApiKey { id: key.id, name: key.name }))
+}
+
+fn query_key(query: Option<&str>) -> Option<String> {
+    query?.split('&')
+        .find_map(|pair| pair.strip_prefix("api_key="))
+        .map(str::to_string)
+}
+
+/// Exchange username and password for an access and a refresh token
+async fn login(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<LoginRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let user: Option<User> = sqlx::query_as("SELECT * FROM users WHERE username = ?")
+        .bind(req.username.trim())
+        .fetch_optional(&state.pool)
+        .await?;
+    let Some(mut user) = user.filter(|user| auth::verify_password(&req.password, &user.password_hash)) else {
+        return Err(ApiError::Unauthorized("Invalid username or password".to_string()));
+    };
+
+    let now = Utc::now();
+    sqlx::query("UPDATE users SET last_login_at = ? WHERE id = ?")
+        .bind(now)
+        .bind(&user.id)
+        .execute(&state.pool)
+        .await?;
+    user.last_login_at = Some(now);
+
+    Ok(success_response(auth::issue_tokens(&state.config.jwt_secret, user)?))
+}
+
+/// New token pair for a refresh token, as long as its user still exists and hasn't changed their password since
+async fn refresh(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<RefreshRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let claims = auth::verify_token(&state.config.jwt_secret, &req.refresh_token, TokenKind::Refresh)
+        .map_err(|e| ApiError::Unauthorized(e.to_string()))?;
+    let user: User = sqlx::query_as("SELECT * FROM users WHERE id = ?")
+        .bind(&claims.sub)
+        .fetch_optional(&state.pool)
+        .await?
+        .ok_or_else(|| ApiError::Unauthorized("User no longer exists".to_string()))?;
+    if !auth::is_current(&claims, &user) {
+        return Err(ApiError::Unauthorized("Token has been revoked".to_string()));
+    }
+
+    Ok(success_response(auth::issue_tokens(&state.config.jwt_secret, user)?))
+}
+
+async fn me(principal: Principal) -> ApiResult<impl axum::response::IntoResponse> {
+    Ok(success_response(principal))
+}
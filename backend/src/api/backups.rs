@@ -1,21 +1,41 @@
 use axum::{
-    extract::{Path, Query, State},
+    extract::{DefaultBodyLimit, Path, Query, State},
     routing::{get, post},
     Json, Router,
     response::Response,
     body::Body,
+    http::{header, HeaderMap, Method, StatusCode},
 };
 use axum_extra::extract::Multipart;
 use serde::{Deserialize, Serialize};
-use sqlx::{SqlitePool, Row};
+use sqlx::Row;
+use std::convert::Infallible;
 use std::path::Path as StdPath;
-use tracing::error;
-
-use crate::models::{Backup, RestoreRequest, Job, CreateJobRequest, JobType};
-use crate::services::FilesystemBackupService;
-use super::{ApiError, ApiResult, success_response, paginated_response};
-
-#[derive(Debug, Serialize)]
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use futures::StreamExt;
+use tokio_util::io::ReaderStream;
+use tracing::{error, info};
+
+use crate::models::{ArchiveEntry, ArchiveEntryKind, Backup, RestoreRequest, PointInTimeRestoreRequest, VerifyRequest, VerifyRemoteRequest, RecompressRequest, CopyBackupRequest, RegisterBackupRequest, Job, CreateJobRequest, JobType};
+use crate::services::events::{self, AppEvent};
+use crate::services::mydumper::ALL_DATABASES;
+use crate::services::upload_sessions::{ChunkError, UploadSession};
+use crate::services::transfers::{self, TransferGuard, TransferKind, TransferStatus};
+use crate::services::{archive_format, backup_copy, bandwidth, backup_register, binlog, catalog, recompress, remote_storage, remote_verification, restore_target, url_import, verification};
+use crate::state::AppState;
+use super::{ApiError, ApiResult, success_response, paginated_response, cursor_response, conditional_response};
+use super::list_params::{self, Cursor, SortOrder};
+use super::export::{self, CsvRecord, ExportQuery};
+use utoipa::{IntoParams, ToSchema};
+
+/// Backups whose task and config names are looked up at once while exporting
+const EXPORT_CHUNK_SIZE: usize = 500;
+
+/// Read buffer size used when streaming archives to the client
+const DOWNLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct BackupWithDatabaseInfo {
     #[serde(flatten)]
     pub backup: Backup,
@@ -26,111 +46,321 @@ pub struct BackupWithDatabaseInfo {
     pub db_config_database_name: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
+pub struct CreateUploadRequest {
+    filename: String,
+    size: u64,
+    database_config_id: String,
+    compression_type: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ImportUrlRequest {
+    url: String,
+    database_config_id: String,
+    /// Overrides the archive name taken from the URL, e.g. for presigned URLs without an extension
+    filename: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
 pub struct ListQuery {
     page: Option<u32>,
     limit: Option<u32>,
     database_config_id: Option<String>,
     task_id: Option<String>,
+    database_name: Option<String>,
+    /// Comma separated, e.g. `manual,uploaded`
+    backup_type: Option<String>,
+    /// Comma separated `pending`, `verified` and `failed`
+    verification_status: Option<String>,
+    /// Backups created at or after this time
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    /// Backups created before this time
+    until: Option<chrono::DateTime<chrono::Utc>>,
+    /// "none" skips the global date sort and scans lazily until the page is filled
+    sort: Option<String>,
+    sort_by: Option<String>,
+    #[serde(default)]
+    #[param(inline)]
+    order: SortOrder,
+    /// Cursor pagination: backups after this one, newest first
+    after_id: Option<String>,
+    /// Cursor pagination: backups created before this time, or the time of `after_id`
+    created_before: Option<chrono::DateTime<chrono::Utc>>,
 }
 
-pub fn routes(pool: SqlitePool) -> Router {
+impl ListQuery {
+    /// Whether a backup passes the filters
+    fn matches(&self, b: &Backup) -> bool {
+        let backup_types = list_params::split_values(self.backup_type.as_deref());
+        let verification_statuses = list_params::split_values(self.verification_status.as_deref());
+        // Backups whose creation time can't be read only show up without a date range
+        let created_at = chrono::DateTime::parse_from_rfc3339(&b.created_at).ok();
+        self.database_config_id.as_ref().is_none_or(|id| b.database_config_id == *id)
+            && self.task_id.as_ref().is_none_or(|id| b.task_id.as_ref() == Some(id))
+            && self.database_name.as_ref().is_none_or(|name| b.database_name == *name)
+            && (backup_types.is_empty() || backup_types.contains(&b.backup_type.as_str()))
+            && (verification_statuses.is_empty() || verification_statuses.contains(&b.verification_status.as_str()))
+            && self.since.is_none_or(|since| created_at.is_some_and(|at| at >= since))
+            && self.until.is_none_or(|until| created_at.is_some_and(|at| at < until))
+    }
+}
+
+impl CsvRecord for BackupWithDatabaseInfo {
+    const COLUMNS: &'static [&'static str] = &[
+        "id", "database_name", "backup_type", "task_id", "task_name", "database_config",
+        "created_at", "file_size", "compression_type", "sha256", "verification_status",
+        "location", "file_path",
+    ];
+
+    fn csv_fields(&self) -> Vec<String> {
+        let backup = &self.backup;
+        vec![
+            backup.id.clone(),
+            backup.database_name.clone(),
+            backup.backup_type.clone(),
+            export::field(&backup.task_id),
+            export::field(&self.task_name),
+            export::field(&self.db_config_name),
+            backup.created_at.clone(),
+            backup.file_size.to_string(),
+            backup.compression_type.clone(),
+            export::field(&backup.sha256_hash),
+            backup.verification_status.as_str().to_string(),
+            backup.location.as_str().to_string(),
+            backup.file_path.clone(),
+        ]
+    }
+}
+
+/// Values of `sort_by` for backups
+const BACKUP_SORT_FIELDS: &[(&str, &str)] = &[
+    ("created_at", "created_at"),
+    ("file_size", "file_size"),
+    ("database_name", "database_name"),
+];
+
+pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/", get(list_backups))
-        .route("/upload", post(upload_backup))
+        .route("/catalog", get(get_catalog))
+        .route("/export", get(export_backups))
+        // Uploads are streamed to disk, so don't apply the default body limit
+        .route("/upload", post(upload_backup).layer(DefaultBodyLimit::disable()))
+        .route("/upload-directory", post(upload_directory).layer(DefaultBodyLimit::disable()))
+        .route("/import-url", post(import_from_url))
+        .route("/register", post(register_backup))
+        .route("/uploads", post(create_upload))
+        .route("/uploads/:upload_id", get(get_upload).patch(upload_chunk))
+        .route("/uploads/:upload_id/complete", post(complete_upload))
         .route("/:id", get(get_backup).delete(delete_backup))
+        .route("/point-in-time-restore", post(restore_point_in_time))
         .route("/:id/restore", post(restore_backup))
+        .route("/:id/verify", post(verify_backup))
+        .route("/:id/verify-remote", get(get_remote_verification).post(verify_remote_backup))
+        .route("/:id/recompress", post(recompress_backup))
+        .route("/:id/copy", post(copy_backup))
         .route("/:id/download", get(download_backup))
+        .route("/:id/contents", get(get_backup_contents))
         .route("/:id/metadata", post(update_metadata))
         .route("/cleanup", post(cleanup_old_backups))
-        .with_state(pool)
+        .with_state(state)
 }
 
+/// A page of backups; answers `If-None-Match` with 304 while the page is unchanged
 async fn list_backups(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Query(query): Query<ListQuery>,
+    headers: HeaderMap,
 ) -> ApiResult<impl axum::response::IntoResponse> {
     let page = query.page.unwrap_or(1);
     let limit = query.limit.unwrap_or(10);
     let offset = (page - 1) * limit;
 
-    // Initialize filesystem backup service
-    let backup_service = FilesystemBackupService::new(
-        std::env::var("BACKUP_DIR").unwrap_or_else(|_| "data/backups".to_string())
-    );
-
-    // Scan filesystem for backups
-    let mut all_backups = backup_service.scan_backups().await
-        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?;
+    let backup_service = &state.backup_service;
+    let start = ((page - 1) * limit) as usize;
 
-    // Apply filters
-    if let Some(ref db_config_id) = query.database_config_id {
-        all_backups.retain(|b| b.database_config_id == *db_config_id);
-    }
-    
-    if let Some(ref task_id) = query.task_id {
-        all_backups.retain(|b| b.task_id.as_ref() == Some(task_id));
+    let sort_field = list_params::sort_column(query.sort_by.as_deref(), BACKUP_SORT_FIELDS)?;
+    let matches_filters = |b: &Backup| query.matches(b);
+
+    let cursor = match (query.created_before, &query.after_id) {
+        (Some(created_at), after_id) => Some(Cursor { created_at, id: after_id.clone() }),
+        (None, Some(after_id)) => {
+            let backup = backup_service.find_backup(after_id).await
+                .map_err(|e| ApiError::InternalError(format!("Failed to find backup: {}", e)))?
+                .ok_or_else(|| ApiError::BadRequest(format!("Unknown after_id '{}', pass created_before as well", after_id)))?;
+            let created_at = chrono::DateTime::parse_from_rfc3339(&backup.created_at)
+                .map_err(|_| ApiError::BadRequest(format!("Backup '{}' has no readable creation time, pass created_before as well", after_id)))?;
+            Some(Cursor { created_at: created_at.with_timezone(&chrono::Utc), id: Some(after_id.clone()) })
+        }
+        (None, None) => None,
+    };
+    if let Some(cursor) = &cursor {
+        cursor.check_order(query.sort_by.as_deref(), query.order)?;
+        if query.sort.as_deref() == Some("none") {
+            return Err(ApiError::BadRequest("after_id and created_before need the sorted listing, leave out sort=none".to_string()));
+        }
     }
 
-    let total = all_backups.len();
-    
-    // Apply pagination
-    let start = ((page - 1) * limit) as usize;
-    let end = std::cmp::min(start + limit as usize, total);
-    let backups = if start < total {
-        all_backups[start..end].to_vec()
+    let (backups, total) = if let Some(cursor) = &cursor {
+        let mut all_backups: Vec<(chrono::DateTime<chrono::Utc>, Backup)> = backup_service.scan_backups().await
+            .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?
+            .into_iter()
+            .filter(|b| matches_filters(b))
+            .filter_map(|b| {
+                let created_at = chrono::DateTime::parse_from_rfc3339(&b.created_at).ok()?.with_timezone(&chrono::Utc);
+                cursor.is_before(created_at, &b.id).then_some((created_at, b))
+            })
+            .collect();
+        // Ties on the creation time are ordered by id, as the cursor compares them
+        all_backups.sort_by(|(a_at, a), (b_at, b)| b_at.cmp(a_at).then_with(|| b.id.cmp(&a.id)));
+        // One more backup than asked for tells whether another page follows
+        all_backups.truncate(limit as usize + 1);
+        (all_backups.into_iter().map(|(_, b)| b).collect(), 0)
+    } else if query.sort.as_deref() == Some("none") {
+        // Unsorted listing: stop scanning once this page is filled. The total is only
+        // known up to the current page, plus one when more backups follow.
+        let (backups, has_more) = backup_service.scan_backups_page(start, limit as usize, matches_filters).await
+            .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?;
+        let total = start + backups.len() + usize::from(has_more);
+        (backups, total)
     } else {
-        Vec::new()
+        // Scan filesystem for backups
+        let mut all_backups = backup_service.scan_backups().await
+            .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?;
+
+        // Apply filters
+        all_backups.retain(|b| matches_filters(b));
+
+        // The scan is sorted newest first already
+        match sort_field {
+            "file_size" => all_backups.sort_by_key(|b| b.file_size),
+            "database_name" => all_backups.sort_by_key(|b| b.database_name.to_lowercase()),
+            _ => all_backups.reverse(),
+        }
+        if query.order == SortOrder::Desc {
+            all_backups.reverse();
+        }
+
+        let total = all_backups.len();
+
+        // Apply pagination
+        let end = std::cmp::min(start + limit as usize, total);
+        let backups = if start < total {
+            all_backups[start..end].to_vec()
+        } else {
+            Vec::new()
+        };
+        (backups, total)
     };
 
     // Enrich backups with database information
     let mut enriched_backups = Vec::new();
     for backup in backups {
-        let mut enriched_backup = BackupWithDatabaseInfo {
-            backup: backup.clone(),
-            task_name: None,
-            task_database_name: None,
-            db_config_name: None,
-            db_config_host: None,
-            db_config_database_name: None,
-        };
+        enriched_backups.push(with_database_info(&state.pool, backup).await);
+    }
+
+    if cursor.is_some() {
+        let content = serde_json::json!([&enriched_backups, limit]);
+        let response = cursor_response(enriched_backups, limit, |b| {
+            // Only backups with a readable creation time get past the cursor
+            let created_at = chrono::DateTime::parse_from_rfc3339(&b.backup.created_at).map(|at| at.with_timezone(&chrono::Utc)).unwrap_or_default();
+            list_params::next_cursor(&b.backup.id, created_at)
+        });
+        return Ok(conditional_response(&headers, &content, response));
+    }
 
-        // Get task and database config info if available
-        if let Some(task_id) = &backup.task_id {
-            let sql = "SELECT t.name as task_name, t.database_name as task_database_name, dc.name as db_config_name, dc.host as db_config_host, dc.database_name as db_config_database_name FROM tasks t LEFT JOIN database_configs dc ON t.database_config_id = dc.id WHERE t.id = ?";
-            if let Ok(row) = sqlx::query(sql).bind(task_id).fetch_optional(&pool).await {
-                if let Some(row) = row {
-                    enriched_backup.task_name = row.get("task_name");
-                    enriched_backup.task_database_name = row.get("task_database_name");
-                    enriched_backup.db_config_name = row.get("db_config_name");
-                    enriched_backup.db_config_host = row.get("db_config_host");
-                    enriched_backup.db_config_database_name = row.get("db_config_database_name");
+    let content = serde_json::json!([&enriched_backups, page, limit, total]);
+    Ok(conditional_response(&headers, &content, paginated_response(enriched_backups, page, limit, total as u64)))
+}
+
+/// A backup with the names of its task and database config, when it has a task
+async fn with_database_info(pool: &sqlx::SqlitePool, backup: Backup) -> BackupWithDatabaseInfo {
+    let mut enriched_backup = BackupWithDatabaseInfo {
+        backup,
+        task_name: None,
+        task_database_name: None,
+        db_config_name: None,
+        db_config_host: None,
+        db_config_database_name: None,
+    };
+
+    // Get task and database config info if available
+    if let Some(task_id) = &enriched_backup.backup.task_id {
+        let sql = "SELECT t.name as task_name, t.database_name as task_database_name, dc.name as db_config_name, dc.host as db_config_host, dc.database_name as db_config_database_name FROM tasks t LEFT JOIN database_configs dc ON t.database_config_id = dc.id WHERE t.id = ?";
+        if let Ok(Some(row)) = sqlx::query(sql).bind(task_id).fetch_optional(pool).await {
+            enriched_backup.task_name = row.get("task_name");
+            enriched_backup.task_database_name = row.get("task_database_name");
+            enriched_backup.db_config_name = row.get("db_config_name");
+            enriched_backup.db_config_host = row.get("db_config_host");
+            enriched_backup.db_config_database_name = row.get("db_config_database_name");
+        }
+    }
+
+    enriched_backup
+}
+
+/// Every backup matching the list filters as CSV or JSON, newest first
+async fn export_backups(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListQuery>,
+    Query(export): Query<ExportQuery>,
+) -> ApiResult<Response> {
+    let mut backups = state.backup_service.scan_backups().await
+        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?;
+    backups.retain(|b| query.matches(b));
+
+    // The catalog is in memory already; only the task and config names are looked up per chunk
+    let pool = state.pool.clone();
+    let chunks = futures::stream::iter(backups.chunks(EXPORT_CHUNK_SIZE).map(<[Backup]>::to_vec).collect::<Vec<_>>())
+        .then(move |chunk| {
+            let pool = pool.clone();
+            async move {
+                let mut enriched = Vec::with_capacity(chunk.len());
+                for backup in chunk {
+                    enriched.push(with_database_info(&pool, backup).await);
                 }
+                Ok::<_, Infallible>(enriched)
             }
-        }
+        });
 
-        enriched_backups.push(enriched_backup);
+    Ok(export::download("backups", export.format, chunks))
+}
+
+/// Manifest of every backup for external inventory tooling.
+///
+/// The body is returned as-is so consumers can check `X-Rdumper-Content-Sha256` and, when a
+/// signing key is configured, `X-Rdumper-Signature` against the exact bytes received.
+async fn get_catalog(
+    State(state): State<Arc<AppState>>,
+) -> Result<Response<Body>, ApiError> {
+    let manifest = catalog::build_manifest(&state.pool, &state.backup_service).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to build catalog: {}", e)))?;
+    let body = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| ApiError::InternalError(format!("Failed to serialize catalog: {}", e)))?;
+
+    let mut response = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("X-Rdumper-Content-Sha256", catalog::content_hash(&body));
+    if let Some(key) = &state.config.catalog_signing_key {
+        let signature = catalog::sign(key, &body)
+            .map_err(|e| ApiError::InternalError(e.to_string()))?;
+        response = response.header("X-Rdumper-Signature", format!("hmac-sha256={}", signature));
     }
 
-    Ok(paginated_response(enriched_backups, page, limit, total as u64))
+    Ok(response.body(Body::from(body)).unwrap())
 }
 
 async fn get_backup(
-    State(_pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
-    // Initialize filesystem backup service
-    let backup_service = FilesystemBackupService::new(
-        std::env::var("BACKUP_DIR").unwrap_or_else(|_| "data/backups".to_string())
-    );
-
-    // Scan filesystem for backups
-    let backups = backup_service.scan_backups().await
-        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?;
+    let backup_service = &state.backup_service;
 
-    // Find backup by ID
-    let backup = backups.into_iter()
-        .find(|b| b.id == id)
+    // Look up backup by ID in the backup catalog
+    let backup = backup_service.find_backup(&id).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?
         .ok_or_else(|| ApiError::NotFound("Backup not found".to_string()))?;
 
     // Load full metadata
@@ -142,16 +372,19 @@ async fn get_backup(
 
 
 async fn upload_backup(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     mut multipart: Multipart,
 ) -> ApiResult<impl axum::response::IntoResponse> {
-    let mut file_data = Vec::new();
+    let transfer = TransferGuard::start(TransferKind::Upload, "upload", None, content_length(&headers));
+    let mut temp_path: Option<String> = None;
+    let mut received: u64 = 0;
     let mut filename = String::new();
     let mut database_config_id = String::new();
     let mut compression_type = "gzip".to_string();
 
     // Parse multipart form data
-    while let Some(field) = multipart.next_field().await.map_err(|e| {
+    while let Some(mut field) = multipart.next_field().await.map_err(|e| {
         error!("Multipart field error: {}", e);
         ApiError::BadRequest(format!("Failed to read multipart field: {}", e))
     })? {
@@ -160,16 +393,23 @@ async fn upload_backup(
         
         match field_name {
             "file" => {
+                if temp_path.is_some() {
+                    return Err(ApiError::BadRequest("Only one file can be uploaded per request".to_string()));
+                }
                 if let Some(name) = field.file_name() {
                     filename = name.to_string();
                     error!("File name: '{}'", filename);
                 }
-                let data = field.bytes().await.map_err(|e| {
-                    error!("Failed to read file bytes: {}", e);
-                    ApiError::BadRequest(format!("Failed to read file data: {}", e))
-                })?;
-                file_data = data.to_vec();
-                error!("File data size: {} bytes", file_data.len());
+                let path = upload_temp_path(&state.config.temp_dir, &filename);
+                match write_upload_field(&mut field, &path, &transfer).await {
+                    Ok(size) => received = size,
+                    Err(e) => {
+                        let _ = tokio::fs::remove_file(&path).await;
+                        return Err(e);
+                    }
+                }
+                temp_path = Some(path);
+                error!("File data size: {} bytes", received);
             }
             "database_config_id" => {
                 let text = field.text().await.map_err(|e| {
@@ -193,28 +433,72 @@ async fn upload_backup(
         }
     }
 
-    if file_data.is_empty() {
+    let Some(temp_path) = temp_path else {
         return Err(ApiError::BadRequest("No file provided".to_string()));
-    }
+    };
 
-    if database_config_id.is_empty() {
-        return Err(ApiError::BadRequest("database_config_id is required".to_string()));
+    let result = async {
+        if received == 0 {
+            return Err(ApiError::BadRequest("No file provided".to_string()));
+        }
+
+        if database_config_id.is_empty() {
+            return Err(ApiError::BadRequest("database_config_id is required".to_string()));
+        }
+
+        // Validate database config exists
+        let db_config_exists: Option<(String,)> = sqlx::query_as(
+            "SELECT id FROM database_configs WHERE id = ?"
+        )
+        .bind(&database_config_id)
+        .fetch_optional(&state.pool)
+        .await?;
+
+        if db_config_exists.is_none() {
+            return Err(ApiError::BadRequest("Database configuration not found".to_string()));
+        }
+
+        import_uploaded_archive(&state, &temp_path, &filename, &database_config_id).await
+    }.await;
+    let _ = tokio::fs::remove_file(&temp_path).await;
+    match &result {
+        Ok(_) => transfer.complete(),
+        Err(_) => transfer.fail(),
     }
+    let backup_id = result?;
 
-    // Validate database config exists
-    let db_config_exists: Option<(String,)> = sqlx::query_as(
-        "SELECT id FROM database_configs WHERE id = ?"
-    )
-    .bind(&database_config_id)
-    .fetch_optional(&pool)
-    .await?;
+    Ok(success_response(serde_json::json!({
+        "message": "Backup uploaded successfully",
+        "backup_id": backup_id,
+        "original_filename": filename
+    })))
+}
 
-    if db_config_exists.is_none() {
-        return Err(ApiError::BadRequest("Database configuration not found".to_string()));
+/// Stream a multipart file field to `path` as it arrives, so archives don't have to fit in memory
+async fn write_upload_field(
+    field: &mut axum_extra::extract::multipart::Field,
+    path: &str,
+    transfer: &TransferGuard,
+) -> ApiResult<u64> {
+    let mut file = tokio::fs::File::create(path).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to write backup file: {}", e)))?;
+    let mut written = 0u64;
+    while let Some(chunk) = field.chunk().await.map_err(|e| {
+        error!("Failed to read file bytes: {}", e);
+        ApiError::BadRequest(format!("Failed to read file data: {}", e))
+    })? {
+        file.write_all(&chunk).await
+            .map_err(|e| ApiError::InternalError(format!("Failed to write backup file: {}", e)))?;
+        transfer.add(chunk.len() as u64);
+        written += chunk.len() as u64;
     }
+    file.sync_all().await
+        .map_err(|e| ApiError::InternalError(format!("Failed to write backup file: {}", e)))?;
+    Ok(written)
+}
 
-    // Create temporary file first
-    let temp_dir = std::env::var("TEMP_DIR").unwrap_or_else(|_| "/tmp".to_string());
+/// Temporary location for a multipart upload, named after the uploaded file
+fn upload_temp_path(temp_dir: &str, filename: &str) -> String {
     let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
     let file_extension = if filename.ends_with(".tar.gz") {
         "tar.gz"
@@ -229,61 +513,538 @@ async fn upload_backup(
         .map(|c| if c.is_alphanumeric() || c == '.' || c == '-' || c == '_' { c } else { '_' })
         .collect::<String>();
     
-    let temp_filename = format!("uploaded_{}_{}.{}", 
+    let temp_filename = format!("uploaded_{}_{}_{}.{}", 
         safe_filename.trim_end_matches(&format!(".{}", file_extension)),
         timestamp,
+        &uuid::Uuid::new_v4().to_string()[..8],
         file_extension
     );
     
-    let temp_path = format!("{}/{}", temp_dir, temp_filename);
+    format!("{}/{}", temp_dir, temp_filename)
+}
 
-    // Write file to temporary location
-    tokio::fs::write(&temp_path, &file_data).await.map_err(|e| {
-        ApiError::InternalError(format!("Failed to write backup file: {}", e))
-    })?;
+/// Upload the loose files of a mydumper output directory and package them as a backup.
+///
+/// Files can be spread over several requests by passing `finalize=false` and reusing the
+/// returned `upload_id`; the request that finalizes packages everything via `BackupProcess`.
+async fn upload_directory(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    mut multipart: Multipart,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let transfer = TransferGuard::start(TransferKind::Upload, "directory upload", None, content_length(&headers));
+    let mut upload_id: Option<String> = None;
+    let mut staging_dir: Option<std::path::PathBuf> = None;
+    let mut database_config_id = String::new();
+    let mut finalize = true;
+    let mut received_files = 0;
+
+    while let Some(mut field) = multipart.next_field().await
+        .map_err(|e| ApiError::BadRequest(format!("Failed to read multipart field: {}", e)))? {
+        let field_name = field.name().unwrap_or("").to_string();
+
+        // Any field with a file name is a dump file
+        if let Some(file_name) = field.file_name().map(|name| name.to_string()) {
+            // Browsers send the relative path for directory uploads; mydumper output is flat
+            let Some(base_name) = StdPath::new(&file_name).file_name().and_then(|n| n.to_str()).map(|n| n.to_string()) else {
+                return Err(ApiError::BadRequest(format!("Invalid file name: {}", file_name)));
+            };
+
+            let dir = match &staging_dir {
+                Some(dir) => dir.clone(),
+                None => {
+                    let id = upload_id.get_or_insert_with(|| uuid::Uuid::new_v4().to_string()).clone();
+                    let dir = state.upload_sessions.staging_dir(&id).await
+                        .map_err(|e| ApiError::BadRequest(format!("Failed to prepare upload: {}", e)))?;
+                    staging_dir = Some(dir.clone());
+                    dir
+                }
+            };
+
+            let mut file = tokio::fs::File::create(dir.join(&base_name)).await
+                .map_err(|e| ApiError::InternalError(format!("Failed to create {}: {}", base_name, e)))?;
+            while let Some(chunk) = field.chunk().await
+                .map_err(|e| ApiError::BadRequest(format!("Failed to read {}: {}", base_name, e)))? {
+                file.write_all(&chunk).await
+                    .map_err(|e| ApiError::InternalError(format!("Failed to write {}: {}", base_name, e)))?;
+                transfer.add(chunk.len() as u64);
+            }
+            file.sync_all().await
+                .map_err(|e| ApiError::InternalError(format!("Failed to write {}: {}", base_name, e)))?;
+            received_files += 1;
+            continue;
+        }
+
+        let text = field.text().await
+            .map_err(|e| ApiError::BadRequest(format!("Failed to read {}: {}", field_name, e)))?;
+        match field_name.as_str() {
+            // Must be sent before the files so they land in the existing staging directory
+            "upload_id" if staging_dir.is_none() => upload_id = Some(text),
+            "database_config_id" => database_config_id = text,
+            "finalize" => finalize = text != "false",
+            _ => {}
+        }
+    }
+
+    let upload_id = upload_id
+        .ok_or_else(|| ApiError::BadRequest("No files provided".to_string()))?;
+    let staging_dir = state.upload_sessions.staging_dir(&upload_id).await
+        .map_err(|e| ApiError::BadRequest(format!("Failed to prepare upload: {}", e)))?;
+
+    transfer.complete();
+
+    if !finalize {
+        return Ok(success_response(serde_json::json!({
+            "upload_id": upload_id,
+            "received_files": received_files,
+            "finalized": false
+        })));
+    }
+
+    if database_config_id.is_empty() {
+        return Err(ApiError::BadRequest("database_config_id is required".to_string()));
+    }
 
-    // Get database config for metadata
     let db_config: crate::models::DatabaseConfig = sqlx::query_as(
         "SELECT * FROM database_configs WHERE id = ?"
     )
     .bind(&database_config_id)
-    .fetch_one(&pool)
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or_else(|| ApiError::BadRequest("Database configuration not found".to_string()))?;
+
+    // Make sure this is actually mydumper output before creating a backup from it
+    let mut file_names = Vec::new();
+    let mut entries = tokio::fs::read_dir(&staging_dir).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to read upload: {}", e)))?;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        file_names.push(entry.file_name().to_string_lossy().to_string());
+    }
+    let looks_like_dump = file_names.iter().any(|name| {
+        name == "metadata" || name.ends_with(".sql") || name.ends_with(".sql.gz") || name.ends_with(".sql.zst")
+    });
+    if !looks_like_dump {
+        return Err(ApiError::BadRequest(
+            "Uploaded files do not look like mydumper output (no metadata or .sql files)".to_string()
+        ));
+    }
+
+    let backup_id = uuid::Uuid::new_v4().to_string();
+    let mut backup_process = state.backup_service.create_backup_process(&backup_id, &db_config, None).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to create backup process: {}", e)))?;
+
+    let tmp_dir = backup_process.tmp_dir().to_path_buf();
+    tokio::fs::create_dir_all(&tmp_dir).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to create tmp directory: {}", e)))?;
+
+    for name in &file_names {
+        let source = staging_dir.join(name);
+        let target = tmp_dir.join(name);
+        // Temp and backup dirs may live on different filesystems
+        if tokio::fs::rename(&source, &target).await.is_err() {
+            tokio::fs::copy(&source, &target).await
+                .map_err(|e| ApiError::InternalError(format!("Failed to copy {}: {}", name, e)))?;
+        }
+    }
+
+    backup_process.complete().await
+        .map_err(|e| ApiError::InternalError(format!("Failed to complete backup: {}", e)))?;
+    state.upload_sessions.remove(&upload_id).await;
+
+    Ok(success_response(serde_json::json!({
+        "message": "Backup uploaded successfully",
+        "backup_id": backup_id,
+        "upload_id": upload_id,
+        "file_count": file_names.len(),
+        "finalized": true
+    })))
+}
+
+/// Register a mydumper directory or archive already on the server, with an explicit config and database mapping
+async fn register_backup(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<RegisterBackupRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let import_dir = state.config.import_dir.as_deref()
+        .ok_or_else(|| ApiError::BadRequest("Registering backups is disabled; start the server with --import-dir".to_string()))?;
+    let source = backup_register::resolve_import_path(import_dir, &req.path).await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    let database_config: crate::models::DatabaseConfig = sqlx::query_as(
+        "SELECT * FROM database_configs WHERE id = ?"
+    )
+    .bind(&req.database_config_id)
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or_else(|| ApiError::BadRequest("Database configuration not found".to_string()))?;
+
+    let task = match &req.task_id {
+        Some(task_id) => {
+            let task: crate::models::Task = sqlx::query_as("SELECT * FROM tasks WHERE id = ?")
+                .bind(task_id)
+                .fetch_optional(&state.pool)
+                .await?
+                .ok_or_else(|| ApiError::BadRequest("Task not found".to_string()))?;
+            if task.database_config_id != database_config.id {
+                return Err(ApiError::BadRequest("Task belongs to a different database configuration".to_string()));
+            }
+            Some(task)
+        }
+        None => None,
+    };
+
+    let options = backup_register::RegisterOptions {
+        database_config,
+        task,
+        database_name: req.database_name.filter(|name| !name.is_empty()),
+        created_at: req.created_at,
+        backup_type: backup_register::EXTERNAL_BACKUP_TYPE,
+    };
+    let metadata = backup_register::register_backup(
+        &state.backup_service,
+        &state.config.temp_dir,
+        &source,
+        options,
+    ).await
+    .map_err(|e| ApiError::BadRequest(format!("Failed to register backup: {}", e)))?;
+
+    let _ = state.logging_service.log_system_with_entity(
+        "backup",
+        &metadata.id,
+        &format!("Registered external backup from {}", source.display()),
+        crate::models::log::LogLevel::Info
+    ).await;
+    events::publish(AppEvent::BackupCreated {
+        backup_id: metadata.id.clone(),
+        file_path: metadata.file_path.clone(),
+    });
+
+    Ok(success_response(serde_json::json!({
+        "message": "Backup registered successfully",
+        "backup_id": metadata.id,
+        "file_path": metadata.file_path,
+        "created_at": metadata.created_at
+    })))
+}
+
+/// Download an archive from a remote URL on the server and import it as a backup, tracked as a job
+async fn import_from_url(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ImportUrlRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let url = url_import::validate_url(&req.url)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let filename = req.filename.clone()
+        .or_else(|| url_import::filename_from_url(&url))
+        .ok_or_else(|| ApiError::BadRequest("Could not determine a file name from the URL".to_string()))?;
+
+    // Validate database config exists
+    let db_config_exists: Option<(String,)> = sqlx::query_as(
+        "SELECT id FROM database_configs WHERE id = ?"
+    )
+    .bind(&req.database_config_id)
+    .fetch_optional(&state.pool)
     .await?;
 
-    // Initialize filesystem backup service
-    let backup_service = FilesystemBackupService::new(
-        std::env::var("BACKUP_DIR").unwrap_or_else(|_| "data/backups".to_string())
-    );
+    if db_config_exists.is_none() {
+        return Err(ApiError::BadRequest("Database configuration not found".to_string()));
+    }
 
-    // For uploaded files, we need to extract them first if they are archives
-    let extract_dir = if filename.ends_with(".tar.gz") || filename.ends_with(".tar.zst") {
-        // Extract the uploaded archive to a temporary directory
-        let extract_path = format!("{}/extracted_{}", temp_dir, timestamp);
-        std::fs::create_dir_all(&extract_path).map_err(|e| ApiError::InternalError(format!("Failed to create extract directory: {}", e)))?;
-        
-        let mut cmd = tokio::process::Command::new("tar");
-        if filename.ends_with(".tar.gz") {
-            cmd.args(&["-xzf", &temp_path, "-C", &extract_path]);
-        } else {
-            cmd.args(&["--zstd", "-xf", &temp_path, "-C", &extract_path]);
+    // Create an import job
+    let job = Job::new(CreateJobRequest {
+        task_id: None,
+        used_database: None,
+        job_type: JobType::Import,
+        backup_path: None,
+    });
+
+    sqlx::query(
+        r#"
+        INSERT INTO jobs (id, task_id, job_type, status, progress, started_at, completed_at, error_message, log_output, backup_path, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&job.id)
+    .bind(&job.task_id)
+    .bind(&job.job_type)
+    .bind(&job.status)
+    .bind(job.progress)
+    .bind(job.started_at)
+    .bind(job.completed_at)
+    .bind(&job.error_message)
+    .bind(&job.log_output)
+    .bind(&job.backup_path)
+    .bind(job.created_at)
+    .execute(&state.pool)
+    .await?;
+
+    let job_id = job.id.clone();
+    let task_state = state.clone();
+    let database_config_id = req.database_config_id.clone();
+    let download_name = filename.clone();
+
+    tokio::spawn(async move {
+        let pool = &task_state.pool;
+        let _ = sqlx::query("UPDATE jobs SET status = ?, started_at = ? WHERE id = ?")
+            .bind("running")
+            .bind(chrono::Utc::now())
+            .bind(&job_id)
+            .execute(pool)
+            .await;
+        events::publish(AppEvent::job_status(&job_id, "running"));
+
+        // Mirror download progress into the job
+        let (progress_tx, mut progress_rx) = tokio::sync::watch::channel(0u32);
+        let progress_pool = pool.clone();
+        let progress_job_id = job_id.clone();
+        let progress_task = tokio::spawn(async move {
+            while progress_rx.changed().await.is_ok() {
+                let percent = *progress_rx.borrow_and_update();
+                let _ = sqlx::query("UPDATE jobs SET progress = ? WHERE id = ?")
+                    .bind(percent as i32)
+                    .bind(&progress_job_id)
+                    .execute(&progress_pool)
+                    .await;
+            }
+        });
+
+        let temp_path = format!("{}/import_{}", task_state.config.temp_dir, job_id);
+        let result = match url_import::download_archive(url, StdPath::new(&temp_path), Some(progress_tx)).await {
+            Ok(_) => import_uploaded_archive(&task_state, &temp_path, &download_name, &database_config_id).await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(format!("Download failed: {}", e)),
+        };
+        let _ = progress_task.await;
+        let _ = tokio::fs::remove_file(&temp_path).await;
+
+        match result {
+            Ok(backup_id) => {
+                let _ = sqlx::query("UPDATE jobs SET status = ?, completed_at = ?, progress = ? WHERE id = ?")
+                    .bind("completed")
+                    .bind(chrono::Utc::now())
+                    .bind(100)
+                    .bind(&job_id)
+                    .execute(pool)
+                    .await;
+                events::publish(AppEvent::job_status(&job_id, "completed"));
+                tracing::info!("Imported backup {} from URL", backup_id);
+            }
+            Err(e) => {
+                error!("URL import failed: {}", e);
+                let _ = sqlx::query("UPDATE jobs SET status = ?, error_message = ?, completed_at = ? WHERE id = ?")
+                    .bind("failed")
+                    .bind(&e)
+                    .bind(chrono::Utc::now())
+                    .bind(&job_id)
+                    .execute(pool)
+                    .await;
+                events::publish(AppEvent::job_status(&job_id, "failed"));
+            }
         }
-        
-        let status = cmd.status().await.map_err(|e| ApiError::InternalError(format!("Failed to execute tar command: {}", e)))?;
-        if !status.success() {
-            return Err(ApiError::InternalError("Failed to extract uploaded archive".to_string()));
+    });
+
+    Ok(success_response(serde_json::json!({
+        "message": "Import job created successfully",
+        "job_id": job.id,
+        "filename": filename
+    })))
+}
+
+/// Start a resumable upload; chunks are then sent with PATCH and an `Upload-Offset` header
+async fn create_upload(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreateUploadRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    if req.filename.is_empty() {
+        return Err(ApiError::BadRequest("filename is required".to_string()));
+    }
+    if req.size == 0 {
+        return Err(ApiError::BadRequest("size must be greater than zero".to_string()));
+    }
+
+    // Validate database config exists
+    let db_config_exists: Option<(String,)> = sqlx::query_as(
+        "SELECT id FROM database_configs WHERE id = ?"
+    )
+    .bind(&req.database_config_id)
+    .fetch_optional(&state.pool)
+    .await?;
+
+    if db_config_exists.is_none() {
+        return Err(ApiError::BadRequest("Database configuration not found".to_string()));
+    }
+
+    let session = UploadSession {
+        id: uuid::Uuid::new_v4().to_string(),
+        filename: req.filename,
+        database_config_id: req.database_config_id,
+        compression_type: req.compression_type.unwrap_or_else(|| "gzip".to_string()),
+        total_size: req.size,
+        created_at: chrono::Utc::now(),
+    };
+    state.upload_sessions.create(&session).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to create upload: {}", e)))?;
+    transfers::register(&session.id, TransferKind::Upload, &session.filename, None, Some(session.total_size));
+
+    Ok(success_response(serde_json::json!({
+        "upload_id": session.id,
+        "offset": 0,
+        "size": session.total_size
+    })))
+}
+
+/// Report how many bytes of a resumable upload have been received
+async fn get_upload(
+    State(state): State<Arc<AppState>>,
+    Path(upload_id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let session = find_upload(&state, &upload_id).await?;
+    let offset = state.upload_sessions.offset(&session.id).await?;
+
+    Ok(success_response(serde_json::json!({
+        "upload_id": session.id,
+        "filename": session.filename,
+        "offset": offset,
+        "size": session.total_size
+    })))
+}
+
+/// Append the request body to a resumable upload at the given `Upload-Offset`
+async fn upload_chunk(
+    State(state): State<Arc<AppState>>,
+    Path(upload_id): Path<String>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Response<Body>, ApiError> {
+    let session = find_upload(&state, &upload_id).await?;
+    let offset = headers.get("Upload-Offset")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+        .ok_or_else(|| ApiError::BadRequest("Missing or invalid Upload-Offset header".to_string()))?;
+
+    let conflict = |message: String, offset: u64| {
+        Response::builder()
+            .status(StatusCode::CONFLICT)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header("Upload-Offset", offset.to_string())
+            .body(Body::from(serde_json::json!({
+                "error": message,
+                "offset": offset,
+                "timestamp": chrono::Utc::now().to_rfc3339()
+            }).to_string()))
+            .unwrap()
+    };
+
+    // The transfer record may be gone after a restart; recreate it so progress stays visible
+    if transfers::get(&session.id).is_none() {
+        transfers::register(&session.id, TransferKind::Upload, &session.filename, None, Some(session.total_size));
+    }
+    transfers::set_progress(&session.id, offset);
+    let transfer_id = session.id.clone();
+    let chunk = body.into_data_stream().inspect(move |data| {
+        if let Ok(data) = data {
+            transfers::add_progress(&transfer_id, data.len() as u64);
         }
-        
-        extract_path
-    } else {
-        // For non-archive files, create a directory and copy the file
-        let file_dir = format!("{}/file_{}", temp_dir, timestamp);
-        std::fs::create_dir_all(&file_dir).map_err(|e| ApiError::InternalError(format!("Failed to create file directory: {}", e)))?;
-        std::fs::copy(&temp_path, format!("{}/{}", file_dir, filename)).map_err(|e| ApiError::InternalError(format!("Failed to copy file: {}", e)))?;
-        file_dir
+    });
+
+    let result = state.upload_sessions.append_chunk(&session, offset, chunk).await;
+    // Re-sync with what actually reached the disk
+    if let Ok(current) = state.upload_sessions.offset(&session.id).await {
+        transfers::set_progress(&session.id, current);
+    }
+
+    let new_offset = match result {
+        Ok(new_offset) => new_offset,
+        Err(ChunkError::OffsetMismatch { expected }) => {
+            return Ok(conflict(format!("Upload offset mismatch, expected {}", expected), expected));
+        }
+        Err(ChunkError::Busy) => {
+            let current = state.upload_sessions.offset(&session.id).await?;
+            return Ok(conflict("Another chunk is currently being written".to_string(), current));
+        }
+        Err(ChunkError::TooLarge) => {
+            return Err(ApiError::BadRequest("Chunk exceeds the declared upload size".to_string()));
+        }
+        Err(ChunkError::Io(e)) => return Err(ApiError::InternalError(format!("Failed to write chunk: {}", e))),
     };
 
-    // Create backup using new BackupProcess system
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("Upload-Offset", new_offset.to_string())
+        .body(Body::from(serde_json::json!({
+            "success": true,
+            "data": {
+                "upload_id": session.id,
+                "offset": new_offset,
+                "size": session.total_size
+            },
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        }).to_string()))
+        .unwrap())
+}
+
+/// Assemble a fully received upload into a backup, like a regular multipart upload
+async fn complete_upload(
+    State(state): State<Arc<AppState>>,
+    Path(upload_id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let session = find_upload(&state, &upload_id).await?;
+    let offset = state.upload_sessions.offset(&session.id).await?;
+    if offset != session.total_size {
+        return Err(ApiError::BadRequest(format!(
+            "Upload incomplete: received {} of {} bytes", offset, session.total_size
+        )));
+    }
+
+    let part_path = state.upload_sessions.part_path(&session.id).to_string_lossy().to_string();
+    let result = import_uploaded_archive(&state, &part_path, &session.filename, &session.database_config_id).await;
+    transfers::finish(&session.id, if result.is_ok() { TransferStatus::Completed } else { TransferStatus::Failed });
+    let backup_id = result?;
+    state.upload_sessions.remove(&session.id).await;
+
+    Ok(success_response(serde_json::json!({
+        "message": "Backup uploaded successfully",
+        "backup_id": backup_id,
+        "original_filename": session.filename
+    })))
+}
+
+async fn find_upload(state: &AppState, upload_id: &str) -> ApiResult<UploadSession> {
+    state.upload_sessions.get(upload_id).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to load upload: {}", e)))?
+        .ok_or_else(|| ApiError::NotFound("Upload not found".to_string()))
+}
+
+/// Package an uploaded archive (or single dump file) into a new backup via `BackupProcess`
+async fn import_uploaded_archive(
+    state: &AppState,
+    temp_path: &str,
+    filename: &str,
+    database_config_id: &str,
+) -> ApiResult<String> {
+    let temp_dir = &state.config.temp_dir;
+
+    // Get database config for metadata
+    let db_config: crate::models::DatabaseConfig = sqlx::query_as(
+        "SELECT * FROM database_configs WHERE id = ?"
+    )
+    .bind(database_config_id)
+    .fetch_one(&state.pool)
+    .await?;
+
+    let backup_service = &state.backup_service;
+
     let backup_id = uuid::Uuid::new_v4().to_string();
+
+    // Extract the upload; the format is detected from its content, not its name
+    let extract_dir = format!("{}/extracted_{}", temp_dir, backup_id);
+    let format = archive_format::extract_async(temp_path.into(), (&extract_dir).into()).await
+        .map_err(|e| ApiError::BadRequest(format!("Failed to extract uploaded file: {}", e)))?;
+    tracing::info!("Importing {} as {} archive", filename, format);
+    let content_dir = archive_format::content_root(StdPath::new(&extract_dir));
+
+    // Create backup using new BackupProcess system
     let mut backup_process = backup_service.create_backup_process(&backup_id, &db_config, None).await
         .map_err(|e| ApiError::InternalError(format!("Failed to create backup process: {}", e)))?;
     
@@ -292,7 +1053,7 @@ async fn upload_backup(
     std::fs::create_dir_all(&tmp_dir).map_err(|e| ApiError::InternalError(format!("Failed to create tmp directory: {}", e)))?;
     
     // Copy files from extract_dir to tmp_dir
-    let mut entries = std::fs::read_dir(&extract_dir).map_err(|e| ApiError::InternalError(format!("Failed to read extract directory: {}", e)))?;
+    let mut entries = std::fs::read_dir(&content_dir).map_err(|e| ApiError::InternalError(format!("Failed to read extract directory: {}", e)))?;
     while let Some(entry) = entries.next() {
         let entry = entry.map_err(|e| ApiError::InternalError(format!("Failed to read directory entry: {}", e)))?;
         let path = entry.path();
@@ -305,44 +1066,30 @@ async fn upload_backup(
     // Complete the backup process
     backup_process.complete().await.map_err(|e| ApiError::InternalError(format!("Failed to complete backup: {}", e)))?;
 
-    // Clean up temporary files and directories
-    let _ = tokio::fs::remove_file(&temp_path).await;
+    // Clean up the extracted files
     let _ = tokio::fs::remove_dir_all(&extract_dir).await;
 
-    Ok(success_response(serde_json::json!({
-        "message": "Backup uploaded successfully",
-        "backup_id": backup_id,
-        "original_filename": filename
-    })))
+    Ok(backup_id)
 }
 
 async fn delete_backup(
-    State(_pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
-    // Initialize filesystem backup service
-    let backup_service = FilesystemBackupService::new(
-        std::env::var("BACKUP_DIR").unwrap_or_else(|_| "data/backups".to_string())
-    );
+    let backup_service = &state.backup_service;
 
-    // Scan filesystem for backups
-    let backups = backup_service.scan_backups().await
-        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?;
-
-    // Find backup by ID
-    let backup = backups.into_iter()
-        .find(|b| b.id == id)
+    // Look up backup by ID in the backup catalog
+    let backup = backup_service.find_backup(&id).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?
         .ok_or_else(|| ApiError::NotFound("Backup not found".to_string()))?;
 
     // Delete backup from filesystem
     backup_service.delete_backup(&backup).await
         .map_err(|e| ApiError::InternalError(format!("Failed to delete backup: {}", e)))?;
+    events::publish(AppEvent::BackupDeleted { backup_id: id.clone() });
 
     // Log the deletion
-    use crate::services::logging::LoggingService;
-    use std::sync::Arc;
-    let logging_service = LoggingService::new(Arc::new(_pool.clone()));
-    let _ = logging_service.log_system_with_entity(
+    let _ = state.logging_service.log_system_with_entity(
         "backup",
         &id,
         &format!("Backup '{}' deleted", backup.backup_type),
@@ -353,23 +1100,64 @@ async fn delete_backup(
 }
 
 async fn restore_backup(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
     Json(req): Json<RestoreRequest>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
-    // Initialize filesystem backup service
-    let backup_service = FilesystemBackupService::new(
-        std::env::var("BACKUP_DIR").unwrap_or_else(|_| "data/backups".to_string())
-    );
+    let backup_service = &state.backup_service;
 
-    // Scan filesystem for backups
-    let backups = backup_service.scan_backups().await
-        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?;
+    // Look up backup by ID in the backup catalog
+    let backup = backup_service.find_backup(&id).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?
+        .ok_or_else(|| ApiError::NotFound("Backup not found".to_string()))?;
+
+    let job_id = start_restore(&state, &backup, req).await?;
 
-    // Find backup by ID
+    Ok(success_response(serde_json::json!({
+        "message": "Restore job created successfully",
+        "job_id": job_id,
+        "backup_id": backup.id
+    })))
+}
+
+/// Restore the latest full backup of a database taken before `point_in_time`, then replay
+/// its archived binlogs up to that moment
+async fn restore_point_in_time(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<PointInTimeRestoreRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let backups = state.backup_service.scan_backups().await
+        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?;
     let backup = backups.into_iter()
-        .find(|b| b.id == id)
-        .ok_or_else(|| ApiError::NotFound("Backup not found".to_string()))?;
+        .filter(|b| b.database_config_id == req.database_config_id && b.database_name == req.database_name)
+        .filter(|b| binlog::check_point_in_time(b, req.point_in_time).is_ok())
+        .max_by(|a, b| a.created_at.cmp(&b.created_at))
+        .ok_or_else(|| ApiError::BadRequest(format!(
+            "No backup of '{}' with archived binlogs covers {}",
+            req.database_name, req.point_in_time
+        )))?;
+
+    let job_id = start_restore(&state, &backup, RestoreRequest {
+        new_database_name: req.new_database_name,
+        overwrite_existing: req.overwrite_existing,
+        point_in_time: Some(req.point_in_time),
+        tables: Vec::new(),
+        target_config_id: req.target_config_id,
+        myloader: req.myloader,
+        ..Default::default()
+    }).await?;
+
+    Ok(success_response(serde_json::json!({
+        "message": "Point-in-time restore job created successfully",
+        "job_id": job_id,
+        "backup_id": backup.id,
+        "backup_created_at": backup.created_at
+    })))
+}
+
+/// Check a restore of `backup` and queue it as a restore job for the worker, returning the job's id
+async fn start_restore(state: &AppState, backup: &Backup, req: RestoreRequest) -> ApiResult<String> {
+    let backup_service = &state.backup_service;
 
     // Validate backup file exists
     if !StdPath::new(&backup.file_path).exists() {
@@ -380,27 +1168,98 @@ async fn restore_backup(
     let metadata = backup.load_metadata().await
         .map_err(|e| ApiError::InternalError(format!("Failed to load backup metadata: {}", e)))?;
 
-    // Use the original database config for restore
-    let target_config_id = backup.database_config_id.clone();
+    if let Some(until) = req.point_in_time {
+        binlog::check_point_in_time(backup, until).map_err(ApiError::BadRequest)?;
+    }
+    let all_databases = metadata.source_database() == ALL_DATABASES;
+    if req.myloader.queries_per_transaction == Some(0) {
+        return Err(ApiError::BadRequest("queries_per_transaction must be at least 1".to_string()));
+    }
+    if all_databases && req.new_database_name.is_some() {
+        return Err(ApiError::BadRequest("Backups of all databases restore into their original databases, rename them with database_map".to_string()));
+    }
+    req.remap.validate().map_err(ApiError::BadRequest)?;
+    if !req.remap.is_empty() && req.point_in_time.is_some() {
+        return Err(ApiError::BadRequest("Binlogs replay under the original names, point-in-time restores can't remap the schema".to_string()));
+    }
+    let tables = if req.tables.is_empty() {
+        Vec::new()
+    } else {
+        if req.point_in_time.is_some() {
+            return Err(ApiError::BadRequest("Point-in-time restores replay whole databases, restore single tables without a point in time".to_string()));
+        }
+        let files = backup_service.list_archive_contents(backup).await
+            .map_err(|e| ApiError::InternalError(format!("Failed to read backup archive: {}", e)))?;
+        let dumped: std::collections::BTreeSet<(String, String)> = files.into_iter()
+            .filter_map(|file| Some((file.database_name?, file.table_name?)))
+            .collect();
+        resolve_restore_tables(&req.tables, &dumped).map_err(ApiError::BadRequest)?
+    };
 
-    // Create a restore job
+    // Restore into the backup's own database config unless another server is chosen
+    let target_config_id = req.target_config_id.clone().unwrap_or_else(|| backup.database_config_id.clone());
+    let target_config: crate::models::DatabaseConfig = sqlx::query_as(
+        "SELECT * FROM database_configs WHERE id = ?"
+    )
+    .bind(&target_config_id)
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Target database configuration not found".to_string()))?;
+
+    // Generate new database name if requested
+    let new_database_name = if let Some(new_name) = req.new_database_name {
+        Some(new_name)
+    } else if all_databases {
+        None
+    } else if let Some(mapped) = req.remap.database_map.get(metadata.source_database()) {
+        Some(mapped.clone())
+    } else if req.overwrite_existing {
+        // Overwrite the database the backup was taken from, on whichever server is the target
+        Some(metadata.source_database().to_string())
+    } else {
+        // Generate a new name with hash
+        let hash = &backup.id[..5];
+        Some(format!("{}_{}", target_config.database_name, hash))
+    };
+
+    // Fail before the job starts when the target can't take the backup
+    let target_version = restore_target::check(&target_config, metadata.server_version.as_deref(), new_database_name.as_deref())
+        .await
+        .map_err(ApiError::BadRequest)?;
+
+    // Queue a restore job, recording which backup is loaded where and how
     let job_request = CreateJobRequest {
         task_id: None,
-        used_database: None, // Restore jobs don't have a specific database
+        used_database: Some(format!("{}/{}", target_config.name, new_database_name.as_deref().unwrap_or(ALL_DATABASES))),
         job_type: JobType::Restore,
         backup_path: Some(backup.file_path.clone()),
     };
 
-    let job = Job::new(job_request);
+    let mut job = Job::new(job_request);
+    job.source_backup_id = Some(backup.id.clone());
+    job.target_config_id = Some(target_config.id.clone());
+    let restore_request = RestoreRequest {
+        new_database_name,
+        overwrite_existing: req.overwrite_existing,
+        point_in_time: req.point_in_time,
+        tables,
+        target_config_id: Some(target_config.id.clone()),
+        myloader: req.myloader,
+        remap: req.remap,
+    };
+    job.priority = Job::PRIORITY_MANUAL;
+    job.restore_request = Some(serde_json::to_string(&restore_request)
+        .map_err(|e| ApiError::InternalError(format!("Failed to store restore request: {}", e)))?);
 
     sqlx::query(
         r#"
-        INSERT INTO jobs (id, task_id, job_type, status, progress, started_at, completed_at, error_message, log_output, backup_path, created_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO jobs (id, task_id, used_database, job_type, status, progress, started_at, completed_at, error_message, log_output, backup_path, created_at, source_backup_id, target_config_id, restore_request, priority)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#
     )
     .bind(&job.id)
     .bind(&job.task_id)
+    .bind(&job.used_database)
     .bind(&job.job_type)
     .bind(&job.status)
     .bind(&job.progress)
@@ -410,116 +1269,327 @@ async fn restore_backup(
     .bind(&job.log_output)
     .bind(&job.backup_path)
     .bind(&job.created_at)
-    .execute(&pool)
+    .bind(&job.source_backup_id)
+    .bind(&job.target_config_id)
+    .bind(&job.restore_request)
+    .bind(job.priority)
+    .execute(&state.pool)
     .await?;
-
-    // Start the actual restore process using myloader
-    let pool_clone = pool.clone();
-    let mydumper_service = crate::services::MydumperService::new(
-        std::env::var("BACKUP_DIR").unwrap_or_else(|_| "data/backups".to_string()),
-        std::env::var("LOG_BASE_DIR").unwrap_or_else(|_| "backend/data/logs".to_string()),
+    info!(
+        "Restore job {}: backup {} ({}) into {} ({})",
+        job.id, backup.id, metadata.server_version.as_deref().unwrap_or("unknown version"), target_config.name, target_version
     );
 
-    // Get target database config
-    let target_config: crate::models::DatabaseConfig = sqlx::query_as(
-        "SELECT * FROM database_configs WHERE id = ?"
-    )
-    .bind(&target_config_id)
-    .fetch_one(&pool)
-    .await?;
+    // The worker starts the job once a slot is free
+    state.worker.wake();
 
-    // Generate new database name if requested
-    let new_database_name = if let Some(new_name) = req.new_database_name {
-        Some(new_name)
-    } else if req.overwrite_existing {
-        None
-    } else {
-        // Generate a new name with hash
-        let hash = &backup.id[..5];
-        Some(format!("{}_{}", target_config.database_name, hash))
-    };
+    Ok(job.id)
+}
 
-    // Clone job.id before moving into async closure
-    let job_id = job.id.clone();
-    let backup_id = backup.id.clone();
-    let job_id_for_async = job_id.clone();
+/// Requested tables as `database.table` entries of the dump; plain names must be unique across its databases
+fn resolve_restore_tables(requested: &[String], dumped: &std::collections::BTreeSet<(String, String)>) -> Result<Vec<String>, String> {
+    requested.iter()
+        .map(|name| {
+            let matches: Vec<&(String, String)> = match name.split_once('.') {
+                Some((database, table)) => dumped.iter().filter(|(db, t)| db == database && t == table).collect(),
+                None => dumped.iter().filter(|(_, t)| t == name).collect(),
+            };
+            match matches.as_slice() {
+                [(database, table)] => Ok(format!("{}.{}", database, table)),
+                [] => Err(format!("Table '{}' isn't in this backup", name)),
+                _ => Err(format!("Table '{}' is in several databases of this backup, name it as database.table", name)),
+            }
+        })
+        .collect()
+}
 
-    // Start restore process asynchronously
-    tokio::spawn(async move {
-        // Update job status to running
-        let _ = sqlx::query(
-            "UPDATE jobs SET status = ?, started_at = ? WHERE id = ?"
-        )
-        .bind("running")
-        .bind(chrono::Utc::now())
-        .bind(&job_id_for_async)
-        .execute(&pool_clone)
-        .await;
-
-        if let Err(e) = mydumper_service.restore_backup(
-            &target_config,
-            &backup.file_path,
-            new_database_name.as_deref(),
-            req.overwrite_existing,
-        ).await {
-            error!("Restore failed: {}", e);
-            
-            // Update job status to failed
-            let _ = sqlx::query(
-                "UPDATE jobs SET status = ?, error_message = ?, completed_at = ? WHERE id = ?"
-            )
-            .bind("failed")
-            .bind(e.to_string())
-            .bind(chrono::Utc::now())
-            .bind(&job_id_for_async)
-            .execute(&pool_clone)
-            .await;
-        } else {
-            // Update job status to completed
-            let _ = sqlx::query(
-                "UPDATE jobs SET status = ?, completed_at = ?, progress = ? WHERE id = ?"
-            )
-            .bind("completed")
-            .bind(chrono::Utc::now())
-            .bind(100)
-            .bind(&job_id_for_async)
-            .execute(&pool_clone)
-            .await;
-        }
-    });
+/// Start a verify job that re-hashes the archive and reads it end to end
+async fn verify_backup(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    req: Option<Json<VerifyRequest>>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let backup_service = &state.backup_service;
+
+    // Look up backup by ID in the backup catalog
+    let backup = backup_service.find_backup(&id).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?
+        .ok_or_else(|| ApiError::NotFound("Backup not found".to_string()))?;
+
+    let task_id = backup.task_id.clone();
+    let mode = req.map(|Json(req)| req.mode).unwrap_or_default();
+    let job_id = verification::start_verify_job(&state.pool, &state.config, backup, task_id, mode).await?;
 
     Ok(success_response(serde_json::json!({
-        "message": "Restore job created successfully",
+        "message": "Verification started",
         "job_id": job_id,
-        "backup_id": backup_id
+        "backup_id": id
     })))
 }
 
-async fn download_backup(
-    State(_pool): State<SqlitePool>,
+/// Start a verify-remote job that checks the uploaded copy against the local metadata
+async fn verify_remote_backup(
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
-) -> Result<Response<Body>, ApiError> {
-    // Initialize filesystem backup service
-    let backup_service = FilesystemBackupService::new(
-        std::env::var("BACKUP_DIR").unwrap_or_else(|_| "data/backups".to_string())
-    );
+    req: Option<Json<VerifyRemoteRequest>>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let backup_service = &state.backup_service;
 
-    // Scan filesystem for backups
-    let backups = backup_service.scan_backups().await
-        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?;
+    // Look up backup by ID in the backup catalog
+    let backup = backup_service.find_backup(&id).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?
+        .ok_or_else(|| ApiError::NotFound("Backup not found".to_string()))?;
 
-    // Find backup by ID
-    let backup = backups.into_iter()
-        .find(|b| b.id == id)
+    let location = backup.remote_location.clone()
+        .ok_or_else(|| ApiError::BadRequest("Backup has no remote copy".to_string()))?;
+    let (target, path) = remote_storage::locate_copy(&state.pool, &location).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to load storage destinations: {}", e)))?
+        .ok_or_else(|| ApiError::BadRequest(format!("No storage destination matches {}", location)))?;
+
+    let mode = req.map(|Json(req)| req.mode).unwrap_or_default();
+    let job_id = remote_verification::start_remote_verify_job(&state.pool, backup_service, backup, target, path, mode).await?;
+
+    Ok(success_response(serde_json::json!({
+        "message": "Remote verification started",
+        "job_id": job_id,
+        "backup_id": id
+    })))
+}
+
+/// Result of the last verify-remote job
+async fn get_remote_verification(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let backup = state.backup_service.find_backup(&id).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?
+        .ok_or_else(|| ApiError::NotFound("Backup not found".to_string()))?;
+
+    Ok(success_response(serde_json::json!({
+        "backup_id": id,
+        "remote_location": backup.remote_location,
+        "last_remote_verification": backup.last_remote_verification
+    })))
+}
+
+/// Convert the archive to another compression format in a recompress job
+async fn recompress_backup(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<RecompressRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let backup_service = &state.backup_service;
+
+    // Look up backup by ID in the backup catalog
+    let backup = backup_service.find_backup(&id).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?
+        .ok_or_else(|| ApiError::NotFound("Backup not found".to_string()))?;
+
+    recompress::validate(&backup, req.compression_type)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    let job_id = recompress::start_recompress_job(&state.pool, backup_service, backup, req.compression_type).await?;
+
+    Ok(success_response(serde_json::json!({
+        "message": "Re-compression started",
+        "job_id": job_id,
+        "backup_id": id
+    })))
+}
+
+/// Copy the archive and its metadata to another destination in a copy job
+async fn copy_backup(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<CopyBackupRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let backup_service = &state.backup_service;
+
+    // Look up backup by ID in the backup catalog
+    let backup = backup_service.find_backup(&id).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?
+        .ok_or_else(|| ApiError::NotFound("Backup not found".to_string()))?;
+
+    let target = backup_copy::resolve_target(&backup, &req.destination)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    let job_id = backup_copy::start_copy_job(&state.pool, backup_service, backup, req.destination, target.clone()).await?;
+
+    Ok(success_response(serde_json::json!({
+        "message": "Copy started",
+        "job_id": job_id,
+        "backup_id": id,
+        "target": target.to_string_lossy()
+    })))
+}
+
+/// Preview what a restore would load: files, databases and tables with approximate row counts,
+/// and mydumper's metadata, read from the archive without extracting it
+async fn get_backup_contents(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let backup_service = &state.backup_service;
+
+    // Look up backup by ID in the backup catalog
+    let backup = backup_service.find_backup(&id).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?
         .ok_or_else(|| ApiError::NotFound("Backup not found".to_string()))?;
 
     if !StdPath::new(&backup.file_path).exists() {
         return Err(ApiError::NotFound("Backup file not found on disk".to_string()));
     }
 
-    // Read the file and return it as a download
-    let file_content = tokio::fs::read(&backup.file_path).await
-        .map_err(|_| ApiError::InternalError("Failed to read backup file".to_string()))?;
+    let inspection = backup_service.inspect_archive(&backup).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to read backup archive: {}", e)))?;
+    // Newer mydumper releases record exact row counts; otherwise count the rows in the data chunks
+    let metadata_rows = inspection.metadata.as_deref()
+        .map(archive_format::metadata_table_rows)
+        .unwrap_or_default();
+    let files: Vec<ArchiveEntry> = inspection.entries.into_iter()
+        .map(|(path, size)| ArchiveEntry::from_path(path, size))
+        .collect();
+
+    // Group schema and data files per table
+    let mut tables: std::collections::BTreeMap<(String, String), (bool, usize, u64, u64)> = std::collections::BTreeMap::new();
+    for file in &files {
+        if let Some(table) = &file.table_name {
+            let key = (file.database_name.clone().unwrap_or_default(), table.clone());
+            let entry = tables.entry(key).or_default();
+            match file.kind {
+                ArchiveEntryKind::TableSchema => entry.0 = true,
+                ArchiveEntryKind::Data => {
+                    entry.1 += 1;
+                    entry.3 += inspection.data_rows.get(&file.path).copied().unwrap_or_default();
+                }
+                _ => {}
+            }
+            entry.2 += file.size;
+        }
+    }
+    let mut databases: std::collections::BTreeMap<String, (usize, u64, u64)> = std::collections::BTreeMap::new();
+    let tables: Vec<serde_json::Value> = tables.into_iter()
+        .map(|((database, table), (has_schema, data_files, size, counted_rows))| {
+            let rows = metadata_rows.get(&(database.clone(), table.clone())).copied().unwrap_or(counted_rows);
+            let totals = databases.entry(database.clone()).or_default();
+            totals.0 += 1;
+            totals.1 += rows;
+            totals.2 += size;
+            serde_json::json!({
+                "database_name": database,
+                "table_name": table,
+                "has_schema": has_schema,
+                "data_files": data_files,
+                "size": size,
+                "rows": rows
+            })
+        })
+        .collect();
+    let databases: Vec<serde_json::Value> = databases.into_iter()
+        .map(|(name, (table_count, rows, size))| serde_json::json!({
+            "name": name,
+            "table_count": table_count,
+            "rows": rows,
+            "size": size
+        }))
+        .collect();
+
+    let metadata = inspection.metadata.map(|content| {
+        let field = |label: &str| content.lines()
+            .find_map(|line| line.split_once(label).map(|(_, value)| value.trim().to_string()));
+        serde_json::json!({
+            "started_at": field("Started dump at:"),
+            "finished_at": field("Finished dump at:"),
+            "binlog_position": binlog::parse_mydumper_metadata(&content),
+            "content": content
+        })
+    });
+
+    Ok(success_response(serde_json::json!({
+        "backup_id": backup.id,
+        "file_count": files.len(),
+        "total_size": files.iter().map(|f| f.size).sum::<u64>(),
+        "databases": databases,
+        "tables": tables,
+        "metadata": metadata,
+        "files": files
+    })))
+}
+
+async fn download_backup(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    method: Method,
+    headers: HeaderMap,
+) -> Result<Response<Body>, ApiError> {
+    let backup_service = &state.backup_service;
+
+    // Look up backup by ID in the backup catalog
+    let backup = backup_service.find_backup(&id).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?
+        .ok_or_else(|| ApiError::NotFound("Backup not found".to_string()))?;
+
+    // Use the size on disk rather than the metadata value, which may be stale
+    let file_metadata = match tokio::fs::metadata(&backup.file_path).await {
+        Ok(metadata) => metadata,
+        Err(_) => return Err(ApiError::NotFound("Backup file not found on disk".to_string())),
+    };
+    let file_size = file_metadata.len();
+    let modified = file_metadata.modified().unwrap_or(std::time::UNIX_EPOCH);
+    let etag = archive_etag(file_size, modified);
+
+    // Only resume from a range if the client still has the same archive (If-Range). That takes a
+    // strong match, so a weak tag or a date never equals the ETag and gets the full archive
+    let range_header = headers.get(header::RANGE).and_then(|v| v.to_str().ok());
+    let if_range_matches = headers.get(header::IF_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .is_none_or(|value| value.trim() == etag);
+    let range = match range_header {
+        Some(value) if if_range_matches => parse_byte_range(value, file_size),
+        _ => ByteRange::Full,
+    };
+
+    let (start, end) = match range {
+        ByteRange::Full => (0, file_size.saturating_sub(1)),
+        ByteRange::Partial(start, end) => (start, end),
+        ByteRange::Unsatisfiable => {
+            return Ok(Response::builder()
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", file_size))
+                .header(header::ACCEPT_RANGES, "bytes")
+                .body(Body::empty())
+                .unwrap());
+        }
+    };
+    let content_length = if file_size == 0 { 0 } else { end - start + 1 };
+
+    // HEAD requests only need the headers, don't touch the archive contents
+    let body = if method == Method::HEAD {
+        Body::empty()
+    } else {
+        // Stream the archive in chunks instead of loading it into memory
+        let mut file = tokio::fs::File::open(&backup.file_path).await
+            .map_err(|_| ApiError::InternalError("Failed to read backup file".to_string()))?;
+        if start > 0 {
+            file.seek(std::io::SeekFrom::Start(start)).await
+                .map_err(|_| ApiError::InternalError("Failed to read backup file".to_string()))?;
+        }
+        // Count streamed bytes; the guard marks the transfer finished when the stream is dropped
+        let transfer = TransferGuard::start(
+            TransferKind::Download,
+            backup.filename().unwrap_or(&backup.id),
+            Some(&backup.id),
+            Some(content_length),
+        );
+        let stream = ReaderStream::with_capacity(file.take(content_length), DOWNLOAD_CHUNK_SIZE)
+            .inspect(move |chunk| {
+                if let Ok(chunk) = chunk {
+                    transfer.add(chunk.len() as u64);
+                }
+            });
+        let throttle = bandwidth::for_task_id(&state.pool, backup.task_id.as_deref()).await;
+        Body::from_stream(bandwidth::pace(throttle, stream))
+    };
 
     let filename = backup.filename().unwrap_or("backup.tar.gz");
     let mime_type = if backup.file_path.ends_with(".tar.gz") {
@@ -530,17 +1600,91 @@ async fn download_backup(
         "application/octet-stream"
     };
 
-    Ok(Response::builder()
-        .status(200)
-        .header("Content-Type", mime_type)
-        .header("Content-Disposition", format!("attachment; filename=\"{}\"", filename))
-        .header("Content-Length", backup.file_size.to_string())
-        .body(Body::from(file_content))
-        .unwrap())
+    let mut response = Response::builder()
+        .header(header::CONTENT_TYPE, mime_type)
+        .header(header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename))
+        .header(header::CONTENT_LENGTH, content_length.to_string())
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::ETAG, &etag)
+        .header(header::LAST_MODIFIED, httpdate(modified));
+
+    response = match range {
+        ByteRange::Partial(start, end) => response
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, file_size)),
+        _ => response.status(StatusCode::OK),
+    };
+
+    Ok(response.body(body).unwrap())
+}
+
+/// Result of evaluating a `Range` header against an archive
+#[derive(Debug, PartialEq)]
+pub(crate) enum ByteRange {
+    Full,
+    Partial(u64, u64),
+    Unsatisfiable,
+}
+
+/// Parse a single `bytes=` range; multiple ranges and invalid ones, e.g. `bytes=500-100`, fall back
+/// to the full file, as a Range header that can't be parsed is ignored
+pub(crate) fn parse_byte_range(value: &str, file_size: u64) -> ByteRange {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return ByteRange::Full;
+    };
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        // Suffix range: the last N bytes
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) => return ByteRange::Unsatisfiable,
+            Err(_) => return ByteRange::Full,
+            Ok(n) => (file_size.saturating_sub(n), file_size.saturating_sub(1)),
+        },
+        (start, "") => match start.parse::<u64>() {
+            Ok(start) => (start, file_size.saturating_sub(1)),
+            Err(_) => return ByteRange::Full,
+        },
+        (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) if start <= end => (start, end.min(file_size.saturating_sub(1))),
+            _ => return ByteRange::Full,
+        },
+    };
+
+    if file_size == 0 || start >= file_size {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Partial(start, end)
+}
+
+/// Strong validator derived from archive size and modification time; archives are written once
+/// and never changed in place, so equal tags mean identical bytes
+fn archive_etag(file_size: u64, modified: std::time::SystemTime) -> String {
+    let mtime = modified.duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    format!("\"{:x}-{:x}\"", file_size, mtime)
+}
+
+/// Format a timestamp as an HTTP date (RFC 7231)
+fn httpdate(time: std::time::SystemTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from(time)
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string()
+}
+
+/// Request body size, used as the expected total of an upload transfer
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers.get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
 }
 
 async fn cleanup_old_backups(
-    State(_pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Query(query): Query<serde_json::Value>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
     let days = query.get("days")
@@ -549,10 +1693,7 @@ async fn cleanup_old_backups(
 
     let cutoff_date = chrono::Utc::now() - chrono::Duration::days(days);
 
-    // Initialize filesystem backup service
-    let backup_service = FilesystemBackupService::new(
-        std::env::var("BACKUP_DIR").unwrap_or_else(|_| "data/backups".to_string())
-    );
+    let backup_service = &state.backup_service;
 
     // Scan filesystem for backups
     let all_backups = backup_service.scan_backups().await
@@ -590,7 +1731,7 @@ async fn cleanup_old_backups(
     })))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateMetadataRequest {
     pub database_name: Option<String>,
     pub database_config_id: Option<String>,
@@ -599,21 +1740,15 @@ pub struct UpdateMetadataRequest {
 }
 
 async fn update_metadata(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
     Json(request): Json<UpdateMetadataRequest>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
-    // Initialize filesystem backup service
-    let backup_service = FilesystemBackupService::new(
-        std::env::var("BACKUP_DIR").unwrap_or_else(|_| "data/backups".to_string())
-    );
+    let backup_service = &state.backup_service;
 
     // Find the backup
-    let backups = backup_service.scan_backups().await
-        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?;
-    
-    let backup = backups.iter()
-        .find(|b| b.id == id)
+    let backup = backup_service.find_backup(&id).await
+        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?
         .ok_or_else(|| ApiError::NotFound("Backup not found".to_string()))?;
 
     // Load current metadata
@@ -0,0 +1,147 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::models::{BlackoutWindow, CreateBlackoutWindowRequest, UpdateBlackoutWindowRequest, LogLevel};
+use crate::services::blackout;
+use crate::state::AppState;
+use super::{ApiError, ApiResult, success_response};
+use utoipa::IntoParams;
+
+#[derive(Deserialize, IntoParams)]
+pub struct ListQuery {
+    /// Only the windows that apply to this task, its own and the global ones
+    task_id: Option<String>,
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(list_windows).post(create_window))
+        .route("/:id", get(get_window).put(update_window).delete(delete_window))
+        .with_state(state)
+}
+
+async fn list_windows(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListQuery>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let windows: Vec<BlackoutWindow> = match &query.task_id {
+        Some(task_id) => blackout::windows_for_task(&state.pool, task_id).await?,
+        None => sqlx::query_as("SELECT * FROM blackout_windows ORDER BY name")
+            .fetch_all(&state.pool)
+            .await?,
+    };
+    Ok(success_response(windows))
+}
+
+async fn get_window(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    Ok(success_response(find_window(&state.pool, &id).await?))
+}
+
+async fn create_window(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreateBlackoutWindowRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let window = BlackoutWindow::new(req);
+    validate(&state.pool, &window).await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO blackout_windows (id, name, task_id, weekdays, start_time, end_time, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&window.id)
+    .bind(&window.name)
+    .bind(&window.task_id)
+    .bind(&window.weekdays)
+    .bind(&window.start_time)
+    .bind(&window.end_time)
+    .bind(window.created_at)
+    .bind(window.updated_at)
+    .execute(&state.pool)
+    .await?;
+
+    let _ = state.logging_service.log_system(&format!("Blackout window '{}' created", window.name), LogLevel::Info).await;
+    state.worker.wake();
+    Ok(success_response(window))
+}
+
+async fn update_window(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateBlackoutWindowRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let mut window = find_window(&state.pool, &id).await?;
+    window.update(req);
+    validate(&state.pool, &window).await?;
+
+    sqlx::query(
+        r#"
+        UPDATE blackout_windows
+        SET name = ?, task_id = ?, weekdays = ?, start_time = ?, end_time = ?, updated_at = ?
+        WHERE id = ?
+        "#
+    )
+    .bind(&window.name)
+    .bind(&window.task_id)
+    .bind(&window.weekdays)
+    .bind(&window.start_time)
+    .bind(&window.end_time)
+    .bind(window.updated_at)
+    .bind(&window.id)
+    .execute(&state.pool)
+    .await?;
+
+    state.worker.wake();
+    Ok(success_response(window))
+}
+
+async fn delete_window(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let result = sqlx::query("DELETE FROM blackout_windows WHERE id = ?")
+        .bind(&id)
+        .execute(&state.pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Blackout window not found".to_string()));
+    }
+
+    Ok(success_response(serde_json::json!({"message": "Blackout window deleted successfully"})))
+}
+
+async fn find_window(pool: &sqlx::SqlitePool, id: &str) -> Result<BlackoutWindow, ApiError> {
+    sqlx::query_as("SELECT * FROM blackout_windows WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Blackout window not found".to_string()))
+}
+
+/// Reject unnamed windows, unknown tasks and unparsable days or times
+async fn validate(pool: &sqlx::SqlitePool, window: &BlackoutWindow) -> Result<(), ApiError> {
+    if window.name.trim().is_empty() {
+        return Err(ApiError::BadRequest("Blackout window name is required".to_string()));
+    }
+    if let Some(task_id) = &window.task_id {
+        let task: Option<(String,)> = sqlx::query_as("SELECT id FROM tasks WHERE id = ?")
+            .bind(task_id)
+            .fetch_optional(pool)
+            .await?;
+        if task.is_none() {
+            return Err(ApiError::BadRequest("Task not found".to_string()));
+        }
+    }
+    window.weekdays().map_err(ApiError::BadRequest)?;
+    window.times().map_err(ApiError::BadRequest)?;
+    Ok(())
+}
@@ -1,85 +1,72 @@
 use axum::{
-    extract::State,
+    extract::{Query, State},
     routing::get,
     Router,
 };
-use sqlx::SqlitePool;
+use serde::Deserialize;
 use serde_json::json;
+use std::sync::Arc;
 
-use crate::services::filesystem_backup::FilesystemBackupService;
+use crate::services::overdue;
+use crate::state::AppState;
 use super::{ApiResult, success_response};
+use utoipa::IntoParams;
 
-pub fn routes(pool: SqlitePool) -> Router {
+pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/stats", get(get_dashboard_stats))
         .route("/recent-backups", get(get_recent_backups))
         .route("/next-tasks", get(get_next_tasks))
-        .with_state(pool)
+        .route("/overdue", get(get_overdue_tasks))
+        .with_state(state)
 }
 
 async fn get_dashboard_stats(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
-    // Get database configs count
-    let db_configs_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM database_configs")
-        .fetch_one(&pool)
-        .await?;
-
-    // Get tasks count
-    let tasks_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM tasks")
-        .fetch_one(&pool)
-        .await?;
-
-    // Get active tasks count
-    let active_tasks_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM tasks WHERE is_active = true")
-        .fetch_one(&pool)
-        .await?;
-
-    // Get total jobs count
-    let total_jobs_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM jobs")
-        .fetch_one(&pool)
-        .await?;
-
-    // Get running jobs count (including compressing)
-    let running_jobs_count: (i64,) = sqlx::query_as(
-        "SELECT COUNT(*) FROM jobs WHERE status IN ('running', 'compressing')"
-    )
-        .fetch_one(&pool)
-        .await?;
-
-    // Get recent backups count (last 24 hours)
-    let recent_backups_count: (i64,) = sqlx::query_as(
-        "SELECT COUNT(*) FROM jobs WHERE job_type = 'backup' AND created_at > datetime('now', '-1 day')"
+    let pool = &state.pool;
+
+    // Collect all table counts in a single round trip
+    let (db_configs_count, tasks_count, active_tasks_count, total_jobs_count, running_jobs_count, recent_backups_count): (i64, i64, i64, i64, i64, i64) = sqlx::query_as(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM database_configs),
+            (SELECT COUNT(*) FROM tasks),
+            (SELECT COUNT(*) FROM tasks WHERE is_active = true),
+            (SELECT COUNT(*) FROM jobs),
+            (SELECT COUNT(*) FROM jobs WHERE status IN ('running', 'compressing', 'uploading')),
+            (SELECT COUNT(*) FROM jobs WHERE job_type = 'backup' AND created_at > datetime('now', '-1 day'))
+        "#
     )
-        .fetch_one(&pool)
+        .fetch_one(pool)
         .await?;
 
-    // Get backup files count from filesystem
-    let backup_base_dir = std::env::var("BACKUP_DIR").unwrap_or_else(|_| "data/backups".to_string());
-    let filesystem_service = FilesystemBackupService::new(backup_base_dir);
-    let backup_files = filesystem_service.scan_backups().await.unwrap_or_default();
+    // Get backup files count from the cached backup catalog
+    let backup_files = state.backup_service.scan_backups().await.unwrap_or_default();
     let backup_files_count = backup_files.len() as i64;
 
     Ok(success_response(json!({
-        "databases": db_configs_count.0,
-        "tasks": tasks_count.0,
-        "active_tasks": active_tasks_count.0,
-        "total_jobs": total_jobs_count.0,
-        "running_jobs": running_jobs_count.0,
-        "recent_backups": recent_backups_count.0,
+        "databases": db_configs_count,
+        "tasks": tasks_count,
+        "active_tasks": active_tasks_count,
+        "total_jobs": total_jobs_count,
+        "running_jobs": running_jobs_count,
+        "recent_backups": recent_backups_count,
         "backup_files": backup_files_count,
         "timestamp": chrono::Utc::now().to_rfc3339()
     })))
 }
 
 async fn get_recent_backups(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     // Get recent backup jobs
     let recent_jobs: Vec<serde_json::Value> = sqlx::query_as::<_, (String, String, String, String, String, i32, String, String, Option<String>)>(
         "SELECT id, job_type, status, created_at, started_at, progress, error_message, log_output, backup_path FROM jobs WHERE job_type = 'backup' ORDER BY created_at DESC LIMIT 5"
     )
-        .fetch_all(&pool)
+        .fetch_all(pool)
         .await?
         .into_iter()
         .map(|(id, job_type, status, created_at, started_at, progress, error_message, log_output, backup_path)| {
@@ -104,13 +91,15 @@ async fn get_recent_backups(
 }
 
 async fn get_next_tasks(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     // Get next 5 scheduled tasks
     let next_tasks: Vec<serde_json::Value> = sqlx::query_as::<_, (String, String, String, String, String, String, i32, bool, Option<String>, Option<String>)>(
         "SELECT t.id, t.name, t.cron_schedule, t.database_config_id, t.created_at, t.updated_at, t.cleanup_days, t.is_active, dc.name as db_name, dc.database_name FROM tasks t LEFT JOIN database_configs dc ON t.database_config_id = dc.id WHERE t.is_active = true ORDER BY t.created_at ASC LIMIT 5"
     )
-        .fetch_all(&pool)
+        .fetch_all(pool)
         .await?
         .into_iter()
         .map(|(id, name, schedule, database_config_id, created_at, updated_at, cleanup_days, is_active, db_name, database_name)| {
@@ -142,3 +131,24 @@ async fn get_next_tasks(
         "timestamp": chrono::Utc::now().to_rfc3339()
     })))
 }
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub(crate) struct OverdueQuery {
+    grace_minutes: Option<i64>,
+}
+
+/// Active tasks that missed their scheduled run or whose last successful backup
+/// is older than their schedule interval (plus a grace period, 60 minutes by default)
+async fn get_overdue_tasks(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<OverdueQuery>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let grace_minutes = query.grace_minutes.unwrap_or(overdue::DEFAULT_GRACE_MINUTES).max(0);
+    let overdue_tasks = overdue::find_overdue_tasks(&state.pool, chrono::Duration::minutes(grace_minutes)).await?;
+
+    Ok(success_response(json!({
+        "overdue_tasks": overdue_tasks,
+        "grace_minutes": grace_minutes,
+        "timestamp": chrono::Utc::now().to_rfc3339()
+    })))
+}
@@ -1,73 +1,147 @@
 use axum::{
     extract::{Path, Query, State},
+    response::IntoResponse,
     routing::{get, post},
     Json, Router,
 };
-use serde::Deserialize;
-use sqlx::SqlitePool;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{QueryBuilder, Sqlite};
 use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::models::{DatabaseConfig, CreateDatabaseConfigRequest, UpdateDatabaseConfigRequest, LogLevel};
-use crate::services::LoggingService;
-use super::{ApiError, ApiResult, success_response, paginated_response};
+use crate::services::{demo, events::{self, AppEvent}, mysql_pool, restore_target, sla};
+use crate::state::AppState;
+use super::{ApiError, ApiResult, success_response, paginated_response, cursor_response};
+use super::list_params::{self, Cursor};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 pub struct ListQuery {
     page: Option<u32>,
     limit: Option<u32>,
     search: Option<String>,
+    /// Cursor pagination: configs after this one, newest first
+    after_id: Option<String>,
+    /// Cursor pagination: configs created before this time, or the time of `after_id`
+    created_before: Option<DateTime<Utc>>,
 }
 
-pub fn routes(pool: SqlitePool) -> Router {
+#[derive(Deserialize, IntoParams)]
+pub struct SlaQuery {
+    violated: Option<bool>,
+}
+
+/// Most recent successful task backup of a database config
+#[derive(Serialize, ToSchema)]
+pub struct LastBackupResponse {
+    database_config_id: String,
+    database_config_name: String,
+    job_id: Option<String>,
+    backup_id: Option<String>,
+    task_id: Option<String>,
+    used_database: Option<String>,
+    completed_at: Option<DateTime<Utc>>,
+    age_hours: Option<f64>,
+    file_size: Option<i64>,
+    /// Whether the archive is still in the backup store (cleanup may have removed it)
+    archive_available: bool,
+}
+
+#[derive(sqlx::FromRow)]
+struct CompletedBackupJob {
+    id: String,
+    task_id: Option<String>,
+    used_database: Option<String>,
+    completed_at: Option<DateTime<Utc>>,
+    backup_path: Option<String>,
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/", get(list_database_configs).post(create_database_config))
+        .route("/sla", get(get_sla_status))
         .route("/:id", get(get_database_config).put(update_database_config).delete(delete_database_config))
         .route("/:id/test", post(test_database_connection))
         .route("/:id/permissions", get(check_database_permissions))
         .route("/:id/databases", get(get_available_databases))
-        .with_state(pool)
+        .route("/:id/last-backup", get(get_last_backup))
+        .with_state(state)
 }
 
 async fn list_database_configs(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Query(query): Query<ListQuery>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     let page = query.page.unwrap_or(1);
     let limit = query.limit.unwrap_or(10);
     let offset = (page - 1) * limit;
 
-    let mut sql = "SELECT * FROM database_configs".to_string();
-    let mut count_sql = "SELECT COUNT(*) as count FROM database_configs".to_string();
-    
-    if let Some(search) = &query.search {
-        let search_clause = format!(" WHERE name LIKE '%{}%' OR host LIKE '%{}%' OR database_name LIKE '%{}%'", search, search, search);
-        sql.push_str(&search_clause);
-        count_sql.push_str(&search_clause);
+    let search = query.search.as_ref().map(|search| format!("%{}%", search));
+    let cursor = Cursor::resolve(pool, "database_configs", query.after_id.as_deref(), query.created_before).await?;
+
+    let mut select = QueryBuilder::new("SELECT * FROM database_configs");
+    push_config_filters(&mut select, search.as_deref());
+    if let Some(cursor) = &cursor {
+        cursor.push_condition(&mut select, "created_at", "id");
+        // One more row than asked for tells whether another page follows
+        select.push(" ORDER BY created_at DESC, id DESC LIMIT ").push_bind(limit as i64 + 1);
+    } else {
+        select.push(" ORDER BY created_at DESC, id DESC LIMIT ")
+            .push_bind(limit as i64)
+            .push(" OFFSET ")
+            .push_bind(offset as i64);
     }
-    
-    sql.push_str(&format!(" ORDER BY created_at DESC LIMIT {} OFFSET {}", limit, offset));
 
-    let configs: Vec<DatabaseConfig> = sqlx::query_as(&sql)
-        .fetch_all(&pool)
-        .await?;
+    let configs: Vec<DatabaseConfig> = select.build_query_as().fetch_all(pool).await?;
 
-    let total: (i64,) = sqlx::query_as(&count_sql)
-        .fetch_one(&pool)
-        .await?;
+    if cursor.is_some() {
+        return Ok(cursor_response(configs, limit, |config| list_params::next_cursor(&config.id, config.created_at)).into_response());
+    }
+
+    let mut count = QueryBuilder::new("SELECT COUNT(*) as count FROM database_configs");
+    push_config_filters(&mut count, search.as_deref());
+    let total: (i64,) = count.build_query_as().fetch_one(pool).await?;
+
+    Ok(paginated_response(configs, page, limit, total.0 as u64).into_response())
+}
 
-    Ok(paginated_response(configs, page, limit, total.0 as u64))
+fn push_config_filters<'a>(builder: &mut QueryBuilder<'a, Sqlite>, search: Option<&'a str>) {
+    builder.push(" WHERE 1=1");
+    if let Some(search) = search {
+        builder.push(" AND (name LIKE ").push_bind(search)
+            .push(" OR host LIKE ").push_bind(search)
+            .push(" OR database_name LIKE ").push_bind(search)
+            .push(")");
+    }
+}
+
+/// Backup freshness of every database config with an RPO target; `?violated=true` lists only violations
+async fn get_sla_status(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SlaQuery>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let mut statuses = sla::evaluate(&state.pool, &state.backup_service).await?;
+    if let Some(violated) = query.violated {
+        statuses.retain(|status| status.violated == violated);
+    }
+    Ok(success_response(statuses))
 }
 
 async fn get_database_config(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     let config: Option<DatabaseConfig> = sqlx::query_as(
         "SELECT * FROM database_configs WHERE id = ?"
     )
     .bind(&id)
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await?;
 
     match config {
@@ -76,17 +150,104 @@ async fn get_database_config(
     }
 }
 
+/// When a database config was last backed up successfully, with the archive's id and size.
+///
+/// The completed backup job is authoritative for the time; the backup catalog supplies
+/// the archive. Without job history the newest task backup in the catalog is used.
+async fn get_last_backup(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
+    let config: DatabaseConfig = sqlx::query_as(
+        "SELECT * FROM database_configs WHERE id = ?"
+    )
+    .bind(&id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| ApiError::NotFound("Database configuration not found".to_string()))?;
+
+    let last_job: Option<CompletedBackupJob> = sqlx::query_as(
+        "SELECT j.id, j.task_id, j.used_database, j.completed_at, j.backup_path FROM jobs j JOIN tasks t ON j.task_id = t.id WHERE t.database_config_id = ? AND j.job_type = 'backup' AND j.status = 'completed' ORDER BY j.completed_at DESC LIMIT 1"
+    )
+    .bind(&id)
+    .fetch_optional(pool)
+    .await?;
+
+    // Uploaded archives are copies of older data, so only backups taken by a task count
+    let backups: Vec<_> = state.backup_service.scan_backups().await?
+        .into_iter()
+        .filter(|b| b.database_config_id == id && b.task_id.is_some())
+        .collect();
+
+    let mut response = LastBackupResponse {
+        database_config_id: config.id,
+        database_config_name: config.name,
+        job_id: None,
+        backup_id: None,
+        task_id: None,
+        used_database: None,
+        completed_at: None,
+        age_hours: None,
+        file_size: None,
+        archive_available: false,
+    };
+
+    if let Some(job) = last_job {
+        // Task backups share their job's id; older ones are matched by archive path
+        let backup = backups.iter()
+            .find(|b| b.id == job.id || job.backup_path.as_deref() == Some(b.file_path.as_str()));
+        response.backup_id = backup.map(|b| b.id.clone());
+        response.file_size = backup.map(|b| b.file_size);
+        response.archive_available = backup.is_some();
+        response.job_id = Some(job.id);
+        response.task_id = job.task_id;
+        response.used_database = job.used_database;
+        response.completed_at = job.completed_at;
+    } else if let Some((backup, created_at)) = backups.iter()
+        .filter_map(|b| DateTime::parse_from_rfc3339(&b.created_at).ok().map(|at| (b, at.with_timezone(&Utc))))
+        .max_by_key(|(_, at)| *at)
+    {
+        response.backup_id = Some(backup.id.clone());
+        response.task_id = backup.task_id.clone();
+        response.used_database = backup.used_database.clone();
+        response.file_size = Some(backup.file_size);
+        response.archive_available = true;
+        response.completed_at = Some(created_at);
+    }
+    response.age_hours = response.completed_at
+        .map(|at| (Utc::now() - at).num_seconds() as f64 / 3600.0);
+
+    Ok(success_response(response))
+}
+
+/// Reject configs assigned to an agent that doesn't exist
+async fn ensure_agent_exists(pool: &sqlx::SqlitePool, config: &DatabaseConfig) -> Result<(), ApiError> {
+    if let Some(agent_id) = &config.agent_id {
+        let agent: Option<(String,)> = sqlx::query_as("SELECT id FROM agents WHERE id = ?")
+            .bind(agent_id)
+            .fetch_optional(pool)
+            .await?;
+        if agent.is_none() {
+            return Err(ApiError::BadRequest("Agent not found".to_string()));
+        }
+    }
+    Ok(())
+}
+
 async fn create_database_config(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Json(req): Json<CreateDatabaseConfigRequest>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
-    let logging_service = LoggingService::new(Arc::new(pool.clone()));
+    let pool = &state.pool;
+
     // Check if name already exists
     let existing: Option<(String,)> = sqlx::query_as(
         "SELECT id FROM database_configs WHERE name = ?"
     )
     .bind(&req.name)
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await?;
 
     if existing.is_some() {
@@ -94,11 +255,12 @@ async fn create_database_config(
     }
 
     let config = DatabaseConfig::new(req);
+    ensure_agent_exists(pool, &config).await?;
 
     sqlx::query(
         r#"
-        INSERT INTO database_configs (id, name, host, port, username, password, database_name, connection_status, last_tested, created_at, updated_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO database_configs (id, name, host, port, username, password, database_name, connection_status, last_tested, rpo_hours, agent_id, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#
     )
     .bind(&config.id)
@@ -110,27 +272,31 @@ async fn create_database_config(
     .bind(&config.database_name)
     .bind(&config.connection_status)
     .bind(&config.last_tested)
+    .bind(config.rpo_hours)
+    .bind(&config.agent_id)
     .bind(&config.created_at)
     .bind(&config.updated_at)
-    .execute(&pool)
+    .execute(pool)
     .await?;
 
     // Log the creation
-    let _ = logging_service.log_connection(&config.id, &format!("Database configuration '{}' created", config.name), LogLevel::Info).await;
+    let _ = state.logging_service.log_connection(&config.id, &format!("Database configuration '{}' created", config.name), LogLevel::Info).await;
 
     Ok(success_response(config))
 }
 
 async fn update_database_config(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
     Json(req): Json<UpdateDatabaseConfigRequest>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     let mut config: DatabaseConfig = sqlx::query_as(
         "SELECT * FROM database_configs WHERE id = ?"
     )
     .bind(&id)
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await?
     .ok_or_else(|| ApiError::NotFound("Database configuration not found".to_string()))?;
 
@@ -141,7 +307,7 @@ async fn update_database_config(
         )
         .bind(new_name)
         .bind(&id)
-        .fetch_optional(&pool)
+        .fetch_optional(pool)
         .await?;
 
         if existing.is_some() {
@@ -150,11 +316,12 @@ async fn update_database_config(
     }
 
     config.update(req);
+    ensure_agent_exists(pool, &config).await?;
 
     sqlx::query(
         r#"
         UPDATE database_configs 
-        SET name = ?, host = ?, port = ?, username = ?, password = ?, database_name = ?, connection_status = ?, last_tested = ?, updated_at = ?
+        SET name = ?, host = ?, port = ?, username = ?, password = ?, database_name = ?, connection_status = ?, last_tested = ?, rpo_hours = ?, agent_id = ?, updated_at = ?
         WHERE id = ?
         "#
     )
@@ -166,21 +333,25 @@ async fn update_database_config(
     .bind(&config.database_name)
     .bind(&config.connection_status)
     .bind(&config.last_tested)
+    .bind(config.rpo_hours)
+    .bind(&config.agent_id)
     .bind(&config.updated_at)
     .bind(&config.id)
-    .execute(&pool)
+    .execute(pool)
     .await?;
 
     Ok(success_response(config))
 }
 
 async fn delete_database_config(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     let result = sqlx::query("DELETE FROM database_configs WHERE id = ?")
         .bind(&id)
-        .execute(&pool)
+        .execute(pool)
         .await?;
 
     if result.rows_affected() == 0 {
@@ -191,41 +362,52 @@ async fn delete_database_config(
 }
 
 async fn test_database_connection(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     // Get database config
     let mut config: DatabaseConfig = sqlx::query_as(
         "SELECT * FROM database_configs WHERE id = ?"
     )
     .bind(&id)
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await?
     .ok_or_else(|| ApiError::NotFound("Database configuration not found".to_string()))?;
 
     // Test connection
     let connection_string = config.connection_string();
-    let test_result = match sqlx::MySqlPool::connect(&connection_string).await {
-        Ok(mysql_pool) => {
-            // Test basic query
-            match sqlx::query("SELECT 1").fetch_one(&mysql_pool).await {
-                Ok(_) => {
-                    config.mark_connection_tested(true);
-                    Ok(serde_json::json!({
-                        "success": true,
-                        "message": "Connection test successful",
-                        "timestamp": chrono::Utc::now().to_rfc3339()
-                    }))
-                },
-                Err(e) => {
-                    config.mark_connection_tested(false);
-                    Err(ApiError::InternalError(format!("Database query failed: {}", e)))
+    let test_result = if demo::is_enabled() {
+        config.mark_connection_tested(true);
+        Ok(serde_json::json!({
+            "success": true,
+            "message": "Connection test successful (demo mode)",
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        }))
+    } else {
+        match sqlx::MySqlPool::connect(&connection_string).await {
+            Ok(mysql_pool) => {
+                // Test basic query
+                match sqlx::query("SELECT 1").fetch_one(&mysql_pool).await {
+                    Ok(_) => {
+                        config.mark_connection_tested(true);
+                        Ok(serde_json::json!({
+                            "success": true,
+                            "message": "Connection test successful",
+                            "timestamp": chrono::Utc::now().to_rfc3339()
+                        }))
+                    },
+                    Err(e) => {
+                        config.mark_connection_tested(false);
+                        Err(ApiError::InternalError(format!("Database query failed: {}", e)))
+                    }
                 }
+            },
+            Err(e) => {
+                config.mark_connection_tested(false);
+                Err(ApiError::InternalError(format!("Failed to connect to database: {}", e)))
             }
-        },
-        Err(e) => {
-            config.mark_connection_tested(false);
-            Err(ApiError::InternalError(format!("Failed to connect to database: {}", e)))
         }
     };
 
@@ -241,9 +423,14 @@ async fn test_database_connection(
     .bind(&config.last_tested)
     .bind(&config.updated_at)
     .bind(&config.id)
-    .execute(&pool)
+    .execute(pool)
     .await?;
 
+    events::publish(AppEvent::ConnectionTested {
+        database_config_id: config.id.clone(),
+        success: test_result.is_ok(),
+    });
+
     match test_result {
         Ok(response) => Ok(success_response(response)),
         Err(e) => Err(e),
@@ -251,17 +438,29 @@ async fn test_database_connection(
 }
 
 async fn check_database_permissions(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     // Get database config
     let config: DatabaseConfig = sqlx::query_as(
         "SELECT * FROM database_configs WHERE id = ?"
     )
     .bind(&id)
-    .fetch_one(&pool)
+    .fetch_one(pool)
     .await?;
 
+    if demo::is_enabled() {
+        return Ok(success_response(serde_json::json!({
+            "can_create_databases": true,
+            "can_create_tables": true,
+            "existing_databases": demo::databases(),
+            "current_database": config.database_name,
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        })));
+    }
+
     // Test connection and check permissions
     let connection_string = format!(
         "mysql://{}:{}@{}:{}/{}",
@@ -272,43 +471,15 @@ async fn check_database_permissions(
         config.database_name
     );
 
-    let pool = sqlx::MySqlPool::connect(&connection_string).await
+    let pool = mysql_pool::shared_pool(&connection_string).await
         .map_err(|e| ApiError::InternalError(format!("Failed to connect to database: {}", e)))?;
 
-    // Test if user can create databases by actually trying to create a test database
-    let test_db_name = format!("rdumper_test_{}", uuid::Uuid::new_v4().to_string().replace('-', "")[..8].to_string());
-    let can_create_db = sqlx::query(&format!("CREATE DATABASE IF NOT EXISTS `{}`", test_db_name))
-        .execute(&pool)
-        .await
-        .is_ok();
-    
-    // Clean up test database if we created it
-    if can_create_db {
-        let _ = sqlx::query(&format!("DROP DATABASE IF EXISTS `{}`", test_db_name))
-            .execute(&pool)
-            .await;
-    }
-
-    // Test if user can create tables by trying to create a test table
-    let test_table_name = format!("rdumper_test_{}", uuid::Uuid::new_v4().to_string().replace('-', "")[..8].to_string());
+    let can_create_db = restore_target::can_create_databases(&pool).await;
     let can_create_tables = if !config.database_name.is_empty() {
-        sqlx::query(&format!(
-            "CREATE TABLE IF NOT EXISTS `{}`.`{}` (id INT PRIMARY KEY)", 
-            config.database_name, test_table_name
-        ))
-        .execute(&pool)
-        .await
-        .is_ok()
+        restore_target::can_create_tables(&pool, &config.database_name).await
     } else {
         false // Can't create tables without a specific database
     };
-    
-    // Clean up test table if we created it
-    if can_create_tables && !config.database_name.is_empty() {
-        let _ = sqlx::query(&format!("DROP TABLE IF EXISTS `{}`.`{}`", config.database_name, test_table_name))
-            .execute(&pool)
-            .await;
-    }
 
     // Get list of existing databases
     let databases: Vec<String> = sqlx::query_scalar("SHOW DATABASES")
@@ -326,20 +497,30 @@ async fn check_database_permissions(
 }
 
 async fn get_available_databases(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     // Get database config
     let config: DatabaseConfig = sqlx::query_as(
         "SELECT * FROM database_configs WHERE id = ?"
     )
     .bind(&id)
-    .fetch_one(&pool)
+    .fetch_one(pool)
     .await?;
 
+    if demo::is_enabled() {
+        return Ok(success_response(serde_json::json!({
+            "databases": demo::databases(),
+            "connection_status": config.connection_status,
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        })));
+    }
+
     // Test connection and get available databases
     let connection_string = config.connection_string();
-    let mysql_pool = sqlx::MySqlPool::connect(&connection_string).await
+    let mysql_pool = mysql_pool::shared_pool(&connection_string).await
         .map_err(|e| ApiError::InternalError(format!("Failed to connect to database: {}", e)))?;
 
     // Get list of available databases
@@ -0,0 +1,41 @@
+use axum::{
+    response::sse::{Event, KeepAlive, Sse},
+    routing::get,
+    Router,
+};
+use futures::stream::{self, Stream};
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use crate::services::events;
+use crate::state::AppState;
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(stream_events))
+        .with_state(state)
+}
+
+/// Server-Sent Events stream of all application events
+async fn stream_events() -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = stream::unfold(events::subscribe(), |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    let sse_event = Event::default()
+                        .event(event.name())
+                        .json_data(&event)
+                        .unwrap_or_else(|_| Event::default().comment("serialization failed"));
+                    return Some((Ok(sse_event), receiver));
+                }
+                // Slow clients skip missed events and simply resync on the next one
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Event stream lagged, skipped {} events", skipped);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
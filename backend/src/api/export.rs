@@ -0,0 +1,98 @@
+use axum::{
+    body::Body,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use futures::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// File format of a list export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct ExportQuery {
+    #[serde(default)]
+    #[param(inline)]
+    pub format: ExportFormat,
+}
+
+/// A list item that can be written as a CSV line
+pub trait CsvRecord {
+    /// Column names, in the order of [`CsvRecord::csv_fields`]
+    const COLUMNS: &'static [&'static str];
+
+    fn csv_fields(&self) -> Vec<String>;
+}
+
+/// One CSV line, quoting fields that contain separators, quotes or line breaks
+fn csv_line<S: AsRef<str>>(fields: impl IntoIterator<Item = S>) -> String {
+    let mut line = fields.into_iter()
+        .map(|field| {
+            let field = field.as_ref();
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push_str("\r\n");
+    line
+}
+
+/// An optional value as a CSV field, empty when missing
+pub fn field<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(ToString::to_string).unwrap_or_default()
+}
+
+/// Download of every record in `chunks`, named `<name>-<time>.csv` or `.json`.
+///
+/// Chunks are written as they arrive, so exports of large lists never sit in memory as a whole;
+/// JSON is a single array.
+pub fn download<T, E, S>(name: &str, format: ExportFormat, chunks: S) -> Response
+where
+    T: CsvRecord + Serialize,
+    E: Into<axum::BoxError> + 'static,
+    S: Stream<Item = Result<Vec<T>, E>> + Send + 'static,
+{
+    let (opening, closing, content_type, extension) = match format {
+        ExportFormat::Csv => (csv_line(T::COLUMNS), String::new(), "text/csv; charset=utf-8", "csv"),
+        ExportFormat::Json => ("[".to_string(), "\n]\n".to_string(), "application/json", "json"),
+    };
+
+    let mut first = true;
+    let records = chunks.map(move |chunk| chunk.map(|records| {
+        let mut out = String::new();
+        for record in records {
+            match format {
+                ExportFormat::Csv => out.push_str(&csv_line(record.csv_fields())),
+                ExportFormat::Json => {
+                    out.push_str(if first { "\n" } else { ",\n" });
+                    out.push_str(&serde_json::to_string(&record).unwrap_or_default());
+                }
+            }
+            first = false;
+        }
+        out
+    }));
+    let body = stream::once(async move { Ok(opening) })
+        .chain(records)
+        .chain(stream::once(async move { Ok(closing) }));
+
+    let filename = format!("{}-{}.{}", name, chrono::Utc::now().format("%Y%m%d-%H%M%S"), extension);
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        Body::from_stream(body),
+    ).into_response()
+}
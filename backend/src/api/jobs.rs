@@ -1,16 +1,32 @@
 use axum::{
     extract::{Path, Query, State},
+    http::HeaderMap,
+    response::{sse::{Event, KeepAlive, Sse}, IntoResponse},
     routing::{get, post},
     Json, Router,
 };
+use futures::stream::{self, Stream};
 use serde::{Deserialize, Serialize};
-use sqlx::{SqlitePool, Row};
-
-use crate::models::{Job, CreateJobRequest, JobStatus};
-use crate::services::progress_tracker::ProgressTracker;
-use super::{ApiError, ApiResult, success_response, paginated_response};
-
-#[derive(Debug, Serialize)]
+use sqlx::{sqlite::SqliteRow, QueryBuilder, Row, Sqlite};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::models::{Job, CreateJobRequest, JobStatus, JobType, LogLevel, RestoreRequest};
+use crate::services::{cancellation, job_history};
+use crate::services::log_tail::LogTail;
+use crate::services::events::{self, AppEvent};
+use crate::services::progress_tracker::{self, ProgressTracker};
+use crate::state::AppState;
+use super::{ApiError, ApiResult, success_response, paginated_response, cursor_response, conditional_response};
+use super::list_params::{self, Cursor, SortOrder};
+use super::export::{self, CsvRecord, ExportQuery};
+use utoipa::{IntoParams, ToSchema};
+
+/// Jobs read per query while exporting
+const EXPORT_CHUNK_SIZE: i64 = 500;
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct JobWithDatabaseInfo {
     #[serde(flatten)]
     pub job: Job,
@@ -21,108 +37,227 @@ pub struct JobWithDatabaseInfo {
     pub db_config_database_name: Option<String>,
 }
 
+impl CsvRecord for JobWithDatabaseInfo {
+    const COLUMNS: &'static [&'static str] = &[
+        "id", "job_type", "status", "task_id", "task_name", "database_config", "used_database",
+        "created_at", "started_at", "completed_at", "duration_seconds", "attempt", "priority",
+        "backup_size", "backup_path", "error_message",
+    ];
+
+    fn csv_fields(&self) -> Vec<String> {
+        let job = &self.job;
+        let duration = job.started_at.zip(job.completed_at)
+            .map(|(started_at, completed_at)| (completed_at - started_at).num_seconds());
+        vec![
+            job.id.clone(),
+            job.job_type.clone(),
+            job.status.clone(),
+            export::field(&job.task_id),
+            export::field(&self.task_name),
+            export::field(&self.db_config_name),
+            export::field(&job.used_database),
+            job.created_at.to_rfc3339(),
+            export::field(&job.started_at.map(|at| at.to_rfc3339())),
+            export::field(&job.completed_at.map(|at| at.to_rfc3339())),
+            export::field(&duration),
+            job.attempt.to_string(),
+            job.priority.to_string(),
+            export::field(&job.backup_size),
+            export::field(&job.backup_path),
+            export::field(&job.error_message),
+        ]
+    }
+}
 
-#[derive(Deserialize)]
+#[derive(Debug, Serialize)]
+pub struct JobArtifact {
+    /// Path relative to the directory it was found in
+    pub name: String,
+    pub size: u64,
+    pub modified_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Deserialize, IntoParams)]
 pub struct ListQuery {
     page: Option<u32>,
     limit: Option<u32>,
+    /// Comma separated, e.g. `failed,cancelled`
     status: Option<String>,
+    /// Comma separated, e.g. `backup,restore`
     job_type: Option<String>,
     task_id: Option<String>,
+    /// Jobs created at or after this time
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    /// Jobs created before this time
+    until: Option<chrono::DateTime<chrono::Utc>>,
+    sort_by: Option<String>,
+    #[serde(default)]
+    #[param(inline)]
+    order: SortOrder,
+    /// Cursor pagination: jobs after this one, newest first
+    after_id: Option<String>,
+    /// Cursor pagination: jobs created before this time, or the time of `after_id`
+    created_before: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Jobs joined with their task and database config, for [`job_from_row`]
+const JOBS_SELECT: &str = "SELECT j.*, t.name as task_name, t.database_name as task_database_name, dc.name as db_config_name, dc.host as db_config_host, dc.database_name as db_config_database_name FROM jobs j LEFT JOIN tasks t ON j.task_id = t.id LEFT JOIN database_configs dc ON t.database_config_id = dc.id";
+
+/// Values of `sort_by` for jobs and what they sort by
+const JOB_SORT_COLUMNS: &[(&str, &str)] = &[
+    ("created_at", "j.created_at"),
+    ("started_at", "j.started_at"),
+    ("completed_at", "j.completed_at"),
+    ("duration", "(julianday(j.completed_at) - julianday(j.started_at))"),
+    ("status", "j.status"),
+    ("job_type", "j.job_type"),
+    ("priority", "j.priority"),
+    ("progress", "j.progress"),
+    ("backup_size", "j.backup_size"),
+    ("task_name", "t.name"),
+];
+
+#[derive(Deserialize, IntoParams)]
+pub struct BulkDeleteQuery {
+    status: Option<String>,
+    /// Date (`2024-01-01`, midnight UTC) or RFC 3339 time the jobs were created before
+    before: Option<String>,
+    task_id: Option<String>,
+    job_type: Option<String>,
+    /// Only count the jobs that would be deleted
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct TailQuery {
+    /// Byte offset of the log to continue from; also taken from `Last-Event-ID` on reconnects
+    offset: Option<u64>,
 }
 
-pub fn routes(pool: SqlitePool) -> Router {
+/// How often a followed log is checked for new lines
+const TAIL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Deserialize, IntoParams)]
+pub struct StatsQuery {
+    pub days: Option<u32>, // Window to look back over, 30 days by default
+    pub job_type: Option<String>, // backup (default) or restore
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct CancelQuery {
+    /// Drop the database a cancelled restore was loading into
+    drop_database: Option<bool>,
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
-        .route("/", get(list_jobs).post(create_job))
+        .route("/", get(list_jobs).post(create_job).delete(delete_jobs))
         .route("/:id", get(get_job).delete(delete_job))
         .route("/:id/cancel", post(cancel_job))
+        .route("/:id/retry", post(retry_job))
         .route("/:id/logs", get(get_job_logs))
+        .route("/:id/artifacts", get(get_job_artifacts))
+        .route("/:id/logs/tail", get(tail_job_logs))
         .route("/:id/progress", get(get_job_progress))
         .route("/:id/detailed-progress", get(get_detailed_progress))
         .route("/active", get(list_active_jobs))
-        .with_state(pool)
+        .route("/export", get(export_jobs))
+        .route("/stats", get(get_job_stats))
+        .with_state(state)
+}
+
+fn push_job_filters<'a>(builder: &mut QueryBuilder<'a, Sqlite>, query: &'a ListQuery) {
+    builder.push(" WHERE 1=1");
+    list_params::push_in(builder, "j.status", query.status.as_deref());
+    list_params::push_in(builder, "j.job_type", query.job_type.as_deref());
+    if let Some(task_id) = &query.task_id {
+        builder.push(" AND j.task_id = ").push_bind(task_id);
+    }
+    if let Some(since) = query.since {
+        builder.push(" AND j.created_at >= ").push_bind(since);
+    }
+    if let Some(until) = query.until {
+        builder.push(" AND j.created_at < ").push_bind(until);
+    }
+}
+
+/// Job with its task and database config, as selected by [`JOBS_SELECT`]
+fn job_from_row(row: &SqliteRow) -> JobWithDatabaseInfo {
+    JobWithDatabaseInfo {
+        job: Job {
+            id: row.get("id"),
+            task_id: row.get("task_id"),
+            used_database: row.get("used_database"),
+            job_type: row.get("job_type"),
+            status: row.get("status"),
+            progress: row.get("progress"),
+            started_at: row.get("started_at"),
+            completed_at: row.get("completed_at"),
+            error_message: row.get("error_message"),
+            log_output: row.get("log_output"),
+            backup_path: row.get("backup_path"),
+            created_at: row.get("created_at"),
+            compression_progress: row.get("compression_progress"),
+            upload_progress: row.get("upload_progress"),
+            attempt: row.get("attempt"),
+            retry_at: row.get("retry_at"),
+            source_backup_id: row.get("source_backup_id"),
+            target_config_id: row.get("target_config_id"),
+            restore_request: row.get("restore_request"),
+            priority: row.get("priority"),
+            retried_from: row.get("retried_from"),
+            backup_size: row.get("backup_size"),
+            resume_dir: row.get("resume_dir"),
+            cpu_seconds: row.get("cpu_seconds"),
+            peak_rss_bytes: row.get("peak_rss_bytes"),
+            bytes_written: row.get("bytes_written"),
+            queue_position: row.get("queue_position"),
+        },
+        task_name: row.get("task_name"),
+        task_database_name: row.get("task_database_name"),
+        db_config_name: row.get("db_config_name"),
+        db_config_host: row.get("db_config_host"),
+        db_config_database_name: row.get("db_config_database_name"),
+    }
 }
 
 async fn list_jobs(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Query(query): Query<ListQuery>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     let page = query.page.unwrap_or(1);
     let limit = query.limit.unwrap_or(10);
     let offset = (page - 1) * limit;
 
-    let mut sql = "SELECT j.*, t.name as task_name, t.database_name as task_database_name, dc.name as db_config_name, dc.host as db_config_host, dc.database_name as db_config_database_name FROM jobs j LEFT JOIN tasks t ON j.task_id = t.id LEFT JOIN database_configs dc ON t.database_config_id = dc.id".to_string();
-    let mut count_sql = "SELECT COUNT(*) as count FROM jobs j LEFT JOIN tasks t ON j.task_id = t.id LEFT JOIN database_configs dc ON t.database_config_id = dc.id".to_string();
-    let mut conditions = Vec::new();
-    
-    if query.status.is_some() {
-        conditions.push("j.status = ?");
+    let sort_column = list_params::sort_column(query.sort_by.as_deref(), JOB_SORT_COLUMNS)?;
+    let order = query.order.sql();
+    let cursor = Cursor::resolve(pool, "jobs", query.after_id.as_deref(), query.created_before).await?;
+
+    let mut select = QueryBuilder::new(JOBS_SELECT);
+    push_job_filters(&mut select, &query);
+    if let Some(cursor) = &cursor {
+        cursor.check_order(query.sort_by.as_deref(), query.order)?;
+        cursor.push_condition(&mut select, "j.created_at", "j.id");
+        // One more row than asked for tells whether another page follows
+        select.push(" ORDER BY j.created_at DESC, j.id DESC LIMIT ").push_bind(limit as i64 + 1);
+    } else {
+        // The id keeps the order stable between pages when the sort column ties
+        select.push(format!(" ORDER BY {} {}, j.id {} LIMIT ", sort_column, order, order))
+            .push_bind(limit as i64)
+            .push(" OFFSET ")
+            .push_bind(offset as i64);
     }
-    
-    if query.job_type.is_some() {
-        conditions.push("j.job_type = ?");
-    }
-    
-    if query.task_id.is_some() {
-        conditions.push("j.task_id = ?");
-    }
-    
-    if !conditions.is_empty() {
-        let where_clause = format!(" WHERE {}", conditions.join(" AND "));
-        sql.push_str(&where_clause);
-        count_sql.push_str(&where_clause);
-    }
-    
-    sql.push_str(&format!(" ORDER BY j.created_at DESC LIMIT {} OFFSET {}", limit, offset));
 
-    let mut query_builder = sqlx::query(&sql);
-    let mut count_query_builder = sqlx::query_as(&count_sql);
-    
-    if let Some(ref status) = query.status {
-        query_builder = query_builder.bind(status);
-        count_query_builder = count_query_builder.bind(status);
-    }
-    
-    if let Some(ref job_type) = query.job_type {
-        query_builder = query_builder.bind(job_type);
-        count_query_builder = count_query_builder.bind(job_type);
-    }
-    
-    if let Some(ref task_id) = query.task_id {
-        query_builder = query_builder.bind(task_id);
-        count_query_builder = count_query_builder.bind(task_id);
-    }
-
-    let rows = query_builder.fetch_all(&pool).await?;
-    let total: (i64,) = count_query_builder.fetch_one(&pool).await?;
-
-    let mut jobs: Vec<JobWithDatabaseInfo> = rows.into_iter().map(|row| {
-        JobWithDatabaseInfo {
-            job: Job {
-                id: row.get("id"),
-                task_id: row.get("task_id"),
-                used_database: row.get("used_database"),
-                job_type: row.get("job_type"),
-                status: row.get("status"),
-                progress: row.get("progress"),
-                started_at: row.get("started_at"),
-                completed_at: row.get("completed_at"),
-                error_message: row.get("error_message"),
-                log_output: row.get("log_output"),
-                backup_path: row.get("backup_path"),
-                created_at: row.get("created_at"),
-            },
-            task_name: row.get("task_name"),
-            task_database_name: row.get("task_database_name"),
-            db_config_name: row.get("db_config_name"),
-            db_config_host: row.get("db_config_host"),
-            db_config_database_name: row.get("db_config_database_name"),
-        }
-    }).collect();
+    let rows = select.build().fetch_all(pool).await?;
+
+    let mut jobs: Vec<JobWithDatabaseInfo> = rows.iter().map(job_from_row).collect();
 
     // Update progress for running jobs using the same logic as detailed progress
     for job in &mut jobs {
-        if job.job.status == "running" || job.job.status == "compressing" {
+        if job.job.status == "running" || job.job.status == "compressing" || job.job.status == "uploading" {
             if let Some(log_output) = &job.job.log_output {
                 if let Some(log_dir) = std::path::Path::new(log_output).parent() {
                     if let Some(log_dir_str) = log_dir.to_str() {
@@ -136,24 +271,67 @@ async fn list_jobs(
         }
     }
 
-    Ok(paginated_response(jobs, page, limit, total.0 as u64))
+    if cursor.is_some() {
+        return Ok(cursor_response(jobs, limit, |job| list_params::next_cursor(&job.job.id, job.job.created_at)).into_response());
+    }
+
+    let mut count = QueryBuilder::new("SELECT COUNT(*) as count FROM jobs j LEFT JOIN tasks t ON j.task_id = t.id LEFT JOIN database_configs dc ON t.database_config_id = dc.id");
+    push_job_filters(&mut count, &query);
+    let total: (i64,) = count.build_query_as().fetch_one(pool).await?;
+
+    Ok(paginated_response(jobs, page, limit, total.0 as u64).into_response())
+}
+
+/// Every job matching the list filters as CSV or JSON, newest first. Jobs are read in chunks
+/// along their creation time, so the export streams however many there are.
+async fn export_jobs(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListQuery>,
+    Query(export): Query<ExportQuery>,
+) -> impl IntoResponse {
+    let chunks = stream::unfold(Some((state.pool.clone(), query, None::<Cursor>)), |next| async move {
+        let (pool, query, cursor) = next?;
+        let rows = {
+            let mut select = QueryBuilder::new(JOBS_SELECT);
+            push_job_filters(&mut select, &query);
+            if let Some(cursor) = &cursor {
+                cursor.push_condition(&mut select, "j.created_at", "j.id");
+            }
+            select.push(" ORDER BY j.created_at DESC, j.id DESC LIMIT ").push_bind(EXPORT_CHUNK_SIZE);
+            select.build().fetch_all(&pool).await
+        };
+        let jobs: Vec<JobWithDatabaseInfo> = match rows {
+            Ok(rows) => rows.iter().map(job_from_row).collect(),
+            Err(e) => return Some((Err(e), None)),
+        };
+
+        // A full chunk may be followed by more; the next one starts after its last job
+        let cursor = jobs.last()
+            .filter(|_| jobs.len() as i64 == EXPORT_CHUNK_SIZE)
+            .map(|last| Cursor { created_at: last.job.created_at, id: Some(last.job.id.clone()) });
+        Some((Ok(jobs), cursor.map(|cursor| (pool, query, Some(cursor)))))
+    });
+
+    export::download("jobs", export.format, chunks)
 }
 
 async fn get_job(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     let mut job: Option<Job> = sqlx::query_as(
         "SELECT * FROM jobs WHERE id = ?"
     )
     .bind(&id)
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await?;
 
     match job {
         Some(mut job) => {
             // Update progress for running jobs using the same logic as detailed progress
-            if job.status == "running" || job.status == "compressing" {
+            if job.status == "running" || job.status == "compressing" || job.status == "uploading" {
                 if let Some(log_output) = &job.log_output {
                     if let Some(log_dir) = std::path::Path::new(log_output).parent() {
                         if let Some(log_dir_str) = log_dir.to_str() {
@@ -172,9 +350,11 @@ async fn get_job(
 }
 
 async fn create_job(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Json(req): Json<CreateJobRequest>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     // Validate task exists if task_id is provided
     // Validate task_id if provided
     if let Some(ref task_id) = req.task_id {
@@ -182,7 +362,7 @@ async fn create_job(
             "SELECT id FROM tasks WHERE id = ?"
         )
         .bind(task_id)
-        .fetch_optional(&pool)
+        .fetch_optional(pool)
         .await?;
 
         if task_exists.is_none() {
@@ -210,24 +390,26 @@ async fn create_job(
     .bind(&job.log_output)
     .bind(&job.backup_path)
     .bind(&job.created_at)
-    .execute(&pool)
+    .execute(pool)
     .await?;
 
     Ok(success_response(job))
 }
 
 async fn delete_job(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
     use std::path::Path as StdPath;
+
+    let pool = &state.pool;
     
     // Check if job exists and is not running
     let job: Option<Job> = sqlx::query_as(
         "SELECT * FROM jobs WHERE id = ?"
     )
     .bind(&id)
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await?;
 
     let job = job.ok_or_else(|| ApiError::NotFound("Job not found".to_string()))?;
@@ -268,18 +450,20 @@ async fn delete_job(
 
     let result = sqlx::query("DELETE FROM jobs WHERE id = ?")
         .bind(&id)
-        .execute(&pool)
+        .execute(pool)
         .await?;
 
     if result.rows_affected() == 0 {
         return Err(ApiError::NotFound("Job not found".to_string()));
     }
 
+    sqlx::query("DELETE FROM job_progress WHERE job_id = ?")
+        .bind(&id)
+        .execute(pool)
+        .await?;
+
     // Log the deletion
-    use crate::services::logging::LoggingService;
-    use std::sync::Arc;
-    let logging_service = LoggingService::new(Arc::new(pool.clone()));
-    let _ = logging_service.log_system_with_entity(
+    let _ = state.logging_service.log_system_with_entity(
         "job",
         &id,
         &format!("Job with status '{}' deleted", job.status),
@@ -289,15 +473,84 @@ async fn delete_job(
     Ok(success_response(serde_json::json!({"message": "Job deleted successfully"})))
 }
 
+/// Delete the finished jobs matching the filters together with their logs. Failed jobs with a
+/// retry still due are kept, as by the retention.
+async fn delete_jobs(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<BulkDeleteQuery>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    if query.status.is_none() && query.before.is_none() {
+        return Err(ApiError::BadRequest("Give a status or a before date to select the jobs to delete".to_string()));
+    }
+    if let Some(status) = &query.status {
+        if !matches!(status.as_str(), "completed" | "failed" | "cancelled") {
+            return Err(ApiError::BadRequest("Only completed, failed or cancelled jobs can be deleted".to_string()));
+        }
+    }
+    let before = query.before.as_deref().map(list_params::parse_before).transpose()?;
+
+    // Jobs still running or queued, or waiting for a retry, are never part of a bulk deletion
+    let mut sql = "SELECT id FROM jobs WHERE status IN ('completed', 'failed', 'cancelled') AND retry_at IS NULL".to_string();
+    if query.status.is_some() {
+        sql.push_str(" AND status = ?");
+    }
+    if before.is_some() {
+        sql.push_str(" AND created_at < ?");
+    }
+    if query.task_id.is_some() {
+        sql.push_str(" AND task_id = ?");
+    }
+    if query.job_type.is_some() {
+        sql.push_str(" AND job_type = ?");
+    }
+    let mut select = sqlx::query_scalar::<_, String>(&sql);
+    if let Some(status) = &query.status {
+        select = select.bind(status);
+    }
+    if let Some(before) = before {
+        select = select.bind(before);
+    }
+    if let Some(task_id) = &query.task_id {
+        select = select.bind(task_id);
+    }
+    if let Some(job_type) = &query.job_type {
+        select = select.bind(job_type);
+    }
+    let job_ids = select.fetch_all(&state.pool).await?;
+
+    if query.dry_run {
+        return Ok(success_response(serde_json::json!({
+            "dry_run": true,
+            "count": job_ids.len()
+        })));
+    }
+
+    let deleted = job_history::delete_jobs(&state.pool, &state.config.log_dir, &job_ids)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to delete jobs: {}", e)))?;
+    let _ = state.logging_service.log_system(
+        &format!("Deleted {} jobs in bulk", deleted),
+        LogLevel::Info
+    ).await;
+
+    Ok(success_response(serde_json::json!({
+        "dry_run": false,
+        "count": deleted
+    })))
+}
+
 async fn cancel_job(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
+    Query(query): Query<CancelQuery>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     let job: Option<Job> = sqlx::query_as(
         "SELECT * FROM jobs WHERE id = ?"
     )
     .bind(&id)
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await?;
 
     let job = job.ok_or_else(|| ApiError::NotFound("Job not found".to_string()))?;
@@ -309,6 +562,19 @@ async fn cancel_job(
         return Err(ApiError::BadRequest("Job cannot be cancelled in its current state".to_string()));
     }
 
+    // Only a database the restore created itself may be dropped, never one it was overwriting
+    let drop_database = query.drop_database.unwrap_or(false);
+    if drop_database {
+        let restore_request = job.restore_request.as_deref()
+            .filter(|_| job.job_type == JobType::Restore.to_string())
+            .and_then(|request| serde_json::from_str::<RestoreRequest>(request).ok());
+        match restore_request {
+            Some(request) if !request.overwrite_existing && request.new_database_name.is_some() => {}
+            Some(_) => return Err(ApiError::BadRequest("Only restores into a new database can drop it on cancellation".to_string())),
+            None => return Err(ApiError::BadRequest("Only restore jobs can drop their database on cancellation".to_string())),
+        }
+    }
+
     sqlx::query(
         "UPDATE jobs SET status = ?, completed_at = ?, error_message = ? WHERE id = ?"
     )
@@ -316,12 +582,12 @@ async fn cancel_job(
     .bind(chrono::Utc::now())
     .bind("Job cancelled by user")
     .bind(&id)
-    .execute(&pool)
+    .execute(pool)
     .await?;
+    events::publish(AppEvent::job_status(&id, &JobStatus::Cancelled.to_string()));
 
     // Clean up backup directory if it exists
-    let backup_dir = std::env::var("BACKUP_DIR").unwrap_or_else(|_| "data/backups".to_string());
-    let job_backup_dir = format!("{}/{}", backup_dir, id);
+    let job_backup_dir = format!("{}/{}", state.config.backup_dir, id);
     if std::path::Path::new(&job_backup_dir).exists() {
         if let Err(e) = std::fs::remove_dir_all(&job_backup_dir) {
             tracing::warn!("Failed to remove backup directory {}: {}", job_backup_dir, e);
@@ -330,7 +596,10 @@ async fn cancel_job(
         }
     }
 
-    // TODO: Send signal to actually cancel the running process
+    // Stop a restore running in this process, which kills myloader and removes its extraction
+    if cancellation::cancel(&id, drop_database) {
+        tracing::info!("Stopping running job {}", id);
+    }
 
     Ok(success_response(serde_json::json!({
         "message": "Job cancelled successfully",
@@ -338,19 +607,107 @@ async fn cancel_job(
     })))
 }
 
+/// Start a failed backup or restore job again as a new pending job, without waiting for the next scheduled run
+async fn retry_job(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
+    let failed: Job = sqlx::query_as("SELECT * FROM jobs WHERE id = ?")
+        .bind(&id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Job not found".to_string()))?;
+
+    if failed.status().ok() != Some(JobStatus::Failed) {
+        return Err(ApiError::BadRequest("Only failed jobs can be retried".to_string()));
+    }
+
+    // Backups run their task again, restores replay the stored restore request
+    let agent_id = if failed.job_type == JobType::Backup.to_string() {
+        let task_id = failed.task_id.as_deref()
+            .ok_or_else(|| ApiError::BadRequest("The job belongs to no task to run again".to_string()))?;
+        let agent_id: Option<Option<String>> = sqlx::query_scalar(
+            "SELECT dc.agent_id FROM tasks t JOIN database_configs dc ON t.database_config_id = dc.id WHERE t.id = ?"
+        )
+        .bind(task_id)
+        .fetch_optional(pool)
+        .await?;
+        agent_id.ok_or_else(|| ApiError::BadRequest("The job's task or its database configuration was deleted".to_string()))?
+    } else if failed.job_type == JobType::Restore.to_string() {
+        if failed.restore_request.is_none() {
+            return Err(ApiError::BadRequest("The restore job has no stored restore request to run again".to_string()));
+        }
+        None
+    } else {
+        return Err(ApiError::BadRequest("Only backup and restore jobs can be retried".to_string()));
+    };
+
+    let mut job = Job::new(CreateJobRequest {
+        task_id: failed.task_id.clone(),
+        used_database: failed.used_database.clone(),
+        job_type: failed.job_type.parse().map_err(ApiError::BadRequest)?,
+        backup_path: if failed.job_type == JobType::Restore.to_string() { failed.backup_path.clone() } else { None },
+    });
+    job.attempt = failed.attempt + 1;
+    job.priority = Job::PRIORITY_MANUAL;
+    job.source_backup_id = failed.source_backup_id.clone();
+    job.target_config_id = failed.target_config_id.clone();
+    job.restore_request = failed.restore_request.clone();
+    job.retried_from = Some(failed.id.clone());
+
+    sqlx::query(
+        r#"
+        INSERT INTO jobs (id, task_id, used_database, job_type, status, progress, started_at, completed_at, error_message, log_output, backup_path, attempt, created_at, source_backup_id, target_config_id, restore_request, priority, retried_from)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&job.id)
+    .bind(&job.task_id)
+    .bind(&job.used_database)
+    .bind(&job.job_type)
+    .bind(&job.status)
+    .bind(job.progress)
+    .bind(job.started_at)
+    .bind(job.completed_at)
+    .bind(&job.error_message)
+    .bind(&job.log_output)
+    .bind(&job.backup_path)
+    .bind(job.attempt)
+    .bind(job.created_at)
+    .bind(&job.source_backup_id)
+    .bind(&job.target_config_id)
+    .bind(&job.restore_request)
+    .bind(job.priority)
+    .bind(&job.retried_from)
+    .execute(pool)
+    .await?;
+
+    // The manual retry replaces a scheduled one
+    sqlx::query("UPDATE jobs SET retry_at = NULL WHERE id = ?")
+        .bind(&failed.id)
+        .execute(pool)
+        .await?;
+    let _ = state.logging_service.log_job(&failed.id, &format!("Retried manually as job {}", job.id), LogLevel::Info).await;
+
+    match agent_id {
+        Some(agent_id) => crate::services::agents::dispatch(pool, &job.id, &agent_id).await?,
+        None => state.worker.wake(),
+    }
+
+    Ok(success_response(job))
+}
+
 async fn get_job_logs(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
-    use crate::services::mydumper::MydumperService;
-    
-    // Create mydumper service instance
-    let backup_dir = std::env::var("BACKUP_DIR").unwrap_or_else(|_| "data/backups".to_string());
-    let log_dir = std::env::var("LOG_DIR").unwrap_or_else(|_| "data/logs".to_string());
-    let mydumper_service = MydumperService::new(backup_dir, log_dir);
+    let pool = &state.pool;
+    let mydumper_service = &state.mydumper_service;
     
     // Try to read logs from file first, then fallback to database
-    match mydumper_service.read_job_logs(&id, &pool).await {
+    match mydumper_service.read_job_logs(&id, pool).await {
         Ok(logs) => {
             Ok(success_response(serde_json::json!({
                 "job_id": id,
@@ -363,7 +720,7 @@ async fn get_job_logs(
                 "SELECT log_output FROM jobs WHERE id = ?"
             )
             .bind(&id)
-            .fetch_optional(&pool)
+            .fetch_optional(pool)
             .await?;
 
             let job = job.ok_or_else(|| ApiError::NotFound("Job not found".to_string()))?;
@@ -376,18 +733,151 @@ async fn get_job_logs(
     }
 }
 
+/// Files in a job's working directories: the tmp folder its dump is written to, as long as the
+/// archive hasn't replaced it, and its log folder
+async fn get_job_artifacts(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let job: Job = sqlx::query_as("SELECT * FROM jobs WHERE id = ?")
+        .bind(&id)
+        .fetch_optional(&state.pool)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Job not found".to_string()))?;
+
+    let tmp_dir = if job.job_type == JobType::Backup.to_string() {
+        state.backup_service.find_unfinished_process(&id).await
+            .map_err(|e| ApiError::InternalError(format!("Failed to look up the backup folder: {}", e)))?
+            .map(|root_dir| root_dir.join("tmp"))
+    } else {
+        None
+    };
+    let log_dir = job_history::log_folder(&state.config.log_dir, &id, job.log_output.as_deref());
+
+    let mut directories = Vec::new();
+    for (kind, dir) in [("tmp", tmp_dir), ("logs", Some(log_dir))] {
+        let Some(dir) = dir.filter(|dir| dir.is_dir()) else {
+            continue;
+        };
+        let files = list_artifacts(&dir).await
+            .map_err(|e| ApiError::InternalError(format!("Failed to list {}: {}", dir.display(), e)))?;
+        directories.push(serde_json::json!({
+            "kind": kind,
+            "path": dir,
+            "total_size": files.iter().map(|file| file.size).sum::<u64>(),
+            "files": files
+        }));
+    }
+
+    Ok(success_response(serde_json::json!({
+        "job_id": id,
+        "status": job.status,
+        "directories": directories
+    })))
+}
+
+/// Every file below `dir`, sorted by path
+async fn list_artifacts(dir: &std::path::Path) -> std::io::Result<Vec<JobArtifact>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let mut entries = tokio::fs::read_dir(&current).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            if metadata.is_dir() {
+                pending.push(entry.path());
+                continue;
+            }
+            files.push(JobArtifact {
+                name: entry.path().strip_prefix(dir).unwrap_or(&entry.path()).to_string_lossy().to_string(),
+                size: metadata.len(),
+                modified_at: metadata.modified().ok().map(chrono::DateTime::from),
+            });
+        }
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(files)
+}
+
+/// Follow a job's mydumper log as Server-Sent Events while it grows.
+///
+/// `log` events carry the new lines and the byte offset after them as id. Once the job has
+/// finished and its log is read to the end, an `end` event carries its status and the stream closes.
+async fn tail_job_logs(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<TailQuery>,
+    headers: HeaderMap,
+) -> ApiResult<Sse<impl Stream<Item = Result<Event, Infallible>>>> {
+    let exists: Option<String> = sqlx::query_scalar("SELECT id FROM jobs WHERE id = ?")
+        .bind(&id)
+        .fetch_optional(&state.pool)
+        .await?;
+    if exists.is_none() {
+        return Err(ApiError::NotFound("Job not found".to_string()));
+    }
+
+    let offset = query.offset
+        .or_else(|| headers.get("last-event-id")?.to_str().ok()?.parse().ok())
+        .unwrap_or(0);
+    let tail = LogTail::new(state.mydumper_service.job_log_path(&id), offset);
+    let pool = state.pool.clone();
+
+    // The final status is kept until the last lines went out
+    let stream = stream::unfold((tail, pool, id, None::<String>, false), |(mut tail, pool, id, mut final_status, closed)| async move {
+        if closed {
+            return None;
+        }
+        loop {
+            let lines = match tail.read(final_status.is_some()).await {
+                Ok(lines) => lines,
+                Err(e) => {
+                    tracing::warn!("Failed to follow log of job {}: {}", id, e);
+                    let event = Event::default().event("error").data(e.to_string());
+                    return Some((Ok(event), (tail, pool, id, final_status, true)));
+                }
+            };
+            if let Some(lines) = lines {
+                let event = Event::default().event("log").id(tail.offset().to_string()).data(lines);
+                return Some((Ok(event), (tail, pool, id, final_status, false)));
+            }
+            if let Some(status) = final_status {
+                let event = Event::default().event("end").data(status);
+                return Some((Ok(event), (tail, pool, id, None, true)));
+            }
+
+            let status: Option<String> = sqlx::query_scalar("SELECT status FROM jobs WHERE id = ?")
+                .bind(&id)
+                .fetch_optional(&pool)
+                .await
+                .unwrap_or(None);
+            match status {
+                Some(status) if matches!(status.as_str(), "pending" | "running" | "compressing" | "uploading") => {
+                    tokio::time::sleep(TAIL_INTERVAL).await;
+                }
+                // Read once more for lines written just before the job finished
+                finished => final_status = Some(finished.unwrap_or_else(|| "deleted".to_string())),
+            }
+        }
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
 async fn get_job_progress(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
     use crate::services::progress_tracker::ProgressTracker;
+
+    let pool = &state.pool;
     
     // Get job from database
     let job: Option<Job> = sqlx::query_as(
         "SELECT * FROM jobs WHERE id = ?"
     )
     .bind(&id)
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await?;
 
     let job = job.ok_or_else(|| ApiError::NotFound("Job not found".to_string()))?;
@@ -430,18 +920,22 @@ async fn get_job_progress(
     })))
 }
 
+/// Pending and running jobs; answers `If-None-Match` with 304 while they are unchanged
 async fn list_active_jobs(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     let mut jobs: Vec<Job> = sqlx::query_as(
-        "SELECT * FROM jobs WHERE status IN ('pending', 'running', 'compressing') ORDER BY created_at DESC"
+        "SELECT * FROM jobs WHERE status IN ('pending', 'running', 'compressing', 'uploading') ORDER BY created_at DESC"
     )
-    .fetch_all(&pool)
+    .fetch_all(pool)
     .await?;
 
     // Update progress for running jobs using the same logic as detailed progress
     for job in &mut jobs {
-        if job.status == "running" || job.status == "compressing" {
+        if job.status == "running" || job.status == "compressing" || job.status == "uploading" {
             if let Some(log_output) = &job.log_output {
                 if let Some(log_dir) = std::path::Path::new(log_output).parent() {
                     if let Some(log_dir_str) = log_dir.to_str() {
@@ -455,36 +949,69 @@ async fn list_active_jobs(
         }
     }
 
-    Ok(success_response(jobs))
+    Ok(conditional_response(&headers, &jobs, success_response(&jobs)))
+}
+
+/// Success rate, durations, bytes produced and failure reasons of the jobs in a time window
+async fn get_job_stats(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<StatsQuery>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let days = query.days.unwrap_or(30);
+    if days == 0 {
+        return Err(ApiError::BadRequest("days must be at least 1".to_string()));
+    }
+    let job_type = query.job_type.unwrap_or_else(|| JobType::Backup.to_string());
+    if job_type != JobType::Backup.to_string() && job_type != JobType::Restore.to_string() {
+        return Err(ApiError::BadRequest(format!("Unknown job type '{}'", job_type)));
+    }
+
+    let since = chrono::Utc::now() - chrono::Duration::days(days as i64);
+    let (overall, tasks) = job_history::job_stats(&state.pool, &job_type, since)
+        .await
+        .map_err(|e| ApiError::InternalError(format!("Failed to compute job statistics: {}", e)))?;
+
+    Ok(success_response(serde_json::json!({
+        "window_days": days,
+        "since": since,
+        "job_type": job_type,
+        "overall": overall,
+        "tasks": tasks
+    })))
 }
 
 async fn get_detailed_progress(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     // Get job information
     let job: Job = sqlx::query_as(
         "SELECT * FROM jobs WHERE id = ?"
     )
     .bind(&id)
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await?
     .ok_or_else(|| ApiError::NotFound("Job not found".to_string()))?;
 
     // Get log directory from job
-    let log_output = job.log_output.as_ref()
-        .ok_or_else(|| ApiError::BadRequest("Job has no log output".to_string()))?;
-    
-    let log_dir = std::path::Path::new(log_output)
-        .parent()
-        .ok_or_else(|| ApiError::BadRequest("Invalid log path".to_string()))?
-        .to_string_lossy()
-        .to_string();
+    let log_dir = job.log_output.as_ref()
+        .and_then(|log_output| std::path::Path::new(log_output).parent())
+        .map(|log_dir| log_dir.to_string_lossy().to_string());
 
     // Create progress tracker and load detailed progress
-    let progress_tracker = ProgressTracker::new(log_dir);
-    let detailed_progress = progress_tracker.load_detailed_progress(&id).await
-        .map_err(|e| ApiError::InternalError(format!("Failed to load detailed progress: {}", e)))?;
+    let from_logs = match log_dir {
+        Some(log_dir) => ProgressTracker::new(log_dir).load_detailed_progress(&id).await,
+        None => Err(anyhow::anyhow!("Job has no log output")),
+    };
+    let detailed_progress = match from_logs {
+        Ok(detailed_progress) => detailed_progress,
+        // The logs of older jobs may be gone, fall back to the last stored snapshot
+        Err(e) => progress_tracker::load_snapshot(pool, &id).await
+            .map_err(|e| ApiError::InternalError(format!("Failed to load progress snapshot: {}", e)))?
+            .ok_or_else(|| ApiError::InternalError(format!("Failed to load detailed progress: {}", e)))?,
+    };
 
     Ok(success_response(detailed_progress))
 }
\ No newline at end of file
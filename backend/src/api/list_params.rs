@@ -0,0 +1,141 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Deserialize;
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+
+use super::ApiError;
+use utoipa::ToSchema;
+
+/// Direction of a sorted list, newest or largest first unless asked otherwise
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortOrder {
+    pub fn sql(self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
+
+/// Values of a comma separated filter such as `status=failed,cancelled`
+pub fn split_values(value: Option<&str>) -> Vec<&str> {
+    value.unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+/// Column expression for `sort_by` out of the sortable `columns`, the first one being the default.
+/// Only these fixed expressions ever reach the SQL.
+pub fn sort_column(sort_by: Option<&str>, columns: &[(&str, &'static str)]) -> Result<&'static str, ApiError> {
+    let Some(sort_by) = sort_by else {
+        return Ok(columns[0].1);
+    };
+    columns.iter()
+        .find(|(name, _)| *name == sort_by)
+        .map(|(_, column)| *column)
+        .ok_or_else(|| ApiError::BadRequest(format!(
+            "Cannot sort by '{}', use one of: {}",
+            sort_by,
+            columns.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", ")
+        )))
+}
+
+/// A `before` filter as a date, meaning midnight UTC, or an RFC 3339 time
+pub fn parse_before(before: &str) -> Result<DateTime<Utc>, ApiError> {
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(before, "%Y-%m-%d") {
+        return Ok(date.and_time(chrono::NaiveTime::MIN).and_utc());
+    }
+    DateTime::parse_from_rfc3339(before)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|_| ApiError::BadRequest(format!("Invalid before date '{}', use YYYY-MM-DD or RFC 3339", before)))
+}
+
+/// Keyset position in a list sorted newest first: the rows after `after_id`, or created before
+/// `created_before`. Unlike offsets it neither slows down deep into a list nor skips rows when
+/// new ones arrive between pages.
+#[derive(Debug, Clone)]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Option<String>,
+}
+
+impl Cursor {
+    /// Cursor from the `after_id` and `created_before` parameters, looking up the creation time of
+    /// `after_id` in `table` unless both are given. None without either, for offset pagination.
+    pub async fn resolve(
+        pool: &SqlitePool,
+        table: &str,
+        after_id: Option<&str>,
+        created_before: Option<DateTime<Utc>>,
+    ) -> Result<Option<Self>, ApiError> {
+        let created_at = match (created_before, after_id) {
+            (Some(created_before), _) => created_before,
+            (None, Some(after_id)) => sqlx::query_scalar(&format!("SELECT created_at FROM {} WHERE id = ?", table))
+                .bind(after_id)
+                .fetch_optional(pool)
+                .await?
+                .ok_or_else(|| ApiError::BadRequest(format!("Unknown after_id '{}', pass created_before as well", after_id)))?,
+            (None, None) => return Ok(None),
+        };
+        Ok(Some(Self { created_at, id: after_id.map(str::to_string) }))
+    }
+
+    /// Cursors walk the default order only, newest first
+    pub fn check_order(&self, sort_by: Option<&str>, order: SortOrder) -> Result<(), ApiError> {
+        if sort_by.is_some_and(|sort_by| sort_by != "created_at") || order != SortOrder::Desc {
+            return Err(ApiError::BadRequest("after_id and created_before page through the newest first order, leave out sort_by and order".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Append the condition for rows after the cursor in a list sorted by `created_column`, then `id_column`
+    pub fn push_condition<'a>(&'a self, builder: &mut QueryBuilder<'a, Sqlite>, created_column: &str, id_column: &str) {
+        match &self.id {
+            Some(id) => {
+                builder.push(format!(" AND ({} < ", created_column)).push_bind(self.created_at)
+                    .push(format!(" OR ({} = ", created_column)).push_bind(self.created_at)
+                    .push(format!(" AND {} < ", id_column)).push_bind(id)
+                    .push("))");
+            }
+            None => {
+                builder.push(format!(" AND {} < ", created_column)).push_bind(self.created_at);
+            }
+        }
+    }
+
+    /// Whether a row comes after the cursor, for lists sorted in memory
+    pub fn is_before(&self, created_at: DateTime<Utc>, id: &str) -> bool {
+        created_at < self.created_at
+            || (created_at == self.created_at && self.id.as_deref().is_some_and(|cursor_id| id < cursor_id))
+    }
+}
+
+/// Cursor parameters continuing after a row, with a UTC time that needs no escaping in a URL
+pub fn next_cursor(id: &str, created_at: DateTime<Utc>) -> serde_json::Value {
+    serde_json::json!({
+        "after_id": id,
+        "created_before": created_at.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+    })
+}
+
+/// Append `AND <column> IN (...)` with one bound parameter per value of a comma separated filter
+pub fn push_in<'a>(builder: &mut QueryBuilder<'a, Sqlite>, column: &str, values: Option<&'a str>) {
+    let values = split_values(values);
+    if values.is_empty() {
+        return;
+    }
+    builder.push(format!(" AND {} IN (", column));
+    let mut separated = builder.separated(", ");
+    for value in values {
+        separated.push_bind(value);
+    }
+    separated.push_unseparated(")");
+}
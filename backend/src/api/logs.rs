@@ -1,15 +1,20 @@
 use axum::{
     extract::{Path, Query, State},
+    response::IntoResponse,
     routing::get,
     Json, Router,
 };
 use serde::Deserialize;
-use sqlx::SqlitePool;
+use sqlx::{QueryBuilder, Sqlite};
+use std::sync::Arc;
 
 use crate::models::{Log, LogType, LogLevel};
-use super::{ApiError, ApiResult, success_response, paginated_response};
+use crate::state::AppState;
+use super::{ApiError, ApiResult, success_response, paginated_response, cursor_response};
+use super::list_params::{self, Cursor};
+use utoipa::IntoParams;
 
-#[derive(Deserialize)]
+#[derive(Default, Deserialize, IntoParams)]
 pub struct ListLogsQuery {
     page: Option<u32>,
     limit: Option<u32>,
@@ -17,76 +22,151 @@ pub struct ListLogsQuery {
     entity_type: Option<String>,
     entity_id: Option<String>,
     level: Option<String>,
+    /// Entries created at or after this time
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    /// Entries created before this time
+    until: Option<chrono::DateTime<chrono::Utc>>,
+    /// Cursor pagination: entries after this one, newest first
+    after_id: Option<String>,
+    /// Cursor pagination: entries created before this time, or the time of `after_id`
+    created_before: Option<chrono::DateTime<chrono::Utc>>,
 }
 
-pub fn routes(pool: SqlitePool) -> Router {
+#[derive(Deserialize, IntoParams)]
+pub struct PurgeLogsQuery {
+    /// Date (`2024-01-01`, midnight UTC) or RFC 3339 time the entries were created before
+    before: Option<String>,
+    log_type: Option<String>,
+    entity_type: Option<String>,
+    entity_id: Option<String>,
+    level: Option<String>,
+    /// Only count the entries that would be deleted
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct CleanupQuery {
+    days: Option<u32>,
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
-        .route("/", get(list_logs))
+        .route("/", get(list_logs).delete(purge_logs))
         .route("/cleanup", get(cleanup_logs))
-        .with_state(pool)
+        .with_state(state)
 }
 
 async fn list_logs(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Query(query): Query<ListLogsQuery>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     let page = query.page.unwrap_or(1);
     let limit = query.limit.unwrap_or(50);
     let offset = (page - 1) * limit;
 
-    let mut sql = "SELECT * FROM logs WHERE 1=1".to_string();
-    let mut count_sql = "SELECT COUNT(*) as count FROM logs WHERE 1=1".to_string();
-    
+    let cursor = Cursor::resolve(pool, "logs", query.after_id.as_deref(), query.created_before).await?;
+
+    let mut select = QueryBuilder::new("SELECT * FROM logs");
+    push_log_filters(&mut select, &query);
+    if let Some(cursor) = &cursor {
+        cursor.push_condition(&mut select, "created_at", "id");
+        // One more row than asked for tells whether another page follows
+        select.push(" ORDER BY created_at DESC, id DESC LIMIT ").push_bind(limit as i64 + 1);
+    } else {
+        select.push(" ORDER BY created_at DESC, id DESC LIMIT ")
+            .push_bind(limit as i64)
+            .push(" OFFSET ")
+            .push_bind(offset as i64);
+    }
+
+    let logs: Vec<Log> = select.build_query_as().fetch_all(pool).await?;
+
+    if cursor.is_some() {
+        return Ok(cursor_response(logs, limit, |log| list_params::next_cursor(&log.id, log.created_at)).into_response());
+    }
+
+    let mut count = QueryBuilder::new("SELECT COUNT(*) as count FROM logs");
+    push_log_filters(&mut count, &query);
+    let total: (i64,) = count.build_query_as().fetch_one(pool).await?;
+
+    Ok(paginated_response(logs, page, limit, total.0 as u64).into_response())
+}
+
+fn push_log_filters<'a>(builder: &mut QueryBuilder<'a, Sqlite>, query: &'a ListLogsQuery) {
+    builder.push(" WHERE 1=1");
     if let Some(log_type) = &query.log_type {
-        let log_type_clause = format!(" AND log_type = '{}'", log_type);
-        sql.push_str(&log_type_clause);
-        count_sql.push_str(&log_type_clause);
+        builder.push(" AND log_type = ").push_bind(log_type);
     }
-    
     if let Some(entity_type) = &query.entity_type {
-        let entity_type_clause = format!(" AND entity_type = '{}'", entity_type);
-        sql.push_str(&entity_type_clause);
-        count_sql.push_str(&entity_type_clause);
+        builder.push(" AND entity_type = ").push_bind(entity_type);
     }
-    
     if let Some(entity_id) = &query.entity_id {
-        let entity_id_clause = format!(" AND entity_id = '{}'", entity_id);
-        sql.push_str(&entity_id_clause);
-        count_sql.push_str(&entity_id_clause);
+        builder.push(" AND entity_id = ").push_bind(entity_id);
     }
-    
     if let Some(level) = &query.level {
-        let level_clause = format!(" AND level = '{}'", level);
-        sql.push_str(&level_clause);
-        count_sql.push_str(&level_clause);
+        builder.push(" AND level = ").push_bind(level);
     }
-    
-    sql.push_str(&format!(" ORDER BY created_at DESC LIMIT {} OFFSET {}", limit, offset));
+    if let Some(since) = query.since {
+        builder.push(" AND created_at >= ").push_bind(since);
+    }
+    if let Some(until) = query.until {
+        builder.push(" AND created_at < ").push_bind(until);
+    }
+}
 
-    let logs: Vec<Log> = sqlx::query_as(&sql)
-        .fetch_all(&pool)
-        .await?;
+/// Delete the entries created before a date, optionally only those of a type, level or entity
+async fn purge_logs(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PurgeLogsQuery>,
+) -> ApiResult<impl IntoResponse> {
+    let before = query.before.as_deref()
+        .ok_or_else(|| ApiError::BadRequest("Give a before date to select the log entries to delete".to_string()))
+        .and_then(list_params::parse_before)?;
+    let filters = ListLogsQuery {
+        log_type: query.log_type,
+        entity_type: query.entity_type,
+        entity_id: query.entity_id,
+        level: query.level,
+        until: Some(before),
+        ..Default::default()
+    };
 
-    let total: (i64,) = sqlx::query_as(&count_sql)
-        .fetch_one(&pool)
-        .await?;
+    if query.dry_run {
+        let mut count = QueryBuilder::new("SELECT COUNT(*) FROM logs");
+        push_log_filters(&mut count, &filters);
+        let (count,): (i64,) = count.build_query_as().fetch_one(&state.pool).await?;
+        return Ok(success_response(serde_json::json!({
+            "dry_run": true,
+            "count": count
+        })));
+    }
 
-    Ok(paginated_response(logs, page, limit, total.0 as u64))
+    let mut delete = QueryBuilder::new("DELETE FROM logs");
+    push_log_filters(&mut delete, &filters);
+    let result = delete.build().execute(&state.pool).await?;
+
+    Ok(success_response(serde_json::json!({
+        "dry_run": false,
+        "count": result.rows_affected()
+    })))
 }
 
 async fn cleanup_logs(
-    State(pool): State<SqlitePool>,
-    Query(params): Query<serde_json::Value>,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CleanupQuery>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
-    let days = params.get("days")
-        .and_then(|v| v.as_u64())
-        .unwrap_or(14) as u32;
+    let pool = &state.pool;
+
+    let days = query.days.unwrap_or(14);
 
     let cutoff_date = chrono::Utc::now() - chrono::Duration::days(days as i64);
     
     let result = sqlx::query("DELETE FROM logs WHERE created_at < ?")
         .bind(cutoff_date)
-        .execute(&pool)
+        .execute(pool)
         .await?;
 
     Ok(success_response(serde_json::json!({
@@ -6,29 +6,82 @@ pub mod logs;
 pub mod system;
 pub mod dashboard;
 pub mod worker;
+pub mod events;
+pub mod transfers;
+pub mod ui_settings;
+pub mod agents;
+pub mod storage_destinations;
+pub mod blackout_windows;
+pub mod openapi;
+pub mod api_keys;
+pub mod auth;
+pub mod users;
+pub mod audit;
+pub mod list_params;
+pub mod search;
+pub mod webhooks;
+pub mod export;
 
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
+    middleware,
     routing::get,
     Json, Router,
 };
 use serde_json::json;
-use sqlx::SqlitePool;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
 use std::sync::Arc;
-use crate::services::TaskWorker;
+use crate::state::AppState;
 
-pub fn create_routes(pool: SqlitePool, worker: Arc<TaskWorker>) -> Router {
+/// Prefix of the current API version. Breaking changes ship under a new version, while
+/// `/api` without a version stays an alias of v1 for existing integrations.
+pub const API_V1: &str = "/api/v1";
+
+pub fn create_routes(state: Arc<AppState>) -> Router {
+    let v1 = v1_routes(state.clone());
+    Router::new()
+        .nest(API_V1, v1.clone())
+        .nest("/api", v1)
+        .layer(middleware::from_fn_with_state(state.clone(), audit::record))
+        .layer(middleware::from_fn_with_state(state, auth::authenticate))
+}
+
+fn v1_routes(state: Arc<AppState>) -> Router {
     Router::new()
-        .nest("/api/database-configs", database_configs::routes(pool.clone()))
-        .nest("/api/tasks", tasks::routes(pool.clone()))
-        .nest("/api/jobs", jobs::routes(pool.clone()))
-        .nest("/api/backups", backups::routes(pool.clone()))
-        .nest("/api/logs", logs::routes(pool.clone()))
-        .nest("/api/system", system::routes(worker.clone()))
-        .nest("/api/dashboard", dashboard::routes(pool.clone()))
-        .nest("/api/worker", worker::routes(worker))
-        .route("/api/health", get(health_check))
+        .nest("/database-configs", database_configs::routes(state.clone()))
+        .nest("/tasks", tasks::routes(state.clone()))
+        .nest("/jobs", jobs::routes(state.clone()))
+        .nest("/backups", backups::routes(state.clone()))
+        .nest("/logs", logs::routes(state.clone()))
+        .nest("/system", system::routes(state.clone()))
+        .nest("/dashboard", dashboard::routes(state.clone()))
+        .nest("/worker", worker::routes(state.clone()))
+        .nest("/events", events::routes(state.clone()))
+        .nest("/transfers", transfers::routes(state.clone()))
+        .nest("/ui-settings", ui_settings::routes(state.clone()))
+        .nest("/agents", agents::routes(state.clone()))
+        .nest("/storage-destinations", storage_destinations::routes(state.clone()))
+        .nest("/blackout-windows", blackout_windows::routes(state.clone()))
+        .nest("/api-keys", api_keys::routes(state.clone()))
+        .nest("/users", users::routes(state.clone()))
+        .nest("/auth", auth::routes(state.clone()))
+        .nest("/audit", audit::routes(state.clone()))
+        .nest("/search", search::routes(state.clone()))
+        .nest("/webhooks", webhooks::routes(state.clone()))
+        .nest("/agent", agents::protocol_routes(state))
+        .route("/health", get(health_check))
+        .merge(openapi::routes())
+}
+
+/// A request path as under the unversioned alias, e.g. `/api/v1/jobs` as `/api/jobs`, for the
+/// middleware that goes by paths
+pub fn unversioned_path(path: &str) -> Cow<'_, str> {
+    match path.strip_prefix(API_V1) {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => Cow::Owned(format!("/api{}", rest)),
+        _ => Cow::Borrowed(path),
+    }
 }
 
 async fn health_check() -> impl IntoResponse {
@@ -45,9 +98,19 @@ pub enum ApiError {
     DatabaseError(sqlx::Error),
     NotFound(String),
     BadRequest(String),
+    Unauthorized(String),
     InternalError(String),
 }
 
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiError::DatabaseError(err) => write!(f, "Database error: {}", err),
+            ApiError::NotFound(msg) | ApiError::BadRequest(msg) | ApiError::Unauthorized(msg) | ApiError::InternalError(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
 impl From<sqlx::Error> for ApiError {
     fn from(err: sqlx::Error) -> Self {
         ApiError::DatabaseError(err)
@@ -69,6 +132,7 @@ impl IntoResponse for ApiError {
             }
             ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg.clone()),
             ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
+            ApiError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg.clone()),
             ApiError::InternalError(msg) => {
                 tracing::error!("Internal error: {}", msg);
                 (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string())
@@ -95,6 +159,51 @@ pub fn success_response<T: serde::Serialize>(data: T) -> impl IntoResponse {
     }))
 }
 
+/// Answer `If-None-Match` for a response showing `content`: 304 Not Modified when the client's copy
+/// is current, otherwise `response`, both with the ETag of `content`. The tag leaves out the
+/// envelope's timestamp, so it only changes with the data and polling clients get cheap 304s.
+pub fn conditional_response(headers: &HeaderMap, content: &impl serde::Serialize, response: impl IntoResponse) -> Response {
+    let Ok(bytes) = serde_json::to_vec(content) else {
+        return response.into_response();
+    };
+    let etag = format!("W/\"{}\"", hex::encode(&Sha256::digest(&bytes)[..16]));
+    // Weak comparison, as If-None-Match asks for
+    let is_current = headers.get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|tags| tags.split(',').map(str::trim).any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag.trim_start_matches("W/")));
+
+    let mut response = if is_current { StatusCode::NOT_MODIFIED.into_response() } else { response.into_response() };
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+    // Browsers keep the response, but ask whether it is still current on every poll
+    response.headers_mut().insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    response
+}
+
+/// A page of a list walked with a cursor: `data` holds up to `limit + 1` rows, the extra one only
+/// telling that more follow. `next_cursor` holds the parameters for the next page.
+pub fn cursor_response<T: serde::Serialize>(
+    mut data: Vec<T>,
+    limit: u32,
+    next_cursor: impl Fn(&T) -> serde_json::Value,
+) -> impl IntoResponse {
+    let has_next = data.len() > limit as usize;
+    data.truncate(limit as usize);
+    let next_cursor = data.last().filter(|_| has_next).map(next_cursor);
+
+    Json(json!({
+        "success": true,
+        "data": data,
+        "pagination": {
+            "limit": limit,
+            "has_next": has_next,
+            "next_cursor": next_cursor
+        },
+        "timestamp": chrono::Utc::now().to_rfc3339()
+    }))
+}
+
 pub fn paginated_response<T: serde::Serialize>(
     data: Vec<T>,
     page: u32,
@@ -0,0 +1,580 @@
+use std::collections::BTreeMap;
+use axum::{
+    response::{Html, IntoResponse},
+    routing::get,
+    Json, Router,
+};
+use serde_json::{json, Map, Value};
+use utoipa::openapi::path::{Parameter, ParameterIn};
+use utoipa::openapi::{Array, Ref, RefOr, Schema};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::models::{
+    progress::DetailedProgress, AgentFailRequest, AgentPollRequest, AgentPollResponse, AgentProgressRequest,
+    ApiKey, AuditEntry, AuditQuery, Backup, BlackoutWindow, CatalogManifest, ChangePasswordRequest,
+    CopyBackupRequest, CreateAgentRequest, CreateApiKeyRequest, CreateBlackoutWindowRequest,
+    CreateDatabaseConfigRequest, CreateJobRequest, CreateStorageDestinationRequest, CreateTaskRequest,
+    CreateUserRequest, CreateWebhookRequest, DatabaseConfig, Job, Log, LoginRequest, PointInTimeRestoreRequest,
+    PutUiSettingRequest, RecompressRequest, RefreshRequest, RegisterBackupRequest, RestoreRequest,
+    StorageDestination, UiSettingResponse, UpdateBlackoutWindowRequest, UpdateDatabaseConfigRequest,
+    UpdateStorageDestinationRequest, UpdateTaskRequest, UpdateWebhookRequest, User, VerifyRemoteRequest,
+    VerifyRequest, Webhook, WebhookDelivery,
+};
+use crate::services::{auth::TokenPair, mydumper::DryRunReport, sla::RpoStatus};
+use super::{
+    agents::AgentResponse, auth::Principal, backups, blackout_windows, dashboard::OverdueQuery, database_configs,
+    export::ExportQuery, jobs, logs, search::{SearchQuery, SearchResult},
+    storage_destinations::StorageDestinationResponse, system::MonitoringQuery, tasks, transfers::TransferResponse,
+    ui_settings::ScopeQuery, webhooks::DeliveriesQuery, worker::WorkerStatusResponse, API_V1,
+};
+
+/// Component schemas collected while the document is built, by name
+type Schemas = Vec<(String, RefOr<Schema>)>;
+/// Adds the components a body or reply needs and returns the schema referring to them
+type SchemaFn = fn(&mut Schemas) -> RefOr<Schema>;
+/// Query parameters of the struct a handler extracts with `Query`
+type ParamsFn = fn() -> Vec<Parameter>;
+
+/// Request body of an operation
+enum Body {
+    Json { schema: SchemaFn, required: bool },
+    /// Anything but JSON, by content type
+    Raw(&'static str),
+}
+
+/// Successful response of an operation
+enum Reply {
+    /// The `Success` envelope around data that is not described further
+    Untyped,
+    /// The `Success` envelope around the schema
+    Data(SchemaFn),
+    /// The `Page` envelope around a list of the schema
+    Page(SchemaFn),
+    /// The schema itself, without an envelope
+    Document(SchemaFn),
+    /// Anything but an envelope, by content type
+    Raw(&'static [&'static str]),
+}
+
+/// One documented API operation
+pub(crate) struct Operation {
+    pub(crate) method: &'static str,
+    pub(crate) path: &'static str,
+    tag: &'static str,
+    summary: &'static str,
+    query: &'static [ParamsFn],
+    body: Option<Body>,
+    reply: Reply,
+}
+
+const fn op(method: &'static str, path: &'static str, tag: &'static str, summary: &'static str) -> Operation {
+    Operation { method, path, tag, summary, query: &[], body: None, reply: Reply::Untyped }
+}
+
+impl Operation {
+    const fn query(self, query: &'static [ParamsFn]) -> Self {
+        Operation { query, ..self }
+    }
+
+    const fn body(self, schema: SchemaFn) -> Self {
+        Operation { body: Some(Body::Json { schema, required: true }), ..self }
+    }
+
+    /// A JSON body the handler also accepts being left out
+    const fn optional_body(self, schema: SchemaFn) -> Self {
+        Operation { body: Some(Body::Json { schema, required: false }), ..self }
+    }
+
+    const fn upload(self, content_type: &'static str) -> Self {
+        Operation { body: Some(Body::Raw(content_type)), ..self }
+    }
+
+    const fn returns(self, schema: SchemaFn) -> Self {
+        Operation { reply: Reply::Data(schema), ..self }
+    }
+
+    const fn pages(self, item: SchemaFn) -> Self {
+        Operation { reply: Reply::Page(item), ..self }
+    }
+
+    const fn document(self, schema: SchemaFn) -> Self {
+        Operation { reply: Reply::Document(schema), ..self }
+    }
+
+    const fn produces(self, content_types: &'static [&'static str]) -> Self {
+        Operation { reply: Reply::Raw(content_types), ..self }
+    }
+}
+
+/// `T` as a component, along with the components it refers to
+fn schema<T: ToSchema>(schemas: &mut Schemas) -> RefOr<Schema> {
+    schemas.push((T::name().into_owned(), T::schema()));
+    T::schemas(schemas);
+    RefOr::Ref(Ref::from_schema_name(T::name()))
+}
+
+fn list<T: ToSchema>(schemas: &mut Schemas) -> RefOr<Schema> {
+    RefOr::T(Schema::Array(Array::new(schema::<T>(schemas))))
+}
+
+/// The `{"message": ...}` most deletes answer with
+fn message(_: &mut Schemas) -> RefOr<Schema> {
+    RefOr::Ref(Ref::from_schema_name("Message"))
+}
+
+fn params<T: IntoParams>() -> Vec<Parameter> {
+    T::into_params(|| Some(ParameterIn::Query))
+}
+
+/// Every route under `/api`, in the order of the modules' routers. The tests compare this list
+/// with the routers and fail on routes missing here or entries without a route.
+pub(crate) const OPERATIONS: &[Operation] = &[
+    op("get", "/api/health", "system", "Liveness check"),
+    op("get", "/api/openapi.json", "system", "This OpenAPI document")
+        .produces(&["application/json"]),
+    op("get", "/api/docs", "system", "Swagger UI for this document")
+        .produces(&["text/html"]),
+
+    op("get", "/api/database-configs", "database-configs", "List database configs")
+        .query(&[params::<database_configs::ListQuery>]).pages(schema::<DatabaseConfig>),
+    op("post", "/api/database-configs", "database-configs", "Create a database config")
+        .body(schema::<CreateDatabaseConfigRequest>).returns(schema::<DatabaseConfig>),
+    op("get", "/api/database-configs/sla", "database-configs", "Backup freshness of every config with an RPO target; `?violated=true` lists only violations")
+        .query(&[params::<database_configs::SlaQuery>]).returns(list::<RpoStatus>),
+    op("get", "/api/database-configs/:id", "database-configs", "Get a database config")
+        .returns(schema::<DatabaseConfig>),
+    op("put", "/api/database-configs/:id", "database-configs", "Update a database config")
+        .body(schema::<UpdateDatabaseConfigRequest>).returns(schema::<DatabaseConfig>),
+    op("delete", "/api/database-configs/:id", "database-configs", "Delete a database config")
+        .returns(message),
+    op("post", "/api/database-configs/:id/test", "database-configs", "Test the connection"),
+    op("get", "/api/database-configs/:id/permissions", "database-configs", "Check the grants mydumper and myloader need"),
+    op("get", "/api/database-configs/:id/databases", "database-configs", "List the databases on the server"),
+    op("get", "/api/database-configs/:id/last-backup", "database-configs", "When the config was last backed up successfully")
+        .returns(schema::<database_configs::LastBackupResponse>),
+
+    op("get", "/api/tasks", "tasks", "List tasks")
+        .query(&[params::<tasks::ListQuery>]).pages(schema::<tasks::TaskWithDatabaseInfo>),
+    op("post", "/api/tasks", "tasks", "Create a task")
+        .body(schema::<CreateTaskRequest>).returns(schema::<tasks::TaskResponse>),
+    op("get", "/api/tasks/calendar.ics", "tasks", "iCalendar feed of upcoming task runs")
+        .query(&[params::<tasks::CalendarQuery>]).produces(&["text/calendar"]),
+    op("get", "/api/tasks/:id", "tasks", "Get a task")
+        .returns(schema::<tasks::TaskResponse>),
+    op("put", "/api/tasks/:id", "tasks", "Update a task")
+        .body(schema::<UpdateTaskRequest>).returns(schema::<tasks::TaskResponse>),
+    op("delete", "/api/tasks/:id", "tasks", "Delete a task")
+        .returns(message),
+    op("post", "/api/tasks/:id/run", "tasks", "Queue a backup of the task now; `?priority=` orders it in the queue")
+        .query(&[params::<tasks::RunTaskQuery>]),
+    op("post", "/api/tasks/:id/dry-run", "tasks", "Show what a run would back up and the mydumper command, without creating a job")
+        .returns(schema::<DryRunReport>),
+    op("post", "/api/tasks/:id/toggle", "tasks", "Activate or deactivate a task"),
+    op("get", "/api/tasks/:id/upcoming", "tasks", "Next planned runs of the task")
+        .query(&[params::<tasks::UpcomingQuery>]).returns(list::<tasks::UpcomingRun>),
+    op("post", "/api/tasks/:id/schedule-once", "tasks", "Run the task once at a given time")
+        .body(schema::<tasks::ScheduleOnceRequest>).returns(schema::<tasks::TaskResponse>),
+    op("delete", "/api/tasks/:id/schedule-once", "tasks", "Cancel a one-off run")
+        .returns(schema::<tasks::TaskResponse>),
+
+    op("get", "/api/jobs", "jobs", "List jobs")
+        .query(&[params::<jobs::ListQuery>]).pages(schema::<jobs::JobWithDatabaseInfo>),
+    op("post", "/api/jobs", "jobs", "Create a job")
+        .body(schema::<CreateJobRequest>).returns(schema::<Job>),
+    op("delete", "/api/jobs", "jobs", "Delete finished jobs by status, date, task or type together with their logs")
+        .query(&[params::<jobs::BulkDeleteQuery>]),
+    op("get", "/api/jobs/active", "jobs", "List pending and running jobs")
+        .returns(list::<Job>),
+    op("get", "/api/jobs/export", "jobs", "All jobs matching the list filters as CSV or JSON (`format=csv|json`)")
+        .query(&[params::<jobs::ListQuery>, params::<ExportQuery>]).produces(&["text/csv", "application/json"]),
+    op("get", "/api/jobs/stats", "jobs", "Success rate, durations, bytes produced and failure reasons over a time window")
+        .query(&[params::<jobs::StatsQuery>]),
+    op("get", "/api/jobs/:id", "jobs", "Get a job")
+        .returns(schema::<Job>),
+    op("delete", "/api/jobs/:id", "jobs", "Delete a job and its logs")
+        .returns(message),
+    op("post", "/api/jobs/:id/cancel", "jobs", "Cancel a pending or running job; `?drop_database=true` drops a partially restored database")
+        .query(&[params::<jobs::CancelQuery>]),
+    op("post", "/api/jobs/:id/retry", "jobs", "Run a failed backup or restore job again")
+        .returns(schema::<Job>),
+    op("get", "/api/jobs/:id/logs", "jobs", "Get the job's log"),
+    op("get", "/api/jobs/:id/logs/tail", "jobs", "Follow the job's log as Server-Sent Events")
+        .query(&[params::<jobs::TailQuery>]).produces(&["text/event-stream"]),
+    op("get", "/api/jobs/:id/artifacts", "jobs", "List the files in the job's tmp and log folders"),
+    op("get", "/api/jobs/:id/progress", "jobs", "Overall progress of the job"),
+    op("get", "/api/jobs/:id/detailed-progress", "jobs", "Per-table progress of a backup")
+        .returns(schema::<DetailedProgress>),
+
+    op("get", "/api/backups", "backups", "List backups")
+        .query(&[params::<backups::ListQuery>]).pages(schema::<backups::BackupWithDatabaseInfo>),
+    op("get", "/api/backups/export", "backups", "All backups matching the list filters as CSV or JSON (`format=csv|json`)")
+        .query(&[params::<backups::ListQuery>, params::<ExportQuery>]).produces(&["text/csv", "application/json"]),
+    op("get", "/api/backups/catalog", "backups", "Signed manifest of every backup for inventory tooling")
+        .document(schema::<CatalogManifest>),
+    op("post", "/api/backups/upload", "backups", "Upload an archive as a multipart form")
+        .upload("multipart/form-data"),
+    op("post", "/api/backups/upload-directory", "backups", "Upload the files of a mydumper directory as a multipart form")
+        .upload("multipart/form-data"),
+    op("post", "/api/backups/import-url", "backups", "Download an archive from a URL and import it")
+        .body(schema::<backups::ImportUrlRequest>),
+    op("post", "/api/backups/register", "backups", "Register a mydumper directory or archive already on the server")
+        .body(schema::<RegisterBackupRequest>),
+    op("post", "/api/backups/uploads", "backups", "Start a resumable upload")
+        .body(schema::<backups::CreateUploadRequest>),
+    op("get", "/api/backups/uploads/:upload_id", "backups", "Bytes received of a resumable upload"),
+    op("patch", "/api/backups/uploads/:upload_id", "backups", "Append a chunk at the `Upload-Offset`")
+        .upload("application/octet-stream"),
+    op("post", "/api/backups/uploads/:upload_id/complete", "backups", "Turn a fully received upload into a backup"),
+    op("post", "/api/backups/point-in-time-restore", "backups", "Restore a database to a point in time from a full backup and archived binlogs")
+        .body(schema::<PointInTimeRestoreRequest>),
+    op("post", "/api/backups/cleanup", "backups", "Delete backups past their task's retention"),
+    op("get", "/api/backups/:id", "backups", "Get a backup")
+        .returns(schema::<Backup>),
+    op("delete", "/api/backups/:id", "backups", "Delete a backup")
+        .returns(message),
+    op("post", "/api/backups/:id/restore", "backups", "Queue a restore of the backup")
+        .body(schema::<RestoreRequest>),
+    op("post", "/api/backups/:id/verify", "backups", "Start a verify job for the archive")
+        .optional_body(schema::<VerifyRequest>),
+    op("get", "/api/backups/:id/verify-remote", "backups", "Result of the last remote verification"),
+    op("post", "/api/backups/:id/verify-remote", "backups", "Check the uploaded copy against the local metadata")
+        .optional_body(schema::<VerifyRemoteRequest>),
+    op("post", "/api/backups/:id/recompress", "backups", "Convert the archive to another compression format")
+        .body(schema::<RecompressRequest>),
+    op("post", "/api/backups/:id/copy", "backups", "Copy the archive to another destination")
+        .body(schema::<CopyBackupRequest>),
+    op("get", "/api/backups/:id/download", "backups", "Download the archive")
+        .produces(&["application/octet-stream"]),
+    op("get", "/api/backups/:id/contents", "backups", "Files, databases and tables in the archive"),
+    op("post", "/api/backups/:id/metadata", "backups", "Update the backup's metadata")
+        .body(schema::<backups::UpdateMetadataRequest>),
+
+    op("get", "/api/logs", "logs", "List log entries")
+        .query(&[params::<logs::ListLogsQuery>]).pages(schema::<Log>),
+    op("delete", "/api/logs", "logs", "Delete log entries created before a date, optionally by type, level or entity")
+        .query(&[params::<logs::PurgeLogsQuery>]),
+    op("get", "/api/logs/cleanup", "logs", "Delete log entries older than `days` (14 by default)")
+        .query(&[params::<logs::CleanupQuery>]),
+
+    op("get", "/api/system/info", "system", "Host and disk information"),
+    op("get", "/api/system/version", "system", "Version information"),
+    op("get", "/api/system/health", "system", "Health of the backend's dependencies"),
+    op("get", "/api/system/status", "system", "OK/WARNING/CRITICAL summary for monitoring checks")
+        .query(&[params::<MonitoringQuery>]).produces(&["text/plain", "application/json"]),
+    op("get", "/api/system/worker", "system", "Task worker status"),
+    op("get", "/api/system/mydumper/version", "system", "Installed mydumper version"),
+    op("get", "/api/system/myloader/version", "system", "Installed myloader version"),
+
+    op("get", "/api/dashboard/stats", "dashboard", "Dashboard counters"),
+    op("get", "/api/dashboard/recent-backups", "dashboard", "Most recent backups"),
+    op("get", "/api/dashboard/next-tasks", "dashboard", "Next scheduled task runs"),
+    op("get", "/api/dashboard/overdue", "dashboard", "Tasks that missed a run or have a stale backup")
+        .query(&[params::<OverdueQuery>]),
+
+    op("get", "/api/worker/status", "worker", "Task worker status")
+        .returns(schema::<WorkerStatusResponse>),
+    op("post", "/api/worker/start", "worker", "Start the task worker"),
+
+    op("get", "/api/events", "events", "Server-Sent Events stream of all application events")
+        .produces(&["text/event-stream"]),
+
+    op("get", "/api/transfers", "transfers", "Running and recently finished uploads and downloads")
+        .returns(list::<TransferResponse>),
+    op("get", "/api/transfers/:id", "transfers", "Get a transfer")
+        .returns(schema::<TransferResponse>),
+
+    op("get", "/api/ui-settings", "ui-settings", "All settings of a scope")
+        .query(&[params::<ScopeQuery>]),
+    op("get", "/api/ui-settings/:key", "ui-settings", "Get a setting")
+        .query(&[params::<ScopeQuery>]).returns(schema::<UiSettingResponse>),
+    op("put", "/api/ui-settings/:key", "ui-settings", "Create or replace a setting")
+        .query(&[params::<ScopeQuery>]).body(schema::<PutUiSettingRequest>).returns(schema::<UiSettingResponse>),
+    op("delete", "/api/ui-settings/:key", "ui-settings", "Delete a setting")
+        .query(&[params::<ScopeQuery>]).returns(message),
+
+    op("get", "/api/agents", "agents", "List agents")
+        .returns(list::<AgentResponse>),
+    op("post", "/api/agents", "agents", "Register an agent")
+        .body(schema::<CreateAgentRequest>),
+    op("delete", "/api/agents/:id", "agents", "Delete an agent")
+        .returns(message),
+    op("post", "/api/agents/:id/token", "agents", "Issue a new token for an agent"),
+
+    op("get", "/api/storage-destinations", "storage-destinations", "List storage destinations")
+        .returns(list::<StorageDestinationResponse>),
+    op("post", "/api/storage-destinations", "storage-destinations", "Create a storage destination")
+        .body(schema::<CreateStorageDestinationRequest>).returns(schema::<StorageDestination>),
+    op("get", "/api/storage-destinations/:id", "storage-destinations", "Get a storage destination")
+        .returns(schema::<StorageDestinationResponse>),
+    op("put", "/api/storage-destinations/:id", "storage-destinations", "Update a storage destination")
+        .body(schema::<UpdateStorageDestinationRequest>).returns(schema::<StorageDestination>),
+    op("delete", "/api/storage-destinations/:id", "storage-destinations", "Delete a storage destination")
+        .returns(message),
+    op("post", "/api/storage-destinations/:id/test", "storage-destinations", "Write and remove a test file"),
+    op("get", "/api/storage-destinations/:id/usage", "storage-destinations", "Stored bytes against the quota"),
+
+    op("get", "/api/blackout-windows", "blackout-windows", "List blackout windows")
+        .query(&[params::<blackout_windows::ListQuery>]).returns(list::<BlackoutWindow>),
+    op("post", "/api/blackout-windows", "blackout-windows", "Create a blackout window")
+        .body(schema::<CreateBlackoutWindowRequest>).returns(schema::<BlackoutWindow>),
+    op("get", "/api/blackout-windows/:id", "blackout-windows", "Get a blackout window")
+        .returns(schema::<BlackoutWindow>),
+    op("put", "/api/blackout-windows/:id", "blackout-windows", "Update a blackout window")
+        .body(schema::<UpdateBlackoutWindowRequest>).returns(schema::<BlackoutWindow>),
+    op("delete", "/api/blackout-windows/:id", "blackout-windows", "Delete a blackout window")
+        .returns(message),
+
+    op("get", "/api/api-keys", "api-keys", "List API keys")
+        .returns(list::<ApiKey>),
+    op("post", "/api/api-keys", "api-keys", "Create an API key; the first one switches authentication on")
+        .body(schema::<CreateApiKeyRequest>),
+    op("delete", "/api/api-keys/:id", "api-keys", "Revoke an API key")
+        .returns(message),
+
+    op("get", "/api/users", "users", "List users")
+        .returns(list::<User>),
+    op("post", "/api/users", "users", "Create a user; the first one switches authentication on")
+        .body(schema::<CreateUserRequest>).returns(schema::<User>),
+    op("delete", "/api/users/:id", "users", "Delete a user")
+        .returns(message),
+    op("put", "/api/users/:id/password", "users", "Set a user's password")
+        .body(schema::<ChangePasswordRequest>).returns(message),
+
+    op("post", "/api/auth/login", "auth", "Exchange username and password for an access and a refresh token")
+        .body(schema::<LoginRequest>).returns(schema::<TokenPair>),
+    op("post", "/api/auth/refresh", "auth", "Exchange a refresh token for a new token pair")
+        .body(schema::<RefreshRequest>).returns(schema::<TokenPair>),
+    op("get", "/api/auth/me", "auth", "The authenticated user or API key")
+        .returns(schema::<Principal>),
+
+    op("get", "/api/audit", "audit", "Mutating API calls with caller and entity before and after, filtered by actor, entity, action, time and outcome")
+        .query(&[params::<AuditQuery>]).pages(schema::<AuditEntry>),
+
+    op("get", "/api/webhooks", "webhooks", "List webhooks")
+        .returns(list::<Webhook>),
+    op("post", "/api/webhooks", "webhooks", "Create a webhook; returns its signing secret once")
+        .body(schema::<CreateWebhookRequest>),
+    op("get", "/api/webhooks/:id", "webhooks", "Get a webhook")
+        .returns(schema::<Webhook>),
+    op("put", "/api/webhooks/:id", "webhooks", "Update a webhook")
+        .body(schema::<UpdateWebhookRequest>).returns(schema::<Webhook>),
+    op("delete", "/api/webhooks/:id", "webhooks", "Delete a webhook and its deliveries")
+        .returns(message),
+    op("get", "/api/webhooks/:id/deliveries", "webhooks", "Calls made to the webhook with the outcome of their last attempt")
+        .query(&[params::<DeliveriesQuery>]).pages(schema::<WebhookDelivery>),
+
+    op("get", "/api/search", "search", "Find tasks, database configs, jobs and backups matching `q`")
+        .query(&[params::<SearchQuery>]).returns(list::<SearchResult>),
+
+    op("post", "/api/agent/poll", "agent-protocol", "Agent heartbeat, handing out the next dispatched job")
+        .body(schema::<AgentPollRequest>).returns(schema::<AgentPollResponse>),
+    op("post", "/api/agent/jobs/:id/progress", "agent-protocol", "Report a job's progress")
+        .body(schema::<AgentProgressRequest>),
+    op("post", "/api/agent/jobs/:id/fail", "agent-protocol", "Report a job's failure")
+        .body(schema::<AgentFailRequest>),
+    op("put", "/api/agent/jobs/:id/archive", "agent-protocol", "Upload the archive of a finished job")
+        .upload("application/octet-stream"),
+];
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/openapi.json", get(openapi_json))
+        .route("/docs", get(swagger_ui))
+}
+
+async fn openapi_json() -> impl IntoResponse {
+    Json(document())
+}
+
+/// Swagger UI for the spec, loaded from a CDN so the backend ships no assets for it
+async fn swagger_ui() -> impl IntoResponse {
+    Html(r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <title>rDumper API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    window.ui = SwaggerUIBundle({ url: '/api/v1/openapi.json', dom_id: '#swagger-ui' });
+  </script>
+</body>
+</html>"#)
+}
+
+/// OpenAPI 3.1 document of all operations under the versioned prefix, with the schemas of the
+/// request bodies, query parameters and replies the operations declare
+pub(crate) fn document() -> Value {
+    let mut schemas = Schemas::new();
+    let mut paths = Map::new();
+    for operation in OPERATIONS {
+        // axum's `:param` segments become OpenAPI's `{param}`
+        let mut parameters = Vec::new();
+        let path = operation.path.replacen("/api", API_V1, 1).split('/')
+            .map(|segment| match segment.strip_prefix(':') {
+                Some(name) => {
+                    parameters.push(json!({
+                        "name": name,
+                        "in": "path",
+                        "required": true,
+                        "schema": { "type": "string" }
+                    }));
+                    format!("{{{}}}", name)
+                }
+                None => segment.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        parameters.extend(operation.query.iter()
+            .flat_map(|params| params())
+            .map(|parameter| to_json(&parameter)));
+
+        let success = match &operation.reply {
+            Reply::Untyped => json_content(component("Success")),
+            Reply::Data(data) => json_content(enveloped("Success", to_json(&data(&mut schemas)))),
+            Reply::Page(item) => json_content(enveloped("Page", json!({
+                "type": "array",
+                "items": to_json(&item(&mut schemas))
+            }))),
+            Reply::Document(document) => json_content(to_json(&document(&mut schemas))),
+            Reply::Raw(content_types) => content_types.iter()
+                .map(|content_type| (content_type.to_string(), json!({})))
+                .collect(),
+        };
+        let mut spec = json!({
+            "tags": [operation.tag],
+            "summary": operation.summary,
+            "operationId": operation_id(operation),
+            "parameters": parameters,
+            "responses": {
+                "200": { "description": "Success", "content": success },
+                "default": { "description": "Error", "content": json_content(component("Error")) }
+            }
+        });
+        if operation.tag == "agent-protocol" {
+            spec["security"] = json!([{ "agentToken": [] }]);
+        } else if matches!(operation.path, "/api/health" | "/api/openapi.json" | "/api/docs" | "/api/auth/login" | "/api/auth/refresh") {
+            spec["security"] = json!([]);
+        }
+        match &operation.body {
+            Some(Body::Json { schema, required }) => {
+                spec["requestBody"] = json!({
+                    "required": required,
+                    "content": json_content(to_json(&schema(&mut schemas)))
+                });
+            }
+            Some(Body::Raw(content_type)) => {
+                spec["requestBody"] = json!({ "required": true, "content": { *content_type: {} } });
+            }
+            None => {}
+        }
+
+        paths.entry(path)
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .expect("path items are objects")
+            .insert(operation.method.to_string(), spec);
+    }
+
+    // Types reached through several operations are collected once per operation
+    let mut components: BTreeMap<String, Value> = schemas.into_iter()
+        .map(|(name, schema)| (name, to_json(&schema)))
+        .collect();
+    components.insert("Success".to_string(), json!({
+        "type": "object",
+        "properties": {
+            "success": { "type": "boolean" },
+            "data": {},
+            "timestamp": { "type": "string", "format": "date-time" }
+        }
+    }));
+    components.insert("Page".to_string(), json!({
+        "type": "object",
+        "properties": {
+            "success": { "type": "boolean" },
+            "data": { "type": "array" },
+            "pagination": component("Pagination"),
+            "timestamp": { "type": "string", "format": "date-time" }
+        }
+    }));
+    components.insert("Pagination".to_string(), json!({
+        "type": "object",
+        "description": "Page numbers and totals, or `next_cursor` for lists paged with `after_id`",
+        "properties": {
+            "page": { "type": "integer" },
+            "limit": { "type": "integer" },
+            "total": { "type": "integer" },
+            "total_pages": { "type": "integer" },
+            "has_next": { "type": "boolean" },
+            "has_prev": { "type": "boolean" },
+            "next_cursor": { "type": ["string", "null"] }
+        }
+    }));
+    components.insert("Message".to_string(), json!({
+        "type": "object",
+        "properties": { "message": { "type": "string" } }
+    }));
+    components.insert("Error".to_string(), json!({
+        "type": "object",
+        "properties": {
+            "error": { "type": "string" },
+            "timestamp": { "type": "string", "format": "date-time" }
+        }
+    }));
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "rDumper API",
+            "description": "Backups and restores of MySQL databases with mydumper and myloader. Every path is also served without `/v1`, as an alias of this version.",
+            "version": env!("CARGO_PKG_VERSION")
+        },
+        "paths": paths,
+        "security": [{ "apiKey": [] }],
+        "components": {
+            "schemas": components,
+            "securitySchemes": {
+                "apiKey": { "type": "http", "scheme": "bearer" },
+                "agentToken": { "type": "http", "scheme": "bearer" }
+            }
+        }
+    })
+}
+
+fn to_json(value: &impl serde::Serialize) -> Value {
+    serde_json::to_value(value).expect("OpenAPI objects serialize to JSON")
+}
+
+fn component(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn json_content(schema: Value) -> Value {
+    json!({ "application/json": { "schema": schema } })
+}
+
+/// `data` inside one of the response envelopes
+fn enveloped(envelope: &str, data: Value) -> Value {
+    json!({
+        "allOf": [
+            component(envelope),
+            { "type": "object", "properties": { "data": data } }
+        ]
+    })
+}
+
+/// A unique camelCase id from method and path, e.g. `getJobsIdLogsTail`
+fn operation_id(operation: &Operation) -> String {
+    let mut id = operation.method.to_string();
+    for word in operation.path.trim_start_matches("/api/").split(['/', '-', '.', '_', ':']) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            id.push(first.to_ascii_uppercase());
+            id.extend(chars);
+        }
+    }
+    id
+}
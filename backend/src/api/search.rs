@@ -0,0 +1,135 @@
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::models::Job;
+use crate::state::AppState;
+use super::{ApiError, ApiResult, success_response};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Deserialize, IntoParams)]
+pub struct SearchQuery {
+    q: Option<String>,
+    /// Results per kind, 10 by default
+    limit: Option<u32>,
+}
+
+/// One hit, tagged with what it is so a client can link and label it
+#[derive(Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum SearchResult {
+    Task {
+        id: String,
+        name: String,
+        database_name: String,
+        is_active: bool,
+    },
+    DatabaseConfig {
+        id: String,
+        name: String,
+        host: String,
+        database_name: String,
+    },
+    Job {
+        id: String,
+        job_type: String,
+        status: String,
+        used_database: Option<String>,
+        error_message: Option<String>,
+        created_at: DateTime<Utc>,
+    },
+    Backup {
+        id: String,
+        database_name: String,
+        backup_type: String,
+        file_size: i64,
+        created_at: String,
+    },
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(search))
+        .with_state(state)
+}
+
+/// `q` for LIKE patterns, with its wildcards matching only themselves
+fn escape_like(q: &str) -> String {
+    q.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Tasks and database configs by name and database, jobs by id and error text and backups by
+/// database name, for the frontend's quick search
+async fn search(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SearchQuery>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+    let q = query.q.as_deref().map(str::trim).unwrap_or_default();
+    if q.is_empty() {
+        return Err(ApiError::BadRequest("Search term q is required".to_string()));
+    }
+    let limit = query.limit.unwrap_or(10).clamp(1, 50);
+    let escaped = escape_like(q);
+    let pattern = format!("%{}%", escaped);
+    let id_prefix = format!("{}%", escaped);
+
+    let mut results = Vec::new();
+
+    let tasks: Vec<(String, String, String, bool)> = sqlx::query_as(
+        "SELECT id, name, database_name, is_active FROM tasks WHERE name LIKE ?1 ESCAPE '\\' OR database_name LIKE ?1 ESCAPE '\\' ORDER BY name COLLATE NOCASE LIMIT ?2"
+    )
+    .bind(&pattern)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+    results.extend(tasks.into_iter().map(|(id, name, database_name, is_active)| SearchResult::Task { id, name, database_name, is_active }));
+
+    let configs: Vec<(String, String, String, String)> = sqlx::query_as(
+        "SELECT id, name, host, database_name FROM database_configs WHERE name LIKE ?1 ESCAPE '\\' OR host LIKE ?1 ESCAPE '\\' OR database_name LIKE ?1 ESCAPE '\\' ORDER BY name COLLATE NOCASE LIMIT ?2"
+    )
+    .bind(&pattern)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+    results.extend(configs.into_iter().map(|(id, name, host, database_name)| SearchResult::DatabaseConfig { id, name, host, database_name }));
+
+    // Ids match from the start, so a shortened id as shown in the UI finds its job
+    let jobs: Vec<Job> = sqlx::query_as(
+        "SELECT * FROM jobs WHERE id LIKE ?1 ESCAPE '\\' OR error_message LIKE ?2 ESCAPE '\\' ORDER BY created_at DESC LIMIT ?3"
+    )
+    .bind(&id_prefix)
+    .bind(&pattern)
+    .bind(limit as i64)
+    .fetch_all(pool)
+    .await?;
+    results.extend(jobs.into_iter().map(|job| SearchResult::Job {
+        id: job.id,
+        job_type: job.job_type,
+        status: job.status,
+        used_database: job.used_database,
+        error_message: job.error_message,
+        created_at: job.created_at,
+    }));
+
+    let needle = q.to_lowercase();
+    let backups = state.backup_service.scan_backups().await
+        .map_err(|e| ApiError::InternalError(format!("Failed to scan backups: {}", e)))?;
+    results.extend(backups.into_iter()
+        .filter(|b| b.database_name.to_lowercase().contains(&needle))
+        .take(limit as usize)
+        .map(|b| SearchResult::Backup {
+            id: b.id,
+            database_name: b.database_name,
+            backup_type: b.backup_type,
+            file_size: b.file_size,
+            created_at: b.created_at,
+        }));
+
+    Ok(success_response(results))
+}
@@ -0,0 +1,217 @@
+use axum::{
+    extract::{Path, State},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::models::{StorageDestination, StorageTarget, CreateStorageDestinationRequest, UpdateStorageDestinationRequest, LogLevel};
+use crate::services::{demo, remote_storage};
+use crate::state::AppState;
+use super::{ApiError, ApiResult, success_response};
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub(crate) struct StorageDestinationResponse {
+    #[serde(flatten)]
+    destination: StorageDestination,
+    task_count: i64,
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(list_destinations).post(create_destination))
+        .route("/:id", get(get_destination).put(update_destination).delete(delete_destination))
+        .route("/:id/test", post(test_destination))
+        .route("/:id/usage", get(get_usage))
+        .with_state(state)
+}
+
+async fn list_destinations(
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let destinations: Vec<StorageDestination> = sqlx::query_as("SELECT * FROM storage_destinations ORDER BY name")
+        .fetch_all(&state.pool)
+        .await?;
+
+    let mut response = Vec::with_capacity(destinations.len());
+    for destination in destinations {
+        let task_count = count_tasks(&state.pool, &destination.id).await?;
+        response.push(StorageDestinationResponse { destination, task_count });
+    }
+    Ok(success_response(response))
+}
+
+async fn get_destination(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let destination = find_destination(&state.pool, &id).await?;
+    let task_count = count_tasks(&state.pool, &destination.id).await?;
+    Ok(success_response(StorageDestinationResponse { destination, task_count }))
+}
+
+async fn create_destination(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreateStorageDestinationRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let destination = StorageDestination::new(req);
+    validate(&state.pool, &destination).await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO storage_destinations (id, name, target, quota_bytes, connection_status, last_tested, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&destination.id)
+    .bind(&destination.name)
+    .bind(&destination.target)
+    .bind(destination.quota_bytes)
+    .bind(&destination.connection_status)
+    .bind(destination.last_tested)
+    .bind(destination.created_at)
+    .bind(destination.updated_at)
+    .execute(&state.pool)
+    .await?;
+
+    let _ = state.logging_service.log_system(&format!("Storage destination '{}' created", destination.name), LogLevel::Info).await;
+    Ok(success_response(destination))
+}
+
+async fn update_destination(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateStorageDestinationRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let mut destination = find_destination(&state.pool, &id).await?;
+    destination.update(req);
+    validate(&state.pool, &destination).await?;
+
+    sqlx::query(
+        r#"
+        UPDATE storage_destinations
+        SET name = ?, target = ?, quota_bytes = ?, connection_status = ?, last_tested = ?, updated_at = ?
+        WHERE id = ?
+        "#
+    )
+    .bind(&destination.name)
+    .bind(&destination.target)
+    .bind(destination.quota_bytes)
+    .bind(&destination.connection_status)
+    .bind(destination.last_tested)
+    .bind(destination.updated_at)
+    .bind(&destination.id)
+    .execute(&state.pool)
+    .await?;
+
+    Ok(success_response(destination))
+}
+
+async fn delete_destination(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let task_count = count_tasks(&state.pool, &id).await?;
+    if task_count > 0 {
+        return Err(ApiError::BadRequest(format!("Storage destination is still used by {} task(s), change them first", task_count)));
+    }
+
+    let result = sqlx::query("DELETE FROM storage_destinations WHERE id = ?")
+        .bind(&id)
+        .execute(&state.pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("Storage destination not found".to_string()));
+    }
+
+    Ok(success_response(serde_json::json!({"message": "Storage destination deleted successfully"})))
+}
+
+/// Write a small file to the destination and remove it again
+async fn test_destination(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let mut destination = find_destination(&state.pool, &id).await?;
+
+    let test_result = if demo::is_enabled() {
+        Ok(())
+    } else {
+        remote_storage::test(&destination.target).await
+    };
+    destination.mark_connection_tested(test_result.is_ok());
+
+    sqlx::query("UPDATE storage_destinations SET connection_status = ?, last_tested = ?, updated_at = ? WHERE id = ?")
+        .bind(&destination.connection_status)
+        .bind(destination.last_tested)
+        .bind(destination.updated_at)
+        .bind(&destination.id)
+        .execute(&state.pool)
+        .await?;
+
+    match test_result {
+        Ok(()) => Ok(success_response(serde_json::json!({
+            "success": true,
+            "message": format!("Upload to {} successful", destination.target.0),
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        }))),
+        // The reason is what the user needs to fix the settings
+        Err(e) => Err(ApiError::BadRequest(format!("Storage destination test failed: {}", e))),
+    }
+}
+
+/// How much is stored at the destination, against its quota
+async fn get_usage(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let destination = find_destination(&state.pool, &id).await?;
+    let used_bytes = if demo::is_enabled() {
+        0
+    } else {
+        remote_storage::usage_of(&destination.target).await
+            .map_err(|e| ApiError::BadRequest(format!("Failed to list storage destination: {}", e)))?
+    };
+
+    Ok(success_response(serde_json::json!({
+        "used_bytes": used_bytes,
+        "quota_bytes": destination.quota_bytes
+    })))
+}
+
+async fn find_destination(pool: &sqlx::SqlitePool, id: &str) -> Result<StorageDestination, ApiError> {
+    sqlx::query_as("SELECT * FROM storage_destinations WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Storage destination not found".to_string()))
+}
+
+async fn count_tasks(pool: &sqlx::SqlitePool, destination_id: &str) -> Result<i64, ApiError> {
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM tasks WHERE storage_destination_id = ?")
+        .bind(destination_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(count)
+}
+
+/// Reject unnamed destinations, duplicate names and incomplete target settings
+async fn validate(pool: &sqlx::SqlitePool, destination: &StorageDestination) -> Result<(), ApiError> {
+    if destination.name.trim().is_empty() {
+        return Err(ApiError::BadRequest("Storage destination name is required".to_string()));
+    }
+    let existing: Option<(String,)> = sqlx::query_as("SELECT id FROM storage_destinations WHERE name = ? AND id != ?")
+        .bind(&destination.name)
+        .bind(&destination.id)
+        .fetch_optional(pool)
+        .await?;
+    if existing.is_some() {
+        return Err(ApiError::BadRequest("Storage destination with this name already exists".to_string()));
+    }
+    if destination.target.0 == StorageTarget::Local {
+        return Err(ApiError::BadRequest("A storage destination needs a remote target".to_string()));
+    }
+    remote_storage::validate(&destination.target).map_err(|e| ApiError::BadRequest(e.to_string()))
+}
@@ -1,23 +1,29 @@
 use axum::{
-    extract::State,
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
     routing::get,
-    Router,
+    Json, Router,
 };
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::{process::Command, sync::Arc};
-use crate::services::TaskWorker;
+use crate::services::demo;
+use crate::state::AppState;
 
 use super::{ApiResult, success_response};
+use utoipa::IntoParams;
 
-pub fn routes(worker: Arc<TaskWorker>) -> Router {
+pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/info", get(get_system_info))
         .route("/version", get(get_version_info))
         .route("/health", get(get_health_status))
+        .route("/status", get(get_monitoring_status))
         .route("/worker", get(get_worker_status))
         .route("/mydumper/version", get(get_mydumper_version))
         .route("/myloader/version", get(get_myloader_version))
-        .with_state(worker)
+        .with_state(state)
 }
 
 async fn get_system_info() -> ApiResult<impl axum::response::IntoResponse> {
@@ -45,6 +51,7 @@ async fn get_version_info() -> ApiResult<impl axum::response::IntoResponse> {
         "git_commit": git_commit,
         "build_date": build_date,
         "rust_version": get_rust_version(),
+        "demo_mode": demo::is_enabled(),
         "timestamp": chrono::Utc::now().to_rfc3339()
     })))
 }
@@ -54,7 +61,8 @@ async fn get_health_status() -> ApiResult<impl axum::response::IntoResponse> {
     let myloader_available = check_myloader_available();
     let disk_space = get_disk_space();
 
-    let overall_status = if mydumper_available && myloader_available {
+    // Demo mode simulates both tools, so missing binaries don't degrade it
+    let overall_status = if demo::is_enabled() || (mydumper_available && myloader_available) {
         "healthy"
     } else {
         "degraded"
@@ -71,9 +79,203 @@ async fn get_health_status() -> ApiResult<impl axum::response::IntoResponse> {
     })))
 }
 
+/// Monitoring check level, ordered by severity (Nagios plugin semantics)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+enum CheckLevel {
+    Ok,
+    Warning,
+    Critical,
+}
+
+impl CheckLevel {
+    /// Nagios plugin exit code
+    fn code(self) -> u8 {
+        self as u8
+    }
+
+    fn from_thresholds(value: u64, warn: u64, crit: u64) -> Self {
+        if value >= crit {
+            Self::Critical
+        } else if value >= warn {
+            Self::Warning
+        } else {
+            Self::Ok
+        }
+    }
+}
+
+impl std::fmt::Display for CheckLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ok => write!(f, "OK"),
+            Self::Warning => write!(f, "WARNING"),
+            Self::Critical => write!(f, "CRITICAL"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct StatusCheck {
+    name: &'static str,
+    status: CheckLevel,
+    value: Option<u64>,
+    message: String,
+    /// Nagios performance data, e.g. `failed_jobs=0;1;3`
+    #[serde(skip)]
+    perfdata: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub(crate) struct MonitoringQuery {
+    /// `text` (default, Nagios plugin output) or `json`
+    format: Option<String>,
+    /// Look-back window for failed jobs
+    hours: Option<i64>,
+    failed_warn: Option<u64>,
+    failed_crit: Option<u64>,
+    disk_warn: Option<u64>,
+    disk_crit: Option<u64>,
+}
+
+/// Overall OK/WARNING/CRITICAL summary for classic monitoring checks (Nagios, Zabbix, ...).
+///
+/// Covers failed jobs, overdue tasks, RPO violations, backup disk usage and the task worker.
+///
+/// Plain text follows the Nagios plugin format (`STATUS - summary | perfdata`) and
+/// CRITICAL answers with 503 so simple HTTP checks alert without parsing the body.
+async fn get_monitoring_status(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<MonitoringQuery>,
+) -> ApiResult<Response> {
+    let hours = query.hours.unwrap_or(24).max(1);
+    let failed_warn = query.failed_warn.unwrap_or(1);
+    let failed_crit = query.failed_crit.unwrap_or(3).max(failed_warn);
+    let disk_warn = query.disk_warn.unwrap_or(85);
+    let disk_crit = query.disk_crit.unwrap_or(95).max(disk_warn);
+    let now = chrono::Utc::now();
+
+    let mut checks = Vec::new();
+
+    let (failed_jobs,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM jobs WHERE status = 'failed' AND created_at >= ?"
+    )
+    .bind(now - chrono::Duration::hours(hours))
+    .fetch_one(&state.pool)
+    .await?;
+    let failed_jobs = failed_jobs as u64;
+    checks.push(StatusCheck {
+        name: "failed_jobs",
+        status: CheckLevel::from_thresholds(failed_jobs, failed_warn, failed_crit),
+        value: Some(failed_jobs),
+        message: format!("{} failed jobs in the last {}h", failed_jobs, hours),
+        perfdata: Some(format!("failed_jobs={};{};{};0", failed_jobs, failed_warn, failed_crit)),
+    });
+
+    let overdue_tasks = crate::services::overdue::find_overdue_tasks(
+        &state.pool,
+        chrono::Duration::minutes(crate::services::overdue::DEFAULT_GRACE_MINUTES),
+    ).await?;
+    let overdue_names: Vec<String> = overdue_tasks.into_iter().map(|task| task.name).collect();
+    checks.push(StatusCheck {
+        name: "overdue_backups",
+        status: if overdue_names.is_empty() { CheckLevel::Ok } else { CheckLevel::Critical },
+        value: Some(overdue_names.len() as u64),
+        message: if overdue_names.is_empty() {
+            "no overdue backups".to_string()
+        } else {
+            format!("{} overdue backups ({})", overdue_names.len(), overdue_names.join(", "))
+        },
+        perfdata: Some(format!("overdue_backups={};;1;0", overdue_names.len())),
+    });
+
+    checks.push(match get_disk_usage_percent(&state.config.backup_dir) {
+        Some(used) => StatusCheck {
+            name: "backup_disk",
+            status: CheckLevel::from_thresholds(used, disk_warn, disk_crit),
+            value: Some(used),
+            message: format!("backup disk {}% used", used),
+            perfdata: Some(format!("backup_disk={}%;{};{};0;100", used, disk_warn, disk_crit)),
+        },
+        None => StatusCheck {
+            name: "backup_disk",
+            status: CheckLevel::Warning,
+            value: None,
+            message: "backup disk usage unknown".to_string(),
+            perfdata: None,
+        },
+    });
+
+    let rpo_violations: Vec<String> = crate::services::sla::evaluate(&state.pool, &state.backup_service).await?
+        .into_iter()
+        .filter(|status| status.violated)
+        .map(|status| status.name)
+        .collect();
+    checks.push(StatusCheck {
+        name: "rpo",
+        status: if rpo_violations.is_empty() { CheckLevel::Ok } else { CheckLevel::Critical },
+        value: Some(rpo_violations.len() as u64),
+        message: if rpo_violations.is_empty() {
+            "all RPO targets met".to_string()
+        } else {
+            format!("RPO violated for {} ({})", rpo_violations.len(), rpo_violations.join(", "))
+        },
+        perfdata: Some(format!("rpo_violations={};;1;0", rpo_violations.len())),
+    });
+
+    let worker_stale = state.worker.get_status().last_tick
+        .is_none_or(|last_tick| (now - last_tick).num_seconds() > 60);
+    checks.push(StatusCheck {
+        name: "worker",
+        status: if worker_stale { CheckLevel::Critical } else { CheckLevel::Ok },
+        value: None,
+        message: if worker_stale { "task worker stale".to_string() } else { "task worker running".to_string() },
+        perfdata: None,
+    });
+
+    let overall = checks.iter().map(|c| c.status).max().unwrap_or(CheckLevel::Ok);
+    // Lead with the problems, like most Nagios plugins do
+    let summary = {
+        let problems: Vec<&str> = checks.iter()
+            .filter(|c| c.status != CheckLevel::Ok)
+            .map(|c| c.message.as_str())
+            .collect();
+        if problems.is_empty() {
+            checks.iter().map(|c| c.message.as_str()).collect::<Vec<_>>().join(", ")
+        } else {
+            problems.join(", ")
+        }
+    };
+    let http_status = if overall == CheckLevel::Critical {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+
+    if query.format.as_deref() == Some("json") {
+        return Ok((http_status, Json(json!({
+            "status": overall,
+            "code": overall.code(),
+            "summary": summary,
+            "checks": checks,
+            "timestamp": now.to_rfc3339()
+        }))).into_response());
+    }
+
+    let perfdata: Vec<&str> = checks.iter().filter_map(|c| c.perfdata.as_deref()).collect();
+    let mut body = format!("{} - {} | {}\n", overall, summary, perfdata.join(" "));
+    for check in &checks {
+        body.push_str(&format!("{}: {} - {}\n", check.name, check.status, check.message));
+    }
+
+    Ok((http_status, [(header::CONTENT_TYPE, "text/plain; charset=utf-8")], body).into_response())
+}
+
 async fn get_worker_status(
-    State(worker): State<Arc<TaskWorker>>,
+    State(state): State<Arc<AppState>>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let worker = &state.worker;
+
     let status = worker.get_status();
     let now = chrono::Utc::now();
     
@@ -296,4 +498,19 @@ fn get_disk_space() -> serde_json::Value {
         }
         _ => json!({})
     }
-}
\ No newline at end of file
+}
+
+/// Used capacity of the filesystem holding `path`, in percent
+fn get_disk_usage_percent(path: &str) -> Option<u64> {
+    let output = Command::new("df").args(["-P", path]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let content = String::from_utf8_lossy(&output.stdout);
+    let line = content.lines().nth(1)?;
+    line.split_whitespace()
+        .nth(4)?
+        .trim_end_matches('%')
+        .parse()
+        .ok()
+}
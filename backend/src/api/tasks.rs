@@ -1,137 +1,300 @@
 use axum::{
     extract::{Path, Query, State},
+    http::header,
+    response::IntoResponse,
     routing::{get, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
-use sqlx::{SqlitePool, Row};
-
-use crate::models::{Task, CreateTaskRequest, UpdateTaskRequest};
-use super::{ApiError, ApiResult, success_response, paginated_response};
-
-#[derive(Debug, Serialize)]
+use sqlx::{QueryBuilder, Row, Sqlite};
+use std::sync::Arc;
+
+use crate::models::{Task, CreateTaskRequest, UpdateTaskRequest, LocalRunTimes, LogLevel};
+use crate::services::{blackout, encryption};
+use crate::services::table_filter::TableFilter;
+use crate::services::calendar::{self, CalendarEvent};
+use crate::state::AppState;
+use super::{ApiError, ApiResult, success_response, paginated_response, cursor_response};
+use super::list_params::{self, Cursor, SortOrder};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct TaskWithDatabaseInfo {
     #[serde(flatten)]
     pub task: Task,
+    #[serde(flatten)]
+    pub local_times: LocalRunTimes,
     pub db_config_name: Option<String>,
     pub db_config_host: Option<String>,
     pub db_config_database_name: Option<String>,
 }
 
-#[derive(Deserialize)]
+/// A single task with its run times also given in the task's timezone
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TaskResponse {
+    #[serde(flatten)]
+    pub task: Task,
+    #[serde(flatten)]
+    pub local_times: LocalRunTimes,
+}
+
+impl From<Task> for TaskResponse {
+    fn from(task: Task) -> Self {
+        Self { local_times: task.local_run_times(), task }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
 pub struct ListQuery {
     page: Option<u32>,
     limit: Option<u32>,
     database_config_id: Option<String>,
     is_active: Option<bool>,
+    /// Comma separated `active`, `inactive` and `paused` (deactivated after failing too often)
+    status: Option<String>,
+    /// Tasks created at or after this time
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    /// Tasks created before this time
+    until: Option<chrono::DateTime<chrono::Utc>>,
+    sort_by: Option<String>,
+    #[serde(default)]
+    #[param(inline)]
+    order: SortOrder,
+    /// Cursor pagination: tasks after this one, newest first
+    after_id: Option<String>,
+    /// Cursor pagination: tasks created before this time, or the time of `after_id`
+    created_before: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Values of `sort_by` for tasks and what they sort by
+const TASK_SORT_COLUMNS: &[(&str, &str)] = &[
+    ("created_at", "t.created_at"),
+    ("updated_at", "t.updated_at"),
+    ("name", "t.name COLLATE NOCASE"),
+    ("last_run", "t.last_run"),
+    ("next_run", "t.next_run"),
+    ("consecutive_failures", "t.consecutive_failures"),
+    ("database", "dc.name COLLATE NOCASE"),
+];
+
+#[derive(Deserialize, ToSchema)]
+pub struct ScheduleOnceRequest {
+    run_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct UpcomingQuery {
+    count: Option<usize>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct RunTaskQuery {
+    /// Queue position of the run, by default ahead of scheduled jobs
+    priority: Option<i32>,
+}
+
+/// A planned run, after blackout windows have been applied
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UpcomingRun {
+    pub run_at: chrono::DateTime<chrono::Utc>,
+    pub run_at_local: String,
+    pub one_off: bool,
+    pub deferred_by: Option<String>, // Name of the blackout window that moved the run
 }
 
-pub fn routes(pool: SqlitePool) -> Router {
+#[derive(Deserialize, IntoParams)]
+pub struct CalendarQuery {
+    days: Option<i64>,
+}
+
+/// Cap on events per task so minute-level schedules don't produce huge feeds
+const MAX_CALENDAR_EVENTS_PER_TASK: usize = 500;
+/// Upper bound for `count` on the upcoming runs preview
+const MAX_UPCOMING_RUNS: usize = 100;
+/// Scheduled runs looked at for the preview, so frequent schedules behind long windows stay cheap
+const MAX_UPCOMING_CANDIDATES: usize = 10_000;
+/// Event length used when a task has no completed backups to estimate from
+const DEFAULT_BACKUP_WINDOW_MINUTES: i64 = 30;
+
+pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/", get(list_tasks).post(create_task))
+        .route("/calendar.ics", get(get_calendar))
         .route("/:id", get(get_task).put(update_task).delete(delete_task))
         .route("/:id/run", post(run_task_now))
+        .route("/:id/dry-run", post(dry_run_task))
         .route("/:id/toggle", post(toggle_task_status))
-        .with_state(pool)
+        .route("/:id/upcoming", get(get_upcoming_runs))
+        .route("/:id/schedule-once", post(schedule_once).delete(cancel_schedule_once))
+        .with_state(state)
+}
+
+fn push_task_filters<'a>(builder: &mut QueryBuilder<'a, Sqlite>, query: &'a ListQuery, statuses: &[&str]) {
+    builder.push(" WHERE 1=1");
+    if let Some(db_config_id) = &query.database_config_id {
+        builder.push(" AND t.database_config_id = ").push_bind(db_config_id);
+    }
+    if let Some(is_active) = query.is_active {
+        builder.push(" AND t.is_active = ").push_bind(is_active);
+    }
+    if !statuses.is_empty() {
+        let conditions: Vec<&str> = statuses.iter()
+            .map(|status| match *status {
+                "active" => "t.is_active = 1",
+                "paused" => "(t.is_active = 0 AND t.auto_paused_at IS NOT NULL)",
+                _ => "(t.is_active = 0 AND t.auto_paused_at IS NULL)",
+            })
+            .collect();
+        builder.push(format!(" AND ({})", conditions.join(" OR ")));
+    }
+    if let Some(since) = query.since {
+        builder.push(" AND t.created_at >= ").push_bind(since);
+    }
+    if let Some(until) = query.until {
+        builder.push(" AND t.created_at < ").push_bind(until);
+    }
 }
 
 async fn list_tasks(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Query(query): Query<ListQuery>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     let page = query.page.unwrap_or(1);
     let limit = query.limit.unwrap_or(10);
     let offset = (page - 1) * limit;
 
-    let mut sql = "SELECT t.*, dc.name as db_config_name, dc.host as db_config_host, dc.database_name as db_config_database_name FROM tasks t LEFT JOIN database_configs dc ON t.database_config_id = dc.id".to_string();
-    let mut count_sql = "SELECT COUNT(*) as count FROM tasks t LEFT JOIN database_configs dc ON t.database_config_id = dc.id".to_string();
-    let mut conditions = Vec::new();
-    
-    if query.database_config_id.is_some() {
-        conditions.push("t.database_config_id = ?");
+    let sort_column = list_params::sort_column(query.sort_by.as_deref(), TASK_SORT_COLUMNS)?;
+    let order = query.order.sql();
+    let statuses = list_params::split_values(query.status.as_deref());
+    if let Some(status) = statuses.iter().find(|status| !matches!(**status, "active" | "inactive" | "paused")) {
+        return Err(ApiError::BadRequest(format!("Unknown task status '{}', use active, inactive or paused", status)));
     }
-    
-    if query.is_active.is_some() {
-        conditions.push("t.is_active = ?");
-    }
-    
-    if !conditions.is_empty() {
-        let where_clause = format!(" WHERE {}", conditions.join(" AND "));
-        sql.push_str(&where_clause);
-        count_sql.push_str(&where_clause);
-    }
-    
-    sql.push_str(&format!(" ORDER BY t.created_at DESC LIMIT {} OFFSET {}", limit, offset));
 
-    let mut query_builder = sqlx::query(&sql);
-    let mut count_query_builder = sqlx::query_as(&count_sql);
-    
-    if let Some(ref db_config_id) = query.database_config_id {
-        query_builder = query_builder.bind(db_config_id);
-        count_query_builder = count_query_builder.bind(db_config_id);
-    }
-    
-    if let Some(is_active) = query.is_active {
-        query_builder = query_builder.bind(is_active);
-        count_query_builder = count_query_builder.bind(is_active);
+    let cursor = Cursor::resolve(pool, "tasks", query.after_id.as_deref(), query.created_before).await?;
+
+    let mut select = QueryBuilder::new("SELECT t.*, dc.name as db_config_name, dc.host as db_config_host, dc.database_name as db_config_database_name FROM tasks t LEFT JOIN database_configs dc ON t.database_config_id = dc.id");
+    push_task_filters(&mut select, &query, &statuses);
+    if let Some(cursor) = &cursor {
+        cursor.check_order(query.sort_by.as_deref(), query.order)?;
+        cursor.push_condition(&mut select, "t.created_at", "t.id");
+        // One more row than asked for tells whether another page follows
+        select.push(" ORDER BY t.created_at DESC, t.id DESC LIMIT ").push_bind(limit as i64 + 1);
+    } else {
+        // The id keeps the order stable between pages when the sort column ties
+        select.push(format!(" ORDER BY {} {}, t.id {} LIMIT ", sort_column, order, order))
+            .push_bind(limit as i64)
+            .push(" OFFSET ")
+            .push_bind(offset as i64);
     }
 
-    let rows = query_builder.fetch_all(&pool).await?;
-    let total: (i64,) = count_query_builder.fetch_one(&pool).await?;
+    let rows = select.build().fetch_all(pool).await?;
 
     let tasks: Vec<TaskWithDatabaseInfo> = rows.into_iter().map(|row| {
+        let task = Task {
+            id: row.get("id"),
+            name: row.get("name"),
+            database_config_id: row.get("database_config_id"),
+            database_name: row.get("database_name"),
+            cron_schedule: row.get("cron_schedule"),
+            compression_type: row.get("compression_type"),
+            cleanup_days: row.get("cleanup_days"),
+            use_non_transactional: row.get("use_non_transactional"),
+            is_active: row.get("is_active"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+            last_run: row.get("last_run"),
+            next_run: row.get("next_run"),
+            pause_after_failures: row.get("pause_after_failures"),
+            consecutive_failures: row.get("consecutive_failures"),
+            auto_paused_at: row.get("auto_paused_at"),
+            verify_interval_days: row.get("verify_interval_days"),
+            storage_destination_id: row.get("storage_destination_id"),
+            keep_local: row.get("keep_local"),
+            bandwidth_limit_kib: row.get("bandwidth_limit_kib"),
+            timezone: row.get("timezone"),
+            retry_max_attempts: row.get("retry_max_attempts"),
+            retry_backoff_seconds: row.get("retry_backoff_seconds"),
+            run_once_at: row.get("run_once_at"),
+            include_tables: row.get("include_tables"),
+            exclude_tables: row.get("exclude_tables"),
+            where_clause: row.get("where_clause"),
+            threads: row.get("threads"),
+            rows_per_chunk: row.get("rows_per_chunk"),
+            chunk_filesize_mb: row.get("chunk_filesize_mb"),
+            long_query_guard_seconds: row.get("long_query_guard_seconds"),
+            binlog_interval_minutes: row.get("binlog_interval_minutes"),
+            encryption_key: row.get("encryption_key"),
+            timeout_minutes: row.get("timeout_minutes"),
+            notify_on: row.get("notify_on"),
+            notify_email: row.get("notify_email"),
+        };
         TaskWithDatabaseInfo {
-            task: Task {
-                id: row.get("id"),
-                name: row.get("name"),
-                database_config_id: row.get("database_config_id"),
-                database_name: row.get("database_name"),
-                cron_schedule: row.get("cron_schedule"),
-                compression_type: row.get("compression_type"),
-                cleanup_days: row.get("cleanup_days"),
-                use_non_transactional: row.get("use_non_transactional"),
-                is_active: row.get("is_active"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-                last_run: row.get("last_run"),
-                next_run: row.get("next_run"),
-            },
+            local_times: task.local_run_times(),
+            task,
             db_config_name: row.get("db_config_name"),
             db_config_host: row.get("db_config_host"),
             db_config_database_name: row.get("db_config_database_name"),
         }
     }).collect();
 
-    Ok(paginated_response(tasks, page, limit, total.0 as u64))
+    if cursor.is_some() {
+        return Ok(cursor_response(tasks, limit, |task| list_params::next_cursor(&task.task.id, task.task.created_at)).into_response());
+    }
+
+    let mut count = QueryBuilder::new("SELECT COUNT(*) as count FROM tasks t LEFT JOIN database_configs dc ON t.database_config_id = dc.id");
+    push_task_filters(&mut count, &query, &statuses);
+    let total: (i64,) = count.build_query_as().fetch_one(pool).await?;
+
+    Ok(paginated_response(tasks, page, limit, total.0 as u64).into_response())
 }
 
 async fn get_task(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     let task: Option<Task> = sqlx::query_as(
         "SELECT * FROM tasks WHERE id = ?"
     )
     .bind(&id)
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await?;
 
     match task {
-        Some(task) => Ok(success_response(task)),
+        Some(task) => Ok(success_response(TaskResponse::from(task))),
         None => Err(ApiError::NotFound("Task not found".to_string())),
     }
 }
 
+/// Reject tasks that upload to a storage destination that doesn't exist
+async fn ensure_destination_exists(pool: &sqlx::SqlitePool, task: &Task) -> Result<(), ApiError> {
+    if let Some(destination_id) = &task.storage_destination_id {
+        let destination: Option<(String,)> = sqlx::query_as("SELECT id FROM storage_destinations WHERE id = ?")
+            .bind(destination_id)
+            .fetch_optional(pool)
+            .await?;
+        if destination.is_none() {
+            return Err(ApiError::BadRequest("Storage destination not found".to_string()));
+        }
+    }
+    Ok(())
+}
+
 async fn create_task(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Json(req): Json<CreateTaskRequest>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     // Validate that database config exists
     let db_config_exists: Option<(String,)> = sqlx::query_as(
         "SELECT id FROM database_configs WHERE id = ?"
     )
     .bind(&req.database_config_id)
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await?;
 
     if db_config_exists.is_none() {
@@ -144,6 +307,15 @@ async fn create_task(
     }
 
     let mut task = Task::new(req);
+    ensure_destination_exists(pool, &task).await?;
+    task.timezone().map_err(ApiError::BadRequest)?;
+    TableFilter::for_task(&task).map_err(ApiError::BadRequest)?;
+    task.validate_where_clause().map_err(ApiError::BadRequest)?;
+    task.validate_threads().map_err(ApiError::BadRequest)?;
+    task.validate_notify_email().map_err(ApiError::BadRequest)?;
+    if let Some(key) = &task.encryption_key {
+        encryption::check_key(key).map_err(ApiError::BadRequest)?;
+    }
     
     // Calculate next run time based on cron schedule
     if let Err(e) = task.update_next_run() {
@@ -152,8 +324,8 @@ async fn create_task(
 
     sqlx::query(
         r#"
-        INSERT INTO tasks (id, name, database_config_id, database_name, cron_schedule, compression_type, cleanup_days, use_non_transactional, is_active, last_run, next_run, created_at, updated_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO tasks (id, name, database_config_id, database_name, cron_schedule, compression_type, cleanup_days, use_non_transactional, is_active, last_run, next_run, pause_after_failures, verify_interval_days, storage_destination_id, keep_local, bandwidth_limit_kib, timezone, retry_max_attempts, retry_backoff_seconds, include_tables, exclude_tables, where_clause, threads, rows_per_chunk, chunk_filesize_mb, long_query_guard_seconds, binlog_interval_minutes, encryption_key, timeout_minutes, notify_on, notify_email, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#
     )
     .bind(&task.id)
@@ -167,24 +339,49 @@ async fn create_task(
     .bind(&task.is_active)
     .bind(&task.last_run)
     .bind(&task.next_run)
+    .bind(task.pause_after_failures)
+    .bind(task.verify_interval_days)
+    .bind(&task.storage_destination_id)
+    .bind(task.keep_local)
+    .bind(task.bandwidth_limit_kib)
+    .bind(&task.timezone)
+    .bind(task.retry_max_attempts)
+    .bind(task.retry_backoff_seconds)
+    .bind(&task.include_tables)
+    .bind(&task.exclude_tables)
+    .bind(&task.where_clause)
+    .bind(task.threads)
+    .bind(task.rows_per_chunk)
+    .bind(task.chunk_filesize_mb)
+    .bind(task.long_query_guard_seconds)
+    .bind(task.binlog_interval_minutes)
+    .bind(&task.encryption_key)
+    .bind(task.timeout_minutes)
+    .bind(&task.notify_on)
+    .bind(&task.notify_email)
     .bind(&task.created_at)
     .bind(&task.updated_at)
-    .execute(&pool)
+    .execute(pool)
     .await?;
 
-    Ok(success_response(task))
+    // Wake the worker so it re-evaluates task schedules immediately
+    state.worker.wake();
+
+    Ok(success_response(TaskResponse::from(task)))
 }
 
 async fn update_task(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
     Json(req): Json<UpdateTaskRequest>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
     let mut task: Task = sqlx::query_as(
         "SELECT * FROM tasks WHERE id = ?"
     )
     .bind(&id)
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await?
     .ok_or_else(|| ApiError::NotFound("Task not found".to_string()))?;
 
@@ -196,6 +393,15 @@ async fn update_task(
     }
 
     task.update(req);
+    ensure_destination_exists(pool, &task).await?;
+    task.timezone().map_err(ApiError::BadRequest)?;
+    TableFilter::for_task(&task).map_err(ApiError::BadRequest)?;
+    task.validate_where_clause().map_err(ApiError::BadRequest)?;
+    task.validate_threads().map_err(ApiError::BadRequest)?;
+    task.validate_notify_email().map_err(ApiError::BadRequest)?;
+    if let Some(key) = &task.encryption_key {
+        encryption::check_key(key).map_err(ApiError::BadRequest)?;
+    }
     
     // Recalculate next run time if cron schedule or active status changed
     if let Err(e) = task.update_next_run() {
@@ -205,7 +411,7 @@ async fn update_task(
     sqlx::query(
         r#"
         UPDATE tasks 
-        SET name = ?, database_name = ?, cron_schedule = ?, compression_type = ?, cleanup_days = ?, use_non_transactional = ?, is_active = ?, next_run = ?, updated_at = ?
+        SET name = ?, database_name = ?, cron_schedule = ?, compression_type = ?, cleanup_days = ?, use_non_transactional = ?, is_active = ?, next_run = ?, pause_after_failures = ?, consecutive_failures = ?, auto_paused_at = ?, verify_interval_days = ?, storage_destination_id = ?, keep_local = ?, bandwidth_limit_kib = ?, timezone = ?, retry_max_attempts = ?, retry_backoff_seconds = ?, include_tables = ?, exclude_tables = ?, where_clause = ?, threads = ?, rows_per_chunk = ?, chunk_filesize_mb = ?, long_query_guard_seconds = ?, binlog_interval_minutes = ?, encryption_key = ?, timeout_minutes = ?, notify_on = ?, notify_email = ?, updated_at = ?
         WHERE id = ?
         "#
     )
@@ -217,41 +423,70 @@ async fn update_task(
     .bind(&task.use_non_transactional)
     .bind(&task.is_active)
     .bind(&task.next_run)
+    .bind(task.pause_after_failures)
+    .bind(task.consecutive_failures)
+    .bind(task.auto_paused_at)
+    .bind(task.verify_interval_days)
+    .bind(&task.storage_destination_id)
+    .bind(task.keep_local)
+    .bind(task.bandwidth_limit_kib)
+    .bind(&task.timezone)
+    .bind(task.retry_max_attempts)
+    .bind(task.retry_backoff_seconds)
+    .bind(&task.include_tables)
+    .bind(&task.exclude_tables)
+    .bind(&task.where_clause)
+    .bind(task.threads)
+    .bind(task.rows_per_chunk)
+    .bind(task.chunk_filesize_mb)
+    .bind(task.long_query_guard_seconds)
+    .bind(task.binlog_interval_minutes)
+    .bind(&task.encryption_key)
+    .bind(task.timeout_minutes)
+    .bind(&task.notify_on)
+    .bind(&task.notify_email)
     .bind(&task.updated_at)
     .bind(&task.id)
-    .execute(&pool)
+    .execute(pool)
     .await?;
 
-    Ok(success_response(task))
+    // Wake the worker so it re-evaluates task schedules immediately
+    state.worker.wake();
+
+    Ok(success_response(TaskResponse::from(task)))
 }
 
 async fn delete_task(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
-    use crate::services::logging::LoggingService;
-    use std::sync::Arc;
-    
+    let pool = &state.pool;
+
     // Get task info before deletion for logging
     let task: Option<Task> = sqlx::query_as("SELECT * FROM tasks WHERE id = ?")
         .bind(&id)
-        .fetch_optional(&pool)
+        .fetch_optional(pool)
         .await?;
     
     let task_name = task.as_ref().map(|t| t.name.clone()).unwrap_or_else(|| "Unknown".to_string());
     
     let result = sqlx::query("DELETE FROM tasks WHERE id = ?")
         .bind(&id)
-        .execute(&pool)
+        .execute(pool)
         .await?;
 
     if result.rows_affected() == 0 {
         return Err(ApiError::NotFound("Task not found".to_string()));
     }
 
+    // Its own blackout windows have nothing left to apply to
+    sqlx::query("DELETE FROM blackout_windows WHERE task_id = ?")
+        .bind(&id)
+        .execute(pool)
+        .await?;
+
     // Log the deletion
-    let logging_service = LoggingService::new(Arc::new(pool.clone()));
-    let _ = logging_service.log_system_with_entity(
+    let _ = state.logging_service.log_system_with_entity(
         "task",
         &id,
         &format!("Task '{}' deleted", task_name),
@@ -262,18 +497,20 @@ async fn delete_task(
 }
 
 async fn run_task_now(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
+    Query(query): Query<RunTaskQuery>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
-    use crate::services::mydumper::MydumperService;
     use crate::models::{CreateJobRequest, JobType};
-    
+
+    let pool = &state.pool;
+
     // Get the task
     let task: Task = sqlx::query_as(
         "SELECT * FROM tasks WHERE id = ?"
     )
     .bind(&id)
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await?
     .ok_or_else(|| ApiError::NotFound("Task not found".to_string()))?;
 
@@ -282,7 +519,7 @@ async fn run_task_now(
         "SELECT * FROM database_configs WHERE id = ?"
     )
     .bind(&task.database_config_id)
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await?
     .ok_or_else(|| ApiError::NotFound("Database configuration not found".to_string()))?;
 
@@ -308,14 +545,15 @@ async fn run_task_now(
         backup_path: None,
     };
     
-    let job = crate::models::Job::new(job_request);
+    let mut job = crate::models::Job::new(job_request);
+    job.priority = query.priority.unwrap_or(crate::models::Job::PRIORITY_MANUAL);
     let job_id = job.id.clone();
 
     // Insert the job into the database
     sqlx::query(
         r#"
-        INSERT INTO jobs (id, task_id, used_database, job_type, status, progress, started_at, completed_at, error_message, log_output, backup_path, created_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO jobs (id, task_id, used_database, job_type, status, progress, started_at, completed_at, error_message, log_output, backup_path, created_at, priority)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#
     )
     .bind(&job.id)
@@ -330,108 +568,346 @@ async fn run_task_now(
     .bind(&job.log_output)
     .bind(&job.backup_path)
     .bind(&job.created_at)
-    .execute(&pool)
+    .bind(job.priority)
+    .execute(pool)
     .await?;
 
-    // Initialize mydumper service
-    let backup_dir = std::env::var("BACKUP_DIR").unwrap_or_else(|_| "data/backups".to_string());
-    let log_dir = std::env::var("LOG_DIR").unwrap_or_else(|_| "data/logs".to_string());
-    let mydumper_service = MydumperService::new(backup_dir, log_dir);
-
-    // Clone job_id for the response before moving it
-    let response_job_id = job_id.clone();
-    
-    // Spawn the backup task asynchronously
-    let task_clone = task.clone();
-    let db_config_clone = db_config.clone();
-    let pool_clone = pool.clone();
-    
-    tokio::spawn(async move {
-        // Determine the database name to use
-        let database_name = match &task_clone.database_name {
-            Some(db_name) => db_name.clone(),
-            None => {
-                // Use the database name from the config, or fail if none specified
-                match db_config_clone.get_database_name() {
-                    Some(db_name) => db_name.clone(),
-                    None => {
-                        tracing::error!("No database name specified for task {} and config has no default database", task_clone.id);
-                        return;
-                    }
-                }
-            }
-        };
+    // The database is only reachable from its agent, which picks the job up on its next poll
+    if let Some(agent_id) = &db_config.agent_id {
+        crate::services::agents::dispatch(pool, &job_id, agent_id).await?;
+        return Ok(success_response(serde_json::json!({
+            "message": "Task dispatched to agent",
+            "job_id": job_id,
+            "task_name": task.name,
+            "database": db_config.name,
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        })));
+    }
 
-        let result = mydumper_service
-            .create_backup_with_progress(&db_config_clone, &database_name, &task_clone, job_id.clone(), &pool_clone)
-            .await;
-
-        match result {
-            Ok(backup_file_path) => {
-                // The backup is already created by the new BackupProcess system
-                // No need to create another backup process
-                tracing::info!("Backup created successfully: {}", backup_file_path);
-
-                // Update job as completed
-                let _ = sqlx::query("UPDATE jobs SET status = ?, completed_at = ?, progress = ?, backup_path = ? WHERE id = ?")
-                    .bind("completed")
-                    .bind(chrono::Utc::now())
-                    .bind(100)
-                    .bind(&backup_file_path)
-                    .bind(&job_id)
-                    .execute(&pool_clone)
-                    .await;
-
-                tracing::info!("Backup task {} completed successfully", task_clone.id);
-            }
-            Err(e) => {
-                tracing::error!("Backup job {} failed: {}", job_id, e);
-                // Update job status to failed
-                let _ = sqlx::query("UPDATE jobs SET status = ?, error_message = ?, completed_at = ? WHERE id = ?")
-                    .bind("failed")
-                    .bind(e.to_string())
-                    .bind(chrono::Utc::now())
-                    .bind(&job_id)
-                    .execute(&pool_clone)
-                    .await;
-            }
-        }
-    });
+    // Manual runs wait for a free slot like any job, but start before queued scheduled ones
+    state.worker.wake();
 
     Ok(success_response(serde_json::json!({
-        "message": "Task execution started successfully",
-        "job_id": response_job_id,
+        "message": "Task execution queued",
+        "job_id": job_id,
         "task_name": task.name,
         "database": db_config.name,
         "timestamp": chrono::Utc::now().to_rfc3339()
     })))
 }
 
+/// Analyze what a run of the task would back up and the mydumper command it would execute,
+/// without creating a job or writing to disk
+async fn dry_run_task(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let pool = &state.pool;
+
+    let task: Task = sqlx::query_as("SELECT * FROM tasks WHERE id = ?")
+        .bind(&id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Task not found".to_string()))?;
+
+    let db_config: crate::models::DatabaseConfig = sqlx::query_as("SELECT * FROM database_configs WHERE id = ?")
+        .bind(&task.database_config_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Database configuration not found".to_string()))?;
+
+    if db_config.agent_id.is_some() {
+        return Err(ApiError::BadRequest("The database is only reachable from its agent, dry runs need a direct connection".to_string()));
+    }
+
+    let database_name = match task.database_name.clone().or_else(|| db_config.get_database_name().cloned()) {
+        Some(database_name) => database_name,
+        None => return Err(ApiError::BadRequest("No database name specified for task and config has no default database".to_string())),
+    };
+
+    let report = state.mydumper_service
+        .dry_run(&db_config, &database_name, &task)
+        .await
+        .map_err(|e| ApiError::BadRequest(format!("Dry run failed: {}", e)))?;
+
+    Ok(success_response(report))
+}
+
 async fn toggle_task_status(
-    State(pool): State<SqlitePool>,
+    State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> ApiResult<impl axum::response::IntoResponse> {
-    let task: Task = sqlx::query_as(
+    let pool = &state.pool;
+
+    let mut task: Task = sqlx::query_as(
         "SELECT * FROM tasks WHERE id = ?"
     )
     .bind(&id)
-    .fetch_optional(&pool)
+    .fetch_optional(pool)
     .await?
     .ok_or_else(|| ApiError::NotFound("Task not found".to_string()))?;
 
     let new_status = !task.is_active;
+    let was_auto_paused = task.auto_paused_at.is_some();
+    task.is_active = new_status;
+    if new_status {
+        // Re-enabling starts a fresh failure streak
+        task.reset_failures();
+    }
+    if let Err(e) = task.update_next_run() {
+        return Err(ApiError::BadRequest(format!("Invalid cron schedule: {}", e)));
+    }
 
     sqlx::query(
-        "UPDATE tasks SET is_active = ?, updated_at = ? WHERE id = ?"
+        "UPDATE tasks SET is_active = ?, next_run = ?, consecutive_failures = ?, auto_paused_at = ?, updated_at = ? WHERE id = ?"
     )
     .bind(new_status)
-    .bind(chrono::Utc::now())
+    .bind(task.next_run)
+    .bind(task.consecutive_failures)
+    .bind(task.auto_paused_at)
+    .bind(task.updated_at)
     .bind(&id)
-    .execute(&pool)
+    .execute(pool)
     .await?;
 
+    let message = match (new_status, was_auto_paused) {
+        (true, true) => "Task re-enabled after being paused for consecutive failures",
+        (true, false) => "Task enabled",
+        (false, _) => "Task disabled",
+    };
+    let _ = state.logging_service.log_task(&id, message, LogLevel::Info).await;
+
+    // Wake the worker so it re-evaluates task schedules immediately
+    state.worker.wake();
+
     Ok(success_response(serde_json::json!({
         "message": format!("Task {} successfully", if new_status { "enabled" } else { "disabled" }),
         "is_active": new_status
     })))
-}
\ No newline at end of file
+}
+
+/// Next planned runs of a task (10 by default) as the worker will start them.
+///
+/// Runs falling into a blackout window move to its end; several runs in one window start only once there.
+async fn get_upcoming_runs(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<UpcomingQuery>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let count = query.count.unwrap_or(10).clamp(1, MAX_UPCOMING_RUNS);
+    let task: Task = sqlx::query_as("SELECT * FROM tasks WHERE id = ?")
+        .bind(&id)
+        .fetch_optional(&state.pool)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Task not found".to_string()))?;
+    let tz = task.timezone().map_err(ApiError::BadRequest)?;
+    let windows = blackout::windows_for_task(&state.pool, &task.id).await?;
+
+    let now = chrono::Utc::now();
+    let mut upcoming: Vec<UpcomingRun> = Vec::with_capacity(count);
+    let mut from = now;
+    let mut candidates = 0;
+    // Runs swallowed by a window don't count, so keep fetching until enough remain
+    while upcoming.len() < count && candidates < MAX_UPCOMING_CANDIDATES {
+        let runs = task.upcoming_runs(from, chrono::DateTime::<chrono::Utc>::MAX_UTC, MAX_UPCOMING_RUNS)
+            .map_err(|e| ApiError::BadRequest(format!("Invalid cron schedule: {}", e)))?;
+        let Some(&last) = runs.last() else {
+            break;
+        };
+        candidates += runs.len();
+        for run in runs {
+            let (run_at, blocked_by) = blackout::next_allowed(&windows, tz, run);
+            if upcoming.last().is_some_and(|previous| previous.run_at >= run_at) {
+                continue;
+            }
+            if upcoming.len() == count {
+                break;
+            }
+            upcoming.push(UpcomingRun {
+                run_at,
+                run_at_local: run_at.with_timezone(&tz).to_rfc3339(),
+                one_off: task.run_once_at == Some(run),
+                deferred_by: blocked_by.map(|window| window.name.clone()),
+            });
+        }
+        from = last + chrono::Duration::seconds(1);
+    }
+
+    Ok(success_response(upcoming))
+}
+
+/// Run the task once at a given time, e.g. right before a maintenance window.
+///
+/// Works for inactive tasks too; the regular schedule is left untouched.
+async fn schedule_once(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<ScheduleOnceRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    if req.run_at <= chrono::Utc::now() {
+        return Err(ApiError::BadRequest("One-off run must be in the future".to_string()));
+    }
+
+    let task = set_run_once_at(&state, &id, Some(req.run_at)).await?;
+    let _ = state.logging_service.log_task(&id, &format!("One-off run scheduled for {}", req.run_at), LogLevel::Info).await;
+    Ok(success_response(TaskResponse::from(task)))
+}
+
+async fn cancel_schedule_once(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let task = set_run_once_at(&state, &id, None).await?;
+    let _ = state.logging_service.log_task(&id, "One-off run cancelled", LogLevel::Info).await;
+    Ok(success_response(TaskResponse::from(task)))
+}
+
+async fn set_run_once_at(
+    state: &AppState,
+    id: &str,
+    run_once_at: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<Task, ApiError> {
+    let mut task: Task = sqlx::query_as("SELECT * FROM tasks WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.pool)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Task not found".to_string()))?;
+
+    task.run_once_at = run_once_at;
+    if let Err(e) = task.update_next_run() {
+        return Err(ApiError::BadRequest(format!("Invalid cron schedule: {}", e)));
+    }
+
+    sqlx::query("UPDATE tasks SET run_once_at = ?, next_run = ?, updated_at = ? WHERE id = ?")
+        .bind(task.run_once_at)
+        .bind(task.next_run)
+        .bind(task.updated_at)
+        .bind(id)
+        .execute(&state.pool)
+        .await?;
+
+    // Wake the worker so it re-evaluates task schedules immediately
+    state.worker.wake();
+    Ok(task)
+}
+
+/// iCalendar feed of upcoming task runs (next 30 days by default) for shared calendars.
+///
+/// Event length is the task's average backup duration, so the feed shows real backup windows.
+async fn get_calendar(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CalendarQuery>,
+) -> ApiResult<impl IntoResponse> {
+    let pool = &state.pool;
+    let days = query.days.unwrap_or(30).clamp(1, 366);
+    let now = chrono::Utc::now();
+    let until = now + chrono::Duration::days(days);
+
+    let rows = sqlx::query(
+        "SELECT t.*, dc.name as db_config_name, dc.database_name as db_config_database_name FROM tasks t LEFT JOIN database_configs dc ON t.database_config_id = dc.id WHERE t.is_active = true OR t.run_once_at IS NOT NULL ORDER BY t.name"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut calendar_events = Vec::new();
+    for row in rows {
+        let task = Task {
+            id: row.get("id"),
+            name: row.get("name"),
+            database_config_id: row.get("database_config_id"),
+            database_name: row.get("database_name"),
+            cron_schedule: row.get("cron_schedule"),
+            compression_type: row.get("compression_type"),
+            cleanup_days: row.get("cleanup_days"),
+            use_non_transactional: row.get("use_non_transactional"),
+            is_active: row.get("is_active"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+            last_run: row.get("last_run"),
+            next_run: row.get("next_run"),
+            pause_after_failures: row.get("pause_after_failures"),
+            consecutive_failures: row.get("consecutive_failures"),
+            auto_paused_at: row.get("auto_paused_at"),
+            verify_interval_days: row.get("verify_interval_days"),
+            storage_destination_id: row.get("storage_destination_id"),
+            keep_local: row.get("keep_local"),
+            bandwidth_limit_kib: row.get("bandwidth_limit_kib"),
+            timezone: row.get("timezone"),
+            retry_max_attempts: row.get("retry_max_attempts"),
+            retry_backoff_seconds: row.get("retry_backoff_seconds"),
+            run_once_at: row.get("run_once_at"),
+            include_tables: row.get("include_tables"),
+            exclude_tables: row.get("exclude_tables"),
+            where_clause: row.get("where_clause"),
+            threads: row.get("threads"),
+            rows_per_chunk: row.get("rows_per_chunk"),
+            chunk_filesize_mb: row.get("chunk_filesize_mb"),
+            long_query_guard_seconds: row.get("long_query_guard_seconds"),
+            binlog_interval_minutes: row.get("binlog_interval_minutes"),
+            encryption_key: row.get("encryption_key"),
+            timeout_minutes: row.get("timeout_minutes"),
+            notify_on: row.get("notify_on"),
+            notify_email: row.get("notify_email"),
+        };
+        let db_config_name: Option<String> = row.get("db_config_name");
+        let database_name = task.database_name.clone()
+            .or_else(|| row.get::<Option<String>, _>("db_config_database_name"))
+            .unwrap_or_default();
+
+        // Unsupported cron patterns simply don't show up in the feed
+        let runs = match task.upcoming_runs(now, until, MAX_CALENDAR_EVENTS_PER_TASK) {
+            Ok(runs) => runs,
+            Err(e) => {
+                tracing::warn!("Skipping task {} in calendar: {}", task.id, e);
+                continue;
+            }
+        };
+        if runs.is_empty() {
+            continue;
+        }
+
+        let duration = estimate_backup_duration(pool, &task.id).await?;
+        let description = format!(
+            "Database: {} ({})\nSchedule: {}\nCompression: {}",
+            database_name,
+            db_config_name.as_deref().unwrap_or("unknown connection"),
+            task.cron_schedule,
+            task.compression_type,
+        );
+
+        calendar_events.extend(runs.into_iter().map(|start| CalendarEvent {
+            uid: format!("{}-{}@rdumper", task.id, start.timestamp()),
+            start,
+            end: start + duration,
+            summary: format!("Backup: {}", task.name),
+            description: description.clone(),
+        }));
+    }
+    calendar_events.sort_by_key(|event| event.start);
+
+    let body = calendar::render_ics("rdumper backups", &calendar_events);
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+            (header::CONTENT_DISPOSITION, "inline; filename=\"rdumper-backups.ics\""),
+        ],
+        body,
+    ))
+}
+
+/// Average duration of the task's last completed backups
+async fn estimate_backup_duration(pool: &sqlx::SqlitePool, task_id: &str) -> Result<chrono::Duration, ApiError> {
+    let jobs: Vec<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
+        "SELECT started_at, completed_at FROM jobs WHERE task_id = ? AND job_type = 'backup' AND status = 'completed' AND started_at IS NOT NULL AND completed_at IS NOT NULL ORDER BY completed_at DESC LIMIT 10"
+    )
+    .bind(task_id)
+    .fetch_all(pool)
+    .await?;
+
+    if jobs.is_empty() {
+        return Ok(chrono::Duration::minutes(DEFAULT_BACKUP_WINDOW_MINUTES));
+    }
+    let total_seconds: i64 = jobs.iter().map(|(started, completed)| (*completed - *started).num_seconds().max(0)).sum();
+    // Keep very short backups visible in calendar views
+    Ok(chrono::Duration::seconds((total_seconds / jobs.len() as i64).max(5 * 60)))
+}
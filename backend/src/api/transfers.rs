@@ -0,0 +1,45 @@
+use axum::{
+    extract::Path,
+    routing::get,
+    Router,
+};
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::services::transfers::{self, Transfer};
+use crate::state::AppState;
+use super::{ApiError, ApiResult, success_response};
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub(crate) struct TransferResponse {
+    #[serde(flatten)]
+    transfer: Transfer,
+    progress: Option<u32>,
+}
+
+impl From<Transfer> for TransferResponse {
+    fn from(transfer: Transfer) -> Self {
+        let progress = transfer.progress();
+        Self { transfer, progress }
+    }
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(list_transfers))
+        .route("/:id", get(get_transfer))
+        .with_state(state)
+}
+
+/// In-flight uploads/downloads plus those that finished in the last few minutes
+async fn list_transfers() -> ApiResult<impl axum::response::IntoResponse> {
+    let list: Vec<TransferResponse> = transfers::list().into_iter().map(TransferResponse::from).collect();
+    Ok(success_response(list))
+}
+
+async fn get_transfer(Path(id): Path<String>) -> ApiResult<impl axum::response::IntoResponse> {
+    let transfer = transfers::get(&id)
+        .ok_or_else(|| ApiError::NotFound("Transfer not found".to_string()))?;
+    Ok(success_response(TransferResponse::from(transfer)))
+}
@@ -0,0 +1,133 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::models::{PutUiSettingRequest, UiSetting, UiSettingResponse, GLOBAL_SCOPE};
+use crate::state::AppState;
+use super::{ApiError, ApiResult, success_response};
+use utoipa::IntoParams;
+
+/// Longest accepted scope or key
+const MAX_NAME_LENGTH: usize = 128;
+/// Largest accepted value, serialized; layouts and filters are small
+const MAX_VALUE_BYTES: usize = 64 * 1024;
+
+#[derive(Deserialize, IntoParams)]
+pub struct ScopeQuery {
+    scope: Option<String>,
+}
+
+impl ScopeQuery {
+    fn scope(&self) -> Result<&str, ApiError> {
+        let scope = self.scope.as_deref().unwrap_or(GLOBAL_SCOPE);
+        validate_name("scope", scope)?;
+        Ok(scope)
+    }
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(list_settings))
+        .route("/:key", get(get_setting).put(put_setting).delete(delete_setting))
+        .with_state(state)
+}
+
+fn validate_name(field: &str, name: &str) -> Result<(), ApiError> {
+    if name.is_empty() || name.len() > MAX_NAME_LENGTH {
+        return Err(ApiError::BadRequest(format!("{} must be 1-{} characters", field, MAX_NAME_LENGTH)));
+    }
+    Ok(())
+}
+
+/// All settings of a scope as a `key -> value` object
+async fn list_settings(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ScopeQuery>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let settings: Vec<UiSetting> = sqlx::query_as("SELECT * FROM ui_settings WHERE scope = ? ORDER BY key")
+        .bind(query.scope()?)
+        .fetch_all(&state.pool)
+        .await?;
+
+    let values: BTreeMap<String, serde_json::Value> = settings.into_iter()
+        .map(UiSettingResponse::from)
+        .map(|setting| (setting.key, setting.value))
+        .collect();
+    Ok(success_response(values))
+}
+
+async fn get_setting(
+    State(state): State<Arc<AppState>>,
+    Path(key): Path<String>,
+    Query(query): Query<ScopeQuery>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let setting: UiSetting = sqlx::query_as("SELECT * FROM ui_settings WHERE scope = ? AND key = ?")
+        .bind(query.scope()?)
+        .bind(&key)
+        .fetch_optional(&state.pool)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("UI setting '{}' not found", key)))?;
+
+    Ok(success_response(UiSettingResponse::from(setting)))
+}
+
+/// Create or replace a setting
+async fn put_setting(
+    State(state): State<Arc<AppState>>,
+    Path(key): Path<String>,
+    Query(query): Query<ScopeQuery>,
+    Json(req): Json<PutUiSettingRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let scope = query.scope()?;
+    validate_name("key", &key)?;
+    let value = serde_json::to_string(&req.value)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid value: {}", e)))?;
+    if value.len() > MAX_VALUE_BYTES {
+        return Err(ApiError::BadRequest(format!("Value exceeds {} bytes", MAX_VALUE_BYTES)));
+    }
+
+    let setting = UiSetting {
+        scope: scope.to_string(),
+        key,
+        value,
+        updated_at: chrono::Utc::now(),
+    };
+    sqlx::query(
+        r#"
+        INSERT INTO ui_settings (scope, key, value, updated_at)
+        VALUES (?, ?, ?, ?)
+        ON CONFLICT (scope, key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at
+        "#
+    )
+    .bind(&setting.scope)
+    .bind(&setting.key)
+    .bind(&setting.value)
+    .bind(setting.updated_at)
+    .execute(&state.pool)
+    .await?;
+
+    Ok(success_response(UiSettingResponse::from(setting)))
+}
+
+async fn delete_setting(
+    State(state): State<Arc<AppState>>,
+    Path(key): Path<String>,
+    Query(query): Query<ScopeQuery>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let result = sqlx::query("DELETE FROM ui_settings WHERE scope = ? AND key = ?")
+        .bind(query.scope()?)
+        .bind(&key)
+        .execute(&state.pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound(format!("UI setting '{}' not found", key)));
+    }
+
+    Ok(success_response(serde_json::json!({ "message": "UI setting deleted successfully" })))
+}
@@ -0,0 +1,106 @@
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get, put},
+    Json, Router,
+};
+use std::sync::Arc;
+
+use crate::models::{ChangePasswordRequest, CreateUserRequest, LogLevel, User};
+use crate::services::auth;
+use crate::state::AppState;
+use super::{ApiError, ApiResult, success_response};
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(list_users).post(create_user))
+        .route("/:id", delete(delete_user))
+        .route("/:id/password", put(change_password))
+        .with_state(state)
+}
+
+fn check_password(password: &str) -> Result<(), ApiError> {
+    if password.chars().count() < auth::MIN_PASSWORD_LEN {
+        return Err(ApiError::BadRequest(format!("Password must have at least {} characters", auth::MIN_PASSWORD_LEN)));
+    }
+    Ok(())
+}
+
+async fn list_users(
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let users: Vec<User> = sqlx::query_as("SELECT * FROM users ORDER BY username")
+        .fetch_all(&state.pool)
+        .await?;
+    Ok(success_response(users))
+}
+
+/// Create a user; the first user switches authentication on for the whole API
+async fn create_user(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreateUserRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let username = req.username.trim().to_string();
+    if username.is_empty() {
+        return Err(ApiError::BadRequest("Username is required".to_string()));
+    }
+    check_password(&req.password)?;
+    let existing: Option<(String,)> = sqlx::query_as("SELECT id FROM users WHERE username = ?")
+        .bind(&username)
+        .fetch_optional(&state.pool)
+        .await?;
+    if existing.is_some() {
+        return Err(ApiError::BadRequest("User with this name already exists".to_string()));
+    }
+
+    let user = User::new(username, auth::hash_password(&req.password)?);
+    sqlx::query("INSERT INTO users (id, username, password_hash, last_login_at, created_at) VALUES (?, ?, ?, ?, ?)")
+        .bind(&user.id)
+        .bind(&user.username)
+        .bind(&user.password_hash)
+        .bind(user.last_login_at)
+        .bind(user.created_at)
+        .execute(&state.pool)
+        .await?;
+
+    let _ = state.logging_service.log_system(&format!("User '{}' created", user.username), LogLevel::Info).await;
+    Ok(success_response(user))
+}
+
+/// Delete a user; their tokens stop working right away
+async fn delete_user(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let username: Option<String> = sqlx::query_scalar("SELECT username FROM users WHERE id = ?")
+        .bind(&id)
+        .fetch_optional(&state.pool)
+        .await?;
+    let username = username.ok_or_else(|| ApiError::NotFound("User not found".to_string()))?;
+
+    sqlx::query("DELETE FROM users WHERE id = ?")
+        .bind(&id)
+        .execute(&state.pool)
+        .await?;
+
+    let _ = state.logging_service.log_system(&format!("User '{}' deleted", username), LogLevel::Info).await;
+    Ok(success_response(serde_json::json!({"message": "User deleted successfully"})))
+}
+
+async fn change_password(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<ChangePasswordRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    check_password(&req.password)?;
+    // Bumping the version signs the user out everywhere
+    let result = sqlx::query("UPDATE users SET password_hash = ?, token_version = token_version + 1 WHERE id = ?")
+        .bind(auth::hash_password(&req.password)?)
+        .bind(&id)
+        .execute(&state.pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(ApiError::NotFound("User not found".to_string()));
+    }
+
+    Ok(success_response(serde_json::json!({"message": "Password changed successfully"})))
+}
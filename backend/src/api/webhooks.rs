@@ -0,0 +1,178 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::models::{CreateWebhookRequest, LogLevel, UpdateWebhookRequest, Webhook, WebhookDelivery, WEBHOOK_EVENTS};
+use crate::services::auth;
+use crate::state::AppState;
+use super::{ApiError, ApiResult, success_response, paginated_response};
+use utoipa::IntoParams;
+
+#[derive(Deserialize, IntoParams)]
+pub struct DeliveriesQuery {
+    page: Option<u32>,
+    limit: Option<u32>,
+    /// `pending`, `delivered` or `failed`
+    status: Option<String>,
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/", get(list_webhooks).post(create_webhook))
+        .route("/:id", get(get_webhook).put(update_webhook).delete(delete_webhook))
+        .route("/:id/deliveries", get(list_deliveries))
+        .with_state(state)
+}
+
+async fn list_webhooks(
+    State(state): State<Arc<AppState>>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let webhooks: Vec<Webhook> = sqlx::query_as("SELECT * FROM webhooks ORDER BY name")
+        .fetch_all(&state.pool)
+        .await?;
+    Ok(success_response(webhooks))
+}
+
+async fn get_webhook(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    Ok(success_response(find_webhook(&state.pool, &id).await?))
+}
+
+/// Create a webhook; its secret is only returned here, generated unless one is given
+async fn create_webhook(
+    State(state): State<Arc<AppState>>,
+    Json(mut req): Json<CreateWebhookRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let secret = req.secret.take().filter(|secret| !secret.is_empty()).unwrap_or_else(auth::generate_secret);
+    let webhook = Webhook::new(req, secret);
+    validate(&webhook)?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO webhooks (id, name, url, secret, events, is_active, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&webhook.id)
+    .bind(&webhook.name)
+    .bind(&webhook.url)
+    .bind(&webhook.secret)
+    .bind(&webhook.events)
+    .bind(webhook.is_active)
+    .bind(webhook.created_at)
+    .bind(webhook.updated_at)
+    .execute(&state.pool)
+    .await?;
+
+    let _ = state.logging_service.log_system(&format!("Webhook '{}' created", webhook.name), LogLevel::Info).await;
+    Ok(success_response(serde_json::json!({
+        "secret": webhook.secret,
+        "webhook": webhook
+    })))
+}
+
+async fn update_webhook(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateWebhookRequest>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let mut webhook = find_webhook(&state.pool, &id).await?;
+    webhook.update(req);
+    validate(&webhook)?;
+
+    sqlx::query(
+        r#"
+        UPDATE webhooks
+        SET name = ?, url = ?, secret = ?, events = ?, is_active = ?, updated_at = ?
+        WHERE id = ?
+        "#
+    )
+    .bind(&webhook.name)
+    .bind(&webhook.url)
+    .bind(&webhook.secret)
+    .bind(&webhook.events)
+    .bind(webhook.is_active)
+    .bind(webhook.updated_at)
+    .bind(&webhook.id)
+    .execute(&state.pool)
+    .await?;
+
+    Ok(success_response(webhook))
+}
+
+/// Delete a webhook together with its deliveries, including those still waiting for a retry
+async fn delete_webhook(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    let webhook = find_webhook(&state.pool, &id).await?;
+    sqlx::query("DELETE FROM webhook_deliveries WHERE webhook_id = ?")
+        .bind(&id)
+        .execute(&state.pool)
+        .await?;
+    sqlx::query("DELETE FROM webhooks WHERE id = ?")
+        .bind(&id)
+        .execute(&state.pool)
+        .await?;
+
+    let _ = state.logging_service.log_system(&format!("Webhook '{}' deleted", webhook.name), LogLevel::Info).await;
+    Ok(success_response(serde_json::json!({"message": "Webhook deleted successfully"})))
+}
+
+/// Calls made to a webhook, newest first, with the response or error of their last attempt
+async fn list_deliveries(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<DeliveriesQuery>,
+) -> ApiResult<impl axum::response::IntoResponse> {
+    find_webhook(&state.pool, &id).await?;
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).clamp(1, 200);
+
+    let deliveries: Vec<WebhookDelivery> = sqlx::query_as(
+        "SELECT * FROM webhook_deliveries WHERE webhook_id = ?1 AND (?2 IS NULL OR status = ?2) ORDER BY created_at DESC, id DESC LIMIT ?3 OFFSET ?4"
+    )
+    .bind(&id)
+    .bind(&query.status)
+    .bind(limit as i64)
+    .bind(((page - 1) * limit) as i64)
+    .fetch_all(&state.pool)
+    .await?;
+    let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM webhook_deliveries WHERE webhook_id = ?1 AND (?2 IS NULL OR status = ?2)")
+        .bind(&id)
+        .bind(&query.status)
+        .fetch_one(&state.pool)
+        .await?;
+
+    Ok(paginated_response(deliveries, page, limit, total as u64))
+}
+
+async fn find_webhook(pool: &sqlx::SqlitePool, id: &str) -> Result<Webhook, ApiError> {
+    sqlx::query_as("SELECT * FROM webhooks WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Webhook not found".to_string()))
+}
+
+/// Reject unnamed webhooks, URLs other than http(s) and unknown events
+fn validate(webhook: &Webhook) -> Result<(), ApiError> {
+    if webhook.name.is_empty() {
+        return Err(ApiError::BadRequest("Webhook name is required".to_string()));
+    }
+    let url = reqwest::Url::parse(&webhook.url)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid webhook URL: {}", e)))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(ApiError::BadRequest("Webhook URL must use http or https".to_string()));
+    }
+    if let Some(event) = webhook.events.split(',').find(|event| !event.is_empty() && !WEBHOOK_EVENTS.contains(event)) {
+        return Err(ApiError::BadRequest(format!("Unknown webhook event '{}', use {}", event, WEBHOOK_EVENTS.join(", "))));
+    }
+    Ok(())
+}
@@ -5,9 +5,10 @@ use axum::{
 };
 use serde::Serialize;
 use std::sync::Arc;
-use crate::services::TaskWorker;
+use crate::state::AppState;
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct WorkerStatusResponse {
     pub is_running: bool,
     pub last_tick: Option<String>,
@@ -43,24 +44,28 @@ impl From<crate::services::WorkerStatus> for WorkerStatusResponse {
     }
 }
 
-pub fn routes(worker: Arc<TaskWorker>) -> Router {
+pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/status", get(get_worker_status))
         .route("/start", post(start_worker))
-        .with_state(worker)
+        .with_state(state)
 }
 
 async fn get_worker_status(
-    State(worker): State<Arc<TaskWorker>>,
+    State(state): State<Arc<AppState>>,
 ) -> crate::api::ApiResult<impl axum::response::IntoResponse> {
+    let worker = &state.worker;
+
     let status = worker.get_status();
     let response = WorkerStatusResponse::from(status);
     Ok(crate::api::success_response(response))
 }
 
 async fn start_worker(
-    State(worker): State<Arc<TaskWorker>>,
+    State(state): State<Arc<AppState>>,
 ) -> crate::api::ApiResult<impl axum::response::IntoResponse> {
+    let worker = &state.worker;
+
     let status = worker.get_status();
     
     if status.is_running {
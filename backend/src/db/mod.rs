@@ -1,16 +1,25 @@
 use anyhow::Result;
+use chrono::Utc;
 use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
-use tracing::info;
+use tracing::{info, warn};
+use uuid::Uuid;
+use crate::models::StorageTarget;
 
 pub async fn create_database_pool(database_url: &str) -> Result<SqlitePool> {
+    // Run migrations on a dedicated connection. Pooled connections that loaded the schema
+    // before an ALTER TABLE would otherwise describe `SELECT *` results with stale columns.
+    let migration_pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect(database_url)
+        .await?;
+    run_migrations(&migration_pool).await?;
+    migration_pool.close().await;
+
     let pool = SqlitePoolOptions::new()
         .max_connections(10)
         .connect(database_url)
         .await?;
 
-    // Run migrations
-    run_migrations(&pool).await?;
-
     Ok(pool)
 }
 
@@ -254,6 +263,16 @@ async fn run_migrations(pool: &SqlitePool) -> Result<()> {
         .await
         .ok(); // Ignore error if column already exists
 
+    // Add compression_progress column to existing jobs table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE jobs ADD COLUMN compression_progress INTEGER
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
     // Add used_database column to existing backups table if it doesn't exist
     sqlx::query(
         r#"
@@ -264,6 +283,680 @@ async fn run_migrations(pool: &SqlitePool) -> Result<()> {
         .await
         .ok(); // Ignore error if column already exists
 
+    // Add rpo_hours column to existing database_configs table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE database_configs ADD COLUMN rpo_hours INTEGER
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add pause_after_failures column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN pause_after_failures INTEGER
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add consecutive_failures column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN consecutive_failures INTEGER NOT NULL DEFAULT 0
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add auto_paused_at column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN auto_paused_at TEXT
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add verify_interval_days column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN verify_interval_days INTEGER
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Create ui_settings table for server-side frontend preferences
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS ui_settings (
+            scope TEXT NOT NULL DEFAULT 'global',
+            key TEXT NOT NULL,
+            value TEXT NOT NULL,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (scope, key)
+        )
+        "#
+    )
+        .execute(pool)
+        .await?;
+
+    // Create agents table for remote instances that execute jobs next to their databases
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS agents (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            token_hash TEXT NOT NULL,
+            version TEXT,
+            last_seen_at TEXT,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#
+    )
+        .execute(pool)
+        .await?;
+
+    // Add agent_id column to existing database_configs table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE database_configs ADD COLUMN agent_id TEXT
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add agent_id column to existing jobs table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE jobs ADD COLUMN agent_id TEXT
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add remote storage columns to existing tasks table if they don't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN remote_storage TEXT
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN keep_local BOOLEAN NOT NULL DEFAULT 1
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add upload_progress column to existing jobs table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE jobs ADD COLUMN upload_progress INTEGER
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Create storage_destinations table for upload targets shared by tasks
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS storage_destinations (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            target TEXT NOT NULL,
+            connection_status TEXT NOT NULL DEFAULT 'untested',
+            last_tested TEXT,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#
+    )
+        .execute(pool)
+        .await?;
+
+    // Add storage_destination_id column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN storage_destination_id TEXT
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add bandwidth_limit_kib column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN bandwidth_limit_kib INTEGER
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add timezone column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN timezone TEXT NOT NULL DEFAULT 'UTC'
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add retry_max_attempts column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN retry_max_attempts INTEGER
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add retry_backoff_seconds column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN retry_backoff_seconds INTEGER
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add attempt column to existing jobs table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE jobs ADD COLUMN attempt INTEGER NOT NULL DEFAULT 1
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add retry_at column to existing jobs table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE jobs ADD COLUMN retry_at TEXT
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add source_backup_id column to existing jobs table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE jobs ADD COLUMN source_backup_id TEXT
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add target_config_id column to existing jobs table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE jobs ADD COLUMN target_config_id TEXT
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add restore_request column to existing jobs table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE jobs ADD COLUMN restore_request TEXT
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add priority column to existing jobs table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE jobs ADD COLUMN priority INTEGER NOT NULL DEFAULT 0
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add retried_from column to existing jobs table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE jobs ADD COLUMN retried_from TEXT
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add backup_size column to existing jobs table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE jobs ADD COLUMN backup_size INTEGER
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add resume_dir column to existing jobs table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE jobs ADD COLUMN resume_dir TEXT
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add cpu_seconds column to existing jobs table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE jobs ADD COLUMN cpu_seconds REAL
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add peak_rss_bytes column to existing jobs table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE jobs ADD COLUMN peak_rss_bytes INTEGER
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add bytes_written column to existing jobs table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE jobs ADD COLUMN bytes_written INTEGER
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add queue_position column to existing jobs table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE jobs ADD COLUMN queue_position INTEGER
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add run_once_at column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN run_once_at TEXT
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add include_tables column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN include_tables TEXT
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add exclude_tables column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN exclude_tables TEXT
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add where_clause column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN where_clause TEXT
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add threads column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN threads INTEGER
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add rows_per_chunk column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN rows_per_chunk INTEGER
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add chunk_filesize_mb column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN chunk_filesize_mb INTEGER
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add long_query_guard_seconds column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN long_query_guard_seconds INTEGER
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add binlog_interval_minutes column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN binlog_interval_minutes INTEGER
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add encryption_key column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN encryption_key TEXT
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add timeout_minutes column to existing tasks table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN timeout_minutes INTEGER
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Add notification columns to existing tasks table if they don't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN notify_on TEXT NOT NULL DEFAULT 'never'
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    sqlx::query(
+        r#"
+        ALTER TABLE tasks ADD COLUMN notify_email TEXT
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Create blackout_windows table for periods in which scheduled backups must not start
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS blackout_windows (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            task_id TEXT,
+            weekdays TEXT NOT NULL DEFAULT '',
+            start_time TEXT NOT NULL,
+            end_time TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE
+        )
+        "#
+    )
+        .execute(pool)
+        .await?;
+
+    // Create job_progress table keeping the last table-level progress of a backup after its logs are gone
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS job_progress (
+            job_id TEXT PRIMARY KEY,
+            snapshot TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (job_id) REFERENCES jobs (id) ON DELETE CASCADE
+        )
+        "#
+    )
+        .execute(pool)
+        .await?;
+
+    // Create api_keys table for clients of the API
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS api_keys (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            key_hash TEXT NOT NULL UNIQUE,
+            prefix TEXT NOT NULL,
+            last_used_at TEXT,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#
+    )
+        .execute(pool)
+        .await?;
+
+    // Create users table for people logging in to the API
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS users (
+            id TEXT PRIMARY KEY,
+            username TEXT NOT NULL UNIQUE,
+            password_hash TEXT NOT NULL,
+            last_login_at TEXT,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )
+        "#
+    )
+        .execute(pool)
+        .await?;
+
+    // Add token_version column to existing users table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE users ADD COLUMN token_version INTEGER NOT NULL DEFAULT 0
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    // Create audit_log table recording every mutating API call
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS audit_log (
+            id TEXT PRIMARY KEY,
+            actor TEXT NOT NULL,
+            actor_kind TEXT NOT NULL,
+            actor_id TEXT,
+            method TEXT NOT NULL,
+            path TEXT NOT NULL,
+            action TEXT NOT NULL,
+            entity_type TEXT NOT NULL,
+            entity_id TEXT,
+            status_code INTEGER NOT NULL,
+            request TEXT,
+            before TEXT,
+            after TEXT,
+            response TEXT,
+            created_at TEXT NOT NULL
+        )
+        "#
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_audit_log_entity ON audit_log (entity_type, entity_id, created_at)")
+        .execute(pool)
+        .await?;
+
+    // Create webhooks table for HTTP callbacks on job and backup events
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS webhooks (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            url TEXT NOT NULL,
+            secret TEXT NOT NULL,
+            events TEXT NOT NULL DEFAULT '',
+            is_active BOOLEAN NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )
+        "#
+    )
+        .execute(pool)
+        .await?;
+
+    // Create webhook_deliveries table, the queue and log of webhook calls
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS webhook_deliveries (
+            id TEXT PRIMARY KEY,
+            webhook_id TEXT NOT NULL,
+            event TEXT NOT NULL,
+            payload TEXT NOT NULL,
+            status TEXT NOT NULL,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            response_status INTEGER,
+            response_body TEXT,
+            error TEXT,
+            next_attempt_at TEXT,
+            created_at TEXT NOT NULL,
+            delivered_at TEXT,
+            FOREIGN KEY (webhook_id) REFERENCES webhooks (id) ON DELETE CASCADE
+        )
+        "#
+    )
+        .execute(pool)
+        .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_due ON webhook_deliveries (status, next_attempt_at)")
+        .execute(pool)
+        .await?;
+
+    // Add quota_bytes column to existing storage_destinations table if it doesn't exist
+    sqlx::query(
+        r#"
+        ALTER TABLE storage_destinations ADD COLUMN quota_bytes INTEGER
+        "#
+    )
+        .execute(pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+    migrate_inline_storage_targets(pool).await?;
+
     info!("Database migrations completed successfully");
     Ok(())
 }
+
+/// Move upload targets that tasks stored inline (`tasks.remote_storage`) into storage destinations
+async fn migrate_inline_storage_targets(pool: &SqlitePool) -> Result<()> {
+    let tasks: Vec<(String, String)> = sqlx::query_as(
+        "SELECT id, remote_storage FROM tasks WHERE remote_storage IS NOT NULL AND storage_destination_id IS NULL"
+    )
+        .fetch_all(pool)
+        .await?;
+
+    for (task_id, target_json) in tasks {
+        let target: StorageTarget = match serde_json::from_str(&target_json) {
+            Ok(target) => target,
+            Err(e) => {
+                warn!("Dropping unreadable upload target of task {}: {}", task_id, e);
+                sqlx::query("UPDATE tasks SET remote_storage = NULL WHERE id = ?").bind(&task_id).execute(pool).await?;
+                continue;
+            }
+        };
+
+        // Tasks that uploaded to the same place share one destination
+        let existing: Option<(String,)> = sqlx::query_as("SELECT id FROM storage_destinations WHERE target = ?")
+            .bind(&target_json)
+            .fetch_optional(pool)
+            .await?;
+        let destination_id = match existing {
+            Some((id,)) => id,
+            None => {
+                let id = Uuid::new_v4().to_string();
+                let name_taken: Option<(String,)> = sqlx::query_as("SELECT id FROM storage_destinations WHERE name = ?")
+                    .bind(target.to_string())
+                    .fetch_optional(pool)
+                    .await?;
+                let name = match name_taken {
+                    Some(_) => format!("{} ({})", target, &id[..8]),
+                    None => target.to_string(),
+                };
+                let now = Utc::now();
+                sqlx::query("INSERT INTO storage_destinations (id, name, target, created_at, updated_at) VALUES (?, ?, ?, ?, ?)")
+                    .bind(&id)
+                    .bind(&name)
+                    .bind(&target_json)
+                    .bind(now)
+                    .bind(now)
+                    .execute(pool)
+                    .await?;
+                info!("Created storage destination '{}' from task {}", name, task_id);
+                id
+            }
+        };
+
+        sqlx::query("UPDATE tasks SET storage_destination_id = ?, remote_storage = NULL WHERE id = ?")
+            .bind(&destination_id)
+            .bind(&task_id)
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
@@ -2,6 +2,7 @@ mod api;
 mod models;
 mod db;
 mod services;
+mod state;
 
 #[cfg(test)]
 mod tests;
@@ -16,9 +17,10 @@ use axum::{
 use clap::Parser;
 use tower_http::cors::CorsLayer;
 use tower_http::services::ServeDir;
-use tracing::{info, instrument, error};
+use tracing::{info, instrument, error, warn};
 use tracing_subscriber;
 use std::fs;
+use std::future::IntoFuture;
 use std::path::Path;
 use std::sync::Arc;
 
@@ -43,6 +45,136 @@ struct Cli {
 
     #[arg(long, default_value = "../frontend/dist")]
     static_dir: String,
+
+    #[arg(long, default_value = "/tmp")]
+    temp_dir: String,
+
+    /// Verify a randomly chosen backup every N days (0 = off)
+    #[arg(long, default_value = "0")]
+    verify_interval_days: u32,
+
+    /// Scheduled verifications also restore the backup into a disposable Docker MySQL container
+    #[arg(long)]
+    verify_sandbox: bool,
+
+    /// Image used for sandbox restores
+    #[arg(long, default_value = "mysql:8.0")]
+    sandbox_image: String,
+
+    /// Host on which published sandbox ports are reachable (e.g. host.docker.internal inside Docker)
+    #[arg(long, default_value = "127.0.0.1")]
+    sandbox_host: String,
+
+    /// Secret for the HMAC-SHA256 signature on the backup catalog (unsigned when not set)
+    #[arg(long, env = "RDUMPER_CATALOG_SIGNING_KEY", hide_env_values = true)]
+    catalog_signing_key: Option<String>,
+
+    /// Secret signing login tokens; without it a random one is used and logins end on restart
+    #[arg(long, env = "RDUMPER_JWT_SECRET", hide_env_values = true)]
+    jwt_secret: Option<String>,
+
+    /// Run as an agent: take backup jobs from this central instance instead of scheduling locally
+    #[arg(long, requires = "agent_token")]
+    central_url: Option<String>,
+
+    /// Token issued by the central instance when the agent was created
+    #[arg(long, env = "RDUMPER_AGENT_TOKEN", hide_env_values = true)]
+    agent_token: Option<String>,
+
+    /// Run at most this many backup and restore jobs at once, queueing the rest (0 = unlimited)
+    #[arg(long, default_value = "0")]
+    max_concurrent_jobs: u32,
+
+    /// Run at most this many backup and restore jobs at once against the same database host (0 = unlimited)
+    #[arg(long, default_value = "0")]
+    max_jobs_per_host: u32,
+
+    /// Delete finished jobs and their log folders once they are older than this many days (0 = keep forever)
+    #[arg(long, default_value = "0")]
+    job_retention_days: u32,
+
+    /// Keep at most this many finished jobs, deleting the oldest with their log folders (0 = unlimited)
+    #[arg(long, default_value = "0")]
+    job_retention_max: u32,
+
+    /// On SIGTERM or Ctrl-C, wait this many seconds for running jobs before interrupting them
+    #[arg(long, default_value = "300")]
+    shutdown_timeout: u64,
+
+    /// Limit remote uploads and backup downloads together to this many KiB/s (0 = unlimited)
+    #[arg(long, default_value = "0")]
+    bandwidth_limit_kib: u32,
+
+    /// Directory with the keys that encrypt backup archives, one file per key holding 32 bytes as hex or base64
+    #[arg(long, env = "RDUMPER_SECRETS_DIR", default_value = "/run/secrets")]
+    secrets_dir: String,
+
+    /// Directory dumps made outside rdumper may be registered from; registering is off without it
+    #[arg(long, env = "RDUMPER_IMPORT_DIR")]
+    import_dir: Option<String>,
+
+    /// SMTP server for job notification emails; emails are off without it
+    #[arg(long, requires = "smtp_from")]
+    smtp_host: Option<String>,
+
+    #[arg(long, default_value = "587")]
+    smtp_port: u16,
+
+    #[arg(long, value_enum, default_value = "starttls")]
+    smtp_security: services::notifications::email::SmtpSecurity,
+
+    #[arg(long, env = "RDUMPER_SMTP_USERNAME")]
+    smtp_username: Option<String>,
+
+    #[arg(long, env = "RDUMPER_SMTP_PASSWORD", hide_env_values = true)]
+    smtp_password: Option<String>,
+
+    /// Sender address of notification emails
+    #[arg(long)]
+    smtp_from: Option<String>,
+
+    /// Comma separated recipients of notification emails for tasks that don't name their own
+    #[arg(long, default_value = "")]
+    notify_email_to: String,
+
+    /// Slack incoming webhook finished backups are posted to, for tasks that want notifications
+    #[arg(long, env = "RDUMPER_SLACK_WEBHOOK_URL", hide_env_values = true)]
+    slack_webhook_url: Option<String>,
+
+    /// Text of Slack messages instead of the default layout, e.g. "{task} {status} after {duration}: {job_url}"
+    #[arg(long)]
+    slack_template: Option<String>,
+
+    /// Discord webhooks finished backups are posted to, comma separated or repeated
+    #[arg(long, env = "RDUMPER_DISCORD_WEBHOOK_URL", hide_env_values = true, value_delimiter = ',')]
+    discord_webhook_url: Vec<String>,
+
+    /// ntfy server finished backups are published to, e.g. https://ntfy.sh
+    #[arg(long, requires = "ntfy_topic")]
+    ntfy_url: Option<String>,
+
+    #[arg(long)]
+    ntfy_topic: Option<String>,
+
+    /// Access token for protected ntfy topics
+    #[arg(long, env = "RDUMPER_NTFY_TOKEN", hide_env_values = true)]
+    ntfy_token: Option<String>,
+
+    /// Gotify server finished backups are pushed to
+    #[arg(long, requires = "gotify_token")]
+    gotify_url: Option<String>,
+
+    /// Token of the Gotify application
+    #[arg(long, env = "RDUMPER_GOTIFY_TOKEN", hide_env_values = true)]
+    gotify_token: Option<String>,
+
+    /// Address the web UI is reached at, e.g. https://rdumper.example.com, for links in notifications
+    #[arg(long)]
+    public_url: Option<String>,
+
+    /// Seed example data and simulate mydumper, myloader and MySQL, for UI development and evaluation
+    #[arg(long)]
+    demo: bool,
 }
 
 fn ensure_sqlite_file(url: &str) -> std::io::Result<()> {
@@ -75,19 +207,70 @@ async fn main() -> Result<()> {
     std::fs::create_dir_all(&cli.backup_dir)?;
     std::fs::create_dir_all(&cli.log_dir)?;
 
-    // Set environment variables for services
-    std::env::set_var("BACKUP_DIR", &cli.backup_dir);
-    std::env::set_var("LOG_DIR", &cli.log_dir);
+    let config = state::AppConfig {
+        backup_dir: cli.backup_dir.clone(),
+        log_dir: cli.log_dir.clone(),
+        temp_dir: cli.temp_dir.clone(),
+        import_dir: cli.import_dir.clone(),
+        verify_interval_days: cli.verify_interval_days,
+        verify_sandbox: cli.verify_sandbox,
+        sandbox: services::sandbox::SandboxSettings {
+            image: cli.sandbox_image.clone(),
+            host: cli.sandbox_host.clone(),
+        },
+        catalog_signing_key: cli.catalog_signing_key.clone(),
+        jwt_secret: cli.jwt_secret.clone().unwrap_or_else(services::auth::generate_secret),
+        max_concurrent_jobs: cli.max_concurrent_jobs,
+        max_jobs_per_host: cli.max_jobs_per_host,
+        job_retention_days: cli.job_retention_days,
+        job_retention_max: cli.job_retention_max,
+        notifications: services::notifications::NotificationSettings {
+            public_url: cli.public_url.clone(),
+            email: cli.smtp_host.clone().map(|host| services::notifications::email::SmtpSettings {
+                host,
+                port: cli.smtp_port,
+                security: cli.smtp_security,
+                username: cli.smtp_username.clone(),
+                password: cli.smtp_password.clone(),
+                from: cli.smtp_from.clone().unwrap_or_default(),
+                to: models::task::split_addresses(&cli.notify_email_to),
+            }),
+            slack: cli.slack_webhook_url.clone().map(|webhook_url| services::notifications::slack::SlackSettings {
+                webhook_url,
+                template: cli.slack_template.clone(),
+            }),
+            discord_webhooks: cli.discord_webhook_url.clone(),
+            ntfy: cli.ntfy_url.clone().map(|url| services::notifications::push::NtfySettings {
+                url,
+                topic: cli.ntfy_topic.clone().unwrap_or_default(),
+                token: cli.ntfy_token.clone(),
+            }),
+            gotify: cli.gotify_url.clone().zip(cli.gotify_token.clone())
+                .map(|(url, token)| services::notifications::push::GotifySettings { url, token }),
+        },
+    };
+
+    services::bandwidth::set_global_limit(cli.bandwidth_limit_kib);
+    services::encryption::set_secrets_dir(&cli.secrets_dir);
 
     // Initialize database
     ensure_sqlite_file(&cli.database_url)?;
     let pool = db::create_database_pool(&cli.database_url).await?;
     info!("Database connection established");
 
+    if cli.demo {
+        info!("Demo mode: backups and restores are simulated");
+        services::demo::enable();
+        if let Err(e) = services::demo::seed(&pool, &config).await {
+            error!("Failed to seed demo data: {}", e);
+        }
+    }
+
     // Start background task worker
     let worker_pool = Arc::new(pool.clone());
-    let task_worker = Arc::new(services::TaskWorker::new(worker_pool));
+    let task_worker = Arc::new(services::TaskWorker::new(worker_pool, config.clone()));
     let worker_for_api = task_worker.clone();
+    let worker_for_shutdown = task_worker.clone();
     
     tokio::spawn(async move {
         if let Err(e) = task_worker.start().await {
@@ -95,8 +278,41 @@ async fn main() -> Result<()> {
         }
     });
 
+    // Start background integrity hashing worker
+    let integrity_worker = services::IntegrityWorker::new(pool.clone(), cli.backup_dir.clone());
+    tokio::spawn(async move {
+        integrity_worker.start().await;
+    });
+
+    // Send job and backup events to webhooks
+    let webhook_dispatcher = services::WebhookDispatcher::new(pool.clone());
+    tokio::spawn(async move {
+        webhook_dispatcher.start().await;
+    });
+
+    // Report finished backups on the configured notification channels
+    let notifier = Arc::new(services::Notifier::new(pool.clone(), &config));
+    tokio::spawn(async move {
+        notifier.start().await;
+    });
+
+    // In agent mode, execute jobs dispatched by the central instance
+    if let (Some(central_url), Some(token)) = (cli.central_url.clone(), cli.agent_token.clone()) {
+        let agent_pool = Arc::new(pool.clone());
+        let agent_config = config.clone();
+        let settings = services::agent_client::AgentSettings { central_url, token };
+        tokio::spawn(async move {
+            services::agent_client::run(agent_pool, agent_config, settings).await;
+        });
+    }
+
+    if !services::auth::required(&pool).await.unwrap_or(true) {
+        warn!("No API key or user exists, the API is open to anyone who can reach it; create one with POST /api/api-keys or POST /api/users");
+    }
+
     // Create API routes
-    let api_routes = api::create_routes(pool.clone(), worker_for_api);
+    let app_state = Arc::new(state::AppState::new(pool.clone(), config, worker_for_api));
+    let api_routes = api::create_routes(app_state);
 
     // SPA fallback handler - serves index.html for any non-API route
     let static_dir = cli.static_dir.clone();
@@ -128,7 +344,50 @@ async fn main() -> Result<()> {
     let listener = tokio::net::TcpListener::bind(format!("{}:{}", cli.host, cli.port)).await?;
     info!("Server listening on {}:{}", cli.host, cli.port);
 
-    axum::serve(listener, app).await?;
+    // The API keeps serving while running jobs drain, so their progress stays visible
+    let server = axum::serve(listener, app).into_future();
+    tokio::pin!(server);
+    tokio::select! {
+        result = &mut server => return result.map_err(Into::into),
+        _ = shutdown_signal() => {}
+    }
+    info!("Shutdown requested, no new jobs will start");
+    tokio::select! {
+        result = &mut server => result?,
+        _ = worker_for_shutdown.drain(std::time::Duration::from_secs(cli.shutdown_timeout)) => {}
+    }
+    // Returning drops the remaining job tasks, which kills their mydumper and myloader children
+    info!("rDumper backend stopped");
 
     Ok(())
+}
+
+/// Resolves on Ctrl-C or, on Unix, SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
 }
\ No newline at end of file
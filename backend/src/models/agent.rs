@@ -0,0 +1,72 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use crate::models::{DatabaseConfig, Job, Task};
+use utoipa::ToSchema;
+
+/// Agents not seen for this long are reported offline
+pub const AGENT_ONLINE_WINDOW_SECS: i64 = 120;
+
+/// A remote rdumper instance that executes jobs for the databases assigned to it
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Agent {
+    pub id: String,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub version: Option<String>,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateAgentRequest {
+    pub name: String,
+}
+
+impl Agent {
+    pub fn new(name: String, token_hash: String) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            token_hash,
+            version: None,
+            last_seen_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.last_seen_at
+            .is_some_and(|seen| Utc::now() - seen < Duration::seconds(AGENT_ONLINE_WINDOW_SECS))
+    }
+}
+
+/// Work handed to an agent: the job plus everything needed to run it without access to the central database
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentJob {
+    pub job: Job,
+    pub task: Task,
+    pub database_config: DatabaseConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentPollRequest {
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentPollResponse {
+    pub job: Option<AgentJob>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentProgressRequest {
+    pub progress: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AgentFailRequest {
+    pub error: String,
+}
@@ -0,0 +1,35 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// A key clients send as `Authorization: Bearer <key>` to use the API
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    pub prefix: String, // First characters of the key, to tell keys apart
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+}
+
+impl ApiKey {
+    pub fn new(name: String, key: &str, key_hash: String) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            key_hash,
+            prefix: key.chars().take(8).collect(),
+            last_used_at: None,
+            created_at: Utc::now(),
+        }
+    }
+}
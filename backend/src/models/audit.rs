@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::Json;
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+/// One mutating API call: who made it, what it touched and how that looked before and after
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct AuditEntry {
+    pub id: String,
+    pub actor: String, // Username, `api-key:<name>` or `anonymous`
+    pub actor_kind: String, // "user", "api_key", "anonymous"
+    pub actor_id: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub action: String, // "create", "update", "delete" or the route's own verb, e.g. "restore"
+    pub entity_type: String, // Resource the route belongs to, e.g. "backups"
+    pub entity_id: Option<String>,
+    pub status_code: i64,
+    #[schema(value_type = Option<Object>)]
+    pub request: Option<Json<Value>>, // JSON body of the call
+    #[schema(value_type = Option<Object>)]
+    pub before: Option<Json<Value>>, // Entity before the call
+    #[schema(value_type = Option<Object>)]
+    pub after: Option<Json<Value>>, // Entity after the call, None once deleted
+    #[schema(value_type = Option<Object>)]
+    pub response: Option<Json<Value>>, // `data` of the response, or the error
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AuditQuery {
+    pub page: Option<u32>,
+    pub limit: Option<u32>,
+    pub actor: Option<String>,
+    pub entity_type: Option<String>,
+    pub entity_id: Option<String>,
+    pub action: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub failed: Option<bool>, // Only calls answered with (true) or without (false) an error status
+    pub after_id: Option<String>, // Cursor pagination: entries after this one
+    pub created_before: Option<DateTime<Utc>>, // Cursor pagination: entries before this time, or the time of `after_id`
+}
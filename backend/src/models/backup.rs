@@ -1,7 +1,10 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize, Deserializer};
+use std::collections::BTreeMap;
 use std::path::Path;
 use uuid::Uuid;
+use super::storage::BackupLocation;
+use utoipa::ToSchema;
 
 fn deserialize_datetime_string<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
 where
@@ -13,7 +16,7 @@ where
         .map(|dt| dt.with_timezone(&Utc))
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Backup {
     pub id: String,
     pub database_name: String,
@@ -26,9 +29,151 @@ pub struct Backup {
     pub compression_type: String,
     pub created_at: String,
     pub backup_type: String, // "manual", "scheduled", "uploaded"
+    pub sha256_hash: Option<String>,
+    pub verification_status: VerificationStatus,
+    /// When the checksum job hashed the archive
+    #[serde(default)]
+    pub verified_at: Option<String>,
+    #[serde(default)]
+    pub size_anomaly: Option<SizeAnomaly>,
+    #[serde(default)]
+    pub last_verification: Option<BackupVerification>,
+    #[serde(default)]
+    pub last_remote_verification: Option<RemoteVerification>,
+    #[serde(default)]
+    pub location: BackupLocation,
+    /// URL of the uploaded copy, e.g. `s3://bucket/prefix/folder/archive.tar.gz`
+    #[serde(default)]
+    pub remote_location: Option<String>,
+    #[serde(default)]
+    pub binlog: Option<BinlogArchive>,
+    /// Archive is encrypted with the task's key; restores decrypt it on the fly
+    #[serde(default)]
+    pub encrypted: bool,
+    /// Version of the server the backup was taken from
+    #[serde(default)]
+    pub server_version: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A position in the server's binary log, e.g. `mysql-bin.000042` at byte 1234
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BinlogPosition {
+    pub file: String,
+    pub position: u64,
+}
+
+/// Binary logs archived next to a full backup, replayed on top of it for point-in-time restores
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BinlogArchive {
+    /// Where the dump's snapshot was taken; replaying starts here
+    pub start: BinlogPosition,
+    /// Binlog files in the backup folder's `binlogs` directory, oldest first
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// When the newest archiving job finished; events up to then can be restored
+    #[serde(default)]
+    pub archived_at: Option<String>,
+}
+
+/// How thoroughly a verify job checks a backup
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifyMode {
+    /// Re-hash the archive and read every entry
+    #[default]
+    Archive,
+    /// Additionally restore into a throwaway MySQL container and query the result
+    SandboxRestore,
+}
+
+/// Outcome of the most recent verify job run against a backup
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BackupVerification {
+    pub job_id: String,
+    pub checked_at: String,
+    #[serde(default)]
+    pub mode: VerifyMode,
+    pub passed: bool,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub sandbox: Option<SandboxReport>,
+}
+
+/// How a verify-remote job checks the uploaded copy of a backup
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteVerifyMode {
+    /// Compare the size reported by the destination, without downloading
+    #[default]
+    Size,
+    /// Download the copy and compare its SHA-256
+    Checksum,
+}
+
+/// Outcome of the most recent verify-remote job run against a backup
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RemoteVerification {
+    pub job_id: String,
+    pub checked_at: String,
+    pub mode: RemoteVerifyMode,
+    pub remote_location: String,
+    pub passed: bool,
+    pub remote_size: Option<u64>,
+    pub remote_sha256: Option<String>,
+    /// Each way the remote copy differs from the local metadata
+    #[serde(default)]
+    pub divergences: Vec<String>,
+    /// Set when the copy couldn't be checked at all, e.g. it is missing
+    pub error: Option<String>,
+}
+
+/// What a sandbox restore found in the restored database
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SandboxReport {
+    pub image: String,
+    pub tables: Vec<SandboxTable>,
+    pub total_rows: i64,
+    pub duration_secs: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SandboxTable {
+    pub name: String,
+    pub rows: i64,
+}
+
+/// Archive size that deviates sharply from recent backups of the same task
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SizeAnomaly {
+    /// Median size of the task's recent backups
+    pub expected_size: i64,
+    /// Signed deviation from the expected size, e.g. -62.5 for "62.5% smaller"
+    pub deviation_percent: f64,
+}
+
+/// State of the background SHA-256 integrity hash for a backup archive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub enum VerificationStatus {
+    #[serde(rename = "pending")]
+    #[default]
+    Pending,
+    #[serde(rename = "verified")]
+    Verified,
+    #[serde(rename = "failed")]
+    Failed,
+}
+
+impl VerificationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerificationStatus::Pending => "pending",
+            VerificationStatus::Verified => "verified",
+            VerificationStatus::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct BackupMetadata {
     pub id: String,
     pub database_name: String,
@@ -42,11 +187,32 @@ pub struct BackupMetadata {
     pub created_at: String,
     pub backup_type: String,
     pub ident: Option<String>,
+    #[serde(default)]
+    pub sha256_hash: Option<String>,
+    #[serde(default)]
+    pub verified_at: Option<String>,
+    #[serde(default)]
+    pub verification_error: Option<String>,
+    #[serde(default)]
+    pub size_anomaly: Option<SizeAnomaly>,
+    #[serde(default)]
+    pub last_verification: Option<BackupVerification>,
+    #[serde(default)]
+    pub remote_location: Option<String>,
+    #[serde(default)]
+    pub last_remote_verification: Option<RemoteVerification>,
+    #[serde(default)]
+    pub binlog: Option<BinlogArchive>,
+    #[serde(default)]
+    pub encrypted: bool,
+    /// Version of the server the dump was taken from, checked against restore targets
+    #[serde(default)]
+    pub server_version: Option<String>,
     pub database_config: DatabaseConfigInfo,
     pub task_info: Option<TaskInfo>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct DatabaseConfigInfo {
     pub id: String,
     pub name: String,
@@ -56,15 +222,17 @@ pub struct DatabaseConfigInfo {
     pub database_name: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TaskInfo {
     pub id: String,
     pub name: String,
     pub schedule: Option<String>,
     pub use_non_transactional: bool,
+    #[serde(default)]
+    pub where_clause: Option<String>, // Row filter the backup was taken with, so partial backups are recognizable
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateBackupRequest {
     pub database_config_id: String,
     pub task_id: Option<String>,
@@ -73,10 +241,240 @@ pub struct CreateBackupRequest {
     pub compression_type: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
 pub struct RestoreRequest {
     pub new_database_name: Option<String>,
     pub overwrite_existing: bool,
+    /// Replay archived binary logs on top of the dump up to this moment
+    #[serde(default)]
+    pub point_in_time: Option<chrono::DateTime<Utc>>,
+    /// Restore only these tables, as `table` or `database.table`, leaving the rest of the schema alone
+    #[serde(default)]
+    pub tables: Vec<String>,
+    /// Database config to restore into, on any server; the backup's own config when missing
+    #[serde(default)]
+    pub target_config_id: Option<String>,
+    #[serde(flatten)]
+    pub myloader: MyloaderOptions,
+    #[serde(flatten)]
+    pub remap: SchemaRemap,
+}
+
+/// Database and table renames applied to a dump before it is loaded, e.g. to restore
+/// `prod_app` as `staging_app` or to prefix every table
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct SchemaRemap {
+    /// Dumped database name to the name it is restored as
+    #[serde(default)]
+    pub database_map: BTreeMap<String, String>,
+    /// Dumped table, as `table` or `database.table`, to the name it is restored as
+    #[serde(default)]
+    pub table_map: BTreeMap<String, String>,
+    /// Prepended to the name of every table `table_map` doesn't rename
+    #[serde(default)]
+    pub table_prefix: Option<String>,
+}
+
+impl SchemaRemap {
+    pub fn is_empty(&self) -> bool {
+        self.database_map.is_empty() && self.table_map.is_empty() && self.table_prefix.as_deref().unwrap_or_default().is_empty()
+    }
+
+    pub fn database(&self, database: &str) -> String {
+        self.database_map.get(database).cloned().unwrap_or_else(|| database.to_string())
+    }
+
+    pub fn table(&self, database: &str, table: &str) -> String {
+        self.table_map.get(&format!("{}.{}", database, table))
+            .or_else(|| self.table_map.get(table))
+            .cloned()
+            .unwrap_or_else(|| format!("{}{}", self.table_prefix.as_deref().unwrap_or_default(), table))
+    }
+
+    /// New names end up in file names and SQL, so only plain identifiers are accepted
+    pub fn validate(&self) -> Result<(), String> {
+        let names = self.database_map.values()
+            .chain(self.table_map.values())
+            .chain(self.table_prefix.iter());
+        for name in names {
+            let valid = !name.is_empty()
+                && name.len() <= 64
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '$'));
+            if !valid {
+                return Err(format!("Invalid name '{}' in schema remapping: use letters, digits, _ and $", name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// myloader tuning for restoring large dumps; unset options keep myloader's defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct MyloaderOptions {
+    /// Threads loading data, 4 when unset
+    #[serde(default)]
+    pub threads: Option<u32>,
+    /// Insert statements per transaction
+    #[serde(default)]
+    pub queries_per_transaction: Option<u32>,
+    /// Create secondary InnoDB indexes after the data is loaded instead of before
+    #[serde(default)]
+    pub innodb_optimize_keys: Option<InnodbOptimizeKeys>,
+    /// How existing tables are cleared when they are overwritten
+    #[serde(default)]
+    pub purge_mode: Option<PurgeMode>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum InnodbOptimizeKeys {
+    /// Add each table's indexes as soon as its data is in
+    AfterImportPerTable,
+    /// Add indexes once all tables are loaded
+    AfterImportAllTables,
+}
+
+impl InnodbOptimizeKeys {
+    pub fn as_arg(&self) -> &'static str {
+        match self {
+            Self::AfterImportPerTable => "AFTER_IMPORT_PER_TABLE",
+            Self::AfterImportAllTables => "AFTER_IMPORT_ALL_TABLES",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PurgeMode {
+    None,
+    Truncate,
+    Drop,
+    Delete,
+}
+
+impl PurgeMode {
+    pub fn as_arg(&self) -> &'static str {
+        match self {
+            Self::None => "NONE",
+            Self::Truncate => "TRUNCATE",
+            Self::Drop => "DROP",
+            Self::Delete => "DELETE",
+        }
+    }
+}
+
+/// Restore a database as it was at `point_in_time`, from whichever backup covers that moment
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PointInTimeRestoreRequest {
+    pub database_config_id: String,
+    pub database_name: String,
+    pub point_in_time: chrono::DateTime<Utc>,
+    pub new_database_name: Option<String>,
+    #[serde(default)]
+    pub overwrite_existing: bool,
+    #[serde(default)]
+    pub target_config_id: Option<String>,
+    #[serde(flatten)]
+    pub myloader: MyloaderOptions,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct VerifyRequest {
+    #[serde(default)]
+    pub mode: VerifyMode,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct VerifyRemoteRequest {
+    #[serde(default)]
+    pub mode: RemoteVerifyMode,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RecompressRequest {
+    pub compression_type: crate::models::CompressionType,
+}
+
+/// Where a backup copy is written
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CopyDestination {
+    /// A directory on the rdumper host, e.g. a mounted NAS share; the backup folder is created inside it
+    Directory { path: String },
+}
+
+impl std::fmt::Display for CopyDestination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CopyDestination::Directory { path } => write!(f, "directory {}", path),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CopyBackupRequest {
+    pub destination: CopyDestination,
+}
+
+/// Register a dump or archive produced outside rdumper
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RegisterBackupRequest {
+    /// Inside the import directory, absolute or relative to it
+    pub path: String,
+    pub database_config_id: String,
+    pub database_name: Option<String>,
+    pub task_id: Option<String>,
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Inventory of every backup in the store, served to external inventory and DR tooling
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CatalogManifest {
+    /// Bumped whenever fields change meaning
+    pub format_version: u32,
+    pub generated_at: String,
+    pub backup_dir: String,
+    pub backup_count: usize,
+    pub total_size: i64,
+    pub backups: Vec<CatalogEntry>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CatalogEntry {
+    pub id: String,
+    pub database_config_id: String,
+    pub database_name: String,
+    pub used_database: Option<String>,
+    pub task_id: Option<String>,
+    pub backup_type: String,
+    pub created_at: String,
+    pub file_path: String,
+    pub meta_path: String,
+    pub file_size: i64,
+    pub compression_type: String,
+    pub sha256_hash: Option<String>,
+    pub verification_status: VerificationStatus,
+    pub retention: CatalogRetention,
+}
+
+/// Whether the task cleanup will remove a backup, and when
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CatalogRetention {
+    pub state: RetentionState,
+    /// `cleanup_days` of the owning task, when a cleanup applies
+    pub cleanup_days: Option<i32>,
+    pub expires_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionState {
+    /// No active task cleanup covers this backup
+    Kept,
+    /// Removed by the task cleanup once `expires_at` has passed
+    Scheduled,
+    /// Past `expires_at`; the next cleanup run removes it
+    Expired,
 }
 
 impl Backup {
@@ -102,6 +500,17 @@ impl Backup {
             compression_type,
             created_at: Utc::now().to_rfc3339(),
             backup_type,
+            sha256_hash: None,
+            verification_status: VerificationStatus::Pending,
+            verified_at: None,
+            size_anomaly: None,
+            last_verification: None,
+            location: BackupLocation::Local,
+            remote_location: None,
+            last_remote_verification: None,
+            binlog: None,
+            encrypted: false,
+            server_version: None,
         }
     }
 
@@ -184,9 +593,114 @@ impl BackupMetadata {
             created_at: backup.created_at.clone(),
             backup_type: backup.backup_type.clone(),
             ident: None, // Will be set when calculating hash
+            sha256_hash: None,
+            verified_at: None,
+            verification_error: None,
+            size_anomaly: None,
+            last_verification: None,
+            remote_location: None,
+            last_remote_verification: None,
+            binlog: None,
+            encrypted: false,
+            server_version: None,
             database_config,
             task_info,
         }
     }
 
-}
\ No newline at end of file
+    /// Database the dump was taken of, `*` for backups of all databases
+    pub fn source_database(&self) -> &str {
+        self.used_database.as_deref()
+            .and_then(|used| used.rsplit('/').next())
+            .unwrap_or(&self.database_name)
+    }
+
+    /// Derive the verification state from the stored hash and error
+    pub fn verification_status(&self) -> VerificationStatus {
+        // A failed verify job keeps the recorded hash, so the error has to win
+        if self.verification_error.is_some() {
+            VerificationStatus::Failed
+        } else if self.sha256_hash.is_some() {
+            VerificationStatus::Verified
+        } else {
+            VerificationStatus::Pending
+        }
+    }
+
+    /// Whether the archive has been fully written and can be hashed
+    pub fn is_archive_complete(&self) -> bool {
+        !self.file_path.is_empty() && self.ident.is_some()
+    }
+}
+/// What a file inside a mydumper archive contains, inferred from its name
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveEntryKind {
+    Metadata,
+    DatabaseSchema,
+    TableSchema,
+    Data,
+    Other,
+}
+
+/// A single file inside a backup archive
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ArchiveEntry {
+    pub path: String,
+    pub size: u64,
+    pub kind: ArchiveEntryKind,
+    pub database_name: Option<String>,
+    pub table_name: Option<String>,
+}
+
+impl ArchiveEntry {
+    /// Classify a file by mydumper's naming scheme (`db.table-schema.sql`, `db.table.00000.sql.gz`, ...)
+    pub fn from_path(path: String, size: u64) -> Self {
+        let file_name = Path::new(&path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("")
+            .to_string();
+
+        let mut stem = file_name.as_str();
+        for ext in [".gz", ".zst"] {
+            stem = stem.strip_suffix(ext).unwrap_or(stem);
+        }
+
+        let (kind, database_name, table_name) = if file_name == "metadata" || file_name.starts_with("metadata.") {
+            (ArchiveEntryKind::Metadata, None, None)
+        } else if let Some(name) = stem.strip_suffix(".sql").or_else(|| stem.strip_suffix(".dat")) {
+            if let Some(database) = name.strip_suffix("-schema-create") {
+                (ArchiveEntryKind::DatabaseSchema, Some(database.to_string()), None)
+            } else if let Some((object, _)) = name.split_once("-schema") {
+                let (database, table) = split_object_name(object);
+                (ArchiveEntryKind::TableSchema, database, table)
+            } else {
+                // Data chunks carry a numeric part suffix, e.g. db.table.00000
+                let object = match name.rsplit_once('.') {
+                    Some((object, part)) if part.chars().all(|c| c.is_ascii_digit()) => object,
+                    _ => name,
+                };
+                let (database, table) = split_object_name(object);
+                (ArchiveEntryKind::Data, database, table)
+            }
+        } else {
+            (ArchiveEntryKind::Other, None, None)
+        };
+
+        Self {
+            path,
+            size,
+            kind,
+            database_name,
+            table_name,
+        }
+    }
+}
+
+fn split_object_name(object: &str) -> (Option<String>, Option<String>) {
+    match object.split_once('.') {
+        Some((database, table)) => (Some(database.to_string()), Some(table.to_string())),
+        None => (None, Some(object.to_string())),
+    }
+}
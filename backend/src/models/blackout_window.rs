@@ -0,0 +1,126 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// A recurring period in which scheduled backups must not start, e.g. office hours on weekdays.
+///
+/// Without a task the window applies to every task. Times are wall-clock times in the
+/// timezone of the task being scheduled; a window whose end is before its start runs past midnight.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct BlackoutWindow {
+    pub id: String,
+    pub name: String,
+    pub task_id: Option<String>, // None applies the window to all tasks
+    pub weekdays: String, // Comma separated days the window starts on, e.g. "mon,tue"; empty means every day
+    pub start_time: String, // "HH:MM"
+    pub end_time: String, // "HH:MM"
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateBlackoutWindowRequest {
+    pub name: String,
+    pub task_id: Option<String>,
+    pub weekdays: Option<Vec<String>>,
+    pub start_time: String,
+    pub end_time: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateBlackoutWindowRequest {
+    pub name: Option<String>,
+    pub task_id: Option<String>, // Empty string makes the window global
+    pub weekdays: Option<Vec<String>>, // Empty list means every day
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+}
+
+impl BlackoutWindow {
+    pub fn new(req: CreateBlackoutWindowRequest) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name: req.name,
+            task_id: req.task_id.filter(|id| !id.is_empty()),
+            weekdays: join_weekdays(req.weekdays.unwrap_or_default()),
+            start_time: req.start_time,
+            end_time: req.end_time,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn update(&mut self, req: UpdateBlackoutWindowRequest) {
+        if let Some(name) = req.name {
+            self.name = name;
+        }
+        if let Some(task_id) = req.task_id {
+            self.task_id = (!task_id.is_empty()).then_some(task_id);
+        }
+        if let Some(weekdays) = req.weekdays {
+            self.weekdays = join_weekdays(weekdays);
+        }
+        if let Some(start_time) = req.start_time {
+            self.start_time = start_time;
+        }
+        if let Some(end_time) = req.end_time {
+            self.end_time = end_time;
+        }
+        self.updated_at = Utc::now();
+    }
+
+    /// Days the window starts on; empty means every day
+    pub fn weekdays(&self) -> Result<Vec<Weekday>, String> {
+        self.weekdays.split(',')
+            .map(str::trim)
+            .filter(|day| !day.is_empty())
+            .map(|day| day.parse::<Weekday>().map_err(|_| format!("Invalid weekday: {}", day)))
+            .collect()
+    }
+
+    pub fn times(&self) -> Result<(NaiveTime, NaiveTime), String> {
+        let parse = |time: &str| NaiveTime::parse_from_str(time, "%H:%M")
+            .map_err(|_| format!("Invalid time '{}', expected HH:MM", time));
+        Ok((parse(&self.start_time)?, parse(&self.end_time)?))
+    }
+
+    /// When the window covering `local` ends, or None if `local` is outside the window.
+    ///
+    /// Equal start and end times block the whole day.
+    pub fn end_of_window_at(&self, local: NaiveDateTime) -> Result<Option<NaiveDateTime>, String> {
+        let weekdays = self.weekdays()?;
+        let (start, end) = self.times()?;
+        let starts_on = |date: chrono::NaiveDate| weekdays.is_empty() || weekdays.contains(&date.weekday());
+        let date = local.date();
+        let time = local.time();
+
+        let window_end = if start < end {
+            (starts_on(date) && time >= start && time < end).then(|| date.and_time(end))
+        } else if time >= start && starts_on(date) {
+            // Started today, runs into tomorrow
+            Some((date + Duration::days(1)).and_time(end))
+        } else if time < end && starts_on(date - Duration::days(1)) {
+            // Started yesterday
+            Some(date.and_time(end))
+        } else {
+            None
+        };
+        Ok(window_end)
+    }
+}
+
+/// Store weekdays in their canonical short form, e.g. ["Monday", "tue"] → "mon,tue"
+fn join_weekdays(weekdays: Vec<String>) -> String {
+    weekdays.iter()
+        .map(|day| match day.parse::<Weekday>() {
+            Ok(weekday) => weekday.to_string().to_lowercase(),
+            // Kept as given so validation can report it
+            Err(_) => day.trim().to_string(),
+        })
+        .filter(|day| !day.is_empty())
+        .collect::<Vec<_>>()
+        .join(",")
+}
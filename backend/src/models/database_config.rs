@@ -2,8 +2,9 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct DatabaseConfig {
     pub id: String,
     pub name: String,
@@ -14,11 +15,13 @@ pub struct DatabaseConfig {
     pub database_name: String, // Database name (can be empty for connection-only configs)
     pub connection_status: String, // "untested", "success", "failed"
     pub last_tested: Option<DateTime<Utc>>,
+    pub rpo_hours: Option<i32>, // Target recovery point objective: max hours since the last successful backup
+    pub agent_id: Option<String>, // Agent that runs jobs for this database; None runs them locally
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateDatabaseConfigRequest {
     pub name: String,
     pub host: String,
@@ -26,9 +29,11 @@ pub struct CreateDatabaseConfigRequest {
     pub username: String,
     pub password: String,
     pub database_name: Option<String>, // Optional database name
+    pub rpo_hours: Option<i32>,
+    pub agent_id: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UpdateDatabaseConfigRequest {
     pub name: Option<String>,
     pub host: Option<String>,
@@ -36,6 +41,8 @@ pub struct UpdateDatabaseConfigRequest {
     pub username: Option<String>,
     pub password: Option<String>,
     pub database_name: Option<String>,
+    pub rpo_hours: Option<i32>, // 0 removes the RPO target
+    pub agent_id: Option<String>, // Empty string runs jobs locally again
 }
 
 impl DatabaseConfig {
@@ -51,6 +58,8 @@ impl DatabaseConfig {
             database_name: req.database_name.unwrap_or_default(),
             connection_status: "untested".to_string(),
             last_tested: None,
+            rpo_hours: req.rpo_hours.filter(|hours| *hours > 0),
+            agent_id: req.agent_id.filter(|id| !id.is_empty()),
             created_at: now,
             updated_at: now,
         }
@@ -75,6 +84,12 @@ impl DatabaseConfig {
         if let Some(database_name) = req.database_name {
             self.database_name = database_name;
         }
+        if let Some(rpo_hours) = req.rpo_hours {
+            self.rpo_hours = (rpo_hours > 0).then_some(rpo_hours);
+        }
+        if let Some(agent_id) = req.agent_id {
+            self.agent_id = (!agent_id.is_empty()).then_some(agent_id);
+        }
         // Reset connection status when config changes
         self.connection_status = "untested".to_string();
         self.last_tested = None;
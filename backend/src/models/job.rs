@@ -2,8 +2,9 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub enum JobType {
     #[serde(rename = "backup")]
     Backup,
@@ -11,6 +12,20 @@ pub enum JobType {
     Restore,
     #[serde(rename = "cleanup")]
     Cleanup,
+    #[serde(rename = "import")]
+    Import,
+    #[serde(rename = "verify")]
+    Verify,
+    #[serde(rename = "recompress")]
+    Recompress,
+    #[serde(rename = "copy")]
+    Copy,
+    #[serde(rename = "verify_remote")]
+    VerifyRemote,
+    #[serde(rename = "binlog")]
+    Binlog,
+    #[serde(rename = "checksum")]
+    Checksum,
 }
 
 impl std::fmt::Display for JobType {
@@ -19,6 +34,13 @@ impl std::fmt::Display for JobType {
             JobType::Backup => write!(f, "backup"),
             JobType::Restore => write!(f, "restore"),
             JobType::Cleanup => write!(f, "cleanup"),
+            JobType::Import => write!(f, "import"),
+            JobType::Verify => write!(f, "verify"),
+            JobType::Recompress => write!(f, "recompress"),
+            JobType::Copy => write!(f, "copy"),
+            JobType::VerifyRemote => write!(f, "verify_remote"),
+            JobType::Binlog => write!(f, "binlog"),
+            JobType::Checksum => write!(f, "checksum"),
         }
     }
 }
@@ -31,12 +53,19 @@ impl std::str::FromStr for JobType {
             "backup" => Ok(JobType::Backup),
             "restore" => Ok(JobType::Restore),
             "cleanup" => Ok(JobType::Cleanup),
+            "import" => Ok(JobType::Import),
+            "verify" => Ok(JobType::Verify),
+            "recompress" => Ok(JobType::Recompress),
+            "copy" => Ok(JobType::Copy),
+            "verify_remote" => Ok(JobType::VerifyRemote),
+            "binlog" => Ok(JobType::Binlog),
+            "checksum" => Ok(JobType::Checksum),
             _ => Err(format!("Invalid job type: {}", s)),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 pub enum JobStatus {
     #[serde(rename = "pending")]
     Pending,
@@ -83,7 +112,7 @@ impl std::str::FromStr for JobStatus {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Job {
     pub id: String,
     pub task_id: Option<String>,
@@ -97,9 +126,24 @@ pub struct Job {
     pub log_output: Option<String>,
     pub backup_path: Option<String>,
     pub created_at: DateTime<Utc>,
+    pub compression_progress: Option<i32>,
+    pub upload_progress: Option<i32>,
+    pub attempt: i32, // 1 for a run's first try, counting up with each retry
+    pub retry_at: Option<DateTime<Utc>>, // When the failed job is retried; cleared once the retry started
+    pub source_backup_id: Option<String>, // Backup a restore job loads
+    pub target_config_id: Option<String>, // Database config a restore job loads into
+    pub restore_request: Option<String>, // Resolved RestoreRequest of a restore job as JSON, kept until the worker runs it
+    pub priority: i32, // Pending jobs start highest first, then oldest first
+    pub retried_from: Option<String>, // Failed job this one was started from as a manual retry
+    pub backup_size: Option<i64>, // Bytes of the archive a backup job produced
+    pub resume_dir: Option<String>, // Backup folder an interrupted backup continues in after a restart
+    pub cpu_seconds: Option<f64>, // CPU time of the job's mydumper process
+    pub peak_rss_bytes: Option<i64>, // Peak memory of the job's mydumper process
+    pub bytes_written: Option<i64>, // Bytes the job's mydumper process wrote to storage
+    pub queue_position: Option<i64>, // Place of a pending job among those waiting for its database config
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateJobRequest {
     pub task_id: Option<String>,
     pub used_database: Option<String>,
@@ -108,6 +152,11 @@ pub struct CreateJobRequest {
 }
 
 impl Job {
+    /// Priority of jobs created by the schedule and its retries
+    pub const PRIORITY_SCHEDULED: i32 = 0;
+    /// Priority of jobs someone asked for, ahead of the scheduled ones
+    pub const PRIORITY_MANUAL: i32 = 10;
+
     pub fn new(req: CreateJobRequest) -> Self {
         let now = Utc::now();
         Self {
@@ -123,6 +172,21 @@ impl Job {
             log_output: None,
             backup_path: req.backup_path,
             created_at: now,
+            compression_progress: None,
+            upload_progress: None,
+            attempt: 1,
+            retry_at: None,
+            source_backup_id: None,
+            target_config_id: None,
+            restore_request: None,
+            priority: Self::PRIORITY_SCHEDULED,
+            retried_from: None,
+            backup_size: None,
+            resume_dir: None,
+            cpu_seconds: None,
+            peak_rss_bytes: None,
+            bytes_written: None,
+            queue_position: None,
         }
     }
 
@@ -4,9 +4,27 @@ pub mod job;
 pub mod backup;
 pub mod progress;
 pub mod log;
+pub mod ui_setting;
+pub mod agent;
+pub mod storage;
+pub mod storage_destination;
+pub mod blackout_window;
+pub mod api_key;
+pub mod user;
+pub mod audit;
+pub mod webhook;
 
 pub use database_config::{DatabaseConfig, CreateDatabaseConfigRequest, UpdateDatabaseConfigRequest};
-pub use task::{Task, CompressionType, CreateTaskRequest, UpdateTaskRequest};
+pub use task::{Task, CompressionType, NotifyOn, CreateTaskRequest, UpdateTaskRequest, LocalRunTimes};
 pub use job::{Job, JobType, JobStatus, CreateJobRequest};
-pub use backup::{Backup, BackupMetadata, DatabaseConfigInfo, TaskInfo, CreateBackupRequest, RestoreRequest};
-pub use log::{Log, LogType, LogLevel, CreateLogRequest};
\ No newline at end of file
+pub use backup::{Backup, BackupMetadata, VerificationStatus, BackupVerification, VerifyMode, VerifyRequest, RemoteVerification, RemoteVerifyMode, VerifyRemoteRequest, RecompressRequest, CopyDestination, CopyBackupRequest, RegisterBackupRequest, SandboxReport, SandboxTable, CatalogManifest, CatalogEntry, CatalogRetention, RetentionState, SizeAnomaly, ArchiveEntry, ArchiveEntryKind, DatabaseConfigInfo, TaskInfo, RestoreRequest, PointInTimeRestoreRequest, SchemaRemap, BinlogArchive, BinlogPosition};
+pub use log::{Log, LogType, LogLevel, CreateLogRequest};
+pub use ui_setting::{UiSetting, UiSettingResponse, PutUiSettingRequest, GLOBAL_SCOPE};
+pub use storage::{StorageTarget, S3Target, SftpTarget, GcsTarget, WebdavTarget, BackupLocation};
+pub use storage_destination::{StorageDestination, CreateStorageDestinationRequest, UpdateStorageDestinationRequest};
+pub use blackout_window::{BlackoutWindow, CreateBlackoutWindowRequest, UpdateBlackoutWindowRequest};
+pub use api_key::{ApiKey, CreateApiKeyRequest};
+pub use audit::{AuditEntry, AuditQuery};
+pub use webhook::{Webhook, WebhookDelivery, CreateWebhookRequest, UpdateWebhookRequest, WEBHOOK_EVENTS};
+pub use user::{User, CreateUserRequest, ChangePasswordRequest, LoginRequest, RefreshRequest};
+pub use agent::{Agent, AgentJob, AgentPollRequest, AgentPollResponse, AgentProgressRequest, AgentFailRequest, CreateAgentRequest};
@@ -1,7 +1,8 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TableProgress {
     pub name: String,
     pub status: TableStatus,
@@ -11,7 +12,7 @@ pub struct TableProgress {
     pub error_message: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub enum TableStatus {
     Pending,
     InProgress,
@@ -20,7 +21,7 @@ pub enum TableStatus {
     Error,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct DetailedProgress {
     pub job_id: String,
     pub overall_progress: u32,
@@ -37,11 +38,14 @@ pub struct DetailedProgress {
     pub last_updated: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct RdumperMeta {
     pub count: u32,
     pub tables: Vec<String>,
     pub excluded_tables: Vec<String>,
+    /// Tables a resumed backup took over from its interrupted run
+    #[serde(default)]
+    pub resumed_tables: Vec<String>,
     pub database_name: String,
     pub started_at: String,
 }
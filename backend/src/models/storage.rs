@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Shown by the API in place of stored credentials; sent back unchanged, it keeps them
+pub const REDACTED_SECRET: &str = "[redacted]";
+
+/// Where a task's archives are pushed once they are written
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StorageTarget {
+    /// Keep archives on this host only
+    Local,
+    /// An S3-compatible bucket (AWS, MinIO, Wasabi, ...)
+    S3(S3Target),
+    /// A directory on an SSH server, written over SFTP
+    Sftp(SftpTarget),
+    /// A Google Cloud Storage bucket, authenticated with a service account
+    Gcs(GcsTarget),
+    /// A WebDAV collection, e.g. a Nextcloud folder
+    Webdav(WebdavTarget),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct S3Target {
+    /// Endpoint URL for non-AWS services, e.g. `https://minio.local:9000`; AWS when not set
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    #[serde(default = "default_region")]
+    pub region: String,
+    pub bucket: String,
+    /// Key prefix inside the bucket, e.g. `rdumper/prod`
+    #[serde(default)]
+    pub prefix: String,
+    pub access_key_id: String,
+    #[serde(default)]
+    pub secret_access_key: String,
+    /// Address the bucket as `endpoint/bucket` instead of `bucket.endpoint`; most MinIO setups need this
+    #[serde(default)]
+    pub path_style: bool,
+}
+
+fn default_region() -> String {
+    "us-east-1".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct SftpTarget {
+    pub host: String,
+    #[serde(default = "default_ssh_port")]
+    pub port: u16,
+    pub username: String,
+    /// Password authentication; ignored when a private key is set
+    #[serde(default)]
+    pub password: Option<String>,
+    /// PEM/OpenSSH private key; without key or password the server's default keys and agent are used
+    #[serde(default)]
+    pub private_key: Option<String>,
+    /// Public key the server must present, as printed by `ssh-keyscan`, e.g. `ssh-ed25519 AAAA...`;
+    /// one per line to accept several. Without one the server has to be in the container's known_hosts
+    #[serde(default)]
+    pub host_key: Option<String>,
+    /// Directory on the server that receives one folder per backup
+    pub remote_path: String,
+}
+
+impl SftpTarget {
+    /// Path of `path` below the remote directory; relative remote paths start in the login directory
+    pub fn remote_file(&self, path: &str) -> String {
+        let base = self.remote_path.trim_end_matches('/');
+        match (base, path) {
+            // The filesystem root itself
+            ("", "") if self.remote_path.starts_with('/') => "/".to_string(),
+            ("", path) if self.remote_path.starts_with('/') => format!("/{}", path),
+            (base, "") => base.to_string(),
+            ("", path) => path.to_string(),
+            (base, path) => format!("{}/{}", base, path),
+        }
+    }
+
+    /// `sftp://` URL of a file below the remote directory, `/~/` marking paths relative to the login directory
+    pub fn url(&self, path: &str) -> String {
+        let file = self.remote_file(path);
+        let file = if file.starts_with('/') { file } else { format!("/~/{}", file) };
+        format!("sftp://{}@{}:{}{}", self.username, self.host, self.port, file)
+    }
+}
+
+fn default_ssh_port() -> u16 {
+    22
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct GcsTarget {
+    pub bucket: String,
+    /// Object name prefix, e.g. `rdumper/prod`
+    #[serde(default)]
+    pub prefix: String,
+    /// The service account key file as downloaded from the Cloud Console
+    #[serde(default)]
+    pub credentials_json: String,
+    /// API endpoint for emulators; `https://storage.googleapis.com` when not set
+    #[serde(default)]
+    pub endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct WebdavTarget {
+    /// Collection that receives one folder per backup, e.g.
+    /// `https://cloud.example.com/remote.php/dav/files/alice/Backups`
+    pub url: String,
+    pub username: String,
+    /// Password or, for Nextcloud, an app password
+    #[serde(default)]
+    pub password: String,
+    /// Upload through Nextcloud's chunking API instead of a single streamed PUT
+    #[serde(default)]
+    pub nextcloud_chunking: bool,
+}
+
+impl StorageTarget {
+    /// Copy for API responses, with passwords, keys and credentials replaced by [`REDACTED_SECRET`]
+    pub fn redacted(&self) -> Self {
+        fn redact(secret: &mut String) {
+            if !secret.is_empty() {
+                *secret = REDACTED_SECRET.to_string();
+            }
+        }
+        let mut target = self.clone();
+        match &mut target {
+            StorageTarget::Local => {}
+            StorageTarget::S3(s3) => redact(&mut s3.secret_access_key),
+            StorageTarget::Sftp(sftp) => sftp.password.iter_mut().chain(sftp.private_key.iter_mut()).for_each(redact),
+            StorageTarget::Gcs(gcs) => redact(&mut gcs.credentials_json),
+            StorageTarget::Webdav(webdav) => redact(&mut webdav.password),
+        }
+        target
+    }
+
+    /// Take the credentials an update sent back as [`REDACTED_SECRET`] from `stored`, as well as
+    /// required ones it left out; optional SFTP credentials that are left out are removed
+    pub fn keep_secrets(&mut self, stored: &StorageTarget) {
+        fn keep(secret: &mut String, stored: &str) {
+            if secret.is_empty() || secret == REDACTED_SECRET {
+                *secret = stored.to_string();
+            }
+        }
+        fn keep_optional(secret: &mut Option<String>, stored: &Option<String>) {
+            if secret.as_deref() == Some(REDACTED_SECRET) {
+                *secret = stored.clone();
+            }
+        }
+        match (self, stored) {
+            (StorageTarget::S3(s3), StorageTarget::S3(stored)) => keep(&mut s3.secret_access_key, &stored.secret_access_key),
+            (StorageTarget::Sftp(sftp), StorageTarget::Sftp(stored)) => {
+                keep_optional(&mut sftp.password, &stored.password);
+                keep_optional(&mut sftp.private_key, &stored.private_key);
+            }
+            (StorageTarget::Gcs(gcs), StorageTarget::Gcs(stored)) => keep(&mut gcs.credentials_json, &stored.credentials_json),
+            (StorageTarget::Webdav(webdav), StorageTarget::Webdav(stored)) => keep(&mut webdav.password, &stored.password),
+            _ => {}
+        }
+    }
+}
+
+impl std::fmt::Display for StorageTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageTarget::Local => write!(f, "local storage"),
+            StorageTarget::S3(s3) => match s3.prefix.trim_matches('/') {
+                "" => write!(f, "s3://{}", s3.bucket),
+                prefix => write!(f, "s3://{}/{}", s3.bucket, prefix),
+            },
+            StorageTarget::Sftp(sftp) => write!(f, "{}", sftp.url("")),
+            StorageTarget::Gcs(gcs) => match gcs.prefix.trim_matches('/') {
+                "" => write!(f, "gs://{}", gcs.bucket),
+                prefix => write!(f, "gs://{}/{}", gcs.bucket, prefix),
+            },
+            StorageTarget::Webdav(webdav) => write!(f, "{}", webdav.url.trim_end_matches('/')),
+        }
+    }
+}
+
+/// Where a backup's archive is available
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupLocation {
+    #[default]
+    Local,
+    /// Uploaded and the local archive removed
+    Remote,
+    LocalAndRemote,
+}
+
+impl BackupLocation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackupLocation::Local => "local",
+            BackupLocation::Remote => "remote",
+            BackupLocation::LocalAndRemote => "local_and_remote",
+        }
+    }
+}
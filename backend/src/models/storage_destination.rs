@@ -0,0 +1,82 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize, Serializer};
+use sqlx::types::Json;
+use sqlx::FromRow;
+use uuid::Uuid;
+use super::storage::StorageTarget;
+use utoipa::ToSchema;
+
+/// A remote target with its credentials, stored once and referenced by tasks
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct StorageDestination {
+    pub id: String,
+    pub name: String,
+    #[serde(serialize_with = "serialize_redacted")]
+    #[schema(value_type = StorageTarget)]
+    pub target: Json<StorageTarget>,
+    pub quota_bytes: Option<i64>, // Maximum total size of everything stored at the destination
+    pub connection_status: String, // "untested", "success", "failed"
+    pub last_tested: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateStorageDestinationRequest {
+    pub name: String,
+    pub target: StorageTarget,
+    pub quota_bytes: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateStorageDestinationRequest {
+    pub name: Option<String>,
+    pub target: Option<StorageTarget>,
+    pub quota_bytes: Option<i64>, // 0 removes the quota
+}
+
+impl StorageDestination {
+    pub fn new(req: CreateStorageDestinationRequest) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name: req.name,
+            target: Json(req.target),
+            quota_bytes: req.quota_bytes.filter(|bytes| *bytes > 0),
+            connection_status: "untested".to_string(),
+            last_tested: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn update(&mut self, req: UpdateStorageDestinationRequest) {
+        if let Some(name) = req.name {
+            self.name = name;
+        }
+        if let Some(mut target) = req.target {
+            target.keep_secrets(&self.target.0);
+            // Different settings haven't been tested yet
+            if target != self.target.0 {
+                self.connection_status = "untested".to_string();
+                self.last_tested = None;
+            }
+            self.target = Json(target);
+        }
+        if let Some(quota_bytes) = req.quota_bytes {
+            self.quota_bytes = (quota_bytes > 0).then_some(quota_bytes);
+        }
+        self.updated_at = Utc::now();
+    }
+
+    pub fn mark_connection_tested(&mut self, success: bool) {
+        self.connection_status = if success { "success".to_string() } else { "failed".to_string() };
+        self.last_tested = Some(Utc::now());
+        self.updated_at = Utc::now();
+    }
+}
+
+/// Credentials never leave the API; the database column is written from the target itself
+fn serialize_redacted<S: Serializer>(target: &Json<StorageTarget>, serializer: S) -> Result<S::Ok, S::Error> {
+    target.0.redacted().serialize(serializer)
+}
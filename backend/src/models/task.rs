@@ -1,9 +1,13 @@
-use chrono::{DateTime, Utc, Duration, Timelike, Datelike};
+use std::str::FromStr;
+use chrono::{DateTime, Utc, Duration};
+use chrono_tz::Tz;
+use cron::Schedule;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, ToSchema)]
 pub enum CompressionType {
     #[serde(rename = "none")]
     None,
@@ -42,7 +46,66 @@ impl std::str::FromStr for CompressionType {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+/// Which finished backups of a task send notifications
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyOn {
+    #[default]
+    Never,
+    Failure,
+    Always,
+}
+
+impl std::fmt::Display for NotifyOn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotifyOn::Never => write!(f, "never"),
+            NotifyOn::Failure => write!(f, "failure"),
+            NotifyOn::Always => write!(f, "always"),
+        }
+    }
+}
+
+impl std::str::FromStr for NotifyOn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "never" => Ok(NotifyOn::Never),
+            "failure" => Ok(NotifyOn::Failure),
+            "always" => Ok(NotifyOn::Always),
+            _ => Err(format!("Invalid notification setting: {}", s)),
+        }
+    }
+}
+
+/// Addresses from a comma separated list, without blanks
+pub fn split_addresses(list: &str) -> Vec<String> {
+    list.split(',').map(str::trim).filter(|address| !address.is_empty()).map(str::to_string).collect()
+}
+
+/// Number of upcoming runs sampled to determine a schedule's interval
+const INTERVAL_SAMPLE_RUNS: usize = 8;
+
+/// Timezone of tasks that don't name one
+pub const DEFAULT_TIMEZONE: &str = "UTC";
+
+/// Wait before the first retry of a failed run when the task sets none
+const DEFAULT_RETRY_BACKOFF_SECONDS: i32 = 60;
+
+/// Upper bound for the doubling retry backoff
+const MAX_RETRY_DELAY_HOURS: i64 = 6;
+
+/// Upper bound for a task's mydumper threads
+const MAX_THREADS: i32 = 64;
+
+/// Longest row filter accepted, well below mydumper's query limits
+const MAX_WHERE_CLAUSE_LENGTH: usize = 1000;
+
+/// Weekday names by their number in standard cron, where both 0 and 7 are Sunday
+const WEEKDAYS: [&str; 8] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT", "SUN"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Task {
     pub id: String,
     pub name: String,
@@ -55,11 +118,34 @@ pub struct Task {
     pub is_active: bool,
     pub last_run: Option<DateTime<Utc>>,
     pub next_run: Option<DateTime<Utc>>,
+    pub pause_after_failures: Option<i32>, // Disable the task after this many consecutive failed runs
+    pub consecutive_failures: i32,
+    pub auto_paused_at: Option<DateTime<Utc>>,
+    pub verify_interval_days: Option<i32>, // Verify a random backup of this task every N days
+    pub storage_destination_id: Option<String>, // Storage destination finished archives are uploaded to; None keeps them local
+    pub keep_local: bool, // Keep the local archive after a successful upload
+    pub bandwidth_limit_kib: Option<i32>, // Upload and download limit in KiB/s, on top of the global one
+    pub timezone: String, // IANA zone the cron schedule is evaluated in, e.g. "Europe/Berlin"
+    pub retry_max_attempts: Option<i32>, // Attempts per run including the first; failed backups are retried until reached
+    pub retry_backoff_seconds: Option<i32>, // Wait before the first retry, doubled for each further one
+    pub run_once_at: Option<DateTime<Utc>>, // One-off run on top of the schedule, also for inactive tasks; cleared once due
+    pub include_tables: Option<String>, // Comma separated table patterns to back up, `*` and `?` as wildcards; None means all
+    pub exclude_tables: Option<String>, // Comma separated table patterns to leave out
+    pub where_clause: Option<String>, // mydumper --where condition applied to every dumped table, e.g. "created_at > NOW() - INTERVAL 30 DAY"
+    pub threads: Option<i32>, // mydumper --threads; None uses the default of 4
+    pub rows_per_chunk: Option<i32>, // mydumper --rows, splits tables into chunks of this many rows
+    pub chunk_filesize_mb: Option<i32>, // mydumper --chunk-filesize, splits tables into files of this size
+    pub long_query_guard_seconds: Option<i32>, // mydumper --long-query-guard; None keeps mydumper's default
+    pub binlog_interval_minutes: Option<i32>, // Archive binary logs into the newest full backup every N minutes
+    pub encryption_key: Option<String>, // Name of the key in the secrets directory that encrypts this task's archives
+    pub timeout_minutes: Option<i32>, // mydumper is killed and the job failed once a dump runs longer
+    pub notify_on: String, // "never", "failure" or "always": which finished backups send notifications
+    pub notify_email: Option<String>, // Comma separated recipients of the task's emails; None uses --notify-email-to
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CreateTaskRequest {
     pub name: String,
     pub database_config_id: String,
@@ -68,9 +154,29 @@ pub struct CreateTaskRequest {
     pub compression_type: Option<CompressionType>,
     pub cleanup_days: Option<i32>,
     pub use_non_transactional: Option<bool>,
+    pub pause_after_failures: Option<i32>,
+    pub verify_interval_days: Option<i32>,
+    pub storage_destination_id: Option<String>,
+    pub keep_local: Option<bool>,
+    pub bandwidth_limit_kib: Option<i32>,
+    pub timezone: Option<String>, // Defaults to UTC
+    pub retry_max_attempts: Option<i32>,
+    pub retry_backoff_seconds: Option<i32>,
+    pub include_tables: Option<String>,
+    pub exclude_tables: Option<String>,
+    pub where_clause: Option<String>,
+    pub threads: Option<i32>,
+    pub rows_per_chunk: Option<i32>,
+    pub chunk_filesize_mb: Option<i32>,
+    pub long_query_guard_seconds: Option<i32>,
+    pub binlog_interval_minutes: Option<i32>,
+    pub encryption_key: Option<String>,
+    pub timeout_minutes: Option<i32>,
+    pub notify_on: Option<NotifyOn>, // Defaults to never
+    pub notify_email: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UpdateTaskRequest {
     pub name: Option<String>,
     pub database_name: Option<String>,
@@ -79,6 +185,33 @@ pub struct UpdateTaskRequest {
     pub cleanup_days: Option<i32>,
     pub use_non_transactional: Option<bool>,
     pub is_active: Option<bool>,
+    pub pause_after_failures: Option<i32>, // 0 turns auto-pausing off
+    pub verify_interval_days: Option<i32>, // 0 turns scheduled verification off
+    pub storage_destination_id: Option<String>, // Empty string stops uploading
+    pub keep_local: Option<bool>,
+    pub bandwidth_limit_kib: Option<i32>, // 0 removes the task's limit
+    pub timezone: Option<String>, // Empty string resets to UTC
+    pub retry_max_attempts: Option<i32>, // 0 turns retries off
+    pub retry_backoff_seconds: Option<i32>, // 0 restores the default backoff
+    pub include_tables: Option<String>, // Empty string backs up all tables again
+    pub exclude_tables: Option<String>, // Empty string removes the exclusions
+    pub where_clause: Option<String>, // Empty string backs up all rows again
+    pub threads: Option<i32>, // 0 restores the default
+    pub rows_per_chunk: Option<i32>, // 0 turns row chunking off
+    pub chunk_filesize_mb: Option<i32>, // 0 turns file size chunking off
+    pub long_query_guard_seconds: Option<i32>, // 0 restores mydumper's default
+    pub binlog_interval_minutes: Option<i32>, // 0 turns binlog archiving off
+    pub encryption_key: Option<String>, // Empty string stores new archives unencrypted again
+    pub timeout_minutes: Option<i32>, // 0 removes the timeout
+    pub notify_on: Option<NotifyOn>,
+    pub notify_email: Option<String>, // Empty string uses the default recipients again
+}
+
+/// A task's `next_run` and `last_run` in its own timezone, as RFC 3339 with the local offset
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct LocalRunTimes {
+    pub next_run_local: Option<String>,
+    pub last_run_local: Option<String>,
 }
 
 impl Task {
@@ -96,6 +229,29 @@ impl Task {
             is_active: true,
             last_run: None,
             next_run: None, // Will be calculated when task is saved
+            pause_after_failures: req.pause_after_failures.filter(|n| *n > 0),
+            consecutive_failures: 0,
+            auto_paused_at: None,
+            verify_interval_days: req.verify_interval_days.filter(|n| *n > 0),
+            storage_destination_id: req.storage_destination_id.filter(|id| !id.is_empty()),
+            keep_local: req.keep_local.unwrap_or(true),
+            bandwidth_limit_kib: req.bandwidth_limit_kib.filter(|kib| *kib > 0),
+            timezone: req.timezone.filter(|tz| !tz.is_empty()).unwrap_or_else(|| DEFAULT_TIMEZONE.to_string()),
+            retry_max_attempts: req.retry_max_attempts.filter(|n| *n > 1),
+            retry_backoff_seconds: req.retry_backoff_seconds.filter(|secs| *secs > 0),
+            run_once_at: None,
+            include_tables: req.include_tables.filter(|patterns| !patterns.trim().is_empty()),
+            exclude_tables: req.exclude_tables.filter(|patterns| !patterns.trim().is_empty()),
+            where_clause: req.where_clause.map(|clause| clause.trim().to_string()).filter(|clause| !clause.is_empty()),
+            threads: req.threads.filter(|n| *n > 0),
+            rows_per_chunk: req.rows_per_chunk.filter(|n| *n > 0),
+            chunk_filesize_mb: req.chunk_filesize_mb.filter(|n| *n > 0),
+            long_query_guard_seconds: req.long_query_guard_seconds.filter(|n| *n > 0),
+            binlog_interval_minutes: req.binlog_interval_minutes.filter(|n| *n > 0),
+            encryption_key: req.encryption_key.map(|key| key.trim().to_string()).filter(|key| !key.is_empty()),
+            timeout_minutes: req.timeout_minutes.filter(|n| *n > 0),
+            notify_on: req.notify_on.unwrap_or_default().to_string(),
+            notify_email: req.notify_email.map(|emails| emails.trim().to_string()).filter(|emails| !emails.is_empty()),
             created_at: now,
             updated_at: now,
         }
@@ -121,150 +277,255 @@ impl Task {
             self.use_non_transactional = use_non_transactional;
         }
         if let Some(is_active) = req.is_active {
+            if is_active && !self.is_active {
+                self.reset_failures();
+            }
             self.is_active = is_active;
         }
+        if let Some(pause_after_failures) = req.pause_after_failures {
+            self.pause_after_failures = (pause_after_failures > 0).then_some(pause_after_failures);
+        }
+        if let Some(verify_interval_days) = req.verify_interval_days {
+            self.verify_interval_days = (verify_interval_days > 0).then_some(verify_interval_days);
+        }
+        if let Some(storage_destination_id) = req.storage_destination_id {
+            self.storage_destination_id = (!storage_destination_id.is_empty()).then_some(storage_destination_id);
+        }
+        if let Some(keep_local) = req.keep_local {
+            self.keep_local = keep_local;
+        }
+        if let Some(bandwidth_limit_kib) = req.bandwidth_limit_kib {
+            self.bandwidth_limit_kib = (bandwidth_limit_kib > 0).then_some(bandwidth_limit_kib);
+        }
+        if let Some(timezone) = req.timezone {
+            self.timezone = if timezone.is_empty() { DEFAULT_TIMEZONE.to_string() } else { timezone };
+        }
+        if let Some(retry_max_attempts) = req.retry_max_attempts {
+            self.retry_max_attempts = (retry_max_attempts > 1).then_some(retry_max_attempts);
+        }
+        if let Some(retry_backoff_seconds) = req.retry_backoff_seconds {
+            self.retry_backoff_seconds = (retry_backoff_seconds > 0).then_some(retry_backoff_seconds);
+        }
+        if let Some(include_tables) = req.include_tables {
+            self.include_tables = (!include_tables.trim().is_empty()).then_some(include_tables);
+        }
+        if let Some(exclude_tables) = req.exclude_tables {
+            self.exclude_tables = (!exclude_tables.trim().is_empty()).then_some(exclude_tables);
+        }
+        if let Some(where_clause) = req.where_clause {
+            let where_clause = where_clause.trim().to_string();
+            self.where_clause = (!where_clause.is_empty()).then_some(where_clause);
+        }
+        if let Some(threads) = req.threads {
+            self.threads = (threads > 0).then_some(threads);
+        }
+        if let Some(rows_per_chunk) = req.rows_per_chunk {
+            self.rows_per_chunk = (rows_per_chunk > 0).then_some(rows_per_chunk);
+        }
+        if let Some(chunk_filesize_mb) = req.chunk_filesize_mb {
+            self.chunk_filesize_mb = (chunk_filesize_mb > 0).then_some(chunk_filesize_mb);
+        }
+        if let Some(long_query_guard_seconds) = req.long_query_guard_seconds {
+            self.long_query_guard_seconds = (long_query_guard_seconds > 0).then_some(long_query_guard_seconds);
+        }
+        if let Some(binlog_interval_minutes) = req.binlog_interval_minutes {
+            self.binlog_interval_minutes = (binlog_interval_minutes > 0).then_some(binlog_interval_minutes);
+        }
+        if let Some(encryption_key) = req.encryption_key {
+            let encryption_key = encryption_key.trim().to_string();
+            self.encryption_key = (!encryption_key.is_empty()).then_some(encryption_key);
+        }
+        if let Some(timeout_minutes) = req.timeout_minutes {
+            self.timeout_minutes = (timeout_minutes > 0).then_some(timeout_minutes);
+        }
+        if let Some(notify_on) = req.notify_on {
+            self.notify_on = notify_on.to_string();
+        }
+        if let Some(notify_email) = req.notify_email {
+            let notify_email = notify_email.trim().to_string();
+            self.notify_email = (!notify_email.is_empty()).then_some(notify_email);
+        }
         self.updated_at = Utc::now();
     }
 
+    /// Clear the failure streak, e.g. when the task is re-enabled
+    pub fn reset_failures(&mut self) {
+        self.consecutive_failures = 0;
+        self.auto_paused_at = None;
+    }
+
     pub fn compression_type(&self) -> Result<CompressionType, String> {
         self.compression_type.parse()
     }
 
-    /// Calculate the next run time based on the cron schedule
-    pub fn calculate_next_run(&self) -> Result<Option<DateTime<Utc>>, String> {
-        if !self.is_active {
-            return Ok(None);
-        }
+    pub fn notify_on(&self) -> NotifyOn {
+        self.notify_on.parse().unwrap_or_default()
+    }
 
-        // Simple cron parser for common patterns
-        let next_run = self.parse_cron_schedule(&self.cron_schedule)?;
-        Ok(Some(next_run))
+    /// Recipients of the task's emails, if it names its own
+    pub fn notify_emails(&self) -> Vec<String> {
+        split_addresses(self.notify_email.as_deref().unwrap_or_default())
     }
 
-    /// Simple cron parser for common patterns
-    fn parse_cron_schedule(&self, cron_expr: &str) -> Result<DateTime<Utc>, String> {
-        let parts: Vec<&str> = cron_expr.split_whitespace().collect();
-        if parts.len() != 5 {
-            return Err(format!("Invalid cron format. Expected 5 parts, got {}", parts.len()));
+    /// Every recipient must look like an address; line breaks would end up in SMTP commands and headers
+    pub fn validate_notify_email(&self) -> Result<(), String> {
+        for address in self.notify_emails() {
+            let valid = address.split_once('@').is_some_and(|(local, domain)| !local.is_empty() && !domain.is_empty())
+                && !address.chars().any(|c| c.is_whitespace() || c.is_control() || matches!(c, '<' | '>'));
+            if !valid {
+                return Err(format!("Invalid notification email address: {}", address));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reject row filters that could carry more than a single condition into mydumper's queries.
+    ///
+    /// Statement separators and comments are refused outside of string literals, and quotes
+    /// and parentheses must be balanced.
+    pub fn validate_where_clause(&self) -> Result<(), String> {
+        let Some(clause) = &self.where_clause else {
+            return Ok(());
+        };
+        if clause.len() > MAX_WHERE_CLAUSE_LENGTH {
+            return Err(format!("WHERE clause is longer than {} characters", MAX_WHERE_CLAUSE_LENGTH));
         }
 
-        let now = Utc::now();
-        
-        // Handle common patterns
-        match cron_expr {
-            "* * * * *" => {
-                // Every minute - next minute
-                Ok(now + Duration::minutes(1))
-            },
-            "0 * * * *" => {
-                // Every hour at minute 0
-                let next_hour = now + Duration::hours(1);
-                Ok(DateTime::from_timestamp(next_hour.timestamp(), 0)
-                    .unwrap_or(next_hour)
-                    .with_minute(0)
-                    .unwrap_or(next_hour)
-                    .with_second(0)
-                    .unwrap_or(next_hour)
-                    .with_nanosecond(0)
-                    .unwrap_or(next_hour))
-            },
-            "0 0 * * *" => {
-                // Daily at midnight
-                let tomorrow = now + Duration::days(1);
-                Ok(DateTime::from_timestamp(tomorrow.timestamp(), 0)
-                    .unwrap_or(tomorrow)
-                    .with_hour(0)
-                    .unwrap_or(tomorrow)
-                    .with_minute(0)
-                    .unwrap_or(tomorrow)
-                    .with_second(0)
-                    .unwrap_or(tomorrow)
-                    .with_nanosecond(0)
-                    .unwrap_or(tomorrow))
-            },
-            "0 0 * * 1" => {
-                // Weekly on Monday at midnight
-                let days_until_monday = (8 - now.weekday().num_days_from_monday()) % 7;
-                let next_monday = if days_until_monday == 0 {
-                    now + Duration::days(7) // Next Monday if today is Monday
-                } else {
-                    now + Duration::days(days_until_monday as i64)
-                };
-                Ok(DateTime::from_timestamp(next_monday.timestamp(), 0)
-                    .unwrap_or(next_monday)
-                    .with_hour(0)
-                    .unwrap_or(next_monday)
-                    .with_minute(0)
-                    .unwrap_or(next_monday)
-                    .with_second(0)
-                    .unwrap_or(next_monday)
-                    .with_nanosecond(0)
-                    .unwrap_or(next_monday))
-            },
-            _ => {
-                // Try to parse as specific time pattern (minute hour * * *)
-                if let Some(next_run) = self.parse_specific_time_pattern(&parts, now) {
-                    Ok(next_run)
-                } else if let Some(interval) = self.parse_interval_pattern(cron_expr) {
-                    Ok(now + interval)
-                } else {
-                    Err(format!("Unsupported cron pattern: {}", cron_expr))
+        let mut quote = None;
+        let mut depth = 0i32;
+        let mut chars = clause.chars().peekable();
+        while let Some(c) = chars.next() {
+            if let Some(open) = quote {
+                if c == '\\' {
+                    chars.next();
+                } else if c == open {
+                    quote = None;
                 }
+                continue;
+            }
+            match c {
+                '\'' | '"' | '`' => quote = Some(c),
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth < 0 {
+                        return Err("Unbalanced parentheses in WHERE clause".to_string());
+                    }
+                }
+                ';' => return Err("WHERE clause must not contain ';'".to_string()),
+                '#' => return Err("WHERE clause must not contain comments".to_string()),
+                '-' if chars.peek() == Some(&'-') => return Err("WHERE clause must not contain comments".to_string()),
+                '/' if chars.peek() == Some(&'*') => return Err("WHERE clause must not contain comments".to_string()),
+                _ => {}
             }
         }
+        if quote.is_some() {
+            return Err("Unterminated string in WHERE clause".to_string());
+        }
+        if depth != 0 {
+            return Err("Unbalanced parentheses in WHERE clause".to_string());
+        }
+        Ok(())
     }
 
-    /// Parse specific time patterns like "0 1 * * *" (daily at 1:00 AM)
-    fn parse_specific_time_pattern(&self, parts: &[&str], now: DateTime<Utc>) -> Option<DateTime<Utc>> {
-        // Pattern: minute hour * * *
-        if parts[2] == "*" && parts[3] == "*" && parts[4] == "*" {
-            if let (Ok(minute), Ok(hour)) = (parts[0].parse::<u32>(), parts[1].parse::<u32>()) {
-                if minute <= 59 && hour <= 23 {
-                    // Calculate next occurrence of this time
-                    let mut next_run = now
-                        .with_hour(hour)
-                        .unwrap_or(now)
-                        .with_minute(minute)
-                        .unwrap_or(now)
-                        .with_second(0)
-                        .unwrap_or(now)
-                        .with_nanosecond(0)
-                        .unwrap_or(now);
-
-                    // If the time has already passed today, schedule for tomorrow
-                    if next_run <= now {
-                        next_run = next_run + Duration::days(1);
-                    }
+    /// mydumper runs as many connections as threads, so keep it to what a server reasonably takes
+    pub fn validate_threads(&self) -> Result<(), String> {
+        match self.threads {
+            Some(threads) if threads > MAX_THREADS => Err(format!("Threads must be at most {}", MAX_THREADS)),
+            _ => Ok(()),
+        }
+    }
 
-                    return Some(next_run);
-                }
-            }
+    pub fn timezone(&self) -> Result<Tz, String> {
+        Tz::from_str(&self.timezone).map_err(|_| format!("Invalid timezone: {}", self.timezone))
+    }
+
+    pub fn local_run_times(&self) -> LocalRunTimes {
+        let tz = self.timezone().unwrap_or(Tz::UTC);
+        LocalRunTimes {
+            next_run_local: self.next_run.map(|t| t.with_timezone(&tz).to_rfc3339()),
+            last_run_local: self.last_run.map(|t| t.with_timezone(&tz).to_rfc3339()),
         }
-        None
     }
 
-    /// Parse interval patterns like "*/5 * * * *" (every 5 minutes)
-    fn parse_interval_pattern(&self, cron_expr: &str) -> Option<Duration> {
-        let parts: Vec<&str> = cron_expr.split_whitespace().collect();
-        if parts.len() != 5 {
+    /// When to retry a run whose `attempt` just failed, or None once all attempts are used up
+    pub fn retry_delay(&self, attempt: i32) -> Option<Duration> {
+        let max_attempts = self.retry_max_attempts?;
+        if attempt >= max_attempts {
             return None;
         }
+        let backoff = Duration::seconds(self.retry_backoff_seconds.unwrap_or(DEFAULT_RETRY_BACKOFF_SECONDS) as i64);
+        let delay = backoff * 2i32.pow((attempt - 1).clamp(0, 16) as u32);
+        Some(delay.min(Duration::hours(MAX_RETRY_DELAY_HOURS)))
+    }
+
+    /// Calculate the next run time based on the cron schedule
+    ///
+    /// A pending one-off run comes first if it is earlier, and is the only run of an inactive task.
+    pub fn calculate_next_run(&self) -> Result<Option<DateTime<Utc>>, String> {
+        let scheduled = if self.is_active {
+            Some(self.parse_cron_schedule(&self.cron_schedule, Utc::now())?)
+        } else {
+            None
+        };
+        Ok(match (scheduled, self.run_once_at) {
+            (Some(scheduled), Some(run_once_at)) => Some(scheduled.min(run_once_at)),
+            (scheduled, run_once_at) => scheduled.or(run_once_at),
+        })
+    }
 
-        // Check for interval patterns in minutes
-        if parts[0].starts_with("*/") && parts[1] == "*" && parts[2] == "*" && parts[3] == "*" && parts[4] == "*" {
-            if let Ok(minutes) = parts[0][2..].parse::<i64>() {
-                return Some(Duration::minutes(minutes));
+    /// Scheduled run times from `from` up to `until`, at most `limit` of them, including a pending one-off run
+    pub fn upcoming_runs(&self, from: DateTime<Utc>, until: DateTime<Utc>, limit: usize) -> Result<Vec<DateTime<Utc>>, String> {
+        let mut runs: Vec<_> = self.run_once_at
+            .filter(|run_once_at| (from..=until).contains(run_once_at))
+            .into_iter()
+            .collect();
+        if self.is_active {
+            // Start from the stored next run so interval schedules stay in phase with the worker
+            let mut next_run = match self.next_run {
+                Some(next_run) if next_run >= from && self.next_run != self.run_once_at => next_run,
+                _ => self.parse_cron_schedule(&self.cron_schedule, from)?,
+            };
+            while next_run <= until && runs.len() <= limit {
+                runs.push(next_run);
+                next_run = self.parse_cron_schedule(&self.cron_schedule, next_run)?;
             }
         }
+        runs.sort();
+        runs.dedup();
+        runs.truncate(limit);
+        Ok(runs)
+    }
 
-        // Check for interval patterns in hours
-        if parts[0] == "0" && parts[1].starts_with("*/") && parts[2] == "*" && parts[3] == "*" && parts[4] == "*" {
-            if let Ok(hours) = parts[1][2..].parse::<i64>() {
-                return Some(Duration::hours(hours));
-            }
+    /// Longest gap between consecutive scheduled runs, sampled over the next few runs.
+    ///
+    /// Uneven schedules (e.g. weekdays only) report their longest gap, so a weekend isn't a miss.
+    pub fn schedule_interval(&self) -> Result<Duration, String> {
+        let mut previous = self.parse_cron_schedule(&self.cron_schedule, Utc::now())?;
+        let mut interval = Duration::zero();
+        for _ in 0..INTERVAL_SAMPLE_RUNS {
+            let next = self.parse_cron_schedule(&self.cron_schedule, previous)?;
+            interval = interval.max(next - previous);
+            previous = next;
         }
+        Ok(interval)
+    }
 
-        None
+    /// First run of the cron schedule after `now`
+    fn parse_cron_schedule(&self, cron_expr: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+        // Evaluate in the task's zone so "0 2 * * *" means 02:00 local time, across DST changes too
+        let now = now.with_timezone(&self.timezone()?);
+        let mut next_runs = Vec::new();
+        for expr in to_cron_crate_syntax(cron_expr)? {
+            let schedule = Schedule::from_str(&expr)
+                // The crate's message points into the translated expression; its last line is the reason
+                .map_err(|e| format!("Invalid cron expression '{}': {}", cron_expr, e.to_string().lines().last().unwrap_or_default()))?;
+            next_runs.extend(schedule.after(&now).next());
+        }
+        next_runs.into_iter()
+            .min()
+            .map(|next| next.with_timezone(&Utc))
+            .ok_or_else(|| format!("Cron expression '{}' never runs again", cron_expr))
     }
 
     /// Update the next run time based on current cron schedule
@@ -277,13 +538,20 @@ impl Task {
     /// Mark task as executed and calculate next run
     pub fn mark_executed(&mut self) -> Result<(), String> {
         self.last_run = Some(Utc::now());
-        self.update_next_run()?;
-        Ok(())
+        self.skip_due_run()
+    }
+
+    /// Move on to the next run without executing the due one; a due one-off run is used up either way
+    pub fn skip_due_run(&mut self) -> Result<(), String> {
+        if self.run_once_at.is_some_and(|run_once_at| run_once_at <= Utc::now()) {
+            self.run_once_at = None;
+        }
+        self.update_next_run()
     }
 
     /// Check if the task should run now
     pub fn should_run_now(&self) -> bool {
-        if !self.is_active {
+        if !self.is_active && self.run_once_at.is_none() {
             return false;
         }
 
@@ -292,4 +560,65 @@ impl Task {
             None => false,
         }
     }
-}
\ No newline at end of file
+}
+
+/// Translate a standard five-field cron expression into the syntax of the `cron` crate.
+///
+/// The crate expects a leading seconds field and numbers weekdays from 1 for Sunday, so numeric
+/// weekdays are spelled out as names. The crate also requires both the day of month and the day
+/// of week to match, while cron runs on days matching either when both are restricted; such
+/// schedules become two expressions, one per field, whose runs are combined.
+fn to_cron_crate_syntax(cron_expr: &str) -> Result<Vec<String>, String> {
+    let parts: Vec<&str> = cron_expr.split_whitespace().collect();
+    if parts.len() != 5 {
+        return Err(format!("Invalid cron format. Expected 5 parts, got {}", parts.len()));
+    }
+    let weekdays = parts[4].split(',')
+        .map(weekday_names)
+        .collect::<Result<Vec<_>, _>>()?
+        .join(",");
+    let restricted = |field: &str| field != "*" && field != "?";
+    let days = if restricted(parts[2]) && restricted(parts[4]) {
+        vec![(parts[2], "*"), ("*", weekdays.as_str())]
+    } else {
+        vec![(parts[2], weekdays.as_str())]
+    };
+    Ok(days.into_iter()
+        .map(|(day_of_month, day_of_week)| format!("0 {} {} {} {} {}", parts[0], parts[1], day_of_month, parts[3], day_of_week))
+        .collect())
+}
+
+/// One item of the weekday field, numeric days and ranges expanded to names
+fn weekday_names(item: &str) -> Result<String, String> {
+    if item == "*" {
+        return Ok(item.to_string());
+    }
+    let (range, step) = match item.split_once('/') {
+        Some((range, step)) => {
+            let step = step.parse::<usize>().ok()
+                .filter(|step| *step > 0)
+                .ok_or_else(|| format!("Invalid step in day of week '{}'", item))?;
+            (range, step)
+        }
+        None => (item, 1),
+    };
+
+    let day = |value: &str| value.parse::<usize>().ok().filter(|day| *day < WEEKDAYS.len());
+    let (first, last) = match range.split_once('-') {
+        _ if range == "*" => (0, 6),
+        Some((first, last)) => match (day(first), day(last)) {
+            (Some(first), Some(last)) => (first, last),
+            // Names and out-of-range numbers are left for the crate to accept or reject
+            _ => return Ok(item.to_string()),
+        },
+        None => match day(range) {
+            Some(first) if step > 1 => (first, 7),
+            Some(first) => (first, first),
+            None => return Ok(item.to_string()),
+        },
+    };
+    if first > last {
+        return Err(format!("Invalid day of week range '{}'", item));
+    }
+    Ok((first..=last).step_by(step).map(|day| WEEKDAYS[day]).collect::<Vec<_>>().join(","))
+}
@@ -0,0 +1,42 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// Scope used when a request doesn't name one
+pub const GLOBAL_SCOPE: &str = "global";
+
+/// A frontend preference such as a dashboard layout or default filter, stored as JSON text
+#[derive(Debug, Clone, FromRow)]
+pub struct UiSetting {
+    /// `global` or a per-user scope such as `user:alice`
+    pub scope: String,
+    pub key: String,
+    pub value: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UiSettingResponse {
+    pub scope: String,
+    pub key: String,
+    pub value: serde_json::Value,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<UiSetting> for UiSettingResponse {
+    fn from(setting: UiSetting) -> Self {
+        Self {
+            // Values are only ever written through serde_json, so this can't fail in practice
+            value: serde_json::from_str(&setting.value).unwrap_or(serde_json::Value::Null),
+            scope: setting.scope,
+            key: setting.key,
+            updated_at: setting.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PutUiSettingRequest {
+    pub value: serde_json::Value,
+}
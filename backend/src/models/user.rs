@@ -0,0 +1,54 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// A person logging in to rDumper
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct User {
+    pub id: String,
+    pub username: String,
+    #[serde(skip_serializing)]
+    pub password_hash: String,
+    /// Bumped on password changes; tokens signed with an older version are refused
+    #[serde(skip_serializing)]
+    pub token_version: i64,
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateUserRequest {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ChangePasswordRequest {
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+impl User {
+    pub fn new(username: String, password_hash: String) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            username,
+            password_hash,
+            token_version: 0,
+            last_login_at: None,
+            created_at: Utc::now(),
+        }
+    }
+}
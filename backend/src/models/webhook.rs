@@ -0,0 +1,102 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::Json;
+use sqlx::FromRow;
+use uuid::Uuid;
+use utoipa::ToSchema;
+
+/// Events a webhook can subscribe to
+pub const WEBHOOK_EVENTS: &[&str] = &["job.started", "job.completed", "job.failed", "backup.deleted"];
+
+/// An HTTP endpoint that is POSTed to when jobs start, complete or fail and when backups are deleted.
+///
+/// Each call carries `X-Rdumper-Signature: sha256=<hex>`, the HMAC-SHA256 of the body keyed
+/// with the webhook's secret.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Webhook {
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub events: String, // Comma separated events from WEBHOOK_EVENTS; empty means all
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateWebhookRequest {
+    pub name: String,
+    pub url: String,
+    pub secret: Option<String>, // Generated when not given
+    pub events: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateWebhookRequest {
+    pub name: Option<String>,
+    pub url: Option<String>,
+    pub secret: Option<String>,
+    pub events: Option<Vec<String>>, // Empty list means all events
+    pub is_active: Option<bool>,
+}
+
+/// One event sent, or still to be sent, to a webhook
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub event: String,
+    #[schema(value_type = Object)]
+    pub payload: Json<Value>, // Body POSTed to the webhook
+    pub status: String, // "pending", "delivered" or "failed" once all attempts are used up
+    pub attempts: i64,
+    pub response_status: Option<i64>, // HTTP status of the last attempt
+    pub response_body: Option<String>, // Start of the last response's body
+    pub error: Option<String>, // Why the last attempt failed
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl Webhook {
+    pub fn new(req: CreateWebhookRequest, secret: String) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name: req.name.trim().to_string(),
+            url: req.url.trim().to_string(),
+            secret,
+            events: req.events.unwrap_or_default().join(","),
+            is_active: true,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn update(&mut self, req: UpdateWebhookRequest) {
+        if let Some(name) = req.name {
+            self.name = name.trim().to_string();
+        }
+        if let Some(url) = req.url {
+            self.url = url.trim().to_string();
+        }
+        if let Some(secret) = req.secret {
+            self.secret = secret;
+        }
+        if let Some(events) = req.events {
+            self.events = events.join(",");
+        }
+        if let Some(is_active) = req.is_active {
+            self.is_active = is_active;
+        }
+        self.updated_at = Utc::now();
+    }
+
+    /// Whether the webhook wants an event
+    pub fn subscribes_to(&self, event: &str) -> bool {
+        self.events.is_empty() || self.events.split(',').any(|e| e == event)
+    }
+}
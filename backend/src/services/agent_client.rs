@@ -0,0 +1,218 @@
+use std::path::Path;
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tokio::time::{sleep, Duration};
+use tokio_util::io::ReaderStream;
+use tracing::{error, info, warn};
+use crate::models::{AgentFailRequest, AgentJob, AgentPollRequest, AgentPollResponse, AgentProgressRequest, CreateJobRequest, Job, JobType};
+use crate::services::{integrity, FilesystemBackupService, MydumperService};
+use crate::state::AppConfig;
+
+/// Pause between polls while the central instance has no work
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+/// How often progress of a running job is reported; doubles as the heartbeat during long dumps
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How an agent reaches its central instance
+#[derive(Debug, Clone)]
+pub struct AgentSettings {
+    pub central_url: String,
+    pub token: String,
+}
+
+impl AgentSettings {
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.central_url.trim_end_matches('/'), path)
+    }
+}
+
+/// Poll the central instance for jobs and run them here, next to the databases.
+///
+/// Only outbound connections are made, so agents work behind firewalls the central instance can't cross.
+pub async fn run(pool: Arc<SqlitePool>, config: AppConfig, settings: AgentSettings) {
+    info!("Agent mode: taking jobs from {}", settings.central_url);
+    let client = reqwest::Client::new();
+
+    loop {
+        let poll = AgentPollRequest { version: Some(env!("CARGO_PKG_VERSION").to_string()) };
+        match post::<_, AgentPollResponse>(&client, &settings, "/api/agent/poll", &poll).await {
+            Ok(AgentPollResponse { job: Some(agent_job) }) => {
+                execute_job(&client, &settings, &pool, &config, agent_job).await;
+                // More work may be queued; ask again right away
+                continue;
+            }
+            Ok(AgentPollResponse { job: None }) => {}
+            Err(e) => warn!("Polling the central instance failed: {}", e),
+        }
+        sleep(POLL_INTERVAL).await;
+    }
+}
+
+async fn execute_job(
+    client: &reqwest::Client,
+    settings: &AgentSettings,
+    pool: &SqlitePool,
+    config: &AppConfig,
+    agent_job: AgentJob,
+) {
+    let AgentJob { job, task, database_config } = agent_job;
+    info!("Running job {} for task '{}'", job.id, task.name);
+
+    let result = async {
+        insert_local_job(pool, &job).await?;
+        let database_name = task.database_name.clone()
+            .or_else(|| database_config.get_database_name().cloned())
+            .ok_or_else(|| anyhow!("No database name specified for task and config has no default database"))?;
+
+        let forwarder = tokio::spawn(forward_progress(client.clone(), settings.clone(), pool.clone(), job.id.clone()));
+        let mydumper_service = MydumperService::new(config.backup_dir.clone(), config.log_dir.clone());
+        let backup = mydumper_service
+            .create_backup_with_progress(&database_config, &database_name, &task, job.id.clone(), pool)
+            .await;
+        forwarder.abort();
+
+        let archive = backup?;
+        upload_archive(client, settings, &job.id, Path::new(&archive)).await?;
+        Ok::<_, anyhow::Error>(archive)
+    }.await;
+
+    let status = match result {
+        Ok(archive) => {
+            info!("Job {} finished, archive handed to the central instance", job.id);
+            // The central instance keeps the catalog; the local copy has served its purpose
+            if let Some(folder) = Path::new(&archive).parent() {
+                if let Err(e) = tokio::fs::remove_dir_all(folder).await {
+                    warn!("Failed to remove local backup {}: {}", folder.display(), e);
+                }
+                FilesystemBackupService::invalidate_all_caches().await;
+            }
+            "completed"
+        }
+        Err(e) => {
+            error!("Job {} failed: {}", job.id, e);
+            let fail = AgentFailRequest { error: e.to_string() };
+            if let Err(report_error) = post::<_, serde_json::Value>(client, settings, &format!("/api/agent/jobs/{}/fail", job.id), &fail).await {
+                warn!("Failed to report failure of job {}: {}", job.id, report_error);
+            }
+            "failed"
+        }
+    };
+
+    let _ = sqlx::query("UPDATE jobs SET status = ?, completed_at = ? WHERE id = ?")
+        .bind(status)
+        .bind(Utc::now())
+        .bind(&job.id)
+        .execute(pool)
+        .await;
+}
+
+/// Local copy of the central job, so MydumperService can track progress exactly as for local jobs
+async fn insert_local_job(pool: &SqlitePool, central: &Job) -> Result<()> {
+    let mut job = Job::new(CreateJobRequest {
+        // The task only exists on the central instance
+        task_id: None,
+        used_database: central.used_database.clone(),
+        job_type: JobType::Backup,
+        backup_path: None,
+    });
+    job.id = central.id.clone();
+
+    sqlx::query(
+        r#"
+        INSERT OR REPLACE INTO jobs (id, task_id, used_database, job_type, status, progress, started_at, completed_at, error_message, log_output, backup_path, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&job.id)
+    .bind(&job.task_id)
+    .bind(&job.used_database)
+    .bind(&job.job_type)
+    .bind(&job.status)
+    .bind(job.progress)
+    .bind(job.started_at)
+    .bind(job.completed_at)
+    .bind(&job.error_message)
+    .bind(&job.log_output)
+    .bind(&job.backup_path)
+    .bind(job.created_at)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Mirror the local job's progress to the central instance until aborted
+async fn forward_progress(client: reqwest::Client, settings: AgentSettings, pool: SqlitePool, job_id: String) {
+    let path = format!("/api/agent/jobs/{}/progress", job_id);
+    loop {
+        sleep(PROGRESS_INTERVAL).await;
+        let progress: i32 = match sqlx::query_as::<_, (i32,)>("SELECT progress FROM jobs WHERE id = ?")
+            .bind(&job_id)
+            .fetch_one(&pool)
+            .await
+        {
+            Ok((progress,)) => progress,
+            Err(_) => continue,
+        };
+        if let Err(e) = post::<_, serde_json::Value>(&client, &settings, &path, &AgentProgressRequest { progress }).await {
+            warn!("Failed to report progress of job {}: {}", job_id, e);
+        }
+    }
+}
+
+/// Stream the finished archive to the central instance, which checks the hash and adds it to its catalog
+async fn upload_archive(client: &reqwest::Client, settings: &AgentSettings, job_id: &str, archive: &Path) -> Result<()> {
+    let sha256_hash = integrity::hash_file(archive.to_path_buf()).await?;
+    let file_name = archive.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| anyhow!("Archive has no file name"))?;
+    let file = tokio::fs::File::open(archive).await?;
+    let size = file.metadata().await?.len();
+
+    let response = client
+        .put(settings.url(&format!("/api/agent/jobs/{}/archive", job_id)))
+        .bearer_auth(&settings.token)
+        .header("X-Rdumper-Filename", file_name)
+        .header("X-Rdumper-Sha256", sha256_hash)
+        .header(reqwest::header::CONTENT_LENGTH, size)
+        .body(reqwest::Body::wrap_stream(ReaderStream::new(file)))
+        .send()
+        .await?;
+    check_status(response).await?;
+    Ok(())
+}
+
+/// POST a JSON body and return the `data` field of the API response
+async fn post<B: Serialize, T: DeserializeOwned>(
+    client: &reqwest::Client,
+    settings: &AgentSettings,
+    path: &str,
+    body: &B,
+) -> Result<T> {
+    let response = client
+        .post(settings.url(path))
+        .bearer_auth(&settings.token)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_vec(body)?)
+        .send()
+        .await?;
+    let bytes = check_status(response).await?;
+    let mut envelope: serde_json::Value = serde_json::from_slice(&bytes)?;
+    Ok(serde_json::from_value(envelope["data"].take())?)
+}
+
+/// Body of a successful response, or the API's error message
+async fn check_status(response: reqwest::Response) -> Result<Vec<u8>> {
+    let status = response.status();
+    let bytes = response.bytes().await?.to_vec();
+    if !status.is_success() {
+        let message = serde_json::from_slice::<serde_json::Value>(&bytes).ok()
+            .and_then(|body| body["error"].as_str().map(str::to_string))
+            .unwrap_or_else(|| String::from_utf8_lossy(&bytes).into_owned());
+        return Err(anyhow!("Central instance answered {}: {}", status, message));
+    }
+    Ok(bytes)
+}
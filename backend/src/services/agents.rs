@@ -0,0 +1,222 @@
+use std::path::Path;
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+use chrono::{Duration, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+use crate::models::{Agent, AgentJob, BackupMetadata, DatabaseConfig, Job, LogLevel, Task};
+use crate::services::backup_register::{self, RegisterOptions};
+use crate::services::events::{self, AppEvent};
+use crate::services::task_worker::{record_failed_attempt, record_task_result};
+use crate::services::{FilesystemBackupService, LoggingService};
+
+/// Running jobs of an agent that has been silent for this long are failed
+const ORPHANED_JOB_MINUTES: i64 = 15;
+/// Backup type recorded for archives uploaded by agents
+const AGENT_BACKUP_TYPE: &str = "scheduled";
+
+/// New random agent token; only its hash is stored
+pub fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(40)
+        .map(char::from)
+        .collect()
+}
+
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Agent owning `token`, recording that it was just seen
+pub async fn authenticate(pool: &SqlitePool, token: &str) -> Result<Option<Agent>> {
+    let agent: Option<Agent> = sqlx::query_as("SELECT * FROM agents WHERE token_hash = ?")
+        .bind(hash_token(token))
+        .fetch_optional(pool)
+        .await?;
+    if let Some(agent) = &agent {
+        sqlx::query("UPDATE agents SET last_seen_at = ? WHERE id = ?")
+            .bind(Utc::now())
+            .bind(&agent.id)
+            .execute(pool)
+            .await?;
+    }
+    Ok(agent)
+}
+
+/// Hand a freshly created pending job to the agent of its database instead of running it here
+pub async fn dispatch(pool: &SqlitePool, job_id: &str, agent_id: &str) -> Result<()> {
+    let (name,): (String,) = sqlx::query_as("SELECT name FROM agents WHERE id = ?")
+        .bind(agent_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| anyhow!("Agent {} not found", agent_id))?;
+
+    sqlx::query("UPDATE jobs SET agent_id = ? WHERE id = ?")
+        .bind(agent_id)
+        .bind(job_id)
+        .execute(pool)
+        .await?;
+
+    let logging_service = LoggingService::new(Arc::new(pool.clone()));
+    let _ = logging_service.log_job(job_id, &format!("Dispatched to agent '{}'", name), LogLevel::Info).await;
+    Ok(())
+}
+
+/// Claim the agent's oldest pending backup job and mark it running
+pub async fn claim_next_job(pool: &SqlitePool, agent: &Agent) -> Result<Option<AgentJob>> {
+    // Agents run one job at a time and only poll when idle, so anything still running was lost in a restart
+    let lost: Vec<Job> = sqlx::query_as("SELECT * FROM jobs WHERE agent_id = ? AND status = 'running'")
+        .bind(&agent.id)
+        .fetch_all(pool)
+        .await?;
+    for job in &lost {
+        warn!("Agent '{}' no longer runs job {}", agent.name, job.id);
+        fail_job(pool, job, "Agent restarted while the job was running").await?;
+    }
+
+    loop {
+        let Some(job) = sqlx::query_as::<_, Job>(
+            "SELECT * FROM jobs WHERE agent_id = ? AND status = 'pending' AND job_type = 'backup' ORDER BY priority DESC, created_at LIMIT 1"
+        )
+        .bind(&agent.id)
+        .fetch_optional(pool)
+        .await? else {
+            return Ok(None);
+        };
+
+        // Guard against a second poll of the same agent claiming the job concurrently
+        let claimed = sqlx::query("UPDATE jobs SET status = ?, started_at = ? WHERE id = ? AND status = 'pending'")
+            .bind("running")
+            .bind(Utc::now())
+            .bind(&job.id)
+            .execute(pool)
+            .await?
+            .rows_affected();
+        if claimed == 0 {
+            continue;
+        }
+        events::publish(AppEvent::job_status(&job.id, "running"));
+
+        match load_job_context(pool, &job).await {
+            Ok((mut task, database_config)) => {
+                // Destinations and their credentials stay on this instance; the agent hands its archive back
+                task.storage_destination_id = None;
+                let logging_service = LoggingService::new(Arc::new(pool.clone()));
+                let _ = logging_service.log_job(&job.id, &format!("Claimed by agent '{}'", agent.name), LogLevel::Info).await;
+                return Ok(Some(AgentJob { job, task, database_config }));
+            }
+            Err(e) => {
+                // Task or config was deleted after dispatch; nothing the agent could run
+                fail_job(pool, &job, &e.to_string()).await?;
+            }
+        }
+    }
+}
+
+async fn load_job_context(pool: &SqlitePool, job: &Job) -> Result<(Task, DatabaseConfig)> {
+    let task_id = job.task_id.as_deref().ok_or_else(|| anyhow!("Job has no task"))?;
+    let task: Task = sqlx::query_as("SELECT * FROM tasks WHERE id = ?")
+        .bind(task_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| anyhow!("Task not found"))?;
+    let database_config: DatabaseConfig = sqlx::query_as("SELECT * FROM database_configs WHERE id = ?")
+        .bind(&task.database_config_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| anyhow!("Database configuration not found"))?;
+    Ok((task, database_config))
+}
+
+/// Running job of `agent` with the given id
+pub async fn find_running_job(pool: &SqlitePool, agent: &Agent, job_id: &str) -> Result<Option<Job>> {
+    Ok(sqlx::query_as("SELECT * FROM jobs WHERE id = ? AND agent_id = ? AND status = 'running'")
+        .bind(job_id)
+        .bind(&agent.id)
+        .fetch_optional(pool)
+        .await?)
+}
+
+pub async fn fail_job(pool: &SqlitePool, job: &Job, error: &str) -> Result<()> {
+    let logging_service = LoggingService::new(Arc::new(pool.clone()));
+    let _ = logging_service.log_job(&job.id, &format!("Backup failed: {}", error), LogLevel::Error).await;
+    sqlx::query("UPDATE jobs SET status = ?, error_message = ?, completed_at = ? WHERE id = ?")
+        .bind("failed")
+        .bind(error)
+        .bind(Utc::now())
+        .bind(&job.id)
+        .execute(pool)
+        .await?;
+    events::publish(AppEvent::job_status(&job.id, "failed"));
+    let _ = record_failed_attempt(pool, &job.id).await;
+    Ok(())
+}
+
+/// Add an archive uploaded by an agent to the catalog and complete its job
+pub async fn complete_with_archive(
+    pool: &SqlitePool,
+    backup_service: &FilesystemBackupService,
+    temp_dir: &str,
+    job: &Job,
+    archive: &Path,
+) -> Result<BackupMetadata> {
+    let (task, database_config) = load_job_context(pool, job).await?;
+    let database_name = task.database_name.clone();
+    let options = RegisterOptions {
+        database_config,
+        task: Some(task),
+        database_name,
+        created_at: None,
+        backup_type: AGENT_BACKUP_TYPE,
+    };
+    let metadata = backup_register::register_backup(backup_service, temp_dir, archive, options).await?;
+
+    sqlx::query("UPDATE jobs SET status = ?, completed_at = ?, progress = ?, backup_path = ?, backup_size = ? WHERE id = ?")
+        .bind("completed")
+        .bind(Utc::now())
+        .bind(100)
+        .bind(&metadata.file_path)
+        .bind(metadata.file_size)
+        .bind(&job.id)
+        .execute(pool)
+        .await?;
+    events::publish(AppEvent::job_status(&job.id, "completed"));
+    events::publish(AppEvent::BackupCreated {
+        backup_id: metadata.id.clone(),
+        file_path: metadata.file_path.clone(),
+    });
+    if let Some(task_id) = &job.task_id {
+        let _ = record_task_result(pool, task_id, true).await;
+    }
+
+    let logging_service = LoggingService::new(Arc::new(pool.clone()));
+    let _ = logging_service.log_job(&job.id, &format!("Backup completed successfully: {}", metadata.file_path), LogLevel::Info).await;
+    Ok(metadata)
+}
+
+/// Fail running jobs whose agent stopped polling, so they don't block their task forever
+pub async fn fail_orphaned_jobs(pool: &SqlitePool) -> Result<usize> {
+    let cutoff = Utc::now() - Duration::minutes(ORPHANED_JOB_MINUTES);
+    let orphaned: Vec<Job> = sqlx::query_as(
+        r#"
+        SELECT jobs.* FROM jobs JOIN agents ON agents.id = jobs.agent_id
+        WHERE jobs.status = 'running' AND (agents.last_seen_at IS NULL OR agents.last_seen_at < ?)
+        "#
+    )
+    .bind(cutoff)
+    .fetch_all(pool)
+    .await?;
+
+    for job in &orphaned {
+        warn!("Agent of job {} went silent, failing the job", job.id);
+        fail_job(pool, job, &format!("Agent has not been seen for {} minutes", ORPHANED_JOB_MINUTES)).await?;
+    }
+    if !orphaned.is_empty() {
+        info!("Failed {} job(s) of unreachable agents", orphaned.len());
+    }
+    Ok(orphaned.len())
+}
@@ -0,0 +1,80 @@
+use anyhow::Result;
+use crate::models::SizeAnomaly;
+use crate::services::FilesystemBackupService;
+
+/// Number of previous backups of a task used as the size baseline
+const SIZE_HISTORY: usize = 5;
+/// Too little history makes the median meaningless
+const MIN_SIZE_HISTORY: usize = 3;
+/// Relative deviation from the baseline that is flagged (0.5 = 50% smaller or larger)
+const SIZE_DEVIATION_THRESHOLD: f64 = 0.5;
+
+/// Completed runs of a task used as the duration baseline
+pub const DURATION_HISTORY: i64 = 20;
+/// A running job is flagged once it exceeds the historical p95 by this factor...
+const DURATION_FACTOR: f64 = 1.5;
+/// ...and by at least this many seconds, so short jobs don't alert on small jitter
+const MIN_DURATION_OVERRUN_SECS: i64 = 10 * 60;
+
+/// Compare a backup size against the median of previous sizes
+pub fn detect_size_anomaly(size: i64, history: &[i64]) -> Option<SizeAnomaly> {
+    if history.len() < MIN_SIZE_HISTORY {
+        return None;
+    }
+
+    let mut sorted = history.to_vec();
+    sorted.sort_unstable();
+    let mid = sorted.len() / 2;
+    let expected_size = if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2
+    } else {
+        sorted[mid]
+    };
+    if expected_size <= 0 {
+        return None;
+    }
+
+    let deviation = (size - expected_size) as f64 / expected_size as f64;
+    if deviation.abs() < SIZE_DEVIATION_THRESHOLD {
+        return None;
+    }
+
+    Some(SizeAnomaly {
+        expected_size,
+        deviation_percent: (deviation * 1000.0).round() / 10.0,
+    })
+}
+
+/// Check a freshly created backup against the task's recent backups
+pub async fn check_backup_size(
+    backup_service: &FilesystemBackupService,
+    backup_id: &str,
+    task_id: &str,
+    size: i64,
+) -> Result<Option<SizeAnomaly>> {
+    let mut previous: Vec<_> = backup_service.scan_backups().await?
+        .into_iter()
+        .filter(|b| b.task_id.as_deref() == Some(task_id) && b.id != backup_id)
+        .collect();
+    // RFC 3339 timestamps sort chronologically
+    previous.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+
+    let history: Vec<i64> = previous.iter().take(SIZE_HISTORY).map(|b| b.file_size).collect();
+    Ok(detect_size_anomaly(size, &history))
+}
+
+/// 95th percentile (nearest rank) of previous run durations in seconds
+pub fn duration_p95(durations: &[i64]) -> Option<i64> {
+    if durations.len() < MIN_SIZE_HISTORY {
+        return None;
+    }
+    let mut sorted = durations.to_vec();
+    sorted.sort_unstable();
+    let rank = ((sorted.len() as f64) * 0.95).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+/// Running time after which a job with the given p95 counts as overrunning
+pub fn duration_limit(p95_secs: i64) -> i64 {
+    ((p95_secs as f64 * DURATION_FACTOR) as i64).max(p95_secs + MIN_DURATION_OVERRUN_SECS)
+}
@@ -0,0 +1,30 @@
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::SqlitePool;
+use crate::models::ApiKey;
+use crate::services::agents::{generate_token, hash_token};
+
+/// New random API key; only its hash is stored
+pub fn generate_key() -> String {
+    generate_token()
+}
+
+pub fn hash_key(key: &str) -> String {
+    hash_token(key)
+}
+
+/// API key matching `key`, recording that it was just used
+pub async fn authenticate(pool: &SqlitePool, key: &str) -> Result<Option<ApiKey>> {
+    let api_key: Option<ApiKey> = sqlx::query_as("SELECT * FROM api_keys WHERE key_hash = ?")
+        .bind(hash_key(key))
+        .fetch_optional(pool)
+        .await?;
+    if let Some(api_key) = &api_key {
+        sqlx::query("UPDATE api_keys SET last_used_at = ? WHERE id = ?")
+            .bind(Utc::now())
+            .bind(&api_key.id)
+            .execute(pool)
+            .await?;
+    }
+    Ok(api_key)
+}
@@ -0,0 +1,405 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+use crate::models::CompressionType;
+use crate::services::backup_process::ZSTD_LEVEL;
+use crate::services::encryption;
+
+/// File name used when a plain SQL dump is unpacked
+pub const PLAIN_SQL_FILE: &str = "dump.sql";
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const XZ_MAGIC: &[u8] = &[0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00];
+const ZIP_MAGIC: &[u8] = &[0x50, 0x4b, 0x03, 0x04];
+/// "ustar" lives at offset 257 of the first tar header
+const TAR_MAGIC_OFFSET: usize = 257;
+
+/// Supported backup archive formats, detected by content rather than file extension
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveFormat {
+    TarGz,
+    TarZst,
+    TarXz,
+    Tar,
+    Zip,
+    SqlGz,
+    Sql,
+}
+
+impl ArchiveFormat {
+    /// Detect the format of a file from its magic bytes, looking inside encrypted archives
+    pub fn detect(path: &Path) -> Result<Self> {
+        let mut head = Vec::with_capacity(4096);
+        encryption::open(path)?.take(4096).read_to_end(&mut head)?;
+
+        if head.starts_with(GZIP_MAGIC) {
+            // A gzip stream is either a tarball or a single compressed SQL file
+            let mut inner = Vec::with_capacity(512);
+            flate2::read::GzDecoder::new(encryption::open(path)?).take(512).read_to_end(&mut inner)?;
+            return Ok(if is_tar_header(&inner) { Self::TarGz } else { Self::SqlGz });
+        }
+        if head.starts_with(ZSTD_MAGIC) {
+            return Ok(Self::TarZst);
+        }
+        if head.starts_with(XZ_MAGIC) {
+            return Ok(Self::TarXz);
+        }
+        if head.starts_with(ZIP_MAGIC) {
+            return Ok(Self::Zip);
+        }
+        if is_tar_header(&head) {
+            return Ok(Self::Tar);
+        }
+        // Anything else without NUL bytes is treated as a plain-text SQL dump
+        if !head.is_empty() && !head.contains(&0) {
+            return Ok(Self::Sql);
+        }
+        Err(anyhow!("Unsupported archive format"))
+    }
+}
+
+impl std::fmt::Display for ArchiveFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TarGz => write!(f, "tar.gz"),
+            Self::TarZst => write!(f, "tar.zst"),
+            Self::TarXz => write!(f, "tar.xz"),
+            Self::Tar => write!(f, "tar"),
+            Self::Zip => write!(f, "zip"),
+            Self::SqlGz => write!(f, "sql.gz"),
+            Self::Sql => write!(f, "sql"),
+        }
+    }
+}
+
+fn is_tar_header(block: &[u8]) -> bool {
+    block.len() >= TAR_MAGIC_OFFSET + 5 && &block[TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + 5] == b"ustar"
+}
+
+/// Open a tar-based archive with the matching decompressor
+fn open_tar(path: &Path, format: ArchiveFormat) -> Result<tar::Archive<Box<dyn Read>>> {
+    let file = BufReader::new(encryption::open(path)?);
+    let reader: Box<dyn Read> = match format {
+        ArchiveFormat::TarGz => Box::new(flate2::bufread::GzDecoder::new(file)),
+        ArchiveFormat::TarZst => Box::new(zstd::Decoder::with_buffer(file)?),
+        ArchiveFormat::TarXz => Box::new(xz2::bufread::XzDecoder::new(file)),
+        ArchiveFormat::Tar => Box::new(file),
+        _ => return Err(anyhow!("{} is not a tar archive", format)),
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+/// List file paths and sizes inside an archive without extracting it
+pub fn list_entries(path: &Path) -> Result<Vec<(String, u64)>> {
+    let format = ArchiveFormat::detect(path)?;
+    let mut entries = Vec::new();
+
+    match format {
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+            for i in 0..archive.len() {
+                let file = archive.by_index(i)?;
+                if file.is_file() {
+                    entries.push((file.name().to_string(), file.size()));
+                }
+            }
+        }
+        ArchiveFormat::Sql => entries.push((PLAIN_SQL_FILE.to_string(), std::fs::metadata(path)?.len())),
+        ArchiveFormat::SqlGz => {
+            let size = std::io::copy(&mut flate2::read::GzDecoder::new(encryption::open(path)?), &mut std::io::sink())?;
+            entries.push((PLAIN_SQL_FILE.to_string(), size));
+        }
+        _ => {
+            let mut archive = open_tar(path, format)?;
+            for entry in archive.entries()? {
+                let entry = entry?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let name = entry.path()?.to_string_lossy().trim_start_matches("./").to_string();
+                entries.push((name, entry.size()));
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// An archive's files read in one pass, with what a restore preview needs from a mydumper dump
+pub struct DumpInspection {
+    pub entries: Vec<(String, u64)>,
+    /// Rows counted in each data chunk, by path
+    pub data_rows: HashMap<String, u64>,
+    /// Text of mydumper's `metadata` file
+    pub metadata: Option<String>,
+}
+
+/// Read a tar or zip archive entry by entry, counting the rows of data chunks (decompressing
+/// chunks mydumper compressed itself) and keeping the `metadata` file, without extracting anything
+pub fn inspect_dump(path: &Path) -> Result<DumpInspection> {
+    let format = ArchiveFormat::detect(path)?;
+    let mut inspection = DumpInspection { entries: Vec::new(), data_rows: HashMap::new(), metadata: None };
+
+    let mut inspect_entry = |name: String, size: u64, reader: &mut dyn Read| -> Result<()> {
+        let file_name = name.rsplit('/').next().unwrap_or(&name);
+        if file_name == "metadata" {
+            let mut content = String::new();
+            reader.read_to_string(&mut content)?;
+            inspection.metadata = Some(content);
+        } else if let Some(kind) = data_chunk_kind(file_name) {
+            let mut counter = RowCounter::new(kind.csv);
+            match kind.compression {
+                Some(CompressionType::Gzip) => std::io::copy(&mut flate2::read::GzDecoder::new(reader), &mut counter)?,
+                Some(_) => std::io::copy(&mut zstd::Decoder::new(reader)?, &mut counter)?,
+                None => std::io::copy(reader, &mut counter)?,
+            };
+            inspection.data_rows.insert(name.clone(), counter.rows);
+        }
+        inspection.entries.push((name, size));
+        Ok(())
+    };
+
+    match format {
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+            for i in 0..archive.len() {
+                let mut file = archive.by_index(i)?;
+                if file.is_file() {
+                    let (name, size) = (file.name().to_string(), file.size());
+                    inspect_entry(name, size, &mut file)?;
+                }
+            }
+        }
+        ArchiveFormat::Sql | ArchiveFormat::SqlGz => inspection.entries = list_entries(path)?,
+        _ => {
+            let mut archive = open_tar(path, format)?;
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let name = entry.path()?.to_string_lossy().trim_start_matches("./").to_string();
+                let size = entry.size();
+                inspect_entry(name, size, &mut entry)?;
+            }
+        }
+    }
+
+    Ok(inspection)
+}
+
+struct DataChunkKind {
+    csv: bool,
+    compression: Option<CompressionType>,
+}
+
+/// Whether a file is a data chunk of a mydumper dump, e.g. `db.table.00000.sql.gz`
+fn data_chunk_kind(file_name: &str) -> Option<DataChunkKind> {
+    let (stem, compression) = if let Some(stem) = file_name.strip_suffix(".gz") {
+        (stem, Some(CompressionType::Gzip))
+    } else if let Some(stem) = file_name.strip_suffix(".zst") {
+        (stem, Some(CompressionType::Zstd))
+    } else {
+        (file_name, None)
+    };
+    let (name, csv) = match (stem.strip_suffix(".sql"), stem.strip_suffix(".dat")) {
+        (Some(name), _) => (name, false),
+        (_, Some(name)) => (name, true),
+        _ => return None,
+    };
+    let is_data = !name.contains("-schema") && name.matches('.').count() >= 1;
+    is_data.then_some(DataChunkKind { csv, compression })
+}
+
+/// Counts rows of a data chunk: mydumper writes one row per line, so rows of an INSERT end
+/// in `),` or `);` and every line of a CSV chunk is a row
+struct RowCounter {
+    csv: bool,
+    tail: [u8; 2],
+    rows: u64,
+}
+
+impl RowCounter {
+    fn new(csv: bool) -> Self {
+        Self { csv, tail: [0; 2], rows: 0 }
+    }
+}
+
+impl Write for RowCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        for &byte in buf {
+            let row_end = self.tail[0] == b')' && (self.tail[1] == b',' || self.tail[1] == b';');
+            if byte == b'\n' && (self.csv || row_end) {
+                self.rows += 1;
+            }
+            self.tail = [self.tail[1], byte];
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Rows per table recorded in the `metadata` file of newer mydumper releases,
+/// whose sections read `[`database`.`table`]` followed by `rows = 123`
+pub fn metadata_table_rows(metadata: &str) -> HashMap<(String, String), u64> {
+    let mut rows = HashMap::new();
+    let mut table = None;
+    for line in metadata.lines().map(str::trim) {
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            table = section.split_once("`.`")
+                .map(|(database, name)| (database.trim_start_matches('`').to_string(), name.trim_end_matches('`').to_string()));
+        } else if let (Some(key), Some((name, value))) = (&table, line.split_once('=')) {
+            if name.trim() == "rows" {
+                if let Ok(count) = value.trim().parse() {
+                    rows.insert(key.clone(), count);
+                }
+            }
+        }
+    }
+    rows
+}
+
+/// Extract any supported archive into `dest`; plain SQL dumps become `dest/dump.sql`
+pub fn extract(path: &Path, dest: &Path) -> Result<ArchiveFormat> {
+    let format = ArchiveFormat::detect(path)?;
+    std::fs::create_dir_all(dest)?;
+
+    match format {
+        ArchiveFormat::Zip => {
+            let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+            for i in 0..archive.len() {
+                let mut file = archive.by_index(i)?;
+                // Skip entries that would escape the destination
+                let Some(relative) = file.enclosed_name() else {
+                    continue;
+                };
+                let target = dest.join(relative);
+                if file.is_dir() {
+                    std::fs::create_dir_all(&target)?;
+                    continue;
+                }
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::io::copy(&mut file, &mut File::create(&target)?)?;
+            }
+        }
+        ArchiveFormat::Sql => {
+            std::fs::copy(path, dest.join(PLAIN_SQL_FILE))?;
+        }
+        ArchiveFormat::SqlGz => {
+            let mut decoder = flate2::read::GzDecoder::new(encryption::open(path)?);
+            std::io::copy(&mut decoder, &mut File::create(dest.join(PLAIN_SQL_FILE))?)?;
+        }
+        _ => open_tar(path, format)?.unpack(dest)?,
+    }
+
+    Ok(format)
+}
+
+/// Rewrite a tar-based archive with another compression, keeping entry paths, modes and mtimes
+pub fn recompress(src: &Path, dest: &Path, target: CompressionType) -> Result<()> {
+    let format = ArchiveFormat::detect(src)?;
+    let mut source = open_tar(src, format)?;
+    let file = File::create(dest)?;
+
+    let file = match target {
+        CompressionType::None => copy_entries(&mut source, BufWriter::new(file))?.into_inner()?,
+        CompressionType::Gzip => {
+            let encoder = flate2::write::GzEncoder::new(BufWriter::new(file), flate2::Compression::default());
+            copy_entries(&mut source, encoder)?.finish()?.into_inner()?
+        }
+        CompressionType::Zstd => {
+            let mut encoder = zstd::Encoder::new(file, ZSTD_LEVEL)?;
+            let workers = std::thread::available_parallelism().map(|n| n.get() as u32).unwrap_or(1);
+            encoder.multithread(workers)?;
+            copy_entries(&mut source, encoder)?.finish()?
+        }
+    };
+    file.sync_all()?;
+    Ok(())
+}
+
+fn copy_entries<W: Write>(source: &mut tar::Archive<Box<dyn Read>>, writer: W) -> Result<W> {
+    let mut builder = tar::Builder::new(writer);
+    for entry in source.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        let mut header = entry.header().clone();
+        // append_data re-encodes the path, so long names survive the copy
+        builder.append_data(&mut header, path, &mut entry)?;
+    }
+    Ok(builder.into_inner()?)
+}
+
+/// Path, size and SHA-256 of every file in a tar-based archive, sorted by path
+pub fn content_digest(path: &Path) -> Result<Vec<(String, u64, String)>> {
+    let format = ArchiveFormat::detect(path)?;
+    let mut archive = open_tar(path, format)?;
+    let mut digest = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.to_string_lossy().trim_start_matches("./").to_string();
+        let mut hasher = Sha256::new();
+        let size = std::io::copy(&mut entry, &mut hasher)?;
+        digest.push((name, size, format!("{:x}", hasher.finalize())));
+    }
+    digest.sort();
+    Ok(digest)
+}
+
+/// Async wrapper running `extract` on the blocking thread pool
+pub async fn extract_async(path: PathBuf, dest: PathBuf) -> Result<ArchiveFormat> {
+    tokio::task::spawn_blocking(move || extract(&path, &dest)).await?
+}
+
+/// Whether an extracted directory is mydumper output (as opposed to plain SQL files)
+pub fn is_mydumper_dump(dir: &Path) -> bool {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries.flatten().any(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                name == "metadata" || name.contains("-schema.sql") || name.contains("-schema-create.sql")
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// Databases in a mydumper dump, from its `<database>-schema-create.sql` files
+pub fn dump_databases(dir: &Path) -> Vec<String> {
+    let mut databases: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries.flatten()
+                .filter_map(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    name.strip_suffix("-schema-create.sql").map(|database| database.to_string())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    databases.sort();
+    databases
+}
+
+/// Descend into a lone top-level directory, as produced by zipping or tarring a folder
+pub fn content_root(dir: &Path) -> PathBuf {
+    let entries: Vec<_> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries.flatten().collect(),
+        Err(_) => return dir.to_path_buf(),
+    };
+    if entries.len() == 1 && entries[0].path().is_dir() {
+        content_root(&entries[0].path())
+    } else {
+        dir.to_path_buf()
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::Result;
+use serde_json::Value;
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+use crate::models::{AuditEntry, AuditQuery};
+
+/// Stands in for secrets in recorded bodies and snapshots
+const REDACTED: &str = "[redacted]";
+
+/// Fields holding passwords, keys or tokens, wherever they appear
+fn is_secret(field: &str) -> bool {
+    field == "key"
+        || field.contains("password")
+        || field.contains("secret")
+        || field.contains("token")
+        || field.contains("private_key")
+        || field.starts_with("credentials")
+        || field.ends_with("_hash")
+}
+
+/// Replace secrets in a value, including in JSON stored as text, e.g. a storage destination's target
+pub fn redact(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(map.into_iter()
+            .map(|(field, value)| {
+                let value = if is_secret(&field) && !value.is_null() { Value::from(REDACTED) } else { redact(value) };
+                (field, value)
+            })
+            .collect()),
+        Value::Array(items) => Value::Array(items.into_iter().map(redact).collect()),
+        Value::String(text) if text.starts_with('{') => match serde_json::from_str::<Value>(&text) {
+            Ok(nested @ Value::Object(_)) => Value::from(redact(nested).to_string()),
+            _ => Value::String(text),
+        },
+        other => other,
+    }
+}
+
+/// Row `id` of `table` as a JSON object, secrets redacted
+pub async fn table_snapshot(pool: &SqlitePool, table: &str, id: &str) -> Result<Option<Value>> {
+    let columns: Vec<String> = sqlx::query_scalar(&format!("SELECT name FROM pragma_table_info('{}')", table))
+        .fetch_all(pool)
+        .await?;
+    if columns.is_empty() {
+        return Ok(None);
+    }
+    let fields = columns.iter()
+        .map(|column| format!("'{}', \"{}\"", column, column))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let row: Option<String> = sqlx::query_scalar(&format!("SELECT json_object({}) FROM \"{}\" WHERE id = ?", fields, table))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|row| redact(serde_json::from_str(&row).unwrap_or(Value::Null))))
+}
+
+pub async fn record(pool: &SqlitePool, entry: &AuditEntry) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO audit_log (id, actor, actor_kind, actor_id, method, path, action, entity_type, entity_id,
+                                status_code, request, before, after, response, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&entry.id)
+    .bind(&entry.actor)
+    .bind(&entry.actor_kind)
+    .bind(&entry.actor_id)
+    .bind(&entry.method)
+    .bind(&entry.path)
+    .bind(&entry.action)
+    .bind(&entry.entity_type)
+    .bind(&entry.entity_id)
+    .bind(entry.status_code)
+    .bind(&entry.request)
+    .bind(&entry.before)
+    .bind(&entry.after)
+    .bind(&entry.response)
+    .bind(entry.created_at)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Entries matching the query, newest first, and how many match in total
+pub async fn list(pool: &SqlitePool, query: &AuditQuery, limit: u32, offset: u32) -> Result<(Vec<AuditEntry>, i64)> {
+    let mut select = QueryBuilder::new("SELECT * FROM audit_log");
+    push_filters(&mut select, query);
+    select.push(" ORDER BY created_at DESC, id DESC LIMIT ").push_bind(limit as i64)
+        .push(" OFFSET ").push_bind(offset as i64);
+    let entries = select.build_query_as::<AuditEntry>().fetch_all(pool).await?;
+
+    let mut count = QueryBuilder::new("SELECT COUNT(*) FROM audit_log");
+    push_filters(&mut count, query);
+    let (total,): (i64,) = count.build_query_as().fetch_one(pool).await?;
+
+    Ok((entries, total))
+}
+
+/// Up to `limit` entries after the cursor in `created_before` and `after_id`, newest first
+pub async fn list_after(pool: &SqlitePool, query: &AuditQuery, limit: u32) -> Result<Vec<AuditEntry>> {
+    let mut select = QueryBuilder::new("SELECT * FROM audit_log");
+    push_filters(&mut select, query);
+    match (query.created_before, &query.after_id) {
+        (Some(created_before), Some(after_id)) => {
+            select.push(" AND (created_at < ").push_bind(created_before)
+                .push(" OR (created_at = ").push_bind(created_before)
+                .push(" AND id < ").push_bind(after_id)
+                .push("))");
+        }
+        (Some(created_before), None) => { select.push(" AND created_at < ").push_bind(created_before); }
+        (None, _) => {}
+    }
+    select.push(" ORDER BY created_at DESC, id DESC LIMIT ").push_bind(limit as i64);
+    Ok(select.build_query_as::<AuditEntry>().fetch_all(pool).await?)
+}
+
+fn push_filters<'a>(builder: &mut QueryBuilder<'a, Sqlite>, query: &'a AuditQuery) {
+    builder.push(" WHERE 1=1");
+    if let Some(actor) = &query.actor {
+        builder.push(" AND actor = ").push_bind(actor);
+    }
+    if let Some(entity_type) = &query.entity_type {
+        builder.push(" AND entity_type = ").push_bind(entity_type);
+    }
+    if let Some(entity_id) = &query.entity_id {
+        builder.push(" AND entity_id = ").push_bind(entity_id);
+    }
+    if let Some(action) = &query.action {
+        builder.push(" AND action = ").push_bind(action);
+    }
+    if let Some(since) = query.since {
+        builder.push(" AND created_at >= ").push_bind(since);
+    }
+    if let Some(until) = query.until {
+        builder.push(" AND created_at < ").push_bind(until);
+    }
+    match query.failed {
+        Some(true) => { builder.push(" AND status_code >= 400"); }
+        Some(false) => { builder.push(" AND status_code < 400"); }
+        None => {}
+    }
+}
@@ -0,0 +1,170 @@
+use std::num::NonZeroU32;
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+use sqlx::SqlitePool;
+use utoipa::ToSchema;
+use crate::models::User;
+
+/// PBKDF2-HMAC-SHA256 rounds for new password hashes
+const PBKDF2_ITERATIONS: u32 = 210_000;
+const SALT_LEN: usize = 16;
+const HASH_LEN: usize = 32;
+/// Lifetime of access tokens; clients renew them with their refresh token
+const ACCESS_TOKEN_MINUTES: i64 = 60;
+const REFRESH_TOKEN_DAYS: i64 = 30;
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenKind {
+    Access,
+    Refresh,
+}
+
+/// Claims of the tokens issued on login
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String, // User id
+    pub name: String,
+    pub kind: TokenKind,
+    /// The user's `token_version` when the token was signed
+    #[serde(default)]
+    pub ver: i64,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub token_type: &'static str,
+    pub expires_in: i64, // Seconds the access token is valid
+    pub user: User,
+}
+
+/// Whether requests must authenticate; the API stays open until the first API key or user exists
+pub async fn required(pool: &SqlitePool) -> Result<bool> {
+    let (count,): (i64,) = sqlx::query_as("SELECT (SELECT COUNT(*) FROM api_keys) + (SELECT COUNT(*) FROM users)")
+        .fetch_one(pool)
+        .await?;
+    Ok(count > 0)
+}
+
+/// Random secret for signing tokens when none is configured; tokens then end with the process
+pub fn generate_secret() -> String {
+    let mut secret = [0u8; 32];
+    SystemRandom::new().fill(&mut secret).expect("system random number generator failed");
+    hex::encode(secret)
+}
+
+/// Salted hash stored as `pbkdf2-sha256$<iterations>$<salt>$<hash>`, salt and hash in hex
+pub fn hash_password(password: &str) -> Result<String> {
+    let mut salt = [0u8; SALT_LEN];
+    SystemRandom::new().fill(&mut salt).map_err(|_| anyhow!("Failed to generate a salt"))?;
+    let mut hash = [0u8; HASH_LEN];
+    pbkdf2::derive(pbkdf2::PBKDF2_HMAC_SHA256, iterations(PBKDF2_ITERATIONS), &salt, password.as_bytes(), &mut hash);
+    Ok(format!("pbkdf2-sha256${}${}${}", PBKDF2_ITERATIONS, hex::encode(salt), hex::encode(hash)))
+}
+
+pub fn verify_password(password: &str, stored: &str) -> bool {
+    let parts: Vec<&str> = stored.split('$').collect();
+    let [scheme, rounds, salt, hash] = parts[..] else {
+        return false;
+    };
+    let (Ok(rounds), Ok(salt), Ok(hash)) = (rounds.parse::<u32>(), hex::decode(salt), hex::decode(hash)) else {
+        return false;
+    };
+    scheme == "pbkdf2-sha256"
+        && rounds > 0
+        && pbkdf2::verify(pbkdf2::PBKDF2_HMAC_SHA256, iterations(rounds), &salt, password.as_bytes(), &hash).is_ok()
+}
+
+fn iterations(rounds: u32) -> NonZeroU32 {
+    NonZeroU32::new(rounds).unwrap_or(NonZeroU32::MIN)
+}
+
+/// Access and refresh token for a user who just logged in
+pub fn issue_tokens(secret: &str, user: User) -> Result<TokenPair> {
+    Ok(TokenPair {
+        access_token: sign(secret, &user, TokenKind::Access, Duration::minutes(ACCESS_TOKEN_MINUTES))?,
+        refresh_token: sign(secret, &user, TokenKind::Refresh, Duration::days(REFRESH_TOKEN_DAYS))?,
+        token_type: "Bearer",
+        expires_in: ACCESS_TOKEN_MINUTES * 60,
+        user,
+    })
+}
+
+/// HS256 JWT for `user`
+fn sign(secret: &str, user: &User, kind: TokenKind, lifetime: Duration) -> Result<String> {
+    let now = Utc::now();
+    let claims = Claims {
+        sub: user.id.clone(),
+        name: user.username.clone(),
+        kind,
+        ver: user.token_version,
+        iat: now.timestamp(),
+        exp: (now + lifetime).timestamp(),
+    };
+    let header = URL_SAFE_NO_PAD.encode(json!({ "alg": "HS256", "typ": "JWT" }).to_string());
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims)?);
+    let signing_input = format!("{}.{}", header, payload);
+    let signature = mac(secret, &signing_input)?.finalize().into_bytes();
+    Ok(format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(signature)))
+}
+
+/// Claims of a token of the given kind signed with `secret` that hasn't expired
+pub fn verify_token(secret: &str, token: &str, kind: TokenKind) -> Result<Claims> {
+    let (signing_input, signature) = token.rsplit_once('.').ok_or_else(|| anyhow!("Malformed token"))?;
+    let (header, payload) = signing_input.split_once('.').ok_or_else(|| anyhow!("Malformed token"))?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| anyhow!("Malformed token"))?;
+    mac(secret, signing_input)?
+        .verify_slice(&signature)
+        .map_err(|_| anyhow!("Invalid token signature"))?;
+
+    // Only our own header is accepted, so the algorithm can't be swapped
+    let header: serde_json::Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(header)?)?;
+    if header["alg"] != "HS256" {
+        return Err(anyhow!("Unsupported token algorithm"));
+    }
+    let claims: Claims = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload)?)?;
+    if claims.kind != kind {
+        return Err(anyhow!("Wrong token kind"));
+    }
+    if claims.exp <= Utc::now().timestamp() {
+        return Err(anyhow!("Token expired"));
+    }
+    Ok(claims)
+}
+
+fn mac(secret: &str, input: &str) -> Result<Hmac<Sha256>> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .map_err(|e| anyhow!("Invalid token secret: {}", e))?;
+    mac.update(input.as_bytes());
+    Ok(mac)
+}
+
+/// Whether a token was signed after the user's last password change
+pub fn is_current(claims: &Claims, user: &User) -> bool {
+    claims.ver == user.token_version
+}
+
+/// User a token belongs to, if the token is valid, the user still exists and hasn't changed their password since
+pub async fn authenticate(pool: &SqlitePool, secret: &str, token: &str) -> Result<Option<User>> {
+    let Ok(claims) = verify_token(secret, token, TokenKind::Access) else {
+        return Ok(None);
+    };
+    let user: Option<User> = sqlx::query_as("SELECT * FROM users WHERE id = ?")
+        .bind(&claims.sub)
+        .fetch_optional(pool)
+        .await?;
+    Ok(user.filter(|user| is_current(&claims, user)))
+}
@@ -0,0 +1,234 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use sqlx::SqlitePool;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::{info, warn};
+use crate::models::{Backup, BackupMetadata, CopyDestination, CreateJobRequest, Job, JobType, LogLevel};
+use crate::services::{integrity, FilesystemBackupService, LoggingService};
+use crate::services::events::{self, AppEvent};
+
+const METADATA_FILE: &str = "rdumper.backup.json";
+const COPY_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Folder the copy of `backup` is written to, rejecting destinations that would clobber a backup
+pub fn resolve_target(backup: &Backup, destination: &CopyDestination) -> Result<PathBuf> {
+    let source_file = Path::new(&backup.file_path);
+    if !source_file.exists() {
+        return Err(anyhow!("Backup file no longer exists"));
+    }
+    let source_folder = source_file.parent().ok_or_else(|| anyhow!("Backup has no folder"))?;
+    let folder_name = source_folder.file_name().ok_or_else(|| anyhow!("Backup has no folder"))?;
+
+    match destination {
+        CopyDestination::Directory { path } => {
+            let dir = Path::new(path);
+            if !dir.is_absolute() {
+                return Err(anyhow!("Destination directory must be an absolute path"));
+            }
+            let target = dir.join(folder_name);
+            if target == source_folder {
+                return Err(anyhow!("Destination is the backup's own folder"));
+            }
+            if target.join(METADATA_FILE).exists() {
+                return Err(anyhow!("{} already contains a backup", target.display()));
+            }
+            Ok(target)
+        }
+    }
+}
+
+/// Create a copy job for `backup` and run it in the background; returns the job id
+pub async fn start_copy_job(
+    pool: &SqlitePool,
+    backup_service: &FilesystemBackupService,
+    backup: Backup,
+    destination: CopyDestination,
+    target: PathBuf,
+) -> Result<String> {
+    let job = Job::new(CreateJobRequest {
+        task_id: backup.task_id.clone(),
+        used_database: backup.used_database.clone(),
+        job_type: JobType::Copy,
+        backup_path: Some(backup.file_path.clone()),
+    });
+
+    sqlx::query(
+        r#"
+        INSERT INTO jobs (id, task_id, used_database, job_type, status, progress, started_at, completed_at, error_message, log_output, backup_path, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&job.id)
+    .bind(&job.task_id)
+    .bind(&job.used_database)
+    .bind(&job.job_type)
+    .bind(&job.status)
+    .bind(job.progress)
+    .bind(job.started_at)
+    .bind(job.completed_at)
+    .bind(&job.error_message)
+    .bind(&job.log_output)
+    .bind(&job.backup_path)
+    .bind(job.created_at)
+    .execute(pool)
+    .await?;
+
+    let pool = pool.clone();
+    let backup_service = backup_service.clone();
+    let job_id = job.id.clone();
+    tokio::spawn(async move {
+        run_copy_job(&pool, &backup_service, &backup, &destination, &target, &job_id).await;
+    });
+
+    Ok(job.id)
+}
+
+async fn run_copy_job(
+    pool: &SqlitePool,
+    backup_service: &FilesystemBackupService,
+    backup: &Backup,
+    destination: &CopyDestination,
+    target: &Path,
+    job_id: &str,
+) {
+    let logging_service = LoggingService::new(Arc::new(pool.clone()));
+
+    let _ = sqlx::query("UPDATE jobs SET status = ?, started_at = ? WHERE id = ?")
+        .bind("running")
+        .bind(Utc::now())
+        .bind(job_id)
+        .execute(pool)
+        .await;
+    events::publish(AppEvent::job_status(job_id, "running"));
+    let _ = logging_service.log_job(
+        job_id,
+        &format!("Copying backup {} to {}", backup.id, destination),
+        LogLevel::Info,
+    ).await;
+
+    let created_folder = !target.exists();
+    match copy_to_directory(pool, backup_service, backup, target, job_id).await {
+        Ok(copy_id) => {
+            info!("Backup {} copied to {}", backup.id, target.display());
+            let _ = logging_service.log_job(
+                job_id,
+                &format!("Copied to {} (backup id {})", target.display(), copy_id),
+                LogLevel::Info,
+            ).await;
+            let _ = sqlx::query("UPDATE jobs SET status = ?, progress = ?, completed_at = ? WHERE id = ?")
+                .bind("completed")
+                .bind(100)
+                .bind(Utc::now())
+                .bind(job_id)
+                .execute(pool)
+                .await;
+            events::publish(AppEvent::job_status(job_id, "completed"));
+        }
+        Err(e) => {
+            warn!("Copying backup {} failed: {}", backup.id, e);
+            // Don't leave a half-written backup folder behind
+            if created_folder {
+                let _ = tokio::fs::remove_dir_all(target).await;
+            }
+            let _ = logging_service.log_job(job_id, &format!("Copy failed: {}", e), LogLevel::Error).await;
+            let _ = sqlx::query("UPDATE jobs SET status = ?, error_message = ?, completed_at = ? WHERE id = ?")
+                .bind("failed")
+                .bind(e.to_string())
+                .bind(Utc::now())
+                .bind(job_id)
+                .execute(pool)
+                .await;
+            events::publish(AppEvent::job_status(job_id, "failed"));
+        }
+    }
+}
+
+/// Copy archive and metadata into `target`, verify the checksum and return the copy's backup id
+async fn copy_to_directory(
+    pool: &SqlitePool,
+    backup_service: &FilesystemBackupService,
+    backup: &Backup,
+    target: &Path,
+    job_id: &str,
+) -> Result<String> {
+    let source = PathBuf::from(&backup.file_path);
+    let file_name = source.file_name().ok_or_else(|| anyhow!("Backup has no file name"))?;
+    let dest = target.join(file_name);
+    let partial = target.join(format!("{}.partial", file_name.to_string_lossy()));
+
+    tokio::fs::create_dir_all(target).await?;
+    copy_with_progress(pool, &source, &partial, job_id).await?;
+
+    // Keep the original modification time so age-based cleanup treats the copy like the source
+    let modified = tokio::fs::metadata(&source).await?.modified()?;
+    let partial_for_mtime = partial.clone();
+    tokio::task::spawn_blocking(move || {
+        std::fs::File::options().write(true).open(&partial_for_mtime)?.set_modified(modified)
+    }).await??;
+
+    let metadata = backup_service.load_backup_metadata(Path::new(&backup.meta_path)).await?;
+    let expected = match &metadata.sha256_hash {
+        Some(hash) => hash.clone(),
+        None => integrity::hash_file(source.clone()).await?,
+    };
+    let actual = integrity::hash_file(partial.clone()).await?;
+    if actual != expected {
+        return Err(anyhow!("Checksum mismatch after copy: expected {}, got {}", expected, actual));
+    }
+    tokio::fs::rename(&partial, &dest).await?;
+
+    // A copy inside the backup store is a clone and needs its own id to show up separately
+    let store = tokio::fs::canonicalize(backup_service.base_dir()).await?;
+    let inside_store = tokio::fs::canonicalize(target).await?.starts_with(&store);
+    let copy = BackupMetadata {
+        id: if inside_store { uuid::Uuid::new_v4().to_string() } else { metadata.id.clone() },
+        file_path: dest.to_string_lossy().to_string(),
+        meta_path: target.join(METADATA_FILE).to_string_lossy().to_string(),
+        sha256_hash: Some(actual),
+        verified_at: Some(Utc::now().to_rfc3339()),
+        verification_error: None,
+        // Only the archive is copied, not the binlogs archived next to it
+        binlog: None,
+        ..metadata
+    };
+    tokio::fs::write(&copy.meta_path, serde_json::to_string_pretty(&copy)?).await?;
+
+    if inside_store {
+        FilesystemBackupService::invalidate_all_caches().await;
+    }
+    Ok(copy.id)
+}
+
+/// Stream `source` to `dest`, publishing progress on the job every few percent
+async fn copy_with_progress(pool: &SqlitePool, source: &Path, dest: &Path, job_id: &str) -> Result<()> {
+    let total = tokio::fs::metadata(source).await?.len().max(1);
+    let mut reader = tokio::fs::File::open(source).await?;
+    let mut writer = tokio::fs::File::create(dest).await?;
+    let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+    let mut copied: u64 = 0;
+    let mut last_percent = 0;
+
+    loop {
+        let read = reader.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..read]).await?;
+        copied += read as u64;
+
+        // The checksum pass afterwards takes the remaining share of the job
+        let percent = (copied * 90 / total) as i32;
+        if percent >= last_percent + 5 {
+            last_percent = percent;
+            let _ = sqlx::query("UPDATE jobs SET progress = ? WHERE id = ?")
+                .bind(percent)
+                .bind(job_id)
+                .execute(pool)
+                .await;
+        }
+    }
+    writer.sync_all().await?;
+    Ok(())
+}
@@ -4,8 +4,12 @@ use std::fs;
 use tokio::fs as async_fs;
 use chrono::Utc;
 use serde::{Serialize, Deserialize};
+use std::io::Write;
+use tokio::sync::watch;
 
-use crate::models::{DatabaseConfig, Task, BackupMetadata, DatabaseConfigInfo, TaskInfo};
+use crate::models::{DatabaseConfig, Task, BackupMetadata, BinlogArchive, DatabaseConfigInfo, TaskInfo};
+use crate::services::{anomaly, binlog, encryption, FilesystemBackupService};
+use crate::services::events::{self, AppEvent};
 
 #[derive(Debug)]
 pub struct BackupProcess {
@@ -65,8 +69,17 @@ impl BackupProcess {
     
     /// Complete the backup process by creating archive and cleaning up
     pub async fn complete(&mut self) -> Result<String> {
+        self.complete_with_progress(None).await
+    }
+
+    /// Complete the backup process, reporting compression progress (0-100) where supported
+    pub async fn complete_with_progress(&mut self, progress: Option<watch::Sender<u32>>) -> Result<String> {
         // Create backup archive
-        let archive_path = self.create_archive().await?;
+        let archive_path = self.create_archive(progress).await?;
+        let archive_path = match self.task.as_ref().and_then(|task| task.encryption_key.clone()) {
+            Some(key_name) => self.encrypt_archive(archive_path, key_name).await?,
+            None => archive_path,
+        };
         
         // Get file size and modification time
         let metadata = async_fs::metadata(&archive_path).await?;
@@ -78,9 +91,25 @@ impl BackupProcess {
         
         // Clean up tmp directory immediately
         self.cleanup_tmp().await?;
+
+        // Make the new archive visible to the next backup listing
+        FilesystemBackupService::invalidate_all_caches().await;
+
+        // A sudden size change against the task's history usually means a truncated dump
+        if let Some(task) = &self.task {
+            if let Err(e) = self.check_size_anomaly(&task.id, file_size).await {
+                tracing::warn!("Size anomaly check failed for backup {}: {}", self.id, e);
+            }
+        }
+
+        let archive_path = archive_path.to_string_lossy().to_string();
+        events::publish(AppEvent::BackupCreated {
+            backup_id: self.id.clone(),
+            file_path: archive_path.clone(),
+        });
         
         // Return the archive path as string
-        Ok(archive_path.to_string_lossy().to_string())
+        Ok(archive_path)
     }
     
     /// Create initial metadata file
@@ -99,6 +128,7 @@ impl BackupProcess {
             name: t.name.clone(),
             schedule: Some(t.cron_schedule.clone()),
             use_non_transactional: t.use_non_transactional,
+            where_clause: t.where_clause.clone(),
         });
         
         // Determine used_database for this backup
@@ -125,6 +155,16 @@ impl BackupProcess {
             created_at: Utc::now().to_rfc3339(),
             backup_type: self.backup_type.clone(),
             ident: None, // Will be set when archive is created
+            sha256_hash: None, // Computed later by the integrity worker
+            verified_at: None,
+            verification_error: None,
+            size_anomaly: None,
+            last_verification: None,
+            remote_location: None,
+            last_remote_verification: None,
+            binlog: None, // Set from mydumper's metadata file once the dump is done
+            encrypted: false,
+            server_version: None, // Set by record_server_version before the dump starts
             database_config: database_config_info,
             task_info,
         };
@@ -136,7 +176,7 @@ impl BackupProcess {
     }
     
     /// Create backup archive from tmp directory
-    async fn create_archive(&self) -> Result<PathBuf> {
+    async fn create_archive(&self, progress: Option<watch::Sender<u32>>) -> Result<PathBuf> {
         let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
         let archive_name = format!("{}-{}.{}", 
             self.database_config.database_name, 
@@ -146,11 +186,30 @@ impl BackupProcess {
         let archive_path = self.root_dir.join(&archive_name);
         
         // Create tar archive
-        self.create_tar_archive(&archive_path).await?;
+        if self.compression_type == "zstd" {
+            self.create_zstd_archive(&archive_path, progress).await?;
+        } else {
+            self.create_tar_archive(&archive_path).await?;
+        }
         
         Ok(archive_path)
     }
     
+    /// Replace the archive with its encrypted form, `<archive>.enc`
+    async fn encrypt_archive(&self, archive_path: PathBuf, key_name: String) -> Result<PathBuf> {
+        let encrypted_path = PathBuf::from(format!("{}.{}", archive_path.display(), encryption::ENCRYPTED_EXTENSION));
+        let (source, dest) = (archive_path.clone(), encrypted_path.clone());
+        let result = tokio::task::spawn_blocking(move || encryption::encrypt_file(&source, &dest, &key_name)).await?;
+
+        // The plaintext archive must not outlive the encryption, whether it succeeded or not
+        let _ = async_fs::remove_file(&archive_path).await;
+        if let Err(e) = result {
+            let _ = async_fs::remove_file(&encrypted_path).await;
+            return Err(e);
+        }
+        Ok(encrypted_path)
+    }
+
     /// Get archive extension based on compression type
     fn get_archive_extension(&self) -> &'static str {
         match self.compression_type.as_str() {
@@ -165,8 +224,8 @@ impl BackupProcess {
     async fn create_tar_archive(&self, output_path: &Path) -> Result<()> {
         use tokio::process::Command;
         
-        // Wait a moment to ensure all files are written
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        // Flush everything mydumper wrote to disk before tar reads it
+        sync_directory(&self.tmp_dir).await?;
         
         let mut cmd = Command::new("tar");
         
@@ -193,10 +252,23 @@ impl BackupProcess {
             return Err(anyhow!("Failed to create tar archive"));
         }
         
+        // Make sure the archive is durable before its size is recorded
+        async_fs::File::open(output_path).await?.sync_all().await?;
+        
         Ok(())
     }
     
     
+    /// Create a tar.zst archive in-process with a multithreaded zstd encoder
+    async fn create_zstd_archive(&self, output_path: &Path, progress: Option<watch::Sender<u32>>) -> Result<()> {
+        // Flush everything mydumper wrote to disk before reading it
+        sync_directory(&self.tmp_dir).await?;
+
+        let source_dir = self.tmp_dir.clone();
+        let output_path = output_path.to_path_buf();
+        tokio::task::spawn_blocking(move || write_zstd_archive(&source_dir, &output_path, progress)).await?
+    }
+
     /// Update metadata with final information
     async fn update_metadata(&self, archive_path: &Path, file_size: i64, sha256_hash: String) -> Result<()> {
         let content = async_fs::read_to_string(&self.meta_file).await?;
@@ -229,6 +301,15 @@ impl BackupProcess {
         Ok(())
     }
     
+    /// Note the version of the server being dumped in the metadata
+    pub async fn record_server_version(&self, version: &str) -> Result<()> {
+        let content = async_fs::read_to_string(&self.meta_file).await?;
+        let mut metadata: BackupMetadata = serde_json::from_str(&content)?;
+        metadata.server_version = Some(version.to_string());
+        async_fs::write(&self.meta_file, serde_json::to_string_pretty(&metadata)?).await?;
+        Ok(())
+    }
+
     /// Update metadata fast (no hash calculation)
     async fn update_metadata_fast(&self, archive_path: &Path, file_size: i64, file_modified: std::time::SystemTime) -> Result<()> {
         let content = async_fs::read_to_string(&self.meta_file).await?;
@@ -237,11 +318,18 @@ impl BackupProcess {
         // Update with file information (no hash needed)
         metadata.file_path = archive_path.to_string_lossy().to_string();
         metadata.file_size = file_size;
+        metadata.encrypted = encryption::is_encrypted(archive_path);
         // Use file modification time as a simple integrity check
         let modified_timestamp = file_modified.duration_since(std::time::UNIX_EPOCH)
             .unwrap_or_default()
             .as_secs();
         metadata.ident = Some(format!("size_{}_modified_{}", file_size, modified_timestamp));
+        // Binlog archiving for point-in-time restores starts where the dump's snapshot was taken
+        metadata.binlog = binlog::read_dump_position(&self.tmp_dir).await.map(|start| BinlogArchive {
+            start,
+            files: Vec::new(),
+            archived_at: None,
+        });
         
         let updated_content = serde_json::to_string_pretty(&metadata)?;
         async_fs::write(&self.meta_file, updated_content).await?;
@@ -249,11 +337,139 @@ impl BackupProcess {
         Ok(())
     }
     
+    /// Flag the backup in its metadata and publish an event when its size is anomalous
+    async fn check_size_anomaly(&self, task_id: &str, file_size: i64) -> Result<()> {
+        let backup_base_dir = self.root_dir.parent()
+            .ok_or_else(|| anyhow!("Backup directory has no parent"))?
+            .to_string_lossy()
+            .to_string();
+        let backup_service = FilesystemBackupService::new(backup_base_dir);
+        let Some(anomaly) = anomaly::check_backup_size(&backup_service, &self.id, task_id, file_size).await? else {
+            return Ok(());
+        };
+
+        tracing::warn!(
+            "Backup {} is {:.1}% off the usual size ({} bytes, expected about {})",
+            self.id, anomaly.deviation_percent, file_size, anomaly.expected_size
+        );
+
+        let content = async_fs::read_to_string(&self.meta_file).await?;
+        let mut metadata: BackupMetadata = serde_json::from_str(&content)?;
+        metadata.size_anomaly = Some(anomaly.clone());
+        async_fs::write(&self.meta_file, serde_json::to_string_pretty(&metadata)?).await?;
+        FilesystemBackupService::invalidate_all_caches().await;
+
+        events::publish(AppEvent::BackupSizeAnomaly {
+            backup_id: self.id.clone(),
+            task_id: task_id.to_string(),
+            file_size,
+            expected_size: anomaly.expected_size,
+            deviation_percent: anomaly.deviation_percent,
+        });
+        Ok(())
+    }
+
     /// Clean up tmp directory
-    async fn cleanup_tmp(&self) -> Result<()> {
+    pub async fn cleanup_tmp(&self) -> Result<()> {
         if self.tmp_dir.exists() {
             async_fs::remove_dir_all(&self.tmp_dir).await?;
         }
         Ok(())
     }
 }
+
+/// Fsync every file below a directory, and the directory entries themselves
+pub(crate) async fn sync_directory(dir: &Path) -> Result<()> {
+    let dir = dir.to_path_buf();
+    tokio::task::spawn_blocking(move || sync_directory_blocking(&dir)).await?
+}
+
+fn sync_directory_blocking(dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            sync_directory_blocking(&path)?;
+        } else if path.is_file() {
+            fs::File::open(&path)?.sync_all()?;
+        }
+    }
+    fs::File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+/// zstd level used for in-process archives, matching the tar CLI default
+pub(crate) const ZSTD_LEVEL: i32 = 3;
+
+/// Write `source_dir` as a tar.zst archive, sending progress by uncompressed bytes
+fn write_zstd_archive(source_dir: &Path, output_path: &Path, progress: Option<watch::Sender<u32>>) -> Result<()> {
+    let total_bytes = directory_size(source_dir)?.max(1);
+
+    let mut encoder = zstd::Encoder::new(fs::File::create(output_path)?, ZSTD_LEVEL)?;
+    let workers = std::thread::available_parallelism().map(|n| n.get() as u32).unwrap_or(1);
+    encoder.multithread(workers)?;
+
+    let writer = ProgressWriter {
+        inner: encoder,
+        written: 0,
+        total: total_bytes,
+        last_percent: 0,
+        progress,
+    };
+    let mut builder = tar::Builder::new(writer);
+    builder.append_dir_all(".", source_dir)?;
+
+    let writer = builder.into_inner()?;
+    let file = writer.inner.finish()?;
+    // Make sure the archive is durable before its size is recorded
+    file.sync_all()?;
+
+    if let Some(progress) = &writer.progress {
+        progress.send_replace(100);
+    }
+    Ok(())
+}
+
+/// Total size of all files below a directory
+fn directory_size(dir: &Path) -> Result<u64> {
+    let mut total = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            total += directory_size(&entry.path())?;
+        } else {
+            total += metadata.len();
+        }
+    }
+    Ok(total)
+}
+
+/// Writer adapter that counts bytes fed to the encoder and publishes a percentage
+struct ProgressWriter<W: Write> {
+    inner: W,
+    written: u64,
+    total: u64,
+    last_percent: u32,
+    progress: Option<watch::Sender<u32>>,
+}
+
+impl<W: Write> Write for ProgressWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.written += n as u64;
+
+        // Tar headers make the stream slightly larger than the files, stay below 100 until done
+        let percent = (self.written * 100 / self.total).min(99) as u32;
+        if percent > self.last_percent {
+            self.last_percent = percent;
+            if let Some(progress) = &self.progress {
+                progress.send_replace(percent);
+            }
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
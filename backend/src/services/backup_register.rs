@@ -0,0 +1,250 @@
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use tracing::info;
+use crate::models::{BackupMetadata, DatabaseConfig, DatabaseConfigInfo, Task, TaskInfo};
+use crate::services::archive_format::{self, ArchiveFormat};
+use crate::services::encryption;
+use crate::services::FilesystemBackupService;
+
+/// Backup type recorded for dumps produced outside rdumper
+pub const EXTERNAL_BACKUP_TYPE: &str = "external";
+
+/// User-supplied mapping for a dump produced outside rdumper
+pub struct RegisterOptions {
+    pub database_config: DatabaseConfig,
+    pub task: Option<Task>,
+    /// Database inside the dump, when it differs from the config's default database
+    pub database_name: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    /// Recorded as the backup's `backup_type`, usually [`EXTERNAL_BACKUP_TYPE`]
+    pub backup_type: &'static str,
+}
+
+/// Resolve a path to register inside the import directory, given absolute or relative to it.
+///
+/// Missing paths and paths outside the directory get the same error, so callers cannot probe the filesystem.
+pub async fn resolve_import_path(import_dir: &str, path: &str) -> Result<PathBuf> {
+    let root = tokio::fs::canonicalize(import_dir).await
+        .map_err(|e| anyhow!("Import directory {} is not accessible: {}", import_dir, e))?;
+    let not_importable = || anyhow!("{} is not a file or directory inside the import directory", path);
+    // Canonicalizing resolves `..` and symlinks before the containment check
+    let source = tokio::fs::canonicalize(root.join(path)).await.map_err(|_| not_importable())?;
+    if source == root || !source.starts_with(&root) {
+        return Err(not_importable());
+    }
+    Ok(source)
+}
+
+/// Register an existing mydumper directory or archive as a backup in the store; returns the new backup's metadata.
+///
+/// Tar archives are hard-linked (or copied) into a new backup folder unchanged; dump directories and
+/// other archive formats are packaged like an upload. The source is never modified.
+pub async fn register_backup(
+    backup_service: &FilesystemBackupService,
+    temp_dir: &str,
+    source: &Path,
+    options: RegisterOptions,
+) -> Result<BackupMetadata> {
+    if !source.is_absolute() {
+        return Err(anyhow!("Path must be absolute"));
+    }
+    if !source.exists() {
+        return Err(anyhow!("{} does not exist", source.display()));
+    }
+    let store = tokio::fs::canonicalize(backup_service.base_dir()).await?;
+    if tokio::fs::canonicalize(source).await?.starts_with(&store) {
+        return Err(anyhow!("{} is already inside the backup directory", source.display()));
+    }
+
+    let backup_id = uuid::Uuid::new_v4().to_string();
+    let (mut metadata, dump_started_at) = if source.is_dir() {
+        if !archive_format::is_mydumper_dump(source) {
+            return Err(anyhow!("{} does not look like mydumper output", source.display()));
+        }
+        let started_at = dump_started_at(source).await;
+        (package_directory(backup_service, &backup_id, source, &options).await?, started_at)
+    } else {
+        let detect_path = source.to_path_buf();
+        let format = tokio::task::spawn_blocking(move || ArchiveFormat::detect(&detect_path)).await??;
+        match format {
+            ArchiveFormat::TarGz | ArchiveFormat::TarZst | ArchiveFormat::Tar => {
+                (link_archive(backup_service, &backup_id, source, format, &options).await?, None)
+            }
+            // Any text file detects as plain SQL, so the name has to agree
+            ArchiveFormat::Sql | ArchiveFormat::SqlGz if !has_sql_extension(source) => {
+                return Err(anyhow!("{} is not a .sql or .sql.gz dump", source.display()));
+            }
+            _ => (package_archive(backup_service, temp_dir, &backup_id, source, &options).await?, None),
+        }
+    };
+
+    // Prefer the caller's timestamp, then the dump's own, then the file age
+    let created_at = match options.created_at.or(dump_started_at) {
+        Some(created_at) => created_at,
+        None => DateTime::<Utc>::from(tokio::fs::metadata(source).await?.modified()?),
+    };
+    let database_name = options.database_name.clone()
+        .unwrap_or_else(|| options.database_config.database_name.clone());
+    metadata.backup_type = options.backup_type.to_string();
+    metadata.created_at = created_at.to_rfc3339();
+    metadata.used_database = Some(format!("{}/{}", options.database_config.name, database_name));
+    metadata.database_name = database_name;
+    backup_service.save_backup_metadata(&metadata).await?;
+    FilesystemBackupService::invalidate_all_caches().await;
+
+    info!("Registered {} as backup {}", source.display(), metadata.id);
+    Ok(metadata)
+}
+
+/// Archive a dump directory into a new backup folder
+async fn package_directory(
+    backup_service: &FilesystemBackupService,
+    backup_id: &str,
+    source: &Path,
+    options: &RegisterOptions,
+) -> Result<BackupMetadata> {
+    let mut backup_process = backup_service
+        .create_backup_process(backup_id, &options.database_config, options.task.as_ref())
+        .await?;
+    copy_dir_files(source, backup_process.tmp_dir()).await?;
+    backup_process.complete().await?;
+    backup_service.load_backup_metadata(&backup_process.meta_file).await
+}
+
+/// Extract a zip or plain SQL dump and package its contents into a new backup folder
+async fn package_archive(
+    backup_service: &FilesystemBackupService,
+    temp_dir: &str,
+    backup_id: &str,
+    source: &Path,
+    options: &RegisterOptions,
+) -> Result<BackupMetadata> {
+    let extract_dir = PathBuf::from(format!("{}/register_{}", temp_dir, backup_id));
+    let result = async {
+        archive_format::extract_async(source.to_path_buf(), extract_dir.clone()).await?;
+        let content_dir = archive_format::content_root(&extract_dir);
+        package_directory(backup_service, backup_id, &content_dir, options).await
+    }.await;
+    let _ = tokio::fs::remove_dir_all(&extract_dir).await;
+    result
+}
+
+/// Place a tar archive into a new backup folder without re-packaging it
+async fn link_archive(
+    backup_service: &FilesystemBackupService,
+    backup_id: &str,
+    source: &Path,
+    format: ArchiveFormat,
+    options: &RegisterOptions,
+) -> Result<BackupMetadata> {
+    let (extension, compression_type) = match format {
+        ArchiveFormat::TarGz => ("tar.gz", "gzip"),
+        ArchiveFormat::TarZst => ("tar.zst", "zstd"),
+        _ => ("tar", "none"),
+    };
+    // Make sure the archive is readable before it appears in the catalog
+    let list_path = source.to_path_buf();
+    let entries = tokio::task::spawn_blocking(move || archive_format::list_entries(&list_path)).await??;
+    if entries.is_empty() {
+        return Err(anyhow!("{} contains no files", source.display()));
+    }
+
+    let folder = Path::new(backup_service.base_dir())
+        .join(backup_service.generate_backup_directory_name(&options.database_config, options.task.as_ref()));
+    tokio::fs::create_dir_all(&folder).await?;
+    // The scanner only recognises these extensions, so names like .tgz are normalised
+    let file_name = source.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let file_name = encryption::strip_extension(&file_name);
+    let stem = [".tar.gz", ".tgz", ".tar.zst", ".tar"].iter()
+        .find_map(|suffix| file_name.strip_suffix(suffix))
+        .unwrap_or(file_name);
+    let encrypted = encryption::is_encrypted(source);
+    let archive_path = if encrypted {
+        folder.join(format!("{}.{}.{}", stem, extension, encryption::ENCRYPTED_EXTENSION))
+    } else {
+        folder.join(format!("{}.{}", stem, extension))
+    };
+
+    // Same filesystem: a hard link is instant and leaves the source untouched
+    if tokio::fs::hard_link(source, &archive_path).await.is_err() {
+        if let Err(e) = tokio::fs::copy(source, &archive_path).await {
+            let _ = tokio::fs::remove_dir_all(&folder).await;
+            return Err(e.into());
+        }
+    }
+    let file_meta = tokio::fs::metadata(&archive_path).await?;
+    let file_size = file_meta.len() as i64;
+    let modified_timestamp = file_meta.modified()?.duration_since(std::time::UNIX_EPOCH)?.as_secs();
+
+    let config = &options.database_config;
+    Ok(BackupMetadata {
+        id: backup_id.to_string(),
+        database_name: config.database_name.clone(),
+        database_config_id: config.id.clone(),
+        task_id: options.task.as_ref().map(|t| t.id.clone()),
+        used_database: None,
+        file_path: archive_path.to_string_lossy().to_string(),
+        meta_path: folder.join("rdumper.backup.json").to_string_lossy().to_string(),
+        file_size,
+        compression_type: compression_type.to_string(),
+        created_at: Utc::now().to_rfc3339(),
+        backup_type: options.backup_type.to_string(),
+        ident: Some(format!("size_{}_modified_{}", file_size, modified_timestamp)),
+        sha256_hash: None, // Computed later by the integrity worker
+        verified_at: None,
+        verification_error: None,
+        size_anomaly: None,
+        last_verification: None,
+        remote_location: None,
+        last_remote_verification: None,
+        binlog: None,
+        encrypted,
+        // Not known for archives made elsewhere
+        server_version: None,
+        database_config: DatabaseConfigInfo {
+            id: config.id.clone(),
+            name: config.name.clone(),
+            host: config.host.clone(),
+            port: config.port as u16,
+            username: config.username.clone(),
+            database_name: config.database_name.clone(),
+        },
+        task_info: options.task.as_ref().map(|t| TaskInfo {
+            id: t.id.clone(),
+            name: t.name.clone(),
+            schedule: Some(t.cron_schedule.clone()),
+            use_non_transactional: t.use_non_transactional,
+            // Not known for archives made elsewhere
+            where_clause: None,
+        }),
+    })
+}
+
+/// Copy the regular files of a dump directory; mydumper output is flat
+async fn copy_dir_files(source: &Path, dest: &Path) -> Result<()> {
+    tokio::fs::create_dir_all(dest).await?;
+    let mut entries = tokio::fs::read_dir(source).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_file() {
+            tokio::fs::copy(entry.path(), dest.join(entry.file_name())).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Start time from mydumper's `metadata` file ("Started dump at: 2025-01-31 02:00:00"), read as UTC
+async fn dump_started_at(dir: &Path) -> Option<DateTime<Utc>> {
+    let content = tokio::fs::read_to_string(dir.join("metadata")).await.ok()?;
+    content.lines()
+        .find_map(|line| line.split_once("Started dump at:"))
+        .and_then(|(_, value)| NaiveDateTime::parse_from_str(value.trim(), "%Y-%m-%d %H:%M:%S").ok())
+        .map(|time| time.and_utc())
+}
+
+/// Whether a file is named like a plain SQL dump, ignoring an encryption extension
+fn has_sql_extension(path: &Path) -> bool {
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let file_name = encryption::strip_extension(&file_name);
+    file_name.ends_with(".sql") || file_name.ends_with(".sql.gz")
+}
@@ -0,0 +1,144 @@
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use futures::{Stream, StreamExt};
+use sqlx::SqlitePool;
+use tokio_util::bytes::Bytes;
+
+/// Request bodies are handed to the connection in slices of this size, so pacing stays smooth
+const SLICE_SIZE: usize = 64 * 1024;
+
+/// A bucket holds at most this many seconds worth of bytes, the largest burst it allows
+const BURST_SECONDS: f64 = 0.25;
+
+/// Token bucket refilled at a fixed rate; `tokens` goes negative while transfers wait
+struct Bucket {
+    bytes_per_second: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    fn new(bytes_per_second: u64) -> Self {
+        let bytes_per_second = bytes_per_second as f64;
+        Self { bytes_per_second, tokens: Self::capacity(bytes_per_second), refilled_at: Instant::now() }
+    }
+
+    fn capacity(bytes_per_second: f64) -> f64 {
+        (bytes_per_second * BURST_SECONDS).max(SLICE_SIZE as f64)
+    }
+
+    /// Take `bytes` out of the bucket and return how long the caller has to wait for them
+    fn take(&mut self, bytes: usize) -> Duration {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.bytes_per_second).min(Self::capacity(self.bytes_per_second));
+        self.refilled_at = now;
+        self.tokens -= bytes as f64;
+        if self.tokens < 0.0 {
+            Duration::from_secs_f64(-self.tokens / self.bytes_per_second)
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+/// Rate limit for a transfer; clones share their buckets, so every holder counts against one limit
+#[derive(Clone)]
+pub struct Throttle {
+    buckets: Vec<Arc<Mutex<Bucket>>>,
+}
+
+impl Throttle {
+    pub fn new(bytes_per_second: u64) -> Self {
+        Self { buckets: vec![Arc::new(Mutex::new(Bucket::new(bytes_per_second.max(1))))] }
+    }
+
+    /// A throttle that stays below both limits
+    pub fn and(mut self, other: Throttle) -> Self {
+        self.buckets.extend(other.buckets);
+        self
+    }
+
+    /// Wait until `bytes` may be sent
+    pub async fn consume(&self, bytes: usize) {
+        let wait = self.buckets.iter()
+            .map(|bucket| bucket.lock().unwrap().take(bytes))
+            .max()
+            .unwrap_or_default();
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+fn global_throttle() -> &'static OnceLock<Throttle> {
+    static GLOBAL: OnceLock<Throttle> = OnceLock::new();
+    &GLOBAL
+}
+
+/// Limit all uploads and downloads together to `kib_per_second`; 0 leaves them unlimited
+pub fn set_global_limit(kib_per_second: u32) {
+    if kib_per_second > 0 {
+        let _ = global_throttle().set(Throttle::new(kib_per_second as u64 * 1024));
+    }
+}
+
+/// The process-wide limit, if one is configured
+pub fn global() -> Option<Throttle> {
+    global_throttle().get().cloned()
+}
+
+/// The global limit combined with a task's own limit in KiB/s
+pub fn with_task_limit(task_limit_kib: Option<i32>) -> Option<Throttle> {
+    let task = task_limit_kib
+        .filter(|kib| *kib > 0)
+        .map(|kib| Throttle::new(kib as u64 * 1024));
+    match (global(), task) {
+        (Some(global), Some(task)) => Some(global.and(task)),
+        (global, task) => global.or(task),
+    }
+}
+
+/// Like `with_task_limit`, looking the task's limit up first
+pub async fn for_task_id(pool: &SqlitePool, task_id: Option<&str>) -> Option<Throttle> {
+    let task_limit = match task_id {
+        Some(task_id) => sqlx::query_scalar::<_, Option<i32>>("SELECT bandwidth_limit_kib FROM tasks WHERE id = ?")
+            .bind(task_id)
+            .fetch_optional(pool)
+            .await
+            .ok()
+            .flatten()
+            .flatten(),
+        None => None,
+    };
+    with_task_limit(task_limit)
+}
+
+/// Request body that is released at the throttle's pace; sent as is without one.
+///
+/// A paced body has no known length, so callers set `content-length` themselves.
+pub fn body(throttle: Option<&Throttle>, data: Vec<u8>) -> reqwest::Body {
+    let Some(throttle) = throttle.cloned() else {
+        return data.into();
+    };
+    let data = Bytes::from(data);
+    let slices = (0..data.len()).step_by(SLICE_SIZE)
+        .map(move |start| data.slice(start..(start + SLICE_SIZE).min(data.len())));
+    reqwest::Body::wrap_stream(pace(Some(throttle), futures::stream::iter(slices).map(Ok::<_, std::io::Error>)))
+}
+
+/// Delay each chunk of `stream` until the throttle allows it
+pub fn pace<S, E>(throttle: Option<Throttle>, stream: S) -> impl Stream<Item = Result<Bytes, E>>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+{
+    stream.then(move |chunk| {
+        let throttle = throttle.clone();
+        async move {
+            if let (Some(throttle), Ok(bytes)) = (&throttle, &chunk) {
+                throttle.consume(bytes.len()).await;
+            }
+            chunk
+        }
+    })
+}
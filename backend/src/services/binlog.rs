@@ -0,0 +1,317 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Local, Utc};
+use sqlx::SqlitePool;
+use tokio::process::Command as TokioCommand;
+use tracing::{info, warn};
+use crate::models::{Backup, BackupMetadata, BinlogArchive, BinlogPosition, CreateJobRequest, DatabaseConfig, Job, JobType, LogLevel};
+use crate::services::{demo, FilesystemBackupService, LoggingService};
+use crate::services::mydumper::ALL_DATABASES;
+use crate::services::events::{self, AppEvent};
+use crate::state::AppConfig;
+
+/// Directory inside a backup folder that archived binlogs are written to
+pub const BINLOG_DIR: &str = "binlogs";
+
+/// Binlog coordinates from the `metadata` file mydumper writes next to a dump.
+///
+/// Understands both the older `SHOW MASTER STATUS:` layout (`Log:` / `Pos:`) and the
+/// INI layout of newer releases (`File =` / `Position =`). The first pair is the source's.
+pub fn parse_mydumper_metadata(content: &str) -> Option<BinlogPosition> {
+    let mut file = None;
+    let mut position = None;
+    for line in content.lines().map(str::trim) {
+        let value = |prefix: &str| line.strip_prefix(prefix).map(|v| v.trim().trim_matches(|c| c == '\'' || c == '"').to_string());
+        if file.is_none() {
+            file = value("Log:").or_else(|| value("File =")).filter(|f| !f.is_empty());
+        }
+        if position.is_none() {
+            position = value("Pos:").or_else(|| value("Position =")).and_then(|p| p.parse::<u64>().ok());
+        }
+        if file.is_some() && position.is_some() {
+            break;
+        }
+    }
+    Some(BinlogPosition { file: file?, position: position? })
+}
+
+/// Where a dump's snapshot was taken, if the dump directory has mydumper's metadata file
+pub async fn read_dump_position(dump_dir: &Path) -> Option<BinlogPosition> {
+    let content = tokio::fs::read_to_string(dump_dir.join("metadata")).await.ok()?;
+    parse_mydumper_metadata(&content)
+}
+
+/// Start binlog jobs for tasks whose archiving interval has elapsed.
+///
+/// Binlogs go into the task's newest complete local backup that knows its start position, so
+/// each full backup carries the changes made until the next one.
+pub async fn start_due_archives(pool: &SqlitePool, config: &AppConfig) -> Result<Vec<String>> {
+    if demo::is_enabled() {
+        return Ok(Vec::new());
+    }
+    let schedules: Vec<(String, i32)> = sqlx::query_as(
+        "SELECT id, binlog_interval_minutes FROM tasks WHERE binlog_interval_minutes > 0"
+    )
+    .fetch_all(pool)
+    .await?;
+    if schedules.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let backup_service = FilesystemBackupService::new(config.backup_dir.clone());
+    let backups = backup_service.scan_backups().await?;
+    let mut started = Vec::new();
+
+    for (task_id, interval_minutes) in schedules {
+        if !is_due(pool, &task_id, interval_minutes as i64).await? {
+            continue;
+        }
+        let newest = backups.iter()
+            .filter(|b| b.task_id.as_deref() == Some(task_id.as_str()) && b.binlog.is_some() && Path::new(&b.file_path).exists())
+            .max_by(|a, b| a.created_at.cmp(&b.created_at));
+        if let Some(backup) = newest {
+            started.push(start_archive_job(pool, config, backup.clone(), task_id).await?);
+        }
+    }
+
+    Ok(started)
+}
+
+/// Whether the task has no binlog job running or created within the interval
+async fn is_due(pool: &SqlitePool, task_id: &str, interval_minutes: i64) -> Result<bool> {
+    let since = Utc::now() - Duration::minutes(interval_minutes);
+    let (recent,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM jobs WHERE job_type = 'binlog' AND task_id = ? AND (created_at > ? OR status IN ('pending', 'running'))"
+    )
+    .bind(task_id)
+    .bind(since)
+    .fetch_one(pool)
+    .await?;
+    Ok(recent == 0)
+}
+
+async fn start_archive_job(pool: &SqlitePool, config: &AppConfig, backup: Backup, task_id: String) -> Result<String> {
+    let job = Job::new(CreateJobRequest {
+        task_id: Some(task_id),
+        used_database: backup.used_database.clone(),
+        job_type: JobType::Binlog,
+        backup_path: Some(backup.file_path.clone()),
+    });
+
+    sqlx::query(
+        r#"
+        INSERT INTO jobs (id, task_id, used_database, job_type, status, progress, started_at, completed_at, error_message, log_output, backup_path, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&job.id)
+    .bind(&job.task_id)
+    .bind(&job.used_database)
+    .bind(&job.job_type)
+    .bind(&job.status)
+    .bind(job.progress)
+    .bind(job.started_at)
+    .bind(job.completed_at)
+    .bind(&job.error_message)
+    .bind(&job.log_output)
+    .bind(&job.backup_path)
+    .bind(job.created_at)
+    .execute(pool)
+    .await?;
+
+    let pool = pool.clone();
+    let config = config.clone();
+    let job_id = job.id.clone();
+    tokio::spawn(async move {
+        run_archive_job(&pool, &config, &backup, &job_id).await;
+    });
+
+    Ok(job.id)
+}
+
+async fn run_archive_job(pool: &SqlitePool, config: &AppConfig, backup: &Backup, job_id: &str) {
+    let logging_service = LoggingService::new(Arc::new(pool.clone()));
+
+    let _ = sqlx::query("UPDATE jobs SET status = ?, started_at = ? WHERE id = ?")
+        .bind("running")
+        .bind(Utc::now())
+        .bind(job_id)
+        .execute(pool)
+        .await;
+    events::publish(AppEvent::job_status(job_id, "running"));
+    let _ = logging_service.log_job(job_id, &format!("Archiving binary logs for backup {}", backup.id), LogLevel::Info).await;
+
+    let status = match archive_binlogs(pool, config, backup).await {
+        Ok(files) => {
+            info!("Archived {} binlog file(s) for backup {}", files, backup.id);
+            let _ = logging_service.log_job(job_id, &format!("Binlogs archived: {} file(s) in total", files), LogLevel::Info).await;
+            let _ = sqlx::query("UPDATE jobs SET status = ?, progress = ?, completed_at = ? WHERE id = ?")
+                .bind("completed")
+                .bind(100)
+                .bind(Utc::now())
+                .bind(job_id)
+                .execute(pool)
+                .await;
+            "completed"
+        }
+        Err(e) => {
+            warn!("Archiving binlogs for backup {} failed: {}", backup.id, e);
+            let _ = logging_service.log_job(job_id, &format!("Archiving binlogs failed: {}", e), LogLevel::Error).await;
+            let _ = sqlx::query("UPDATE jobs SET status = ?, error_message = ?, completed_at = ? WHERE id = ?")
+                .bind("failed")
+                .bind(e.to_string())
+                .bind(Utc::now())
+                .bind(job_id)
+                .execute(pool)
+                .await;
+            "failed"
+        }
+    };
+    events::publish(AppEvent::job_status(job_id, status));
+}
+
+/// Fetch binlogs from the server into the backup folder and record them; returns the number of files.
+///
+/// Starts at the newest archived file, which may have grown since, and stops at the server's current log.
+async fn archive_binlogs(pool: &SqlitePool, config: &AppConfig, backup: &Backup) -> Result<usize> {
+    let backup_service = FilesystemBackupService::new(config.backup_dir.clone());
+    let mut metadata = backup_service.load_backup_metadata(Path::new(&backup.meta_path)).await?;
+    let archive = metadata.binlog.clone()
+        .ok_or_else(|| anyhow!("Backup has no binlog start position"))?;
+    let database_config: DatabaseConfig = sqlx::query_as("SELECT * FROM database_configs WHERE id = ?")
+        .bind(&metadata.database_config_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| anyhow!("Database configuration {} no longer exists", metadata.database_config_id))?;
+
+    let binlog_dir = binlog_dir(&metadata)?;
+    tokio::fs::create_dir_all(&binlog_dir).await?;
+    let first_file = archive.files.last().unwrap_or(&archive.start.file);
+
+    let output = TokioCommand::new("mysqlbinlog")
+        .arg("--read-from-remote-server")
+        .arg("--host").arg(&database_config.host)
+        .arg("--port").arg(database_config.port.to_string())
+        .arg("--user").arg(&database_config.username)
+        .arg(format!("--password={}", database_config.password))
+        .arg("--raw")
+        .arg("--to-last-log")
+        // With --raw the result file is a prefix the binlog names are appended to
+        .arg(format!("--result-file={}/", binlog_dir.display()))
+        .arg(first_file)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| anyhow!("Failed to run mysqlbinlog: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!("mysqlbinlog failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    let mut files: Vec<String> = std::fs::read_dir(&binlog_dir)?
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+        .filter(|name| *name >= archive.start.file)
+        .collect();
+    files.sort();
+
+    metadata.binlog = Some(BinlogArchive {
+        files: files.clone(),
+        archived_at: Some(Utc::now().to_rfc3339()),
+        ..archive
+    });
+    backup_service.save_backup_metadata(&metadata).await?;
+    Ok(files.len())
+}
+
+fn binlog_dir(metadata: &BackupMetadata) -> Result<PathBuf> {
+    Path::new(&metadata.meta_path).parent()
+        .map(|folder| folder.join(BINLOG_DIR))
+        .ok_or_else(|| anyhow!("Backup metadata has no folder"))
+}
+
+/// Check that a point-in-time restore of `backup` up to `until` can be served by its binlogs
+pub fn check_point_in_time(backup: &Backup, until: DateTime<Utc>) -> Result<(), String> {
+    let archive = backup.binlog.as_ref()
+        .filter(|archive| !archive.files.is_empty())
+        .ok_or_else(|| "Backup has no archived binlogs for a point-in-time restore".to_string())?;
+    let created_at = DateTime::parse_from_rfc3339(&backup.created_at).map_err(|e| e.to_string())?;
+    if until < created_at {
+        return Err("Point in time is before the backup was taken".to_string());
+    }
+    let archived_at = archive.archived_at.as_deref()
+        .and_then(|at| DateTime::parse_from_rfc3339(at).ok());
+    if archived_at.is_none_or(|archived_at| until > archived_at) {
+        return Err(format!(
+            "Point in time is after the newest archived binlog ({})",
+            archive.archived_at.as_deref().unwrap_or("never")
+        ));
+    }
+    Ok(())
+}
+
+/// Apply the backup's archived binlogs to `target_database` up to `until`.
+///
+/// Only events of `source_database` are replayed, renamed to the target when they differ;
+/// backups of all databases replay every event into the database it was logged for.
+pub async fn replay(
+    database_config: &DatabaseConfig,
+    backup: &Backup,
+    source_database: &str,
+    target_database: &str,
+    until: DateTime<Utc>,
+) -> Result<()> {
+    let metadata: BackupMetadata = serde_json::from_str(&tokio::fs::read_to_string(&backup.meta_path).await?)?;
+    let archive = metadata.binlog.as_ref()
+        .ok_or_else(|| anyhow!("Backup has no archived binlogs"))?;
+    let binlog_dir = binlog_dir(&metadata)?;
+    if demo::is_enabled() {
+        return Ok(());
+    }
+
+    let mut binlog_cmd = TokioCommand::new("mysqlbinlog");
+    binlog_cmd
+        // Applies to the first file only, which is the one the dump's snapshot was taken in
+        .arg(format!("--start-position={}", archive.start.position))
+        // mysqlbinlog reads the stop time in the local timezone
+        .arg(format!("--stop-datetime={}", until.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S")));
+    let all_databases = source_database == ALL_DATABASES;
+    if !all_databases {
+        binlog_cmd.arg(format!("--database={}", source_database));
+        if source_database != target_database {
+            binlog_cmd.arg(format!("--rewrite-db={}->{}", source_database, target_database));
+        }
+    }
+    let mut binlog = binlog_cmd
+        .args(archive.files.iter().map(|file| binlog_dir.join(file)))
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| anyhow!("Failed to run mysqlbinlog: {}", e))?;
+    let events: Stdio = binlog.stdout.take()
+        .ok_or_else(|| anyhow!("Failed to read mysqlbinlog output"))?
+        .try_into()?;
+
+    let target = if all_databases { "their original databases" } else { target_database };
+    info!("Replaying {} binlog file(s) into {} up to {}", archive.files.len(), target, until);
+    let mysql_status = TokioCommand::new("mysql")
+        .arg("--host").arg(&database_config.host)
+        .arg("--port").arg(database_config.port.to_string())
+        .arg("--user").arg(&database_config.username)
+        .arg(format!("--password={}", database_config.password))
+        .args((!all_databases).then_some(target_database))
+        .stdin(events)
+        .kill_on_drop(true)
+        .status()
+        .await?;
+    let binlog_status = binlog.wait().await?;
+
+    if !binlog_status.success() {
+        return Err(anyhow!("mysqlbinlog failed while replaying binlogs"));
+    }
+    if !mysql_status.success() {
+        return Err(anyhow!("mysql failed while applying binlog events"));
+    }
+    Ok(())
+}
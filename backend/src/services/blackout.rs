@@ -0,0 +1,50 @@
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use sqlx::SqlitePool;
+use crate::models::BlackoutWindow;
+
+/// Bound on how many back-to-back windows are skipped, so windows covering every day still end
+const MAX_CHAINED_WINDOWS: usize = 64;
+
+/// Windows that apply to a task: its own and the global ones
+pub async fn windows_for_task(pool: &SqlitePool, task_id: &str) -> Result<Vec<BlackoutWindow>, sqlx::Error> {
+    sqlx::query_as("SELECT * FROM blackout_windows WHERE task_id IS NULL OR task_id = ? ORDER BY name")
+        .bind(task_id)
+        .fetch_all(pool)
+        .await
+}
+
+/// First moment at or after `at` outside every window, with the window that blocked `at`.
+///
+/// Returns `at` itself and no window when a run may start right away. Windows are
+/// evaluated in `tz`; windows with invalid settings are ignored.
+pub fn next_allowed(windows: &[BlackoutWindow], tz: Tz, at: DateTime<Utc>) -> (DateTime<Utc>, Option<&BlackoutWindow>) {
+    let mut allowed = at;
+    let mut blocked_by = None;
+    for _ in 0..MAX_CHAINED_WINDOWS {
+        let local = allowed.with_timezone(&tz).naive_local();
+        let covering = windows.iter()
+            .find_map(|window| window.end_of_window_at(local).ok().flatten().map(|end| (window, end)));
+        let Some((window, end)) = covering else {
+            break;
+        };
+        blocked_by.get_or_insert(window);
+        let end = to_utc(tz, end);
+        if end <= allowed {
+            break;
+        }
+        allowed = end;
+    }
+    (allowed, blocked_by)
+}
+
+/// A local wall-clock time as UTC; times skipped by a DST change move forward by the gap
+fn to_utc(tz: Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    let mut local = local;
+    loop {
+        if let Some(time) = tz.from_local_datetime(&local).earliest() {
+            return time.with_timezone(&Utc);
+        }
+        local += Duration::minutes(30);
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+
+/// Maximum line length in octets before folding (RFC 5545 §3.1)
+const MAX_LINE_OCTETS: usize = 75;
+
+/// A single VEVENT in an iCalendar feed
+#[derive(Debug, Clone)]
+pub struct CalendarEvent {
+    pub uid: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub summary: String,
+    pub description: String,
+}
+
+/// Render events as an RFC 5545 iCalendar document
+pub fn render_ics(calendar_name: &str, events: &[CalendarEvent]) -> String {
+    let stamp = format_datetime(Utc::now());
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//rdumper//Backup schedule//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(calendar_name)),
+    ];
+
+    for event in events {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", event.uid));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("DTSTART:{}", format_datetime(event.start)));
+        lines.push(format!("DTEND:{}", format_datetime(event.end)));
+        lines.push(format!("SUMMARY:{}", escape_text(&event.summary)));
+        lines.push(format!("DESCRIPTION:{}", escape_text(&event.description)));
+        lines.push("TRANSP:TRANSPARENT".to_string());
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut output = String::new();
+    for line in lines {
+        output.push_str(&fold_line(&line));
+        output.push_str("\r\n");
+    }
+    output
+}
+
+fn format_datetime(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escape TEXT values: backslash, semicolon, comma and newlines
+fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Fold long lines with CRLF + space, never splitting a UTF-8 character
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_LINE_OCTETS * 3);
+    let mut line_octets = 0;
+    for c in line.chars() {
+        // Continuation lines start with a space, which counts towards their length
+        if line_octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            line_octets = 1;
+        }
+        folded.push(c);
+        line_octets += c.len_utf8();
+    }
+    folded
+}
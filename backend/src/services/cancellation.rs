@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::Notify;
+
+/// Cancellation request of a running job
+#[derive(Default)]
+struct Cancellation {
+    notify: Notify,
+    cancelled: AtomicBool,
+    drop_database: AtomicBool,
+}
+
+/// Jobs running in this process that can be cancelled, by job ID
+fn running_jobs() -> &'static Mutex<HashMap<String, Arc<Cancellation>>> {
+    static RUNNING: OnceLock<Mutex<HashMap<String, Arc<Cancellation>>>> = OnceLock::new();
+    RUNNING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Registration of a running job, removed again when the job ends and this is dropped
+pub struct CancelGuard {
+    job_id: String,
+    cancellation: Arc<Cancellation>,
+}
+
+/// Make `job_id` cancellable through `cancel` while the returned guard lives
+pub fn register(job_id: &str) -> CancelGuard {
+    let cancellation = Arc::new(Cancellation::default());
+    running_jobs().lock().unwrap().insert(job_id.to_string(), cancellation.clone());
+    CancelGuard { job_id: job_id.to_string(), cancellation }
+}
+
+/// Ask a registered job to stop; false if it isn't running in this process
+pub fn cancel(job_id: &str, drop_database: bool) -> bool {
+    let Some(cancellation) = running_jobs().lock().unwrap().get(job_id).cloned() else {
+        return false;
+    };
+    cancellation.drop_database.store(drop_database, Ordering::SeqCst);
+    cancellation.cancelled.store(true, Ordering::SeqCst);
+    // Stores a permit, so a job not waiting yet still sees it
+    cancellation.notify.notify_one();
+    true
+}
+
+impl CancelGuard {
+    /// Resolves once the job is cancelled
+    pub async fn cancelled(&self) {
+        self.cancellation.notify.notified().await
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Whether the canceller asked for the partially restored database to be dropped
+    pub fn drop_database(&self) -> bool {
+        self.cancellation.drop_database.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for CancelGuard {
+    fn drop(&mut self) {
+        running_jobs().lock().unwrap().remove(&self.job_id);
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use crate::models::{Backup, CatalogEntry, CatalogManifest, CatalogRetention, RetentionState};
+use crate::services::FilesystemBackupService;
+
+const CATALOG_FORMAT_VERSION: u32 = 1;
+
+/// Build the catalog of all backups in the store, oldest first
+pub async fn build_manifest(pool: &SqlitePool, backup_service: &FilesystemBackupService) -> Result<CatalogManifest> {
+    // Only active tasks with a cleanup window delete backups, see TaskWorker::cleanup_old_backups
+    let cleanup_days: HashMap<String, i32> = sqlx::query_as::<_, (String, i32)>(
+        "SELECT id, cleanup_days FROM tasks WHERE is_active = true AND cleanup_days > 0"
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+
+    let mut backups = backup_service.scan_backups().await?;
+    backups.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+
+    let now = Utc::now();
+    let entries: Vec<CatalogEntry> = backups.into_iter()
+        .map(|backup| {
+            let days = backup.task_id.as_ref().and_then(|id| cleanup_days.get(id)).copied();
+            let retention = retention(&backup, days, now);
+            CatalogEntry {
+                id: backup.id,
+                database_config_id: backup.database_config_id,
+                database_name: backup.database_name,
+                used_database: backup.used_database,
+                task_id: backup.task_id,
+                backup_type: backup.backup_type,
+                created_at: backup.created_at,
+                file_path: backup.file_path,
+                meta_path: backup.meta_path,
+                file_size: backup.file_size,
+                compression_type: backup.compression_type,
+                sha256_hash: backup.sha256_hash,
+                verification_status: backup.verification_status,
+                retention,
+            }
+        })
+        .collect();
+
+    Ok(CatalogManifest {
+        format_version: CATALOG_FORMAT_VERSION,
+        generated_at: now.to_rfc3339(),
+        backup_dir: backup_service.base_dir().to_string(),
+        backup_count: entries.len(),
+        total_size: entries.iter().map(|e| e.file_size).sum(),
+        backups: entries,
+    })
+}
+
+fn retention(backup: &Backup, cleanup_days: Option<i32>, now: DateTime<Utc>) -> CatalogRetention {
+    let created_at = DateTime::parse_from_rfc3339(&backup.created_at).ok().map(|t| t.with_timezone(&Utc));
+    match (cleanup_days, created_at) {
+        (Some(days), Some(created_at)) => {
+            let expires_at = created_at + Duration::days(days as i64);
+            CatalogRetention {
+                state: if expires_at <= now { RetentionState::Expired } else { RetentionState::Scheduled },
+                cleanup_days: Some(days),
+                expires_at: Some(expires_at.to_rfc3339()),
+            }
+        }
+        // The cleanup skips backups whose creation date can't be parsed
+        _ => CatalogRetention {
+            state: RetentionState::Kept,
+            cleanup_days: None,
+            expires_at: None,
+        },
+    }
+}
+
+/// Hex HMAC-SHA256 of `body` under `key`
+pub fn sign(key: &str, body: &[u8]) -> Result<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes())
+        .map_err(|e| anyhow!("Invalid signing key: {}", e))?;
+    mac.update(body);
+    Ok(hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Hex SHA-256 of `body`, for consumers without the signing key
+pub fn content_hash(body: &[u8]) -> String {
+    hex::encode(Sha256::digest(body))
+}
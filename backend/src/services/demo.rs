@@ -0,0 +1,360 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use sqlx::SqlitePool;
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+use crate::models::{
+    CompressionType, CreateDatabaseConfigRequest, CreateJobRequest, CreateTaskRequest, DatabaseConfig, Job, JobType, Task,
+};
+use crate::services::FilesystemBackupService;
+use crate::state::AppConfig;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Example database the demo seeds a configuration and task for
+struct DemoDatabase {
+    config_name: &'static str,
+    database_name: &'static str,
+    tables: &'static [&'static str],
+    /// Non-InnoDB tables, shown as skipped like a real dump would
+    excluded: &'static [(&'static str, &'static str)],
+    task_name: &'static str,
+    cron_schedule: &'static str,
+    compression: CompressionType,
+    cleanup_days: i32,
+}
+
+const DEMO_DATABASES: &[DemoDatabase] = &[
+    DemoDatabase {
+        config_name: "Shop (demo)",
+        database_name: "shop",
+        tables: &["customers", "orders", "order_items", "products", "inventory", "coupons"],
+        excluded: &[("legacy_sessions", "MyISAM")],
+        task_name: "Shop nightly",
+        cron_schedule: "0 2 * * *",
+        compression: CompressionType::Gzip,
+        cleanup_days: 14,
+    },
+    DemoDatabase {
+        config_name: "Analytics (demo)",
+        database_name: "analytics",
+        tables: &["events", "page_views", "sessions", "daily_rollups"],
+        excluded: &[],
+        task_name: "Analytics every 6 hours",
+        cron_schedule: "0 */6 * * *",
+        compression: CompressionType::Zstd,
+        cleanup_days: 3,
+    },
+];
+
+/// Tables dumped for databases the demo doesn't know, e.g. configs added by hand
+const FALLBACK_TABLES: &[&str] = &["users", "settings", "audit_log"];
+
+/// Hosts of seeded configs; `.invalid` never resolves, so nothing real is contacted
+const DEMO_HOST: &str = "demo.invalid";
+
+/// Version every demo server reports
+pub const SERVER_VERSION: &str = "8.0.36";
+
+/// Simulate mydumper, myloader and MySQL for the rest of the process
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// InnoDB and excluded tables of a demo database, in the shape the engine analysis returns
+pub fn tables(database_name: &str) -> (Vec<String>, Vec<String>) {
+    match DEMO_DATABASES.iter().find(|db| db.database_name == database_name) {
+        Some(db) => (
+            db.tables.iter().map(|t| t.to_string()).collect(),
+            db.excluded.iter().map(|(t, engine)| format!("{} ({})", t, engine)).collect(),
+        ),
+        None => (FALLBACK_TABLES.iter().map(|t| t.to_string()).collect(), Vec::new()),
+    }
+}
+
+/// Data plus index size of each table in a demo database, fixed per table name so estimates are stable
+pub fn table_sizes(database_name: &str) -> Vec<(String, i64)> {
+    let (tables, excluded) = tables(database_name);
+    tables.into_iter()
+        .chain(excluded.iter().map(|entry| entry.split(" (").next().unwrap_or(entry).to_string()))
+        .map(|table| {
+            let size = table.bytes().map(i64::from).sum::<i64>() * 256 * 1024;
+            (table, size)
+        })
+        .collect()
+}
+
+/// Databases a demo server reports
+pub fn databases() -> Vec<String> {
+    let mut databases = vec!["information_schema".to_string(), "mysql".to_string()];
+    databases.extend(DEMO_DATABASES.iter().map(|db| db.database_name.to_string()));
+    databases
+}
+
+/// Stand-in for a mydumper run: writes dump files and mydumper-style log lines over a few seconds,
+/// so the progress tracker and log views behave as for a real backup
+pub async fn simulate_dump(output_dir: &Path, log_file: &mut tokio::fs::File, database_name: &str, tables: &[String]) -> Result<()> {
+    let started_at = Utc::now();
+    let total = tables.len();
+    for (index, table) in tables.iter().enumerate() {
+        let rows = rand::thread_rng().gen_range(500..50_000);
+        let thread = index % 4 + 1;
+        log_line(log_file, &format!("{}.{} has ~{} rows", database_name, table, rows)).await?;
+        for percent in [0, 25, 50, 75, 100] {
+            let delay = Duration::from_millis(rand::thread_rng().gen_range(200..600));
+            tokio::time::sleep(delay).await;
+            log_line(
+                log_file,
+                &format!("Thread {}: `{}`.`{}` [ {}% ] | Tables: {}/{}", thread, database_name, table, percent, index + 1, total),
+            ).await?;
+        }
+        // Files appear table by table like mydumper's, so an interrupted dump leaves the finished ones
+        write_dump_files(output_dir, database_name, std::slice::from_ref(table), started_at).await?;
+    }
+    log_line(log_file, &format!("Finished dump at: {}", Utc::now().format("%Y-%m-%d %H:%M:%S"))).await?;
+    Ok(())
+}
+
+/// Stand-in for a myloader run: takes a moment per table in the dump
+pub async fn simulate_restore(source_dir: &Path, target_database: &str, selected_tables: &[String]) -> Result<()> {
+    let mut tables = Vec::new();
+    let mut entries = tokio::fs::read_dir(source_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if let Some(table) = entry.file_name().to_string_lossy().strip_suffix("-schema.sql") {
+            tables.push(table.to_string());
+        }
+    }
+    if !selected_tables.is_empty() {
+        tables = selected_tables.to_vec();
+    }
+    tables.sort();
+    tokio::time::sleep(Duration::from_secs((tables.len() as u64).clamp(2, 10))).await;
+    info!("Demo restore of {} tables ({}) into {} finished", tables.len(), tables.join(", "), target_database);
+    Ok(())
+}
+
+async fn log_line(log_file: &mut tokio::fs::File, message: &str) -> Result<()> {
+    let line = format!("{} [INFO] - {}\n", Utc::now().format("%Y-%m-%d %H:%M:%S"), message);
+    log_file.write_all(line.as_bytes()).await?;
+    log_file.flush().await?;
+    Ok(())
+}
+
+/// Files in mydumper's layout with a handful of generated rows per table
+async fn write_dump_files(dir: &Path, database_name: &str, tables: &[String], started_at: DateTime<Utc>) -> Result<()> {
+    tokio::fs::create_dir_all(dir).await?;
+    tokio::fs::write(
+        dir.join("metadata"),
+        format!(
+            "Started dump at: {}\nSHOW MASTER STATUS:\n\tLog: mysql-bin.000042\n\tPos: 1337\n\nFinished dump at: {}\n",
+            started_at.format("%Y-%m-%d %H:%M:%S"),
+            Utc::now().format("%Y-%m-%d %H:%M:%S"),
+        ),
+    ).await?;
+    tokio::fs::write(
+        dir.join(format!("{}-schema-create.sql", database_name)),
+        format!("CREATE DATABASE IF NOT EXISTS `{}`;\n", database_name),
+    ).await?;
+
+    for table in tables {
+        tokio::fs::write(
+            dir.join(format!("{}.{}-schema.sql", database_name, table)),
+            format!(
+                "CREATE TABLE `{}` (\n  `id` int NOT NULL AUTO_INCREMENT,\n  `name` varchar(255) DEFAULT NULL,\n  `created_at` datetime DEFAULT NULL,\n  PRIMARY KEY (`id`)\n) ENGINE=InnoDB;\n",
+                table
+            ),
+        ).await?;
+        let rows = rand::thread_rng().gen_range(20..200);
+        let values: Vec<String> = (1..=rows)
+            .map(|id| format!("({},'{}-{}','{}')", id, table, id, started_at.format("%Y-%m-%d %H:%M:%S")))
+            .collect();
+        tokio::fs::write(
+            dir.join(format!("{}.{}.00000.sql", database_name, table)),
+            format!("INSERT INTO `{}` VALUES\n{};\n", table, values.join(",\n")),
+        ).await?;
+    }
+    Ok(())
+}
+
+/// Create example configs, tasks and a few days of backup history, unless the instance already has configs
+pub async fn seed(pool: &SqlitePool, config: &AppConfig) -> Result<()> {
+    let (existing,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM database_configs")
+        .fetch_one(pool)
+        .await?;
+    if existing > 0 {
+        info!("Demo mode: database configurations exist, skipping seed data");
+        return Ok(());
+    }
+
+    let backup_service = FilesystemBackupService::new(config.backup_dir.clone());
+    for demo in DEMO_DATABASES {
+        let mut db_config = DatabaseConfig::new(CreateDatabaseConfigRequest {
+            name: demo.config_name.to_string(),
+            host: DEMO_HOST.to_string(),
+            port: Some(3306),
+            username: "demo".to_string(),
+            password: "demo".to_string(),
+            database_name: Some(demo.database_name.to_string()),
+            rpo_hours: Some(24),
+            agent_id: None,
+        });
+        db_config.mark_connection_tested(true);
+        sqlx::query(
+            r#"
+            INSERT INTO database_configs (id, name, host, port, username, password, database_name, connection_status, last_tested, rpo_hours, agent_id, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&db_config.id)
+        .bind(&db_config.name)
+        .bind(&db_config.host)
+        .bind(db_config.port)
+        .bind(&db_config.username)
+        .bind(&db_config.password)
+        .bind(&db_config.database_name)
+        .bind(&db_config.connection_status)
+        .bind(db_config.last_tested)
+        .bind(db_config.rpo_hours)
+        .bind(&db_config.agent_id)
+        .bind(db_config.created_at)
+        .bind(db_config.updated_at)
+        .execute(pool)
+        .await?;
+
+        let mut task = Task::new(CreateTaskRequest {
+            name: demo.task_name.to_string(),
+            database_config_id: db_config.id.clone(),
+            database_name: Some(demo.database_name.to_string()),
+            cron_schedule: demo.cron_schedule.to_string(),
+            compression_type: Some(demo.compression),
+            cleanup_days: Some(demo.cleanup_days),
+            use_non_transactional: None,
+            pause_after_failures: None,
+            verify_interval_days: None,
+            storage_destination_id: None,
+            keep_local: None,
+            bandwidth_limit_kib: None,
+            timezone: None,
+            retry_max_attempts: None,
+            retry_backoff_seconds: None,
+            include_tables: None,
+            exclude_tables: None,
+            where_clause: None,
+            threads: None,
+            rows_per_chunk: None,
+            chunk_filesize_mb: None,
+            long_query_guard_seconds: None,
+            binlog_interval_minutes: None,
+            encryption_key: None,
+            timeout_minutes: None,
+            notify_on: None,
+            notify_email: None,
+        });
+        task.update_next_run().map_err(|e| anyhow!(e))?;
+        sqlx::query(
+            r#"
+            INSERT INTO tasks (id, name, database_config_id, database_name, cron_schedule, compression_type, cleanup_days, use_non_transactional, is_active, last_run, next_run, pause_after_failures, verify_interval_days, storage_destination_id, keep_local, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&task.id)
+        .bind(&task.name)
+        .bind(&task.database_config_id)
+        .bind(&task.database_name)
+        .bind(&task.cron_schedule)
+        .bind(&task.compression_type)
+        .bind(task.cleanup_days)
+        .bind(task.use_non_transactional)
+        .bind(task.is_active)
+        .bind(task.last_run)
+        .bind(task.next_run)
+        .bind(task.pause_after_failures)
+        .bind(task.verify_interval_days)
+        .bind(&task.storage_destination_id)
+        .bind(task.keep_local)
+        .bind(task.created_at)
+        .bind(task.updated_at)
+        .execute(pool)
+        .await?;
+
+        // One backup per day for the last three days
+        for days_ago in 1..=3 {
+            seed_backup(pool, &backup_service, &db_config, &task, demo.database_name, days_ago).await?;
+        }
+    }
+
+    FilesystemBackupService::invalidate_all_caches().await;
+    info!("Demo mode: seeded {} database configurations with tasks and backups", DEMO_DATABASES.len());
+    Ok(())
+}
+
+/// A completed backup job with a real archive, dated `days_ago` days back
+async fn seed_backup(
+    pool: &SqlitePool,
+    backup_service: &FilesystemBackupService,
+    db_config: &DatabaseConfig,
+    task: &Task,
+    database_name: &str,
+    days_ago: i64,
+) -> Result<()> {
+    let created_at = Utc::now() - chrono::Duration::days(days_ago);
+    let mut job = Job::new(CreateJobRequest {
+        task_id: Some(task.id.clone()),
+        used_database: Some(format!("{}/{}", db_config.name, database_name)),
+        job_type: JobType::Backup,
+        backup_path: None,
+    });
+
+    let (tables, _) = tables(database_name);
+    let mut backup_process = backup_service.create_backup_process(&job.id, db_config, Some(task)).await?;
+    write_dump_files(backup_process.tmp_dir(), database_name, &tables, created_at).await?;
+    let archive_path = backup_process.complete().await?;
+
+    let mut metadata = backup_service.load_backup_metadata(&backup_process.meta_file).await?;
+    metadata.created_at = created_at.to_rfc3339();
+    metadata.used_database = job.used_database.clone();
+    backup_service.save_backup_metadata(&metadata).await?;
+    // Age-based cleanup and listings look at the file time
+    let modified = std::time::SystemTime::from(created_at);
+    let archive_for_mtime = archive_path.clone();
+    tokio::task::spawn_blocking(move || {
+        std::fs::File::options().write(true).open(&archive_for_mtime)?.set_modified(modified)
+    }).await??;
+
+    job.status = "completed".to_string();
+    job.progress = 100;
+    job.started_at = Some(created_at);
+    job.completed_at = Some(created_at + chrono::Duration::seconds(rand::thread_rng().gen_range(20..240)));
+    job.log_output = Some(format!("Demo backup of {} ({} tables)", database_name, tables.len()));
+    job.backup_path = Some(archive_path);
+    job.created_at = created_at;
+    sqlx::query(
+        r#"
+        INSERT INTO jobs (id, task_id, used_database, job_type, status, progress, started_at, completed_at, error_message, log_output, backup_path, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&job.id)
+    .bind(&job.task_id)
+    .bind(&job.used_database)
+    .bind(&job.job_type)
+    .bind(&job.status)
+    .bind(job.progress)
+    .bind(job.started_at)
+    .bind(job.completed_at)
+    .bind(&job.error_message)
+    .bind(&job.log_output)
+    .bind(&job.backup_path)
+    .bind(job.created_at)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
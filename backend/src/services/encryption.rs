@@ -0,0 +1,212 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+
+/// Marks an archive encrypted by rDumper
+const MAGIC: &[u8] = b"RDUMPENC";
+const VERSION: u8 = 1;
+/// Appended to the archive's file name once it is encrypted
+pub const ENCRYPTED_EXTENSION: &str = "enc";
+/// Plaintext bytes per sealed chunk
+const CHUNK_SIZE: usize = 1024 * 1024;
+/// Random part of each chunk's nonce; the rest is the chunk counter and a final-chunk flag
+const NONCE_PREFIX_LEN: usize = NONCE_LEN - 5;
+/// Used when `set_secrets_dir` isn't called, the mount point of Docker and Kubernetes secrets
+const DEFAULT_SECRETS_DIR: &str = "/run/secrets";
+
+fn secrets_dir_cell() -> &'static OnceLock<PathBuf> {
+    static SECRETS_DIR: OnceLock<PathBuf> = OnceLock::new();
+    &SECRETS_DIR
+}
+
+/// Directory holding encryption keys, one file per key named after it
+pub fn set_secrets_dir(dir: &str) {
+    let _ = secrets_dir_cell().set(PathBuf::from(dir));
+}
+
+fn secrets_dir() -> &'static Path {
+    secrets_dir_cell().get_or_init(|| PathBuf::from(DEFAULT_SECRETS_DIR))
+}
+
+/// Load the 256-bit key stored as hex or base64 under `name` in the secrets directory
+fn load_key(name: &str) -> Result<LessSafeKey> {
+    let valid_name = !name.is_empty()
+        && name.len() <= u8::MAX as usize
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if !valid_name {
+        return Err(anyhow!("Invalid encryption key name '{}'", name));
+    }
+    let path = secrets_dir().join(name);
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| anyhow!("Encryption key '{}' not found in {}: {}", name, secrets_dir().display(), e))?;
+    let content = content.trim();
+    let bytes = hex::decode(content)
+        .or_else(|_| base64::engine::general_purpose::STANDARD.decode(content))
+        .map_err(|_| anyhow!("Encryption key '{}' is neither hex nor base64", name))?;
+    let key = UnboundKey::new(&AES_256_GCM, &bytes)
+        .map_err(|_| anyhow!("Encryption key '{}' must be 32 bytes, found {}", name, bytes.len()))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Check that `name` refers to a usable key, so tasks can't be saved with a missing one
+pub fn check_key(name: &str) -> Result<(), String> {
+    load_key(name).map(|_| ()).map_err(|e| e.to_string())
+}
+
+fn chunk_nonce(prefix: &[u8; NONCE_PREFIX_LEN], counter: u32, last: bool) -> Nonce {
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_LEN..NONCE_LEN - 1].copy_from_slice(&counter.to_be_bytes());
+    nonce[NONCE_LEN - 1] = last as u8;
+    Nonce::assume_unique_for_key(nonce)
+}
+
+/// Encrypt `src` into `dest` with AES-256-GCM under the key `key_name`.
+///
+/// The file starts with the magic, version, key name and nonce prefix; the data follows in
+/// chunks of up to 1 MiB, each sealed with the header as associated data. The last chunk is
+/// flagged in its nonce, so a truncated file fails to decrypt instead of restoring partially.
+pub fn encrypt_file(src: &Path, dest: &Path, key_name: &str) -> Result<()> {
+    let key = load_key(key_name)?;
+    let mut prefix = [0u8; NONCE_PREFIX_LEN];
+    SystemRandom::new().fill(&mut prefix).map_err(|_| anyhow!("Failed to generate a nonce"))?;
+
+    let mut header = MAGIC.to_vec();
+    header.push(VERSION);
+    header.push(key_name.len() as u8);
+    header.extend_from_slice(key_name.as_bytes());
+    header.extend_from_slice(&prefix);
+
+    let mut reader = BufReader::new(File::open(src)?);
+    let mut writer = BufWriter::new(File::create(dest)?);
+    writer.write_all(&header)?;
+
+    let mut chunk = Vec::with_capacity(CHUNK_SIZE + AES_256_GCM.tag_len());
+    let mut counter: u32 = 0;
+    loop {
+        chunk.clear();
+        (&mut reader).take(CHUNK_SIZE as u64).read_to_end(&mut chunk)?;
+        // A short read is the end of the file; a file of whole chunks ends with an empty one
+        let last = chunk.len() < CHUNK_SIZE;
+        key.seal_in_place_append_tag(chunk_nonce(&prefix, counter, last), Aad::from(&header), &mut chunk)
+            .map_err(|_| anyhow!("Failed to encrypt archive"))?;
+        writer.write_all(&[last as u8])?;
+        writer.write_all(&(chunk.len() as u32).to_be_bytes())?;
+        writer.write_all(&chunk)?;
+        if last {
+            break;
+        }
+        counter = counter.checked_add(1).ok_or_else(|| anyhow!("Archive is too large to encrypt"))?;
+    }
+
+    writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    Ok(())
+}
+
+/// File name of the archive inside an encrypted one, e.g. `db.tar.gz` for `db.tar.gz.enc`
+pub fn strip_extension(file_name: &str) -> &str {
+    file_name.strip_suffix(ENCRYPTED_EXTENSION)
+        .and_then(|name| name.strip_suffix('.'))
+        .unwrap_or(file_name)
+}
+
+/// Whether `path` is an archive written by `encrypt_file`
+pub fn is_encrypted(path: &Path) -> bool {
+    let mut head = [0u8; MAGIC.len()];
+    File::open(path).and_then(|mut file| file.read_exact(&mut head)).is_ok() && head == MAGIC
+}
+
+/// Open a backup archive for reading, decrypting it on the fly when it is encrypted
+pub fn open(path: &Path) -> Result<Box<dyn Read>> {
+    let file = BufReader::new(File::open(path)?);
+    if is_encrypted(path) {
+        Ok(Box::new(DecryptReader::new(file)?))
+    } else {
+        Ok(Box::new(file))
+    }
+}
+
+/// Plaintext of an encrypted archive, authenticated chunk by chunk
+pub struct DecryptReader<R> {
+    inner: R,
+    key: LessSafeKey,
+    header: Vec<u8>,
+    prefix: [u8; NONCE_PREFIX_LEN],
+    counter: u32,
+    chunk: Vec<u8>,
+    position: usize,
+    finished: bool,
+}
+
+impl<R: Read> DecryptReader<R> {
+    /// Read the header and load the key it names from the secrets directory
+    pub fn new(mut inner: R) -> Result<Self> {
+        let mut fixed = [0u8; MAGIC.len() + 2];
+        inner.read_exact(&mut fixed)?;
+        if &fixed[..MAGIC.len()] != MAGIC {
+            return Err(anyhow!("Archive is not encrypted"));
+        }
+        if fixed[MAGIC.len()] != VERSION {
+            return Err(anyhow!("Unsupported encryption version {}", fixed[MAGIC.len()]));
+        }
+        let mut key_name = vec![0u8; fixed[MAGIC.len() + 1] as usize];
+        inner.read_exact(&mut key_name)?;
+        let mut prefix = [0u8; NONCE_PREFIX_LEN];
+        inner.read_exact(&mut prefix)?;
+
+        let key_name = String::from_utf8(key_name).map_err(|_| anyhow!("Encrypted archive has an invalid key name"))?;
+        let key = load_key(&key_name)?;
+        let mut header = fixed.to_vec();
+        header.extend_from_slice(key_name.as_bytes());
+        header.extend_from_slice(&prefix);
+
+        Ok(Self { inner, key, header, prefix, counter: 0, chunk: Vec::new(), position: 0, finished: false })
+    }
+
+    fn read_chunk(&mut self) -> std::io::Result<()> {
+        let invalid = |message: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string());
+
+        let mut chunk_header = [0u8; 5];
+        self.inner.read_exact(&mut chunk_header)
+            .map_err(|_| invalid("Encrypted archive is truncated"))?;
+        let last = chunk_header[0] == 1;
+        let len = u32::from_be_bytes([chunk_header[1], chunk_header[2], chunk_header[3], chunk_header[4]]) as usize;
+        if len > CHUNK_SIZE + AES_256_GCM.tag_len() {
+            return Err(invalid("Encrypted archive has an oversized chunk"));
+        }
+
+        self.chunk.resize(len, 0);
+        self.inner.read_exact(&mut self.chunk)
+            .map_err(|_| invalid("Encrypted archive is truncated"))?;
+        let nonce = chunk_nonce(&self.prefix, self.counter, last);
+        let plaintext_len = self.key.open_in_place(nonce, Aad::from(&self.header), &mut self.chunk)
+            .map_err(|_| invalid("Encrypted archive failed authentication, wrong key or corrupted data"))?
+            .len();
+        self.chunk.truncate(plaintext_len);
+        self.position = 0;
+        self.counter = self.counter.wrapping_add(1);
+        self.finished = last;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecryptReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.position == self.chunk.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            self.read_chunk()?;
+        }
+        let n = buf.len().min(self.chunk.len() - self.position);
+        buf[..n].copy_from_slice(&self.chunk[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+    }
+}
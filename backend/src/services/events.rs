@@ -0,0 +1,100 @@
+use std::sync::OnceLock;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// Buffered events per subscriber before slow clients start missing events
+const EVENT_BUFFER: usize = 256;
+
+/// Typed application events broadcast to `/api/events` subscribers
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AppEvent {
+    JobStatusChanged {
+        job_id: String,
+        status: String,
+    },
+    BackupCreated {
+        backup_id: String,
+        file_path: String,
+    },
+    BackupDeleted {
+        backup_id: String,
+    },
+    WorkerTick {
+        total_ticks: u64,
+        at: DateTime<Utc>,
+    },
+    ConnectionTested {
+        database_config_id: String,
+        success: bool,
+    },
+    BackupSizeAnomaly {
+        backup_id: String,
+        task_id: String,
+        file_size: i64,
+        expected_size: i64,
+        deviation_percent: f64,
+    },
+    JobDurationAnomaly {
+        job_id: String,
+        task_id: String,
+        running_seconds: i64,
+        expected_seconds: i64,
+    },
+    RpoStatusChanged {
+        database_config_id: String,
+        violated: bool,
+        age_hours: f64,
+    },
+    TaskAutoPaused {
+        task_id: String,
+        consecutive_failures: i32,
+    },
+    JobRetryScheduled {
+        job_id: String,
+        task_id: String,
+        attempt: i32,
+        retry_at: DateTime<Utc>,
+    },
+}
+
+impl AppEvent {
+    pub fn job_status(job_id: &str, status: &str) -> Self {
+        AppEvent::JobStatusChanged {
+            job_id: job_id.to_string(),
+            status: status.to_string(),
+        }
+    }
+
+    /// Event name used for the SSE `event:` field
+    pub fn name(&self) -> &'static str {
+        match self {
+            AppEvent::JobStatusChanged { .. } => "job_status_changed",
+            AppEvent::BackupCreated { .. } => "backup_created",
+            AppEvent::BackupDeleted { .. } => "backup_deleted",
+            AppEvent::WorkerTick { .. } => "worker_tick",
+            AppEvent::ConnectionTested { .. } => "connection_tested",
+            AppEvent::BackupSizeAnomaly { .. } => "backup_size_anomaly",
+            AppEvent::JobDurationAnomaly { .. } => "job_duration_anomaly",
+            AppEvent::RpoStatusChanged { .. } => "rpo_status_changed",
+            AppEvent::TaskAutoPaused { .. } => "task_auto_paused",
+            AppEvent::JobRetryScheduled { .. } => "job_retry_scheduled",
+        }
+    }
+}
+
+fn bus() -> &'static broadcast::Sender<AppEvent> {
+    static BUS: OnceLock<broadcast::Sender<AppEvent>> = OnceLock::new();
+    BUS.get_or_init(|| broadcast::channel(EVENT_BUFFER).0)
+}
+
+/// Broadcast an event to all current subscribers; dropped silently when nobody listens
+pub fn publish(event: AppEvent) {
+    let _ = bus().send(event);
+}
+
+/// Subscribe to all events published from now on
+pub fn subscribe() -> broadcast::Receiver<AppEvent> {
+    bus().subscribe()
+}
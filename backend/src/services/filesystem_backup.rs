@@ -1,13 +1,58 @@
 use anyhow::{anyhow, Result};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 use tokio::fs;
+use tokio::sync::RwLock;
 use tracing::{warn, info};
 use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
 
-use crate::models::{Backup, BackupMetadata, DatabaseConfigInfo, TaskInfo, DatabaseConfig, Task};
-use crate::services::backup_process::BackupProcess;
+use crate::models::{ArchiveEntry, Backup, BackupLocation, BackupMetadata, DatabaseConfigInfo, TaskInfo, DatabaseConfig, Task};
+use crate::services::{archive_format, binlog, encryption};
+use crate::services::backup_process::{sync_directory, BackupProcess};
 
+/// How long a cached scan is trusted before the filesystem is walked again.
+/// Changes made through the API invalidate the cache immediately; the TTL only
+/// matters for backups copied into or removed from the backup directory by hand.
+const CATALOG_TTL: Duration = Duration::from_secs(300);
+
+/// Result of a full filesystem scan, indexed by backup id
+struct CatalogSnapshot {
+    backups: Vec<Backup>,
+    index: HashMap<String, usize>,
+    scanned_at: Instant,
+}
+
+impl CatalogSnapshot {
+    fn new(backups: Vec<Backup>) -> Self {
+        let index = backups.iter()
+            .enumerate()
+            .map(|(i, b)| (b.id.clone(), i))
+            .collect();
+        Self { backups, index, scanned_at: Instant::now() }
+    }
+
+    fn is_fresh(&self) -> bool {
+        self.scanned_at.elapsed() < CATALOG_TTL
+    }
+}
+
+/// Process-wide backup catalog shared by all service instances, keyed by backup base directory
+fn catalog() -> &'static RwLock<HashMap<String, CatalogSnapshot>> {
+    static CATALOG: OnceLock<RwLock<HashMap<String, CatalogSnapshot>>> = OnceLock::new();
+    CATALOG.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Position of a lazy backup directory walk
+struct ScanCursor {
+    pending_dirs: Vec<PathBuf>,
+    entries: Option<fs::ReadDir>,
+}
+
+#[derive(Clone)]
 pub struct FilesystemBackupService {
     backup_base_dir: String,
 }
@@ -16,9 +61,14 @@ impl FilesystemBackupService {
     pub fn new(backup_base_dir: String) -> Self {
         Self { backup_base_dir }
     }
+
+    /// Root directory of the backup store
+    pub fn base_dir(&self) -> &str {
+        &self.backup_base_dir
+    }
     
     /// Generate a human-readable backup directory name: <db-name>-<uuid>
-    fn generate_backup_directory_name(&self, database_config: &DatabaseConfig, task: Option<&Task>) -> String {
+    pub(crate) fn generate_backup_directory_name(&self, database_config: &DatabaseConfig, task: Option<&Task>) -> String {
         let uuid = uuid::Uuid::new_v4().to_string();
         
         // Use used_database format if we have task info, otherwise fall back to config name
@@ -46,6 +96,17 @@ impl FilesystemBackupService {
             .replace("|", "_");
         format!("{}-{}", sanitized_name, uuid)
     }
+
+    /// The `<config>-<database>` part of a directory name from `generate_backup_directory_name`,
+    /// shared by every backup of the same database
+    pub(crate) fn backup_name_stem(directory_name: &str) -> &str {
+        // "-" plus a hyphenated UUID is 37 bytes
+        let split = directory_name.len().saturating_sub(37);
+        match directory_name.get(split..) {
+            Some(suffix) if split > 0 && suffix.starts_with('-') && uuid::Uuid::parse_str(&suffix[1..]).is_ok() => &directory_name[..split],
+            _ => directory_name,
+        }
+    }
     
     /// Create a new backup process
     pub async fn create_backup_process(
@@ -75,16 +136,100 @@ impl FilesystemBackupService {
         Ok(backup_process)
     }
 
-    /// Scan filesystem for all backups and return them as Backup structs
+    /// Reopen the backup process a job left in `root_dir`, keeping its metadata and dump files
+    pub fn resume_backup_process(
+        &self,
+        root_dir: PathBuf,
+        backup_id: &str,
+        database_config: &DatabaseConfig,
+        task: Option<&Task>,
+    ) -> BackupProcess {
+        let compression_type = task.map(|t| t.compression_type.clone()).unwrap_or_else(|| "gzip".to_string());
+        BackupProcess::new(
+            backup_id.to_string(),
+            root_dir,
+            database_config.clone(),
+            task.cloned(),
+            "scheduled".to_string(),
+            compression_type,
+        )
+    }
+
+    /// The folder of an unfinished backup process, one whose dump is still in its tmp directory
+    pub async fn find_unfinished_process(&self, backup_id: &str) -> Result<Option<PathBuf>> {
+        let mut entries = match fs::read_dir(&self.backup_base_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let root_dir = entry.path();
+            if !root_dir.join("tmp").is_dir() {
+                continue;
+            }
+            let Ok(metadata) = self.load_backup_metadata(&root_dir.join("rdumper.backup.json")).await else {
+                continue;
+            };
+            if metadata.id == backup_id {
+                return Ok(Some(root_dir));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Return all backups, served from the shared catalog when a fresh scan is cached
     pub async fn scan_backups(&self) -> Result<Vec<Backup>> {
-        let mut backups = Vec::new();
-        
-        if !Path::new(&self.backup_base_dir).exists() {
-            return Ok(backups);
+        if let Some(snapshot) = catalog().read().await.get(&self.backup_base_dir) {
+            if snapshot.is_fresh() {
+                return Ok(snapshot.backups.clone());
+            }
+        }
+
+        let mut catalog = catalog().write().await;
+        // Another request may have refreshed the catalog while we waited for the lock
+        if let Some(snapshot) = catalog.get(&self.backup_base_dir) {
+            if snapshot.is_fresh() {
+                return Ok(snapshot.backups.clone());
+            }
+        }
+
+        let backups = self.scan_filesystem().await?;
+        catalog.insert(self.backup_base_dir.clone(), CatalogSnapshot::new(backups.clone()));
+        Ok(backups)
+    }
+
+    /// Look up a single backup by id without walking the backup directory on every call.
+    /// Falls back to one rescan if the id is unknown or its archive vanished from disk.
+    pub async fn find_backup(&self, id: &str) -> Result<Option<Backup>> {
+        self.scan_backups().await?;
+
+        if let Some(snapshot) = catalog().read().await.get(&self.backup_base_dir) {
+            if let Some(&i) = snapshot.index.get(id) {
+                let backup = &snapshot.backups[i];
+                if Path::new(&backup.file_path).exists() {
+                    return Ok(Some(backup.clone()));
+                }
+            }
         }
 
-        // Recursively search for backup files
-        self.scan_directory_recursive(Path::new(&self.backup_base_dir), &mut backups).await?;
+        self.invalidate_cache().await;
+        let backups = self.scan_backups().await?;
+        Ok(backups.into_iter().find(|b| b.id == id))
+    }
+
+    /// Drop the cached scan for this service's backup directory
+    pub async fn invalidate_cache(&self) {
+        catalog().write().await.remove(&self.backup_base_dir);
+    }
+
+    /// Drop all cached scans, e.g. after a backup process wrote a new archive
+    pub async fn invalidate_all_caches() {
+        catalog().write().await.clear();
+    }
+
+    /// Walk the backup directory and return all backups as Backup structs
+    async fn scan_filesystem(&self) -> Result<Vec<Backup>> {
+        let mut backups: Vec<Backup> = self.stream_backups().try_collect().await?;
 
         // Sort by creation date (newest first)
         backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
@@ -92,72 +237,104 @@ impl FilesystemBackupService {
         Ok(backups)
     }
 
-    /// Recursively scan directory for backup files
-    async fn scan_directory_recursive(&self, dir_path: &Path, backups: &mut Vec<Backup>) -> Result<()> {
-        tracing::info!("Scanning directory: {:?}", dir_path);
-        let mut entries = fs::read_dir(dir_path).await?;
-        
-        while let Some(entry) = entries.next_entry().await? {
-            let path = entry.path();
-            
-            if path.is_dir() {
-                tracing::info!("Found directory: {:?}", path);
-                // Check if this is a backup folder (contains rdumper.backup.json)
-                let meta_file = path.join("rdumper.backup.json");
-                tracing::info!("Checking for metadata file: {:?}", meta_file);
-                if meta_file.exists() {
-                    tracing::info!("Found metadata file, processing backup folder");
-                    // This is a backup folder, load its metadata
-                    match self.load_backup_metadata(&meta_file).await {
-                        Ok(metadata) => {
-                            // Find the backup file in this folder
-                            if let Some(backup_file) = self.find_backup_file_in_folder(&path).await? {
-                                let backup = Backup {
-                                    id: metadata.id,
-                                    database_name: metadata.database_name,
-                                    database_config_id: metadata.database_config_id,
-                                    task_id: metadata.task_id,
-                                    used_database: metadata.used_database,
-                                    file_path: backup_file.to_string_lossy().to_string(),
-                                    meta_path: meta_file.to_string_lossy().to_string(),
-                                    file_size: metadata.file_size,
-                                    compression_type: metadata.compression_type,
-                                    created_at: metadata.created_at,
-                                    backup_type: metadata.backup_type,
-                                };
-                                backups.push(backup);
-                            }
+    /// Lazily walk the backup directory, yielding backups in directory order as they are found
+    pub fn stream_backups(&self) -> impl Stream<Item = Result<Backup>> + '_ {
+        let root = PathBuf::from(&self.backup_base_dir);
+        let cursor = ScanCursor {
+            pending_dirs: if root.exists() { vec![root] } else { Vec::new() },
+            entries: None,
+        };
+
+        stream::try_unfold(cursor, move |mut cursor| async move {
+            loop {
+                let entries = match cursor.entries.as_mut() {
+                    Some(entries) => entries,
+                    None => match cursor.pending_dirs.pop() {
+                        Some(dir) => {
+                            tracing::info!("Scanning directory: {:?}", dir);
+                            cursor.entries.insert(fs::read_dir(&dir).await?)
                         }
-                        Err(e) => {
-                            warn!("Failed to load metadata from {}: {}", meta_file.display(), e);
+                        None => return Ok(None),
+                    },
+                };
+
+                match entries.next_entry().await? {
+                    Some(entry) => {
+                        if let Some(backup) = self.inspect_entry(&entry.path(), &mut cursor.pending_dirs).await? {
+                            return Ok(Some((backup, cursor)));
                         }
                     }
-                } else {
-                    // Check if this directory contains backup files without metadata
-                    if let Some(backup_file) = self.find_backup_file_in_folder(&path).await? {
-                        // Found a backup file without metadata, create it
-                        info!("Found backup file without metadata: {}, creating metadata", backup_file.display());
-                        let meta_path = self.create_metadata_file_for_backup(&backup_file).await?;
-                        let backup = self.create_dummy_backup(&backup_file, &meta_path).await?;
-                        backups.push(backup);
-                    } else {
-                        // Recursively scan subdirectories that are not backup folders
-                        Box::pin(self.scan_directory_recursive(&path, backups)).await?;
-                    }
+                    None => cursor.entries = None,
                 }
-            } else if path.is_file() {
-                // Check if this is a backup file in the root directory
-                if self.is_backup_file(&path).is_some() {
-                    // Found a backup file without metadata, create it
-                    info!("Found backup file without metadata: {}, creating metadata", path.display());
-                    let meta_path = self.create_metadata_file_for_backup(&path).await?;
-                    let backup = self.create_dummy_backup(&path, &meta_path).await?;
-                    backups.push(backup);
+            }
+        })
+    }
+
+    /// Return one page of unsorted backups, stopping the scan once the page is filled.
+    /// The flag tells whether more matching backups exist after this page.
+    pub async fn scan_backups_page<F>(&self, offset: usize, limit: usize, filter: F) -> Result<(Vec<Backup>, bool)>
+    where
+        F: Fn(&Backup) -> bool,
+    {
+        let mut page: Vec<Backup> = self.stream_backups()
+            .try_filter(|backup| std::future::ready(filter(backup)))
+            .skip(offset)
+            .take(limit + 1)
+            .try_collect()
+            .await?;
+
+        let has_more = page.len() > limit;
+        page.truncate(limit);
+        Ok((page, has_more))
+    }
+
+    /// Turn a directory entry into a backup, queueing plain subdirectories for scanning
+    async fn inspect_entry(&self, path: &Path, pending_dirs: &mut Vec<PathBuf>) -> Result<Option<Backup>> {
+        if path.is_dir() {
+            tracing::info!("Found directory: {:?}", path);
+            // Check if this is a backup folder (contains rdumper.backup.json)
+            let meta_file = path.join("rdumper.backup.json");
+            tracing::info!("Checking for metadata file: {:?}", meta_file);
+            if meta_file.exists() {
+                tracing::info!("Found metadata file, processing backup folder");
+                // This is a backup folder, load its metadata
+                match self.load_backup_metadata(&meta_file).await {
+                    Ok(metadata) => {
+                        // Find the backup file in this folder
+                        if let Some(backup_file) = self.find_backup_file_in_folder(path).await? {
+                            let location = match metadata.remote_location {
+                                Some(_) => BackupLocation::LocalAndRemote,
+                                None => BackupLocation::Local,
+                            };
+                            return Ok(Some(backup_from_metadata(metadata, &backup_file, &meta_file, location)));
+                        }
+                        // Archives moved off-host keep their folder with only the metadata
+                        if metadata.remote_location.is_some() && !metadata.file_path.is_empty() {
+                            let archive = PathBuf::from(&metadata.file_path);
+                            return Ok(Some(backup_from_metadata(metadata, &archive, &meta_file, BackupLocation::Remote)));
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Failed to load metadata from {}: {}", meta_file.display(), e);
+                    }
                 }
+            } else if let Some(backup_file) = self.find_backup_file_in_folder(path).await? {
+                // Found a backup file without metadata, create it
+                info!("Found backup file without metadata: {}, creating metadata", backup_file.display());
+                let meta_path = self.create_metadata_file_for_backup(&backup_file).await?;
+                return Ok(Some(self.create_dummy_backup(&backup_file, &meta_path).await?));
+            } else {
+                // Scan subdirectories that are not backup folders
+                pending_dirs.push(path.to_path_buf());
             }
+        } else if path.is_file() && self.is_backup_file(path).is_some() {
+            // Found a backup file without metadata in a plain directory, create it
+            info!("Found backup file without metadata: {}, creating metadata", path.display());
+            let meta_path = self.create_metadata_file_for_backup(path).await?;
+            return Ok(Some(self.create_dummy_backup(path, &meta_path).await?));
         }
-        
-        Ok(())
+
+        Ok(None)
     }
 
     /// Find backup file in a folder
@@ -169,6 +346,7 @@ impl FilesystemBackupService {
             
             if path.is_file() {
                 if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
+                    let file_name = encryption::strip_extension(file_name);
                     if file_name.ends_with(".tar.gz") || file_name.ends_with(".tar.zst") || file_name.ends_with(".tar") {
                         return Ok(Some(path));
                     }
@@ -182,7 +360,8 @@ impl FilesystemBackupService {
     /// Check if a file is a backup file
     fn is_backup_file(&self, path: &Path) -> Option<String> {
         if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-            if file_name.ends_with(".tar.gz") || file_name.ends_with(".tar.zst") || file_name.ends_with(".tar") {
+            let archive_name = encryption::strip_extension(file_name);
+            if archive_name.ends_with(".tar.gz") || archive_name.ends_with(".tar.zst") || archive_name.ends_with(".tar") {
                 return Some(file_name.to_string());
             }
         }
@@ -223,11 +402,13 @@ impl FilesystemBackupService {
         let modified_timestamp = modified_time.duration_since(std::time::UNIX_EPOCH)?.as_secs();
         
         // Determine compression type from file extension
-        let compression_type = if backup_path.to_string_lossy().ends_with(".tar.zst") {
+        let archive_path = backup_path.to_string_lossy();
+        let archive_path = encryption::strip_extension(&archive_path);
+        let compression_type = if archive_path.ends_with(".tar.zst") {
             "zstd"
-        } else if backup_path.to_string_lossy().ends_with(".tar.gz") {
+        } else if archive_path.ends_with(".tar.gz") {
             "gzip"
-        } else if backup_path.to_string_lossy().ends_with(".tar") {
+        } else if archive_path.ends_with(".tar") {
             "none"
         } else {
             "unknown"
@@ -240,7 +421,7 @@ impl FilesystemBackupService {
         
         // Try to extract database name and timestamp from filename
         // Expected format: <database>-<timestamp>.tar.gz
-        let (database_name, created_at, ident) = self.parse_backup_filename(filename, file_size, modified_timestamp);
+        let (database_name, created_at, ident) = self.parse_backup_filename(encryption::strip_extension(filename), file_size, modified_timestamp);
         
         // Create metadata with extracted information
         let dummy_metadata = serde_json::json!({
@@ -256,6 +437,7 @@ impl FilesystemBackupService {
             "created_at": created_at,
             "backup_type": "external",
             "ident": ident,
+            "encrypted": encryption::is_encrypted(backup_path),
             "database_config": {
                 "id": "unknown",
                 "name": format!("Unknown Database ({})", database_name),
@@ -308,22 +490,7 @@ impl FilesystemBackupService {
     async fn create_dummy_backup(&self, backup_path: &Path, meta_path: &Path) -> Result<Backup> {
         // Load the metadata we just created to get the extracted information
         let metadata = self.load_backup_metadata(meta_path).await?;
-        
-        let backup = Backup {
-            id: metadata.id,
-            database_name: metadata.database_name,
-            database_config_id: metadata.database_config_id,
-            task_id: metadata.task_id,
-            used_database: metadata.used_database,
-            file_path: backup_path.to_string_lossy().to_string(),
-            meta_path: meta_path.to_string_lossy().to_string(),
-            file_size: metadata.file_size,
-            compression_type: metadata.compression_type,
-            created_at: metadata.created_at,
-            backup_type: metadata.backup_type,
-        };
-        
-        Ok(backup)
+        Ok(backup_from_metadata(metadata, backup_path, meta_path, BackupLocation::Local))
     }
 
     /// Load backup metadata from JSON file
@@ -337,6 +504,7 @@ impl FilesystemBackupService {
     pub async fn save_backup_metadata(&self, metadata: &BackupMetadata) -> Result<()> {
         let content = serde_json::to_string_pretty(metadata)?;
         fs::write(&metadata.meta_path, content).await?;
+        self.invalidate_cache().await;
         Ok(())
     }
 
@@ -344,8 +512,8 @@ impl FilesystemBackupService {
     async fn create_tar_gz_from_directory(&self, source_dir: &str, output_path: &Path) -> Result<()> {
         use tokio::process::Command;
         
-        // Wait a moment to ensure all files are written
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        // Flush the source files to disk before tar reads them
+        sync_directory(Path::new(source_dir)).await?;
         
         let mut cmd = Command::new("tar");
         cmd.args(&[
@@ -409,6 +577,7 @@ impl FilesystemBackupService {
             name: t.name.clone(),
             schedule: Some(t.cron_schedule.clone()),
             use_non_transactional: t.use_non_transactional,
+            where_clause: t.where_clause.clone(),
         });
         
         // Determine used_database for this backup
@@ -536,6 +705,7 @@ impl FilesystemBackupService {
             name: t.name.clone(),
             schedule: Some(t.cron_schedule.clone()),
             use_non_transactional: t.use_non_transactional,
+            where_clause: t.where_clause.clone(),
         });
         
         let backup_metadata = BackupMetadata::new(&backup, database_config_info, task_info);
@@ -556,6 +726,14 @@ impl FilesystemBackupService {
         if std::path::Path::new(&backup.meta_path).exists() {
             fs::remove_file(&backup.meta_path).await?;
         }
+
+        // Binlogs archived for point-in-time restores belong to this backup only
+        if let Some(parent) = std::path::Path::new(&backup.meta_path).parent() {
+            let binlog_dir = parent.join(binlog::BINLOG_DIR);
+            if binlog_dir.exists() {
+                fs::remove_dir_all(&binlog_dir).await?;
+            }
+        }
         
         // Try to remove empty parent directory
         if let Some(parent) = std::path::Path::new(&backup.file_path).parent() {
@@ -565,10 +743,27 @@ impl FilesystemBackupService {
                 }
             }
         }
-        
+
+        self.invalidate_cache().await;
         Ok(())
     }
 
+    /// List the files inside a backup archive by reading only the archive headers
+    pub async fn list_archive_contents(&self, backup: &Backup) -> Result<Vec<ArchiveEntry>> {
+        let file_path = PathBuf::from(&backup.file_path);
+
+        let entries = tokio::task::spawn_blocking(move || archive_format::list_entries(&file_path)).await??;
+        Ok(entries.into_iter()
+            .map(|(path, size)| ArchiveEntry::from_path(path, size))
+            .collect())
+    }
+
+    /// Read the archive once for a restore preview: its files, row counts and mydumper metadata
+    pub async fn inspect_archive(&self, backup: &Backup) -> Result<archive_format::DumpInspection> {
+        let file_path = PathBuf::from(&backup.file_path);
+        tokio::task::spawn_blocking(move || archive_format::inspect_dump(&file_path)).await?
+    }
+
     /// Get backup statistics
     pub async fn get_backup_stats(&self) -> Result<BackupStats> {
         let backups = self.scan_backups().await?;
@@ -593,6 +788,35 @@ impl FilesystemBackupService {
     }
 }
 
+/// Catalog entry for a backup folder's metadata and archive
+fn backup_from_metadata(metadata: BackupMetadata, archive: &Path, meta_path: &Path, location: BackupLocation) -> Backup {
+    let verification_status = metadata.verification_status();
+    Backup {
+        id: metadata.id,
+        database_name: metadata.database_name,
+        database_config_id: metadata.database_config_id,
+        task_id: metadata.task_id,
+        used_database: metadata.used_database,
+        file_path: archive.to_string_lossy().to_string(),
+        meta_path: meta_path.to_string_lossy().to_string(),
+        file_size: metadata.file_size,
+        compression_type: metadata.compression_type,
+        created_at: metadata.created_at,
+        backup_type: metadata.backup_type,
+        sha256_hash: metadata.sha256_hash,
+        verification_status,
+        verified_at: metadata.verified_at,
+        size_anomaly: metadata.size_anomaly,
+        last_verification: metadata.last_verification,
+        location,
+        remote_location: metadata.remote_location,
+        last_remote_verification: metadata.last_remote_verification,
+        binlog: metadata.binlog,
+        encrypted: metadata.encrypted,
+        server_version: metadata.server_version,
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BackupStats {
     pub total_count: usize,
@@ -0,0 +1,347 @@
+use std::path::Path;
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use ring::rand::SystemRandom;
+use ring::signature::{RsaKeyPair, RSA_PKCS1_SHA256};
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncReadExt;
+use tokio::sync::watch;
+use crate::models::GcsTarget;
+use crate::services::bandwidth::{self, Throttle};
+use crate::services::remote_storage::RemoteObject;
+use crate::services::s3_storage::uri_encode;
+
+/// Archives up to this size go up in one request; larger ones as resumable uploads in chunks of
+/// this size, which GCS requires to be a multiple of 256 KiB
+const CHUNK_SIZE: usize = 16 * 1024 * 1024;
+const DEFAULT_ENDPOINT: &str = "https://storage.googleapis.com";
+const DEFAULT_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+const SCOPE: &str = "https://www.googleapis.com/auth/devstorage.read_write";
+
+/// The fields rdumper needs from a service account key file
+#[derive(Deserialize)]
+struct ServiceAccountKey {
+    #[serde(rename = "type")]
+    key_type: String,
+    client_email: String,
+    private_key: String,
+    #[serde(default)]
+    private_key_id: Option<String>,
+    #[serde(default)]
+    token_uri: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ObjectList {
+    #[serde(default)]
+    items: Vec<ObjectResource>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ObjectResource {
+    name: String,
+    /// The JSON API sends sizes as strings
+    size: String,
+    updated: Option<DateTime<Utc>>,
+}
+
+/// Check that a service account key file is usable without contacting Google
+pub fn validate_credentials(credentials_json: &str) -> Result<()> {
+    parse_credentials(credentials_json).map(|_| ())
+}
+
+/// Minimal Google Cloud Storage client using the JSON API with an OAuth token from a service account
+pub struct GcsClient {
+    target: GcsTarget,
+    http: reqwest::Client,
+    access_token: String,
+}
+
+impl GcsClient {
+    /// Exchange a signed JWT for an access token; tokens last an hour, longer than any single upload
+    pub async fn connect(target: GcsTarget) -> Result<Self> {
+        let (key, key_pair) = parse_credentials(&target.credentials_json)?;
+        let token_uri = key.token_uri.clone().unwrap_or_else(|| DEFAULT_TOKEN_URI.to_string());
+        let now = Utc::now().timestamp();
+
+        let header = URL_SAFE_NO_PAD.encode(json!({ "alg": "RS256", "typ": "JWT", "kid": key.private_key_id }).to_string());
+        let claims = URL_SAFE_NO_PAD.encode(json!({
+            "iss": key.client_email,
+            "scope": SCOPE,
+            "aud": token_uri,
+            "iat": now,
+            "exp": now + 3600,
+        }).to_string());
+        let signing_input = format!("{}.{}", header, claims);
+        let mut signature = vec![0u8; key_pair.public().modulus_len()];
+        key_pair.sign(&RSA_PKCS1_SHA256, &SystemRandom::new(), signing_input.as_bytes(), &mut signature)
+            .map_err(|_| anyhow!("Failed to sign the GCS token request"))?;
+        let assertion = format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(signature));
+
+        // Resumable uploads answer 308 without a Location header, which must not be followed
+        let http = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        let response = http.post(&token_uri)
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(format!("grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Ajwt-bearer&assertion={}", assertion))
+            .send()
+            .await?;
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            let description = serde_json::from_str::<serde_json::Value>(&text).ok()
+                .and_then(|v| v["error_description"].as_str().or(v["error"].as_str()).map(str::to_string))
+                .unwrap_or(text);
+            return Err(anyhow!("GCS rejected the service account ({}): {}", status, description));
+        }
+        let token: TokenResponse = serde_json::from_str(&text)
+            .map_err(|e| anyhow!("Invalid GCS token response: {}", e))?;
+
+        Ok(Self { target, http, access_token: token.access_token })
+    }
+
+    /// Full object name for a path below the configured prefix
+    pub fn object_name(&self, path: &str) -> String {
+        let prefix = self.target.prefix.trim_matches('/');
+        if prefix.is_empty() {
+            path.to_string()
+        } else {
+            format!("{}/{}", prefix, path)
+        }
+    }
+
+    pub fn url_for(&self, name: &str) -> String {
+        format!("gs://{}/{}", self.target.bucket, name)
+    }
+
+    /// Upload a file, as resumable upload when it is larger than one chunk; returns the file's SHA-256
+    pub async fn upload_file(&self, name: &str, path: &Path, progress: Option<watch::Sender<u32>>, throttle: Option<&Throttle>) -> Result<String> {
+        let total = tokio::fs::metadata(path).await?.len();
+        let mut file = tokio::fs::File::open(path).await?;
+        let mut hasher = Sha256::new();
+
+        if total <= CHUNK_SIZE as u64 {
+            let mut body = Vec::with_capacity(total as usize);
+            file.read_to_end(&mut body).await?;
+            hasher.update(&body);
+            self.put_object_paced(name, body, throttle).await?;
+            if let Some(tx) = &progress {
+                let _ = tx.send(100);
+            }
+            return Ok(hex::encode(hasher.finalize()));
+        }
+
+        let response = self.http.post(self.upload_url(name, "resumable"))
+            .bearer_auth(&self.access_token)
+            .header("x-upload-content-type", "application/octet-stream")
+            .header("x-upload-content-length", total)
+            .header("content-length", 0)
+            .send()
+            .await?;
+        let response = check(response).await?;
+        let session = response.headers().get("location")
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| anyhow!("GCS returned no upload session"))?
+            .to_string();
+
+        let result = async {
+            let mut sent: u64 = 0;
+            while sent < total {
+                let chunk = read_chunk(&mut file).await?;
+                if chunk.is_empty() {
+                    return Err(anyhow!("Archive shrank while uploading"));
+                }
+                hasher.update(&chunk);
+                let end = sent + chunk.len() as u64;
+                let response = self.http.put(&session)
+                    .header("content-range", format!("bytes {}-{}/{}", sent, end - 1, total))
+                    .header("content-length", chunk.len())
+                    .body(bandwidth::body(throttle, chunk))
+                    .send()
+                    .await?;
+                // 308 means "chunk stored, send the next one"; GCS may keep less than it was sent
+                if response.status() == StatusCode::PERMANENT_REDIRECT {
+                    let stored = response.headers().get("range")
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|range| range.rsplit('-').next())
+                        .and_then(|last| last.parse::<u64>().ok())
+                        .map(|last| last + 1)
+                        .unwrap_or(0);
+                    if stored != end {
+                        return Err(anyhow!("GCS stored {} of {} bytes", stored, end));
+                    }
+                } else {
+                    check(response).await?;
+                }
+                sent = end;
+                if let Some(tx) = &progress {
+                    let _ = tx.send((sent * 100 / total) as u32);
+                }
+            }
+            Ok(())
+        }.await;
+
+        if let Err(e) = result {
+            // Cancel the session so the partial object is discarded
+            let _ = self.http.delete(&session).header("content-length", 0).send().await;
+            return Err(e);
+        }
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    pub async fn put_object(&self, name: &str, body: Vec<u8>) -> Result<()> {
+        self.put_object_paced(name, body, None).await
+    }
+
+    /// `put_object` with the body released at the throttle's pace
+    async fn put_object_paced(&self, name: &str, body: Vec<u8>, throttle: Option<&Throttle>) -> Result<()> {
+        let response = self.http.post(self.upload_url(name, "media"))
+            .bearer_auth(&self.access_token)
+            .header("content-type", "application/octet-stream")
+            .header("content-length", body.len())
+            .body(bandwidth::body(throttle, body))
+            .send()
+            .await?;
+        check(response).await?;
+        Ok(())
+    }
+
+    pub async fn get_object(&self, name: &str) -> Result<Vec<u8>> {
+        Ok(self.open_object(name).await?.bytes().await?.to_vec())
+    }
+
+    /// Start downloading an object; the body is streamed from the response
+    pub async fn open_object(&self, name: &str) -> Result<reqwest::Response> {
+        let response = self.http.get(format!("{}?alt=media", self.object_url(name)))
+            .bearer_auth(&self.access_token)
+            .send()
+            .await?;
+        check(response).await
+    }
+
+    /// Size of an object from its metadata
+    pub async fn object_size(&self, name: &str) -> Result<u64> {
+        let response = self.http.get(self.object_url(name))
+            .bearer_auth(&self.access_token)
+            .send()
+            .await?;
+        let object: ObjectResource = serde_json::from_str(&check(response).await?.text().await?)
+            .map_err(|e| anyhow!("Invalid GCS object metadata: {}", e))?;
+        object.size.parse().map_err(|_| anyhow!("GCS sent an invalid size for {}", name))
+    }
+
+    pub async fn delete_object(&self, name: &str) -> Result<()> {
+        let response = self.http.delete(self.object_url(name))
+            .bearer_auth(&self.access_token)
+            .send()
+            .await?;
+        check(response).await?;
+        Ok(())
+    }
+
+    /// Every object below the configured prefix, with paths relative to it
+    pub async fn list_objects(&self) -> Result<Vec<RemoteObject>> {
+        let prefix = self.object_name("");
+        let mut objects = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut url = format!("{}/storage/v1/b/{}/o?prefix={}", self.endpoint(), uri_encode(&self.target.bucket, true), uri_encode(&prefix, true));
+            if let Some(token) = &page_token {
+                url.push_str(&format!("&pageToken={}", uri_encode(token, true)));
+            }
+            let response = self.http.get(url).bearer_auth(&self.access_token).send().await?;
+            let list: ObjectList = serde_json::from_str(&check(response).await?.text().await?)
+                .map_err(|e| anyhow!("Invalid GCS object listing: {}", e))?;
+            objects.extend(list.items.into_iter().map(|item| RemoteObject {
+                path: item.name.strip_prefix(&prefix).unwrap_or(&item.name).to_string(),
+                size: item.size.parse().unwrap_or(0),
+                modified: item.updated,
+            }));
+            page_token = list.next_page_token;
+            if page_token.is_none() {
+                return Ok(objects);
+            }
+        }
+    }
+
+    fn endpoint(&self) -> &str {
+        self.target.endpoint.as_deref().unwrap_or(DEFAULT_ENDPOINT).trim_end_matches('/')
+    }
+
+    fn object_url(&self, name: &str) -> String {
+        format!("{}/storage/v1/b/{}/o/{}", self.endpoint(), uri_encode(&self.target.bucket, true), uri_encode(name, true))
+    }
+
+    fn upload_url(&self, name: &str, upload_type: &str) -> String {
+        format!(
+            "{}/upload/storage/v1/b/{}/o?uploadType={}&name={}",
+            self.endpoint(), uri_encode(&self.target.bucket, true), upload_type, uri_encode(name, true)
+        )
+    }
+}
+
+fn parse_credentials(credentials_json: &str) -> Result<(ServiceAccountKey, RsaKeyPair)> {
+    let key: ServiceAccountKey = serde_json::from_str(credentials_json)
+        .map_err(|e| anyhow!("Invalid service account JSON: {}", e))?;
+    if key.key_type != "service_account" {
+        return Err(anyhow!("Credentials must be a service account key, got type '{}'", key.key_type));
+    }
+
+    let base64: String = key.private_key.lines()
+        .filter(|line| !line.starts_with("-----"))
+        .map(str::trim)
+        .collect();
+    let der = STANDARD.decode(base64).map_err(|_| anyhow!("Service account private key is not valid PEM"))?;
+    // Key files from Google hold PKCS#8; PKCS#1 shows up when keys were converted by hand
+    let key_pair = if key.private_key.contains("BEGIN RSA PRIVATE KEY") {
+        RsaKeyPair::from_der(&der)
+    } else {
+        RsaKeyPair::from_pkcs8(&der)
+    }.map_err(|e| anyhow!("Service account private key is not a usable RSA key: {}", e))?;
+    Ok((key, key_pair))
+}
+
+/// Fail on non-success status codes, with the message from GCS's JSON error body
+async fn check(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let text = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<serde_json::Value>(&text).ok()
+        .and_then(|v| v["error"]["message"].as_str().map(str::to_string))
+        .unwrap_or(text);
+    Err(match status {
+        StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED => anyhow!("GCS denied access ({}): {}", status, message),
+        StatusCode::NOT_FOUND => anyhow!("GCS bucket or object not found: {}", message),
+        _ => anyhow!("GCS request failed ({}): {}", status, message),
+    })
+}
+
+/// Fill a buffer with up to one chunk, reading until the chunk is full or the file ends
+async fn read_chunk(file: &mut tokio::fs::File) -> Result<Vec<u8>> {
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    let mut filled = 0;
+    while filled < CHUNK_SIZE {
+        let read = file.read(&mut chunk[filled..]).await?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+    }
+    chunk.truncate(filled);
+    Ok(chunk)
+}
@@ -0,0 +1,181 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use tokio::sync::broadcast;
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn, error};
+use crate::models::{Backup, BackupLocation, CreateJobRequest, Job, JobType, VerificationStatus};
+use crate::services::FilesystemBackupService;
+use crate::services::events::{self, AppEvent};
+
+/// Pause between catalog sweeps when nothing is left to hash
+const IDLE_INTERVAL: Duration = Duration::from_secs(60);
+/// Short pause between two archives so hashing never saturates the disk
+const BACKOFF_INTERVAL: Duration = Duration::from_secs(2);
+const HASH_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Low-priority background worker that computes SHA-256 hashes of finished backup archives,
+/// each as a `checksum` job
+pub struct IntegrityWorker {
+    pool: SqlitePool,
+    backup_dir: String,
+}
+
+impl IntegrityWorker {
+    pub fn new(pool: SqlitePool, backup_dir: String) -> Self {
+        Self { pool, backup_dir }
+    }
+
+    /// Start the worker loop, hashing one archive at a time
+    pub async fn start(&self) {
+        info!("Starting integrity worker...");
+        let mut backup_events = events::subscribe();
+
+        loop {
+            match self.verify_next().await {
+                Ok(true) => {
+                    sleep(BACKOFF_INTERVAL).await;
+                    continue;
+                }
+                Ok(false) => {}
+                Err(e) => error!("Integrity worker error: {}", e),
+            }
+            // New backups are hashed as soon as they are complete rather than on the next sweep
+            tokio::select! {
+                _ = sleep(IDLE_INTERVAL) => {}
+                _ = next_backup_created(&mut backup_events) => {}
+            }
+        }
+    }
+
+    /// Hash the next pending backup; returns false when there was nothing to do
+    async fn verify_next(&self) -> Result<bool> {
+        let backup_service = FilesystemBackupService::new(self.backup_dir.clone());
+        let backups = backup_service.scan_backups().await?;
+
+        for backup in backups {
+            // Remote-only archives were hashed while uploading and can't be read here
+            if backup.verification_status != VerificationStatus::Pending || backup.location == BackupLocation::Remote {
+                continue;
+            }
+
+            let meta_path = Path::new(&backup.meta_path);
+            let mut metadata = match backup_service.load_backup_metadata(meta_path).await {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    warn!("Skipping hash for backup {}: {}", backup.id, e);
+                    continue;
+                }
+            };
+
+            // Archives still being written have no file path or ident yet
+            if !metadata.is_archive_complete() {
+                continue;
+            }
+
+            info!("Computing SHA-256 for backup {}", backup.id);
+            let job_id = self.start_checksum_job(&backup).await?;
+            let result = hash_file(PathBuf::from(&backup.file_path)).await;
+            match &result {
+                Ok(hash) => {
+                    metadata.sha256_hash = Some(hash.clone());
+                    metadata.verified_at = Some(Utc::now().to_rfc3339());
+                    metadata.verification_error = None;
+                }
+                Err(e) => {
+                    warn!("Failed to hash backup {}: {}", backup.id, e);
+                    metadata.verification_error = Some(e.to_string());
+                }
+            }
+
+            backup_service.save_backup_metadata(&metadata).await?;
+            self.finish_checksum_job(&job_id, result.err().map(|e| e.to_string())).await?;
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    /// Record the hashing of `backup` as a running job, so it shows up next to the backup's other jobs
+    async fn start_checksum_job(&self, backup: &Backup) -> Result<String> {
+        let mut job = Job::new(CreateJobRequest {
+            task_id: backup.task_id.clone(),
+            used_database: backup.used_database.clone(),
+            job_type: JobType::Checksum,
+            backup_path: Some(backup.file_path.clone()),
+        });
+        job.status = "running".to_string();
+        job.started_at = Some(Utc::now());
+
+        sqlx::query(
+            r#"
+            INSERT INTO jobs (id, task_id, used_database, job_type, status, progress, started_at, completed_at, error_message, log_output, backup_path, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        )
+        .bind(&job.id)
+        .bind(&job.task_id)
+        .bind(&job.used_database)
+        .bind(&job.job_type)
+        .bind(&job.status)
+        .bind(job.progress)
+        .bind(job.started_at)
+        .bind(job.completed_at)
+        .bind(&job.error_message)
+        .bind(&job.log_output)
+        .bind(&job.backup_path)
+        .bind(job.created_at)
+        .execute(&self.pool)
+        .await?;
+        events::publish(AppEvent::job_status(&job.id, "running"));
+        Ok(job.id)
+    }
+
+    async fn finish_checksum_job(&self, job_id: &str, error: Option<String>) -> Result<()> {
+        let status = if error.is_some() { "failed" } else { "completed" };
+        sqlx::query("UPDATE jobs SET status = ?, progress = ?, error_message = ?, completed_at = ? WHERE id = ?")
+            .bind(status)
+            .bind(if error.is_some() { 0 } else { 100 })
+            .bind(&error)
+            .bind(Utc::now())
+            .bind(job_id)
+            .execute(&self.pool)
+            .await?;
+        events::publish(AppEvent::job_status(job_id, status));
+        Ok(())
+    }
+}
+
+/// Wait for the next finished backup; never returns once the event bus is gone
+async fn next_backup_created(receiver: &mut broadcast::Receiver<AppEvent>) {
+    loop {
+        match receiver.recv().await {
+            Ok(AppEvent::BackupCreated { .. }) | Err(broadcast::error::RecvError::Lagged(_)) => return,
+            Ok(_) => {}
+            Err(broadcast::error::RecvError::Closed) => std::future::pending().await,
+        }
+    }
+}
+
+/// Compute the hex-encoded SHA-256 of a file on the blocking thread pool
+pub async fn hash_file(path: PathBuf) -> Result<String> {
+    tokio::task::spawn_blocking(move || {
+        let mut file = std::fs::File::open(&path)?;
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+
+        loop {
+            let read = file.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+
+        Ok(hex::encode(hasher.finalize()))
+    })
+    .await?
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use tracing::warn;
+
+/// Most distinct failure reasons reported per group
+const MAX_FAILURE_REASONS: usize = 5;
+
+/// Outcome and duration figures over a set of jobs
+#[derive(Debug, Default, Serialize)]
+pub struct JobStats {
+    pub total: i64,
+    pub completed: i64,
+    pub failed: i64,
+    pub cancelled: i64,
+    pub success_rate: Option<f64>, // Completed share of completed and failed jobs
+    pub avg_seconds: Option<f64>,
+    pub p50_seconds: Option<f64>,
+    pub p95_seconds: Option<f64>,
+    pub bytes_produced: i64,
+    pub failure_reasons: Vec<FailureReason>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FailureReason {
+    pub reason: Option<String>,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaskJobStats {
+    pub task_id: Option<String>,
+    pub task_name: Option<String>,
+    #[serde(flatten)]
+    pub stats: JobStats,
+}
+
+/// Delete finished jobs beyond the retention: those finished before `days` ago and those past the
+/// newest `max_jobs`, 0 disabling either. Failed jobs with a retry still due are kept.
+pub async fn enforce_retention(pool: &SqlitePool, log_dir: &str, days: u32, max_jobs: u32) -> Result<u64> {
+    let mut expired: Vec<String> = Vec::new();
+
+    if days > 0 {
+        let cutoff = Utc::now() - chrono::Duration::days(days as i64);
+        expired.extend(sqlx::query_scalar::<_, String>(
+            "SELECT id FROM jobs WHERE status IN ('completed', 'failed', 'cancelled') AND retry_at IS NULL
+             AND COALESCE(completed_at, created_at) < ?"
+        )
+        .bind(cutoff)
+        .fetch_all(pool)
+        .await?);
+    }
+
+    if max_jobs > 0 {
+        expired.extend(sqlx::query_scalar::<_, String>(
+            "SELECT id FROM jobs WHERE status IN ('completed', 'failed', 'cancelled') AND retry_at IS NULL
+             ORDER BY created_at DESC LIMIT -1 OFFSET ?"
+        )
+        .bind(max_jobs as i64)
+        .fetch_all(pool)
+        .await?);
+    }
+
+    expired.sort();
+    expired.dedup();
+    delete_jobs(pool, log_dir, &expired).await
+}
+
+/// Delete job rows together with their log folders, returning how many rows went
+pub async fn delete_jobs(pool: &SqlitePool, log_dir: &str, job_ids: &[String]) -> Result<u64> {
+    let mut deleted = 0;
+    for job_id in job_ids {
+        let log_output: Option<Option<String>> = sqlx::query_scalar("SELECT log_output FROM jobs WHERE id = ?")
+            .bind(job_id)
+            .fetch_optional(pool)
+            .await?;
+        let Some(log_output) = log_output else {
+            continue;
+        };
+        remove_logs(log_dir, job_id, log_output.as_deref()).await;
+
+        deleted += sqlx::query("DELETE FROM jobs WHERE id = ?")
+            .bind(job_id)
+            .execute(pool)
+            .await?
+            .rows_affected();
+        sqlx::query("DELETE FROM job_progress WHERE job_id = ?")
+            .bind(job_id)
+            .execute(pool)
+            .await?;
+    }
+    Ok(deleted)
+}
+
+/// The log folder of a job, the one its log file lives in or else the one named after it
+pub fn log_folder(log_dir: &str, job_id: &str, log_output: Option<&str>) -> PathBuf {
+    let log_root = Path::new(log_dir);
+    log_output
+        .and_then(|log_file| Path::new(log_file).parent())
+        // Never follow a log path out of the log directory
+        .filter(|folder| folder.starts_with(log_root) && *folder != log_root)
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| log_root.join(job_id))
+}
+
+/// Remove the log folder of a job
+async fn remove_logs(log_dir: &str, job_id: &str, log_output: Option<&str>) {
+    let folder = log_folder(log_dir, job_id, log_output);
+    if folder.exists() {
+        if let Err(e) = tokio::fs::remove_dir_all(&folder).await {
+            warn!("Failed to remove log folder {}: {}", folder.display(), e);
+        }
+    }
+}
+
+/// Statistics over the jobs of a type created since `since`, overall and per task
+pub async fn job_stats(pool: &SqlitePool, job_type: &str, since: DateTime<Utc>) -> Result<(JobStats, Vec<TaskJobStats>)> {
+    let overall = aggregate(pool, job_type, since, "NULL")
+        .await?
+        .into_values()
+        .next()
+        .unwrap_or_default();
+
+    let mut tasks = Vec::new();
+    for (task_id, stats) in aggregate(pool, job_type, since, "task_id").await? {
+        let task_name: Option<String> = match &task_id {
+            Some(task_id) => sqlx::query_scalar("SELECT name FROM tasks WHERE id = ?")
+                .bind(task_id)
+                .fetch_optional(pool)
+                .await?,
+            None => None,
+        };
+        tasks.push(TaskJobStats { task_id, task_name, stats });
+    }
+    tasks.sort_by_key(|task| std::cmp::Reverse(task.stats.total));
+
+    Ok((overall, tasks))
+}
+
+/// Run the aggregations grouped by the `group` column expression, NULL putting all jobs in one group
+async fn aggregate(
+    pool: &SqlitePool,
+    job_type: &str,
+    since: DateTime<Utc>,
+    group: &str,
+) -> Result<HashMap<Option<String>, JobStats>> {
+    const DURATION: &str = "(julianday(completed_at) - julianday(started_at)) * 86400";
+    const TIMED: &str = "status = 'completed' AND started_at IS NOT NULL AND completed_at IS NOT NULL";
+
+    let mut groups: HashMap<Option<String>, JobStats> = HashMap::new();
+
+    let rows = sqlx::query(&format!(
+        "SELECT {group} AS grp, COUNT(*) AS total,
+                SUM(status = 'completed') AS completed,
+                SUM(status = 'failed') AS failed,
+                SUM(status = 'cancelled') AS cancelled,
+                AVG(CASE WHEN {TIMED} THEN {DURATION} END) AS avg_seconds,
+                SUM(CASE WHEN status = 'completed' THEN backup_size END) AS bytes_produced
+         FROM jobs WHERE job_type = ? AND created_at >= ?
+         GROUP BY {group}"
+    ))
+    .bind(job_type)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+    for row in rows {
+        let completed: i64 = row.get("completed");
+        let failed: i64 = row.get("failed");
+        groups.insert(row.get("grp"), JobStats {
+            total: row.get("total"),
+            completed,
+            failed,
+            cancelled: row.get("cancelled"),
+            success_rate: (completed + failed > 0).then(|| completed as f64 / (completed + failed) as f64),
+            avg_seconds: row.get("avg_seconds"),
+            bytes_produced: row.get::<Option<i64>, _>("bytes_produced").unwrap_or(0),
+            ..Default::default()
+        });
+    }
+
+    // Nearest-rank percentiles: the first duration whose rank reaches the share of the group
+    let rows = sqlx::query(&format!(
+        "WITH ranked AS (
+             SELECT {group} AS grp, {DURATION} AS seconds,
+                    ROW_NUMBER() OVER (PARTITION BY {group} ORDER BY {DURATION}) AS position,
+                    COUNT(*) OVER (PARTITION BY {group}) AS size
+             FROM jobs WHERE job_type = ? AND created_at >= ? AND {TIMED}
+         )
+         SELECT grp,
+                MIN(CASE WHEN position >= 0.5 * size THEN seconds END) AS p50_seconds,
+                MIN(CASE WHEN position >= 0.95 * size THEN seconds END) AS p95_seconds
+         FROM ranked GROUP BY grp"
+    ))
+    .bind(job_type)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+    for row in rows {
+        if let Some(stats) = groups.get_mut(&row.get::<Option<String>, _>("grp")) {
+            stats.p50_seconds = row.get("p50_seconds");
+            stats.p95_seconds = row.get("p95_seconds");
+        }
+    }
+
+    let rows = sqlx::query(&format!(
+        "SELECT {group} AS grp, error_message, COUNT(*) AS count
+         FROM jobs WHERE job_type = ? AND created_at >= ? AND status = 'failed'
+         GROUP BY {group}, error_message
+         ORDER BY count DESC"
+    ))
+    .bind(job_type)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+    for row in rows {
+        if let Some(stats) = groups.get_mut(&row.get::<Option<String>, _>("grp")) {
+            if stats.failure_reasons.len() < MAX_FAILURE_REASONS {
+                stats.failure_reasons.push(FailureReason {
+                    reason: row.get("error_message"),
+                    count: row.get("count"),
+                });
+            }
+        }
+    }
+
+    Ok(groups)
+}
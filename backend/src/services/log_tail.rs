@@ -0,0 +1,58 @@
+use std::io::SeekFrom;
+use std::path::PathBuf;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Most bytes handed out by one read, so a large backlog arrives in several pieces
+const MAX_READ: u64 = 64 * 1024;
+
+/// Follows a growing log file from a byte offset, handing out whole lines only
+pub struct LogTail {
+    path: PathBuf,
+    offset: u64,
+}
+
+impl LogTail {
+    pub fn new(path: PathBuf, offset: u64) -> Self {
+        Self { path, offset }
+    }
+
+    /// Byte offset up to which the file has been handed out
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Lines written since the last read, None when there are none (yet).
+    ///
+    /// A trailing line without newline is held back until it is complete, unless `partial`
+    /// asks for everything, e.g. once the writer has finished.
+    pub async fn read(&mut self, partial: bool) -> std::io::Result<Option<String>> {
+        let mut file = match tokio::fs::File::open(&self.path).await {
+            Ok(file) => file,
+            // The job hasn't started writing its log yet
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        // A log rewritten from scratch is followed from its start again
+        if file.metadata().await?.len() < self.offset {
+            self.offset = 0;
+        }
+        file.seek(SeekFrom::Start(self.offset)).await?;
+
+        let mut buffer = Vec::new();
+        file.take(MAX_READ).read_to_end(&mut buffer).await?;
+        if !partial || buffer.len() as u64 == MAX_READ {
+            match buffer.iter().rposition(|b| *b == b'\n') {
+                Some(end) => buffer.truncate(end + 1),
+                // A single line longer than a read goes out in pieces
+                None if buffer.len() as u64 == MAX_READ => {}
+                None => buffer.clear(),
+            }
+        }
+        if buffer.is_empty() {
+            return Ok(None);
+        }
+
+        self.offset += buffer.len() as u64;
+        Ok(Some(String::from_utf8_lossy(&buffer).trim_end_matches('\n').to_string()))
+    }
+}
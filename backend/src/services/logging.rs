@@ -3,6 +3,7 @@ use std::sync::Arc;
 use tracing::{info, warn, error};
 use crate::models::{Log, LogType, LogLevel, CreateLogRequest};
 
+#[derive(Clone)]
 pub struct LoggingService {
     db_pool: Arc<SqlitePool>,
 }
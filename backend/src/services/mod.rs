@@ -5,10 +5,56 @@ pub mod progress_tracker;
 pub mod backup_process;
 pub mod task_worker;
 pub mod logging;
+pub mod integrity;
+pub mod mysql_pool;
+pub mod events;
+pub mod upload_sessions;
+pub mod url_import;
+pub mod archive_format;
+pub mod transfers;
+pub mod calendar;
+pub mod sla;
+pub mod anomaly;
+pub mod overdue;
+pub mod verification;
+pub mod sandbox;
+pub mod recompress;
+pub mod backup_copy;
+pub mod backup_register;
+pub mod catalog;
+pub mod agents;
+pub mod agent_client;
+pub mod demo;
+pub mod s3_storage;
+pub mod sftp_storage;
+pub mod gcs_storage;
+pub mod webdav_storage;
+pub mod remote_storage;
+pub mod remote_verification;
+pub mod bandwidth;
+pub mod blackout;
+pub mod schema_remap;
+pub mod table_filter;
+pub mod binlog;
+pub mod encryption;
+pub mod restore_target;
+pub mod cancellation;
+pub mod log_tail;
+pub mod job_history;
+pub mod resource_usage;
+pub mod api_keys;
+pub mod auth;
+pub mod audit;
+pub mod webhooks;
+pub mod notifications;
 
 pub use mydumper::MydumperService;
 pub use filesystem_backup::FilesystemBackupService;
 pub use backup_process::BackupProcess;
 pub use task_worker::{TaskWorker, WorkerStatus};
 pub use logging::LoggingService;
+pub use integrity::IntegrityWorker;
+pub use webhooks::WebhookDispatcher;
+pub use notifications::Notifier;
+pub use upload_sessions::UploadSessionStore;
 // pub use scheduler::TaskScheduler; // Currently unused
\ No newline at end of file
@@ -1,14 +1,81 @@
 use anyhow::{anyhow, Result};
-use std::path::Path;
+use serde::Serialize;
+use utoipa::ToSchema;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use tokio::io::AsyncWriteExt;
 use tokio::process::Command as TokioCommand;
 use tokio::fs::File;
 use tracing::{error, info, warn};
-use sqlx::{SqlitePool, MySqlPool, Row};
+use sqlx::{SqlitePool, Row};
+
+use crate::models::{DatabaseConfig, Task, CompressionType, RestoreRequest};
+use crate::services::{archive_format, bandwidth, demo, events::{self, AppEvent}, mysql_pool, remote_storage, restore_target, schema_remap, table_filter::TableFilter};
+use crate::services::progress_tracker::{self, ProgressSnapshots};
+use crate::services::resource_usage::{self, ResourceUsage};
+use crate::models::progress::TableStatus;
+
+/// mydumper threads for tasks that don't set their own
+const DEFAULT_THREADS: i32 = 4;
+
+/// Task database name that backs up every database on the server
+pub const ALL_DATABASES: &str = "*";
+
+/// Schemas of the server itself, left out of all-database backups
+const SYSTEM_DATABASES: [&str; 4] = ["information_schema", "mysql", "performance_schema", "sys"];
+
+/// Share of the raw table data a compressed dump or archive typically takes up
+const COMPRESSED_RATIO: f64 = 0.35;
+
+/// Engines mydumper skips unless a task backs up non-transactional tables
+const NON_TRANSACTIONAL_ENGINES: &str = "MyISAM,MEMORY,CSV,ARCHIVE,FEDERATED,MERGE,BLACKHOLE";
+
+/// Databases and tables a backup run covers, after the engine analysis and the task's table filter
+struct DumpPlan {
+    /// The task's database, or `ALL_DATABASES`
+    database_name: String,
+    table_filter: Option<TableFilter>,
+    databases: Vec<String>,
+    per_database: Vec<PlannedDatabase>,
+    /// InnoDB tables of all databases
+    innodb_tables: Vec<String>,
+    /// Non-InnoDB tables of all databases as "name (ENGINE)"
+    excluded_tables: Vec<String>,
+}
+
+struct PlannedDatabase {
+    name: String,
+    /// InnoDB tables that are dumped
+    tables: Vec<String>,
+    excluded_tables: Vec<String>,
+}
 
-use crate::models::{DatabaseConfig, Task, CompressionType};
+/// What a backup run of a task would do, reported without dumping anything
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DryRunReport {
+    pub database_name: String,
+    pub databases: Vec<DryRunDatabase>,
+    pub table_count: usize,
+    pub excluded_table_count: usize,
+    /// Data and index size of the dumped tables in bytes, before compression
+    pub estimated_size: i64,
+    /// Space the dump and its archive need on the backup volume
+    pub required_space: u64,
+    /// Free space on the backup volume, if `df` could tell
+    pub available_space: Option<u64>,
+    /// mydumper invocation with the password masked
+    pub command: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DryRunDatabase {
+    pub name: String,
+    pub tables: Vec<String>,
+    pub excluded_tables: Vec<String>,
+    pub estimated_size: i64,
+}
 
+#[derive(Clone)]
 pub struct MydumperService {
     backup_base_dir: String,
     log_base_dir: String,
@@ -21,9 +88,12 @@ impl MydumperService {
 
     /// Analyze table engines and return InnoDB tables, excluding MyISAM and other non-transactional engines
     async fn analyze_table_engines(&self, database_config: &DatabaseConfig, database_name: &str) -> Result<(Vec<String>, Vec<String>)> {
+        if demo::is_enabled() {
+            return Ok(demo::tables(database_name));
+        }
         let connection_string = database_config.connection_string_with_db(database_name);
 
-        let pool = MySqlPool::connect(&connection_string).await?;
+        let pool = mysql_pool::shared_pool(&connection_string).await?;
         
         // Query to get table names and their engines
         let query = "SELECT TABLE_NAME, ENGINE FROM information_schema.TABLES WHERE TABLE_SCHEMA = ?";
@@ -63,11 +133,225 @@ impl MydumperService {
             }
         }
 
-        pool.close().await;
-
         Ok((innodb_tables, excluded_tables))
     }
 
+    /// Databases an all-database backup covers: every schema except the system ones
+    async fn list_databases(&self, database_config: &DatabaseConfig) -> Result<Vec<String>> {
+        let databases = if demo::is_enabled() {
+            demo::databases()
+        } else {
+            let pool = mysql_pool::shared_pool(&database_config.connection_string()).await?;
+            sqlx::query_scalar("SELECT SCHEMA_NAME FROM information_schema.SCHEMATA ORDER BY SCHEMA_NAME")
+                .fetch_all(&pool)
+                .await?
+        };
+        Ok(databases.into_iter()
+            .filter(|db| !SYSTEM_DATABASES.contains(&db.to_lowercase().as_str()))
+            .collect())
+    }
+
+    /// Data plus index size of each table in `database_name`, as information_schema reports it
+    async fn table_sizes(&self, database_config: &DatabaseConfig, database_name: &str) -> Result<Vec<(String, i64)>> {
+        if demo::is_enabled() {
+            return Ok(demo::table_sizes(database_name));
+        }
+        let pool = mysql_pool::shared_pool(&database_config.connection_string_with_db(database_name)).await?;
+        let sizes = sqlx::query_as(
+            "SELECT TABLE_NAME, CAST(COALESCE(DATA_LENGTH, 0) + COALESCE(INDEX_LENGTH, 0) AS SIGNED) FROM information_schema.TABLES WHERE TABLE_SCHEMA = ?"
+        )
+        .bind(database_name)
+        .fetch_all(&pool)
+        .await?;
+        Ok(sizes)
+    }
+
+    /// Data plus index size of the dumped tables, per database of the plan
+    async fn planned_sizes(&self, database_config: &DatabaseConfig, plan: &DumpPlan) -> Result<Vec<i64>> {
+        let mut sizes = Vec::new();
+        for planned in &plan.per_database {
+            let size = self.table_sizes(database_config, &planned.name).await?
+                .into_iter()
+                .filter(|(table, _)| planned.tables.contains(table))
+                .map(|(_, size)| size)
+                .sum();
+            sizes.push(size);
+        }
+        Ok(sizes)
+    }
+
+    /// Resolve the task's databases and the tables dumped from each
+    async fn plan_dump(&self, database_config: &DatabaseConfig, database_name: &str, table_filter: Option<TableFilter>) -> Result<DumpPlan> {
+        let databases = if database_name == ALL_DATABASES {
+            let databases = self.list_databases(database_config).await?;
+            if databases.is_empty() {
+                return Err(anyhow!("Server has no databases besides the system ones"));
+            }
+            databases
+        } else {
+            vec![database_name.to_string()]
+        };
+
+        let mut plan = DumpPlan {
+            database_name: database_name.to_string(),
+            table_filter,
+            databases: databases.clone(),
+            per_database: Vec::new(),
+            innodb_tables: Vec::new(),
+            excluded_tables: Vec::new(),
+        };
+        for database in databases {
+            let (mut innodb, mut excluded) = self.analyze_table_engines(database_config, &database).await?;
+
+            // Tables outside the task's filter aren't part of the backup at all
+            if let Some(filter) = &plan.table_filter {
+                innodb.retain(|table| filter.matches(table));
+                excluded.retain(|table| filter.matches(table_name(table)));
+            }
+            plan.innodb_tables.extend(innodb.iter().cloned());
+            plan.excluded_tables.extend(excluded.iter().cloned());
+            plan.per_database.push(PlannedDatabase { name: database, tables: innodb, excluded_tables: excluded });
+        }
+        Ok(plan)
+    }
+
+    /// Compare the space a run of the plan needs with what is free on the backup volume.
+    ///
+    /// Only a volume known to be too small fails the check; when the size or free space
+    /// can't be determined the backup goes ahead.
+    async fn check_disk_space(&self, database_config: &DatabaseConfig, task: &Task, plan: &DumpPlan) -> Result<()> {
+        let estimated_size = match self.planned_sizes(database_config, plan).await {
+            Ok(sizes) => sizes.iter().sum(),
+            Err(e) => {
+                warn!("Skipping disk space check, failed to estimate the backup size: {}", e);
+                return Ok(());
+            }
+        };
+        let Some(available) = available_space(&self.backup_base_dir).await else {
+            warn!("Skipping disk space check, free space of {} is unknown", self.backup_base_dir);
+            return Ok(());
+        };
+
+        let required = required_space(estimated_size, &task.compression_type().unwrap_or(CompressionType::Gzip));
+        if required > available {
+            return Err(anyhow!(
+                "Not enough disk space for the backup: about {} needed, {} free in {}",
+                remote_storage::format_size(required), remote_storage::format_size(available), self.backup_base_dir
+            ));
+        }
+        info!("Disk space check passed: about {} needed, {} free", remote_storage::format_size(required), remote_storage::format_size(available));
+        Ok(())
+    }
+
+    /// The folder of an interrupted backup job if its dump can be resumed, i.e. has complete tables
+    pub async fn find_resumable_dump(&self, pool: &SqlitePool, job_id: &str) -> Result<Option<PathBuf>> {
+        let backup_service = crate::services::FilesystemBackupService::new(self.backup_base_dir.clone());
+        let Some(root_dir) = backup_service.find_unfinished_process(job_id).await? else {
+            return Ok(None);
+        };
+        let tables = resumable_tables(pool, job_id, &root_dir.join("tmp")).await?;
+        Ok((!tables.is_empty()).then_some(root_dir))
+    }
+
+    /// mydumper arguments for a run of `task`, dumping into `output_dir`
+    fn mydumper_args(&self, database_config: &DatabaseConfig, task: &Task, plan: &DumpPlan, output_dir: &Path, log_file_path: &str) -> Vec<String> {
+        let mut args: Vec<String> = vec![
+            "--host".into(), database_config.host.clone(),
+            "--port".into(), database_config.port.to_string(),
+            "--user".into(), database_config.username.clone(),
+            "--password".into(), database_config.password.clone(),
+            "--outputdir".into(), output_dir.to_string_lossy().to_string(),
+            "--verbose".into(), "3".into(),
+            "--threads".into(), task.threads.unwrap_or(DEFAULT_THREADS).to_string(),
+            "--logfile".into(), log_file_path.to_string(),
+            "--triggers".into(),
+            "--events".into(),
+            "--routines".into(),
+        ];
+
+        // Add non-transactional tables option if enabled
+        if task.use_non_transactional {
+            args.extend(["--trx-tables".into(), "0".into(), "--no-backup-locks".into()]);
+        } else {
+            // For safe InnoDB-only backup, ignore non-InnoDB engines
+            args.extend(["--ignore-engines".into(), NON_TRANSACTIONAL_ENGINES.into()]);
+        }
+
+        if plan.database_name == ALL_DATABASES {
+            // One dump of the listed databases, so system schemas stay out and the filter still applies
+            args.extend(plan.table_filter.clone().unwrap_or_default().mydumper_args(&plan.databases));
+        } else {
+            args.extend(["--database".into(), plan.database_name.clone()]);
+            if let Some(filter) = &plan.table_filter {
+                args.extend(filter.mydumper_args(&plan.databases));
+            }
+        }
+        if let Some(rows) = task.rows_per_chunk {
+            args.extend(["--rows".into(), rows.to_string()]);
+        }
+        if let Some(chunk_filesize_mb) = task.chunk_filesize_mb {
+            args.extend(["--chunk-filesize".into(), chunk_filesize_mb.to_string()]);
+        }
+        if let Some(long_query_guard_seconds) = task.long_query_guard_seconds {
+            args.extend(["--long-query-guard".into(), long_query_guard_seconds.to_string()]);
+        }
+        if let Some(where_clause) = &task.where_clause {
+            args.extend(["--where".into(), where_clause.clone()]);
+        }
+
+        // Add compression if specified
+        match task.compression_type().unwrap_or(CompressionType::Gzip) {
+            CompressionType::Gzip => args.push("--compress".into()),
+            CompressionType::Zstd => args.push("--compress-protocol".into()),
+            CompressionType::None => {}
+        }
+        args
+    }
+
+    /// Connect and analyze the task's databases like a backup run would, without writing anything
+    pub async fn dry_run(&self, database_config: &DatabaseConfig, database_name: &str, task: &Task) -> Result<DryRunReport> {
+        let table_filter = TableFilter::for_task(task).map_err(|e| anyhow!(e))?;
+        let plan = self.plan_dump(database_config, database_name, table_filter).await?;
+
+        let sizes = self.planned_sizes(database_config, &plan).await?;
+        let databases: Vec<DryRunDatabase> = plan.per_database.iter()
+            .zip(&sizes)
+            .map(|(planned, size)| DryRunDatabase {
+                name: planned.name.clone(),
+                tables: planned.tables.clone(),
+                excluded_tables: planned.excluded_tables.clone(),
+                estimated_size: *size,
+            })
+            .collect();
+        let estimated_size = sizes.iter().sum();
+
+        // Paths a run would use; the job and backup folder don't exist until it starts
+        let backup_service = crate::services::FilesystemBackupService::new(self.backup_base_dir.clone());
+        let output_dir = Path::new(&self.backup_base_dir)
+            .join(backup_service.generate_backup_directory_name(database_config, Some(task)))
+            .join("tmp");
+        let log_file_path = format!("{}/<job-id>/mydumper.log", self.log_base_dir);
+        let mut args = self.mydumper_args(database_config, task, &plan, &output_dir, &log_file_path);
+        if let Some(index) = args.iter().position(|arg| arg == "--password") {
+            args[index + 1] = "********".to_string();
+        }
+        let command = std::iter::once("mydumper".to_string())
+            .chain(args.iter().map(|arg| shell_quote(arg)))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Ok(DryRunReport {
+            database_name: database_name.to_string(),
+            table_count: plan.innodb_tables.len(),
+            excluded_table_count: plan.excluded_tables.len(),
+            estimated_size,
+            required_space: required_space(estimated_size, &task.compression_type().unwrap_or(CompressionType::Gzip)),
+            available_space: available_space(&self.backup_base_dir).await,
+            databases,
+            command,
+        })
+    }
+
     pub async fn create_backup_with_progress(
         &self,
         database_config: &DatabaseConfig,
@@ -83,15 +367,29 @@ impl MydumperService {
             error!("Failed to update job status to running: {}", e);
         }
 
-        // Analyze table engines for logging purposes
-        let (innodb_tables, excluded_tables) = match self.analyze_table_engines(database_config, database_name).await {
-            Ok(result) => result,
+        let table_filter = match TableFilter::for_task(task) {
+            Ok(filter) => filter,
             Err(e) => {
-                error!("Failed to analyze table engines for job {}: {}", job_id, e);
+                let _ = self.update_job_status(pool, &job_id, "failed", Some(&e), None).await;
+                return Err(anyhow!(e));
+            }
+        };
+
+        let plan = match self.plan_dump(database_config, database_name, table_filter).await {
+            Ok(plan) => plan,
+            Err(e) => {
+                error!("Failed to analyze databases for job {}: {}", job_id, e);
                 let _ = self.update_job_status(pool, &job_id, "failed", Some(&e.to_string()), None).await;
                 return Err(e);
             }
         };
+        let DumpPlan { databases, innodb_tables, excluded_tables, table_filter, .. } = &plan;
+        if database_name == ALL_DATABASES {
+            info!("Backing up all {} databases: {}", databases.len(), databases.join(", "));
+        }
+        if let Some(filter) = &table_filter {
+            info!("Table filter for {}: include [{}], exclude [{}]", database_name, filter.include.join(", "), filter.exclude.join(", "));
+        }
         
         // Log table analysis results
         info!("Database {} analysis: {} InnoDB tables, {} non-InnoDB tables will be ignored", 
@@ -102,9 +400,54 @@ impl MydumperService {
             warn!("MyDumper will ignore these tables using --ignore-engines parameter");
         }
 
-        // Create backup process using new system
+        // Fail now rather than when the volume fills up halfway through the dump
+        if let Err(e) = self.check_disk_space(database_config, task, &plan).await {
+            error!("Backup for job {} aborted: {}", job_id, e);
+            let _ = self.update_job_status(pool, &job_id, "failed", Some(&e.to_string()), None).await;
+            return Err(e);
+        }
+
+        // Create backup process using new system, or pick up the one a restart interrupted
         let backup_service = crate::services::FilesystemBackupService::new(self.backup_base_dir.clone());
-        let mut backup_process = backup_service.create_backup_process(&job_id, database_config, Some(task)).await?;
+        let resume_dir: Option<String> = sqlx::query_scalar("SELECT resume_dir FROM jobs WHERE id = ?")
+            .bind(&job_id)
+            .fetch_one(pool)
+            .await?;
+        let (mut backup_process, resumed_tables) = match resume_dir.filter(|dir| Path::new(dir).join("tmp").is_dir()) {
+            Some(root_dir) => {
+                let backup_process = backup_service.resume_backup_process(PathBuf::from(root_dir), &job_id, database_config, Some(task));
+                let resumed_tables: Vec<String> = resumable_tables(pool, &job_id, backup_process.tmp_dir()).await?
+                    .into_iter()
+                    .filter(|table| innodb_tables.contains(table))
+                    .collect();
+                discard_partial_tables(backup_process.tmp_dir(), database_name, &resumed_tables).await?;
+                (backup_process, resumed_tables)
+            }
+            None => (backup_service.create_backup_process(&job_id, database_config, Some(task)).await?, Vec::new()),
+        };
+
+        // A resumed run dumps only the tables missing from the interrupted one, next to its files
+        let remaining_tables: Vec<String> = innodb_tables.iter()
+            .filter(|table| !resumed_tables.contains(table))
+            .cloned()
+            .collect();
+        let resume_plan = (!resumed_tables.is_empty()).then(|| DumpPlan {
+            database_name: plan.database_name.clone(),
+            table_filter: Some(TableFilter { include: remaining_tables.clone(), exclude: Vec::new() }),
+            databases: databases.clone(),
+            per_database: vec![PlannedDatabase { name: database_name.to_string(), tables: remaining_tables.clone(), excluded_tables: Vec::new() }],
+            innodb_tables: remaining_tables.clone(),
+            excluded_tables: Vec::new(),
+        });
+        let dump_plan = resume_plan.as_ref().unwrap_or(&plan);
+        let output_dir = match resume_plan {
+            Some(_) => backup_process.tmp_dir().join("resume"),
+            None => backup_process.tmp_dir().to_path_buf(),
+        };
+        match restore_target::server_version(database_config).await {
+            Ok(version) => backup_process.record_server_version(&version).await?,
+            Err(e) => warn!("Failed to read the server version for job {}: {}", job_id, e),
+        }
 
         // Create log directory for mydumper logs
         let log_dir = format!("{}/{}", self.log_base_dir, job_id);
@@ -116,9 +459,17 @@ impl MydumperService {
         
         let rdumper_meta = serde_json::json!({
             "count": table_count,
-            "tables": innodb_tables.iter().map(|t| t.clone()).collect::<Vec<String>>(),
+            "tables": remaining_tables,
             "excluded_tables": excluded_tables.iter().map(|t| t.clone()).collect::<Vec<String>>(),
+            "resumed_tables": resumed_tables,
             "database_name": database_name,
+            "databases": databases,
+            "table_filter": table_filter.as_ref().map(|filter| serde_json::json!({
+                "include": filter.include,
+                "exclude": filter.exclude,
+                "mydumper_args": filter.mydumper_args(databases),
+            })),
+            "where_clause": task.where_clause,
             "started_at": chrono::Utc::now().to_rfc3339()
         });
         
@@ -127,8 +478,11 @@ impl MydumperService {
         info!("Database {} has {} total tables ({} InnoDB will be backed up)", 
               database_name, table_count, innodb_tables.len());
 
-        // Create log file
+        // Create log file, keeping the one of an interrupted run
         let log_file_path = format!("{}/mydumper.log", log_dir);
+        if !resumed_tables.is_empty() && Path::new(&log_file_path).exists() {
+            tokio::fs::rename(&log_file_path, format!("{}/mydumper.interrupted.log", log_dir)).await?;
+        }
         let mut log_file = File::create(&log_file_path).await?;
 
         // Update job status to running
@@ -139,79 +493,143 @@ impl MydumperService {
             chrono::Utc::now().format("%Y-%m-%d %H:%M:%S"), 
             database_name);
         log_file.write_all(start_log.as_bytes()).await?;
+        if !resumed_tables.is_empty() {
+            let resume_log = format!("[{}] INFO: Resuming interrupted backup, {} of {} tables already dumped\n",
+                chrono::Utc::now().format("%Y-%m-%d %H:%M:%S"),
+                resumed_tables.len(),
+                innodb_tables.len());
+            log_file.write_all(resume_log.as_bytes()).await?;
+            info!("Job {}: resuming backup with {} remaining tables", job_id, remaining_tables.len());
+        }
         log_file.flush().await?;
 
-        // Build mydumper command
+        // Build mydumper command; killed when the task's timeout drops the wait for it
         let mut cmd = TokioCommand::new("mydumper");
-        cmd.arg("--host").arg(&database_config.host)
-            .arg("--port").arg(database_config.port.to_string())
-            .arg("--user").arg(&database_config.username)
-            .arg("--password").arg(&database_config.password)
-            .arg("--database").arg(database_name)
-            .arg("--outputdir").arg(backup_process.tmp_dir())
-            .arg("--verbose").arg("3")
-            .arg("--threads").arg("4")
-            .arg("--logfile").arg(&log_file_path)
-            .arg("--triggers")
-            .arg("--events")
-            .arg("--routines");
-
-        // Add non-transactional tables option if enabled
-        if task.use_non_transactional {
-            cmd.arg("--trx-tables").arg("0");
-            cmd.arg("--no-backup-locks");
-        } else {
-            // For safe InnoDB-only backup, ignore non-InnoDB engines
-            cmd.arg("--ignore-engines").arg("MyISAM,MEMORY,CSV,ARCHIVE,FEDERATED,MERGE,BLACKHOLE");
-            info!("Ignoring non-InnoDB engines: MyISAM,MEMORY,CSV,ARCHIVE,FEDERATED,MERGE,BLACKHOLE");
+        cmd.kill_on_drop(true).args(self.mydumper_args(database_config, task, dump_plan, &output_dir, &log_file_path));
+        if !task.use_non_transactional {
+            info!("Ignoring non-InnoDB engines: {}", NON_TRANSACTIONAL_ENGINES);
         }
-
-        // Add compression if specified
-        let compression = task.compression_type().unwrap_or(CompressionType::Gzip);
-        match compression {
-            CompressionType::Gzip => {
-                cmd.arg("--compress");
-            }
-            CompressionType::Zstd => {
-                cmd.arg("--compress-protocol");
-            }
-            CompressionType::None => {
-                // No compression flags
-            }
+        if let Some(where_clause) = &task.where_clause {
+            info!("Backing up only rows matching: {}", where_clause);
         }
 
         info!("Executing mydumper command for database: {}", database_name);
 
-        // Execute mydumper command and wait for completion
-        let status = cmd.status().await?;
+        // Keep the table-level progress in the database, it outlives the log directory
+        let snapshots = ProgressSnapshots::start(&log_dir, pool, &job_id);
+
+        // Execute mydumper command and wait for completion, sampling what it consumes
+        let mut usage = None;
+        let dump = async {
+            if remaining_tables.is_empty() {
+                // The interrupted run got every table, only the archive is missing
+                Ok::<_, anyhow::Error>((true, Some(0)))
+            } else if demo::is_enabled() {
+                for planned in &dump_plan.per_database {
+                    demo::simulate_dump(&output_dir, &mut log_file, &planned.name, &planned.tables).await?;
+                }
+                Ok((true, Some(0)))
+            } else {
+                let mut child = cmd.spawn()?;
+                let status = resource_usage::wait_measured(&mut child, &mut usage).await?;
+                Ok((status.success(), status.code()))
+            }
+        };
+        let (success, exit_code) = match task.timeout_minutes {
+            Some(minutes) => match tokio::time::timeout(std::time::Duration::from_secs(minutes as u64 * 60), dump).await {
+                Ok(result) => result?,
+                Err(_) => {
+                    let error_msg = format!("mydumper timed out after {} minutes", minutes);
+                    error!("Job {}: {}", job_id, error_msg);
+                    drop(log_file);
+                    append_log(&log_file_path, &format!("ERROR: {}, process killed", error_msg)).await;
+                    snapshots.finish().await;
+                    record_usage(pool, &job_id, usage.as_ref()).await;
+                    if let Err(e) = backup_process.cleanup_tmp().await {
+                        warn!("Failed to remove tmp directory of job {}: {}", job_id, e);
+                    }
+                    self.update_job_status(pool, &job_id, "failed", Some(&error_msg), Some(&log_file_path)).await?;
+                    return Err(anyhow!(error_msg));
+                }
+            },
+            None => dump.await?,
+        };
+        snapshots.finish().await;
+        record_usage(pool, &job_id, usage.as_ref()).await;
 
         let completion_log = format!("[{}] mydumper process completed with status: {:?}\n", 
             chrono::Utc::now().format("%Y-%m-%d %H:%M:%S"), 
-            status.code());
+            exit_code);
         log_file.write_all(completion_log.as_bytes()).await?;
         log_file.flush().await?;
+        log_file.sync_all().await?;
+        drop(log_file); // Close our handle before the archive step reads the backup
 
-        if !status.success() {
-            error!("mydumper failed with exit code: {:?}", status.code());
-            let error_msg = format!("mydumper failed with exit code: {:?}", status.code());
+        if !success {
+            error!("mydumper failed with exit code: {:?}", exit_code);
+            let error_msg = format!("mydumper failed with exit code: {:?}", exit_code);
             self.update_job_status(pool, &job_id, "failed", Some(&error_msg), Some(&log_file_path)).await?;
             return Err(anyhow!("mydumper failed: {}", error_msg));
         }
 
         info!("MyDumper completed successfully for database: {}", database_name);
 
+        if output_dir != backup_process.tmp_dir() {
+            merge_dump_files(&output_dir, backup_process.tmp_dir()).await?;
+        }
+
         // Update job status to compressing before creating archive
         self.update_job_status(pool, &job_id, "compressing", None, Some(&log_file_path)).await?;
 
-        // Complete the backup process (creates archive, calculates hash, updates metadata, cleans up tmp)
-        let backup_file_path = backup_process.complete().await?;
+        // Mirror compression progress into the job while the archive is written
+        let (progress_tx, progress_task) = mirror_progress(pool, &job_id, "compression_progress");
+
+        // Complete the backup process (creates archive, updates metadata, cleans up tmp)
+        let backup_file_path = backup_process.complete_with_progress(Some(progress_tx)).await?;
+        let _ = progress_task.await;
+
+        // Push the archive off-host when the task has a storage destination
+        if let Some(destination) = remote_storage::task_destination(pool, task).await? {
+            let target = destination.target.0.clone();
+            self.update_job_status(pool, &job_id, "uploading", None, Some(&log_file_path)).await?;
+            append_log(&log_file_path, &format!("INFO: Uploading archive to {}", target)).await;
+
+            let archive_size = tokio::fs::metadata(&backup_file_path).await?.len();
+            let uploaded = async {
+                remote_storage::ensure_quota(&destination, task, archive_size).await?;
+                let (progress_tx, progress_task) = mirror_progress(pool, &job_id, "upload_progress");
+                let uploaded = remote_storage::push_backup(
+                    &target,
+                    task.keep_local,
+                    &backup_service,
+                    &backup_process.meta_file,
+                    Some(progress_tx),
+                    bandwidth::with_task_limit(task.bandwidth_limit_kib),
+                ).await;
+                let _ = progress_task.await;
+                uploaded
+            }.await;
+
+            match uploaded {
+                Ok(location) => append_log(&log_file_path, &format!("INFO: Uploaded archive to {}", location)).await,
+                Err(e) => {
+                    // The local archive stays in place, so only the off-host copy is missing
+                    let error_msg = format!("Upload to {} failed: {}", target, e);
+                    error!("{}", error_msg);
+                    append_log(&log_file_path, &format!("ERROR: {}", error_msg)).await;
+                    self.update_job_status(pool, &job_id, "failed", Some(&error_msg), Some(&log_file_path)).await?;
+                    self.update_job_backup_path(pool, &job_id, &backup_file_path).await?;
+                    return Err(anyhow!(error_msg));
+                }
+            }
+        }
+
+        // Record the archive first, so whoever reacts to the completion sees its size
+        self.update_job_backup_path(pool, &job_id, &backup_file_path).await?;
 
         // Update job to completed
         self.update_job_status(pool, &job_id, "completed", None, Some(&log_file_path)).await?;
 
-        // Update job with backup file path
-        self.update_job_backup_path(pool, &job_id, &backup_file_path).await?;
-
         Ok(backup_file_path)
     }
 
@@ -284,13 +702,16 @@ impl MydumperService {
         db_query = db_query.bind(job_id);
         
         db_query.execute(pool).await?;
+        events::publish(AppEvent::job_status(job_id, status));
         Ok(())
     }
 
 
     async fn update_job_backup_path(&self, pool: &SqlitePool, job_id: &str, backup_path: &str) -> Result<()> {
-        sqlx::query("UPDATE jobs SET backup_path = ? WHERE id = ?")
+        let backup_size = tokio::fs::metadata(backup_path).await.ok().map(|metadata| metadata.len() as i64);
+        sqlx::query("UPDATE jobs SET backup_path = ?, backup_size = ? WHERE id = ?")
             .bind(backup_path)
+            .bind(backup_size)
             .bind(job_id)
             .execute(pool)
             .await?;
@@ -299,6 +720,11 @@ impl MydumperService {
 
     // Method to parse logs and calculate real-time progress
 
+    /// mydumper log file of a backup job, written while the job runs
+    pub fn job_log_path(&self, job_id: &str) -> PathBuf {
+        Path::new(&self.log_base_dir).join(job_id).join("mydumper.log")
+    }
+
     // Method to read logs from file
     pub async fn read_job_logs(&self, job_id: &str, pool: &SqlitePool) -> Result<String> {
         // Get job from database to find log path or backup path
@@ -338,46 +764,112 @@ impl MydumperService {
         Ok("No logs available for this job".to_string())
     }
 
-    pub async fn restore_backup(
-        &self,
-        database_config: &DatabaseConfig,
-        backup_path: &str,
-        new_database_name: Option<&str>,
-        overwrite_existing: bool,
-    ) -> Result<()> {
+    /// Load a backup into `database_config`'s server as `request` describes
+    pub async fn restore_backup(&self, database_config: &DatabaseConfig, backup_path: &str, request: &RestoreRequest) -> Result<()> {
         info!("Starting restore from backup: {}", backup_path);
 
         let backup_path = Path::new(backup_path);
+        // A remapped dump is rewritten in a directory of its own, removed once it is loaded
+        let remap_dir = (!request.remap.is_empty())
+            .then(|| backup_path.with_extension(format!("remap-{}", uuid::Uuid::new_v4().simple())));
+        
+        let extract_dir = remap_dir.clone().unwrap_or_else(|| backup_path.with_extension(""));
+        // Also removes the extraction when a cancelled restore is dropped midway
+        let scratch = ScratchDir((backup_path.is_file() && (remap_dir.is_some() || !extract_dir.exists())).then(|| extract_dir.clone()));
         
         // Extract archive if it's compressed
         let source_dir = if backup_path.is_file() {
-            self.extract_compressed_archive(backup_path).await?
-        } else {
+            self.extract_compressed_archive(backup_path, &extract_dir).await?
+        } else if remap_dir.is_none() {
             backup_path.to_string_lossy().to_string()
+        } else {
+            return Err(anyhow!("Schema remapping rewrites the dump and needs an archived backup"));
+        };
+
+        let result = self.load_dump(database_config, &source_dir, request).await;
+        if remap_dir.is_none() {
+            scratch.keep();
+        }
+        result
+    }
+
+    /// Load an extracted dump with myloader, or the mysql client for plain SQL dumps
+    async fn load_dump(&self, database_config: &DatabaseConfig, source_dir: &str, request: &RestoreRequest) -> Result<()> {
+        let new_database_name = request.new_database_name.as_deref();
+        let overwrite_existing = request.overwrite_existing;
+        let tuning = &request.myloader;
+
+        // Rename databases and tables in the extracted files, and the tables asked for with them
+        let tables: Vec<String> = if request.remap.is_empty() {
+            request.tables.clone()
+        } else {
+            let (dir, remap) = (PathBuf::from(source_dir), request.remap.clone());
+            tokio::task::spawn_blocking(move || schema_remap::apply(&dir, &remap)).await??;
+            info!("Remapped schema of {}", source_dir);
+            request.tables.iter().map(|entry| schema_remap::table_entry(&request.remap, entry)).collect()
         };
+        let tables = tables.as_slice();
 
         let target_database = new_database_name.unwrap_or("restored_db");
 
+        if demo::is_enabled() {
+            return demo::simulate_restore(Path::new(source_dir), target_database, tables).await;
+        }
+
         // If creating a new database, create it first
         if let Some(new_db_name) = new_database_name {
             info!("Creating new database: {}", new_db_name);
             self.create_database(database_config, new_db_name).await?;
         }
 
-        // Build myloader command
+        // Plain SQL dumps (uploaded .sql/.sql.gz files) can't be loaded by myloader
+        if !archive_format::is_mydumper_dump(Path::new(source_dir)) {
+            if !tables.is_empty() {
+                return Err(anyhow!("Single tables can only be restored from mydumper backups"));
+            }
+            self.restore_plain_sql(database_config, Path::new(source_dir), target_database).await?;
+            info!("Restore completed successfully for database: {}", target_database);
+            return Ok(());
+        }
+
+        // Build myloader command; killed when a cancelled restore drops the wait for it
         let mut cmd = TokioCommand::new("myloader");
-        cmd.arg("--host").arg(&database_config.host)
+        cmd.kill_on_drop(true)
+            .arg("--host").arg(&database_config.host)
             .arg("--port").arg(database_config.port.to_string())
             .arg("--user").arg(&database_config.username)
-            .arg("--password").arg(&database_config.password)
-            .arg("--database").arg(target_database)
-            .arg("--directory").arg(&source_dir)
+            .arg("--password").arg(&database_config.password);
+
+        // Dumps of several databases restore each one under its own name unless a target is given
+        let dumped_databases = archive_format::dump_databases(Path::new(source_dir));
+        let target_database = if new_database_name.is_none() && dumped_databases.len() > 1 {
+            dumped_databases.join(", ")
+        } else {
+            cmd.arg("--database").arg(target_database);
+            target_database.to_string()
+        };
+
+        cmd.arg("--directory").arg(source_dir)
             .arg("--verbose").arg("3")
-            .arg("--threads").arg("4");
+            .arg("--threads").arg(tuning.threads.unwrap_or(4).to_string());
 
         if overwrite_existing {
             cmd.arg("--overwrite-tables");
         }
+        if let Some(queries) = tuning.queries_per_transaction {
+            cmd.arg("--queries-per-transaction").arg(queries.to_string());
+        }
+        if let Some(optimize_keys) = tuning.innodb_optimize_keys {
+            cmd.arg(format!("--innodb-optimize-keys={}", optimize_keys.as_arg()));
+        }
+        if let Some(purge_mode) = tuning.purge_mode {
+            cmd.arg("--purge-mode").arg(purge_mode.as_arg());
+        }
+        // Entries name the tables by their database in the dump, not the restore target
+        if !tables.is_empty() {
+            cmd.arg("--tables-list").arg(tables.join(","));
+            info!("Restoring only tables: {}", tables.join(", "));
+        }
 
         info!("Executing myloader command for database: {}", target_database);
 
@@ -403,7 +895,7 @@ impl MydumperService {
             database_config.port
         );
 
-        let pool = sqlx::MySqlPool::connect(&connection_string).await?;
+        let pool = mysql_pool::shared_pool(&connection_string).await?;
         
         // Create the database
         sqlx::query(&format!("CREATE DATABASE IF NOT EXISTS `{}`", database_name))
@@ -474,27 +966,52 @@ impl MydumperService {
 
 
 
-    async fn extract_compressed_archive(&self, archive_path: &Path) -> Result<String> {
-        let extract_dir = archive_path.with_extension("");
-        std::fs::create_dir_all(&extract_dir)?;
+    async fn extract_compressed_archive(&self, archive_path: &Path, extract_dir: &Path) -> Result<String> {
+        let format = archive_format::extract_async(archive_path.to_path_buf(), extract_dir.to_path_buf()).await
+            .map_err(|e| anyhow!("Failed to extract compressed archive: {}", e))?;
+        info!("Extracted {} archive to {}", format, extract_dir.display());
 
-        let mut cmd = TokioCommand::new("tar");
-        
-        if archive_path.extension().and_then(|s| s.to_str()) == Some("gz") {
-            cmd.args(&["-xzf", &archive_path.to_string_lossy(), "-C", &extract_dir.to_string_lossy()]);
-        } else if archive_path.extension().and_then(|s| s.to_str()) == Some("zst") {
-            cmd.args(&["--zstd", "-xf", &archive_path.to_string_lossy(), "-C", &extract_dir.to_string_lossy()]);
-        } else {
-            return Err(anyhow!("Unsupported archive format"));
+        Ok(archive_format::content_root(extract_dir).to_string_lossy().to_string())
+    }
+
+    /// Load plain SQL dump files with the mysql client, for backups that aren't mydumper output
+    async fn restore_plain_sql(&self, database_config: &DatabaseConfig, source_dir: &Path, target_database: &str) -> Result<()> {
+        let mut sql_files: Vec<_> = std::fs::read_dir(source_dir)?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("sql"))
+            .collect();
+        sql_files.sort();
+
+        if sql_files.is_empty() {
+            return Err(anyhow!("Backup contains neither mydumper output nor .sql files"));
         }
 
-        let status = cmd.status().await?;
-        
-        if !status.success() {
-            return Err(anyhow!("Failed to extract compressed archive"));
+        for sql_file in sql_files {
+            info!("Loading {} into {} with mysql client", sql_file.display(), target_database);
+            let mut child = TokioCommand::new("mysql")
+                .arg("--host").arg(&database_config.host)
+                .arg("--port").arg(database_config.port.to_string())
+                .arg("--user").arg(&database_config.username)
+                .arg(format!("--password={}", database_config.password))
+                .arg(target_database)
+                .stdin(Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()?;
+
+            let mut stdin = child.stdin.take().ok_or_else(|| anyhow!("Failed to open mysql stdin"))?;
+            let mut file = File::open(&sql_file).await?;
+            tokio::io::copy(&mut file, &mut stdin).await?;
+            drop(stdin);
+
+            let status = child.wait().await?;
+            if !status.success() {
+                error!("mysql failed with exit code: {:?}", status.code());
+                return Err(anyhow!("mysql failed with exit code: {:?}", status.code()));
+            }
         }
 
-        Ok(extract_dir.to_string_lossy().to_string())
+        Ok(())
     }
 
     // pub fn is_mydumper_available(&self) -> bool {
@@ -515,4 +1032,149 @@ impl MydumperService {
 
 
 
-}
\ No newline at end of file
+}
+
+/// Space a dump of `raw_size` bytes of table data needs: the dump files in the tmp directory
+/// plus the archive made from them, which exist side by side until the archive is complete
+fn required_space(raw_size: i64, compression: &CompressionType) -> u64 {
+    let raw_size = raw_size.max(0) as f64;
+    let compressed = raw_size * COMPRESSED_RATIO;
+    let (dump, archive) = match compression {
+        // mydumper compresses every file it writes
+        CompressionType::Gzip => (compressed, compressed),
+        // Only the archive is compressed
+        CompressionType::Zstd => (raw_size, compressed),
+        CompressionType::None => (raw_size, raw_size),
+    };
+    (dump + archive) as u64
+}
+
+/// Free bytes on the filesystem holding `path`, from `df`
+async fn available_space(path: &str) -> Option<u64> {
+    let output = TokioCommand::new("df").args(["-P", "-k", path]).output().await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let content = String::from_utf8_lossy(&output.stdout);
+    let kib: u64 = content.lines().nth(1)?.split_whitespace().nth(3)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Directory extracted for a restore, removed when dropped unless kept
+struct ScratchDir(Option<PathBuf>);
+
+impl ScratchDir {
+    fn keep(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        if let Some(dir) = self.0.take() {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+}
+
+/// Tables of an interrupted backup that were dumped completely: marked done in its last progress
+/// snapshot and with their schema file in the dump directory. Only single-database dumps qualify.
+async fn resumable_tables(pool: &SqlitePool, job_id: &str, dump_dir: &Path) -> Result<Vec<String>> {
+    let Some(progress) = progress_tracker::load_snapshot(pool, job_id).await? else {
+        return Ok(Vec::new());
+    };
+    if progress.database_name == ALL_DATABASES || !dump_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    let mut entries = tokio::fs::read_dir(dump_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        files.push(entry.file_name().to_string_lossy().to_string());
+    }
+    Ok(progress.tables.into_iter()
+        .filter(|table| matches!(table.status, TableStatus::Completed))
+        .filter(|table| {
+            let schema_file = format!("{}.{}-schema.sql", progress.database_name, table.name);
+            files.iter().any(|file| file.starts_with(&schema_file))
+        })
+        .map(|table| table.name)
+        .collect())
+}
+
+/// Remove the files of tables an interrupted dump didn't finish, and what a resumed run left behind
+async fn discard_partial_tables(dump_dir: &Path, database_name: &str, kept_tables: &[String]) -> Result<()> {
+    let kept = |file: &str| kept_tables.iter().any(|table| {
+        file.starts_with(&format!("{}.{}.", database_name, table))
+            || file.starts_with(&format!("{}.{}-schema", database_name, table))
+    });
+    let mut entries = tokio::fs::read_dir(dump_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let file = entry.file_name().to_string_lossy().to_string();
+        if entry.file_type().await?.is_dir() {
+            tokio::fs::remove_dir_all(entry.path()).await?;
+        } else if file.starts_with(&format!("{}.", database_name)) && !kept(&file) {
+            tokio::fs::remove_file(entry.path()).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Move the files of a resumed dump into the interrupted one's directory, replacing shared ones
+async fn merge_dump_files(from: &Path, into: &Path) -> Result<()> {
+    let mut entries = tokio::fs::read_dir(from).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        tokio::fs::rename(entry.path(), into.join(entry.file_name())).await?;
+    }
+    tokio::fs::remove_dir(from).await?;
+    Ok(())
+}
+
+/// Table name of an excluded-table entry, which reads "name (ENGINE)"
+fn table_name(entry: &str) -> &str {
+    entry.rsplit_once(" (").map_or(entry, |(name, _)| name)
+}
+
+/// Quote an argument for display in a shell command line
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=,".contains(c)) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// Write percentages sent on the returned channel into a progress column of the job
+pub(crate) fn mirror_progress(pool: &SqlitePool, job_id: &str, column: &'static str) -> (tokio::sync::watch::Sender<u32>, tokio::task::JoinHandle<()>) {
+    let (progress_tx, mut progress_rx) = tokio::sync::watch::channel(0u32);
+    let progress_pool = pool.clone();
+    let progress_job_id = job_id.to_string();
+    let progress_task = tokio::spawn(async move {
+        while progress_rx.changed().await.is_ok() {
+            let percent = *progress_rx.borrow_and_update();
+            let _ = sqlx::query(&format!("UPDATE jobs SET {} = ? WHERE id = ?", column))
+                .bind(percent as i32)
+                .bind(&progress_job_id)
+                .execute(&progress_pool)
+                .await;
+        }
+    });
+    (progress_tx, progress_task)
+}
+
+/// Store what mydumper consumed on the job, if it could be measured
+async fn record_usage(pool: &SqlitePool, job_id: &str, usage: Option<&ResourceUsage>) {
+    if let Some(usage) = usage {
+        if let Err(e) = usage.record(pool, job_id).await {
+            warn!("Failed to record resource usage of job {}: {}", job_id, e);
+        }
+    }
+}
+
+/// Add a line to a job's mydumper log after mydumper itself has finished
+async fn append_log(log_file_path: &str, message: &str) {
+    let line = format!("[{}] {}\n", chrono::Utc::now().format("%Y-%m-%d %H:%M:%S"), message);
+    if let Ok(mut file) = tokio::fs::OpenOptions::new().append(true).open(log_file_path).await {
+        let _ = file.write_all(line.as_bytes()).await;
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use sqlx::mysql::{MySqlPool, MySqlPoolOptions};
+use tokio::sync::Mutex;
+use tracing::info;
+
+/// Pools that have not been used for this long are closed
+const POOL_TTL: Duration = Duration::from_secs(5 * 60);
+/// Connections per cached pool; kept low for servers with strict max_connections
+const POOL_MAX_CONNECTIONS: u32 = 2;
+
+struct CachedPool {
+    pool: MySqlPool,
+    last_used: Instant,
+}
+
+/// Cached MySQL pools keyed by connection string
+fn pools() -> &'static Mutex<HashMap<String, CachedPool>> {
+    static POOLS: OnceLock<Mutex<HashMap<String, CachedPool>>> = OnceLock::new();
+    POOLS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Get a shared MySQL pool for a connection string, connecting on first use
+pub async fn shared_pool(connection_string: &str) -> Result<MySqlPool, sqlx::Error> {
+    {
+        let mut pools = pools().lock().await;
+        evict_expired(&mut pools);
+
+        if let Some(cached) = pools.get_mut(connection_string) {
+            cached.last_used = Instant::now();
+            return Ok(cached.pool.clone());
+        }
+    }
+
+    // Connect without holding the lock so one unreachable server doesn't block the others
+    let pool = MySqlPoolOptions::new()
+        .max_connections(POOL_MAX_CONNECTIONS)
+        .idle_timeout(POOL_TTL)
+        .connect(connection_string)
+        .await?;
+
+    let mut pools = pools().lock().await;
+    let cached = pools.entry(connection_string.to_string()).or_insert(CachedPool {
+        pool,
+        last_used: Instant::now(),
+    });
+    Ok(cached.pool.clone())
+}
+
+/// Close and drop pools whose TTL has expired
+fn evict_expired(pools: &mut HashMap<String, CachedPool>) {
+    let expired: Vec<String> = pools.iter()
+        .filter(|(_, cached)| cached.last_used.elapsed() >= POOL_TTL)
+        .map(|(key, _)| key.clone())
+        .collect();
+
+    for key in expired {
+        if let Some(cached) = pools.remove(&key) {
+            info!("Closing idle MySQL pool");
+            tokio::spawn(async move { cached.pool.close().await });
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::{bail, Result};
+use serde_json::{json, Value};
+use super::JobSummary;
+
+/// Embed colors by outcome
+const COLOR_COMPLETED: u32 = 0x2ECC71;
+const COLOR_FAILED: u32 = 0xE74C3C;
+/// Characters of the log tail shown at most, when the error leaves room for it
+const MAX_LOG_CHARS: usize = 3000;
+/// Log characters kept when a long error competes for the description, as context of the error
+const MIN_LOG_CHARS: usize = 1000;
+/// Discord rejects embeds exceeding these with HTTP 400
+const MAX_TITLE_CHARS: usize = 256;
+const MAX_DESCRIPTION_CHARS: usize = 4096;
+const MAX_FIELD_VALUE_CHARS: usize = 1024;
+const MAX_FOOTER_CHARS: usize = 2048;
+const MAX_EMBED_CHARS: usize = 6000;
+/// The code block around the log tail
+const LOG_FENCE_CHARS: usize = "```\n\n```".len();
+
+/// Post the summary of a finished job to a Discord webhook
+pub async fn send(http: &reqwest::Client, webhook_url: &str, summary: &JobSummary) -> Result<()> {
+    let response = http.post(webhook_url)
+        .header("Content-Type", "application/json")
+        .body(payload(summary).to_string())
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        bail!("Discord answered HTTP {}: {}", status, body.trim());
+    }
+    Ok(())
+}
+
+/// One embed colored by outcome, with the facts as fields and the error and log tail as description.
+///
+/// Every part is cut to Discord's limits, the description to what the other parts leave of the
+/// embed's total.
+pub(crate) fn payload(summary: &JobSummary) -> Value {
+    let mut fields = vec![
+        ("Task", summary.task_name.clone()),
+        ("Database", summary.database.clone()),
+        ("Status", summary.status().to_string()),
+    ];
+    if let Some(duration) = summary.duration() {
+        fields.push(("Duration", duration));
+    }
+    if let Some(size) = summary.size() {
+        fields.push(("Archive size", size));
+    }
+    if summary.attempt > 1 {
+        fields.push(("Attempt", summary.attempt.to_string()));
+    }
+    let fields: Vec<(&str, String)> = fields.into_iter()
+        .map(|(name, value)| (name, truncate(&value, MAX_FIELD_VALUE_CHARS)))
+        .collect();
+    let title = truncate(&summary.title(), MAX_TITLE_CHARS);
+    let footer = truncate(&format!("Job {}", summary.job_id), MAX_FOOTER_CHARS);
+
+    let used = title.chars().count()
+        + footer.chars().count()
+        + fields.iter().map(|(name, value)| name.chars().count() + value.chars().count()).sum::<usize>();
+    let mut budget = MAX_DESCRIPTION_CHARS.min(MAX_EMBED_CHARS.saturating_sub(used));
+    let mut description = String::new();
+    if let Some(error) = &summary.error_message {
+        let reserved = summary.log_tail.as_ref()
+            .map_or(0, |log_tail| log_tail.chars().count().min(MIN_LOG_CHARS) + LOG_FENCE_CHARS);
+        let error = truncate(&format!("**Error:** {}\n", error), budget.saturating_sub(reserved));
+        budget -= error.chars().count();
+        description.push_str(&error);
+    }
+    if let Some(log_tail) = &summary.log_tail {
+        let max_chars = MAX_LOG_CHARS.min(budget.saturating_sub(LOG_FENCE_CHARS));
+        if max_chars > 0 {
+            // Keep the end, that's where the failure is
+            let log_tail = truncate_start(log_tail, max_chars);
+            description.push_str(&format!("```\n{}\n```", log_tail.replace("```", "'''")));
+        }
+    }
+
+    let mut embed = json!({
+        "title": title,
+        "color": if summary.succeeded { COLOR_COMPLETED } else { COLOR_FAILED },
+        "fields": fields.iter().map(|(name, value)| field(name, value)).collect::<Vec<_>>(),
+        "footer": { "text": footer },
+    });
+    if !description.is_empty() {
+        embed["description"] = json!(description);
+    }
+    if let Some(job_url) = &summary.job_url {
+        embed["url"] = json!(job_url);
+    }
+    if let Some(completed_at) = summary.completed_at {
+        embed["timestamp"] = json!(completed_at.to_rfc3339());
+    }
+
+    json!({
+        "username": "rDumper",
+        "embeds": [embed],
+        // Task names and errors must not ping anyone
+        "allowed_mentions": { "parse": [] },
+    })
+}
+
+fn field(name: &str, value: &str) -> Value {
+    // Discord rejects empty field values as well
+    let value = if value.trim().is_empty() { "-" } else { value };
+    json!({ "name": name, "value": value, "inline": true })
+}
+
+/// The start of `text` in at most `max_chars` characters, an ellipsis marking the cut
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    if max_chars > 0 {
+        truncated.push('…');
+    }
+    truncated
+}
+
+/// The end of `text` in at most `max_chars` characters, an ellipsis marking the cut
+fn truncate_start(text: &str, max_chars: usize) -> String {
+    let count = text.chars().count();
+    if count <= max_chars {
+        return text.to_string();
+    }
+    let mut truncated = String::new();
+    if max_chars > 0 {
+        truncated.push('…');
+    }
+    truncated.extend(text.chars().skip(count - max_chars.saturating_sub(1)));
+    truncated
+}
@@ -0,0 +1,180 @@
+use std::sync::Arc;
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufStream};
+use tokio::net::TcpStream;
+use tokio::time::Duration;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+use super::JobSummary;
+
+/// Limit for the whole SMTP conversation of one email
+const SEND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How the connection to the SMTP server is secured
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum SmtpSecurity {
+    /// Plain connection upgraded with STARTTLS, usually on port 587
+    Starttls,
+    /// TLS from the start, usually on port 465
+    Tls,
+    /// Unencrypted, only for relays on the same host or network
+    None,
+}
+
+/// SMTP server job emails are sent through
+#[derive(Debug, Clone)]
+pub struct SmtpSettings {
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    /// Recipients of tasks that don't name their own
+    pub to: Vec<String>,
+}
+
+/// Email the summary of a finished job to `recipients`
+pub async fn send(settings: &SmtpSettings, recipients: &[String], summary: &JobSummary) -> Result<()> {
+    let message = message(settings, recipients, summary);
+    tokio::time::timeout(SEND_TIMEOUT, deliver(settings, recipients, &message))
+        .await
+        .map_err(|_| anyhow!("SMTP server {} did not answer in time", settings.host))?
+}
+
+async fn deliver(settings: &SmtpSettings, recipients: &[String], message: &str) -> Result<()> {
+    let tcp = TcpStream::connect((settings.host.as_str(), settings.port))
+        .await
+        .with_context(|| format!("Failed to connect to SMTP server {}:{}", settings.host, settings.port))?;
+
+    if settings.security == SmtpSecurity::Tls {
+        let mut stream = BufStream::new(tls_connect(&settings.host, tcp).await?);
+        reply(&mut stream, 220).await?;
+        return transaction(&mut stream, settings, recipients, message).await;
+    }
+
+    let mut stream = BufStream::new(tcp);
+    reply(&mut stream, 220).await?;
+    if settings.security == SmtpSecurity::Starttls {
+        command(&mut stream, &format!("EHLO {}", hostname()), 250).await?;
+        command(&mut stream, "STARTTLS", 220).await?;
+        let mut stream = BufStream::new(tls_connect(&settings.host, stream.into_inner()).await?);
+        return transaction(&mut stream, settings, recipients, message).await;
+    }
+    transaction(&mut stream, settings, recipients, message).await
+}
+
+/// Greet, log in and hand over the message on an established (and, if wanted, encrypted) connection
+async fn transaction<S>(stream: &mut BufStream<S>, settings: &SmtpSettings, recipients: &[String], message: &str) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    command(stream, &format!("EHLO {}", hostname()), 250).await?;
+    if let (Some(username), Some(password)) = (&settings.username, &settings.password) {
+        let credentials = BASE64.encode(format!("\0{}\0{}", username, password));
+        command(stream, &format!("AUTH PLAIN {}", credentials), 235).await
+            .context("SMTP login failed")?;
+    }
+    command(stream, &format!("MAIL FROM:<{}>", settings.from), 250).await?;
+    for recipient in recipients {
+        command(stream, &format!("RCPT TO:<{}>", recipient), 250).await?;
+    }
+    command(stream, "DATA", 354).await?;
+    stream.write_all(message.as_bytes()).await?;
+    command(stream, ".", 250).await?;
+    // The message is accepted at this point, so a server hanging up early is no failure
+    let _ = command(stream, "QUIT", 221).await;
+    Ok(())
+}
+
+async fn tls_connect(host: &str, tcp: TcpStream) -> Result<tokio_rustls::client::TlsStream<TcpStream>> {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let config = ClientConfig::builder_with_provider(Arc::new(tokio_rustls::rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let server_name = ServerName::try_from(host.to_string())
+        .map_err(|_| anyhow!("Invalid SMTP host name: {}", host))?;
+    TlsConnector::from(Arc::new(config))
+        .connect(server_name, tcp)
+        .await
+        .with_context(|| format!("TLS handshake with SMTP server {} failed", host))
+}
+
+/// Send one command line and wait for its reply
+async fn command<S>(stream: &mut BufStream<S>, line: &str, expected: u16) -> Result<String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.write_all(line.as_bytes()).await?;
+    stream.write_all(b"\r\n").await?;
+    stream.flush().await?;
+    let verb = line.split(' ').next().unwrap_or(line);
+    reply(stream, expected).await.with_context(|| format!("SMTP {} rejected", verb))
+}
+
+/// Read a possibly multi-line reply, failing unless its code is in the class of `expected`
+async fn reply<S>(stream: &mut BufStream<S>, expected: u16) -> Result<String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut text = String::new();
+    loop {
+        let mut line = String::new();
+        if stream.read_line(&mut line).await? == 0 {
+            bail!("SMTP server closed the connection");
+        }
+        let line = line.trim_end();
+        let code: u16 = line.get(..3).and_then(|code| code.parse().ok())
+            .ok_or_else(|| anyhow!("Unexpected SMTP reply: {}", line))?;
+        text.push_str(line.get(4..).unwrap_or_default());
+        // "250-..." continues the reply, "250 ..." ends it
+        if line.as_bytes().get(3) == Some(&b'-') {
+            text.push('\n');
+            continue;
+        }
+        if code / 100 != expected / 100 {
+            bail!("{} {}", code, text);
+        }
+        return Ok(text);
+    }
+}
+
+/// Name this host introduces itself with
+fn hostname() -> String {
+    std::env::var("HOSTNAME").ok().filter(|name| !name.is_empty()).unwrap_or_else(|| "localhost".to_string())
+}
+
+/// The message in SMTP DATA form: headers, a base64 body and the terminating dot line
+fn message(settings: &SmtpSettings, recipients: &[String], summary: &JobSummary) -> String {
+    let domain = settings.from.rsplit_once('@').map(|(_, domain)| domain).unwrap_or("localhost");
+    let body = BASE64.encode(summary.text());
+    let mut message = format!(
+        "From: rDumper <{}>\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: <{}@{}>\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\n",
+        settings.from,
+        recipients.join(", "),
+        encode_header(&summary.title()),
+        chrono::Utc::now().to_rfc2822(),
+        uuid::Uuid::new_v4(),
+        domain,
+    );
+    // Lines of at most 76 characters, as MIME asks for; base64 never starts one with a dot
+    for chunk in body.as_bytes().chunks(76) {
+        message.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        message.push_str("\r\n");
+    }
+    message
+}
+
+/// A header value as RFC 2047 encoded word when it isn't plain ASCII
+fn encode_header(value: &str) -> String {
+    if value.is_ascii() {
+        value.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", BASE64.encode(value))
+    }
+}
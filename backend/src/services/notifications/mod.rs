@@ -0,0 +1,307 @@
+pub mod discord;
+pub mod email;
+pub mod push;
+pub mod slack;
+
+use std::io::SeekFrom;
+use std::path::Path;
+use std::sync::Arc;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Duration;
+use tracing::{info, warn};
+use crate::models::{Job, LogLevel, NotifyOn, Task};
+use crate::services::events::{self, AppEvent};
+use crate::services::remote_storage::format_size;
+use crate::state::AppConfig;
+use self::email::SmtpSettings;
+use self::push::{GotifySettings, NtfySettings};
+use self::slack::SlackSettings;
+
+/// Lines from the end of a failed job's log that go into its notification
+const LOG_TAIL_LINES: usize = 30;
+/// Bytes read from the end of the log to find them
+const LOG_TAIL_BYTES: u64 = 16 * 1024;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Channels finished backups are reported on; all unset turns notifications off
+#[derive(Debug, Clone, Default)]
+pub struct NotificationSettings {
+    /// Address the web UI is reached at, for links to the job
+    pub public_url: Option<String>,
+    pub email: Option<SmtpSettings>,
+    pub slack: Option<SlackSettings>,
+    pub discord_webhooks: Vec<String>,
+    pub ntfy: Option<NtfySettings>,
+    pub gotify: Option<GotifySettings>,
+}
+
+impl NotificationSettings {
+    fn is_empty(&self) -> bool {
+        self.email.is_none()
+            && self.slack.is_none()
+            && self.discord_webhooks.is_empty()
+            && self.ntfy.is_none()
+            && self.gotify.is_none()
+    }
+}
+
+/// What a notification says about a finished backup job
+#[derive(Debug, Clone)]
+pub struct JobSummary {
+    pub job_id: String,
+    pub task_name: String,
+    pub database: String,
+    pub succeeded: bool,
+    pub attempt: i32,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub backup_size: Option<i64>,
+    pub error_message: Option<String>,
+    /// Last lines of the log, only for failed jobs
+    pub log_tail: Option<String>,
+    /// The job in the web UI, when the public URL is known
+    pub job_url: Option<String>,
+}
+
+impl JobSummary {
+    fn new(job: &Job, task: &Task, log_tail: Option<String>, public_url: Option<&str>) -> Self {
+        Self {
+            job_id: job.id.clone(),
+            task_name: task.name.clone(),
+            database: job.used_database.clone()
+                .or_else(|| task.database_name.clone())
+                .unwrap_or_else(|| "-".to_string()),
+            succeeded: job.status == "completed",
+            attempt: job.attempt,
+            started_at: job.started_at,
+            completed_at: job.completed_at,
+            backup_size: job.backup_size,
+            error_message: job.error_message.clone(),
+            log_tail,
+            job_url: public_url.map(|url| format!("{}/jobs?job={}", url.trim_end_matches('/'), job.id)),
+        }
+    }
+
+    pub fn status(&self) -> &'static str {
+        if self.succeeded { "completed" } else { "failed" }
+    }
+
+    /// How urgent the outcome is: failures are errors, backups that only succeeded after retries warnings
+    pub fn level(&self) -> LogLevel {
+        match (self.succeeded, self.attempt) {
+            (false, _) => LogLevel::Error,
+            (true, 1) => LogLevel::Info,
+            (true, _) => LogLevel::Warn,
+        }
+    }
+
+    /// One line naming the task and outcome, used as email subject and message title
+    pub fn title(&self) -> String {
+        let outcome = if self.succeeded { "succeeded" } else { "failed" };
+        format!("[rDumper] Backup of {} {}", self.task_name, outcome)
+    }
+
+    /// Run time as e.g. `1h 02m 05s`
+    pub fn duration(&self) -> Option<String> {
+        let seconds = (self.completed_at? - self.started_at?).num_seconds().max(0);
+        Some(match (seconds / 3600, seconds % 3600 / 60, seconds % 60) {
+            (0, 0, s) => format!("{}s", s),
+            (0, m, s) => format!("{}m {:02}s", m, s),
+            (h, m, s) => format!("{}h {:02}m {:02}s", h, m, s),
+        })
+    }
+
+    pub fn size(&self) -> Option<String> {
+        self.backup_size.map(|bytes| format_size(bytes.max(0) as u64))
+    }
+
+    /// Plain text report with the facts, the error and the log tail
+    pub fn text(&self) -> String {
+        let mut text = format!("{}\n\n", self.title());
+        text.push_str(&format!("Task:      {}\n", self.task_name));
+        text.push_str(&format!("Database:  {}\n", self.database));
+        text.push_str(&format!("Status:    {}\n", self.status()));
+        if self.attempt > 1 {
+            text.push_str(&format!("Attempt:   {}\n", self.attempt));
+        }
+        if let Some(started_at) = self.started_at {
+            text.push_str(&format!("Started:   {}\n", started_at.format("%Y-%m-%d %H:%M:%S UTC")));
+        }
+        if let Some(duration) = self.duration() {
+            text.push_str(&format!("Duration:  {}\n", duration));
+        }
+        if let Some(size) = self.size() {
+            text.push_str(&format!("Size:      {}\n", size));
+        }
+        text.push_str(&format!("Job:       {}\n", self.job_url.as_deref().unwrap_or(&self.job_id)));
+        if let Some(error) = &self.error_message {
+            text.push_str(&format!("\nError: {}\n", error));
+        }
+        if let Some(log_tail) = &self.log_tail {
+            text.push_str(&format!("\nLast lines of the log:\n\n{}\n", log_tail));
+        }
+        text
+    }
+
+    /// A message template with `{task}`, `{database}`, `{status}`, `{duration}`, `{size}`,
+    /// `{attempt}`, `{error}`, `{job_id}` and `{job_url}` filled in, `-` standing in for what's unknown
+    pub fn render(&self, template: &str) -> String {
+        let or_dash = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        template
+            .replace("{task}", &self.task_name)
+            .replace("{database}", &self.database)
+            .replace("{status}", self.status())
+            .replace("{duration}", &or_dash(self.duration()))
+            .replace("{size}", &or_dash(self.size()))
+            .replace("{attempt}", &self.attempt.to_string())
+            .replace("{error}", &or_dash(self.error_message.clone()))
+            .replace("{job_id}", &self.job_id)
+            .replace("{job_url}", &or_dash(self.job_url.clone()))
+    }
+}
+
+/// Reports finished backup jobs of tasks that ask for it on the configured channels
+pub struct Notifier {
+    pool: SqlitePool,
+    log_dir: String,
+    settings: NotificationSettings,
+    http: reqwest::Client,
+}
+
+impl Notifier {
+    pub fn new(pool: SqlitePool, config: &AppConfig) -> Self {
+        Self {
+            pool,
+            log_dir: config.log_dir.clone(),
+            settings: config.notifications.clone(),
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    pub async fn start(self: Arc<Self>) {
+        if self.settings.is_empty() {
+            return;
+        }
+        info!("Starting notifier...");
+        let mut app_events = events::subscribe();
+
+        loop {
+            match app_events.recv().await {
+                Ok(AppEvent::JobStatusChanged { job_id, status }) if matches!(status.as_str(), "completed" | "failed") => {
+                    // Sending may take a while, events keep being taken in the meantime
+                    let notifier = self.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = notifier.notify(&job_id).await {
+                            warn!("Failed to send notification for job {}: {}", job_id, e);
+                        }
+                    });
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(missed)) => warn!("Notifier missed {} events", missed),
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+
+    /// Send the notifications for a finished job, if its task wants them
+    async fn notify(&self, job_id: &str) -> Result<()> {
+        let job: Option<Job> = sqlx::query_as("SELECT * FROM jobs WHERE id = ? AND job_type = 'backup'")
+            .bind(job_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        let Some(job) = job else {
+            return Ok(());
+        };
+        let task: Option<Task> = sqlx::query_as("SELECT * FROM tasks WHERE id = ?")
+            .bind(&job.task_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        let Some(task) = task else {
+            return Ok(());
+        };
+        let wanted = match task.notify_on() {
+            NotifyOn::Never => false,
+            NotifyOn::Failure => job.status == "failed",
+            NotifyOn::Always => true,
+        };
+        if !wanted {
+            return Ok(());
+        }
+
+        let log_tail = if job.status == "failed" {
+            self.log_tail(&job).await
+        } else {
+            None
+        };
+        let summary = JobSummary::new(&job, &task, log_tail, self.settings.public_url.as_deref());
+
+        if let Some(smtp) = &self.settings.email {
+            let mut recipients = task.notify_emails();
+            if recipients.is_empty() {
+                recipients = smtp.to.clone();
+            }
+            if recipients.is_empty() {
+                warn!("Task {} wants notifications, but no email recipients are configured", task.name);
+            } else if let Err(e) = email::send(smtp, &recipients, &summary).await {
+                warn!("Failed to email the result of job {}: {}", job.id, e);
+            } else {
+                info!("Emailed the result of job {} to {}", job.id, recipients.join(", "));
+            }
+        }
+        if let Some(slack) = &self.settings.slack {
+            match slack::send(&self.http, slack, &summary).await {
+                Ok(()) => info!("Posted the result of job {} to Slack", job.id),
+                Err(e) => warn!("Failed to post the result of job {} to Slack: {}", job.id, e),
+            }
+        }
+        for webhook_url in &self.settings.discord_webhooks {
+            match discord::send(&self.http, webhook_url, &summary).await {
+                Ok(()) => info!("Posted the result of job {} to Discord", job.id),
+                Err(e) => warn!("Failed to post the result of job {} to Discord: {}", job.id, e),
+            }
+        }
+        if let Some(ntfy) = &self.settings.ntfy {
+            match push::send_ntfy(&self.http, ntfy, &summary).await {
+                Ok(()) => info!("Published the result of job {} to ntfy topic {}", job.id, ntfy.topic),
+                Err(e) => warn!("Failed to publish the result of job {} to ntfy: {}", job.id, e),
+            }
+        }
+        if let Some(gotify) = &self.settings.gotify {
+            match push::send_gotify(&self.http, gotify, &summary).await {
+                Ok(()) => info!("Pushed the result of job {} to Gotify", job.id),
+                Err(e) => warn!("Failed to push the result of job {} to Gotify: {}", job.id, e),
+            }
+        }
+        Ok(())
+    }
+
+    /// The last lines of the job's log, if it has one
+    async fn log_tail(&self, job: &Job) -> Option<String> {
+        let path = job.log_output.as_deref()
+            .map(Path::new)
+            .filter(|path| path.starts_with(&self.log_dir))
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| Path::new(&self.log_dir).join(&job.id).join("mydumper.log"));
+        let mut file = tokio::fs::File::open(&path).await.ok()?;
+        let start = file.metadata().await.ok()?.len().saturating_sub(LOG_TAIL_BYTES);
+        file.seek(SeekFrom::Start(start)).await.ok()?;
+        let mut content = Vec::new();
+        file.read_to_end(&mut content).await.ok()?;
+
+        let content = String::from_utf8_lossy(&content);
+        let mut lines: Vec<&str> = content.lines().collect();
+        if start > 0 && !lines.is_empty() {
+            // Most likely cut off in the middle
+            lines.remove(0);
+        }
+        let tail = lines[lines.len().saturating_sub(LOG_TAIL_LINES)..].join("\n");
+        (!tail.trim().is_empty()).then_some(tail)
+    }
+}
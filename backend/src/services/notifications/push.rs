@@ -0,0 +1,113 @@
+use anyhow::{bail, Result};
+use serde_json::json;
+use crate::models::LogLevel;
+use super::JobSummary;
+
+/// ntfy server and topic finished backups are published to
+#[derive(Debug, Clone)]
+pub struct NtfySettings {
+    /// Server root, e.g. `https://ntfy.sh`
+    pub url: String,
+    pub topic: String,
+    /// Access token for protected topics
+    pub token: Option<String>,
+}
+
+/// Gotify server finished backups are pushed to
+#[derive(Debug, Clone)]
+pub struct GotifySettings {
+    pub url: String,
+    /// Token of the Gotify application the messages appear under
+    pub token: String,
+}
+
+/// ntfy priority, 1 (min) to 5 (max); failures vibrate and ring through do-not-disturb
+fn ntfy_priority(level: &LogLevel) -> u8 {
+    match level {
+        LogLevel::Debug => 1,
+        LogLevel::Info => 3,
+        LogLevel::Warn => 4,
+        LogLevel::Error => 5,
+    }
+}
+
+/// Gotify priority, 0 to 10; from 8 on clients show a high-priority notification
+fn gotify_priority(level: &LogLevel) -> u8 {
+    match level {
+        LogLevel::Debug => 0,
+        LogLevel::Info => 4,
+        LogLevel::Warn => 6,
+        LogLevel::Error => 9,
+    }
+}
+
+/// Short body for a push notification: the facts and, for failures, the error
+fn message(summary: &JobSummary) -> String {
+    let mut lines = vec![format!("Database: {}", summary.database)];
+    if let Some(duration) = summary.duration() {
+        lines.push(format!("Duration: {}", duration));
+    }
+    if let Some(size) = summary.size() {
+        lines.push(format!("Archive size: {}", size));
+    }
+    if summary.attempt > 1 {
+        lines.push(format!("Attempt: {}", summary.attempt));
+    }
+    if let Some(error) = &summary.error_message {
+        lines.push(format!("Error: {}", error));
+    }
+    lines.join("\n")
+}
+
+/// Publish the summary of a finished job to the ntfy topic
+pub async fn send_ntfy(http: &reqwest::Client, settings: &NtfySettings, summary: &JobSummary) -> Result<()> {
+    let level = summary.level();
+    let mut body = json!({
+        "topic": settings.topic,
+        "title": summary.title(),
+        "message": message(summary),
+        "priority": ntfy_priority(&level),
+        "tags": [if summary.succeeded { "white_check_mark" } else { "x" }],
+    });
+    if let Some(job_url) = &summary.job_url {
+        body["click"] = json!(job_url);
+    }
+
+    // JSON is published to the server root, which keeps non-ASCII titles out of headers
+    let mut request = http.post(settings.url.trim_end_matches('/'))
+        .header("Content-Type", "application/json")
+        .body(body.to_string());
+    if let Some(token) = &settings.token {
+        request = request.bearer_auth(token);
+    }
+    check(request.send().await?, "ntfy").await
+}
+
+/// Push the summary of a finished job to Gotify
+pub async fn send_gotify(http: &reqwest::Client, settings: &GotifySettings, summary: &JobSummary) -> Result<()> {
+    let mut body = json!({
+        "title": summary.title(),
+        "message": message(summary),
+        "priority": gotify_priority(&summary.level()),
+    });
+    if let Some(job_url) = &summary.job_url {
+        body["extras"] = json!({ "client::notification": { "click": { "url": job_url } } });
+    }
+
+    let response = http.post(format!("{}/message", settings.url.trim_end_matches('/')))
+        .header("Content-Type", "application/json")
+        .header("X-Gotify-Key", &settings.token)
+        .body(body.to_string())
+        .send()
+        .await?;
+    check(response, "Gotify").await
+}
+
+async fn check(response: reqwest::Response, service: &str) -> Result<()> {
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        bail!("{} answered HTTP {}: {}", service, status, body.trim());
+    }
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use anyhow::{bail, Result};
+use serde_json::{json, Value};
+use super::JobSummary;
+
+/// Characters of the log tail shown in a message, Slack cuts section text at 3000
+const MAX_LOG_CHARS: usize = 2500;
+
+/// Slack incoming webhook finished backups are posted to
+#[derive(Debug, Clone)]
+pub struct SlackSettings {
+    pub webhook_url: String,
+    /// Message text with placeholders like `{task}`, see [`JobSummary::render`]; None posts the default layout
+    pub template: Option<String>,
+}
+
+/// Post the summary of a finished job to the webhook
+pub async fn send(http: &reqwest::Client, settings: &SlackSettings, summary: &JobSummary) -> Result<()> {
+    let response = http.post(&settings.webhook_url)
+        .header("Content-Type", "application/json")
+        .body(payload(settings, summary).to_string())
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        bail!("Slack answered HTTP {}: {}", status, body.trim());
+    }
+    Ok(())
+}
+
+/// The message: the rendered template, or a header, the facts as fields, the error, the log tail and a link
+fn payload(settings: &SlackSettings, summary: &JobSummary) -> Value {
+    if let Some(template) = &settings.template {
+        return json!({ "text": summary.render(template) });
+    }
+
+    let icon = if summary.succeeded { ":white_check_mark:" } else { ":x:" };
+    let mut fields = vec![
+        field("Task", &summary.task_name),
+        field("Database", &summary.database),
+        field("Status", summary.status()),
+    ];
+    if let Some(duration) = summary.duration() {
+        fields.push(field("Duration", &duration));
+    }
+    if let Some(size) = summary.size() {
+        fields.push(field("Archive size", &size));
+    }
+    if summary.attempt > 1 {
+        fields.push(field("Attempt", &summary.attempt.to_string()));
+    }
+
+    let mut blocks = vec![
+        json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": format!("{} *{}*", icon, escape(&summary.title())) },
+        }),
+        json!({ "type": "section", "fields": fields }),
+    ];
+    if let Some(error) = &summary.error_message {
+        blocks.push(json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": format!("*Error:* {}", escape(error)) },
+        }));
+    }
+    if let Some(log_tail) = &summary.log_tail {
+        // Keep the end, that's where the failure is
+        let skip = log_tail.chars().count().saturating_sub(MAX_LOG_CHARS);
+        let log_tail: String = log_tail.chars().skip(skip).collect();
+        blocks.push(json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": format!("```{}```", escape(&log_tail)) },
+        }));
+    }
+    if let Some(job_url) = &summary.job_url {
+        blocks.push(json!({
+            "type": "actions",
+            "elements": [{
+                "type": "button",
+                "text": { "type": "plain_text", "text": "View job" },
+                "url": job_url,
+            }],
+        }));
+    }
+
+    // `text` is what shows up in notifications and clients without blocks
+    json!({ "text": summary.title(), "blocks": blocks })
+}
+
+fn field(name: &str, value: &str) -> Value {
+    json!({ "type": "mrkdwn", "text": format!("*{}*\n{}", name, escape(value)) })
+}
+
+/// Text with the characters Slack reads as markup escaped
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use crate::models::Task;
+
+/// Grace period before a missed run or a stale backup counts as overdue
+pub const DEFAULT_GRACE_MINUTES: i64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverdueReason {
+    /// The scheduled run time passed without the worker starting the task
+    MissedRun,
+    /// No successful backup within one schedule interval
+    StaleBackup,
+}
+
+/// An active task that missed its schedule or hasn't produced a recent backup
+#[derive(Debug, Clone, Serialize)]
+pub struct OverdueTask {
+    pub task_id: String,
+    pub name: String,
+    pub database_config_id: String,
+    pub database_config_name: Option<String>,
+    pub cron_schedule: String,
+    pub next_run: Option<DateTime<Utc>>,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub schedule_interval_minutes: Option<i64>,
+    /// Minutes since the task became overdue (grace period included)
+    pub overdue_minutes: i64,
+    pub reasons: Vec<OverdueReason>,
+}
+
+/// Active tasks that are overdue, most overdue first
+pub async fn find_overdue_tasks(pool: &SqlitePool, grace: Duration) -> Result<Vec<OverdueTask>> {
+    let tasks: Vec<Task> = sqlx::query_as(
+        "SELECT * FROM tasks WHERE is_active = true ORDER BY name"
+    )
+    .fetch_all(pool)
+    .await?;
+    if tasks.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let config_names: HashMap<String, String> = sqlx::query_as::<_, (String, String)>(
+        "SELECT id, name FROM database_configs"
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+
+    let last_successes: HashMap<String, DateTime<Utc>> = sqlx::query_as::<_, (String, DateTime<Utc>)>(
+        "SELECT task_id, MAX(completed_at) FROM jobs WHERE job_type = 'backup' AND status = 'completed' AND task_id IS NOT NULL AND completed_at IS NOT NULL GROUP BY task_id"
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+
+    let now = Utc::now();
+    let mut overdue: Vec<OverdueTask> = tasks.into_iter().filter_map(|task| {
+        let mut reasons = Vec::new();
+        let mut overdue_since: Option<DateTime<Utc>> = None;
+
+        if let Some(next_run) = task.next_run {
+            if next_run + grace < now {
+                reasons.push(OverdueReason::MissedRun);
+                overdue_since = Some(next_run + grace);
+            }
+        }
+
+        // Unsupported cron patterns have no interval, so only the missed-run check applies
+        let interval = task.schedule_interval().ok();
+        let last_success_at = last_successes.get(&task.id).copied();
+        if let Some(interval) = interval {
+            // Tasks that never succeeded get one interval from creation before counting as stale
+            let deadline = last_success_at.unwrap_or(task.created_at) + interval + grace;
+            if deadline < now {
+                reasons.push(OverdueReason::StaleBackup);
+                overdue_since = Some(overdue_since.map_or(deadline, |since| since.min(deadline)));
+            }
+        }
+
+        let overdue_since = overdue_since?;
+        Some(OverdueTask {
+            database_config_name: config_names.get(&task.database_config_id).cloned(),
+            task_id: task.id,
+            name: task.name,
+            database_config_id: task.database_config_id,
+            cron_schedule: task.cron_schedule,
+            next_run: task.next_run,
+            last_success_at,
+            schedule_interval_minutes: interval.map(|interval| interval.num_minutes()),
+            overdue_minutes: (now - overdue_since).num_minutes(),
+            reasons,
+        })
+    }).collect();
+
+    overdue.sort_by_key(|task| std::cmp::Reverse(task.overdue_minutes));
+    Ok(overdue)
+}
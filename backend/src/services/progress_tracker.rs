@@ -1,16 +1,87 @@
 use anyhow::{anyhow, Result};
+use std::collections::{HashMap, HashSet};
+use std::io::SeekFrom;
 use std::path::Path;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use sqlx::SqlitePool;
 use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::Mutex;
 use tracing::{info, warn};
 use chrono::Utc;
 use regex::Regex;
 
 use crate::models::progress::{DetailedProgress, TableProgress, TableStatus, RdumperMeta};
 
+/// Parser states that have not been polled for this long are dropped
+const PARSER_STATE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How often a running backup's progress is written to the database
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(10);
+
 pub struct ProgressTracker {
     log_dir: String,
 }
 
+/// Incremental parse state for one mydumper log, kept between polls
+struct LogParserState {
+    offset: u64,
+    tables: Vec<TableProgress>,
+    thread_to_table: HashMap<u32, String>,
+    table_to_threads: HashMap<String, HashSet<u32>>,
+    is_finished: bool,
+    last_polled: Instant,
+}
+
+impl LogParserState {
+    fn new(table_names: &[String]) -> Self {
+        // Initialize all tables as pending
+        let tables = table_names.iter().map(|table_name| TableProgress {
+            name: table_name.clone(),
+            status: TableStatus::Pending,
+            progress_percent: None,
+            started_at: None,
+            completed_at: None,
+            error_message: None,
+        }).collect();
+
+        Self {
+            offset: 0,
+            tables,
+            thread_to_table: HashMap::new(),
+            table_to_threads: HashMap::new(),
+            is_finished: false,
+            last_polled: Instant::now(),
+        }
+    }
+}
+
+struct LogPatterns {
+    data: Regex,
+    error: Regex,
+    table_info: Regex,
+    finished: Regex,
+}
+
+/// Regex patterns for actual mydumper log format, compiled once
+fn log_patterns() -> &'static LogPatterns {
+    static PATTERNS: OnceLock<LogPatterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| LogPatterns {
+        // Format: 2025-09-29 14:53:21 [INFO] - Thread 3: `sbtest`.`sbtest3` [ 0% ] | Tables: 10/16
+        data: Regex::new(r"Thread (\d+): `[^`]+`\.`([^`]+)` \[ (\d+)% \]").unwrap(),
+        error: Regex::new(r"ERROR.*`([^`]+)`").unwrap(),
+        table_info: Regex::new(r"([^.]+)\.([^ ]+) has ~(\d+) rows").unwrap(),
+        finished: Regex::new(r"Finished dump at:").unwrap(),
+    })
+}
+
+/// Parser states keyed by log file path
+fn parser_states() -> &'static Mutex<HashMap<String, LogParserState>> {
+    static STATES: OnceLock<Mutex<HashMap<String, LogParserState>>> = OnceLock::new();
+    STATES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
 impl ProgressTracker {
     pub fn new(log_dir: String) -> Self {
         Self { log_dir }
@@ -25,11 +96,8 @@ impl ProgressTracker {
         let meta_content = fs::read_to_string(&meta_file).await?;
         let meta: RdumperMeta = serde_json::from_str(&meta_content)?;
 
-        // Load log content
-        let log_content = fs::read_to_string(&log_file).await?;
-
-        // Parse table progress from log
-        let mut tables = self.parse_table_progress(&log_content, &meta.tables).await?;
+        // Parse only the log lines written since the last poll
+        let mut tables = self.parse_table_progress(&log_file, &meta.tables).await?;
         
         // Add excluded tables as skipped
         for table_name in &meta.excluded_tables {
@@ -43,6 +111,18 @@ impl ProgressTracker {
             });
         }
 
+        // Tables dumped before an interruption are done already
+        for table_name in &meta.resumed_tables {
+            tables.push(TableProgress {
+                name: table_name.clone(),
+                status: TableStatus::Completed,
+                progress_percent: Some(100),
+                started_at: None,
+                completed_at: None,
+                error_message: None,
+            });
+        }
+
         // Calculate overall progress
         let total_tables = tables.len() as u32;
         let completed_tables = tables.iter().filter(|t| matches!(t.status, TableStatus::Completed)).count() as u32;
@@ -74,115 +154,196 @@ impl ProgressTracker {
         })
     }
 
-    /// Parse table progress from mydumper log using thread tracking
-    async fn parse_table_progress(&self, log_content: &str, table_names: &[String]) -> Result<Vec<TableProgress>> {
-        let mut tables = Vec::new();
-        
-        // Regex patterns for actual mydumper log format
-        // Format: 2025-09-29 14:53:21 [INFO] - Thread 3: `sbtest`.`sbtest3` [ 0% ] | Tables: 10/16
-        let data_pattern = Regex::new(r"Thread (\d+): `[^`]+`\.`([^`]+)` \[ (\d+)% \]")?;
-        let error_pattern = Regex::new(r"ERROR.*`([^`]+)`")?;
-        let table_info_pattern = Regex::new(r"([^.]+)\.([^ ]+) has ~(\d+) rows")?;
-        let finished_pattern = Regex::new(r"Finished dump at:")?;
+    /// Store the job's current progress as its snapshot, replacing the previous one
+    pub async fn save_snapshot(&self, pool: &SqlitePool, job_id: &str) -> Result<()> {
+        let progress = self.load_detailed_progress(job_id).await?;
+        sqlx::query(
+            "INSERT INTO job_progress (job_id, snapshot, updated_at) VALUES (?, ?, ?)
+             ON CONFLICT(job_id) DO UPDATE SET snapshot = excluded.snapshot, updated_at = excluded.updated_at"
+        )
+        .bind(job_id)
+        .bind(serde_json::to_string(&progress)?)
+        .bind(progress.last_updated)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
 
-        // Initialize all tables as pending
-        for table_name in table_names {
-            tables.push(TableProgress {
-                name: table_name.clone(),
-                status: TableStatus::Pending,
-                progress_percent: None,
-                started_at: None,
-                completed_at: None,
-                error_message: None,
-            });
+    /// Parse new lines of the mydumper log into the cached per-job state
+    async fn parse_table_progress(&self, log_file: &str, table_names: &[String]) -> Result<Vec<TableProgress>> {
+        let mut states = parser_states().lock().await;
+        states.retain(|_, state| state.last_polled.elapsed() < PARSER_STATE_TTL);
+
+        let state = states.entry(log_file.to_string())
+            .or_insert_with(|| LogParserState::new(table_names));
+        state.last_polled = Instant::now();
+
+        let mut file = fs::File::open(log_file).await?;
+        let file_len = file.metadata().await?.len();
+
+        // The log was truncated or recreated, start over
+        if file_len < state.offset {
+            *state = LogParserState::new(table_names);
         }
 
-        // Track which thread is working on which table
-        let mut thread_to_table: std::collections::HashMap<u32, String> = std::collections::HashMap::new();
-        let mut table_to_threads: std::collections::HashMap<String, std::collections::HashSet<u32>> = std::collections::HashMap::new();
+        if file_len > state.offset {
+            file.seek(SeekFrom::Start(state.offset)).await?;
+            let mut buffer = Vec::with_capacity((file_len - state.offset) as usize);
+            file.read_to_end(&mut buffer).await?;
 
-        // Check if backup is finished
-        let is_finished = finished_pattern.is_match(log_content);
-        
-        // Parse log lines
-        for line in log_content.lines() {
-            // Check for table info (table started)
-            if let Some(caps) = table_info_pattern.captures(line) {
-                let table_name = caps.get(2).unwrap().as_str(); // Second capture group is table name
-                if let Some(table) = tables.iter_mut().find(|t| t.name == table_name) {
-                    table.status = TableStatus::InProgress;
-                    if table.started_at.is_none() {
-                        table.started_at = Some(Utc::now());
-                    }
+            // Leave a trailing partial line for the next poll
+            if let Some(last_newline) = buffer.iter().rposition(|&b| b == b'\n') {
+                let complete = String::from_utf8_lossy(&buffer[..=last_newline]);
+                for line in complete.lines() {
+                    Self::parse_line(state, line);
+                }
+                state.offset += last_newline as u64 + 1;
+            }
+        }
+
+        // If backup is finished, mark all remaining tables as completed
+        if state.is_finished {
+            for table in state.tables.iter_mut() {
+                if !matches!(table.status, TableStatus::Error) && !matches!(table.status, TableStatus::Completed) {
+                    table.status = TableStatus::Completed;
+                    table.progress_percent = Some(100);
+                    table.completed_at = Some(Utc::now());
                 }
             }
+        }
+
+        Ok(state.tables.clone())
+    }
+
+    /// Apply a single log line to the parser state using thread tracking
+    fn parse_line(state: &mut LogParserState, line: &str) {
+        let patterns = log_patterns();
+        let tables = &mut state.tables;
 
-            // Check for data progress and track thread assignments
-            if let Some(caps) = data_pattern.captures(line) {
-                let thread_id = caps.get(1).unwrap().as_str().parse::<u32>().unwrap_or(0);
-                let table_name = caps.get(2).unwrap().as_str();
-                let progress = caps.get(3).unwrap().as_str().parse::<u32>().unwrap_or(0);
-                
-                // Check if this thread was working on a different table before
-                if let Some(previous_table) = thread_to_table.get(&thread_id) {
-                    if previous_table != table_name {
-                        // Thread switched to a new table, mark previous table as completed if no other threads are working on it
-                        if let Some(threads) = table_to_threads.get(previous_table) {
-                            if threads.len() <= 1 {
-                                // Only this thread was working on the previous table, mark it as completed
-                                if let Some(table) = tables.iter_mut().find(|t| t.name == *previous_table) {
-                                    if !matches!(table.status, TableStatus::Error) {
-                                        table.status = TableStatus::Completed;
-                                        table.progress_percent = Some(100);
-                                        table.completed_at = Some(Utc::now());
-                                    }
+        if patterns.finished.is_match(line) {
+            state.is_finished = true;
+        }
+
+        // Check for table info (table started)
+        if let Some(caps) = patterns.table_info.captures(line) {
+            let table_name = caps.get(2).unwrap().as_str(); // Second capture group is table name
+            if let Some(table) = tables.iter_mut().find(|t| t.name == table_name) {
+                table.status = TableStatus::InProgress;
+                if table.started_at.is_none() {
+                    table.started_at = Some(Utc::now());
+                }
+            }
+        }
+
+        // Check for data progress and track thread assignments
+        if let Some(caps) = patterns.data.captures(line) {
+            let thread_id = caps.get(1).unwrap().as_str().parse::<u32>().unwrap_or(0);
+            let table_name = caps.get(2).unwrap().as_str();
+            let progress = caps.get(3).unwrap().as_str().parse::<u32>().unwrap_or(0);
+
+            // Check if this thread was working on a different table before
+            if let Some(previous_table) = state.thread_to_table.get(&thread_id) {
+                if previous_table != table_name {
+                    // Thread switched to a new table, mark previous table as completed if no other threads are working on it
+                    if let Some(threads) = state.table_to_threads.get_mut(previous_table) {
+                        if threads.len() <= 1 {
+                            // Only this thread was working on the previous table, mark it as completed
+                            if let Some(table) = tables.iter_mut().find(|t| t.name == *previous_table) {
+                                if !matches!(table.status, TableStatus::Error) {
+                                    table.status = TableStatus::Completed;
+                                    table.progress_percent = Some(100);
+                                    table.completed_at = Some(Utc::now());
                                 }
                             }
-                            // Remove this thread from the previous table's thread set
-                            table_to_threads.get_mut(previous_table).unwrap().remove(&thread_id);
                         }
-                    }
-                }
-                
-                // Update thread-to-table mapping
-                thread_to_table.insert(thread_id, table_name.to_string());
-                
-                // Update table-to-threads mapping
-                table_to_threads.entry(table_name.to_string())
-                    .or_insert_with(std::collections::HashSet::new)
-                    .insert(thread_id);
-                
-                // Update table progress
-                if let Some(table) = tables.iter_mut().find(|t| t.name == table_name) {
-                    table.status = TableStatus::InProgress;
-                    table.progress_percent = Some(progress);
-                    if table.started_at.is_none() {
-                        table.started_at = Some(Utc::now());
+                        // Remove this thread from the previous table's thread set
+                        threads.remove(&thread_id);
                     }
                 }
             }
 
-            // Check for errors
-            if let Some(caps) = error_pattern.captures(line) {
-                let table_name = caps.get(1).unwrap().as_str();
-                if let Some(table) = tables.iter_mut().find(|t| t.name == table_name) {
-                    table.status = TableStatus::Error;
-                    table.error_message = Some("Error during backup".to_string());
+            // Update thread-to-table mapping
+            state.thread_to_table.insert(thread_id, table_name.to_string());
+
+            // Update table-to-threads mapping
+            state.table_to_threads.entry(table_name.to_string())
+                .or_default()
+                .insert(thread_id);
+
+            // Update table progress
+            if let Some(table) = tables.iter_mut().find(|t| t.name == table_name) {
+                table.status = TableStatus::InProgress;
+                table.progress_percent = Some(progress);
+                if table.started_at.is_none() {
+                    table.started_at = Some(Utc::now());
                 }
             }
         }
-        
-        // If backup is finished, mark all remaining tables as completed
-        if is_finished {
-            for table in tables.iter_mut() {
-                if !matches!(table.status, TableStatus::Error) && !matches!(table.status, TableStatus::Completed) {
-                    table.status = TableStatus::Completed;
-                    table.progress_percent = Some(100);
-                    table.completed_at = Some(Utc::now());
+
+        // Check for errors
+        if let Some(caps) = patterns.error.captures(line) {
+            let table_name = caps.get(1).unwrap().as_str();
+            if let Some(table) = tables.iter_mut().find(|t| t.name == table_name) {
+                table.status = TableStatus::Error;
+                table.error_message = Some("Error during backup".to_string());
+            }
+        }
+    }
+}
+
+/// Periodic progress snapshots of a running backup, stopped when dropped
+pub struct ProgressSnapshots {
+    tracker: ProgressTracker,
+    pool: SqlitePool,
+    job_id: String,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl ProgressSnapshots {
+    /// Snapshot the job's progress from the logs in `log_dir` every few seconds
+    pub fn start(log_dir: &str, pool: &SqlitePool, job_id: &str) -> Self {
+        let tracker = ProgressTracker::new(log_dir.to_string());
+        let task_pool = pool.clone();
+        let task_job_id = job_id.to_string();
+        let task = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SNAPSHOT_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(e) = tracker.save_snapshot(&task_pool, &task_job_id).await {
+                    warn!("Failed to snapshot progress of job {}: {}", task_job_id, e);
                 }
             }
+        });
+
+        Self {
+            tracker: ProgressTracker::new(log_dir.to_string()),
+            pool: pool.clone(),
+            job_id: job_id.to_string(),
+            task,
         }
+    }
 
-        Ok(tables)
+    /// Stop snapshotting and record the progress the job ended with
+    pub async fn finish(self) {
+        self.task.abort();
+        if let Err(e) = self.tracker.save_snapshot(&self.pool, &self.job_id).await {
+            warn!("Failed to snapshot final progress of job {}: {}", self.job_id, e);
+        }
     }
 }
+
+impl Drop for ProgressSnapshots {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// The last progress snapshot stored for a job
+pub async fn load_snapshot(pool: &SqlitePool, job_id: &str) -> Result<Option<DetailedProgress>> {
+    let snapshot: Option<String> = sqlx::query_scalar("SELECT snapshot FROM job_progress WHERE job_id = ?")
+        .bind(job_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(snapshot.map(|snapshot| serde_json::from_str(&snapshot)).transpose()?)
+}
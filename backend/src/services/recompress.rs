@@ -0,0 +1,243 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+use crate::models::{Backup, CompressionType, CreateJobRequest, Job, JobType, LogLevel};
+use crate::services::{archive_format, encryption, integrity, FilesystemBackupService, LoggingService};
+use crate::services::events::{self, AppEvent};
+
+/// Archive suffixes replaced when deriving the new file name
+const ARCHIVE_SUFFIXES: &[&str] = &[".tar.gz", ".tgz", ".tar.zst", ".tar.xz", ".tar"];
+
+/// File name extension for archives written with `compression`
+fn archive_extension(compression: CompressionType) -> &'static str {
+    match compression {
+        CompressionType::None => "tar",
+        CompressionType::Gzip => "tar.gz",
+        CompressionType::Zstd => "tar.zst",
+    }
+}
+
+/// Path of the re-compressed archive next to the original
+fn target_path(original: &Path, compression: CompressionType) -> PathBuf {
+    let file_name = original.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let stem = ARCHIVE_SUFFIXES.iter()
+        .find_map(|suffix| file_name.strip_suffix(suffix))
+        .unwrap_or(&file_name);
+    original.with_file_name(format!("{}.{}", stem, archive_extension(compression)))
+}
+
+/// Check that `backup` can be converted to `compression`, so the API can reject requests up front
+pub fn validate(backup: &Backup, compression: CompressionType) -> Result<()> {
+    let path = Path::new(&backup.file_path);
+    if !path.exists() {
+        return Err(anyhow!("Backup file no longer exists"));
+    }
+    if encryption::is_encrypted(path) {
+        return Err(anyhow!("Encrypted backups can't be re-compressed"));
+    }
+    let format = archive_format::ArchiveFormat::detect(path)?;
+    let current = match format {
+        archive_format::ArchiveFormat::TarGz => Some(CompressionType::Gzip),
+        archive_format::ArchiveFormat::TarZst => Some(CompressionType::Zstd),
+        archive_format::ArchiveFormat::Tar => Some(CompressionType::None),
+        archive_format::ArchiveFormat::TarXz => None,
+        _ => return Err(anyhow!("Only tar archives can be re-compressed, this backup is {}", format)),
+    };
+    if current == Some(compression) {
+        return Err(anyhow!("Backup is already {} compressed", compression));
+    }
+    Ok(())
+}
+
+/// Create a recompress job for `backup` and run it in the background; returns the job id
+pub async fn start_recompress_job(
+    pool: &SqlitePool,
+    backup_service: &FilesystemBackupService,
+    backup: Backup,
+    compression: CompressionType,
+) -> Result<String> {
+    let job = Job::new(CreateJobRequest {
+        task_id: backup.task_id.clone(),
+        used_database: backup.used_database.clone(),
+        job_type: JobType::Recompress,
+        backup_path: Some(backup.file_path.clone()),
+    });
+
+    sqlx::query(
+        r#"
+        INSERT INTO jobs (id, task_id, used_database, job_type, status, progress, started_at, completed_at, error_message, log_output, backup_path, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&job.id)
+    .bind(&job.task_id)
+    .bind(&job.used_database)
+    .bind(&job.job_type)
+    .bind(&job.status)
+    .bind(job.progress)
+    .bind(job.started_at)
+    .bind(job.completed_at)
+    .bind(&job.error_message)
+    .bind(&job.log_output)
+    .bind(&job.backup_path)
+    .bind(job.created_at)
+    .execute(pool)
+    .await?;
+
+    let pool = pool.clone();
+    let backup_service = backup_service.clone();
+    let job_id = job.id.clone();
+    tokio::spawn(async move {
+        run_recompress_job(&pool, &backup_service, &backup, compression, &job_id).await;
+    });
+
+    Ok(job.id)
+}
+
+async fn run_recompress_job(
+    pool: &SqlitePool,
+    backup_service: &FilesystemBackupService,
+    backup: &Backup,
+    compression: CompressionType,
+    job_id: &str,
+) {
+    let logging_service = LoggingService::new(Arc::new(pool.clone()));
+
+    let _ = sqlx::query("UPDATE jobs SET status = ?, started_at = ? WHERE id = ?")
+        .bind("running")
+        .bind(Utc::now())
+        .bind(job_id)
+        .execute(pool)
+        .await;
+    events::publish(AppEvent::job_status(job_id, "running"));
+    let _ = logging_service.log_job(
+        job_id,
+        &format!("Re-compressing backup {} to {}", backup.id, compression),
+        LogLevel::Info,
+    ).await;
+
+    match recompress_backup(pool, backup_service, backup, compression, job_id).await {
+        Ok((old_size, new_size)) => {
+            info!("Backup {} re-compressed to {}: {} -> {} bytes", backup.id, compression, old_size, new_size);
+            let _ = logging_service.log_job(
+                job_id,
+                &format!("Re-compressed to {}: {} -> {} bytes", compression, old_size, new_size),
+                LogLevel::Info,
+            ).await;
+            let _ = sqlx::query("UPDATE jobs SET status = ?, progress = ?, completed_at = ? WHERE id = ?")
+                .bind("completed")
+                .bind(100)
+                .bind(Utc::now())
+                .bind(job_id)
+                .execute(pool)
+                .await;
+            events::publish(AppEvent::job_status(job_id, "completed"));
+        }
+        Err(e) => {
+            warn!("Re-compressing backup {} failed: {}", backup.id, e);
+            let _ = logging_service.log_job(job_id, &format!("Re-compression failed: {}", e), LogLevel::Error).await;
+            let _ = sqlx::query("UPDATE jobs SET status = ?, error_message = ?, completed_at = ? WHERE id = ?")
+                .bind("failed")
+                .bind(e.to_string())
+                .bind(Utc::now())
+                .bind(job_id)
+                .execute(pool)
+                .await;
+            events::publish(AppEvent::job_status(job_id, "failed"));
+        }
+    }
+}
+
+async fn set_progress(pool: &SqlitePool, job_id: &str, progress: i32) {
+    let _ = sqlx::query("UPDATE jobs SET progress = ? WHERE id = ?")
+        .bind(progress)
+        .bind(job_id)
+        .execute(pool)
+        .await;
+}
+
+/// Write the new archive, prove it holds the same files, then swap it in; returns old and new size
+async fn recompress_backup(
+    pool: &SqlitePool,
+    backup_service: &FilesystemBackupService,
+    backup: &Backup,
+    compression: CompressionType,
+    job_id: &str,
+) -> Result<(u64, u64)> {
+    validate(backup, compression)?;
+
+    let original = PathBuf::from(&backup.file_path);
+    let target = target_path(&original, compression);
+    if target.exists() {
+        return Err(anyhow!("{} already exists", target.display()));
+    }
+    // Written under a name the backup scanner ignores until it has been verified
+    let partial = target.with_file_name(format!(
+        "{}.partial",
+        target.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+    ));
+
+    let result = write_and_compare(&original, &partial, compression, pool, job_id).await;
+    if let Err(e) = result {
+        let _ = tokio::fs::remove_file(&partial).await;
+        return Err(e);
+    }
+
+    // Keep the original modification time so age-based cleanup and listings are unaffected
+    let original_meta = tokio::fs::metadata(&original).await?;
+    let modified = original_meta.modified()?;
+    let partial_for_mtime = partial.clone();
+    tokio::task::spawn_blocking(move || {
+        std::fs::File::options().write(true).open(&partial_for_mtime)?.set_modified(modified)
+    }).await??;
+
+    let sha256_hash = integrity::hash_file(partial.clone()).await?;
+    let new_size = tokio::fs::metadata(&partial).await?.len();
+    tokio::fs::rename(&partial, &target).await?;
+    set_progress(pool, job_id, 95).await;
+
+    // Point the metadata at the new archive before the original goes away
+    let mut metadata = backup_service.load_backup_metadata(Path::new(&backup.meta_path)).await?;
+    metadata.file_path = target.to_string_lossy().to_string();
+    metadata.file_size = new_size as i64;
+    metadata.compression_type = compression.to_string();
+    metadata.ident = Some(sha256_hash.clone());
+    metadata.sha256_hash = Some(sha256_hash);
+    metadata.verified_at = Some(Utc::now().to_rfc3339());
+    metadata.verification_error = None;
+    backup_service.save_backup_metadata(&metadata).await?;
+
+    tokio::fs::remove_file(&original).await?;
+    FilesystemBackupService::invalidate_all_caches().await;
+
+    Ok((original_meta.len(), new_size))
+}
+
+async fn write_and_compare(
+    original: &Path,
+    partial: &Path,
+    compression: CompressionType,
+    pool: &SqlitePool,
+    job_id: &str,
+) -> Result<()> {
+    let (src, dest) = (original.to_path_buf(), partial.to_path_buf());
+    tokio::task::spawn_blocking(move || archive_format::recompress(&src, &dest, compression)).await??;
+    set_progress(pool, job_id, 50).await;
+
+    let (src, dest) = (original.to_path_buf(), partial.to_path_buf());
+    let (before, after) = tokio::task::spawn_blocking(move || -> Result<_> {
+        Ok((archive_format::content_digest(&src)?, archive_format::content_digest(&dest)?))
+    }).await??;
+    set_progress(pool, job_id, 90).await;
+
+    if before != after {
+        return Err(anyhow!(
+            "Content check failed: original has {} files, re-compressed archive differs",
+            before.len()
+        ));
+    }
+    Ok(())
+}
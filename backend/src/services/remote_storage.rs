@@ -0,0 +1,500 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use tokio::sync::watch;
+use tracing::{info, warn};
+use crate::models::{BackupMetadata, SftpTarget, StorageDestination, StorageTarget, Task};
+use crate::services::bandwidth::Throttle;
+use crate::services::gcs_storage::{self, GcsClient};
+use crate::services::s3_storage::S3Client;
+use crate::services::sftp_storage::{self, SftpSession};
+use crate::services::webdav_storage::{self, WebdavClient};
+use crate::services::FilesystemBackupService;
+
+const METADATA_FILE: &str = "rdumper.backup.json";
+/// Written and removed again by `test`
+const PROBE_FILE: &str = ".rdumper-test";
+
+/// A file at a destination, with its path relative to the configured prefix
+pub struct RemoteObject {
+    pub path: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+}
+
+/// Reject incomplete target settings before they are stored on a task
+pub fn validate(target: &StorageTarget) -> Result<()> {
+    match target {
+        StorageTarget::Local => Ok(()),
+        StorageTarget::S3(s3) => {
+            if s3.bucket.trim().is_empty() {
+                return Err(anyhow!("S3 bucket is required"));
+            }
+            if s3.access_key_id.is_empty() || s3.secret_access_key.is_empty() {
+                return Err(anyhow!("S3 access key id and secret access key are required"));
+            }
+            if let Some(endpoint) = &s3.endpoint {
+                if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+                    return Err(anyhow!("S3 endpoint must start with http:// or https://"));
+                }
+            }
+            Ok(())
+        }
+        StorageTarget::Sftp(sftp) => {
+            // A leading dash would be read as an ssh option
+            if sftp.host.trim().is_empty() || sftp.host.starts_with('-') {
+                return Err(anyhow!("SFTP host is required"));
+            }
+            if sftp.username.trim().is_empty() || sftp.username.starts_with('-') {
+                return Err(anyhow!("SFTP username is required"));
+            }
+            if sftp.remote_path.trim().is_empty() {
+                return Err(anyhow!("SFTP remote path is required"));
+            }
+            if let Some(host_key) = sftp.host_key.as_deref().filter(|key| !key.trim().is_empty()) {
+                sftp_storage::known_hosts(host_key)?;
+            }
+            Ok(())
+        }
+        StorageTarget::Gcs(gcs) => {
+            if gcs.bucket.trim().is_empty() {
+                return Err(anyhow!("GCS bucket is required"));
+            }
+            if let Some(endpoint) = &gcs.endpoint {
+                if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+                    return Err(anyhow!("GCS endpoint must start with http:// or https://"));
+                }
+            }
+            gcs_storage::validate_credentials(&gcs.credentials_json)
+        }
+        StorageTarget::Webdav(webdav) => {
+            if !webdav.url.starts_with("http://") && !webdav.url.starts_with("https://") {
+                return Err(anyhow!("WebDAV URL must start with http:// or https://"));
+            }
+            if webdav.username.trim().is_empty() {
+                return Err(anyhow!("WebDAV username is required"));
+            }
+            webdav_storage::validate(webdav)
+        }
+    }
+}
+
+/// The target of a task's storage destination, `None` when the task keeps its backups local
+pub async fn task_target(pool: &SqlitePool, task: &Task) -> Result<Option<StorageTarget>> {
+    Ok(task_destination(pool, task).await?.map(|destination| destination.target.0))
+}
+
+/// A task's storage destination, `None` when the task keeps its backups local
+pub async fn task_destination(pool: &SqlitePool, task: &Task) -> Result<Option<StorageDestination>> {
+    let Some(destination_id) = &task.storage_destination_id else {
+        return Ok(None);
+    };
+    let destination = sqlx::query_as("SELECT * FROM storage_destinations WHERE id = ?")
+        .bind(destination_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| anyhow!("Storage destination {} not found", destination_id))?;
+    Ok(Some(destination))
+}
+
+/// Make sure `needed` more bytes fit into the destination's quota before uploading.
+///
+/// Usage is the total size of everything stored at the destination. When the upload would go
+/// over the quota, the task's expired backups are removed first, as the scheduled retention
+/// cleanup would; if that doesn't free enough space, the upload is refused.
+pub async fn ensure_quota(destination: &StorageDestination, task: &Task, needed: u64) -> Result<()> {
+    let Some(quota) = destination.quota_bytes.map(|bytes| bytes as u64) else {
+        return Ok(());
+    };
+    let mut store = RemoteStore::connect(&destination.target).await?;
+    let result = async {
+        let mut used = usage(&mut store).await?;
+        if used + needed > quota {
+            let cutoff = Utc::now() - chrono::Duration::days(task.cleanup_days as i64);
+            let deleted = cleanup_store(&mut store, &task.id, cutoff).await?;
+            if deleted > 0 {
+                info!("Removed {} expired backup(s) of task {} to stay within the quota of {}", deleted, task.name, destination.name);
+                used = usage(&mut store).await?;
+            }
+        }
+        if used + needed > quota {
+            return Err(anyhow!(
+                "Storage destination '{}' is full: {} of its {} quota are used and the backup needs {}",
+                destination.name, format_size(used), format_size(quota), format_size(needed)
+            ));
+        }
+        Ok(())
+    }.await;
+    store.close().await?;
+    result
+}
+
+/// Total size of everything stored at `target`
+pub async fn usage_of(target: &StorageTarget) -> Result<u64> {
+    let mut store = RemoteStore::connect(target).await?;
+    let result = usage(&mut store).await;
+    store.close().await?;
+    result
+}
+
+async fn usage(store: &mut RemoteStore) -> Result<u64> {
+    Ok(store.list().await?.iter().map(|object| object.size).sum())
+}
+
+/// Human-readable size, e.g. `1.5 GiB`
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// The storage destination holding the copy at `remote_location`, with the copy's path there.
+///
+/// Matches the location against each destination's base URL; the most specific one wins
+/// when prefixes overlap.
+pub async fn locate_copy(pool: &SqlitePool, remote_location: &str) -> Result<Option<(StorageTarget, String)>> {
+    let destinations: Vec<StorageDestination> = sqlx::query_as("SELECT * FROM storage_destinations")
+        .fetch_all(pool)
+        .await?;
+
+    let mut best: Option<(usize, StorageTarget, String)> = None;
+    for destination in destinations {
+        let target = destination.target.0;
+        let base = format!("{}/", target.to_string().trim_end_matches('/'));
+        let Some(path) = remote_location.strip_prefix(&base) else {
+            continue;
+        };
+        if best.as_ref().is_some_and(|(len, _, _)| *len >= base.len()) {
+            continue;
+        }
+        // WebDAV locations are URLs with encoded path segments
+        let path = match target {
+            StorageTarget::Webdav(_) => webdav_storage::percent_decode(path),
+            _ => path.to_string(),
+        };
+        best = Some((base.len(), target, path));
+    }
+    Ok(best.map(|(_, target, path)| (target, path)))
+}
+
+/// Size of a file at `target` and, with `checksum`, its SHA-256 read by downloading it
+pub async fn inspect(
+    target: &StorageTarget,
+    path: &str,
+    checksum: bool,
+    progress: Option<watch::Sender<u32>>,
+) -> Result<(u64, Option<String>)> {
+    let mut store = RemoteStore::connect(target).await?;
+    let result = async {
+        if checksum {
+            let (size, sha256_hash) = store.hash(path, progress).await?;
+            Ok((size, Some(sha256_hash)))
+        } else {
+            Ok((store.size(path).await?, None))
+        }
+    }.await;
+    store.close().await?;
+    result
+}
+
+/// Check that `target` accepts uploads by writing a small file and removing it again
+pub async fn test(target: &StorageTarget) -> Result<()> {
+    let mut store = RemoteStore::connect(target).await?;
+    let result = async {
+        store.write(PROBE_FILE, format!("rdumper connection test {}", Utc::now().to_rfc3339()).into_bytes()).await?;
+        store.delete(PROBE_FILE).await
+    }.await;
+    store.close().await?;
+    result
+}
+
+/// Upload a finished backup's archive and metadata to `target` and record where it went.
+///
+/// Objects are named `<prefix>/<backup folder>/<file>`, mirroring the local layout; on GCS the
+/// folder sits below its `<config>-<database>` stem so lifecycle rules can match it. Without
+/// `keep_local` the local archive is removed afterwards; its metadata stays so the backup is
+/// still listed. `throttle` paces the archive upload. Returns the archive's remote URL.
+pub async fn push_backup(
+    target: &StorageTarget,
+    keep_local: bool,
+    backup_service: &FilesystemBackupService,
+    meta_path: &Path,
+    progress: Option<watch::Sender<u32>>,
+    throttle: Option<Throttle>,
+) -> Result<String> {
+    let mut metadata = backup_service.load_backup_metadata(meta_path).await?;
+    let archive = PathBuf::from(&metadata.file_path);
+    let folder = archive.parent().and_then(|p| p.file_name())
+        .ok_or_else(|| anyhow!("Backup has no folder"))?
+        .to_string_lossy()
+        .to_string();
+    let file_name = archive.file_name()
+        .ok_or_else(|| anyhow!("Backup has no archive file"))?
+        .to_string_lossy()
+        .to_string();
+
+    // The metadata goes along, so the destination is usable without this instance
+    let location = match target {
+        StorageTarget::Local => return Err(anyhow!("Local storage has no remote to upload to")),
+        StorageTarget::S3(s3) => {
+            let client = S3Client::new(s3.clone());
+            let key = client.key(&format!("{}/{}", folder, file_name));
+            let sha256_hash = client.upload_file(&key, &archive, progress, throttle.as_ref()).await?;
+            let location = client.url_for(&key);
+            record_upload(&mut metadata, sha256_hash, &location)?;
+            client.put_object(&client.key(&format!("{}/{}", folder, METADATA_FILE)), serde_json::to_vec_pretty(&metadata)?).await?;
+            location
+        }
+        StorageTarget::Sftp(sftp) => {
+            let mut session = SftpSession::connect(sftp).await?;
+            session.mkdir_all(&sftp.remote_file(&folder)).await?;
+            let path = format!("{}/{}", folder, file_name);
+            let sha256_hash = session.upload_file(&sftp.remote_file(&path), &archive, progress, throttle.as_ref()).await?;
+            let location = sftp.url(&path);
+            record_upload(&mut metadata, sha256_hash, &location)?;
+            session.write_file(&sftp.remote_file(&format!("{}/{}", folder, METADATA_FILE)), &serde_json::to_vec_pretty(&metadata)?).await?;
+            session.close().await?;
+            location
+        }
+        StorageTarget::Gcs(gcs) => {
+            let client = GcsClient::connect(gcs.clone()).await?;
+            // Grouping backups by database lets lifecycle rules target one database by prefix
+            let dir = format!("{}/{}", FilesystemBackupService::backup_name_stem(&folder), folder);
+            let name = client.object_name(&format!("{}/{}", dir, file_name));
+            let sha256_hash = client.upload_file(&name, &archive, progress, throttle.as_ref()).await?;
+            let location = client.url_for(&name);
+            record_upload(&mut metadata, sha256_hash, &location)?;
+            client.put_object(&client.object_name(&format!("{}/{}", dir, METADATA_FILE)), serde_json::to_vec_pretty(&metadata)?).await?;
+            location
+        }
+        StorageTarget::Webdav(webdav) => {
+            let client = WebdavClient::new(webdav.clone());
+            client.mkcol(&folder).await?;
+            let path = format!("{}/{}", folder, file_name);
+            let sha256_hash = client.upload_file(&path, &archive, progress, throttle.as_ref()).await?;
+            let location = client.url_for(&path);
+            record_upload(&mut metadata, sha256_hash, &location)?;
+            client.put_bytes(&format!("{}/{}", folder, METADATA_FILE), serde_json::to_vec_pretty(&metadata)?).await?;
+            location
+        }
+    };
+    backup_service.save_backup_metadata(&metadata).await?;
+
+    if !keep_local {
+        tokio::fs::remove_file(&archive).await?;
+        info!("Removed local archive {} after upload", archive.display());
+    }
+    FilesystemBackupService::invalidate_all_caches().await;
+
+    info!("Uploaded backup {} to {}", metadata.id, location);
+    Ok(location)
+}
+
+/// Store the hash computed while uploading and where the archive went
+fn record_upload(metadata: &mut BackupMetadata, sha256_hash: String, location: &str) -> Result<()> {
+    // The upload read every byte, so it doubles as the integrity hash
+    match &metadata.sha256_hash {
+        Some(expected) if *expected != sha256_hash => {
+            return Err(anyhow!("Archive changed while uploading: expected {}, read {}", expected, sha256_hash));
+        }
+        Some(_) => {}
+        None => {
+            metadata.sha256_hash = Some(sha256_hash);
+            metadata.verified_at = Some(Utc::now().to_rfc3339());
+        }
+    }
+    metadata.remote_location = Some(location.to_string());
+    Ok(())
+}
+
+/// Delete the backups of `task_id` that were created before `cutoff` from `target`.
+///
+/// Backup folders are recognised by their metadata file; only folders whose metadata names the
+/// task are touched, so several tasks (or instances) can share a destination. Returns the
+/// number of backups deleted.
+pub async fn cleanup_expired(target: &StorageTarget, task_id: &str, cutoff: DateTime<Utc>) -> Result<u64> {
+    let mut store = RemoteStore::connect(target).await?;
+    let result = cleanup_store(&mut store, task_id, cutoff).await;
+    store.close().await?;
+    result
+}
+
+async fn cleanup_store(store: &mut RemoteStore, task_id: &str, cutoff: DateTime<Utc>) -> Result<u64> {
+    let mut folders: BTreeMap<String, Vec<RemoteObject>> = BTreeMap::new();
+    for object in store.list().await? {
+        let folder = object.path.rsplit_once('/').map(|(folder, _)| folder.to_string()).unwrap_or_default();
+        folders.entry(folder).or_default().push(object);
+    }
+
+    let mut deleted = 0;
+    for (folder, objects) in folders {
+        if !objects.iter().any(|o| o.path.ends_with(&format!("/{}", METADATA_FILE))) {
+            continue;
+        }
+        // Anything written after the cutoff can't belong to an expired backup
+        if objects.iter().any(|o| o.modified.is_some_and(|m| m >= cutoff)) {
+            continue;
+        }
+        let metadata: BackupMetadata = match store.read(&format!("{}/{}", folder, METADATA_FILE)).await
+            .and_then(|content| serde_json::from_slice(&content).map_err(Into::into))
+        {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                warn!("Skipping remote folder {}: unreadable metadata: {}", folder, e);
+                continue;
+            }
+        };
+        let created_at = DateTime::parse_from_rfc3339(&metadata.created_at).map(|c| c.with_timezone(&Utc));
+        if metadata.task_id.as_deref() != Some(task_id) || !created_at.is_ok_and(|c| c < cutoff) {
+            continue;
+        }
+
+        // The metadata goes last, so an interrupted cleanup is picked up again next time
+        let (meta, files): (Vec<_>, Vec<_>) = objects.into_iter().partition(|o| o.path.ends_with(&format!("/{}", METADATA_FILE)));
+        let mut freed = 0;
+        for object in files.iter().chain(meta.iter()) {
+            store.delete(&object.path).await?;
+            freed += object.size;
+        }
+        store.remove_dir(&folder).await?;
+        deleted += 1;
+        info!("Deleted remote backup {} ({}, {} bytes)", metadata.id, folder, freed);
+    }
+    Ok(deleted)
+}
+
+/// A connected client for one destination, for the operations that work the same on all of them
+enum RemoteStore {
+    S3(S3Client),
+    Sftp(SftpTarget, Box<SftpSession>),
+    Gcs(GcsClient),
+    Webdav(WebdavClient),
+}
+
+impl RemoteStore {
+    async fn connect(target: &StorageTarget) -> Result<Self> {
+        Ok(match target {
+            StorageTarget::Local => return Err(anyhow!("Local storage is not a remote destination")),
+            StorageTarget::S3(s3) => Self::S3(S3Client::new(s3.clone())),
+            StorageTarget::Sftp(sftp) => Self::Sftp(sftp.clone(), Box::new(SftpSession::connect(sftp).await?)),
+            StorageTarget::Gcs(gcs) => Self::Gcs(GcsClient::connect(gcs.clone()).await?),
+            StorageTarget::Webdav(webdav) => Self::Webdav(WebdavClient::new(webdav.clone())),
+        })
+    }
+
+    /// Every file at the destination, recursively
+    async fn list(&mut self) -> Result<Vec<RemoteObject>> {
+        match self {
+            Self::S3(client) => client.list_objects().await,
+            Self::Gcs(client) => client.list_objects().await,
+            Self::Webdav(client) => client.list_objects().await,
+            Self::Sftp(target, session) => {
+                let mut objects = Vec::new();
+                let mut pending = vec![String::new()];
+                while let Some(dir) = pending.pop() {
+                    for entry in session.read_dir(&target.remote_file(&dir)).await? {
+                        let path = if dir.is_empty() { entry.name } else { format!("{}/{}", dir, entry.name) };
+                        if entry.is_dir {
+                            pending.push(path);
+                        } else {
+                            objects.push(RemoteObject { path, size: entry.size, modified: entry.modified });
+                        }
+                    }
+                }
+                Ok(objects)
+            }
+        }
+    }
+
+    async fn write(&mut self, path: &str, content: Vec<u8>) -> Result<()> {
+        match self {
+            Self::S3(client) => client.put_object(&client.key(path), content).await,
+            Self::Gcs(client) => client.put_object(&client.object_name(path), content).await,
+            Self::Webdav(client) => client.put_bytes(path, content).await,
+            Self::Sftp(target, session) => {
+                session.mkdir_all(&target.remote_file("")).await?;
+                session.write_file(&target.remote_file(path), &content).await
+            }
+        }
+    }
+
+    async fn read(&mut self, path: &str) -> Result<Vec<u8>> {
+        match self {
+            Self::S3(client) => client.get_object(&client.key(path)).await,
+            Self::Gcs(client) => client.get_object(&client.object_name(path)).await,
+            Self::Webdav(client) => client.get(path).await,
+            Self::Sftp(target, session) => session.read_file(&target.remote_file(path)).await,
+        }
+    }
+
+    async fn size(&mut self, path: &str) -> Result<u64> {
+        match self {
+            Self::S3(client) => client.object_size(&client.key(path)).await,
+            Self::Gcs(client) => client.object_size(&client.object_name(path)).await,
+            Self::Webdav(client) => client.size(path).await,
+            Self::Sftp(target, session) => Ok(session.stat(&target.remote_file(path)).await?.0),
+        }
+    }
+
+    /// Download a file without holding it in memory, returning its size and SHA-256
+    async fn hash(&mut self, path: &str, progress: Option<watch::Sender<u32>>) -> Result<(u64, String)> {
+        let response = match self {
+            Self::S3(client) => client.open_object(&client.key(path)).await?,
+            Self::Gcs(client) => client.open_object(&client.object_name(path)).await?,
+            Self::Webdav(client) => client.open(path).await?,
+            Self::Sftp(target, session) => return session.hash_file(&target.remote_file(path), progress).await,
+        };
+
+        let total = response.content_length().unwrap_or(0).max(1);
+        let mut hasher = Sha256::new();
+        let mut read = 0u64;
+        let mut body = response.bytes_stream();
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk?;
+            hasher.update(&chunk);
+            read += chunk.len() as u64;
+            if let Some(tx) = &progress {
+                let _ = tx.send((read * 100 / total).min(100) as u32);
+            }
+        }
+        Ok((read, hex::encode(hasher.finalize())))
+    }
+
+    async fn delete(&mut self, path: &str) -> Result<()> {
+        match self {
+            Self::S3(client) => client.delete_object(&client.key(path)).await,
+            Self::Gcs(client) => client.delete_object(&client.object_name(path)).await,
+            Self::Webdav(client) => client.delete(path).await,
+            Self::Sftp(target, session) => session.remove(&target.remote_file(path)).await,
+        }
+    }
+
+    /// Remove a folder emptied by `delete`; object stores have no folders to remove
+    async fn remove_dir(&mut self, path: &str) -> Result<()> {
+        match self {
+            Self::S3(_) | Self::Gcs(_) => Ok(()),
+            Self::Webdav(client) => client.delete(path).await,
+            Self::Sftp(target, session) => session.rmdir(&target.remote_file(path)).await,
+        }
+    }
+
+    async fn close(self) -> Result<()> {
+        match self {
+            Self::Sftp(_, session) => (*session).close().await,
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use std::path::Path;
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+use crate::models::{Backup, BackupMetadata, CreateJobRequest, Job, JobType, LogLevel, RemoteVerification, RemoteVerifyMode, StorageTarget};
+use crate::services::{mydumper, remote_storage, FilesystemBackupService, LoggingService};
+use crate::services::events::{self, AppEvent};
+
+/// What the destination reported for the remote copy
+struct RemoteCopy {
+    size: u64,
+    sha256_hash: Option<String>,
+}
+
+/// Create a verify-remote job for `backup` and run it in the background; returns the job id.
+///
+/// `target` and `path` locate the uploaded archive, see `remote_storage::locate_copy`.
+pub async fn start_remote_verify_job(
+    pool: &SqlitePool,
+    backup_service: &FilesystemBackupService,
+    backup: Backup,
+    target: StorageTarget,
+    path: String,
+    mode: RemoteVerifyMode,
+) -> Result<String> {
+    let job = Job::new(CreateJobRequest {
+        task_id: backup.task_id.clone(),
+        used_database: backup.used_database.clone(),
+        job_type: JobType::VerifyRemote,
+        backup_path: Some(backup.file_path.clone()),
+    });
+
+    sqlx::query(
+        r#"
+        INSERT INTO jobs (id, task_id, used_database, job_type, status, progress, started_at, completed_at, error_message, log_output, backup_path, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&job.id)
+    .bind(&job.task_id)
+    .bind(&job.used_database)
+    .bind(&job.job_type)
+    .bind(&job.status)
+    .bind(job.progress)
+    .bind(job.started_at)
+    .bind(job.completed_at)
+    .bind(&job.error_message)
+    .bind(&job.log_output)
+    .bind(&job.backup_path)
+    .bind(job.created_at)
+    .execute(pool)
+    .await?;
+
+    let pool = pool.clone();
+    let backup_service = backup_service.clone();
+    let job_id = job.id.clone();
+    tokio::spawn(async move {
+        run_remote_verify_job(&pool, &backup_service, &backup, &target, &path, &job_id, mode).await;
+    });
+
+    Ok(job.id)
+}
+
+async fn run_remote_verify_job(
+    pool: &SqlitePool,
+    backup_service: &FilesystemBackupService,
+    backup: &Backup,
+    target: &StorageTarget,
+    path: &str,
+    job_id: &str,
+    mode: RemoteVerifyMode,
+) {
+    let logging_service = LoggingService::new(Arc::new(pool.clone()));
+    let location = backup.remote_location.clone().unwrap_or_default();
+
+    let _ = sqlx::query("UPDATE jobs SET status = ?, started_at = ? WHERE id = ?")
+        .bind("running")
+        .bind(Utc::now())
+        .bind(job_id)
+        .execute(pool)
+        .await;
+    events::publish(AppEvent::job_status(job_id, "running"));
+    let _ = logging_service.log_job(job_id, &format!("Checking remote copy {} of backup {}", location, backup.id), LogLevel::Info).await;
+
+    let checksum = mode == RemoteVerifyMode::Checksum;
+    let (progress_tx, progress_task) = mydumper::mirror_progress(pool, job_id, "progress");
+    let copy = remote_storage::inspect(target, path, checksum, checksum.then_some(progress_tx)).await
+        .map(|(size, sha256_hash)| RemoteCopy { size, sha256_hash });
+    let _ = progress_task.await;
+
+    let result = match backup_service.load_backup_metadata(Path::new(&backup.meta_path)).await {
+        Ok(mut metadata) => {
+            let verification = compare(&metadata, job_id, mode, &location, copy);
+            let outcome = if !verification.divergences.is_empty() {
+                Err(anyhow!("Remote copy differs: {}", verification.divergences.join("; ")))
+            } else if let Some(e) = &verification.error {
+                Err(anyhow!("Remote copy could not be checked: {}", e))
+            } else {
+                Ok(())
+            };
+            metadata.last_remote_verification = Some(verification);
+            if let Err(e) = backup_service.save_backup_metadata(&metadata).await {
+                warn!("Failed to record remote verification result for backup {}: {}", backup.id, e);
+            }
+            outcome
+        }
+        Err(e) => Err(anyhow!("Failed to load backup metadata: {}", e)),
+    };
+
+    let status = match &result {
+        Ok(()) => {
+            info!("Remote copy of backup {} verified", backup.id);
+            let _ = logging_service.log_job(job_id, &format!("Remote copy {} matches the backup", location), LogLevel::Info).await;
+            let _ = sqlx::query("UPDATE jobs SET status = ?, progress = ?, completed_at = ? WHERE id = ?")
+                .bind("completed")
+                .bind(100)
+                .bind(Utc::now())
+                .bind(job_id)
+                .execute(pool)
+                .await;
+            "completed"
+        }
+        Err(e) => {
+            warn!("Remote verification of backup {} failed: {}", backup.id, e);
+            let _ = logging_service.log_job(job_id, &format!("Remote verification failed: {}", e), LogLevel::Error).await;
+            let _ = sqlx::query("UPDATE jobs SET status = ?, error_message = ?, completed_at = ? WHERE id = ?")
+                .bind("failed")
+                .bind(e.to_string())
+                .bind(Utc::now())
+                .bind(job_id)
+                .execute(pool)
+                .await;
+            "failed"
+        }
+    };
+    events::publish(AppEvent::job_status(job_id, status));
+}
+
+/// Compare what the destination reported against the size and hash recorded in the metadata
+fn compare(
+    metadata: &BackupMetadata,
+    job_id: &str,
+    mode: RemoteVerifyMode,
+    location: &str,
+    copy: Result<RemoteCopy>,
+) -> RemoteVerification {
+    let mut verification = RemoteVerification {
+        job_id: job_id.to_string(),
+        checked_at: Utc::now().to_rfc3339(),
+        mode,
+        remote_location: location.to_string(),
+        passed: false,
+        remote_size: None,
+        remote_sha256: None,
+        divergences: Vec::new(),
+        error: None,
+    };
+    let copy = match copy {
+        Ok(copy) => copy,
+        Err(e) => {
+            verification.error = Some(e.to_string());
+            return verification;
+        }
+    };
+
+    if copy.size != metadata.file_size as u64 {
+        verification.divergences.push(format!("size is {} bytes, expected {}", copy.size, metadata.file_size));
+    }
+    if let Some(remote) = &copy.sha256_hash {
+        // Older backups only carry the hash in their ident
+        let expected = metadata.sha256_hash.as_ref()
+            .or(metadata.ident.as_ref().filter(|ident| ident.len() == 64 && ident.chars().all(|c| c.is_ascii_hexdigit())));
+        match expected {
+            Some(expected) if expected != remote => {
+                verification.divergences.push(format!("SHA-256 is {}, expected {}", remote, expected));
+            }
+            Some(_) => {}
+            None => verification.error = Some("No SHA-256 is recorded for the backup to compare against".to_string()),
+        }
+    }
+
+    verification.passed = verification.divergences.is_empty() && verification.error.is_none();
+    verification.remote_size = Some(copy.size);
+    verification.remote_sha256 = copy.sha256_hash;
+    verification
+}
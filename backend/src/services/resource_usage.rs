@@ -0,0 +1,82 @@
+use std::process::ExitStatus;
+use std::time::Duration;
+use sqlx::SqlitePool;
+use tokio::process::Child;
+
+/// How often a running child's counters are read
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Clock ticks per second of the CPU times in /proc, USER_HZ, which Linux fixes at 100
+const CLOCK_TICKS: f64 = 100.0;
+
+/// What a child process consumed, as last read from /proc before it exited
+#[derive(Debug, Clone)]
+pub struct ResourceUsage {
+    /// User and system CPU time of all its threads
+    pub cpu_seconds: f64,
+    /// Highest resident set size it reached
+    pub peak_rss_bytes: i64,
+    /// Bytes it caused to be written to storage
+    pub bytes_written: i64,
+}
+
+impl ResourceUsage {
+    /// Read the counters of a running process, None once it is gone or without /proc
+    pub async fn read(pid: u32) -> Option<Self> {
+        let stat = tokio::fs::read_to_string(format!("/proc/{}/stat", pid)).await.ok()?;
+        // The command name in parentheses may contain spaces; the fields after it are fixed
+        let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+        let ticks: u64 = fields.get(11)?.parse::<u64>().ok()? + fields.get(12)?.parse::<u64>().ok()?;
+
+        let status = tokio::fs::read_to_string(format!("/proc/{}/status", pid)).await.ok()?;
+        let peak_rss_kib: i64 = status.lines()
+            .find_map(|line| line.strip_prefix("VmHWM:"))
+            .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+            .unwrap_or(0);
+
+        // Needs the same user as the child; without it only CPU and memory are known
+        let bytes_written = tokio::fs::read_to_string(format!("/proc/{}/io", pid)).await.ok()
+            .and_then(|io| io.lines()
+                .find_map(|line| line.strip_prefix("write_bytes:"))
+                .and_then(|value| value.trim().parse().ok()))
+            .unwrap_or(0);
+
+        Some(Self {
+            cpu_seconds: ticks as f64 / CLOCK_TICKS,
+            peak_rss_bytes: peak_rss_kib * 1024,
+            bytes_written,
+        })
+    }
+
+    /// Store the usage on a job
+    pub async fn record(&self, pool: &SqlitePool, job_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE jobs SET cpu_seconds = ?, peak_rss_bytes = ?, bytes_written = ? WHERE id = ?")
+            .bind(self.cpu_seconds)
+            .bind(self.peak_rss_bytes)
+            .bind(self.bytes_written)
+            .bind(job_id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Wait for a child to exit while sampling its resource usage into `usage`, which keeps the
+/// last sample even when the wait is abandoned, e.g. by a timeout
+pub async fn wait_measured(child: &mut Child, usage: &mut Option<ResourceUsage>) -> std::io::Result<ExitStatus> {
+    let Some(pid) = child.id() else {
+        return child.wait().await;
+    };
+
+    let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+    loop {
+        tokio::select! {
+            status = child.wait() => return status,
+            _ = interval.tick() => {
+                if let Some(sample) = ResourceUsage::read(pid).await {
+                    *usage = Some(sample);
+                }
+            }
+        }
+    }
+}
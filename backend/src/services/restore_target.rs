@@ -0,0 +1,136 @@
+use anyhow::Result;
+use sqlx::MySqlPool;
+use tracing::info;
+use crate::models::DatabaseConfig;
+use crate::services::{demo, mysql_pool};
+
+/// Version string of the server behind `config`, e.g. `8.0.36` or `10.11.6-MariaDB`
+pub async fn server_version(config: &DatabaseConfig) -> Result<String> {
+    if demo::is_enabled() {
+        return Ok(demo::SERVER_VERSION.to_string());
+    }
+    let pool = mysql_pool::shared_pool(&config.connection_string_with_db("")).await?;
+    Ok(sqlx::query_scalar("SELECT VERSION()").fetch_one(&pool).await?)
+}
+
+/// Whether the user may create databases, tried with a throwaway one
+pub async fn can_create_databases(pool: &MySqlPool) -> bool {
+    let probe = probe_name();
+    let created = sqlx::query(&format!("CREATE DATABASE IF NOT EXISTS `{}`", probe))
+        .execute(pool)
+        .await
+        .is_ok();
+    if created {
+        let _ = sqlx::query(&format!("DROP DATABASE IF EXISTS `{}`", probe)).execute(pool).await;
+    }
+    created
+}
+
+/// Whether the user may create tables in `database`, tried with a throwaway one
+pub async fn can_create_tables(pool: &MySqlPool, database: &str) -> bool {
+    let probe = probe_name();
+    let created = sqlx::query(&format!("CREATE TABLE IF NOT EXISTS `{}`.`{}` (id INT PRIMARY KEY)", database, probe))
+        .execute(pool)
+        .await
+        .is_ok();
+    if created {
+        let _ = sqlx::query(&format!("DROP TABLE IF EXISTS `{}`.`{}`", database, probe)).execute(pool).await;
+    }
+    created
+}
+
+/// Drop `database` on the server behind `config`, e.g. what a cancelled restore left behind
+pub async fn drop_database(config: &DatabaseConfig, database: &str) -> Result<()> {
+    if demo::is_enabled() {
+        info!("Demo mode: dropping database {}", database);
+        return Ok(());
+    }
+    let pool = mysql_pool::shared_pool(&config.connection_string_with_db("")).await?;
+    sqlx::query(&format!("DROP DATABASE IF EXISTS `{}`", database.replace('`', "``")))
+        .execute(&pool)
+        .await?;
+    info!("Dropped database {} on {}", database, config.name);
+    Ok(())
+}
+
+fn probe_name() -> String {
+    format!("rdumper_test_{}", &uuid::Uuid::new_v4().simple().to_string()[..8])
+}
+
+/// Check that `target` can take a restore of a backup taken from a server running `source_version`.
+///
+/// The target must run the same server flavor at the same or a newer version, and the user
+/// must be able to create `database`, or tables in it when it already exists.
+pub async fn check(target: &DatabaseConfig, source_version: Option<&str>, database: Option<&str>) -> Result<String, String> {
+    let target_version = server_version(target).await
+        .map_err(|e| format!("Can't connect to target server {}: {}", target.name, e))?;
+
+    if let Some(source_version) = source_version {
+        let (source, target_parsed) = (ServerVersion::parse(source_version), ServerVersion::parse(&target_version));
+        if source.mariadb != target_parsed.mariadb {
+            return Err(format!(
+                "Backup was taken from {} {} but {} runs {} {}; restores need the same server flavor",
+                source.flavor(), source_version, target.name, target_parsed.flavor(), target_version
+            ));
+        }
+        if target_parsed.release < source.release {
+            return Err(format!(
+                "{} runs {} {}, older than the backup's source {}; restore into the same or a newer version",
+                target.name, target_parsed.flavor(), target_version, source_version
+            ));
+        }
+    }
+
+    if demo::is_enabled() {
+        return Ok(target_version);
+    }
+    let pool = mysql_pool::shared_pool(&target.connection_string_with_db(""))
+        .await
+        .map_err(|e| format!("Can't connect to target server {}: {}", target.name, e))?;
+    let existing: Vec<String> = sqlx::query_scalar("SHOW DATABASES")
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let allowed = match database {
+        Some(database) if existing.iter().any(|db| db == database) => can_create_tables(&pool, database).await,
+        _ => can_create_databases(&pool).await,
+    };
+    if !allowed {
+        return Err(format!(
+            "User {} lacks the privileges to restore on {}{}",
+            target.username,
+            target.name,
+            database.map(|db| format!(" into '{}'", db)).unwrap_or_default()
+        ));
+    }
+
+    info!("Restore target {} runs {}", target.name, target_version);
+    Ok(target_version)
+}
+
+/// Major, minor and patch release of a server plus its flavor
+struct ServerVersion {
+    release: (u32, u32, u32),
+    mariadb: bool,
+}
+
+impl ServerVersion {
+    fn parse(version: &str) -> Self {
+        // MariaDB 10 used to report itself as "5.5.5-10.x.y-MariaDB" to old clients
+        let numbers = version.strip_prefix("5.5.5-").unwrap_or(version);
+        let mut parts = numbers
+            .split(|c: char| !c.is_ascii_digit() && c != '.')
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0));
+        Self {
+            release: (parts.next().unwrap_or(0), parts.next().unwrap_or(0), parts.next().unwrap_or(0)),
+            mariadb: version.to_lowercase().contains("mariadb"),
+        }
+    }
+
+    fn flavor(&self) -> &'static str {
+        if self.mariadb { "MariaDB" } else { "MySQL" }
+    }
+}
@@ -0,0 +1,344 @@
+use std::path::Path;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::{Method, StatusCode};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncReadExt;
+use tokio::sync::watch;
+use crate::models::S3Target;
+use crate::services::bandwidth::{self, Throttle};
+use crate::services::remote_storage::RemoteObject;
+
+/// Archives up to this size go up in a single PUT; larger ones as multipart uploads with parts of this size
+const PART_SIZE: usize = 16 * 1024 * 1024;
+
+/// Headers covered by every request signature
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+/// Minimal S3 client for the handful of calls rdumper needs, signed with AWS Signature Version 4
+pub struct S3Client {
+    target: S3Target,
+    http: reqwest::Client,
+}
+
+impl S3Client {
+    pub fn new(target: S3Target) -> Self {
+        Self { target, http: reqwest::Client::new() }
+    }
+
+    /// Full object key for a path below the configured prefix
+    pub fn key(&self, path: &str) -> String {
+        let prefix = self.target.prefix.trim_matches('/');
+        if prefix.is_empty() {
+            path.to_string()
+        } else {
+            format!("{}/{}", prefix, path)
+        }
+    }
+
+    pub fn url_for(&self, key: &str) -> String {
+        format!("s3://{}/{}", self.target.bucket, key)
+    }
+
+    /// Upload a file, as multipart upload when it is larger than one part; returns the file's SHA-256
+    pub async fn upload_file(&self, key: &str, path: &Path, progress: Option<watch::Sender<u32>>, throttle: Option<&Throttle>) -> Result<String> {
+        let total = tokio::fs::metadata(path).await?.len();
+        let mut file = tokio::fs::File::open(path).await?;
+        let mut hasher = Sha256::new();
+
+        if total <= PART_SIZE as u64 {
+            let mut body = Vec::with_capacity(total as usize);
+            file.read_to_end(&mut body).await?;
+            hasher.update(&body);
+            self.send_paced(Method::PUT, key, &[], body, throttle).await?;
+            if let Some(tx) = &progress {
+                let _ = tx.send(100);
+            }
+            return Ok(hex::encode(hasher.finalize()));
+        }
+
+        let upload_id = self.create_multipart_upload(key).await?;
+        let result = async {
+            let mut etags = Vec::new();
+            let mut sent: u64 = 0;
+            loop {
+                let part = read_part(&mut file).await?;
+                if part.is_empty() {
+                    break;
+                }
+                hasher.update(&part);
+                sent += part.len() as u64;
+                let part_number = (etags.len() + 1).to_string();
+                let response = self.send_paced(
+                    Method::PUT,
+                    key,
+                    &[("partNumber", part_number.as_str()), ("uploadId", upload_id.as_str())],
+                    part,
+                    throttle,
+                ).await?;
+                let etag = response.headers().get("etag")
+                    .and_then(|v| v.to_str().ok())
+                    .ok_or_else(|| anyhow!("S3 returned no ETag for part {}", part_number))?
+                    .to_string();
+                etags.push(etag);
+                if let Some(tx) = &progress {
+                    let _ = tx.send((sent * 100 / total.max(1)) as u32);
+                }
+            }
+            self.complete_multipart_upload(key, &upload_id, &etags).await
+        }.await;
+
+        if let Err(e) = result {
+            // Drop the uploaded parts so they don't keep costing storage
+            let _ = self.send(Method::DELETE, key, &[("uploadId", upload_id.as_str())], Vec::new()).await;
+            return Err(e);
+        }
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    pub async fn put_object(&self, key: &str, body: Vec<u8>) -> Result<()> {
+        self.send(Method::PUT, key, &[], body).await?;
+        Ok(())
+    }
+
+    pub async fn get_object(&self, key: &str) -> Result<Vec<u8>> {
+        Ok(self.open_object(key).await?.bytes().await?.to_vec())
+    }
+
+    /// Start downloading an object; the body is streamed from the response
+    pub async fn open_object(&self, key: &str) -> Result<reqwest::Response> {
+        self.send(Method::GET, key, &[], Vec::new()).await
+    }
+
+    /// Size of an object from a HEAD request
+    pub async fn object_size(&self, key: &str) -> Result<u64> {
+        let response = self.send(Method::HEAD, key, &[], Vec::new()).await?;
+        content_length(&response).ok_or_else(|| anyhow!("S3 sent no size for {}", key))
+    }
+
+    pub async fn delete_object(&self, key: &str) -> Result<()> {
+        self.send(Method::DELETE, key, &[], Vec::new()).await?;
+        Ok(())
+    }
+
+    /// Every object below the configured prefix, with paths relative to it
+    pub async fn list_objects(&self) -> Result<Vec<RemoteObject>> {
+        let prefix = self.key("");
+        let mut objects = Vec::new();
+        let mut continuation: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", prefix.as_str())];
+            if let Some(token) = &continuation {
+                query.push(("continuation-token", token.as_str()));
+            }
+            let body = self.send(Method::GET, "", &query, Vec::new()).await?.text().await?;
+            for entry in body.split("<Contents>").skip(1) {
+                let key = xml_value(entry, "Key").map(|k| xml_unescape(&k)).unwrap_or_default();
+                objects.push(RemoteObject {
+                    path: key.strip_prefix(&prefix).unwrap_or(&key).to_string(),
+                    size: xml_value(entry, "Size").and_then(|s| s.parse().ok()).unwrap_or(0),
+                    modified: xml_value(entry, "LastModified")
+                        .and_then(|m| DateTime::parse_from_rfc3339(&m).ok())
+                        .map(|m| m.with_timezone(&Utc)),
+                });
+            }
+            continuation = match xml_value(&body, "IsTruncated").as_deref() {
+                Some("true") => xml_value(&body, "NextContinuationToken").map(|t| xml_unescape(&t)),
+                _ => None,
+            };
+            if continuation.is_none() {
+                return Ok(objects);
+            }
+        }
+    }
+
+    async fn create_multipart_upload(&self, key: &str) -> Result<String> {
+        let response = self.send(Method::POST, key, &[("uploads", "")], Vec::new()).await?;
+        let body = response.text().await?;
+        xml_value(&body, "UploadId").ok_or_else(|| anyhow!("S3 returned no UploadId"))
+    }
+
+    async fn complete_multipart_upload(&self, key: &str, upload_id: &str, etags: &[String]) -> Result<()> {
+        let parts: String = etags.iter().enumerate()
+            .map(|(i, etag)| format!("<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>", i + 1, etag))
+            .collect();
+        let body = format!("<CompleteMultipartUpload>{}</CompleteMultipartUpload>", parts);
+        let response = self.send(Method::POST, key, &[("uploadId", upload_id)], body.into_bytes()).await?;
+        // Completion can fail after the 200 status line has been sent
+        let text = response.text().await?;
+        if text.contains("<Error>") {
+            return Err(anyhow!("S3 rejected the multipart upload: {}", xml_value(&text, "Message").unwrap_or(text)));
+        }
+        Ok(())
+    }
+
+    /// Send a signed request and fail on non-success status codes
+    async fn send(&self, method: Method, key: &str, query: &[(&str, &str)], body: Vec<u8>) -> Result<reqwest::Response> {
+        self.send_paced(method, key, query, body, None).await
+    }
+
+    /// `send` with the body released at the throttle's pace
+    async fn send_paced(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+        throttle: Option<&Throttle>,
+    ) -> Result<reqwest::Response> {
+        let (base, host, path) = self.location(key)?;
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+
+        let canonical_query = canonical_query(query);
+        let canonical_request = canonical_request(method.as_str(), &path, &canonical_query, &host, &payload_hash, &amz_date);
+        let scope = format!("{}/{}/s3/aws4_request", date, self.target.region);
+        let signing_key = signing_key(&self.target.secret_access_key, &date, &self.target.region, "s3");
+        let signature = signature(&signing_key, &amz_date, &scope, &canonical_request);
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.target.access_key_id, scope, SIGNED_HEADERS, signature
+        );
+
+        let url = if canonical_query.is_empty() {
+            format!("{}{}", base, path)
+        } else {
+            format!("{}{}?{}", base, path, canonical_query)
+        };
+        let mut request = self.http.request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("authorization", authorization);
+        if throttle.is_some() {
+            request = request.header("content-length", body.len());
+        }
+        let response = request.body(bandwidth::body(throttle, body)).send().await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            let message = xml_value(&text, "Message").or_else(|| xml_value(&text, "Code")).unwrap_or_default();
+            return Err(match status {
+                StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED => anyhow!("S3 denied access ({}): {}", status, message),
+                StatusCode::NOT_FOUND => anyhow!("S3 bucket or object not found: {}", message),
+                _ => anyhow!("S3 request failed ({}): {}", status, message),
+            });
+        }
+        Ok(response)
+    }
+
+    /// Base URL, Host header and canonical path for an object, honouring path-style addressing
+    fn location(&self, key: &str) -> Result<(String, String, String)> {
+        let endpoint = match &self.target.endpoint {
+            Some(endpoint) => endpoint.trim_end_matches('/').to_string(),
+            None => format!("https://s3.{}.amazonaws.com", self.target.region),
+        };
+        let (scheme, host) = endpoint.split_once("://")
+            .ok_or_else(|| anyhow!("S3 endpoint must start with http:// or https://"))?;
+        let key_path = uri_encode(key, false);
+        if self.target.path_style {
+            // Bucket-level requests (listing) address the bucket itself
+            let path = if key.is_empty() { format!("/{}", self.target.bucket) } else { format!("/{}/{}", self.target.bucket, key_path) };
+            Ok((format!("{}://{}", scheme, host), host.to_string(), path))
+        } else {
+            let host = format!("{}.{}", self.target.bucket, host);
+            Ok((format!("{}://{}", scheme, host), host, format!("/{}", key_path)))
+        }
+    }
+}
+
+/// The Content-Length header; `Response::content_length` reports the body, which HEAD doesn't have
+pub(crate) fn content_length(response: &reqwest::Response) -> Option<u64> {
+    response.headers().get(reqwest::header::CONTENT_LENGTH)?.to_str().ok()?.parse().ok()
+}
+
+/// Fill a buffer with up to one part, reading until the part is full or the file ends
+async fn read_part(file: &mut tokio::fs::File) -> Result<Vec<u8>> {
+    let mut part = vec![0u8; PART_SIZE];
+    let mut filled = 0;
+    while filled < PART_SIZE {
+        let read = file.read(&mut part[filled..]).await?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+    }
+    part.truncate(filled);
+    Ok(part)
+}
+
+/// Query string with keys and values encoded and sorted, as SigV4 expects it
+pub(crate) fn canonical_query(query: &[(&str, &str)]) -> String {
+    let mut sorted_query: Vec<(String, String)> = query.iter()
+        .map(|(k, v)| (uri_encode(k, true), uri_encode(v, true)))
+        .collect();
+    sorted_query.sort();
+    sorted_query.iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Canonical request over the headers in `SIGNED_HEADERS`
+pub(crate) fn canonical_request(method: &str, path: &str, canonical_query: &str, host: &str, payload_hash: &str, amz_date: &str) -> String {
+    format!(
+        "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+        method, path, canonical_query, host, payload_hash, amz_date, SIGNED_HEADERS, payload_hash
+    )
+}
+
+/// Key derived from the secret for one day, region and service
+pub(crate) fn signing_key(secret_access_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let mut key = hmac(format!("AWS4{}", secret_access_key).as_bytes(), date.as_bytes());
+    for part in [region, service, "aws4_request"] {
+        key = hmac(&key, part.as_bytes());
+    }
+    key
+}
+
+/// Hex signature of a canonical request within `scope`
+pub(crate) fn signature(signing_key: &[u8], amz_date: &str, scope: &str, canonical_request: &str) -> String {
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date, scope, hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+    hex::encode(hmac(signing_key, string_to_sign.as_bytes()))
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encode per SigV4 rules; `/` stays literal in object paths
+pub(crate) fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Text of the first `<tag>` element; S3 responses are small enough that a full XML parser isn't needed
+fn xml_value(xml: &str, tag: &str) -> Option<String> {
+    let start = xml.find(&format!("<{}>", tag))? + tag.len() + 2;
+    let end = start + xml[start..].find(&format!("</{}>", tag))?;
+    Some(xml[start..end].to_string())
+}
+
+/// Undo the entity escaping S3 applies to keys in listings
+fn xml_unescape(value: &str) -> String {
+    value.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
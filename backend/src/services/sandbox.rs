@@ -0,0 +1,186 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use sqlx::mysql::{MySqlPool, MySqlPoolOptions};
+use tokio::process::Command as TokioCommand;
+use tracing::{info, warn};
+use crate::models::{ArchiveEntryKind, Backup, DatabaseConfig, RestoreRequest, SandboxReport, SandboxTable};
+use crate::services::{FilesystemBackupService, MydumperService};
+
+/// How long a fresh MySQL container may take to accept connections
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(180);
+/// Database the backup is restored into inside the sandbox
+const SANDBOX_DATABASE: &str = "rdumper_sandbox";
+
+/// Where to start sandbox containers and how to reach them
+#[derive(Debug, Clone)]
+pub struct SandboxSettings {
+    pub image: String,
+    /// Host the published MySQL port is reachable on, e.g. `host.docker.internal` when rdumper itself runs in Docker
+    pub host: String,
+}
+
+/// Restore `backup` into a disposable MySQL container, query the result and remove the container again
+pub async fn sandbox_restore(
+    settings: &SandboxSettings,
+    backup_service: &FilesystemBackupService,
+    mydumper_service: &MydumperService,
+    backup: &Backup,
+) -> Result<SandboxReport> {
+    let started = Instant::now();
+    let name = format!("rdumper-sandbox-{}", &uuid::Uuid::new_v4().to_string()[..8]);
+    let password: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(24)
+        .map(char::from)
+        .collect();
+
+    docker(&[
+        "run", "-d", "--rm",
+        "--name", &name,
+        "-e", &format!("MYSQL_ROOT_PASSWORD={}", password),
+        "-p", "3306",
+        &settings.image,
+    ]).await?;
+    info!("Started sandbox container {} ({})", name, settings.image);
+
+    let result = restore_and_inspect(settings, backup_service, mydumper_service, backup, &name, &password).await;
+
+    // Always tear the container down, whatever happened inside
+    if let Err(e) = docker(&["rm", "-f", &name]).await {
+        warn!("Failed to remove sandbox container {}: {}", name, e);
+    }
+
+    let tables = result?;
+    Ok(SandboxReport {
+        image: settings.image.clone(),
+        total_rows: tables.iter().map(|t| t.rows).sum(),
+        tables,
+        duration_secs: started.elapsed().as_secs() as i64,
+    })
+}
+
+async fn restore_and_inspect(
+    settings: &SandboxSettings,
+    backup_service: &FilesystemBackupService,
+    mydumper_service: &MydumperService,
+    backup: &Backup,
+    container: &str,
+    password: &str,
+) -> Result<Vec<SandboxTable>> {
+    let port = published_port(container).await?;
+    let pool = wait_until_ready(&settings.host, port, password).await?;
+
+    let config = DatabaseConfig {
+        id: container.to_string(),
+        name: container.to_string(),
+        host: settings.host.clone(),
+        port: port as i32,
+        username: "root".to_string(),
+        password: password.to_string(),
+        database_name: SANDBOX_DATABASE.to_string(),
+        connection_status: "success".to_string(),
+        last_tested: None,
+        rpo_hours: None,
+        agent_id: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+
+    // Restores extract next to the archive; only clean up what this run created
+    let extract_dir = Path::new(&backup.file_path).with_extension("");
+    let extracted_before = extract_dir.exists();
+    let restored = mydumper_service.restore_backup(&config, &backup.file_path, &RestoreRequest {
+        new_database_name: Some(SANDBOX_DATABASE.to_string()),
+        ..Default::default()
+    }).await;
+    if !extracted_before && extract_dir.is_dir() {
+        let _ = tokio::fs::remove_dir_all(&extract_dir).await;
+    }
+    restored?;
+
+    let table_names: Vec<(String,)> = sqlx::query_as(
+        "SELECT CAST(table_name AS CHAR) FROM information_schema.tables WHERE table_schema = ? ORDER BY table_name"
+    )
+    .bind(SANDBOX_DATABASE)
+    .fetch_all(&pool)
+    .await?;
+    if table_names.is_empty() {
+        return Err(anyhow!("Restore finished but the sandbox database has no tables"));
+    }
+
+    let mut tables = Vec::with_capacity(table_names.len());
+    for (name,) in table_names {
+        let (rows,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM `{}`.`{}`", SANDBOX_DATABASE, name))
+            .fetch_one(&pool)
+            .await
+            .map_err(|e| anyhow!("Failed to read table {}: {}", name, e))?;
+        tables.push(SandboxTable { name, rows });
+    }
+    pool.close().await;
+
+    // Every table with a schema file in the archive has to exist after the restore
+    let restored_names: BTreeSet<&str> = tables.iter().map(|t| t.name.as_str()).collect();
+    let missing: Vec<String> = backup_service.list_archive_contents(backup).await?
+        .into_iter()
+        .filter(|entry| entry.kind == ArchiveEntryKind::TableSchema)
+        .filter_map(|entry| entry.table_name)
+        .filter(|table| !restored_names.contains(table.as_str()))
+        .collect();
+    if !missing.is_empty() {
+        return Err(anyhow!("Restored database is missing tables: {}", missing.join(", ")));
+    }
+
+    Ok(tables)
+}
+
+/// Host port Docker published for the container's MySQL port
+async fn published_port(container: &str) -> Result<u16> {
+    let output = docker(&["port", container, "3306/tcp"]).await?;
+    // One line per address family, e.g. "0.0.0.0:49153"
+    output.lines()
+        .filter_map(|line| line.rsplit(':').next())
+        .find_map(|port| port.trim().parse().ok())
+        .ok_or_else(|| anyhow!("Could not determine sandbox port from '{}'", output.trim()))
+}
+
+/// Poll until the server accepts connections; the image restarts mysqld once during initialisation
+async fn wait_until_ready(host: &str, port: u16, password: &str) -> Result<MySqlPool> {
+    let url = format!("mysql://root:{}@{}:{}/", password, host, port);
+    let deadline = Instant::now() + STARTUP_TIMEOUT;
+    loop {
+        let attempt = MySqlPoolOptions::new()
+            .max_connections(1)
+            .acquire_timeout(Duration::from_secs(5))
+            .connect(&url)
+            .await;
+        match attempt {
+            Ok(pool) => return Ok(pool),
+            Err(e) if Instant::now() >= deadline => {
+                return Err(anyhow!("Sandbox MySQL did not become ready within {}s: {}", STARTUP_TIMEOUT.as_secs(), e));
+            }
+            Err(_) => tokio::time::sleep(Duration::from_secs(2)).await,
+        }
+    }
+}
+
+/// Run a docker CLI command and return its stdout
+async fn docker(args: &[&str]) -> Result<String> {
+    let output = TokioCommand::new("docker")
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| anyhow!("Docker is not available: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "docker {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,185 @@
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use anyhow::{anyhow, Result};
+use regex::bytes::{Captures, Regex};
+use crate::models::{ArchiveEntry, ArchiveEntryKind, CompressionType, SchemaRemap};
+use crate::services::backup_process::ZSTD_LEVEL;
+
+/// Statements naming a table in mydumper's schema and data files, followed by the backquoted name
+const TABLE_NAME_PATTERN: &str = r"((?:CREATE TABLE(?: IF NOT EXISTS)?|DROP (?:TABLE|VIEW)(?: IF EXISTS)?|(?:INSERT|REPLACE)(?: IGNORE)? INTO|INTO TABLE|REFERENCES|VIEW|(?:INSERT|UPDATE|DELETE) ON) )`([^`]+)`";
+/// The database statement of a `<database>-schema-create.sql` file
+const DATABASE_NAME_PATTERN: &str = r"(CREATE (?:DATABASE|SCHEMA)(?: IF NOT EXISTS)? )`([^`]+)`";
+
+/// Apply `remap` to an extracted mydumper dump in place, so myloader loads it under the new names.
+///
+/// Files are renamed and the names in the statements mydumper writes itself are rewritten:
+/// CREATE, DROP and INSERT statements, foreign key references and trigger targets. Names
+/// inside view, trigger and routine bodies are left alone.
+pub fn apply(dir: &Path, remap: &SchemaRemap) -> Result<()> {
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            entries.push(ArchiveEntry::from_path(entry.file_name().to_string_lossy().to_string(), 0));
+        }
+    }
+    // References are only rewritten for tables of the dump, not ones living elsewhere
+    let tables: BTreeSet<(String, String)> = entries.iter()
+        .filter(|entry| entry.kind == ArchiveEntryKind::TableSchema)
+        .filter_map(|entry| Some((entry.database_name.clone()?, entry.table_name.clone()?)))
+        .collect();
+
+    let table_names = Regex::new(TABLE_NAME_PATTERN)?;
+    let database_names = Regex::new(DATABASE_NAME_PATTERN)?;
+    let mut renamed = BTreeSet::new();
+
+    for entry in &entries {
+        let (old_prefix, new_prefix, database) = match (&entry.kind, &entry.database_name, &entry.table_name) {
+            (ArchiveEntryKind::Metadata, _, _) => {
+                if entry.path == "metadata" {
+                    rewrite_metadata(&dir.join(&entry.path), remap)?;
+                }
+                continue;
+            }
+            (ArchiveEntryKind::DatabaseSchema, Some(database), _) | (ArchiveEntryKind::TableSchema, None, Some(database)) => {
+                (database.clone(), remap.database(database), database)
+            }
+            (ArchiveEntryKind::TableSchema | ArchiveEntryKind::Data, Some(database), Some(table)) => (
+                format!("{}.{}", database, table),
+                format!("{}.{}", remap.database(database), remap.table(database, table)),
+                database,
+            ),
+            _ => continue,
+        };
+
+        let new_name = format!("{}{}", new_prefix, &entry.path[old_prefix.len()..]);
+        // Data chunks only name their own table
+        if entry.kind == ArchiveEntryKind::Data && new_name == entry.path {
+            continue;
+        }
+        if new_name != entry.path && (!renamed.insert(new_name.clone()) || dir.join(&new_name).exists()) {
+            return Err(anyhow!("Schema remapping maps {} onto a name that is already taken: {}", entry.path, new_name));
+        }
+
+        let rename_line = |line: &[u8]| -> Option<Vec<u8>> {
+            let mut changed = false;
+            let mut line = table_names.replace_all(line, |caps: &Captures| {
+                let name = String::from_utf8_lossy(&caps[2]).to_string();
+                if !tables.contains(&(database.clone(), name.clone())) {
+                    return caps[0].to_vec();
+                }
+                let new_name = remap.table(database, &name);
+                changed |= new_name != name;
+                [&caps[1], b"`", new_name.as_bytes(), b"`"].concat()
+            }).into_owned();
+            if entry.kind == ArchiveEntryKind::DatabaseSchema {
+                line = database_names.replace_all(&line, |caps: &Captures| {
+                    let name = String::from_utf8_lossy(&caps[2]).to_string();
+                    let new_name = remap.database(&name);
+                    changed |= new_name != name;
+                    [&caps[1], b"`", new_name.as_bytes(), b"`"].concat()
+                }).into_owned();
+            }
+            // LOAD DATA statements name the CSV chunk they read, which is renamed too
+            if line.starts_with(b"LOAD DATA") {
+                let (old_file, new_file) = (format!("'{}.", old_prefix), format!("'{}.", new_prefix));
+                if let Some(at) = find(&line, old_file.as_bytes()) {
+                    line.splice(at..at + old_file.len(), new_file.bytes());
+                    changed = true;
+                }
+            }
+            changed.then_some(line)
+        };
+
+        let src = dir.join(&entry.path);
+        let dest = dir.join(&new_name);
+        // CSV chunks hold nothing but rows
+        let stem = entry.path.trim_end_matches(".gz").trim_end_matches(".zst");
+        if stem.ends_with(".dat") {
+            if src != dest {
+                std::fs::rename(&src, &dest)?;
+            }
+        } else {
+            rewrite_file(&src, &dest, rename_line)?;
+        }
+    }
+    Ok(())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+/// Rename the `[`database`.`table`]` sections of newer mydumper's `metadata` file
+fn rewrite_metadata(path: &Path, remap: &SchemaRemap) -> Result<()> {
+    let content = std::fs::read_to_string(path)?;
+    let rewritten: Vec<String> = content.lines()
+        .map(|line| {
+            let section = line.trim().strip_prefix("[`")
+                .and_then(|l| l.strip_suffix("`]"))
+                .and_then(|l| l.split_once("`.`"));
+            match section {
+                Some((database, table)) => format!("[`{}`.`{}`]", remap.database(database), remap.table(database, table)),
+                None => line.to_string(),
+            }
+        })
+        .collect();
+    std::fs::write(path, rewritten.join("\n") + "\n")?;
+    Ok(())
+}
+
+/// Copy `src` to `dest` line by line through `rewrite`, keeping its gzip or zstd compression,
+/// and remove `src`. Data rows start with `(` and are copied without being looked at.
+fn rewrite_file(src: &Path, dest: &Path, rewrite: impl Fn(&[u8]) -> Option<Vec<u8>>) -> Result<()> {
+    let file_name = src.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let compression = if file_name.ends_with(".gz") {
+        Some(CompressionType::Gzip)
+    } else if file_name.ends_with(".zst") {
+        Some(CompressionType::Zstd)
+    } else {
+        None
+    };
+
+    let file = File::open(src)?;
+    let mut reader: Box<dyn BufRead> = match compression {
+        Some(CompressionType::Gzip) => Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(file))),
+        Some(_) => Box::new(BufReader::new(zstd::Decoder::new(file)?)),
+        None => Box::new(BufReader::new(file)),
+    };
+    let tmp = dest.with_extension("remap");
+    let output = BufWriter::new(File::create(&tmp)?);
+    match compression {
+        Some(CompressionType::Gzip) => {
+            let encoder = flate2::write::GzEncoder::new(output, flate2::Compression::default());
+            copy_lines(&mut reader, encoder, &rewrite)?.finish()?.flush()?
+        }
+        Some(_) => copy_lines(&mut reader, zstd::Encoder::new(output, ZSTD_LEVEL)?, &rewrite)?.finish()?.flush()?,
+        None => copy_lines(&mut reader, output, &rewrite)?.flush()?,
+    }
+
+    std::fs::remove_file(src)?;
+    std::fs::rename(&tmp, dest)?;
+    Ok(())
+}
+
+fn copy_lines<W: Write>(reader: &mut dyn BufRead, mut writer: W, rewrite: &impl Fn(&[u8]) -> Option<Vec<u8>>) -> Result<W> {
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        match line.first() {
+            Some(b'(') => writer.write_all(&line)?,
+            _ => writer.write_all(&rewrite(&line).unwrap_or_else(|| line.clone()))?,
+        }
+        line.clear();
+    }
+    Ok(writer)
+}
+
+/// Remapped name of a `database.table` entry of a restore's table list
+pub fn table_entry(remap: &SchemaRemap, entry: &str) -> String {
+    match entry.split_once('.') {
+        Some((database, table)) => format!("{}.{}", remap.database(database), remap.table(database, table)),
+        None => entry.to_string(),
+    }
+}
@@ -0,0 +1,563 @@
+use std::collections::VecDeque;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Stdio;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use tempfile::{NamedTempFile, TempPath};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command as TokioCommand};
+use tokio::sync::watch;
+use crate::models::SftpTarget;
+use crate::services::bandwidth::Throttle;
+
+/// Bytes per WRITE request; every SFTP server accepts at least 32 KiB
+const CHUNK_SIZE: usize = 32 * 1024;
+/// WRITE requests sent ahead of their acknowledgements, so latency doesn't cap throughput
+const MAX_IN_FLIGHT: usize = 16;
+
+const FXP_INIT: u8 = 1;
+const FXP_VERSION: u8 = 2;
+const FXP_OPEN: u8 = 3;
+const FXP_CLOSE: u8 = 4;
+const FXP_READ: u8 = 5;
+const FXP_WRITE: u8 = 6;
+const FXP_OPENDIR: u8 = 11;
+const FXP_READDIR: u8 = 12;
+const FXP_REMOVE: u8 = 13;
+const FXP_MKDIR: u8 = 14;
+const FXP_RMDIR: u8 = 15;
+const FXP_STAT: u8 = 17;
+const FXP_RENAME: u8 = 18;
+const FXP_STATUS: u8 = 101;
+const FXP_HANDLE: u8 = 102;
+const FXP_DATA: u8 = 103;
+const FXP_NAME: u8 = 104;
+const FXP_ATTRS: u8 = 105;
+
+const FX_EOF: u32 = 1;
+
+const FXF_READ: u32 = 0x01;
+const FXF_WRITE: u32 = 0x02;
+const FXF_CREAT: u32 = 0x08;
+const FXF_TRUNC: u32 = 0x10;
+
+/// Name the pinned host keys are listed under, so they apply whatever host and port are configured
+const HOST_KEY_ALIAS: &str = "rdumper-sftp-destination";
+
+/// A directory entry as returned by READDIR
+pub struct SftpEntry {
+    pub name: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    pub is_dir: bool,
+}
+
+/// SFTP (protocol version 3) spoken over the `ssh` client's sftp subsystem, so authentication,
+/// host keys and ciphers are left to OpenSSH
+pub struct SftpSession {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: ChildStdout,
+    stderr: Option<ChildStderr>,
+    next_id: u32,
+    // Key, askpass and known_hosts files have to exist until ssh has authenticated
+    _secrets: Vec<TempPath>,
+}
+
+impl SftpSession {
+    pub async fn connect(target: &SftpTarget) -> Result<Self> {
+        let mut cmd = TokioCommand::new("ssh");
+        cmd.arg("-s")
+            .arg("-p").arg(target.port.to_string())
+            .arg("-l").arg(&target.username)
+            .arg("-o").arg("StrictHostKeyChecking=yes")
+            .arg("-o").arg("ConnectTimeout=30")
+            .arg("-o").arg("ServerAliveInterval=30");
+
+        let mut secrets = Vec::new();
+        // Only the pinned keys are trusted; a known_hosts in the container would be lost with it anyway
+        if let Some(host_key) = non_empty(&target.host_key) {
+            let known_hosts = secret_file(&known_hosts(host_key)?, 0o600)?;
+            cmd.arg("-o").arg(format!("UserKnownHostsFile=\"{}\"", known_hosts.display()))
+                .arg("-o").arg("GlobalKnownHostsFile=/dev/null")
+                .arg("-o").arg(format!("HostKeyAlias={}", HOST_KEY_ALIAS))
+                .arg("-o").arg("UpdateHostKeys=no");
+            secrets.push(known_hosts);
+        }
+        match (non_empty(&target.private_key), non_empty(&target.password)) {
+            (Some(key), _) => {
+                // ssh refuses keys without a trailing newline
+                let key = if key.ends_with('\n') { key.to_string() } else { format!("{}\n", key) };
+                let key_file = secret_file(&key, 0o600)?;
+                cmd.arg("-i").arg(&key_file)
+                    .arg("-o").arg("IdentitiesOnly=yes")
+                    .arg("-o").arg("BatchMode=yes");
+                secrets.push(key_file);
+            }
+            (None, Some(password)) => {
+                // There is no tty to type into, so ssh asks a helper that echoes the password from the environment
+                let askpass = secret_file("#!/bin/sh\nprintf '%s\\n' \"$RDUMPER_SFTP_PASSWORD\"\n", 0o700)?;
+                cmd.env("SSH_ASKPASS", &askpass)
+                    .env("SSH_ASKPASS_REQUIRE", "force")
+                    .env("RDUMPER_SFTP_PASSWORD", password)
+                    .arg("-o").arg("PreferredAuthentications=password,keyboard-interactive")
+                    .arg("-o").arg("NumberOfPasswordPrompts=1");
+                secrets.push(askpass);
+            }
+            (None, None) => {
+                cmd.arg("-o").arg("BatchMode=yes");
+            }
+        }
+
+        let mut child = cmd.arg("--").arg(&target.host).arg("sftp")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow!("ssh is not available: {}", e))?;
+
+        let mut session = Self {
+            stdin: child.stdin.take().ok_or_else(|| anyhow!("Failed to open ssh stdin"))?,
+            stdout: child.stdout.take().ok_or_else(|| anyhow!("Failed to open ssh stdout"))?,
+            stderr: child.stderr.take(),
+            child,
+            next_id: 0,
+            _secrets: secrets,
+        };
+
+        session.send(FXP_INIT, &3u32.to_be_bytes()).await?;
+        let (kind, _) = session.read_packet().await?;
+        if kind != FXP_VERSION {
+            return Err(anyhow!("SFTP server sent packet type {} instead of its version", kind));
+        }
+        Ok(session)
+    }
+
+    /// Create `dir` and its missing parents
+    pub async fn mkdir_all(&mut self, dir: &str) -> Result<()> {
+        let mut path = if dir.starts_with('/') { String::from("/") } else { String::new() };
+        for component in dir.split('/').filter(|c| !c.is_empty()) {
+            if !path.is_empty() && !path.ends_with('/') {
+                path.push('/');
+            }
+            path.push_str(component);
+            if !self.exists(&path).await? {
+                let id = self.request(FXP_MKDIR, |p| {
+                    put_string(p, path.as_bytes());
+                    p.extend_from_slice(&0u32.to_be_bytes());
+                }).await?;
+                self.expect_ok(id).await.map_err(|e| anyhow!("Failed to create {}: {}", path, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Upload a file through a `.part` file renamed into place at the end; returns the file's SHA-256
+    pub async fn upload_file(&mut self, remote: &str, path: &Path, progress: Option<watch::Sender<u32>>, throttle: Option<&Throttle>) -> Result<String> {
+        let total = tokio::fs::metadata(path).await?.len();
+        let mut file = tokio::fs::File::open(path).await?;
+        let mut hasher = Sha256::new();
+        let partial = format!("{}.part", remote);
+        let handle = self.open_for_write(&partial).await?;
+
+        let mut in_flight = VecDeque::new();
+        let mut offset: u64 = 0;
+        let mut acknowledged: u64 = 0;
+        let mut last_percent = 0;
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+        loop {
+            let read = file.read(&mut buffer).await?;
+            if read == 0 && in_flight.is_empty() {
+                break;
+            }
+            if read > 0 {
+                let chunk = &buffer[..read];
+                hasher.update(chunk);
+                if let Some(throttle) = throttle {
+                    throttle.consume(read).await;
+                }
+                let id = self.request(FXP_WRITE, |p| {
+                    put_string(p, &handle);
+                    p.extend_from_slice(&offset.to_be_bytes());
+                    put_string(p, chunk);
+                }).await?;
+                in_flight.push_back((id, read as u64));
+                offset += read as u64;
+            }
+            if in_flight.len() >= MAX_IN_FLIGHT || (read == 0 && !in_flight.is_empty()) {
+                let (id, length) = in_flight.pop_front().expect("in-flight writes");
+                self.expect_ok(id).await.map_err(|e| anyhow!("Failed to write {}: {}", partial, e))?;
+                acknowledged += length;
+                let percent = (acknowledged * 100 / total.max(1)) as u32;
+                if let (Some(tx), true) = (&progress, percent != last_percent) {
+                    let _ = tx.send(percent);
+                    last_percent = percent;
+                }
+            }
+        }
+
+        self.close_handle(&handle).await?;
+        self.replace(&partial, remote).await?;
+        if let Some(tx) = &progress {
+            let _ = tx.send(100);
+        }
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    /// Write a small file in one go, replacing an existing one
+    pub async fn write_file(&mut self, remote: &str, data: &[u8]) -> Result<()> {
+        let partial = format!("{}.part", remote);
+        let handle = self.open_for_write(&partial).await?;
+        let id = self.request(FXP_WRITE, |p| {
+            put_string(p, &handle);
+            p.extend_from_slice(&0u64.to_be_bytes());
+            put_string(p, data);
+        }).await?;
+        self.expect_ok(id).await.map_err(|e| anyhow!("Failed to write {}: {}", partial, e))?;
+        self.close_handle(&handle).await?;
+        self.replace(&partial, remote).await
+    }
+
+    /// Entries of a directory, without `.` and `..`
+    pub async fn read_dir(&mut self, dir: &str) -> Result<Vec<SftpEntry>> {
+        let id = self.request(FXP_OPENDIR, |p| put_string(p, dir.as_bytes())).await?;
+        let handle = self.expect_handle(id).await.map_err(|e| anyhow!("Failed to open {}: {}", dir, e))?;
+
+        let mut entries = Vec::new();
+        loop {
+            let id = self.request(FXP_READDIR, |p| put_string(p, &handle)).await?;
+            let (kind, mut body) = self.read_response(id).await?;
+            match kind {
+                FXP_NAME => {
+                    for _ in 0..take_u32(&mut body)? {
+                        let name = String::from_utf8_lossy(&take_string(&mut body)?).into_owned();
+                        take_string(&mut body)?; // ls -l style long name
+                        let (size, modified, permissions) = take_attrs(&mut body)?;
+                        if name != "." && name != ".." {
+                            // S_IFDIR in the file type bits
+                            entries.push(SftpEntry { name, size, modified, is_dir: permissions & 0o170000 == 0o040000 });
+                        }
+                    }
+                }
+                FXP_STATUS if peek_status(&body) == Some(FX_EOF) => break,
+                FXP_STATUS => status_result(&mut body).map_err(|e| anyhow!("Failed to list {}: {}", dir, e))?,
+                _ => return Err(anyhow!("Unexpected SFTP packet type {}", kind)),
+            }
+        }
+        self.close_handle(&handle).await?;
+        Ok(entries)
+    }
+
+    pub async fn read_file(&mut self, path: &str) -> Result<Vec<u8>> {
+        let mut content = Vec::new();
+        self.read_chunks(path, |chunk| content.extend_from_slice(chunk)).await?;
+        Ok(content)
+    }
+
+    /// Read a file without holding it in memory, returning its size and SHA-256
+    pub async fn hash_file(&mut self, path: &str, progress: Option<watch::Sender<u32>>) -> Result<(u64, String)> {
+        let total = self.stat(path).await?.0.max(1);
+        let mut hasher = Sha256::new();
+        let mut read = 0u64;
+        self.read_chunks(path, |chunk| {
+            hasher.update(chunk);
+            read += chunk.len() as u64;
+            if let Some(tx) = &progress {
+                let _ = tx.send((read * 100 / total).min(100) as u32);
+            }
+        }).await?;
+        Ok((read, hex::encode(hasher.finalize())))
+    }
+
+    /// Size and modification time of a file
+    pub async fn stat(&mut self, path: &str) -> Result<(u64, Option<DateTime<Utc>>)> {
+        let id = self.request(FXP_STAT, |p| put_string(p, path.as_bytes())).await?;
+        let (kind, mut body) = self.read_response(id).await?;
+        match kind {
+            FXP_ATTRS => take_attrs(&mut body).map(|(size, modified, _)| (size, modified)),
+            FXP_STATUS => {
+                status_result(&mut body).map_err(|e| anyhow!("Failed to stat {}: {}", path, e))?;
+                Err(anyhow!("SFTP server sent no attributes for {}", path))
+            }
+            _ => Err(anyhow!("Unexpected SFTP packet type {}", kind)),
+        }
+    }
+
+    pub async fn remove(&mut self, path: &str) -> Result<()> {
+        let id = self.request(FXP_REMOVE, |p| put_string(p, path.as_bytes())).await?;
+        self.expect_ok(id).await.map_err(|e| anyhow!("Failed to remove {}: {}", path, e))
+    }
+
+    /// Remove an empty directory
+    pub async fn rmdir(&mut self, path: &str) -> Result<()> {
+        let id = self.request(FXP_RMDIR, |p| put_string(p, path.as_bytes())).await?;
+        self.expect_ok(id).await.map_err(|e| anyhow!("Failed to remove {}: {}", path, e))
+    }
+
+    /// End the session and wait for ssh to exit
+    pub async fn close(mut self) -> Result<()> {
+        drop(self.stdin);
+        self.child.wait().await?;
+        Ok(())
+    }
+
+    /// Read a file front to back, handing each chunk to `consume`
+    async fn read_chunks(&mut self, path: &str, mut consume: impl FnMut(&[u8])) -> Result<()> {
+        let id = self.request(FXP_OPEN, |p| {
+            put_string(p, path.as_bytes());
+            p.extend_from_slice(&FXF_READ.to_be_bytes());
+            p.extend_from_slice(&0u32.to_be_bytes());
+        }).await?;
+        let handle = self.expect_handle(id).await.map_err(|e| anyhow!("Failed to open {}: {}", path, e))?;
+
+        let mut offset = 0u64;
+        loop {
+            let id = self.request(FXP_READ, |p| {
+                put_string(p, &handle);
+                p.extend_from_slice(&offset.to_be_bytes());
+                p.extend_from_slice(&(CHUNK_SIZE as u32).to_be_bytes());
+            }).await?;
+            let (kind, mut body) = self.read_response(id).await?;
+            match kind {
+                FXP_DATA => {
+                    let data = take_string(&mut body)?;
+                    offset += data.len() as u64;
+                    consume(&data);
+                }
+                FXP_STATUS if peek_status(&body) == Some(FX_EOF) => break,
+                FXP_STATUS => status_result(&mut body).map_err(|e| anyhow!("Failed to read {}: {}", path, e))?,
+                _ => return Err(anyhow!("Unexpected SFTP packet type {}", kind)),
+            }
+        }
+        self.close_handle(&handle).await
+    }
+
+    async fn exists(&mut self, path: &str) -> Result<bool> {
+        let id = self.request(FXP_STAT, |p| put_string(p, path.as_bytes())).await?;
+        let (kind, mut body) = self.read_response(id).await?;
+        match kind {
+            FXP_ATTRS => Ok(true),
+            FXP_STATUS => Ok(status_result(&mut body).is_ok()),
+            _ => Err(anyhow!("Unexpected SFTP packet type {}", kind)),
+        }
+    }
+
+    async fn open_for_write(&mut self, path: &str) -> Result<Vec<u8>> {
+        let id = self.request(FXP_OPEN, |p| {
+            put_string(p, path.as_bytes());
+            p.extend_from_slice(&(FXF_WRITE | FXF_CREAT | FXF_TRUNC).to_be_bytes());
+            p.extend_from_slice(&0u32.to_be_bytes());
+        }).await?;
+        self.expect_handle(id).await.map_err(|e| anyhow!("Failed to open {}: {}", path, e))
+    }
+
+    async fn expect_handle(&mut self, id: u32) -> Result<Vec<u8>> {
+        let (kind, mut body) = self.read_response(id).await?;
+        match kind {
+            FXP_HANDLE => take_string(&mut body),
+            FXP_STATUS => {
+                status_result(&mut body)?;
+                Err(anyhow!("SFTP server sent no handle"))
+            }
+            _ => Err(anyhow!("Unexpected SFTP packet type {}", kind)),
+        }
+    }
+
+    async fn close_handle(&mut self, handle: &[u8]) -> Result<()> {
+        let id = self.request(FXP_CLOSE, |p| put_string(p, handle)).await?;
+        self.expect_ok(id).await
+    }
+
+    /// Rename over an existing file; plain SFTP v3 rename refuses to overwrite
+    async fn replace(&mut self, from: &str, to: &str) -> Result<()> {
+        let id = self.request(FXP_REMOVE, |p| put_string(p, to.as_bytes())).await?;
+        let _ = self.expect_ok(id).await;
+        let id = self.request(FXP_RENAME, |p| {
+            put_string(p, from.as_bytes());
+            put_string(p, to.as_bytes());
+        }).await?;
+        self.expect_ok(id).await.map_err(|e| anyhow!("Failed to rename {} to {}: {}", from, to, e))
+    }
+
+    /// Send a request with a fresh id; `fill` appends the fields after the id
+    async fn request(&mut self, kind: u8, fill: impl FnOnce(&mut Vec<u8>)) -> Result<u32> {
+        self.next_id = self.next_id.wrapping_add(1);
+        let mut payload = self.next_id.to_be_bytes().to_vec();
+        fill(&mut payload);
+        self.send(kind, &payload).await?;
+        Ok(self.next_id)
+    }
+
+    async fn send(&mut self, kind: u8, payload: &[u8]) -> Result<()> {
+        let mut packet = Vec::with_capacity(payload.len() + 5);
+        packet.extend_from_slice(&(payload.len() as u32 + 1).to_be_bytes());
+        packet.push(kind);
+        packet.extend_from_slice(payload);
+        if self.stdin.write_all(&packet).await.is_err() {
+            return Err(self.connection_error().await);
+        }
+        Ok(())
+    }
+
+    async fn expect_ok(&mut self, id: u32) -> Result<()> {
+        let (kind, mut body) = self.read_response(id).await?;
+        if kind != FXP_STATUS {
+            return Err(anyhow!("Unexpected SFTP packet type {}", kind));
+        }
+        status_result(&mut body)
+    }
+
+    /// Read the next packet, which has to answer request `id`; returns its type and the fields after the id
+    async fn read_response(&mut self, id: u32) -> Result<(u8, Vec<u8>)> {
+        let (kind, mut body) = self.read_packet().await?;
+        if take_u32(&mut body)? != id {
+            return Err(anyhow!("SFTP server answered out of order"));
+        }
+        Ok((kind, body))
+    }
+
+    async fn read_packet(&mut self) -> Result<(u8, Vec<u8>)> {
+        let mut header = [0u8; 5];
+        if self.stdout.read_exact(&mut header).await.is_err() {
+            return Err(self.connection_error().await);
+        }
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        if length == 0 || length > 256 * 1024 {
+            return Err(anyhow!("Invalid SFTP packet length {}", length));
+        }
+        let mut body = vec![0u8; length - 1];
+        if self.stdout.read_exact(&mut body).await.is_err() {
+            return Err(self.connection_error().await);
+        }
+        Ok((header[4], body))
+    }
+
+    /// ssh exits when the connection fails; its stderr says why (refused, auth failure, host key mismatch)
+    async fn connection_error(&mut self) -> anyhow::Error {
+        let mut message = String::new();
+        if let Some(mut stderr) = self.stderr.take() {
+            let _ = stderr.read_to_string(&mut message).await;
+        }
+        let message = message.lines()
+            .filter(|line| !line.starts_with("Warning: Permanently added"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if message.trim().is_empty() {
+            anyhow!("SSH connection closed unexpectedly")
+        } else {
+            anyhow!("SSH connection failed: {}", message.trim())
+        }
+    }
+}
+
+/// known_hosts lines for the keys of a destination's `host_key`, which holds one key per line as
+/// `type key` or, like `ssh-keyscan` prints it, `host type key`
+pub fn known_hosts(host_key: &str) -> Result<String> {
+    let mut lines = String::new();
+    for line in host_key.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let is_key_type = |field: &str| field.starts_with("ssh-") || field.starts_with("ecdsa-") || field.starts_with("sk-");
+        let (key_type, key) = match fields[..] {
+            [key_type, key, ..] if is_key_type(key_type) => (key_type, key),
+            [_, key_type, key, ..] if is_key_type(key_type) => (key_type, key),
+            _ => return Err(anyhow!("SFTP host key '{}' is not a public key as printed by ssh-keyscan", line)),
+        };
+        if !key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '=')) {
+            return Err(anyhow!("SFTP host key '{}' is not a public key as printed by ssh-keyscan", line));
+        }
+        lines.push_str(&format!("{} {} {}\n", HOST_KEY_ALIAS, key_type, key));
+    }
+    if lines.is_empty() {
+        return Err(anyhow!("SFTP host key holds no key"));
+    }
+    Ok(lines)
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().filter(|v| !v.trim().is_empty())
+}
+
+/// Write `content` to a private temporary file that is deleted when the returned path is dropped
+fn secret_file(content: &str, mode: u32) -> Result<TempPath> {
+    let mut file = NamedTempFile::new()?;
+    file.write_all(content.as_bytes())?;
+    file.as_file().set_permissions(std::fs::Permissions::from_mode(mode))?;
+    // Closing the handle matters for the askpass script: Linux won't execute a file open for writing
+    Ok(file.into_temp_path())
+}
+
+fn put_string(packet: &mut Vec<u8>, value: &[u8]) {
+    packet.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    packet.extend_from_slice(value);
+}
+
+fn take_u32(body: &mut Vec<u8>) -> Result<u32> {
+    if body.len() < 4 {
+        return Err(anyhow!("Truncated SFTP packet"));
+    }
+    let value = u32::from_be_bytes([body[0], body[1], body[2], body[3]]);
+    body.drain(..4);
+    Ok(value)
+}
+
+fn take_string(body: &mut Vec<u8>) -> Result<Vec<u8>> {
+    let length = take_u32(body)? as usize;
+    if body.len() < length {
+        return Err(anyhow!("Truncated SFTP packet"));
+    }
+    Ok(body.drain(..length).collect())
+}
+
+fn take_u64(body: &mut Vec<u8>) -> Result<u64> {
+    let high = take_u32(body)? as u64;
+    let low = take_u32(body)? as u64;
+    Ok(high << 32 | low)
+}
+
+/// Size, modification time and permission bits from an ATTRS structure
+fn take_attrs(body: &mut Vec<u8>) -> Result<(u64, Option<DateTime<Utc>>, u32)> {
+    let flags = take_u32(body)?;
+    let size = if flags & 0x01 != 0 { take_u64(body)? } else { 0 };
+    if flags & 0x02 != 0 {
+        take_u32(body)?;
+        take_u32(body)?;
+    }
+    let permissions = if flags & 0x04 != 0 { take_u32(body)? } else { 0 };
+    let modified = if flags & 0x08 != 0 {
+        take_u32(body)?;
+        DateTime::from_timestamp(take_u32(body)? as i64, 0)
+    } else {
+        None
+    };
+    if flags & 0x8000_0000 != 0 {
+        for _ in 0..take_u32(body)? {
+            take_string(body)?;
+            take_string(body)?;
+        }
+    }
+    Ok((size, modified, permissions))
+}
+
+/// Status code of an SSH_FXP_STATUS body without consuming it
+fn peek_status(body: &[u8]) -> Option<u32> {
+    body.get(..4).map(|code| u32::from_be_bytes([code[0], code[1], code[2], code[3]]))
+}
+
+/// Turn an SSH_FXP_STATUS body into Ok for SSH_FX_OK and the server's message otherwise
+fn status_result(body: &mut Vec<u8>) -> Result<()> {
+    let code = take_u32(body)?;
+    if code == 0 {
+        return Ok(());
+    }
+    let message = take_string(body).map(|m| String::from_utf8_lossy(&m).into_owned()).unwrap_or_default();
+    Err(match code {
+        2 => anyhow!("No such file or directory"),
+        3 => anyhow!("Permission denied"),
+        _ if !message.is_empty() => anyhow!("{}", message),
+        _ => anyhow!("SFTP error code {}", code),
+    })
+}
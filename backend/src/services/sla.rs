@@ -0,0 +1,85 @@
+use std::collections::HashSet;
+use std::sync::{Mutex, OnceLock};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use utoipa::ToSchema;
+use crate::models::DatabaseConfig;
+use crate::services::FilesystemBackupService;
+
+/// Backup freshness of a database config measured against its RPO target
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RpoStatus {
+    pub database_config_id: String,
+    pub name: String,
+    pub rpo_hours: i32,
+    pub last_backup_id: Option<String>,
+    pub last_backup_at: Option<DateTime<Utc>>,
+    /// Hours since the last successful backup (or since the config was created, if there is none)
+    pub age_hours: f64,
+    pub violated: bool,
+}
+
+/// Config IDs that violated their RPO at the last evaluation pass
+fn violating() -> &'static Mutex<HashSet<String>> {
+    static VIOLATING: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    VIOLATING.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// Evaluate every database config that declares an RPO target
+pub async fn evaluate(pool: &SqlitePool, backup_service: &FilesystemBackupService) -> Result<Vec<RpoStatus>> {
+    let configs: Vec<DatabaseConfig> = sqlx::query_as(
+        "SELECT * FROM database_configs WHERE rpo_hours IS NOT NULL AND rpo_hours > 0 ORDER BY name"
+    )
+    .fetch_all(pool)
+    .await?;
+    if configs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Uploaded archives are copies of older data, so only backups taken by a task count
+    let backups = backup_service.scan_backups().await?;
+    let now = Utc::now();
+
+    Ok(configs.into_iter().filter_map(|config| {
+        let rpo_hours = config.rpo_hours?;
+        let last_backup = backups.iter()
+            .filter(|b| b.database_config_id == config.id && b.task_id.is_some())
+            .filter_map(|b| {
+                DateTime::parse_from_rfc3339(&b.created_at).ok().map(|at| (b, at.with_timezone(&Utc)))
+            })
+            .max_by_key(|(_, at)| *at);
+
+        // Newly added configs get one RPO window before they count as violating
+        let since = last_backup.map(|(_, at)| at).unwrap_or(config.created_at);
+        let age_hours = (now - since).num_seconds() as f64 / 3600.0;
+
+        Some(RpoStatus {
+            database_config_id: config.id.clone(),
+            name: config.name.clone(),
+            rpo_hours,
+            last_backup_id: last_backup.map(|(b, _)| b.id.clone()),
+            last_backup_at: last_backup.map(|(_, at)| at),
+            age_hours: (age_hours * 10.0).round() / 10.0,
+            violated: age_hours > rpo_hours as f64,
+        })
+    }).collect())
+}
+
+/// Evaluate all RPO targets and return the configs whose violation state changed since the last pass
+pub async fn evaluate_changes(pool: &SqlitePool, backup_service: &FilesystemBackupService) -> Result<Vec<RpoStatus>> {
+    let statuses = evaluate(pool, backup_service).await?;
+    let mut violating = violating().lock().unwrap();
+
+    let changed = statuses.iter()
+        .filter(|status| status.violated != violating.contains(&status.database_config_id))
+        .cloned()
+        .collect();
+
+    *violating = statuses.iter()
+        .filter(|status| status.violated)
+        .map(|status| status.database_config_id.clone())
+        .collect();
+    Ok(changed)
+}
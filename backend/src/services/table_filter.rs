@@ -0,0 +1,86 @@
+use regex::Regex;
+use serde::Serialize;
+use crate::models::Task;
+
+/// Which tables of a task's database are backed up, from its `include_tables` and `exclude_tables`.
+///
+/// Patterns are table names in which `*` matches any run of characters and `?` a single one.
+/// Without include patterns every table is included; exclusions always win.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TableFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl TableFilter {
+    /// The task's filter, or None if it backs up every table
+    pub fn for_task(task: &Task) -> Result<Option<Self>, String> {
+        let filter = Self {
+            include: split_patterns(task.include_tables.as_deref().unwrap_or_default())?,
+            exclude: split_patterns(task.exclude_tables.as_deref().unwrap_or_default())?,
+        };
+        Ok((!filter.include.is_empty() || !filter.exclude.is_empty()).then_some(filter))
+    }
+
+    pub fn matches(&self, table: &str) -> bool {
+        let any_matches = |patterns: &[String]| patterns.iter()
+            .any(|pattern| Regex::new(&format!("^{}$", glob_to_regex(pattern))).is_ok_and(|re| re.is_match(table)));
+        (self.include.is_empty() || any_matches(&self.include)) && !any_matches(&self.exclude)
+    }
+
+    /// mydumper flags applying the filter to the tables of `databases`.
+    ///
+    /// Plain table names in a single database become a `--tables-list`; anything else needs a
+    /// `--regex` on "database.table", with exclusions as a negative lookahead.
+    pub fn mydumper_args(&self, databases: &[String]) -> Vec<String> {
+        let is_plain = |pattern: &String| !pattern.contains(['*', '?']);
+        if let [database] = databases {
+            if !self.include.is_empty() && self.exclude.is_empty() && self.include.iter().all(is_plain) {
+                let tables = self.include.iter()
+                    .map(|table| format!("{}.{}", database, table))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                return vec!["--tables-list".to_string(), tables];
+            }
+        }
+
+        let alternatives = |patterns: &[String]| patterns.iter().map(|p| glob_to_regex(p)).collect::<Vec<_>>().join("|");
+        let database = format!("(?:{})", databases.iter().map(|db| regex::escape(db)).collect::<Vec<_>>().join("|"));
+        let exclude = if self.exclude.is_empty() {
+            String::new()
+        } else {
+            format!(r"(?!{}\.(?:{})$)", database, alternatives(&self.exclude))
+        };
+        let include = if self.include.is_empty() {
+            ".*".to_string()
+        } else {
+            format!("(?:{})", alternatives(&self.include))
+        };
+        vec!["--regex".to_string(), format!(r"^{}{}\.{}$", exclude, database, include)]
+    }
+}
+
+/// Patterns separated by commas or whitespace, rejecting characters MySQL doesn't allow in unquoted names
+fn split_patterns(patterns: &str) -> Result<Vec<String>, String> {
+    patterns.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|pattern| !pattern.is_empty())
+        .map(|pattern| {
+            let valid = pattern.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '$' | '-' | '*' | '?'));
+            if valid {
+                Ok(pattern.to_string())
+            } else {
+                Err(format!("Invalid table pattern '{}': use table names with * and ? as wildcards", pattern))
+            }
+        })
+        .collect()
+}
+
+fn glob_to_regex(pattern: &str) -> String {
+    pattern.chars()
+        .map(|c| match c {
+            '*' => ".*".to_string(),
+            '?' => ".".to_string(),
+            _ => regex::escape(&c.to_string()),
+        })
+        .collect()
+}
@@ -1,10 +1,16 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use tokio::time::{sleep, Duration};
+use tokio::sync::{broadcast, Notify};
+use tokio::time::{sleep, Duration, Instant};
 use sqlx::SqlitePool;
 use tracing::{info, warn, error};
 use chrono::{DateTime, Utc};
-use crate::models::{Task, Job, JobType, JobStatus, CreateJobRequest, DatabaseConfig, LogLevel};
-use crate::services::{MydumperService, LoggingService};
+use crate::models::{Task, Job, JobType, JobStatus, CreateJobRequest, DatabaseConfig, LogLevel, RestoreRequest};
+use crate::services::{FilesystemBackupService, MydumperService, LoggingService, UploadSessionStore};
+use crate::services::events::{self, AppEvent};
+use crate::services::{agents, binlog, blackout, cancellation, job_history, remote_storage, restore_target, verification};
+use crate::state::AppConfig;
 
 #[derive(Debug, Clone)]
 pub struct WorkerStatus {
@@ -16,27 +22,53 @@ pub struct WorkerStatus {
 
 pub struct TaskWorker {
     db_pool: Arc<SqlitePool>,
+    config: AppConfig,
     status: Arc<Mutex<WorkerStatus>>,
+    wakeup: Notify,
+    /// Running jobs already reported as overrunning, so each alerts only once
+    overrun_jobs: Mutex<HashSet<String>>,
+    /// Set on shutdown; the worker starts no more jobs
+    draining: AtomicBool,
 }
 
+/// Upper bound for the worker sleep so the status tick stays fresh
+const MAX_IDLE: Duration = Duration::from_secs(60);
+/// Interval between cleanup runs
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Interval between RPO (backup freshness) evaluations
+const RPO_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// Interval between checks for due backup verifications
+const VERIFY_CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+/// How often tasks are checked for due binlog archiving
+const BINLOG_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
 impl TaskWorker {
-    pub fn new(db_pool: Arc<SqlitePool>) -> Self {
+    pub fn new(db_pool: Arc<SqlitePool>, config: AppConfig) -> Self {
         Self {
             db_pool,
+            config,
             status: Arc::new(Mutex::new(WorkerStatus {
                 is_running: false,
                 last_tick: None,
                 total_ticks: 0,
                 tasks_executed: 0,
             })),
+            wakeup: Notify::new(),
+            overrun_jobs: Mutex::new(HashSet::new()),
+            draining: AtomicBool::new(false),
         }
     }
 
+    /// Wake the worker early, e.g. after a task was created, changed or run manually
+    pub fn wake(&self) {
+        self.wakeup.notify_one();
+    }
+
     pub fn get_status(&self) -> WorkerStatus {
         self.status.lock().unwrap().clone()
     }
 
-    /// Start the background worker that runs every minute
+    /// Start the background worker; it sleeps until the next task is due or it is woken up
     pub async fn start(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         info!("Starting task worker...");
         
@@ -49,47 +81,133 @@ impl TaskWorker {
             status.is_running = true;
         }
         
+        let mut last_cleanup = Instant::now();
+        let mut last_rpo_check: Option<Instant> = None;
+        let mut last_verify_check: Option<Instant> = None;
+        let mut last_binlog_check: Option<Instant> = None;
+        let mut worker_events = events::subscribe();
+
+        if let Err(e) = self.interrupt_running_jobs("Interrupted by a restart of rDumper").await {
+            error!("Failed to clean up interrupted jobs: {}", e);
+        }
+
         loop {
+            if self.draining.load(Ordering::SeqCst) {
+                self.status.lock().unwrap().is_running = false;
+                info!("Task worker stopped for shutdown");
+                return Ok(());
+            }
+
             // Update last tick time
             {
                 let mut status = self.status.lock().unwrap();
                 status.last_tick = Some(Utc::now());
                 status.total_ticks += 1;
+                events::publish(AppEvent::WorkerTick {
+                    total_ticks: status.total_ticks,
+                    at: Utc::now(),
+                });
             }
             
+            // Queued jobs go first, they have been waiting longer than newly due tasks or were asked for manually
+            if let Err(e) = self.start_queued_jobs().await {
+                error!("Error starting queued jobs: {}", e);
+            }
+
             if let Err(e) = self.check_and_execute_tasks().await {
                 error!("Error in task worker: {}", e);
             }
+
+            if let Err(e) = self.start_due_retries().await {
+                error!("Error retrying failed jobs: {}", e);
+            }
             
-            // Run cleanup tasks every hour (every 60 ticks)
-            let should_run_cleanup = {
-                let status = self.status.lock().unwrap();
-                status.total_ticks % 60 == 0
-            };
-            
-            if should_run_cleanup {
+            // Run cleanup tasks every hour
+            if last_cleanup.elapsed() >= CLEANUP_INTERVAL {
+                last_cleanup = Instant::now();
                 if let Err(e) = self.run_cleanup_tasks().await {
                     error!("Error in cleanup tasks: {}", e);
                 }
             }
             
-            // Sleep for 1 minute
-            sleep(Duration::from_secs(60)).await;
+            if let Err(e) = self.check_job_durations().await {
+                error!("Error checking job durations: {}", e);
+            }
+
+            if let Err(e) = agents::fail_orphaned_jobs(&self.db_pool).await {
+                error!("Error checking agent jobs: {}", e);
+            }
+
+            if last_rpo_check.is_none_or(|at| at.elapsed() >= RPO_CHECK_INTERVAL) {
+                last_rpo_check = Some(Instant::now());
+                if let Err(e) = self.check_rpo_targets().await {
+                    error!("Error evaluating RPO targets: {}", e);
+                }
+            }
+
+            if last_verify_check.is_none_or(|at| at.elapsed() >= VERIFY_CHECK_INTERVAL) {
+                last_verify_check = Some(Instant::now());
+                match verification::start_due_verifications(&self.db_pool, &self.config).await {
+                    Ok(started) if !started.is_empty() => info!("Started {} scheduled backup verification(s)", started.len()),
+                    Ok(_) => {}
+                    Err(e) => error!("Error starting scheduled verifications: {}", e),
+                }
+            }
+
+            if last_binlog_check.is_none_or(|at| at.elapsed() >= BINLOG_CHECK_INTERVAL) {
+                last_binlog_check = Some(Instant::now());
+                match binlog::start_due_archives(&self.db_pool, &self.config).await {
+                    Ok(started) if !started.is_empty() => info!("Started {} binlog archiving job(s)", started.len()),
+                    Ok(_) => {}
+                    Err(e) => error!("Error starting binlog archiving: {}", e),
+                }
+            }
+
+            // Sleep until the next task is due, at most one minute, or until woken up
+            let idle = match self.next_due_at().await {
+                Ok(Some(next_run)) => (next_run - Utc::now()).to_std().unwrap_or(Duration::ZERO).min(MAX_IDLE),
+                Ok(None) => MAX_IDLE,
+                Err(e) => {
+                    error!("Failed to determine next task run: {}", e);
+                    MAX_IDLE
+                }
+            };
+
+            tokio::select! {
+                _ = sleep(idle) => {}
+                _ = self.wakeup.notified() => {}
+                _ = slot_or_retry_event(&mut worker_events) => {}
+            }
         }
     }
 
+    /// Earliest scheduled run among all active tasks, or pending retry of a failed job
+    async fn next_due_at(&self) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        let next_runs: Vec<(DateTime<Utc>,)> = sqlx::query_as(
+            "SELECT next_run FROM tasks WHERE (is_active = true OR run_once_at IS NOT NULL) AND next_run IS NOT NULL
+             UNION ALL SELECT retry_at FROM jobs WHERE status = 'failed' AND retry_at IS NOT NULL"
+        )
+        .fetch_all(&*self.db_pool)
+        .await?;
+
+        Ok(next_runs.into_iter().map(|(next_run,)| next_run).min())
+    }
+
     /// Check all active tasks and execute them if their time has come
     async fn check_and_execute_tasks(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        // Get all active tasks
+        // Get all active tasks and those with a one-off run
         let tasks = sqlx::query_as::<_, Task>(
-            "SELECT * FROM tasks WHERE is_active = true"
+            "SELECT * FROM tasks WHERE is_active = true OR run_once_at IS NOT NULL"
         )
         .fetch_all(&*self.db_pool)
         .await?;
 
         let mut executed_count = 0;
-        for task in tasks {
+        for mut task in tasks {
             if task.should_run_now() {
+                if self.defer_for_blackout(&mut task).await? {
+                    continue;
+                }
                 let task_id = task.id.clone();
                 if let Err(e) = self.execute_task(task).await {
                     error!("Failed to execute task {}: {}", task_id, e);
@@ -108,6 +226,35 @@ impl TaskWorker {
         Ok(())
     }
 
+    /// Move a due run past the blackout windows it falls into; returns whether it was deferred
+    async fn defer_for_blackout(&self, task: &mut Task) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let windows = blackout::windows_for_task(&self.db_pool, &task.id).await?;
+        let tz = task.timezone().unwrap_or(chrono_tz::Tz::UTC);
+        let (allowed, blocked_by) = blackout::next_allowed(&windows, tz, Utc::now());
+        let Some(window) = blocked_by else {
+            return Ok(false);
+        };
+
+        task.next_run = Some(allowed);
+        task.updated_at = Utc::now();
+        sqlx::query("UPDATE tasks SET next_run = ?, updated_at = ? WHERE id = ?")
+            .bind(task.next_run)
+            .bind(task.updated_at)
+            .bind(&task.id)
+            .execute(&*self.db_pool)
+            .await?;
+
+        let message = format!(
+            "Run deferred until {} by blackout window '{}'",
+            allowed.with_timezone(&tz).format("%Y-%m-%d %H:%M %Z"),
+            window.name
+        );
+        info!("Task {}: {}", task.id, message);
+        let logging_service = LoggingService::new(self.db_pool.clone());
+        let _ = logging_service.log_task(&task.id, &message, LogLevel::Info).await;
+        Ok(true)
+    }
+
     /// Execute a single task
     async fn execute_task(&self, mut task: Task) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         info!("Executing task: {} ({})", task.name, task.id);
@@ -117,7 +264,7 @@ impl TaskWorker {
 
         // Check if there's already a running job for this task
         let running_job = sqlx::query_as::<_, Job>(
-            "SELECT * FROM jobs WHERE task_id = ? AND status IN ('pending', 'running')"
+            "SELECT * FROM jobs WHERE task_id = ? AND job_type = 'backup' AND status IN ('pending', 'running')"
         )
         .bind(&task.id)
         .fetch_optional(&*self.db_pool)
@@ -184,11 +331,12 @@ impl TaskWorker {
             .await?;
 
             // Update task's next run time
-            task.update_next_run()?;
+            task.skip_due_run()?;
             sqlx::query(
-                "UPDATE tasks SET next_run = ?, updated_at = ? WHERE id = ?"
+                "UPDATE tasks SET next_run = ?, run_once_at = ?, updated_at = ? WHERE id = ?"
             )
             .bind(&task.next_run)
+            .bind(task.run_once_at)
             .bind(&task.updated_at)
             .bind(&task.id)
             .execute(&*self.db_pool)
@@ -197,6 +345,31 @@ impl TaskWorker {
             return Ok(());
         }
 
+        self.start_backup_job(&task, 1).await?;
+
+        // Update task's last_run and next_run
+        task.mark_executed()?;
+        sqlx::query(
+            "UPDATE tasks SET last_run = ?, next_run = ?, run_once_at = ?, updated_at = ? WHERE id = ?"
+        )
+        .bind(task.last_run)
+        .bind(task.next_run)
+        .bind(task.run_once_at)
+        .bind(task.updated_at)
+        .bind(&task.id)
+        .execute(&*self.db_pool)
+        .await?;
+
+        info!("Updated task {} - last_run: {:?}, next_run: {:?}", 
+              task.id, task.last_run, task.next_run);
+
+        Ok(())
+    }
+
+    /// Create a backup job for `task` and start it locally or hand it to the task's agent
+    async fn start_backup_job(&self, task: &Task, attempt: i32) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let logging_service = LoggingService::new(self.db_pool.clone());
+
         // Get database info for this task
         let db_config: DatabaseConfig = sqlx::query_as(
             "SELECT * FROM database_configs WHERE id = ?"
@@ -229,12 +402,13 @@ impl TaskWorker {
             backup_path: None,
         };
 
-        let job = Job::new(job_request);
+        let mut job = Job::new(job_request);
+        job.attempt = attempt;
 
         // Insert the job into database
         sqlx::query(
-            "INSERT INTO jobs (id, task_id, used_database, job_type, status, progress, started_at, completed_at, error_message, log_output, backup_path, created_at) 
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            "INSERT INTO jobs (id, task_id, used_database, job_type, status, progress, started_at, completed_at, error_message, log_output, backup_path, attempt, created_at) 
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
         .bind(&job.id)
         .bind(&job.task_id)
@@ -247,12 +421,18 @@ impl TaskWorker {
         .bind(&job.error_message)
         .bind(&job.log_output)
         .bind(&job.backup_path)
+        .bind(job.attempt)
         .bind(&job.created_at)
         .execute(&*self.db_pool)
         .await?;
 
         info!("Created job {} for task {}", job.id, task.id);
-        let _ = logging_service.log_job(&job.id, &format!("Job created for task '{}'", task.name), LogLevel::Info).await;
+        let message = if attempt > 1 {
+            format!("Job created for task '{}', attempt {}", task.name, attempt)
+        } else {
+            format!("Job created for task '{}'", task.name)
+        };
+        let _ = logging_service.log_job(&job.id, &message, LogLevel::Info).await;
 
         // Get the database config for this task
         let db_config: DatabaseConfig = sqlx::query_as(
@@ -263,16 +443,45 @@ impl TaskWorker {
         .await?
         .ok_or_else(|| "Database configuration not found".to_string())?;
 
+        if let Some(agent_id) = &db_config.agent_id {
+            // The database is only reachable from its agent, which picks the job up on its next poll
+            if let Err(e) = agents::dispatch(&self.db_pool, &job.id, agent_id).await {
+                error!("Failed to dispatch job {} to agent {}: {}", job.id, agent_id, e);
+                let _ = agents::fail_job(&self.db_pool, &job, &format!("Failed to dispatch to agent: {}", e)).await;
+            }
+        } else if self.has_free_slot(&db_config).await? {
+            self.spawn_backup(job.id.clone(), task.clone(), db_config).await?;
+        } else {
+            info!("Job {} queued, concurrency limit reached or {} busy", job.id, db_config.name);
+            let _ = logging_service.log_job(&job.id, "Queued until a backup slot frees up", LogLevel::Info).await;
+            // The next round through the queue gives the job its position
+            self.wake();
+        }
+
+        Ok(())
+    }
+
+    /// Run a backup job on this host in the background
+    async fn spawn_backup(&self, job_id: String, task: Task, db_config: DatabaseConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        // Count the job against the limits before the spawned process gets to update it, unless it was cancelled meanwhile
+        let started = sqlx::query("UPDATE jobs SET status = ?, started_at = ?, queue_position = NULL WHERE id = ? AND status = 'pending'")
+            .bind("running")
+            .bind(Utc::now())
+            .bind(&job_id)
+            .execute(&*self.db_pool)
+            .await?
+            .rows_affected();
+        if started == 0 {
+            return Ok(());
+        }
+
         // Start the backup process asynchronously
         let db_pool = self.db_pool.clone();
-        let job_id = job.id.clone();
-        let task_clone = task.clone();
-        let db_config_clone = db_config.clone();
+        let task_clone = task;
+        let db_config_clone = db_config;
+        let mydumper_service = MydumperService::new(self.config.backup_dir.clone(), self.config.log_dir.clone());
 
         tokio::spawn(async move {
-            let backup_dir = std::env::var("BACKUP_DIR").unwrap_or_else(|_| "data/backups".to_string());
-            let log_dir = std::env::var("LOG_DIR").unwrap_or_else(|_| "data/logs".to_string());
-            let mydumper_service = MydumperService::new(backup_dir, log_dir);
             let logging_service = LoggingService::new(db_pool.clone());
 
             // Determine the database name to use
@@ -285,7 +494,7 @@ impl TaskWorker {
                         None => {
                             error!("No database name specified for task {} and config has no default database", task_clone.id);
                             let _ = logging_service.log_job(&job_id, "No database name specified for task and config has no default database", LogLevel::Error).await;
-                            
+                        
                             // Update job as failed
                             let _ = sqlx::query("UPDATE jobs SET status = ?, completed_at = ?, error_message = ? WHERE id = ?")
                                 .bind("failed")
@@ -294,6 +503,8 @@ impl TaskWorker {
                                 .bind(&job_id)
                                 .execute(&*db_pool)
                                 .await;
+                            events::publish(AppEvent::job_status(&job_id, "failed"));
+                            let _ = record_task_result(&db_pool, &task_clone.id, false).await;
                             return;
                         }
                     }
@@ -303,12 +514,18 @@ impl TaskWorker {
             let result = mydumper_service
                 .create_backup_with_progress(&db_config_clone, &database_name, &task_clone, job_id.clone(), &db_pool)
                 .await;
+            // The backup mostly announces its outcome itself, which mustn't reach subscribers twice
+            let announced: Option<String> = sqlx::query_scalar("SELECT status FROM jobs WHERE id = ?")
+                .bind(&job_id)
+                .fetch_optional(&*db_pool)
+                .await
+                .unwrap_or(None);
 
             match result {
                 Ok(backup_file_path) => {
                     info!("Backup created successfully: {}", backup_file_path);
                     let _ = logging_service.log_job(&job_id, &format!("Backup completed successfully: {}", backup_file_path), LogLevel::Info).await;
-                    
+                
                     // Update job as completed
                     let _ = sqlx::query("UPDATE jobs SET status = ?, completed_at = ?, progress = ?, backup_path = ? WHERE id = ?")
                         .bind("completed")
@@ -318,11 +535,15 @@ impl TaskWorker {
                         .bind(&job_id)
                         .execute(&*db_pool)
                         .await;
+                    if announced.as_deref() != Some("completed") {
+                        events::publish(AppEvent::job_status(&job_id, "completed"));
+                    }
+                    let _ = record_task_result(&db_pool, &task_clone.id, true).await;
                 }
                 Err(e) => {
                     error!("Backup job {} failed: {}", job_id, e);
                     let _ = logging_service.log_job(&job_id, &format!("Backup failed: {}", e), LogLevel::Error).await;
-                    
+                
                     // Update job status to failed
                     let _ = sqlx::query("UPDATE jobs SET status = ?, error_message = ?, completed_at = ? WHERE id = ?")
                         .bind("failed")
@@ -331,25 +552,330 @@ impl TaskWorker {
                         .bind(&job_id)
                         .execute(&*db_pool)
                         .await;
+                    if announced.as_deref() != Some("failed") {
+                        events::publish(AppEvent::job_status(&job_id, "failed"));
+                    }
+                    let _ = record_failed_attempt(&db_pool, &job_id).await;
                 }
             }
         });
 
-        // Update task's last_run and next_run
-        task.mark_executed()?;
-        sqlx::query(
-            "UPDATE tasks SET last_run = ?, next_run = ?, updated_at = ? WHERE id = ?"
+        Ok(())
+    }
+
+    /// Run a queued restore job on this host in the background
+    async fn spawn_restore(&self, job: Job, target_config: DatabaseConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        // Count the job against the limits before the restore gets going, unless it was cancelled meanwhile
+        let started = sqlx::query("UPDATE jobs SET status = ?, started_at = ?, queue_position = NULL WHERE id = ? AND status = 'pending'")
+            .bind("running")
+            .bind(Utc::now())
+            .bind(&job.id)
+            .execute(&*self.db_pool)
+            .await?
+            .rows_affected();
+        if started == 0 {
+            return Ok(());
+        }
+        events::publish(AppEvent::job_status(&job.id, "running"));
+        let cancellation = cancellation::register(&job.id);
+
+        let db_pool = self.db_pool.clone();
+        let backup_service = FilesystemBackupService::new(self.config.backup_dir.clone());
+        let mydumper_service = MydumperService::new(self.config.backup_dir.clone(), self.config.log_dir.clone());
+
+        tokio::spawn(async move {
+            let logging_service = LoggingService::new(db_pool.clone());
+            let mut target_database = None;
+            let restore = async {
+                let req: RestoreRequest = serde_json::from_str(job.restore_request.as_deref().unwrap_or_default())
+                    .map_err(|e| anyhow::anyhow!("Restore job has no valid restore request: {}", e))?;
+                target_database = req.new_database_name.clone();
+                let backup = backup_service.find_backup(job.source_backup_id.as_deref().unwrap_or_default()).await?
+                    .ok_or_else(|| anyhow::anyhow!("Backup was deleted while the restore was queued"))?;
+                mydumper_service.restore_backup(&target_config, &backup.file_path, &req).await?;
+                if let Some(until) = req.point_in_time {
+                    // Binlog events are filtered by the database that was dumped
+                    let metadata = backup_service.load_backup_metadata(std::path::Path::new(&backup.meta_path)).await?;
+                    let source_database = metadata.source_database();
+                    let target_database = req.new_database_name.as_deref().unwrap_or(source_database);
+                    binlog::replay(&target_config, &backup, source_database, target_database, until).await
+                        .map_err(|e| anyhow::anyhow!("Point-in-time replay failed: {}", e))?;
+                }
+                Ok::<_, anyhow::Error>(())
+            };
+            // Dropping the restore on cancellation kills myloader and removes its extraction
+            let result = tokio::select! {
+                result = restore => result,
+                _ = cancellation.cancelled() => Err(anyhow::anyhow!("Restore cancelled")),
+            };
+
+            match result {
+                Err(_) if cancellation.is_cancelled() => {
+                    info!("Restore job {} cancelled", job.id);
+                    let _ = logging_service.log_job(&job.id, "Restore cancelled by user", LogLevel::Warn).await;
+                    if let Some(database) = target_database.filter(|_| cancellation.drop_database()) {
+                        match restore_target::drop_database(&target_config, &database).await {
+                            Ok(()) => {
+                                let _ = logging_service.log_job(&job.id, &format!("Dropped partially restored database {}", database), LogLevel::Info).await;
+                            }
+                            Err(e) => {
+                                warn!("Failed to drop partially restored database {}: {}", database, e);
+                                let _ = logging_service.log_job(&job.id, &format!("Failed to drop partially restored database {}: {}", database, e), LogLevel::Error).await;
+                            }
+                        }
+                    }
+                }
+                Ok(()) => {
+                    let _ = logging_service.log_job(&job.id, "Restore completed successfully", LogLevel::Info).await;
+                    let _ = sqlx::query("UPDATE jobs SET status = ?, completed_at = ?, progress = ? WHERE id = ? AND status = 'running'")
+                        .bind("completed")
+                        .bind(Utc::now())
+                        .bind(100)
+                        .bind(&job.id)
+                        .execute(&*db_pool)
+                        .await;
+                    events::publish(AppEvent::job_status(&job.id, "completed"));
+                }
+                Err(e) => {
+                    error!("Restore job {} failed: {}", job.id, e);
+                    let _ = logging_service.log_job(&job.id, &format!("Restore failed: {}", e), LogLevel::Error).await;
+                    let _ = sqlx::query("UPDATE jobs SET status = ?, error_message = ?, completed_at = ? WHERE id = ? AND status = 'running'")
+                        .bind("failed")
+                        .bind(e.to_string())
+                        .bind(Utc::now())
+                        .bind(&job.id)
+                        .execute(&*db_pool)
+                        .await;
+                    events::publish(AppEvent::job_status(&job.id, "failed"));
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Stop starting jobs and give the running local backups and restores up to `timeout` to finish.
+    /// Those still running afterwards are interrupted, as if the next start found them.
+    pub async fn drain(&self, timeout: Duration) {
+        self.draining.store(true, Ordering::SeqCst);
+        self.wake();
+
+        let deadline = Instant::now() + timeout;
+        let mut reported = false;
+        loop {
+            let running: i64 = match sqlx::query_scalar(
+                "SELECT COUNT(*) FROM jobs WHERE job_type IN ('backup', 'restore') AND agent_id IS NULL AND status IN ('running', 'compressing', 'uploading')"
+            )
+            .fetch_one(&*self.db_pool)
+            .await
+            {
+                Ok(running) => running,
+                Err(e) => {
+                    error!("Failed to count running jobs: {}", e);
+                    break;
+                }
+            };
+            if running == 0 {
+                return;
+            }
+            if Instant::now() >= deadline {
+                warn!("{} job(s) still running after {}s, interrupting them", running, timeout.as_secs());
+                break;
+            }
+            if !reported {
+                info!("Waiting up to {}s for {} running job(s) to finish", timeout.as_secs(), running);
+                reported = true;
+            }
+            sleep(Duration::from_secs(1)).await;
+        }
+
+        if let Err(e) = self.interrupt_running_jobs("Interrupted by a shutdown of rDumper").await {
+            error!("Failed to mark interrupted jobs: {}", e);
+        }
+    }
+
+    /// Fail local backup and restore jobs that can't finish in this process, so they don't hold slots forever.
+    /// Backups whose dump already has complete tables are queued again to dump only the missing ones.
+    async fn interrupt_running_jobs(&self, reason: &str) -> Result<(), sqlx::Error> {
+        let backups: Vec<String> = sqlx::query_scalar(
+            "SELECT id FROM jobs WHERE job_type = 'backup' AND agent_id IS NULL AND status IN ('running', 'compressing')"
         )
-        .bind(&task.last_run)
-        .bind(&task.next_run)
-        .bind(&task.updated_at)
-        .bind(&task.id)
+        .fetch_all(&*self.db_pool)
+        .await?;
+        let mydumper_service = MydumperService::new(self.config.backup_dir.clone(), self.config.log_dir.clone());
+        for job_id in backups {
+            let root_dir = match mydumper_service.find_resumable_dump(&self.db_pool, &job_id).await {
+                Ok(Some(root_dir)) => root_dir,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Failed to check whether job {} can resume: {}", job_id, e);
+                    continue;
+                }
+            };
+            sqlx::query("UPDATE jobs SET status = 'pending', resume_dir = ?, progress = 0 WHERE id = ?")
+                .bind(root_dir.to_string_lossy().to_string())
+                .bind(&job_id)
+                .execute(&*self.db_pool)
+                .await?;
+            info!("Interrupted backup job {} will resume from {}", job_id, root_dir.display());
+        }
+
+        let interrupted = sqlx::query(
+            "UPDATE jobs SET status = 'failed', error_message = ?, completed_at = ?
+             WHERE job_type IN ('backup', 'restore') AND agent_id IS NULL AND status IN ('running', 'compressing', 'uploading')"
+        )
+        .bind(reason)
+        .bind(Utc::now())
         .execute(&*self.db_pool)
+        .await?
+        .rows_affected();
+        if interrupted > 0 {
+            warn!("Marked {} interrupted backup or restore job(s) as failed", interrupted);
+        }
+        Ok(())
+    }
+
+    /// Whether another backup or restore may start against a database config within the concurrency
+    /// limits. Jobs of one config always run one at a time, so a server isn't dumped twice at once.
+    async fn has_free_slot(&self, db_config: &DatabaseConfig) -> Result<bool, sqlx::Error> {
+        let (max_total, max_per_host) = (self.config.max_concurrent_jobs as i64, self.config.max_jobs_per_host as i64);
+        let (running, on_host, on_config): (i64, i64, i64) = sqlx::query_as(
+            "SELECT COUNT(*), COALESCE(SUM(dc.host = ?), 0), COALESCE(SUM(dc.id = ?), 0) FROM jobs j
+             LEFT JOIN tasks t ON j.task_id = t.id
+             LEFT JOIN database_configs dc ON COALESCE(t.database_config_id, j.target_config_id) = dc.id
+             WHERE j.job_type IN ('backup', 'restore') AND j.agent_id IS NULL AND j.status IN ('running', 'compressing', 'uploading')"
+        )
+        .bind(&db_config.host)
+        .bind(&db_config.id)
+        .fetch_one(&*self.db_pool)
         .await?;
+        Ok(on_config == 0 && (max_total == 0 || running < max_total) && (max_per_host == 0 || on_host < max_per_host))
+    }
 
-        info!("Updated task {} - last_run: {:?}, next_run: {:?}", 
-              task.id, task.last_run, task.next_run);
+    /// Record a job's place among the jobs waiting for the same database config, 1 starting next
+    async fn set_queue_position(&self, job_id: &str, config_id: &str, positions: &mut HashMap<String, i64>) -> Result<(), sqlx::Error> {
+        let position = positions.entry(config_id.to_string()).or_insert(0);
+        *position += 1;
+        sqlx::query("UPDATE jobs SET queue_position = ? WHERE id = ?")
+            .bind(*position)
+            .bind(job_id)
+            .execute(&*self.db_pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Start queued backup and restore jobs, oldest first, as far as the concurrency limits allow
+    async fn start_queued_jobs(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let queued: Vec<Job> = sqlx::query_as(
+            "SELECT * FROM jobs WHERE status = 'pending' AND job_type IN ('backup', 'restore') AND agent_id IS NULL ORDER BY priority DESC, created_at"
+        )
+        .fetch_all(&*self.db_pool)
+        .await?;
+
+        let mut positions = HashMap::new();
+        for job in queued {
+            if job.job_type == JobType::Restore.to_string() {
+                let target_config: Option<DatabaseConfig> = sqlx::query_as("SELECT * FROM database_configs WHERE id = ?")
+                    .bind(&job.target_config_id)
+                    .fetch_optional(&*self.db_pool)
+                    .await?;
+                let Some(target_config) = target_config else {
+                    sqlx::query("UPDATE jobs SET status = ?, error_message = ?, completed_at = ? WHERE id = ?")
+                        .bind("cancelled")
+                        .bind("Target database configuration was deleted while the restore was queued")
+                        .bind(Utc::now())
+                        .bind(&job.id)
+                        .execute(&*self.db_pool)
+                        .await?;
+                    events::publish(AppEvent::job_status(&job.id, "cancelled"));
+                    continue;
+                };
+                if self.has_free_slot(&target_config).await? {
+                    info!("Starting queued restore job {} into {}", job.id, target_config.name);
+                    self.spawn_restore(job, target_config).await?;
+                } else {
+                    self.set_queue_position(&job.id, &target_config.id, &mut positions).await?;
+                }
+                continue;
+            }
+
+            let task: Option<Task> = sqlx::query_as("SELECT * FROM tasks WHERE id = ?")
+                .bind(&job.task_id)
+                .fetch_optional(&*self.db_pool)
+                .await?;
+            let db_config: Option<DatabaseConfig> = match &task {
+                Some(task) => sqlx::query_as("SELECT * FROM database_configs WHERE id = ?")
+                    .bind(&task.database_config_id)
+                    .fetch_optional(&*self.db_pool)
+                    .await?,
+                None => None,
+            };
+            let (Some(task), Some(db_config)) = (task, db_config) else {
+                sqlx::query("UPDATE jobs SET status = ?, error_message = ?, completed_at = ? WHERE id = ?")
+                    .bind("cancelled")
+                    .bind("Task or database configuration was deleted while the job was queued")
+                    .bind(Utc::now())
+                    .bind(&job.id)
+                    .execute(&*self.db_pool)
+                    .await?;
+                events::publish(AppEvent::job_status(&job.id, "cancelled"));
+                continue;
+            };
 
+            if self.has_free_slot(&db_config).await? {
+                info!("Starting queued job {} of task {}", job.id, task.id);
+                self.spawn_backup(job.id.clone(), task, db_config).await?;
+            } else {
+                self.set_queue_position(&job.id, &db_config.id, &mut positions).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Start the retries of failed backup jobs whose backoff has passed
+    async fn start_due_retries(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let due: Vec<Job> = sqlx::query_as(
+            "SELECT * FROM jobs WHERE status = 'failed' AND retry_at IS NOT NULL AND retry_at <= ?"
+        )
+        .bind(Utc::now())
+        .fetch_all(&*self.db_pool)
+        .await?;
+
+        for job in due {
+            sqlx::query("UPDATE jobs SET retry_at = NULL WHERE id = ?")
+                .bind(&job.id)
+                .execute(&*self.db_pool)
+                .await?;
+            let Some(task_id) = &job.task_id else {
+                continue;
+            };
+            let task: Option<Task> = sqlx::query_as("SELECT * FROM tasks WHERE id = ?")
+                .bind(task_id)
+                .fetch_optional(&*self.db_pool)
+                .await?;
+            let Some(task) = task.filter(|task| task.is_active) else {
+                continue;
+            };
+
+            // A newer run of the task makes the retry pointless
+            let (newer_jobs,): (i64,) = sqlx::query_as(
+                "SELECT COUNT(*) FROM jobs WHERE task_id = ? AND job_type = 'backup' AND created_at > ?"
+            )
+            .bind(task_id)
+            .bind(job.created_at)
+            .fetch_one(&*self.db_pool)
+            .await?;
+            if newer_jobs > 0 {
+                let logging_service = LoggingService::new(self.db_pool.clone());
+                let _ = logging_service.log_job(&job.id, "Retry skipped, the task has run again since", LogLevel::Info).await;
+                continue;
+            }
+
+            info!("Retrying failed job {} of task {} (attempt {})", job.id, task.id, job.attempt + 1);
+            if let Err(e) = self.start_backup_job(&task, job.attempt + 1).await {
+                error!("Failed to retry job {}: {}", job.id, e);
+            }
+        }
         Ok(())
     }
 
@@ -403,6 +929,114 @@ impl TaskWorker {
             }
         }
 
+        // Clean up finished jobs past the configured history retention
+        let (days, max_jobs) = (self.config.job_retention_days, self.config.job_retention_max);
+        if days > 0 || max_jobs > 0 {
+            match job_history::enforce_retention(&self.db_pool, &self.config.log_dir, days, max_jobs).await {
+                Ok(deleted) if deleted > 0 => {
+                    info!("Deleted {} jobs past the history retention", deleted);
+                    let _ = logging_service.log_worker(
+                        &format!("Deleted {} jobs past the history retention", deleted),
+                        LogLevel::Info
+                    ).await;
+                }
+                Ok(_) => {}
+                Err(e) => error!("Failed to enforce job history retention: {}", e),
+            }
+        }
+
+        // Clean up resumable uploads that were abandoned for more than a day
+        let upload_sessions = UploadSessionStore::new(&self.config.temp_dir);
+        match upload_sessions.cleanup_stale(chrono::Duration::hours(24)).await {
+            Ok(removed) if removed > 0 => info!("Removed {} abandoned uploads", removed),
+            Ok(_) => {}
+            Err(e) => error!("Failed to clean up abandoned uploads: {}", e),
+        }
+
+        Ok(())
+    }
+
+    /// Alert on running backups that take much longer than the task's historical p95
+    async fn check_job_durations(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let running: Vec<(String, String, DateTime<Utc>)> = sqlx::query_as(
+            "SELECT id, task_id, started_at FROM jobs WHERE status = 'running' AND job_type = 'backup' AND task_id IS NOT NULL AND started_at IS NOT NULL"
+        )
+        .fetch_all(&*self.db_pool)
+        .await?;
+
+        // Forget jobs that finished; nothing left to alert on
+        let running_ids: HashSet<&String> = running.iter().map(|(id, _, _)| id).collect();
+        self.overrun_jobs.lock().unwrap().retain(|id| running_ids.contains(id));
+
+        let now = Utc::now();
+        for (job_id, task_id, started_at) in &running {
+            if self.overrun_jobs.lock().unwrap().contains(job_id) {
+                continue;
+            }
+
+            let history: Vec<(DateTime<Utc>, DateTime<Utc>)> = sqlx::query_as(
+                "SELECT started_at, completed_at FROM jobs WHERE task_id = ? AND job_type = 'backup' AND status = 'completed' AND started_at IS NOT NULL AND completed_at IS NOT NULL ORDER BY completed_at DESC LIMIT ?"
+            )
+            .bind(task_id)
+            .bind(crate::services::anomaly::DURATION_HISTORY)
+            .fetch_all(&*self.db_pool)
+            .await?;
+            let durations: Vec<i64> = history.iter()
+                .map(|(started, completed)| (*completed - *started).num_seconds().max(0))
+                .collect();
+            let Some(p95) = crate::services::anomaly::duration_p95(&durations) else {
+                continue;
+            };
+
+            let running_seconds = (now - *started_at).num_seconds();
+            if running_seconds <= crate::services::anomaly::duration_limit(p95) {
+                continue;
+            }
+
+            self.overrun_jobs.lock().unwrap().insert(job_id.clone());
+            let message = format!(
+                "Backup running for {} min, usually done within {} min (p95)",
+                running_seconds / 60,
+                (p95 + 59) / 60
+            );
+            warn!("Job {}: {}", job_id, message);
+            let logging_service = LoggingService::new(self.db_pool.clone());
+            let _ = logging_service.log_job(job_id, &message, LogLevel::Warn).await;
+            events::publish(AppEvent::JobDurationAnomaly {
+                job_id: job_id.clone(),
+                task_id: task_id.clone(),
+                running_seconds,
+                expected_seconds: p95,
+            });
+        }
+        Ok(())
+    }
+
+    /// Log and publish databases that started or stopped violating their RPO target
+    async fn check_rpo_targets(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let backup_service = crate::services::FilesystemBackupService::new(self.config.backup_dir.clone());
+        let changes = crate::services::sla::evaluate_changes(&self.db_pool, &backup_service).await?;
+        let logging_service = LoggingService::new(self.db_pool.clone());
+
+        for status in changes {
+            let (message, level) = if status.violated {
+                let last_backup = match status.last_backup_at {
+                    Some(_) => format!("last successful backup {:.1}h ago", status.age_hours),
+                    None => format!("no successful backup for {:.1}h", status.age_hours),
+                };
+                warn!("RPO violated for '{}': {} (target {}h)", status.name, last_backup, status.rpo_hours);
+                (format!("RPO violated: {}, target is {}h", last_backup, status.rpo_hours), LogLevel::Warn)
+            } else {
+                info!("RPO restored for '{}'", status.name);
+                (format!("RPO met again: last successful backup {:.1}h ago", status.age_hours), LogLevel::Info)
+            };
+            let _ = logging_service.log_connection(&status.database_config_id, &message, level).await;
+            events::publish(AppEvent::RpoStatusChanged {
+                database_config_id: status.database_config_id,
+                violated: status.violated,
+                age_hours: status.age_hours,
+            });
+        }
         Ok(())
     }
 
@@ -412,7 +1046,7 @@ impl TaskWorker {
         use tokio::fs;
         use chrono::Utc;
         
-        let backup_dir = "./data/backups";
+        let backup_dir = self.config.backup_dir.as_str();
         if !Path::new(backup_dir).exists() {
             return Ok(0);
         }
@@ -470,8 +1104,129 @@ impl TaskWorker {
                     }
                 }
             }
+
+            // Copies pushed to a remote destination expire on the same schedule
+            let remote = async {
+                match remote_storage::task_target(&self.db_pool, &task).await? {
+                    Some(target) => remote_storage::cleanup_expired(&target, &task.id, cutoff_date).await,
+                    None => Ok(0),
+                }
+            }.await;
+            match remote {
+                Ok(count) => deleted_count += count,
+                Err(e) => warn!("Failed to clean up remote backups of task {}: {}", task.name, e),
+            }
+        }
+
+        if deleted_count > 0 {
+            crate::services::FilesystemBackupService::invalidate_all_caches().await;
         }
 
         Ok(deleted_count)
     }
 }
+
+/// Track a task's failure streak after one of its backup jobs finished.
+///
+/// Tasks with `pause_after_failures` set are disabled once the streak reaches it,
+/// so a broken task stops producing a failed job on every scheduled run.
+pub async fn record_task_result(db_pool: &SqlitePool, task_id: &str, succeeded: bool) -> Result<(), sqlx::Error> {
+    if succeeded {
+        sqlx::query("UPDATE tasks SET consecutive_failures = 0 WHERE id = ?")
+            .bind(task_id)
+            .execute(db_pool)
+            .await?;
+        return Ok(());
+    }
+
+    sqlx::query("UPDATE tasks SET consecutive_failures = consecutive_failures + 1 WHERE id = ?")
+        .bind(task_id)
+        .execute(db_pool)
+        .await?;
+
+    let now = Utc::now();
+    let paused = sqlx::query(
+        "UPDATE tasks SET is_active = false, next_run = NULL, auto_paused_at = ?, updated_at = ?
+         WHERE id = ? AND is_active = true AND pause_after_failures > 0 AND consecutive_failures >= pause_after_failures"
+    )
+    .bind(now)
+    .bind(now)
+    .bind(task_id)
+    .execute(db_pool)
+    .await?
+    .rows_affected() > 0;
+
+    if paused {
+        let (name, consecutive_failures): (String, i32) = sqlx::query_as(
+            "SELECT name, consecutive_failures FROM tasks WHERE id = ?"
+        )
+        .bind(task_id)
+        .fetch_one(db_pool)
+        .await?;
+
+        warn!("Task '{}' paused after {} consecutive failures", name, consecutive_failures);
+        let logging_service = LoggingService::new(Arc::new(db_pool.clone()));
+        let _ = logging_service.log_task(
+            task_id,
+            &format!("Task paused automatically after {} consecutive failed runs", consecutive_failures),
+            LogLevel::Warn,
+        ).await;
+        events::publish(AppEvent::TaskAutoPaused { task_id: task_id.to_string(), consecutive_failures });
+    }
+    Ok(())
+}
+
+/// Handle a failed backup job: schedule its retry if the task has attempts left,
+/// otherwise count the run as failed towards the task's failure streak.
+///
+/// Only the last attempt of a run counts, so retried runs that recover don't break a streak of successes.
+pub async fn record_failed_attempt(db_pool: &SqlitePool, job_id: &str) -> Result<(), sqlx::Error> {
+    let job: Option<Job> = sqlx::query_as("SELECT * FROM jobs WHERE id = ?")
+        .bind(job_id)
+        .fetch_optional(db_pool)
+        .await?;
+    let Some(task_id) = job.as_ref().and_then(|job| job.task_id.clone()) else {
+        return Ok(());
+    };
+    let attempt = job.map(|job| job.attempt).unwrap_or(1);
+    let task: Option<Task> = sqlx::query_as("SELECT * FROM tasks WHERE id = ?")
+        .bind(&task_id)
+        .fetch_optional(db_pool)
+        .await?;
+
+    let Some((task, delay)) = task.and_then(|task| task.retry_delay(attempt).map(|delay| (task, delay))) else {
+        return record_task_result(db_pool, &task_id, false).await;
+    };
+    let retry_at = Utc::now() + delay;
+    sqlx::query("UPDATE jobs SET retry_at = ? WHERE id = ?")
+        .bind(retry_at)
+        .bind(job_id)
+        .execute(db_pool)
+        .await?;
+
+    let message = format!(
+        "Attempt {} of {} failed, retrying at {}",
+        attempt,
+        task.retry_max_attempts.unwrap_or(attempt),
+        retry_at.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    info!("Job {}: {}", job_id, message);
+    let logging_service = LoggingService::new(Arc::new(db_pool.clone()));
+    let _ = logging_service.log_job(job_id, &message, LogLevel::Warn).await;
+    // Also wakes the worker, which may be sleeping past `retry_at`
+    events::publish(AppEvent::JobRetryScheduled { job_id: job_id.to_string(), task_id, attempt, retry_at });
+    Ok(())
+}
+
+/// Resolves once a job finished, which may free a slot for a queued job, or a retry was scheduled
+async fn slot_or_retry_event(events: &mut broadcast::Receiver<AppEvent>) {
+    loop {
+        match events.recv().await {
+            Ok(AppEvent::JobRetryScheduled { .. }) => return,
+            Ok(AppEvent::JobStatusChanged { status, .. }) if matches!(status.as_str(), "completed" | "failed" | "cancelled") => return,
+            Err(broadcast::error::RecvError::Closed) => std::future::pending().await,
+            // Missed events only delay the retry until the next tick
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Finished transfers stay visible this long so the UI can show their final state
+const FINISHED_RETENTION_SECS: i64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferKind {
+    Upload,
+    Download,
+    Import,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferStatus {
+    Active,
+    Completed,
+    Failed,
+    Interrupted,
+}
+
+/// Lightweight, in-memory progress record for an upload or download
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Transfer {
+    pub id: String,
+    pub kind: TransferKind,
+    pub name: String,
+    pub backup_id: Option<String>,
+    pub bytes_transferred: u64,
+    pub total_bytes: Option<u64>,
+    pub status: TransferStatus,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Transfer {
+    pub fn progress(&self) -> Option<u32> {
+        self.total_bytes
+            .filter(|total| *total > 0)
+            .map(|total| ((self.bytes_transferred.min(total) * 100) / total) as u32)
+    }
+}
+
+/// In-flight and recently finished transfers keyed by id
+fn transfers() -> &'static Mutex<HashMap<String, Transfer>> {
+    static TRANSFERS: OnceLock<Mutex<HashMap<String, Transfer>>> = OnceLock::new();
+    TRANSFERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Register a transfer (or reset an existing one with the same id, e.g. a resumed upload)
+pub fn register(id: &str, kind: TransferKind, name: &str, backup_id: Option<&str>, total_bytes: Option<u64>) {
+    let now = Utc::now();
+    let mut transfers = transfers().lock().unwrap();
+    prune(&mut transfers);
+    transfers.insert(id.to_string(), Transfer {
+        id: id.to_string(),
+        kind,
+        name: name.to_string(),
+        backup_id: backup_id.map(|id| id.to_string()),
+        bytes_transferred: 0,
+        total_bytes,
+        status: TransferStatus::Active,
+        started_at: now,
+        updated_at: now,
+    });
+}
+
+/// Set the absolute number of bytes transferred so far
+pub fn set_progress(id: &str, bytes: u64) {
+    if let Some(transfer) = transfers().lock().unwrap().get_mut(id) {
+        transfer.bytes_transferred = bytes;
+        transfer.status = TransferStatus::Active;
+        transfer.updated_at = Utc::now();
+    }
+}
+
+/// Add to the number of bytes transferred
+pub fn add_progress(id: &str, bytes: u64) {
+    if let Some(transfer) = transfers().lock().unwrap().get_mut(id) {
+        transfer.bytes_transferred += bytes;
+        transfer.updated_at = Utc::now();
+    }
+}
+
+pub fn finish(id: &str, status: TransferStatus) {
+    if let Some(transfer) = transfers().lock().unwrap().get_mut(id) {
+        transfer.status = status;
+        transfer.updated_at = Utc::now();
+    }
+}
+
+/// All active transfers plus those finished within the retention window, newest first
+pub fn list() -> Vec<Transfer> {
+    let mut transfers = transfers().lock().unwrap();
+    prune(&mut transfers);
+    let mut list: Vec<Transfer> = transfers.values().cloned().collect();
+    list.sort_by_key(|t| std::cmp::Reverse(t.started_at));
+    list
+}
+
+pub fn get(id: &str) -> Option<Transfer> {
+    transfers().lock().unwrap().get(id).cloned()
+}
+
+fn prune(transfers: &mut HashMap<String, Transfer>) {
+    let cutoff = Utc::now() - Duration::seconds(FINISHED_RETENTION_SECS);
+    transfers.retain(|_, t| t.status == TransferStatus::Active || t.updated_at >= cutoff);
+}
+
+/// Tracks a single-request transfer; marks it completed or interrupted when dropped
+pub struct TransferGuard {
+    id: String,
+    finished: bool,
+}
+
+impl TransferGuard {
+    pub fn start(kind: TransferKind, name: &str, backup_id: Option<&str>, total_bytes: Option<u64>) -> Self {
+        let id = uuid::Uuid::new_v4().to_string();
+        register(&id, kind, name, backup_id, total_bytes);
+        Self { id, finished: false }
+    }
+
+    pub fn add(&self, bytes: u64) {
+        add_progress(&self.id, bytes);
+    }
+
+    pub fn complete(mut self) {
+        self.finished = true;
+        finish(&self.id, TransferStatus::Completed);
+    }
+
+    pub fn fail(mut self) {
+        self.finished = true;
+        finish(&self.id, TransferStatus::Failed);
+    }
+}
+
+impl Drop for TransferGuard {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        // Streams are dropped both on completion and on client disconnect; compare byte counts
+        let status = match get(&self.id) {
+            Some(t) if t.total_bytes.is_some_and(|total| t.bytes_transferred >= total) => TransferStatus::Completed,
+            _ => TransferStatus::Interrupted,
+        };
+        finish(&self.id, status);
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+/// Resumable upload state persisted next to the partial file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadSession {
+    pub id: String,
+    pub filename: String,
+    pub database_config_id: String,
+    pub compression_type: String,
+    pub total_size: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Why a chunk was not appended
+#[derive(Debug)]
+pub enum ChunkError {
+    /// The client's offset doesn't match what is on disk; it should re-query and resume
+    OffsetMismatch { expected: u64 },
+    /// Another request is currently writing to this upload
+    Busy,
+    /// The chunk would grow the upload past its declared size
+    TooLarge,
+    Io(anyhow::Error),
+}
+
+/// Upload IDs that currently have a chunk being written
+fn active_uploads() -> &'static Mutex<HashSet<String>> {
+    static ACTIVE: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    ACTIVE.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// Releases the per-upload write lock when the chunk request ends
+struct ActiveUpload(String);
+
+impl Drop for ActiveUpload {
+    fn drop(&mut self) {
+        active_uploads().lock().unwrap().remove(&self.0);
+    }
+}
+
+/// File-backed store for resumable (chunked) archive uploads
+#[derive(Clone)]
+pub struct UploadSessionStore {
+    upload_dir: PathBuf,
+}
+
+impl UploadSessionStore {
+    pub fn new(temp_dir: &str) -> Self {
+        Self { upload_dir: Path::new(temp_dir).join("rdumper-uploads") }
+    }
+
+    /// Register a new upload and create its empty partial file
+    pub async fn create(&self, session: &UploadSession) -> Result<()> {
+        fs::create_dir_all(&self.upload_dir).await?;
+        fs::write(self.session_path(&session.id), serde_json::to_vec_pretty(session)?).await?;
+        fs::File::create(self.part_path(&session.id)).await?;
+        Ok(())
+    }
+
+    /// Load an upload session, None if it doesn't exist (or was already completed)
+    pub async fn get(&self, upload_id: &str) -> Result<Option<UploadSession>> {
+        if !Self::is_valid_id(upload_id) {
+            return Ok(None);
+        }
+        match fs::read(self.session_path(upload_id)).await {
+            Ok(content) => Ok(Some(serde_json::from_slice(&content)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Number of bytes received so far
+    pub async fn offset(&self, upload_id: &str) -> Result<u64> {
+        Ok(fs::metadata(self.part_path(upload_id)).await?.len())
+    }
+
+    /// Append a chunk at `offset`, returning the new offset
+    pub async fn append_chunk<S, B, E>(&self, session: &UploadSession, offset: u64, mut chunk: S) -> Result<u64, ChunkError>
+    where
+        S: Stream<Item = Result<B, E>> + Unpin,
+        B: AsRef<[u8]>,
+        E: std::fmt::Display,
+    {
+        if !active_uploads().lock().unwrap().insert(session.id.clone()) {
+            return Err(ChunkError::Busy);
+        }
+        let _guard = ActiveUpload(session.id.clone());
+
+        let current = self.offset(&session.id).await.map_err(ChunkError::Io)?;
+        if current != offset {
+            return Err(ChunkError::OffsetMismatch { expected: current });
+        }
+
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(self.part_path(&session.id))
+            .await
+            .map_err(|e| ChunkError::Io(e.into()))?;
+
+        let mut written = current;
+        let mut result = Ok(());
+        while let Some(data) = chunk.next().await {
+            let data = match data {
+                Ok(data) => data,
+                Err(e) => {
+                    // Keep what arrived so far; the client resumes from the new offset
+                    result = Err(ChunkError::Io(anyhow!("Upload interrupted: {}", e)));
+                    break;
+                }
+            };
+            let data = data.as_ref();
+            if written + data.len() as u64 > session.total_size {
+                result = Err(ChunkError::TooLarge);
+                break;
+            }
+            file.write_all(data).await.map_err(|e| ChunkError::Io(e.into()))?;
+            written += data.len() as u64;
+        }
+
+        file.sync_all().await.map_err(|e| ChunkError::Io(e.into()))?;
+        result.map(|_| written)
+    }
+
+    /// Path of the partial archive being assembled
+    pub fn part_path(&self, upload_id: &str) -> PathBuf {
+        self.upload_dir.join(format!("{}.part", upload_id))
+    }
+
+    /// Staging directory for loose dump files uploaded across one or more requests
+    pub async fn staging_dir(&self, upload_id: &str) -> Result<PathBuf> {
+        if !Self::is_valid_id(upload_id) {
+            return Err(anyhow!("Invalid upload id"));
+        }
+        let dir = self.upload_dir.join(format!("{}.d", upload_id));
+        fs::create_dir_all(&dir).await?;
+        Ok(dir)
+    }
+
+    /// Remove an upload's session, partial file and staging directory
+    pub async fn remove(&self, upload_id: &str) {
+        let _ = fs::remove_file(self.session_path(upload_id)).await;
+        let _ = fs::remove_file(self.part_path(upload_id)).await;
+        let _ = fs::remove_dir_all(self.upload_dir.join(format!("{}.d", upload_id))).await;
+    }
+
+    /// Remove uploads that were started more than `max_age` ago and never completed
+    pub async fn cleanup_stale(&self, max_age: chrono::Duration) -> Result<u64> {
+        let mut entries = match fs::read_dir(&self.upload_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let cutoff = Utc::now() - max_age;
+        let mut removed = 0;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+
+            // Staging directories have no session file, so go by their last write
+            if path.extension().and_then(|e| e.to_str()) == Some("d") {
+                let modified = entry.metadata().await?.modified()?;
+                if DateTime::<Utc>::from(modified) < cutoff {
+                    let _ = fs::remove_dir_all(&path).await;
+                    removed += 1;
+                }
+                continue;
+            }
+
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(upload_id) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            if let Ok(Some(session)) = self.get(upload_id).await {
+                if session.created_at < cutoff {
+                    self.remove(upload_id).await;
+                    removed += 1;
+                }
+            }
+        }
+        Ok(removed)
+    }
+
+    fn session_path(&self, upload_id: &str) -> PathBuf {
+        self.upload_dir.join(format!("{}.json", upload_id))
+    }
+
+    /// Upload IDs are generated UUIDs; reject anything that could escape the upload directory
+    fn is_valid_id(upload_id: &str) -> bool {
+        !upload_id.is_empty() && upload_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    }
+}
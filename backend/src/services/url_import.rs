@@ -0,0 +1,62 @@
+use std::path::Path;
+use anyhow::{anyhow, Result};
+use futures::StreamExt;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::watch;
+use crate::services::transfers::{TransferGuard, TransferKind};
+
+/// Validate a remote archive URL; only HTTPS is accepted (this covers S3 presigned URLs)
+pub fn validate_url(url: &str) -> Result<reqwest::Url> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| anyhow!("Invalid URL: {}", e))?;
+    if parsed.scheme() != "https" {
+        return Err(anyhow!("Only https:// URLs are supported"));
+    }
+    if parsed.host_str().is_none() {
+        return Err(anyhow!("URL has no host"));
+    }
+    Ok(parsed)
+}
+
+/// Archive file name taken from the last path segment of a URL (query strings ignored)
+pub fn filename_from_url(url: &reqwest::Url) -> Option<String> {
+    url.path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+}
+
+/// Stream a remote archive to `dest`, reporting progress (0-100) when the size is known
+pub async fn download_archive(url: reqwest::Url, dest: &Path, progress: Option<watch::Sender<u32>>) -> Result<u64> {
+    let response = reqwest::get(url).await?.error_for_status()?;
+    let total = response.content_length();
+    // Presigned URLs carry credentials in the query string, so only the file name is recorded
+    let name = filename_from_url(response.url()).unwrap_or_else(|| "remote archive".to_string());
+    let transfer = TransferGuard::start(TransferKind::Import, &name, None, total);
+
+    let mut file = tokio::fs::File::create(dest).await?;
+    let mut stream = response.bytes_stream();
+    let mut downloaded: u64 = 0;
+    let mut last_percent = 0;
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        file.write_all(&chunk).await?;
+        downloaded += chunk.len() as u64;
+        transfer.add(chunk.len() as u64);
+
+        if let (Some(progress), Some(total)) = (&progress, total) {
+            let percent = ((downloaded * 100) / total.max(1)).min(99) as u32;
+            if percent != last_percent {
+                last_percent = percent;
+                progress.send_replace(percent);
+            }
+        }
+    }
+
+    file.sync_all().await?;
+    transfer.complete();
+    if let Some(progress) = &progress {
+        progress.send_replace(100);
+    }
+    Ok(downloaded)
+}
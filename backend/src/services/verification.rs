@@ -0,0 +1,269 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+use chrono::{Duration, Utc};
+use rand::seq::SliceRandom;
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+use crate::models::{Backup, BackupVerification, CreateJobRequest, Job, JobType, LogLevel, SandboxReport, VerifyMode};
+use crate::services::{archive_format, integrity, sandbox, FilesystemBackupService, LoggingService, MydumperService};
+use crate::services::events::{self, AppEvent};
+use crate::state::AppConfig;
+
+/// What a successful verification found inside the archive
+#[derive(Debug)]
+pub struct VerifySummary {
+    pub sha256_hash: String,
+    pub files: usize,
+    pub uncompressed_bytes: u64,
+    pub sandbox: Option<SandboxReport>,
+}
+
+/// Re-hash an archive against its recorded checksum and read it end to end.
+///
+/// Reading every entry forces full decompression, which catches truncated or corrupt archives.
+pub async fn verify_archive(backup: &Backup) -> Result<VerifySummary> {
+    let path = PathBuf::from(&backup.file_path);
+    if !path.exists() {
+        return Err(anyhow!("Archive file is missing: {}", backup.file_path));
+    }
+
+    let sha256_hash = integrity::hash_file(path.clone()).await?;
+    if let Some(expected) = &backup.sha256_hash {
+        if *expected != sha256_hash {
+            return Err(anyhow!("Checksum mismatch: expected {}, got {}", expected, sha256_hash));
+        }
+    }
+
+    let entries = tokio::task::spawn_blocking(move || archive_format::list_entries(&path)).await??;
+    if entries.is_empty() {
+        return Err(anyhow!("Archive contains no files"));
+    }
+
+    Ok(VerifySummary {
+        sha256_hash,
+        files: entries.len(),
+        uncompressed_bytes: entries.iter().map(|(_, size)| size).sum(),
+        sandbox: None,
+    })
+}
+
+/// Create a verify job for `backup` and run it in the background; returns the job id
+pub async fn start_verify_job(
+    pool: &SqlitePool,
+    config: &AppConfig,
+    backup: Backup,
+    task_id: Option<String>,
+    mode: VerifyMode,
+) -> Result<String> {
+    let job = Job::new(CreateJobRequest {
+        task_id,
+        used_database: backup.used_database.clone(),
+        job_type: JobType::Verify,
+        backup_path: Some(backup.file_path.clone()),
+    });
+
+    sqlx::query(
+        r#"
+        INSERT INTO jobs (id, task_id, used_database, job_type, status, progress, started_at, completed_at, error_message, log_output, backup_path, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#
+    )
+    .bind(&job.id)
+    .bind(&job.task_id)
+    .bind(&job.used_database)
+    .bind(&job.job_type)
+    .bind(&job.status)
+    .bind(job.progress)
+    .bind(job.started_at)
+    .bind(job.completed_at)
+    .bind(&job.error_message)
+    .bind(&job.log_output)
+    .bind(&job.backup_path)
+    .bind(job.created_at)
+    .execute(pool)
+    .await?;
+
+    let pool = pool.clone();
+    let config = config.clone();
+    let job_id = job.id.clone();
+    tokio::spawn(async move {
+        run_verify_job(&pool, &config, &backup, &job_id, mode).await;
+    });
+
+    Ok(job.id)
+}
+
+async fn run_verify_job(pool: &SqlitePool, config: &AppConfig, backup: &Backup, job_id: &str, mode: VerifyMode) {
+    let logging_service = LoggingService::new(Arc::new(pool.clone()));
+    let backup_service = FilesystemBackupService::new(config.backup_dir.clone());
+
+    let _ = sqlx::query("UPDATE jobs SET status = ?, started_at = ? WHERE id = ?")
+        .bind("running")
+        .bind(Utc::now())
+        .bind(job_id)
+        .execute(pool)
+        .await;
+    events::publish(AppEvent::job_status(job_id, "running"));
+    let _ = logging_service.log_job(job_id, &format!("Verifying backup {}", backup.id), LogLevel::Info).await;
+
+    let mut result = verify_archive(backup).await;
+    if let (Ok(summary), VerifyMode::SandboxRestore) = (&mut result, mode) {
+        let _ = logging_service.log_job(job_id, &format!("Restoring into a disposable {} container", config.sandbox.image), LogLevel::Info).await;
+        let mydumper_service = MydumperService::new(config.backup_dir.clone(), config.log_dir.clone());
+        match sandbox::sandbox_restore(&config.sandbox, &backup_service, &mydumper_service, backup).await {
+            Ok(report) => summary.sandbox = Some(report),
+            Err(e) => result = Err(anyhow!("Sandbox restore failed: {}", e)),
+        }
+    }
+    if let Err(e) = record_verification(&backup_service, backup, job_id, mode, &result).await {
+        warn!("Failed to record verification result for backup {}: {}", backup.id, e);
+    }
+
+    let status = match &result {
+        Ok(summary) => {
+            info!("Backup {} verified: {} files", backup.id, summary.files);
+            let _ = logging_service.log_job(
+                job_id,
+                &format!("Verification passed: {} files, {} bytes uncompressed", summary.files, summary.uncompressed_bytes),
+                LogLevel::Info,
+            ).await;
+            if let Some(report) = &summary.sandbox {
+                let _ = logging_service.log_job(
+                    job_id,
+                    &format!("Sandbox restore passed: {} tables, {} rows in {}s", report.tables.len(), report.total_rows, report.duration_secs),
+                    LogLevel::Info,
+                ).await;
+            }
+            let _ = sqlx::query("UPDATE jobs SET status = ?, progress = ?, completed_at = ? WHERE id = ?")
+                .bind("completed")
+                .bind(100)
+                .bind(Utc::now())
+                .bind(job_id)
+                .execute(pool)
+                .await;
+            "completed"
+        }
+        Err(e) => {
+            warn!("Verification of backup {} failed: {}", backup.id, e);
+            let _ = logging_service.log_job(job_id, &format!("Verification failed: {}", e), LogLevel::Error).await;
+            let _ = sqlx::query("UPDATE jobs SET status = ?, error_message = ?, completed_at = ? WHERE id = ?")
+                .bind("failed")
+                .bind(e.to_string())
+                .bind(Utc::now())
+                .bind(job_id)
+                .execute(pool)
+                .await;
+            "failed"
+        }
+    };
+    events::publish(AppEvent::job_status(job_id, status));
+}
+
+/// Store the outcome on the backup metadata; a failure also marks the integrity state as failed
+async fn record_verification(
+    backup_service: &FilesystemBackupService,
+    backup: &Backup,
+    job_id: &str,
+    mode: VerifyMode,
+    result: &Result<VerifySummary>,
+) -> Result<()> {
+    let mut metadata = backup_service.load_backup_metadata(Path::new(&backup.meta_path)).await?;
+    let checked_at = Utc::now().to_rfc3339();
+
+    match result {
+        Ok(summary) => {
+            // Backups the integrity worker hasn't reached yet get their hash from this run
+            if metadata.sha256_hash.is_none() {
+                metadata.sha256_hash = Some(summary.sha256_hash.clone());
+            }
+            metadata.verified_at = Some(checked_at.clone());
+            metadata.verification_error = None;
+        }
+        Err(e) => metadata.verification_error = Some(e.to_string()),
+    }
+    metadata.last_verification = Some(BackupVerification {
+        job_id: job_id.to_string(),
+        checked_at,
+        mode,
+        passed: result.is_ok(),
+        error: result.as_ref().err().map(|e| e.to_string()),
+        sandbox: result.as_ref().ok().and_then(|summary| summary.sandbox.clone()),
+    });
+
+    backup_service.save_backup_metadata(&metadata).await
+}
+
+/// Start verify jobs for tasks, and the global schedule, whose verification interval has elapsed.
+///
+/// Each due schedule verifies one randomly chosen backup: the task's own backups for per-task
+/// schedules, any backup in the store for the global one.
+pub async fn start_due_verifications(pool: &SqlitePool, config: &AppConfig) -> Result<Vec<String>> {
+    let global_interval_days = config.verify_interval_days;
+    let mode = if config.verify_sandbox { VerifyMode::SandboxRestore } else { VerifyMode::Archive };
+    let schedules: Vec<(String, i32)> = sqlx::query_as(
+        "SELECT id, verify_interval_days FROM tasks WHERE verify_interval_days > 0"
+    )
+    .fetch_all(pool)
+    .await?;
+    if schedules.is_empty() && global_interval_days == 0 {
+        return Ok(Vec::new());
+    }
+
+    let backup_service = FilesystemBackupService::new(config.backup_dir.clone());
+    let backups = backup_service.scan_backups().await?;
+    let mut started = Vec::new();
+
+    for (task_id, interval_days) in schedules {
+        if !is_due(pool, Some(&task_id), interval_days as i64).await? {
+            continue;
+        }
+        let candidates: Vec<&Backup> = backups.iter()
+            .filter(|b| b.task_id.as_deref() == Some(task_id.as_str()))
+            .collect();
+        if let Some(backup) = pick_random_complete(&backup_service, candidates).await {
+            started.push(start_verify_job(pool, config, backup, Some(task_id), mode).await?);
+        }
+    }
+
+    if global_interval_days > 0 && is_due(pool, None, global_interval_days as i64).await? {
+        if let Some(backup) = pick_random_complete(&backup_service, backups.iter().collect()).await {
+            started.push(start_verify_job(pool, config, backup, None, mode).await?);
+        }
+    }
+
+    Ok(started)
+}
+
+/// Whether no verify job ran for this schedule within the interval; `None` is the global schedule
+async fn is_due(pool: &SqlitePool, task_id: Option<&str>, interval_days: i64) -> Result<bool> {
+    let since = Utc::now() - Duration::days(interval_days);
+    let (recent,): (i64,) = match task_id {
+        Some(task_id) => sqlx::query_as(
+            "SELECT COUNT(*) FROM jobs WHERE job_type = 'verify' AND task_id = ? AND created_at > ?"
+        )
+        .bind(task_id)
+        .bind(since)
+        .fetch_one(pool)
+        .await?,
+        None => sqlx::query_as(
+            "SELECT COUNT(*) FROM jobs WHERE job_type = 'verify' AND task_id IS NULL AND created_at > ?"
+        )
+        .bind(since)
+        .fetch_one(pool)
+        .await?,
+    };
+    Ok(recent == 0)
+}
+
+/// A random backup whose archive has been fully written
+async fn pick_random_complete(backup_service: &FilesystemBackupService, mut candidates: Vec<&Backup>) -> Option<Backup> {
+    candidates.shuffle(&mut rand::thread_rng());
+    for backup in candidates {
+        match backup_service.load_backup_metadata(Path::new(&backup.meta_path)).await {
+            Ok(metadata) if metadata.is_archive_complete() => return Some(backup.clone()),
+            _ => continue,
+        }
+    }
+    None
+}
@@ -0,0 +1,334 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use regex::Regex;
+use reqwest::{Method, StatusCode};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncReadExt;
+use tokio::sync::watch;
+use tokio_util::io::ReaderStream;
+use crate::models::WebdavTarget;
+use crate::services::bandwidth::{self, Throttle};
+use crate::services::remote_storage::RemoteObject;
+use crate::services::s3_storage::{content_length, uri_encode};
+
+/// Size of the pieces sent through Nextcloud's chunking API, which wants 5 MiB or more per chunk
+const CHUNK_SIZE: usize = 10 * 1024 * 1024;
+/// Read size for streamed PUTs
+const STREAM_BUFFER: usize = 256 * 1024;
+const FILES_SEGMENT: &str = "/remote.php/dav/files/";
+const PROPFIND_BODY: &str = r#"<?xml version="1.0"?><d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/><d:getcontentlength/><d:getlastmodified/></d:prop></d:propfind>"#;
+
+/// Servers pick their own namespace prefixes, so the multistatus parsing ignores them
+struct MultistatusPatterns {
+    response: Regex,
+    href: Regex,
+    length: Regex,
+    modified: Regex,
+    collection: Regex,
+}
+
+fn multistatus_patterns() -> &'static MultistatusPatterns {
+    static PATTERNS: OnceLock<MultistatusPatterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| MultistatusPatterns {
+        response: Regex::new(r"(?s)<(?:[\w-]+:)?response\b.*?</(?:[\w-]+:)?response>").unwrap(),
+        href: Regex::new(r"<(?:[\w-]+:)?href>([^<]*)</").unwrap(),
+        length: Regex::new(r"<(?:[\w-]+:)?getcontentlength[^>]*>(\d+)</").unwrap(),
+        modified: Regex::new(r"<(?:[\w-]+:)?getlastmodified[^>]*>([^<]+)</").unwrap(),
+        collection: Regex::new(r"<(?:[\w-]+:)?collection\s*/?>").unwrap(),
+    })
+}
+
+/// Minimal WebDAV client: MKCOL plus PUT, streamed from disk or through Nextcloud chunking,
+/// so archives never have to fit in memory
+pub struct WebdavClient {
+    target: WebdavTarget,
+    http: reqwest::Client,
+}
+
+impl WebdavClient {
+    pub fn new(target: WebdavTarget) -> Self {
+        Self { target, http: reqwest::Client::new() }
+    }
+
+    /// URL of a path below the configured collection
+    pub fn url_for(&self, path: &str) -> String {
+        let encoded: Vec<String> = path.split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| uri_encode(segment, true))
+            .collect();
+        format!("{}/{}", self.target.url.trim_end_matches('/'), encoded.join("/"))
+    }
+
+    /// Create a collection below the configured one; an existing collection is fine
+    pub async fn mkcol(&self, path: &str) -> Result<()> {
+        let response = self.request(mkcol(), &self.url_for(path)).send().await?;
+        if response.status() == StatusCode::METHOD_NOT_ALLOWED {
+            return Ok(());
+        }
+        check(response).await?;
+        Ok(())
+    }
+
+    /// Upload a file and return its SHA-256
+    pub async fn upload_file(&self, path: &str, file: &Path, progress: Option<watch::Sender<u32>>, throttle: Option<&Throttle>) -> Result<String> {
+        if self.target.nextcloud_chunking {
+            self.upload_chunked(path, file, progress, throttle).await
+        } else {
+            self.upload_streamed(path, file, progress, throttle).await
+        }
+    }
+
+    pub async fn put_bytes(&self, path: &str, body: Vec<u8>) -> Result<()> {
+        let response = self.request(Method::PUT, &self.url_for(path)).body(body).send().await?;
+        check(response).await?;
+        Ok(())
+    }
+
+    pub async fn get(&self, path: &str) -> Result<Vec<u8>> {
+        Ok(self.open(path).await?.bytes().await?.to_vec())
+    }
+
+    /// Start downloading a file; the body is streamed from the response
+    pub async fn open(&self, path: &str) -> Result<reqwest::Response> {
+        let response = self.request(Method::GET, &self.url_for(path)).send().await?;
+        check(response).await
+    }
+
+    /// Size of a file from a HEAD request
+    pub async fn size(&self, path: &str) -> Result<u64> {
+        let response = check(self.request(Method::HEAD, &self.url_for(path)).send().await?).await?;
+        content_length(&response).ok_or_else(|| anyhow!("WebDAV server sent no size for {}", path))
+    }
+
+    /// Delete a file, or a collection with everything in it
+    pub async fn delete(&self, path: &str) -> Result<()> {
+        let response = self.request(Method::DELETE, &self.url_for(path)).send().await?;
+        check(response).await?;
+        Ok(())
+    }
+
+    /// Every file below the configured collection, with paths relative to it.
+    /// Walks one level per PROPFIND since many servers refuse `Depth: infinity`.
+    pub async fn list_objects(&self) -> Result<Vec<RemoteObject>> {
+        let patterns = multistatus_patterns();
+        let base = percent_decode(href_path(self.target.url.trim_end_matches('/')));
+        let mut objects = Vec::new();
+        let mut pending = vec![String::new()];
+        while let Some(dir) = pending.pop() {
+            let url = format!("{}/", self.url_for(&dir).trim_end_matches('/'));
+            let response = self.request(Method::from_bytes(b"PROPFIND")?, &url)
+                .header("depth", "1")
+                .header("content-type", "application/xml")
+                .body(PROPFIND_BODY)
+                .send()
+                .await?;
+            let body = check(response).await?.text().await?;
+
+            for entry in patterns.response.find_iter(&body).map(|m| m.as_str()) {
+                let Some(href) = patterns.href.captures(entry) else { continue };
+                let href = percent_decode(href_path(&href[1]));
+                let path = href.strip_prefix(&base).unwrap_or(&href).trim_matches('/').to_string();
+                // The listed collection reports itself first
+                if path == dir {
+                    continue;
+                }
+                if patterns.collection.is_match(entry) {
+                    pending.push(path);
+                } else {
+                    objects.push(RemoteObject {
+                        path,
+                        size: patterns.length.captures(entry).and_then(|c| c[1].parse().ok()).unwrap_or(0),
+                        modified: patterns.modified.captures(entry)
+                            .and_then(|c| DateTime::parse_from_rfc2822(c[1].trim()).ok())
+                            .map(|m| m.with_timezone(&Utc)),
+                    });
+                }
+            }
+        }
+        Ok(objects)
+    }
+
+    /// One PUT whose body is read from disk while it is sent
+    async fn upload_streamed(&self, path: &str, file: &Path, progress: Option<watch::Sender<u32>>, throttle: Option<&Throttle>) -> Result<String> {
+        let total = tokio::fs::metadata(file).await?.len();
+        let reader = tokio::fs::File::open(file).await?;
+        let hasher = Arc::new(Mutex::new(Sha256::new()));
+
+        let stream_hasher = hasher.clone();
+        let mut sent: u64 = 0;
+        let mut last_percent = 0;
+        let stream = ReaderStream::with_capacity(reader, STREAM_BUFFER).map(move |chunk| {
+            if let Ok(bytes) = &chunk {
+                stream_hasher.lock().unwrap().update(bytes);
+                sent += bytes.len() as u64;
+                let percent = (sent * 100 / total.max(1)) as u32;
+                if let (Some(tx), true) = (&progress, percent != last_percent) {
+                    let _ = tx.send(percent);
+                    last_percent = percent;
+                }
+            }
+            chunk
+        });
+
+        // An explicit length keeps servers that reject chunked transfer encoding happy
+        let response = self.request(Method::PUT, &self.url_for(path))
+            .header("content-length", total)
+            .body(reqwest::Body::wrap_stream(bandwidth::pace(throttle.cloned(), stream)))
+            .send()
+            .await?;
+        check(response).await?;
+
+        let hash = hasher.lock().unwrap().clone().finalize();
+        Ok(hex::encode(hash))
+    }
+
+    /// Nextcloud chunking v2: chunks go into a temporary upload collection that a final MOVE
+    /// assembles at the destination, so a failed chunk doesn't restart the whole archive
+    async fn upload_chunked(&self, path: &str, file: &Path, progress: Option<watch::Sender<u32>>, throttle: Option<&Throttle>) -> Result<String> {
+        let total = tokio::fs::metadata(file).await?.len();
+        let mut reader = tokio::fs::File::open(file).await?;
+        let mut hasher = Sha256::new();
+        let destination = self.url_for(path);
+        let upload_dir = format!("{}/rdumper-{}", self.uploads_url()?, uuid::Uuid::new_v4());
+
+        let response = self.request(mkcol(), &upload_dir)
+            .header("destination", &destination)
+            .send()
+            .await?;
+        check(response).await?;
+
+        let result = async {
+            let mut sent: u64 = 0;
+            let mut index = 1;
+            loop {
+                let chunk = read_chunk(&mut reader).await?;
+                if chunk.is_empty() {
+                    break;
+                }
+                hasher.update(&chunk);
+                sent += chunk.len() as u64;
+                let response = self.request(Method::PUT, &format!("{}/{:05}", upload_dir, index))
+                    .header("destination", &destination)
+                    .header("oc-total-length", total)
+                    .header("content-length", chunk.len())
+                    .body(bandwidth::body(throttle, chunk))
+                    .send()
+                    .await?;
+                check(response).await?;
+                index += 1;
+                if let Some(tx) = &progress {
+                    let _ = tx.send((sent * 100 / total.max(1)) as u32);
+                }
+            }
+
+            let response = self.request(Method::from_bytes(b"MOVE")?, &format!("{}/.file", upload_dir))
+                .header("destination", &destination)
+                .header("oc-total-length", total)
+                .header("overwrite", "T")
+                .send()
+                .await?;
+            check(response).await?;
+            Ok(())
+        }.await;
+
+        if let Err(e) = result {
+            // Nextcloud would expire the chunks eventually; removing them frees the quota now
+            let _ = self.request(Method::DELETE, &upload_dir).send().await;
+            return Err(e);
+        }
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    /// `.../remote.php/dav/uploads/<user>`, the chunk area belonging to a files URL
+    fn uploads_url(&self) -> Result<String> {
+        let url = self.target.url.trim_end_matches('/');
+        let start = url.find(FILES_SEGMENT)
+            .ok_or_else(|| anyhow!("Nextcloud chunking needs a URL containing {}<user>/", FILES_SEGMENT))?;
+        let user = url[start + FILES_SEGMENT.len()..].split('/').next().unwrap_or_default();
+        Ok(format!("{}/remote.php/dav/uploads/{}", &url[..start], user))
+    }
+
+    fn request(&self, method: Method, url: &str) -> reqwest::RequestBuilder {
+        self.http.request(method, url).basic_auth(&self.target.username, Some(&self.target.password))
+    }
+}
+
+/// Check that a Nextcloud chunking target has a URL the chunk area can be derived from
+pub fn validate(target: &WebdavTarget) -> Result<()> {
+    if target.nextcloud_chunking {
+        WebdavClient::new(target.clone()).uploads_url()?;
+    }
+    Ok(())
+}
+
+fn mkcol() -> Method {
+    Method::from_bytes(b"MKCOL").expect("MKCOL is a valid method")
+}
+
+/// Fail on non-success status codes with a hint for the common WebDAV ones
+async fn check(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let text = response.text().await.unwrap_or_default();
+    // Nextcloud (Sabre) puts the reason into <s:message>
+    let detail = text.find("<s:message>")
+        .and_then(|start| text[start + 11..].split("</s:message>").next())
+        .map(|message| format!(": {}", message))
+        .unwrap_or_default();
+    Err(match status.as_u16() {
+        401 | 403 => anyhow!("WebDAV denied access ({}){}", status, detail),
+        404 | 409 => anyhow!("WebDAV collection not found ({}){}", status, detail),
+        507 => anyhow!("WebDAV server is out of space{}", detail),
+        _ => anyhow!("WebDAV request failed ({}){}", status, detail),
+    })
+}
+
+/// The path part of an href, which servers may send as a full URL
+fn href_path(href: &str) -> &str {
+    match href.split_once("://") {
+        Some((_, rest)) => rest.find('/').map(|start| &rest[start..]).unwrap_or("/"),
+        None => href,
+    }
+}
+
+pub(crate) fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes.get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Fill a buffer with up to one chunk, reading until the chunk is full or the file ends
+async fn read_chunk(file: &mut tokio::fs::File) -> Result<Vec<u8>> {
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    let mut filled = 0;
+    while filled < CHUNK_SIZE {
+        let read = file.read(&mut chunk[filled..]).await?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+    }
+    chunk.truncate(filled);
+    Ok(chunk)
+}
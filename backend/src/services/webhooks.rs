@@ -0,0 +1,203 @@
+use anyhow::Result;
+use chrono::{Duration as ChronoDuration, Utc};
+use serde_json::{json, Value};
+use sqlx::types::Json;
+use sqlx::SqlitePool;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+use crate::models::{Job, Webhook, WebhookDelivery};
+use crate::services::catalog;
+use crate::services::events::{self, AppEvent};
+
+/// Attempts per delivery before it is given up
+const MAX_ATTEMPTS: i64 = 5;
+/// How often due retries are looked for while no events arrive
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Deliveries sent per sweep, so a backlog doesn't hold up new events
+const BATCH_SIZE: i64 = 20;
+/// Response body kept on a delivery for debugging
+const MAX_RESPONSE_BODY: usize = 2048;
+
+/// Wait before the attempt after `attempts` failed ones: 30s, 2m, 8m, 32m
+fn retry_delay(attempts: i64) -> ChronoDuration {
+    ChronoDuration::seconds(30 * 4_i64.pow(attempts.clamp(1, 4) as u32 - 1))
+}
+
+/// Webhook event and payload data for an application event, None for events webhooks don't get
+async fn webhook_event(pool: &SqlitePool, event: &AppEvent) -> Result<Option<(&'static str, Value)>> {
+    match event {
+        AppEvent::JobStatusChanged { job_id, status } => {
+            let name = match status.as_str() {
+                "running" => "job.started",
+                "completed" => "job.completed",
+                "failed" => "job.failed",
+                _ => return Ok(None),
+            };
+            let job: Option<Job> = sqlx::query_as("SELECT * FROM jobs WHERE id = ?")
+                .bind(job_id)
+                .fetch_optional(pool)
+                .await?;
+            Ok(job.map(|job| (name, json!({ "job": job }))))
+        }
+        AppEvent::BackupDeleted { backup_id } => Ok(Some(("backup.deleted", json!({ "backup_id": backup_id })))),
+        _ => Ok(None),
+    }
+}
+
+/// Sends application events to the webhooks subscribed to them.
+///
+/// Deliveries are queued in `webhook_deliveries` first, so failed ones are retried with a
+/// growing delay, also after a restart, and every attempt can be looked up later.
+pub struct WebhookDispatcher {
+    pool: SqlitePool,
+    http: reqwest::Client,
+}
+
+impl WebhookDispatcher {
+    pub fn new(pool: SqlitePool) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self { pool, http }
+    }
+
+    pub async fn start(&self) {
+        info!("Starting webhook dispatcher...");
+        let mut app_events = events::subscribe();
+
+        loop {
+            if let Err(e) = self.deliver_due().await {
+                warn!("Webhook delivery failed: {}", e);
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                received = app_events.recv() => match received {
+                    Ok(event) => {
+                        if let Err(e) = self.enqueue(&event).await {
+                            warn!("Failed to queue webhook deliveries for {}: {}", event.name(), e);
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => warn!("Webhook dispatcher missed {} events", missed),
+                    Err(RecvError::Closed) => return,
+                },
+            }
+        }
+    }
+
+    /// Queue a delivery of the event for every active webhook subscribed to it
+    async fn enqueue(&self, event: &AppEvent) -> Result<()> {
+        let Some((name, data)) = webhook_event(&self.pool, event).await? else {
+            return Ok(());
+        };
+        let webhooks: Vec<Webhook> = sqlx::query_as("SELECT * FROM webhooks WHERE is_active = 1")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let now = Utc::now();
+        for webhook in webhooks.iter().filter(|webhook| webhook.subscribes_to(name)) {
+            let id = Uuid::new_v4().to_string();
+            let payload = json!({
+                "id": id,
+                "event": name,
+                "created_at": now,
+                "data": data,
+            });
+            sqlx::query(
+                "INSERT INTO webhook_deliveries (id, webhook_id, event, payload, status, attempts, next_attempt_at, created_at) VALUES (?, ?, ?, ?, 'pending', 0, ?, ?)"
+            )
+                .bind(&id)
+                .bind(&webhook.id)
+                .bind(name)
+                .bind(Json(payload))
+                .bind(now)
+                .bind(now)
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Send the pending deliveries whose next attempt is due
+    async fn deliver_due(&self) -> Result<()> {
+        let deliveries: Vec<WebhookDelivery> = sqlx::query_as(
+            "SELECT * FROM webhook_deliveries WHERE status = 'pending' AND next_attempt_at <= ? ORDER BY next_attempt_at LIMIT ?"
+        )
+            .bind(Utc::now())
+            .bind(BATCH_SIZE)
+            .fetch_all(&self.pool)
+            .await?;
+
+        for delivery in deliveries {
+            let webhook: Option<Webhook> = sqlx::query_as("SELECT * FROM webhooks WHERE id = ?")
+                .bind(&delivery.webhook_id)
+                .fetch_optional(&self.pool)
+                .await?;
+            match webhook {
+                Some(webhook) => self.attempt(&webhook, &delivery).await?,
+                None => {
+                    sqlx::query("DELETE FROM webhook_deliveries WHERE id = ?")
+                        .bind(&delivery.id)
+                        .execute(&self.pool)
+                        .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// POST a delivery once and record the outcome, scheduling a retry when it failed
+    async fn attempt(&self, webhook: &Webhook, delivery: &WebhookDelivery) -> Result<()> {
+        let body = serde_json::to_vec(&delivery.payload.0)?;
+        let signature = catalog::sign(&webhook.secret, &body)?;
+        let result = self.http.post(&webhook.url)
+            .header("Content-Type", "application/json")
+            .header("User-Agent", concat!("rdumper/", env!("CARGO_PKG_VERSION")))
+            .header("X-Rdumper-Event", &delivery.event)
+            .header("X-Rdumper-Delivery", &delivery.id)
+            .header("X-Rdumper-Signature", format!("sha256={}", signature))
+            .body(body)
+            .send()
+            .await;
+
+        let (response_status, response_body, error) = match result {
+            Ok(response) => {
+                let status = response.status();
+                let text = response.text().await.unwrap_or_default();
+                let text: String = text.chars().take(MAX_RESPONSE_BODY).collect();
+                let error = (!status.is_success()).then(|| format!("HTTP {}", status));
+                (Some(status.as_u16() as i64), Some(text), error)
+            }
+            Err(e) => (None, None, Some(e.to_string())),
+        };
+
+        let attempts = delivery.attempts + 1;
+        let now = Utc::now();
+        let (status, next_attempt_at, delivered_at) = match &error {
+            None => ("delivered", None, Some(now)),
+            Some(_) if attempts >= MAX_ATTEMPTS => ("failed", None, None),
+            Some(_) => ("pending", Some(now + retry_delay(attempts)), None),
+        };
+        if let Some(error) = &error {
+            warn!("Webhook '{}' delivery {} attempt {} failed: {}", webhook.name, delivery.id, attempts, error);
+        }
+
+        sqlx::query(
+            "UPDATE webhook_deliveries SET status = ?, attempts = ?, response_status = ?, response_body = ?, error = ?, next_attempt_at = ?, delivered_at = ? WHERE id = ?"
+        )
+            .bind(status)
+            .bind(attempts)
+            .bind(response_status)
+            .bind(response_body)
+            .bind(error)
+            .bind(next_attempt_at)
+            .bind(delivered_at)
+            .bind(&delivery.id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
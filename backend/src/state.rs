@@ -0,0 +1,59 @@
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use crate::services::notifications::NotificationSettings;
+use crate::services::sandbox::SandboxSettings;
+use crate::services::{FilesystemBackupService, LoggingService, MydumperService, TaskWorker, UploadSessionStore};
+
+/// Runtime configuration resolved once at startup
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    pub backup_dir: String,
+    pub log_dir: String,
+    pub temp_dir: String,
+    /// Root that `POST /api/backups/register` may read from; `None` disables registering
+    pub import_dir: Option<String>,
+    /// Verify a random backup from the whole store every N days; 0 disables it
+    pub verify_interval_days: u32,
+    /// Scheduled verifications also restore into a disposable MySQL container
+    pub verify_sandbox: bool,
+    pub sandbox: SandboxSettings,
+    /// HMAC key for `GET /api/backups/catalog`
+    pub catalog_signing_key: Option<String>,
+    /// HMAC key for the tokens issued on login
+    pub jwt_secret: String,
+    /// Backup jobs running at once on this instance; 0 means no limit
+    pub max_concurrent_jobs: u32,
+    /// Backup jobs running at once against the same database host; 0 means no limit
+    pub max_jobs_per_host: u32,
+    /// Delete finished jobs and their logs after N days; 0 keeps them
+    pub job_retention_days: u32,
+    /// Keep at most the newest N finished jobs; 0 means no limit
+    pub job_retention_max: u32,
+    /// Channels finished backups are reported on
+    pub notifications: NotificationSettings,
+}
+
+/// Shared application state handed to every API handler
+pub struct AppState {
+    pub pool: SqlitePool,
+    pub config: AppConfig,
+    pub backup_service: FilesystemBackupService,
+    pub mydumper_service: MydumperService,
+    pub logging_service: LoggingService,
+    pub upload_sessions: UploadSessionStore,
+    pub worker: Arc<TaskWorker>,
+}
+
+impl AppState {
+    pub fn new(pool: SqlitePool, config: AppConfig, worker: Arc<TaskWorker>) -> Self {
+        Self {
+            backup_service: FilesystemBackupService::new(config.backup_dir.clone()),
+            mydumper_service: MydumperService::new(config.backup_dir.clone(), config.log_dir.clone()),
+            logging_service: LoggingService::new(Arc::new(pool.clone())),
+            upload_sessions: UploadSessionStore::new(&config.temp_dir),
+            pool,
+            config,
+            worker,
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+use crate::models::User;
+use crate::services::auth::{is_current, issue_tokens, verify_token, TokenKind};
+
+const SECRET: &str = "test-secret";
+
+fn user() -> User {
+    User {
+        id: "user-1".to_string(),
+        username: "admin".to_string(),
+        password_hash: String::new(),
+        token_version: 0,
+        last_login_at: None,
+        created_at: Utc::now(),
+    }
+}
+
+/// HS256 token over arbitrary claims, signed independently of the code under test
+fn token(secret: &str, header: serde_json::Value, claims: serde_json::Value) -> String {
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header.to_string()),
+        URL_SAFE_NO_PAD.encode(claims.to_string())
+    );
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(signing_input.as_bytes());
+    format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes()))
+}
+
+fn claims(exp: i64) -> serde_json::Value {
+    json!({ "sub": "user-1", "name": "admin", "kind": "access", "iat": Utc::now().timestamp(), "exp": exp })
+}
+
+#[test]
+fn test_issued_tokens_verify() {
+    let tokens = issue_tokens(SECRET, user()).unwrap();
+    let claims = verify_token(SECRET, &tokens.access_token, TokenKind::Access).unwrap();
+    assert_eq!(claims.sub, "user-1");
+    assert_eq!(claims.name, "admin");
+    assert!(verify_token(SECRET, &tokens.refresh_token, TokenKind::Refresh).is_ok());
+}
+
+#[test]
+fn test_token_kinds_are_not_interchangeable() {
+    let tokens = issue_tokens(SECRET, user()).unwrap();
+    assert!(verify_token(SECRET, &tokens.refresh_token, TokenKind::Access).is_err());
+    assert!(verify_token(SECRET, &tokens.access_token, TokenKind::Refresh).is_err());
+}
+
+#[test]
+fn test_rejects_other_secret() {
+    let tokens = issue_tokens(SECRET, user()).unwrap();
+    assert!(verify_token("other-secret", &tokens.access_token, TokenKind::Access).is_err());
+}
+
+#[test]
+fn test_rejects_tampered_claims() {
+    let tokens = issue_tokens(SECRET, user()).unwrap();
+    let parts: Vec<&str> = tokens.access_token.split('.').collect();
+    let forged = URL_SAFE_NO_PAD.encode(json!({
+        "sub": "user-2", "name": "other", "kind": "access", "iat": 0, "exp": Utc::now().timestamp() + 3600,
+    }).to_string());
+    let tampered = format!("{}.{}.{}", parts[0], forged, parts[2]);
+    assert!(verify_token(SECRET, &tampered, TokenKind::Access).is_err());
+}
+
+#[test]
+fn test_rejects_expired_token() {
+    let header = json!({ "alg": "HS256", "typ": "JWT" });
+    let valid = token(SECRET, header.clone(), claims(Utc::now().timestamp() + 60));
+    assert!(verify_token(SECRET, &valid, TokenKind::Access).is_ok());
+    let expired = token(SECRET, header, claims(Utc::now().timestamp() - 1));
+    assert!(verify_token(SECRET, &expired, TokenKind::Access).is_err());
+}
+
+#[test]
+fn test_rejects_other_algorithms() {
+    let exp = Utc::now().timestamp() + 60;
+    let unsigned = format!(
+        "{}.{}.",
+        URL_SAFE_NO_PAD.encode(json!({ "alg": "none", "typ": "JWT" }).to_string()),
+        URL_SAFE_NO_PAD.encode(claims(exp).to_string())
+    );
+    assert!(verify_token(SECRET, &unsigned, TokenKind::Access).is_err());
+    let other = token(SECRET, json!({ "alg": "HS512", "typ": "JWT" }), claims(exp));
+    assert!(verify_token(SECRET, &other, TokenKind::Access).is_err());
+}
+
+#[test]
+fn test_rejects_malformed_tokens() {
+    for token in ["", "abc", "a.b", "a.b.c"] {
+        assert!(verify_token(SECRET, token, TokenKind::Access).is_err(), "{}", token);
+    }
+}
+
+#[test]
+fn test_password_change_revokes_earlier_tokens() {
+    let tokens = issue_tokens(SECRET, user()).unwrap();
+    let mut changed = user();
+    changed.token_version += 1;
+    for (token, kind) in [(&tokens.access_token, TokenKind::Access), (&tokens.refresh_token, TokenKind::Refresh)] {
+        let claims = verify_token(SECRET, token, kind).unwrap();
+        assert!(is_current(&claims, &user()));
+        assert!(!is_current(&claims, &changed));
+    }
+
+    let renewed = issue_tokens(SECRET, changed.clone()).unwrap();
+    assert!(is_current(&verify_token(SECRET, &renewed.access_token, TokenKind::Access).unwrap(), &changed));
+}
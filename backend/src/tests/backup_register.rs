@@ -0,0 +1,50 @@
+use crate::services::backup_register::resolve_import_path;
+
+/// An import directory holding `shop/metadata`, with `secret.txt` next to it outside the import directory
+fn import_fixture() -> (tempfile::TempDir, String) {
+    let dir = tempfile::TempDir::new().unwrap();
+    let import_dir = dir.path().join("import");
+    std::fs::create_dir_all(import_dir.join("shop")).unwrap();
+    std::fs::write(import_dir.join("shop/metadata"), "Started dump at: 2025-01-31 02:00:00").unwrap();
+    std::fs::write(dir.path().join("secret.txt"), "secret").unwrap();
+    let import_dir = import_dir.to_string_lossy().to_string();
+    (dir, import_dir)
+}
+
+#[tokio::test]
+async fn test_resolve_import_path_accepts_relative_and_absolute_paths() {
+    let (_dir, import_dir) = import_fixture();
+    let expected = std::fs::canonicalize(format!("{}/shop", import_dir)).unwrap();
+
+    assert_eq!(resolve_import_path(&import_dir, "shop").await.unwrap(), expected);
+    assert_eq!(resolve_import_path(&import_dir, &format!("{}/shop", import_dir)).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn test_resolve_import_path_rejects_paths_outside_the_import_directory() {
+    let (dir, import_dir) = import_fixture();
+    let secret = dir.path().join("secret.txt");
+
+    assert!(resolve_import_path(&import_dir, "../secret.txt").await.is_err());
+    assert!(resolve_import_path(&import_dir, &secret.to_string_lossy()).await.is_err());
+    assert!(resolve_import_path(&import_dir, ".").await.is_err());
+}
+
+#[tokio::test]
+async fn test_resolve_import_path_rejects_symlinks_leaving_the_import_directory() {
+    let (dir, import_dir) = import_fixture();
+    std::os::unix::fs::symlink(dir.path().join("secret.txt"), format!("{}/link.sql", import_dir)).unwrap();
+
+    assert!(resolve_import_path(&import_dir, "link.sql").await.is_err());
+}
+
+#[tokio::test]
+async fn test_resolve_import_path_does_not_reveal_whether_a_path_exists() {
+    let (dir, import_dir) = import_fixture();
+    let existing = dir.path().join("secret.txt").to_string_lossy().to_string();
+    let missing = dir.path().join("missing.txt").to_string_lossy().to_string();
+
+    let existing_error = resolve_import_path(&import_dir, &existing).await.unwrap_err().to_string();
+    let missing_error = resolve_import_path(&import_dir, &missing).await.unwrap_err().to_string();
+    assert_eq!(existing_error.replace(&existing, "PATH"), missing_error.replace(&missing, "PATH"));
+}
@@ -0,0 +1,33 @@
+use crate::api::backups::{parse_byte_range, ByteRange};
+
+#[test]
+fn test_closed_and_open_ranges() {
+    assert_eq!(parse_byte_range("bytes=0-99", 1000), ByteRange::Partial(0, 99));
+    assert_eq!(parse_byte_range("bytes=500-", 1000), ByteRange::Partial(500, 999));
+    // An end beyond the file is cut to its last byte
+    assert_eq!(parse_byte_range("bytes=900-5000", 1000), ByteRange::Partial(900, 999));
+}
+
+#[test]
+fn test_suffix_ranges() {
+    assert_eq!(parse_byte_range("bytes=-100", 1000), ByteRange::Partial(900, 999));
+    assert_eq!(parse_byte_range("bytes=-5000", 1000), ByteRange::Partial(0, 999));
+    assert_eq!(parse_byte_range("bytes=-0", 1000), ByteRange::Unsatisfiable);
+}
+
+#[test]
+fn test_unsatisfiable_ranges() {
+    assert_eq!(parse_byte_range("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+    assert_eq!(parse_byte_range("bytes=0-10", 0), ByteRange::Unsatisfiable);
+}
+
+#[test]
+fn test_unsupported_ranges_serve_the_full_file() {
+    assert_eq!(parse_byte_range("items=0-10", 1000), ByteRange::Full);
+    assert_eq!(parse_byte_range("bytes=0-10,20-30", 1000), ByteRange::Full);
+    assert_eq!(parse_byte_range("bytes=abc-", 1000), ByteRange::Full);
+    assert_eq!(parse_byte_range("bytes=-abc", 1000), ByteRange::Full);
+    assert_eq!(parse_byte_range("bytes=10", 1000), ByteRange::Full);
+    // A last byte before the first is syntactically invalid, so the header is ignored
+    assert_eq!(parse_byte_range("bytes=500-100", 1000), ByteRange::Full);
+}
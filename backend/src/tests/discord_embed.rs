@@ -0,0 +1,65 @@
+use chrono::Utc;
+use serde_json::Value;
+use crate::services::notifications::discord::payload;
+use crate::services::notifications::JobSummary;
+
+fn summary(task_name: &str, error_message: &str, log_tail: &str) -> JobSummary {
+    JobSummary {
+        job_id: "job-1".to_string(),
+        task_name: task_name.to_string(),
+        database: String::new(),
+        succeeded: false,
+        attempt: 2,
+        started_at: Some(Utc::now()),
+        completed_at: Some(Utc::now()),
+        backup_size: None,
+        error_message: Some(error_message.to_string()),
+        log_tail: Some(log_tail.to_string()),
+        job_url: None,
+    }
+}
+
+fn chars(value: &Value) -> usize {
+    value.as_str().map_or(0, |text| text.chars().count())
+}
+
+/// Characters Discord counts towards the 6000 of an embed
+fn embed_total(embed: &Value) -> usize {
+    chars(&embed["title"])
+        + chars(&embed["description"])
+        + chars(&embed["footer"]["text"])
+        + embed["fields"].as_array().unwrap().iter()
+            .map(|field| chars(&field["name"]) + chars(&field["value"]))
+            .sum::<usize>()
+}
+
+#[test]
+fn test_short_report_is_complete() {
+    let payload = payload(&summary("nightly", "Access denied", "line 1\nline 2"));
+    let embed = &payload["embeds"][0];
+    let description = embed["description"].as_str().unwrap();
+    assert!(description.contains("**Error:** Access denied"));
+    assert!(description.contains("line 1\nline 2"));
+    // Empty values are replaced, Discord refuses them
+    assert!(embed["fields"].as_array().unwrap().iter().all(|field| chars(&field["value"]) > 0));
+}
+
+#[test]
+fn test_long_report_stays_within_discord_limits() {
+    let error = format!("mydumper failed: {}", "x".repeat(20_000));
+    let log_tail = format!("{}the last line", "ü".repeat(20_000));
+    let payload = payload(&summary(&"t".repeat(3000), &error, &log_tail));
+    let embed = &payload["embeds"][0];
+
+    assert!(chars(&embed["title"]) <= 256);
+    assert!(chars(&embed["description"]) <= 4096);
+    for field in embed["fields"].as_array().unwrap() {
+        assert!(chars(&field["value"]) <= 1024);
+    }
+    assert!(embed_total(embed) <= 6000, "{} characters", embed_total(embed));
+
+    // The start of the error and the end of the log survive
+    let description = embed["description"].as_str().unwrap();
+    assert!(description.starts_with("**Error:** mydumper failed: xxx"));
+    assert!(description.ends_with("the last line\n```"));
+}
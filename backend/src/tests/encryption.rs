@@ -0,0 +1,78 @@
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use crate::services::encryption;
+
+const KEY_NAME: &str = "test-key";
+
+/// The secrets directory can only be set once per process, so all tests share one holding the test key
+fn secrets_dir() -> &'static PathBuf {
+    static DIR: OnceLock<PathBuf> = OnceLock::new();
+    DIR.get_or_init(|| {
+        let dir = tempfile::TempDir::new().expect("Failed to create temp dir").keep();
+        std::fs::write(dir.join(KEY_NAME), hex::encode([7u8; 32])).unwrap();
+        encryption::set_secrets_dir(&dir.to_string_lossy());
+        dir
+    })
+}
+
+/// Encrypt `data` and return the encrypted file's bytes
+fn encrypt(data: &[u8]) -> Vec<u8> {
+    secrets_dir();
+    let dir = tempfile::TempDir::new().unwrap();
+    let src = dir.path().join("db.tar.gz");
+    let dest = dir.path().join("db.tar.gz.enc");
+    std::fs::write(&src, data).unwrap();
+    encryption::encrypt_file(&src, &dest, KEY_NAME).unwrap();
+    assert!(encryption::is_encrypted(&dest));
+    std::fs::read(dest).unwrap()
+}
+
+fn decrypt(encrypted: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut reader = encryption::DecryptReader::new(encrypted)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+    let mut plaintext = Vec::new();
+    reader.read_to_end(&mut plaintext)?;
+    Ok(plaintext)
+}
+
+/// Deterministic data that doesn't repeat within a chunk
+fn data(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 31 % 251) as u8).collect()
+}
+
+#[test]
+fn test_round_trip() {
+    const MIB: usize = 1024 * 1024;
+    // Empty, partial, exactly one chunk (followed by an empty final one) and several chunks
+    for len in [0, 1000, MIB, 2 * MIB + 12345] {
+        let plaintext = data(len);
+        let encrypted = encrypt(&plaintext);
+        assert_eq!(decrypt(&encrypted).unwrap(), plaintext, "{} bytes", len);
+    }
+}
+
+#[test]
+fn test_detects_modified_data() {
+    let mut encrypted = encrypt(&data(5000));
+    let last = encrypted.len() - 1;
+    encrypted[last] ^= 1;
+    assert!(decrypt(&encrypted).is_err());
+}
+
+#[test]
+fn test_detects_truncation() {
+    // Cutting off the final chunk leaves a file of complete chunks, none flagged as the last
+    let encrypted = encrypt(&data(1024 * 1024 + 10));
+    let first_chunk_end = encrypted.len() - (5 + 10 + 16);
+    assert!(decrypt(&encrypted[..first_chunk_end]).is_err());
+    assert!(decrypt(&encrypted[..encrypted.len() - 1]).is_err());
+}
+
+#[test]
+fn test_unknown_key_is_refused() {
+    secrets_dir();
+    assert!(encryption::check_key(KEY_NAME).is_ok());
+    assert!(encryption::check_key("missing-key").is_err());
+    assert!(encryption::check_key("../test-key").is_err());
+}
@@ -0,0 +1,11 @@
+mod auth_tokens;
+mod backup_register;
+mod byte_range;
+mod discord_embed;
+mod encryption;
+mod openapi_routes;
+mod s3_signing;
+mod sftp_host_key;
+mod storage_secrets;
+mod task_schedule;
+mod where_clause;
@@ -0,0 +1,119 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use regex::Regex;
+use crate::api::openapi::{document, OPERATIONS};
+
+/// Body of `fn name` in `source`, up to the closing brace at the start of a line
+fn function_body<'a>(source: &'a str, name: &str) -> &'a str {
+    let start = source.find(&format!("fn {}(", name))
+        .unwrap_or_else(|| panic!("fn {} not found", name));
+    let end = start + source[start..].find("\n}").expect("function has a closing brace");
+    &source[start..end]
+}
+
+/// Method and full path of every `.route(...)` in a router function, below `prefix`
+fn routes_of(body: &str, prefix: &str) -> Vec<(String, String)> {
+    let route = Regex::new(r#"\.route\("([^"]*)",\s*(.*)\)\s*$"#).unwrap();
+    let method = Regex::new(r"\b(get|post|put|patch|delete)\(").unwrap();
+    body.lines()
+        .filter_map(|line| route.captures(line.trim()))
+        .flat_map(|captures| {
+            let path = match &captures[1] {
+                "/" => format!("/api{}", prefix),
+                path => format!("/api{}{}", prefix, path),
+            };
+            method.captures_iter(&captures[2])
+                .map(|m| (m[1].to_string(), path.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Every route the API routers register, read from their source
+fn registered_routes() -> BTreeSet<(String, String)> {
+    let api_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/api");
+    let read = |module: &str| std::fs::read_to_string(api_dir.join(format!("{}.rs", module))).unwrap();
+    let mod_source = read("mod");
+    let v1_routes = function_body(&mod_source, "v1_routes");
+
+    let mut routes: BTreeSet<_> = routes_of(v1_routes, "").into_iter().collect();
+    let nest = Regex::new(r#"\.nest\("([^"]+)",\s*(\w+)::(\w+)\("#).unwrap();
+    for captures in nest.captures_iter(v1_routes) {
+        let source = read(&captures[2]);
+        routes.extend(routes_of(function_body(&source, &captures[3]), &captures[1]));
+    }
+    let merge = Regex::new(r"\.merge\((\w+)::(\w+)\(").unwrap();
+    for captures in merge.captures_iter(v1_routes) {
+        let source = read(&captures[1]);
+        routes.extend(routes_of(function_body(&source, &captures[2]), ""));
+    }
+    routes
+}
+
+#[test]
+fn test_every_route_is_documented() {
+    let registered = registered_routes();
+    let documented: BTreeSet<_> = OPERATIONS.iter()
+        .map(|operation| (operation.method.to_string(), operation.path.to_string()))
+        .collect();
+    assert!(registered.len() > 100, "Only {} routes found, is the router source still parsed correctly?", registered.len());
+
+    let undocumented: Vec<_> = registered.difference(&documented).collect();
+    assert!(undocumented.is_empty(), "Routes missing from openapi::OPERATIONS: {:?}", undocumented);
+    let stale: Vec<_> = documented.difference(&registered).collect();
+    assert!(stale.is_empty(), "openapi::OPERATIONS entries without a route: {:?}", stale);
+}
+
+#[test]
+fn test_operations_are_unique() {
+    let mut seen = BTreeSet::new();
+    for operation in OPERATIONS {
+        assert!(seen.insert((operation.method, operation.path)), "{} {} is listed twice", operation.method, operation.path);
+    }
+}
+
+/// Every `$ref` below `value`
+fn references(value: &serde_json::Value, found: &mut BTreeSet<String>) {
+    match value {
+        serde_json::Value::Object(object) => {
+            if let Some(serde_json::Value::String(reference)) = object.get("$ref") {
+                found.insert(reference.clone());
+            }
+            object.values().for_each(|value| references(value, found));
+        }
+        serde_json::Value::Array(values) => values.iter().for_each(|value| references(value, found)),
+        _ => {}
+    }
+}
+
+#[test]
+fn test_document_references_resolve() {
+    let document = document();
+    let mut found = BTreeSet::new();
+    references(&document, &mut found);
+    let unresolved: Vec<_> = found.iter()
+        .filter(|reference| {
+            let name = reference.trim_start_matches("#/components/schemas/");
+            document["components"]["schemas"].get(name).is_none()
+        })
+        .collect();
+    assert!(unresolved.is_empty(), "References without a component: {:?}", unresolved);
+}
+
+#[test]
+fn test_document_describes_query_and_bodies() {
+    let document = document();
+    let list_jobs = &document["paths"]["/api/v1/jobs"]["get"];
+    let names: Vec<_> = list_jobs["parameters"].as_array().unwrap().iter()
+        .filter(|parameter| parameter["in"] == "query")
+        .map(|parameter| parameter["name"].as_str().unwrap())
+        .collect();
+    for name in ["status", "sort_by", "after_id"] {
+        assert!(names.contains(&name), "GET /jobs lacks the {} parameter: {:?}", name, names);
+    }
+
+    let create_task = &document["paths"]["/api/v1/tasks"]["post"];
+    assert_eq!(create_task["requestBody"]["content"]["application/json"]["schema"]["$ref"], "#/components/schemas/CreateTaskRequest");
+    assert!(document["components"]["schemas"]["CreateTaskRequest"]["properties"]["cron_schedule"].is_object());
+    assert!(document["paths"]["/api/v1/jobs/{id}"]["get"].get("requestBody").is_none());
+}
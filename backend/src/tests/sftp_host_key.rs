@@ -0,0 +1,34 @@
+use crate::services::sftp_storage::known_hosts;
+
+const ED25519: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl";
+
+#[test]
+fn test_plain_and_keyscan_lines() {
+    let expected = format!("rdumper-sftp-destination ssh-ed25519 {}\n", ED25519);
+    assert_eq!(known_hosts(&format!("ssh-ed25519 {}", ED25519)).unwrap(), expected);
+    assert_eq!(known_hosts(&format!("ssh-ed25519 {} root@backup", ED25519)).unwrap(), expected);
+    assert_eq!(known_hosts(&format!("[backup.example.com]:2222 ssh-ed25519 {}", ED25519)).unwrap(), expected);
+}
+
+#[test]
+fn test_several_keys_and_comments() {
+    let host_key = format!(
+        "# backup.example.com:22 SSH-2.0-OpenSSH_9.6\nbackup.example.com ssh-ed25519 {}\n\nbackup.example.com ecdsa-sha2-nistp256 AAAAE2VjZHNh\n",
+        ED25519
+    );
+    assert_eq!(known_hosts(&host_key).unwrap().lines().count(), 2);
+}
+
+#[test]
+fn test_rejects_other_values() {
+    for host_key in [
+        "",
+        "# only a comment",
+        "SHA256:uNiVztksCsDhcc0u9e8BujQXVUpKZIDTMczCvj3tD2s",
+        "ssh-ed25519",
+        "ssh-ed25519 AAAA\nmalicious-option AAAA",
+        "ssh-ed25519 AAAA,other-host",
+    ] {
+        assert!(known_hosts(host_key).is_err(), "{}", host_key);
+    }
+}
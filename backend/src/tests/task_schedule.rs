@@ -0,0 +1,92 @@
+use chrono::{DateTime, Duration, Utc};
+use serde_json::json;
+use crate::models::Task;
+
+fn task(cron_schedule: &str, timezone: &str) -> Task {
+    Task::new(serde_json::from_value(json!({
+        "name": "Test Task",
+        "database_config_id": "test-db-1",
+        "cron_schedule": cron_schedule,
+        "timezone": timezone,
+    })).unwrap())
+}
+
+fn at(time: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc)
+}
+
+/// The next `count` runs after `from`, as RFC 3339 strings
+fn runs(cron_schedule: &str, timezone: &str, from: &str, count: usize) -> Vec<String> {
+    let from = at(from);
+    task(cron_schedule, timezone)
+        .upcoming_runs(from, from + Duration::days(400), count)
+        .unwrap()
+        .iter()
+        .map(|run| run.to_rfc3339())
+        .collect()
+}
+
+#[test]
+fn test_daily_schedule() {
+    // 2024-01-01 is a Monday
+    assert_eq!(
+        runs("30 2 * * *", "UTC", "2024-01-01T03:00:00Z", 2),
+        ["2024-01-02T02:30:00+00:00", "2024-01-03T02:30:00+00:00"]
+    );
+}
+
+#[test]
+fn test_numeric_weekdays_follow_cron() {
+    // 0 and 7 are both Sunday, 1 is Monday
+    assert_eq!(runs("0 4 * * 0", "UTC", "2024-01-01T00:00:00Z", 1), ["2024-01-07T04:00:00+00:00"]);
+    assert_eq!(runs("0 4 * * 7", "UTC", "2024-01-01T00:00:00Z", 1), ["2024-01-07T04:00:00+00:00"]);
+    assert_eq!(runs("0 4 * * 1", "UTC", "2024-01-01T05:00:00Z", 1), ["2024-01-08T04:00:00+00:00"]);
+}
+
+#[test]
+fn test_weekday_ranges_and_steps() {
+    // Friday evening, so the weekend is skipped
+    assert_eq!(
+        runs("0 3 * * 1-5", "UTC", "2024-01-05T04:00:00Z", 2),
+        ["2024-01-08T03:00:00+00:00", "2024-01-09T03:00:00+00:00"]
+    );
+    // Every other day from Sunday: Sunday, Tuesday, Thursday, Saturday
+    assert_eq!(
+        runs("0 3 * * */2", "UTC", "2024-01-01T00:00:00Z", 3),
+        ["2024-01-02T03:00:00+00:00", "2024-01-04T03:00:00+00:00", "2024-01-06T03:00:00+00:00"]
+    );
+}
+
+#[test]
+fn test_schedule_in_task_timezone() {
+    // 02:00 in Berlin is 01:00 UTC in winter and 00:00 UTC in summer
+    assert_eq!(runs("0 2 * * *", "Europe/Berlin", "2024-01-01T12:00:00Z", 1), ["2024-01-02T01:00:00+00:00"]);
+    assert_eq!(runs("0 2 * * *", "Europe/Berlin", "2024-07-01T12:00:00Z", 1), ["2024-07-02T00:00:00+00:00"]);
+}
+
+#[test]
+fn test_invalid_expressions() {
+    for cron_schedule in ["0 3 * *", "0 3 * * * *", "61 3 * * *", "0 3 * * 5-1"] {
+        let task = task(cron_schedule, "UTC");
+        assert!(task.upcoming_runs(Utc::now(), Utc::now() + Duration::days(7), 1).is_err(), "{}", cron_schedule);
+    }
+}
+
+#[test]
+fn test_day_of_month_or_day_of_week() {
+    // Like cron, the 1st of the month and every Monday: Monday 2024-01-01, 08, 15, 22, 29, then Thursday 2024-02-01
+    assert_eq!(
+        runs("0 3 1 * 1", "UTC", "2024-01-01T00:00:00Z", 6),
+        [
+            "2024-01-01T03:00:00+00:00",
+            "2024-01-08T03:00:00+00:00",
+            "2024-01-15T03:00:00+00:00",
+            "2024-01-22T03:00:00+00:00",
+            "2024-01-29T03:00:00+00:00",
+            "2024-02-01T03:00:00+00:00",
+        ]
+    );
+    // Either field alone keeps restricting the days
+    assert_eq!(runs("0 3 13 * *", "UTC", "2024-01-01T00:00:00Z", 1), ["2024-01-13T03:00:00+00:00"]);
+    assert_eq!(runs("0 3 * * 5", "UTC", "2024-01-01T00:00:00Z", 1), ["2024-01-05T03:00:00+00:00"]);
+}
@@ -0,0 +1,50 @@
+use serde_json::json;
+use crate::models::Task;
+
+fn task_with_where(clause: &str) -> Task {
+    let mut task = Task::new(serde_json::from_value(json!({
+        "name": "Test Task",
+        "database_config_id": "test-db-1",
+        "cron_schedule": "0 2 * * *",
+    })).unwrap());
+    task.where_clause = Some(clause.to_string());
+    task
+}
+
+#[test]
+fn test_accepts_single_conditions() {
+    for clause in [
+        "created_at > NOW() - INTERVAL 30 DAY",
+        "(status = 'active' OR status = 'pending') AND id > 10",
+        "note = 'semicolons; -- and # are fine in strings'",
+        "name = 'it\\'s quoted'",
+        "`order` - 1 > 0",
+    ] {
+        assert_eq!(task_with_where(clause).validate_where_clause(), Ok(()), "{}", clause);
+    }
+}
+
+#[test]
+fn test_rejects_statement_separators_and_comments() {
+    for clause in [
+        "1 = 1; DROP TABLE users",
+        "id > 0 -- rest of the query",
+        "id > 0 # rest of the query",
+        "id > 0 /* rest of the query */",
+    ] {
+        assert!(task_with_where(clause).validate_where_clause().is_err(), "{}", clause);
+    }
+}
+
+#[test]
+fn test_rejects_unbalanced_clauses() {
+    for clause in ["(id > 0", "id > 0)", ") OR (", "name = 'open", "name = \"open"] {
+        assert!(task_with_where(clause).validate_where_clause().is_err(), "{}", clause);
+    }
+}
+
+#[test]
+fn test_rejects_overlong_clauses() {
+    let clause = format!("id IN ({})", vec!["1"; 600].join(","));
+    assert!(task_with_where(&clause).validate_where_clause().is_err());
+}
@@ -1,3 +1,7 @@
+import { useAuth } from '../stores/auth.js'
+
+const { loginRequired } = useAuth()
+
 // API Base URL - automatische Erkennung mit Runtime-Fallback
 const getApiBaseUrl = () => {
     // 1. Laufzeitkonfig (aus config.js, erzeugt im Docker-Container)
@@ -29,21 +33,72 @@ const getApiBaseUrl = () => {
 
 export const API_BASE_URL = getApiBaseUrl();
 
+// Credential sent with every request once the backend requires one: a login's access token or an API key
+const ACCESS_TOKEN_STORAGE = 'rdumper_access_token'
+const REFRESH_TOKEN_STORAGE = 'rdumper_refresh_token'
+const API_KEY_STORAGE = 'rdumper_api_key'
+
+const storeTokens = (tokens) => {
+  localStorage.setItem(ACCESS_TOKEN_STORAGE, tokens.access_token)
+  localStorage.setItem(REFRESH_TOKEN_STORAGE, tokens.refresh_token)
+}
+
+const clearCredentials = () => {
+  localStorage.removeItem(ACCESS_TOKEN_STORAGE)
+  localStorage.removeItem(REFRESH_TOKEN_STORAGE)
+  localStorage.removeItem(API_KEY_STORAGE)
+}
+
 // Generic API client
 class ApiClient {
   constructor(baseUrl = API_BASE_URL) {
     this.baseUrl = baseUrl
   }
 
-  async request(endpoint, options = {}) {
+  credential() {
+    return localStorage.getItem(ACCESS_TOKEN_STORAGE) || localStorage.getItem(API_KEY_STORAGE)
+  }
+
+  // Authorization header for the stored credential, if any
+  authHeaders() {
+    const credential = this.credential()
+    return credential ? { Authorization: `Bearer ${credential}` } : {}
+  }
+
+  // URL with the credential as query parameter, for EventSource and download links which can't send headers
+  withKey(url) {
+    const credential = this.credential()
+    if (!credential) return url
+    return `${url}${url.includes('?') ? '&' : '?'}api_key=${encodeURIComponent(credential)}`
+  }
+
+  // Renew an expired access token; true if the request can be retried
+  async refreshLogin() {
+    const refreshToken = localStorage.getItem(REFRESH_TOKEN_STORAGE)
+    if (!refreshToken) return false
+    const response = await fetch(`${this.baseUrl}/api/v1/auth/refresh`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ refresh_token: refreshToken }),
+    })
+    if (!response.ok) {
+      clearCredentials()
+      return false
+    }
+    storeTokens((await response.json()).data)
+    return true
+  }
+
+  async request(endpoint, options = {}, retried = false) {
     const url = `${this.baseUrl}${endpoint}`
     
     const config = {
+      ...options,
       headers: {
         'Content-Type': 'application/json',
+        ...this.authHeaders(),
         ...options.headers,
       },
-      ...options,
     }
 
     if (config.body && typeof config.body === 'object') {
@@ -52,7 +107,13 @@ class ApiClient {
 
     try {
       const response = await fetch(url, config)
-      
+
+      if (response.status === 401 && !endpoint.startsWith('/api/v1/auth/')) {
+        if (!retried && await this.refreshLogin()) {
+          return this.request(endpoint, options, true)
+        }
+        loginRequired.value = true
+      }
       if (!response.ok) {
         const errorData = await response.json().catch(() => ({ error: 'Unknown error' }))
         throw new Error(errorData.error || `HTTP ${response.status}`)
@@ -97,127 +158,268 @@ class ApiClient {
 // Create API client instance
 const apiClient = new ApiClient()
 
+// Auth API - login with a user account or an API key
+export const authApi = {
+  async login(username, password) {
+    const response = await apiClient.post('/api/v1/auth/login', { username, password })
+    clearCredentials()
+    storeTokens(response.data)
+    loginRequired.value = false
+    return response
+  },
+
+  // Use an API key instead of a login, e.g. on instances without user accounts
+  useApiKey(key) {
+    clearCredentials()
+    localStorage.setItem(API_KEY_STORAGE, key.trim())
+    loginRequired.value = false
+  },
+
+  logout() {
+    clearCredentials()
+    loginRequired.value = true
+  },
+
+  me() {
+    return apiClient.get('/api/v1/auth/me')
+  },
+}
+
 // Database Configs API
 export const databaseConfigsApi = {
   // List all database configurations
   list(params = {}) {
-    return apiClient.get('/api/database-configs', params)
+    return apiClient.get('/api/v1/database-configs', params)
   },
 
   // Get a specific database configuration
   get(id) {
-    return apiClient.get(`/api/database-configs/${id}`)
+    return apiClient.get(`/api/v1/database-configs/${id}`)
   },
 
   // Create a new database configuration
   create(data) {
-    return apiClient.post('/api/database-configs', data)
+    return apiClient.post('/api/v1/database-configs', data)
   },
 
   // Update a database configuration
   update(id, data) {
-    return apiClient.put(`/api/database-configs/${id}`, data)
+    return apiClient.put(`/api/v1/database-configs/${id}`, data)
   },
 
   // Delete a database configuration
   delete(id) {
-    return apiClient.delete(`/api/database-configs/${id}`)
+    return apiClient.delete(`/api/v1/database-configs/${id}`)
   },
 
   // Test database connection
   test(id) {
-    return apiClient.post(`/api/database-configs/${id}/test`)
+    return apiClient.post(`/api/v1/database-configs/${id}/test`)
   },
 
   // Check database permissions
   checkPermissions(id) {
-    return apiClient.get(`/api/database-configs/${id}/permissions`)
+    return apiClient.get(`/api/v1/database-configs/${id}/permissions`)
   },
 
   // Get available databases for a connection
   getDatabases(id) {
-    return apiClient.get(`/api/database-configs/${id}/databases`)
+    return apiClient.get(`/api/v1/database-configs/${id}/databases`)
+  },
+
+  // Backup freshness against each config's RPO target
+  sla(params = {}) {
+    return apiClient.get('/api/v1/database-configs/sla', params)
+  },
+
+  // Most recent successful backup of a database configuration
+  lastBackup(id) {
+    return apiClient.get(`/api/v1/database-configs/${id}/last-backup`)
   }
 }
 
 // Tasks API
 export const tasksApi = {
   list(params = {}) {
-    return apiClient.get('/api/tasks', params)
+    return apiClient.get('/api/v1/tasks', params)
   },
 
   get(id) {
-    return apiClient.get(`/api/tasks/${id}`)
+    return apiClient.get(`/api/v1/tasks/${id}`)
   },
 
   create(data) {
-    return apiClient.post('/api/tasks', data)
+    return apiClient.post('/api/v1/tasks', data)
   },
 
   update(id, data) {
-    return apiClient.put(`/api/tasks/${id}`, data)
+    return apiClient.put(`/api/v1/tasks/${id}`, data)
   },
 
   delete(id) {
-    return apiClient.delete(`/api/tasks/${id}`)
+    return apiClient.delete(`/api/v1/tasks/${id}`)
   },
 
   run(id) {
-    return apiClient.post(`/api/tasks/${id}/run`)
+    return apiClient.post(`/api/v1/tasks/${id}/run`)
+  },
+
+  dryRun(id) {
+    return apiClient.post(`/api/v1/tasks/${id}/dry-run`)
   },
 
   toggle(id) {
-    return apiClient.post(`/api/tasks/${id}/toggle`)
+    return apiClient.post(`/api/v1/tasks/${id}/toggle`)
+  },
+
+  upcoming(id, count = 10) {
+    return apiClient.get(`/api/v1/tasks/${id}/upcoming`, { count })
+  },
+
+  scheduleOnce(id, runAt) {
+    return apiClient.post(`/api/v1/tasks/${id}/schedule-once`, { run_at: runAt })
+  },
+
+  cancelScheduleOnce(id) {
+    return apiClient.delete(`/api/v1/tasks/${id}/schedule-once`)
   }
 }
 
 // Jobs API
 export const jobsApi = {
   list(params = {}) {
-    return apiClient.get('/api/jobs', params)
+    return apiClient.get('/api/v1/jobs', params)
+  },
+
+  // URL downloading every job matching the list filters, format 'csv' or 'json'
+  exportUrl(params = {}, format = 'csv') {
+    const query = new URLSearchParams({ ...params, format }).toString()
+    return apiClient.withKey(`${apiClient.baseUrl}/api/v1/jobs/export?${query}`)
   },
 
   get(id) {
-    return apiClient.get(`/api/jobs/${id}`)
+    return apiClient.get(`/api/v1/jobs/${id}`)
+  },
+
+  cancel(id, dropDatabase = false) {
+    const query = dropDatabase ? '?drop_database=true' : ''
+    return apiClient.post(`/api/v1/jobs/${id}/cancel${query}`)
+  },
+
+  retry(id) {
+    return apiClient.post(`/api/v1/jobs/${id}/retry`)
   },
 
-  cancel(id) {
-    return apiClient.post(`/api/jobs/${id}/cancel`)
+  // Delete finished jobs by status and/or creation date; dryRun only counts them
+  deleteMany({ status, before, dryRun = false }) {
+    const params = new URLSearchParams()
+    if (status) params.set('status', status)
+    if (before) params.set('before', before)
+    if (dryRun) params.set('dry_run', 'true')
+    return apiClient.delete(`/api/v1/jobs?${params}`)
   },
 
   delete(id) {
-    return apiClient.delete(`/api/jobs/${id}`)
+    return apiClient.delete(`/api/v1/jobs/${id}`)
   },
 
   logs(id) {
-    return apiClient.get(`/api/jobs/${id}/logs`)
+    return apiClient.get(`/api/v1/jobs/${id}/logs`)
+  },
+
+  // Follow a running job's log; returns a function that closes the stream
+  tailLogs(id, onLines, onEnd) {
+    const source = new EventSource(apiClient.withKey(`${apiClient.baseUrl}/api/v1/jobs/${id}/logs/tail`))
+    source.addEventListener('log', (message) => onLines(message.data))
+    source.addEventListener('end', (message) => {
+      source.close()
+      onEnd(message.data)
+    })
+    return () => source.close()
   },
 
   active() {
-    return apiClient.get('/api/jobs/active')
+    return apiClient.get('/api/v1/jobs/active')
   },
 
   detailedProgress(id) {
-    return apiClient.get(`/api/jobs/${id}/detailed-progress`)
+    return apiClient.get(`/api/v1/jobs/${id}/detailed-progress`)
   }
 }
 
 // Backups API
 export const backupsApi = {
   list(params = {}) {
-    return apiClient.get('/api/backups', params)
+    return apiClient.get('/api/v1/backups', params)
+  },
+
+  // URL downloading every backup matching the list filters, format 'csv' or 'json'
+  exportUrl(params = {}, format = 'csv') {
+    const query = new URLSearchParams({ ...params, format }).toString()
+    return apiClient.withKey(`${apiClient.baseUrl}/api/v1/backups/export?${query}`)
   },
 
   get(id) {
-    return apiClient.get(`/api/backups/${id}`)
+    return apiClient.get(`/api/v1/backups/${id}`)
   },
 
   restore(id, data) {
-    return apiClient.post(`/api/backups/${id}/restore`, data)
+    return apiClient.post(`/api/v1/backups/${id}/restore`, data)
+  },
+
+  // Restore the latest backup covering data.point_in_time and replay its binlogs up to that moment
+  restorePointInTime(data) {
+    return apiClient.post('/api/v1/backups/point-in-time-restore', data)
+  },
+
+  contents(id) {
+    return apiClient.get(`/api/v1/backups/${id}/contents`)
+  },
+
+  // Re-hash the archive and read it end to end in a verify job; 'sandbox_restore' also restores it into a throwaway MySQL container
+  verify(id, mode = 'archive') {
+    return apiClient.post(`/api/v1/backups/${id}/verify`, { mode })
+  },
+
+  // Check the uploaded copy against the local size ('size') or re-download and hash it ('checksum')
+  verifyRemote(id, mode = 'size') {
+    return apiClient.post(`/api/v1/backups/${id}/verify-remote`, { mode })
+  },
+
+  remoteVerification(id) {
+    return apiClient.get(`/api/v1/backups/${id}/verify-remote`)
+  },
+
+  // Convert the archive to another compression format ('none', 'gzip' or 'zstd') in a recompress job
+  recompress(id, compressionType) {
+    return apiClient.post(`/api/v1/backups/${id}/recompress`, { compression_type: compressionType })
+  },
+
+  // Copy archive and metadata to a destination such as { type: 'directory', path: '/mnt/offsite' }
+  copy(id, destination) {
+    return apiClient.post(`/api/v1/backups/${id}/copy`, { destination })
+  },
+
+  // Register a mydumper directory or archive that already exists on the server, e.g. from a pre-rdumper cron job
+  register(path, databaseConfigId, options = {}) {
+    return apiClient.post('/api/v1/backups/register', {
+      path,
+      database_config_id: databaseConfigId,
+      ...options,
+    })
+  },
+
+  // Let the server download an archive (e.g. an S3 presigned URL) and import it as a job
+  importUrl(url, databaseConfigId, filename = null) {
+    return apiClient.post('/api/v1/backups/import-url', {
+      url,
+      database_config_id: databaseConfigId,
+      ...(filename ? { filename } : {}),
+    })
   },
 
   async download(id) {
-    const response = await fetch(`${apiClient.baseUrl}/api/backups/${id}/download`)
+    const response = await fetch(`${apiClient.baseUrl}/api/v1/backups/${id}/download`, { headers: apiClient.authHeaders() })
     if (!response.ok) {
       throw new Error(`Download failed: ${response.status}`)
     }
@@ -225,89 +427,145 @@ export const backupsApi = {
   },
 
   async downloadFile(id, filename) {
-    const response = await this.download(id)
-    const blob = await response.blob()
-    const url = window.URL.createObjectURL(blob)
+    // Check availability first, then let the browser stream the archive straight to disk
+    const response = await fetch(`${apiClient.baseUrl}/api/v1/backups/${id}/download`, { method: 'HEAD', headers: apiClient.authHeaders() })
+    if (!response.ok) {
+      throw new Error(`Download failed: ${response.status}`)
+    }
     const a = document.createElement('a')
-    a.href = url
+    a.href = apiClient.withKey(`${apiClient.baseUrl}/api/v1/backups/${id}/download`)
     a.download = filename || 'backup.tar.gz'
     document.body.appendChild(a)
     a.click()
-    window.URL.revokeObjectURL(url)
     document.body.removeChild(a)
   },
 
   delete(id) {
-    return apiClient.delete(`/api/backups/${id}`)
+    return apiClient.delete(`/api/v1/backups/${id}`)
   },
 
   cleanup(days = 30) {
-    return apiClient.post('/api/backups/cleanup', { days })
+    return apiClient.post('/api/v1/backups/cleanup', { days })
   },
 
   updateMetadata(id, metadata) {
-    return apiClient.post(`/api/backups/${id}/metadata`, metadata)
+    return apiClient.post(`/api/v1/backups/${id}/metadata`, metadata)
   },
 
-  // Upload backup file
-  async upload(file, databaseConfigId, compressionType = 'gzip') {
-    const formData = new FormData()
-    formData.append('file', file)
-    formData.append('database_config_id', databaseConfigId)
-    formData.append('compression_type', compressionType)
+  // Upload backup file in resumable chunks so large archives survive flaky connections and proxy body limits
+  async upload(file, databaseConfigId, compressionType = 'gzip', onProgress = null) {
+    const chunkSize = 8 * 1024 * 1024
+    const maxRetries = 5
 
-    console.log('Uploading file:', file.name, 'Size:', file.size)
-    console.log('Database config ID:', databaseConfigId)
-    console.log('Compression type:', compressionType)
+    const session = await apiClient.post('/api/v1/backups/uploads', {
+      filename: file.name,
+      size: file.size,
+      database_config_id: databaseConfigId,
+      compression_type: compressionType,
+    })
+    const uploadId = session.data.upload_id
+    let offset = session.data.offset
+    let retries = 0
+
+    while (offset < file.size) {
+      try {
+        const response = await fetch(`${apiClient.baseUrl}/api/v1/backups/uploads/${uploadId}`, {
+          method: 'PATCH',
+          headers: {
+            ...apiClient.authHeaders(),
+            'Content-Type': 'application/offset+octet-stream',
+            'Upload-Offset': String(offset),
+          },
+          body: file.slice(offset, offset + chunkSize),
+        })
+
+        if (!response.ok && response.status !== 409) {
+          const errorData = await response.json().catch(() => ({ error: 'Unknown error' }))
+          throw new Error(errorData.error || `HTTP ${response.status}`)
+        }
 
-    try {
-      const response = await fetch(`${apiClient.baseUrl}/api/backups/upload`, {
+        // On 409 the server tells us where to resume
+        const result = await response.json()
+        offset = response.ok ? result.data.offset : result.offset
+        retries = 0
+        if (onProgress) onProgress(Math.round((offset / file.size) * 100))
+      } catch (error) {
+        if (++retries > maxRetries) {
+          console.error('Upload error:', error)
+          throw error
+        }
+        // Ask the server how much arrived before retrying
+        await new Promise(resolve => setTimeout(resolve, 1000 * retries))
+        const status = await apiClient.get(`/api/v1/backups/uploads/${uploadId}`).catch(() => null)
+        if (status) offset = status.data.offset
+      }
+    }
+
+    return apiClient.post(`/api/v1/backups/uploads/${uploadId}/complete`)
+  },
+
+  // Upload the loose files of a mydumper output directory in batches; the last batch finalizes the backup
+  async uploadDirectory(files, databaseConfigId, onProgress = null) {
+    const batchSize = 64 * 1024 * 1024
+    const totalSize = files.reduce((sum, file) => sum + file.size, 0)
+    const batches = []
+    let current = []
+    let currentSize = 0
+    for (const file of files) {
+      if (current.length && currentSize + file.size > batchSize) {
+        batches.push(current)
+        current = []
+        currentSize = 0
+      }
+      current.push(file)
+      currentSize += file.size
+    }
+    batches.push(current)
+
+    let uploadId = null
+    let uploaded = 0
+    let result = null
+    for (let i = 0; i < batches.length; i++) {
+      const formData = new FormData()
+      if (uploadId) formData.append('upload_id', uploadId)
+      formData.append('database_config_id', databaseConfigId)
+      formData.append('finalize', i === batches.length - 1 ? 'true' : 'false')
+      batches[i].forEach(file => formData.append('files', file, file.name))
+
+      const response = await fetch(`${apiClient.baseUrl}/api/v1/backups/upload-directory`, {
         method: 'POST',
+        headers: apiClient.authHeaders(),
         body: formData,
-        // Don't set Content-Type header - let the browser set it with boundary
       })
-
-      console.log('Response status:', response.status)
-      console.log('Response headers:', Object.fromEntries(response.headers.entries()))
-
       if (!response.ok) {
-        const errorText = await response.text()
-        console.error('Upload failed - response text:', errorText)
-        
-        let errorData
-        try {
-          errorData = JSON.parse(errorText)
-        } catch (e) {
-          errorData = { error: errorText || 'Unknown error' }
-        }
-        
+        const errorData = await response.json().catch(() => ({ error: 'Unknown error' }))
         throw new Error(errorData.error || `HTTP ${response.status}`)
       }
 
-      const result = await response.json()
-      console.log('Upload successful:', result)
-      return result
-    } catch (error) {
-      console.error('Upload error:', error)
-      throw error
+      result = await response.json()
+      uploadId = result.data.upload_id
+      uploaded += batches[i].reduce((sum, file) => sum + file.size, 0)
+      if (onProgress) onProgress(Math.round((uploaded / totalSize) * 100))
     }
+
+    return result
   }
 }
 
 // System API
 export const systemApi = {
   info() {
-    return apiClient.get('/api/system')
+    return apiClient.get('/api/v1/system')
   },
 
   health() {
-    return apiClient.get('/api/health')
+    return apiClient.get('/api/v1/health')
   },
 
   // System API
   async getSystemInfo() {
     try {
-      const response = await apiClient.request('/api/system/info')
+      const response = await apiClient.request('/api/v1/system/info')
       return response
     } catch (error) {
       console.error('Failed to fetch system info:', error)
@@ -317,7 +575,7 @@ export const systemApi = {
 
   async getVersionInfo() {
     try {
-      const response = await apiClient.request('/api/system/version')
+      const response = await apiClient.request('/api/v1/system/version')
       return response
     } catch (error) {
       console.error('Failed to fetch version info:', error)
@@ -327,7 +585,7 @@ export const systemApi = {
 
   async getHealthStatus() {
     try {
-      const response = await apiClient.request('/api/system/health')
+      const response = await apiClient.request('/api/v1/system/health')
       return response
     } catch (error) {
       console.error('Failed to fetch health status:', error)
@@ -337,7 +595,7 @@ export const systemApi = {
 
   async getMyDumperVersion() {
     try {
-      const response = await apiClient.request('/api/system/mydumper/version')
+      const response = await apiClient.request('/api/v1/system/mydumper/version')
       return response
     } catch (error) {
       console.error('Failed to fetch mydumper version:', error)
@@ -347,7 +605,7 @@ export const systemApi = {
 
   async getMyLoaderVersion() {
     try {
-      const response = await apiClient.request('/api/system/myloader/version')
+      const response = await apiClient.request('/api/v1/system/myloader/version')
       return response
     } catch (error) {
       console.error('Failed to fetch myloader version:', error)
@@ -357,7 +615,7 @@ export const systemApi = {
 
   async getWorkerStatus() {
     try {
-      const response = await apiClient.request('/api/system/worker')
+      const response = await apiClient.request('/api/v1/system/worker')
       return response
     } catch (error) {
       console.error('Failed to fetch worker status:', error)
@@ -377,15 +635,22 @@ export const logsApi = {
       if (params.level) queryParams.append('level', params.level)
       if (params.entity_type) queryParams.append('entity_type', params.entity_type)
       if (params.entity_id) queryParams.append('entity_id', params.entity_id)
+      if (params.since) queryParams.append('since', params.since)
+      if (params.until) queryParams.append('until', params.until)
       
       const queryString = queryParams.toString()
-      const endpoint = queryString ? `/api/logs?${queryString}` : '/api/logs'
+      const endpoint = queryString ? `/api/v1/logs?${queryString}` : '/api/v1/logs'
       const response = await apiClient.request(endpoint)
       return response
     } catch (error) {
       console.error('Failed to fetch logs:', error)
       return { success: false, data: [], total: 0 }
     }
+  },
+
+  // Delete entries created before `before`, optionally only of a log_type, level or entity
+  purge(params) {
+    return apiClient.delete(`/api/v1/logs?${new URLSearchParams(params).toString()}`)
   }
 }
 
@@ -393,7 +658,7 @@ export const logsApi = {
 export const dashboardApi = {
   async getStats() {
     try {
-      const response = await apiClient.request('/api/dashboard/stats')
+      const response = await apiClient.request('/api/v1/dashboard/stats')
       return response
     } catch (error) {
       console.error('Failed to fetch dashboard stats:', error)
@@ -406,7 +671,7 @@ export const dashboardApi = {
 
   async getRecentBackups() {
     try {
-      const response = await apiClient.request('/api/dashboard/recent-backups')
+      const response = await apiClient.request('/api/v1/dashboard/recent-backups')
       return response
     } catch (error) {
       console.error('Failed to fetch recent backups:', error)
@@ -416,7 +681,7 @@ export const dashboardApi = {
 
   async getNextTasks() {
     try {
-      const response = await apiClient.request('/api/dashboard/next-tasks')
+      const response = await apiClient.request('/api/v1/dashboard/next-tasks')
       return response
     } catch (error) {
       console.error('Failed to fetch next tasks:', error)
@@ -424,6 +689,17 @@ export const dashboardApi = {
     }
   },
 
+  async getOverdue(graceMinutes = null) {
+    try {
+      const query = graceMinutes !== null ? `?grace_minutes=${graceMinutes}` : ''
+      const response = await apiClient.request(`/api/v1/dashboard/overdue${query}`)
+      return response
+    } catch (error) {
+      console.error('Failed to fetch overdue tasks:', error)
+      return { success: false, data: { overdue_tasks: [] } }
+    }
+  },
+
   async getRecentJobs(limit = 10) {
     try {
       const response = await jobsApi.list({ limit, sort: 'created_at', order: 'desc' })
@@ -435,4 +711,153 @@ export const dashboardApi = {
   }
 }
 
-export default apiClient
\ No newline at end of file
+// Events API - server-sent events for live updates
+export const eventsApi = {
+  // Subscribe to backend events; returns a function that closes the stream
+  subscribe(handler, types = ['job_status_changed', 'backup_created', 'backup_deleted', 'worker_tick', 'connection_tested', 'backup_size_anomaly', 'job_duration_anomaly', 'rpo_status_changed', 'task_auto_paused']) {
+    const source = new EventSource(apiClient.withKey(`${apiClient.baseUrl}/api/v1/events`))
+    types.forEach(type => {
+      source.addEventListener(type, (message) => {
+        try {
+          handler(JSON.parse(message.data))
+        } catch (error) {
+          console.error('Failed to parse event:', error)
+        }
+      })
+    })
+    return () => source.close()
+  }
+}
+
+export default apiClient
+// Transfers API - progress of in-flight uploads and downloads
+export const transfersApi = {
+  async list() {
+    return apiClient.request('/api/v1/transfers')
+  },
+
+  async get(id) {
+    return apiClient.request(`/api/v1/transfers/${id}`)
+  }
+}
+
+// UI settings API - frontend preferences stored server-side, 'global' unless a scope is given
+export const uiSettingsApi = {
+  async list(scope = 'global') {
+    return apiClient.get('/api/v1/ui-settings', { scope })
+  },
+
+  async get(key, scope = 'global') {
+    return apiClient.get(`/api/v1/ui-settings/${encodeURIComponent(key)}`, { scope })
+  },
+
+  async set(key, value, scope = 'global') {
+    return apiClient.put(`/api/v1/ui-settings/${encodeURIComponent(key)}?scope=${encodeURIComponent(scope)}`, { value })
+  },
+
+  async delete(key, scope = 'global') {
+    return apiClient.delete(`/api/v1/ui-settings/${encodeURIComponent(key)}?scope=${encodeURIComponent(scope)}`)
+  }
+}
+
+// Agents API - remote rdumper instances that run backups for this one
+export const agentsApi = {
+  async list() {
+    return apiClient.get('/api/v1/agents')
+  },
+
+  async create(name) {
+    return apiClient.post('/api/v1/agents', { name })
+  },
+
+  async delete(id) {
+    return apiClient.delete(`/api/v1/agents/${id}`)
+  },
+
+  async rotateToken(id) {
+    return apiClient.post(`/api/v1/agents/${id}/token`)
+  }
+}
+
+// Storage Destinations API - remote targets shared by tasks
+export const storageDestinationsApi = {
+  list() {
+    return apiClient.get('/api/v1/storage-destinations')
+  },
+
+  get(id) {
+    return apiClient.get(`/api/v1/storage-destinations/${id}`)
+  },
+
+  create(data) {
+    return apiClient.post('/api/v1/storage-destinations', data)
+  },
+
+  update(id, data) {
+    return apiClient.put(`/api/v1/storage-destinations/${id}`, data)
+  },
+
+  delete(id) {
+    return apiClient.delete(`/api/v1/storage-destinations/${id}`)
+  },
+
+  // Write a probe file to the destination and remove it again
+  test(id) {
+    return apiClient.post(`/api/v1/storage-destinations/${id}/test`)
+  },
+
+  // Total size of everything stored at the destination, listed on demand
+  usage(id) {
+    return apiClient.get(`/api/v1/storage-destinations/${id}/usage`)
+  }
+}
+
+// Blackout Windows API - periods in which scheduled backups must not start
+export const blackoutWindowsApi = {
+  // All windows, or with a task id only those applying to that task
+  list(taskId = null) {
+    return apiClient.get('/api/v1/blackout-windows', taskId ? { task_id: taskId } : {})
+  },
+
+  create(data) {
+    return apiClient.post('/api/v1/blackout-windows', data)
+  },
+
+  update(id, data) {
+    return apiClient.put(`/api/v1/blackout-windows/${id}`, data)
+  },
+
+  delete(id) {
+    return apiClient.delete(`/api/v1/blackout-windows/${id}`)
+  }
+}
+
+export const searchApi = {
+  // Tasks, database configs, jobs and backups matching q, each with its `type`
+  search(q, limit = 10) {
+    return apiClient.get('/api/v1/search', { q, limit })
+  }
+}
+
+export const webhooksApi = {
+  list() {
+    return apiClient.get('/api/v1/webhooks')
+  },
+
+  // Returns the webhook and its signing secret, which can't be read back later
+  create(data) {
+    return apiClient.post('/api/v1/webhooks', data)
+  },
+
+  update(id, data) {
+    return apiClient.put(`/api/v1/webhooks/${id}`, data)
+  },
+
+  delete(id) {
+    return apiClient.delete(`/api/v1/webhooks/${id}`)
+  },
+
+  deliveries(id, params = {}) {
+    return apiClient.get(`/api/v1/webhooks/${id}/deliveries`, params)
+  }
+}
@@ -8,6 +8,7 @@ import "./assets/main.css";
 // Import views
 import Dashboard from './views/Dashboard.vue'
 import DatabaseConfigs from './views/DatabaseConfigs.vue'
+import StorageDestinations from './views/StorageDestinations.vue'
 import Tasks from './views/Tasks.vue'
 import Jobs from './views/Jobs.vue'
 import Backups from './views/Backups.vue'
@@ -16,6 +17,7 @@ import System from './views/System.vue'
 const routes = [
   { path: '/', name: 'Dashboard', component: Dashboard },
   { path: '/databases', name: 'DatabaseConfigs', component: DatabaseConfigs },
+  { path: '/storage', name: 'StorageDestinations', component: StorageDestinations },
   { path: '/tasks', name: 'Tasks', component: Tasks },
   { path: '/jobs', name: 'Jobs', component: Jobs },
   { path: '/backups', name: 'Backups', component: Backups },
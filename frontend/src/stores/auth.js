@@ -0,0 +1,8 @@
+import { ref } from 'vue'
+
+// Set when the backend rejected a request for missing or invalid credentials
+const loginRequired = ref(false)
+
+export function useAuth() {
+  return { loginRequired }
+}